@@ -33,7 +33,7 @@ use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 
-use crate::credentials::Credentials;
+use crate::credentials::{Credentials, SecretString};
 use crate::error::AuthError;
 use crate::provider::{AuthData, AuthMethod, AuthProvider};
 
@@ -89,7 +89,7 @@ pub enum FedAuthWorkflow {
 #[derive(Clone)]
 pub struct AzureAdAuth {
     /// The access token.
-    token: Cow<'static, str>,
+    token: SecretString,
     /// When the token expires (if known).
     expires_at: Option<Instant>,
     /// The library type to report to the server.
@@ -107,7 +107,7 @@ impl AzureAdAuth {
     /// * `token` - A valid JWT access token for Azure SQL Database
     pub fn with_token(token: impl Into<Cow<'static, str>>) -> Self {
         Self {
-            token: token.into(),
+            token: SecretString::from(token.into()),
             expires_at: None,
             library: FedAuthLibrary::SecurityToken,
         }
@@ -124,7 +124,7 @@ impl AzureAdAuth {
     /// * `expires_in` - Duration until the token expires
     pub fn with_token_expiring(token: impl Into<Cow<'static, str>>, expires_in: Duration) -> Self {
         Self {
-            token: token.into(),
+            token: SecretString::from(token.into()),
             expires_at: Some(Instant::now() + expires_in),
             library: FedAuthLibrary::SecurityToken,
         }
@@ -135,7 +135,11 @@ impl AzureAdAuth {
     /// Returns an error if the credentials are not Azure AD credentials.
     pub fn from_credentials(credentials: &Credentials) -> Result<Self, AuthError> {
         match credentials {
-            Credentials::AzureAccessToken { token } => Ok(Self::with_token(token.to_string())),
+            Credentials::AzureAccessToken { token } => Ok(Self {
+                token: token.clone(),
+                expires_at: None,
+                library: FedAuthLibrary::SecurityToken,
+            }),
             _ => Err(AuthError::UnsupportedMethod(
                 "AzureAdAuth requires Azure AD credentials".into(),
             )),
@@ -197,6 +201,7 @@ impl AzureAdAuth {
         // Token is sent as UTF-16LE
         let token_utf16: Vec<u8> = self
             .token
+            .expose_secret()
             .encode_utf16()
             .flat_map(|c| c.to_le_bytes())
             .collect();
@@ -226,7 +231,7 @@ impl AuthProvider for AzureAdAuth {
         tracing::debug!("authenticating with Azure AD token");
 
         Ok(AuthData::FedAuth {
-            token: self.token.to_string(),
+            token: self.token.expose_secret().to_string(),
             nonce: None,
         })
     }
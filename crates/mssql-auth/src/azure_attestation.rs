@@ -0,0 +1,510 @@
+//! Microsoft Azure Attestation verifier for Always Encrypted secure enclaves.
+//!
+//! With the `AAS` attestation protocol, SQL Server returns a JSON Web Token
+//! issued by a Microsoft Azure Attestation instance. This verifier checks:
+//!
+//! - The token is signed (RS256) by a key published by the attestation
+//!   instance at `<authority>/certs`
+//! - The issuer is the configured attestation instance and the token is
+//!   within its validity period
+//! - The enclave-held data claim (`aas-ehd` / `maa-ehd`) is the enclave
+//!   identity key from the attestation information
+//! - For VBS enclaves, the `rp_data` claim is the nonce sent by the client
+//!
+//! Signing keys are cached per attestation instance and refreshed when a
+//! token names an unknown key.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use mssql_auth::azure_attestation::AzureAttestationVerifier;
+//! use mssql_auth::AttestationProtocol;
+//! use mssql_client::EncryptionConfig;
+//!
+//! let config = EncryptionConfig::new()
+//!     .with_provider(key_store)
+//!     .with_enclave_attestation(
+//!         AttestationProtocol::AzureAttestation,
+//!         "https://myinstance.eus.attest.azure.net/attest/SgxEnclave",
+//!     )
+//!     .with_attestation_verifier(AzureAttestationVerifier::new());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use azure_core::Value;
+use azure_core::base64;
+use azure_core::http::{HttpClient, Method, Request, Url, new_http_client};
+use parking_lot::Mutex;
+use ring::signature::{self, RsaPublicKeyComponents};
+use rustls_pki_types::CertificateDer;
+use tracing::debug;
+use webpki::EndEntityCert;
+
+use crate::enclave::{
+    AttestationEvidence, AttestationInfo, AttestationProtocol, AttestationVerifier, EnclaveType,
+};
+use crate::encryption::EncryptionError;
+
+/// Clock skew tolerated when checking token lifetime, in seconds.
+const CLOCK_SKEW_SECS: i64 = 300;
+
+/// A key published by an attestation instance for verifying its tokens.
+#[derive(Clone)]
+struct SigningKey {
+    kid: String,
+    material: KeyMaterial,
+}
+
+#[derive(Clone)]
+enum KeyMaterial {
+    /// DER-encoded X.509 certificate (`x5c`).
+    Certificate(Vec<u8>),
+    /// Raw RSA public key components (`n`, `e`).
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+}
+
+impl SigningKey {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match &self.material {
+            KeyMaterial::Certificate(der) => {
+                let der = CertificateDer::from(der.as_slice());
+                EndEntityCert::try_from(&der)
+                    .and_then(|cert| {
+                        cert.verify_signature(
+                            webpki::ring::RSA_PKCS1_2048_8192_SHA256,
+                            message,
+                            signature,
+                        )
+                    })
+                    .is_ok()
+            }
+            KeyMaterial::Rsa { n, e } => RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok(),
+        }
+    }
+}
+
+/// Verifier for Microsoft Azure Attestation tokens.
+///
+/// ## Thread Safety
+///
+/// This verifier is `Send + Sync` and can be shared by every connection.
+pub struct AzureAttestationVerifier {
+    http: Arc<dyn HttpClient>,
+    /// Signing keys by attestation authority.
+    signing_keys: Mutex<HashMap<String, Arc<Vec<SigningKey>>>>,
+}
+
+impl AzureAttestationVerifier {
+    /// Create a verifier using the default Azure HTTP client.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_http_client(new_http_client())
+    }
+
+    /// Create a verifier that fetches signing keys with the given client.
+    #[must_use]
+    pub fn with_http_client(http: Arc<dyn HttpClient>) -> Self {
+        Self {
+            http,
+            signing_keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the signing keys of an attestation authority, fetching them if
+    /// they are not cached or `refresh` is set.
+    async fn signing_keys(
+        &self,
+        authority: &str,
+        refresh: bool,
+    ) -> Result<Arc<Vec<SigningKey>>, EncryptionError> {
+        if !refresh {
+            if let Some(keys) = self.signing_keys.lock().get(authority) {
+                return Ok(Arc::clone(keys));
+            }
+        }
+
+        let url = Url::parse(&format!("{authority}/certs")).map_err(|e| {
+            EncryptionError::ConfigurationError(format!("invalid attestation URL: {e}"))
+        })?;
+        debug!(%url, "fetching attestation signing keys");
+
+        let response = self
+            .http
+            .execute_request(&Request::new(url, Method::Get))
+            .await
+            .map_err(|e| {
+                EncryptionError::AttestationFailed(format!(
+                    "failed to fetch signing keys from {authority}: {e}"
+                ))
+            })?;
+        if !response.status().is_success() {
+            return Err(EncryptionError::AttestationFailed(format!(
+                "failed to fetch signing keys from {authority}: HTTP {}",
+                response.status()
+            )));
+        }
+        let body = response.into_body().collect().await.map_err(|e| {
+            EncryptionError::AttestationFailed(format!("failed to read signing keys: {e}"))
+        })?;
+
+        let keys = Arc::new(parse_signing_keys(&body)?);
+        self.signing_keys
+            .lock()
+            .insert(authority.to_string(), Arc::clone(&keys));
+        Ok(keys)
+    }
+}
+
+impl Default for AzureAttestationVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AzureAttestationVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureAttestationVerifier")
+            .field("cached_authorities", &self.signing_keys.lock().len())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl AttestationVerifier for AzureAttestationVerifier {
+    fn protocol(&self) -> AttestationProtocol {
+        AttestationProtocol::AzureAttestation
+    }
+
+    async fn verify(
+        &self,
+        attestation_url: &str,
+        info: &AttestationInfo,
+        nonce: &[u8],
+    ) -> Result<(), EncryptionError> {
+        let AttestationEvidence::AzureAttestationToken(token) = &info.evidence else {
+            return Err(EncryptionError::AttestationFailed(
+                "attestation information has no Azure Attestation token".into(),
+            ));
+        };
+        let authority = authority(attestation_url)?;
+        let token = Token::parse(token)?;
+
+        let mut keys = self.signing_keys(&authority, false).await?;
+        if !keys.iter().any(|k| k.kid == token.kid) {
+            // The instance may have rotated its keys
+            keys = self.signing_keys(&authority, true).await?;
+        }
+        let key = keys.iter().find(|k| k.kid == token.kid).ok_or_else(|| {
+            EncryptionError::AttestationFailed(format!(
+                "attestation token signed by unknown key {}",
+                token.kid
+            ))
+        })?;
+        if !key.verify(token.signed.as_bytes(), &token.signature) {
+            return Err(EncryptionError::AttestationFailed(
+                "attestation token signature is invalid".into(),
+            ));
+        }
+
+        validate_claims(&token.claims, &authority, info, nonce, unix_now())
+    }
+}
+
+/// A decoded, not yet verified, JSON Web Token.
+struct Token {
+    kid: String,
+    claims: Value,
+    /// `header.payload`, the signed part of the token.
+    signed: String,
+    signature: Vec<u8>,
+}
+
+impl Token {
+    fn parse(token: &str) -> Result<Self, EncryptionError> {
+        let malformed = || EncryptionError::AttestationFailed("malformed attestation token".into());
+
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+
+        let decode_json = |part: &str| -> Result<Value, EncryptionError> {
+            let bytes = base64::decode_url_safe(part).map_err(|_| malformed())?;
+            azure_core::json::from_json(bytes).map_err(|_| malformed())
+        };
+        let header = decode_json(header)?;
+        if header.get("alg").and_then(Value::as_str) != Some("RS256") {
+            return Err(EncryptionError::AttestationFailed(
+                "attestation token must be signed with RS256".into(),
+            ));
+        }
+        let kid = header
+            .get("kid")
+            .and_then(Value::as_str)
+            .ok_or_else(malformed)?
+            .to_string();
+
+        Ok(Self {
+            kid,
+            claims: decode_json(payload)?,
+            signed: token[..header_payload_len(token)].to_string(),
+            signature: base64::decode_url_safe(signature).map_err(|_| malformed())?,
+        })
+    }
+}
+
+/// Length of the `header.payload` prefix of a token.
+fn header_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
+
+/// The `scheme://host[:port]` authority of an attestation URL.
+fn authority(attestation_url: &str) -> Result<String, EncryptionError> {
+    let url = Url::parse(attestation_url).map_err(|e| {
+        EncryptionError::ConfigurationError(format!("invalid attestation URL: {e}"))
+    })?;
+    let host = url.host_str().ok_or_else(|| {
+        EncryptionError::ConfigurationError(format!(
+            "attestation URL has no host: {attestation_url}"
+        ))
+    })?;
+    Ok(match url.port() {
+        Some(port) => format!("{}://{host}:{port}", url.scheme()),
+        None => format!("{}://{host}", url.scheme()),
+    })
+}
+
+/// Parse a JSON Web Key Set.
+fn parse_signing_keys(body: &[u8]) -> Result<Vec<SigningKey>, EncryptionError> {
+    let invalid = || EncryptionError::AttestationFailed("invalid signing key set".into());
+
+    let jwks: Value = azure_core::json::from_json(body).map_err(|_| invalid())?;
+    let keys = jwks
+        .get("keys")
+        .and_then(Value::as_array)
+        .ok_or_else(invalid)?;
+
+    let mut signing_keys = Vec::with_capacity(keys.len());
+    for key in keys {
+        let Some(kid) = key.get("kid").and_then(Value::as_str) else {
+            continue;
+        };
+        let certificate = key
+            .get("x5c")
+            .and_then(Value::as_array)
+            .and_then(|chain| chain.first())
+            .and_then(Value::as_str);
+        let material = match (
+            certificate,
+            key.get("n").and_then(Value::as_str),
+            key.get("e").and_then(Value::as_str),
+        ) {
+            (Some(certificate), _, _) => {
+                KeyMaterial::Certificate(base64::decode(certificate).map_err(|_| invalid())?)
+            }
+            (None, Some(n), Some(e)) => KeyMaterial::Rsa {
+                n: base64::decode_url_safe(n).map_err(|_| invalid())?,
+                e: base64::decode_url_safe(e).map_err(|_| invalid())?,
+            },
+            _ => continue,
+        };
+        signing_keys.push(SigningKey {
+            kid: kid.to_string(),
+            material,
+        });
+    }
+    Ok(signing_keys)
+}
+
+/// Check the issuer, lifetime and enclave binding claims of a token.
+fn validate_claims(
+    claims: &Value,
+    authority: &str,
+    info: &AttestationInfo,
+    nonce: &[u8],
+    now: i64,
+) -> Result<(), EncryptionError> {
+    let fail = |msg: String| Err(EncryptionError::AttestationFailed(msg));
+
+    let issuer = claims.get("iss").and_then(Value::as_str).unwrap_or("");
+    if issuer.trim_end_matches('/') != authority {
+        return fail(format!(
+            "attestation token issued by {issuer}, expected {authority}"
+        ));
+    }
+    if let Some(exp) = claims.get("exp").and_then(Value::as_i64) {
+        if now > exp + CLOCK_SKEW_SECS {
+            return fail("attestation token has expired".into());
+        }
+    }
+    if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+        if now + CLOCK_SKEW_SECS < nbf {
+            return fail("attestation token is not yet valid".into());
+        }
+    }
+
+    let identity = info.identity.as_ref().ok_or_else(|| {
+        EncryptionError::AttestationFailed("attestation information has no enclave key".into())
+    })?;
+    if claim_bytes(claims, &["aas-ehd", "maa-ehd"]).as_deref() != Some(identity.as_blob()) {
+        return fail("attestation token does not attest the enclave public key".into());
+    }
+    if info.enclave_type == Some(EnclaveType::Vbs)
+        && claim_bytes(claims, &["rp_data"]).as_deref() != Some(nonce)
+    {
+        return fail("attestation token does not carry the request nonce".into());
+    }
+
+    Ok(())
+}
+
+/// Decode the first present base64url claim of `names`.
+fn claim_bytes(claims: &Value, names: &[&str]) -> Option<Vec<u8>> {
+    names
+        .iter()
+        .find_map(|name| claims.get(*name).and_then(Value::as_str))
+        .and_then(|value| base64::decode_url_safe(value).ok())
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::enclave::{EnclaveDhInfo, EnclavePublicKey};
+    use bytes::Bytes;
+    use rsa::traits::PublicKeyParts;
+    use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+    use sha2::{Digest, Sha256};
+
+    const AUTHORITY: &str = "https://test.eus.attest.azure.net";
+
+    fn signer() -> &'static RsaPrivateKey {
+        static KEY: std::sync::OnceLock<RsaPrivateKey> = std::sync::OnceLock::new();
+        KEY.get_or_init(|| RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap())
+    }
+
+    fn signing_key() -> SigningKey {
+        SigningKey {
+            kid: "key-1".into(),
+            material: KeyMaterial::Rsa {
+                n: signer().n().to_bytes_be(),
+                e: signer().e().to_bytes_be(),
+            },
+        }
+    }
+
+    fn sign_token(claims: &Value) -> String {
+        let header = base64::encode_url_safe(br#"{"alg":"RS256","kid":"key-1"}"#);
+        let payload = base64::encode_url_safe(claims.to_string());
+        let signed = format!("{header}.{payload}");
+        let signature = signer()
+            .sign(
+                Pkcs1v15Sign::new::<Sha256>(),
+                Sha256::digest(signed.as_bytes()).as_slice(),
+            )
+            .unwrap();
+        format!("{signed}.{}", base64::encode_url_safe(signature))
+    }
+
+    fn info(identity: &[u8], enclave_type: EnclaveType) -> AttestationInfo {
+        let mut blob = Vec::new();
+        blob.extend_from_slice(&0x3141_5352u32.to_le_bytes());
+        blob.extend_from_slice(&[0u8; 20]);
+        blob.extend_from_slice(identity);
+        AttestationInfo {
+            identity: Some(EnclavePublicKey::from_blob(Bytes::from(blob)).unwrap()),
+            enclave_type: Some(enclave_type),
+            evidence: AttestationEvidence::None,
+            session_id: 1,
+            dh_info: EnclaveDhInfo {
+                public_key: Bytes::new(),
+                signature: Bytes::new(),
+            },
+        }
+    }
+
+    fn claims(info: &AttestationInfo, nonce: &[u8]) -> Value {
+        let identity = info.identity.as_ref().unwrap().as_blob();
+        azure_core::json::from_json(format!(
+            r#"{{"iss":"{AUTHORITY}","exp":2000,"nbf":1000,"aas-ehd":"{}","rp_data":"{}"}}"#,
+            base64::encode_url_safe(identity),
+            base64::encode_url_safe(nonce)
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_authority() {
+        assert_eq!(
+            authority("https://test.eus.attest.azure.net/attest/SgxEnclave").unwrap(),
+            AUTHORITY
+        );
+        assert_eq!(
+            authority("https://localhost:8443/attest").unwrap(),
+            "https://localhost:8443"
+        );
+        assert!(authority("not a url").is_err());
+    }
+
+    #[test]
+    fn test_token_signature() {
+        let info = info(b"", EnclaveType::Sgx);
+        let token = Token::parse(&sign_token(&claims(&info, b""))).unwrap();
+        assert_eq!(token.kid, "key-1");
+        assert!(signing_key().verify(token.signed.as_bytes(), &token.signature));
+        assert!(!signing_key().verify(b"tampered", &token.signature));
+    }
+
+    #[test]
+    fn test_token_rejects_other_algorithms() {
+        let header = base64::encode_url_safe(br#"{"alg":"none","kid":"key-1"}"#);
+        let token = format!("{header}.e30.");
+        assert!(Token::parse(&token).is_err());
+        assert!(Token::parse("only.two").is_err());
+    }
+
+    #[test]
+    fn test_validate_claims() {
+        let nonce = [7u8; 16];
+        let vbs = info(b"", EnclaveType::Vbs);
+        let claims = claims(&vbs, &nonce);
+
+        assert!(validate_claims(&claims, AUTHORITY, &vbs, &nonce, 1500).is_ok());
+        // Expired, not yet valid, wrong issuer
+        assert!(validate_claims(&claims, AUTHORITY, &vbs, &nonce, 3000).is_err());
+        assert!(validate_claims(&claims, AUTHORITY, &vbs, &nonce, 100).is_err());
+        assert!(validate_claims(&claims, "https://other", &vbs, &nonce, 1500).is_err());
+        // VBS tokens must echo the nonce
+        assert!(validate_claims(&claims, AUTHORITY, &vbs, &[0u8; 16], 1500).is_err());
+        // The token must attest this enclave's key
+        let other = info(b"other", EnclaveType::Vbs);
+        assert!(validate_claims(&claims, AUTHORITY, &other, &nonce, 1500).is_err());
+    }
+
+    #[test]
+    fn test_parse_signing_keys() {
+        let n = base64::encode_url_safe(signer().n().to_bytes_be());
+        let jwks = format!(
+            r#"{{"keys":[{{"kid":"key-1","kty":"RSA","n":"{n}","e":"AQAB"}},{{"kid":"no-material"}}]}}"#
+        );
+        let keys = parse_signing_keys(jwks.as_bytes()).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].kid, "key-1");
+        assert!(parse_signing_keys(b"{}").is_err());
+    }
+}
@@ -24,10 +24,10 @@
 //!
 //! The provider uses Azure Identity for authentication. The following methods are supported:
 //!
-//! - **DefaultAzureCredential**: Tries multiple authentication methods automatically
-//! - **Environment variables**: Uses `AZURE_CLIENT_ID`, `AZURE_CLIENT_SECRET`, `AZURE_TENANT_ID`
+//! - **Developer tools**: Azure CLI (`az login`) and similar, via [`AzureKeyVaultProvider::new`]
 //! - **Managed Identity**: For Azure VMs, App Service, AKS, etc.
-//! - **Azure CLI**: Uses credentials from `az login`
+//! - **Service Principal**: Client ID and secret of an application registration
+//! - **Any `TokenCredential`**: Via [`AzureKeyVaultProvider::with_credential`]
 //!
 //! ## Example
 //!
@@ -35,12 +35,16 @@
 //! use mssql_auth::azure_keyvault::AzureKeyVaultProvider;
 //! use mssql_auth::ColumnEncryptionConfig;
 //!
-//! // Create provider with default Azure credentials
+//! // Create provider with developer tools credentials
 //! let provider = AzureKeyVaultProvider::new()?;
 //!
-//! // Or with a specific credential
+//! // In production, with a managed identity or service principal
+//! let provider = AzureKeyVaultProvider::with_managed_identity()?;
+//! let provider = AzureKeyVaultProvider::with_client_secret(tenant_id, client_id, secret)?;
+//!
+//! // Or with any credential
 //! let credential = azure_identity::DeveloperToolsCredential::new(None)?;
-//! let provider = AzureKeyVaultProvider::with_credential(Arc::new(credential));
+//! let provider = AzureKeyVaultProvider::with_credential(credential);
 //!
 //! // Register with encryption config
 //! let config = ColumnEncryptionConfig::new()
@@ -54,17 +58,27 @@
 //! - All communication uses TLS
 //! - Audit logs are available in Azure Key Vault
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use azure_identity::DeveloperToolsCredential;
+use azure_core::credentials::{Secret, TokenCredential};
+use azure_core::error::ErrorKind;
+use azure_core::http::StatusCode;
+use azure_identity::{
+    ClientSecretCredential, DeveloperToolsCredential, ManagedIdentityCredential,
+    ManagedIdentityCredentialOptions, UserAssignedId,
+};
 use azure_security_keyvault_keys::KeyClient;
 use azure_security_keyvault_keys::models::{
-    EncryptionAlgorithm, KeyClientUnwrapKeyOptions, KeyOperationParameters,
+    EncryptionAlgorithm, KeyClientUnwrapKeyOptions, KeyClientWrapKeyOptions, KeyOperationParameters,
 };
+use parking_lot::RwLock;
+use sha2::{Digest, Sha256};
 use tracing::{debug, instrument};
 use url::Url;
 
 use crate::encryption::{EncryptionError, KeyStoreProvider};
+use crate::key_unwrap::encode_encrypted_cek;
 
 /// SQL Server provider name for Azure Key Vault.
 const PROVIDER_NAME: &str = "AZURE_KEY_VAULT";
@@ -77,9 +91,17 @@ const PROVIDER_NAME: &str = "AZURE_KEY_VAULT";
 /// ## Thread Safety
 ///
 /// This provider is `Send + Sync` and can be safely shared across threads.
+///
+/// ## Client Caching
+///
+/// One Key Vault client is kept per vault URL and reused across operations.
+/// A client is dropped when the vault rejects its credentials, so the next
+/// operation starts over with a fresh client.
 pub struct AzureKeyVaultProvider {
     /// Azure credential for authentication.
-    credential: Arc<DeveloperToolsCredential>,
+    credential: Arc<dyn TokenCredential>,
+    /// Key Vault clients by vault URL.
+    clients: RwLock<HashMap<String, Arc<KeyClient>>>,
 }
 
 impl AzureKeyVaultProvider {
@@ -91,8 +113,8 @@ impl AzureKeyVaultProvider {
     /// 1. Azure CLI credentials (`az login`)
     /// 2. Other developer tools (Visual Studio Code, etc.)
     ///
-    /// For production environments, use [`Self::with_credential`] with a specific
-    /// credential type such as managed identity or service principal.
+    /// For production environments, use [`Self::with_managed_identity`],
+    /// [`Self::with_client_secret`] or [`Self::with_credential`].
     ///
     /// # Errors
     ///
@@ -104,27 +126,77 @@ impl AzureKeyVaultProvider {
     /// let provider = AzureKeyVaultProvider::new()?;
     /// ```
     pub fn new() -> Result<Self, EncryptionError> {
-        let credential = DeveloperToolsCredential::new(None).map_err(|e| {
-            EncryptionError::ConfigurationError(format!("Failed to create Azure credential: {}", e))
-        })?;
-        Ok(Self { credential })
+        let credential = DeveloperToolsCredential::new(None).map_err(credential_error)?;
+        Ok(Self::with_credential(credential))
     }
 
     /// Create a new Azure Key Vault provider with an existing credential.
     ///
-    /// Use this when you need to share a credential across multiple providers.
+    /// Accepts any Azure Identity credential. Use this when you need to share
+    /// a credential across multiple providers or with other Azure clients.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// use azure_identity::DeveloperToolsCredential;
+    /// use azure_identity::WorkloadIdentityCredential;
     ///
-    /// let credential = Arc::new(DeveloperToolsCredential::new(None)?);
+    /// let credential = WorkloadIdentityCredential::new(None)?;
     /// let provider = AzureKeyVaultProvider::with_credential(credential);
     /// ```
     #[must_use]
-    pub fn with_credential(credential: Arc<DeveloperToolsCredential>) -> Self {
-        Self { credential }
+    pub fn with_credential(credential: Arc<dyn TokenCredential>) -> Self {
+        Self {
+            credential,
+            clients: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a new Azure Key Vault provider using the system-assigned managed identity.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if credential initialization fails.
+    pub fn with_managed_identity() -> Result<Self, EncryptionError> {
+        Self::managed_identity(None)
+    }
+
+    /// Create a new Azure Key Vault provider using a user-assigned managed
+    /// identity, identified by its client ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if credential initialization fails.
+    pub fn with_user_assigned_identity(
+        client_id: impl Into<String>,
+    ) -> Result<Self, EncryptionError> {
+        Self::managed_identity(Some(ManagedIdentityCredentialOptions {
+            user_assigned_id: Some(UserAssignedId::ClientId(client_id.into())),
+            ..Default::default()
+        }))
+    }
+
+    /// Create a new Azure Key Vault provider using a service principal's client secret.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if credential initialization fails.
+    pub fn with_client_secret(
+        tenant_id: impl AsRef<str>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Result<Self, EncryptionError> {
+        let secret = Secret::new(client_secret.into());
+        let credential =
+            ClientSecretCredential::new(tenant_id.as_ref(), client_id.into(), secret, None)
+                .map_err(credential_error)?;
+        Ok(Self::with_credential(credential))
+    }
+
+    fn managed_identity(
+        options: Option<ManagedIdentityCredentialOptions>,
+    ) -> Result<Self, EncryptionError> {
+        let credential = ManagedIdentityCredential::new(options).map_err(credential_error)?;
+        Ok(Self::with_credential(credential))
     }
 
     /// Parse a CMK path into vault URL, key name, and optional version.
@@ -163,18 +235,58 @@ impl AzureKeyVaultProvider {
         Ok((vault_url, key_name, key_version))
     }
 
-    /// Create a Key Vault client for a specific vault.
-    fn create_client(&self, vault_url: &str) -> Result<KeyClient, EncryptionError> {
-        KeyClient::new(vault_url, self.credential.clone(), None).map_err(|e| {
+    /// Get the Key Vault client for a specific vault, creating it on first use.
+    fn client(&self, vault_url: &str) -> Result<Arc<KeyClient>, EncryptionError> {
+        if let Some(client) = self.clients.read().get(vault_url) {
+            return Ok(Arc::clone(client));
+        }
+
+        let client = KeyClient::new(vault_url, self.credential.clone(), None).map_err(|e| {
             EncryptionError::CmkError(format!("Failed to create Key Vault client: {}", e))
-        })
+        })?;
+        let mut clients = self.clients.write();
+        Ok(Arc::clone(
+            clients
+                .entry(vault_url.to_string())
+                .or_insert_with(|| Arc::new(client)),
+        ))
+    }
+
+    /// Drop the cached client for a vault if `error` is an authentication failure.
+    fn check_auth_error(&self, vault_url: &str, error: &azure_core::Error) {
+        if is_auth_error(error) && self.clients.write().remove(vault_url).is_some() {
+            debug!(
+                vault_url,
+                "dropped Key Vault client after authentication failure"
+            );
+        }
+    }
+
+    /// Drop all cached Key Vault clients.
+    pub fn clear_clients(&self) {
+        self.clients.write().clear();
     }
 }
 
+/// Check whether a Key Vault error means the credentials were rejected.
+fn is_auth_error(error: &azure_core::Error) -> bool {
+    matches!(error.kind(), ErrorKind::Credential)
+        || matches!(
+            error.http_status(),
+            Some(StatusCode::Unauthorized | StatusCode::Forbidden)
+        )
+}
+
+/// Map a credential construction error.
+fn credential_error(e: azure_core::Error) -> EncryptionError {
+    EncryptionError::ConfigurationError(format!("Failed to create Azure credential: {}", e))
+}
+
 impl std::fmt::Debug for AzureKeyVaultProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AzureKeyVaultProvider")
             .field("provider_name", &PROVIDER_NAME)
+            .field("cached_clients", &self.clients.read().len())
             .finish_non_exhaustive()
     }
 }
@@ -197,8 +309,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
         // Parse the CMK path
         let (vault_url, key_name, key_version) = Self::parse_cmk_path(cmk_path)?;
 
-        // Create client for this vault
-        let client = self.create_client(&vault_url)?;
+        let client = self.client(&vault_url)?;
 
         // Map algorithm name to Azure Key Vault algorithm
         let kv_algorithm = map_algorithm(algorithm)?;
@@ -229,6 +340,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
                 options,
             )
             .await
+            .inspect_err(|e| self.check_auth_error(&vault_url, e))
             .map_err(|e| {
                 EncryptionError::CekDecryptionFailed(format!("Key Vault unwrap failed: {}", e))
             })?
@@ -246,6 +358,67 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
         Ok(decrypted)
     }
 
+    #[instrument(skip(self, cek), fields(cmk_path = %cmk_path, algorithm = %algorithm))]
+    async fn encrypt_cek(
+        &self,
+        cmk_path: &str,
+        algorithm: &str,
+        cek: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        debug!("Encrypting CEK using Azure Key Vault");
+
+        // Parse the CMK path
+        let (vault_url, key_name, key_version) = Self::parse_cmk_path(cmk_path)?;
+
+        let client = self.client(&vault_url)?;
+
+        // Build wrap parameters
+        let parameters = KeyOperationParameters {
+            algorithm: Some(map_algorithm(algorithm)?),
+            value: Some(cek.to_vec()),
+            ..Default::default()
+        };
+
+        // Build options with key version if provided
+        let options = key_version.map(|v| KeyClientWrapKeyOptions {
+            key_version: Some(v),
+            ..Default::default()
+        });
+
+        // Call Key Vault wrap operation
+        let result = client
+            .wrap_key(
+                &key_name,
+                parameters.try_into().map_err(|e| {
+                    EncryptionError::EncryptionFailed(format!("Failed to create request: {}", e))
+                })?,
+                options,
+            )
+            .await
+            .inspect_err(|e| self.check_auth_error(&vault_url, e))
+            .map_err(|e| {
+                EncryptionError::EncryptionFailed(format!("Key Vault wrap failed: {}", e))
+            })?
+            .into_model()
+            .map_err(|e| {
+                EncryptionError::EncryptionFailed(format!("Failed to parse response: {}", e))
+            })?;
+
+        let ciphertext = result.result.ok_or_else(|| {
+            EncryptionError::EncryptionFailed("Key Vault wrap returned no result".into())
+        })?;
+
+        // Wrap in the SQL Server format and sign it with the same key
+        let mut encrypted_cek = encode_encrypted_cek(cmk_path, &ciphertext)?;
+        let signature = self
+            .sign_data(cmk_path, &Sha256::digest(&encrypted_cek))
+            .await?;
+        encrypted_cek.extend_from_slice(&signature);
+
+        debug!("Successfully encrypted CEK using Azure Key Vault");
+        Ok(encrypted_cek)
+    }
+
     #[instrument(skip(self, data), fields(cmk_path = %cmk_path))]
     async fn sign_data(&self, cmk_path: &str, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
         debug!("Signing data using Azure Key Vault");
@@ -253,8 +426,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
         // Parse the CMK path
         let (vault_url, key_name, key_version) = Self::parse_cmk_path(cmk_path)?;
 
-        // Create client for this vault
-        let client = self.create_client(&vault_url)?;
+        let client = self.client(&vault_url)?;
 
         // Build sign parameters - use RS256 (RSA-SHA256) by default
         use azure_security_keyvault_keys::models::{
@@ -282,6 +454,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
                 options,
             )
             .await
+            .inspect_err(|e| self.check_auth_error(&vault_url, e))
             .map_err(|e| EncryptionError::CmkError(format!("Key Vault sign failed: {}", e)))?
             .into_model()
             .map_err(|e| EncryptionError::CmkError(format!("Failed to parse response: {}", e)))?;
@@ -307,8 +480,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
         // Parse the CMK path
         let (vault_url, key_name, key_version) = Self::parse_cmk_path(cmk_path)?;
 
-        // Create client for this vault
-        let client = self.create_client(&vault_url)?;
+        let client = self.client(&vault_url)?;
 
         // Build verify parameters
         use azure_security_keyvault_keys::models::{
@@ -337,6 +509,7 @@ impl KeyStoreProvider for AzureKeyVaultProvider {
                 options,
             )
             .await
+            .inspect_err(|e| self.check_auth_error(&vault_url, e))
             .map_err(|e| EncryptionError::CmkError(format!("Key Vault verify failed: {}", e)))?
             .into_model()
             .map_err(|e| EncryptionError::CmkError(format!("Failed to parse response: {}", e)))?;
@@ -419,6 +592,81 @@ fn parse_sql_server_encrypted_cek(data: &[u8]) -> Result<&[u8], EncryptionError>
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_credential_constructors() {
+        let provider = AzureKeyVaultProvider::with_client_secret(
+            "00000000-0000-0000-0000-000000000000",
+            "11111111-1111-1111-1111-111111111111",
+            "secret",
+        )
+        .expect("client secret credential should be created");
+        assert_eq!(provider.provider_name(), PROVIDER_NAME);
+
+        let credential: Arc<dyn TokenCredential> =
+            ManagedIdentityCredential::new(None).expect("managed identity credential");
+        let provider = AzureKeyVaultProvider::with_credential(credential);
+        assert_eq!(provider.provider_name(), PROVIDER_NAME);
+
+        assert!(AzureKeyVaultProvider::with_client_secret("not a tenant", "id", "secret").is_err());
+    }
+
+    #[test]
+    fn test_client_cache() {
+        let credential: Arc<dyn TokenCredential> =
+            ManagedIdentityCredential::new(None).expect("managed identity credential");
+        let provider = AzureKeyVaultProvider::with_credential(credential);
+        let vault = "https://myvault.vault.azure.net";
+
+        let first = provider.client(vault).expect("client");
+        let second = provider.client(vault).expect("client");
+        assert!(Arc::ptr_eq(&first, &second));
+        let other = provider
+            .client("https://other.vault.azure.net")
+            .expect("client");
+        assert!(!Arc::ptr_eq(&first, &other));
+
+        // Only authentication failures drop the cached client
+        let error = azure_core::Error::with_message(ErrorKind::Io, "connection reset");
+        provider.check_auth_error(vault, &error);
+        assert!(Arc::ptr_eq(
+            &first,
+            &provider.client(vault).expect("client")
+        ));
+
+        let error = azure_core::Error::from(ErrorKind::HttpResponse {
+            status: StatusCode::Unauthorized,
+            error_code: None,
+            raw_response: None,
+        });
+        provider.check_auth_error(vault, &error);
+        assert!(!Arc::ptr_eq(
+            &first,
+            &provider.client(vault).expect("client")
+        ));
+
+        provider.clear_clients();
+        assert_eq!(provider.clients.read().len(), 0);
+    }
+
+    #[test]
+    fn test_is_auth_error() {
+        let credential = azure_core::Error::with_message(ErrorKind::Credential, "no token");
+        assert!(is_auth_error(&credential));
+
+        for (status, expected) in [
+            (StatusCode::Unauthorized, true),
+            (StatusCode::Forbidden, true),
+            (StatusCode::NotFound, false),
+        ] {
+            let error = azure_core::Error::from(ErrorKind::HttpResponse {
+                status,
+                error_code: None,
+                raw_response: None,
+            });
+            assert_eq!(is_auth_error(&error), expected);
+        }
+    }
+
     #[test]
     fn test_parse_cmk_path() {
         // Full path with version
@@ -1,8 +1,9 @@
 //! Credential types for authentication.
 //!
 //! This module provides credential types for various SQL Server authentication methods.
-//! When the `zeroize` feature is enabled, sensitive credential data is securely
-//! zeroed from memory when dropped.
+//! Passwords, tokens and client secrets are held in [`SecretString`], which
+//! securely zeroes them from memory when dropped if the `zeroize` feature is
+//! enabled. `Debug` output never includes them.
 
 use std::borrow::Cow;
 
@@ -13,6 +14,9 @@ use zeroize::{Zeroize, ZeroizeOnDrop};
 ///
 /// This enum represents the various authentication methods supported.
 /// Credentials are designed to minimize copying of sensitive data.
+///
+/// Passwords, tokens and client secrets are [`SecretString`]s, zeroed on
+/// drop with the `zeroize` feature.
 #[derive(Clone)]
 pub enum Credentials {
     /// SQL Server authentication with username and password.
@@ -20,13 +24,13 @@ pub enum Credentials {
         /// Username.
         username: Cow<'static, str>,
         /// Password.
-        password: Cow<'static, str>,
+        password: SecretString,
     },
 
     /// Azure Active Directory / Entra ID access token.
     AzureAccessToken {
         /// The access token string.
-        token: Cow<'static, str>,
+        token: SecretString,
     },
 
     /// Azure Managed Identity (for VMs and containers).
@@ -44,7 +48,7 @@ pub enum Credentials {
         /// Client ID.
         client_id: Cow<'static, str>,
         /// Client secret.
-        client_secret: Cow<'static, str>,
+        client_secret: SecretString,
     },
 
     /// Integrated Windows Authentication (Kerberos/NTLM).
@@ -57,7 +61,7 @@ pub enum Credentials {
         /// Path to certificate file.
         cert_path: Cow<'static, str>,
         /// Optional password for encrypted certificates.
-        password: Option<Cow<'static, str>>,
+        password: Option<SecretString>,
     },
 }
 
@@ -69,14 +73,14 @@ impl Credentials {
     ) -> Self {
         Self::SqlServer {
             username: username.into(),
-            password: password.into(),
+            password: SecretString::from(password.into()),
         }
     }
 
     /// Create Azure access token credentials.
     pub fn azure_token(token: impl Into<Cow<'static, str>>) -> Self {
         Self::AzureAccessToken {
-            token: token.into(),
+            token: SecretString::from(token.into()),
         }
     }
 
@@ -163,14 +167,14 @@ impl std::fmt::Debug for Credentials {
 
 /// A secret string that is securely zeroed from memory when dropped.
 ///
-/// This type is only available when the `zeroize` feature is enabled.
-/// It ensures that sensitive data like passwords and tokens are overwritten
-/// with zeros when they go out of scope.
-#[cfg(feature = "zeroize")]
-#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+/// With the `zeroize` feature enabled, sensitive data like passwords and
+/// tokens are overwritten with zeros when they go out of scope. The type and
+/// its `Drop` impl exist either way, so enabling the feature does not change
+/// how values holding it can be moved or matched.
+#[derive(Clone)]
+#[cfg_attr(feature = "zeroize", derive(Zeroize))]
 pub struct SecretString(String);
 
-#[cfg(feature = "zeroize")]
 impl SecretString {
     /// Create a new secret string.
     pub fn new(value: impl Into<String>) -> Self {
@@ -189,27 +193,40 @@ impl SecretString {
     }
 }
 
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        self.0.zeroize();
+    }
+}
+
 #[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SecretString {}
+
 impl std::fmt::Debug for SecretString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[REDACTED]")
     }
 }
 
-#[cfg(feature = "zeroize")]
 impl From<String> for SecretString {
     fn from(s: String) -> Self {
         Self::new(s)
     }
 }
 
-#[cfg(feature = "zeroize")]
 impl From<&str> for SecretString {
     fn from(s: &str) -> Self {
         Self::new(s)
     }
 }
 
+impl From<Cow<'static, str>> for SecretString {
+    fn from(s: Cow<'static, str>) -> Self {
+        Self::new(s)
+    }
+}
+
 /// Secure credentials with automatic zeroization on drop.
 ///
 /// This type is only available when the `zeroize` feature is enabled.
@@ -408,16 +425,19 @@ impl std::fmt::Debug for SecureCredentials {
 impl From<Credentials> for SecureCredentials {
     fn from(creds: Credentials) -> Self {
         match creds {
-            Credentials::SqlServer { username, password } => {
-                SecureCredentials::sql_server(username.into_owned(), password.into_owned())
-            }
-            Credentials::AzureAccessToken { token } => {
-                SecureCredentials::azure_token(token.into_owned())
-            }
+            Credentials::SqlServer { username, password } => SecureCredentials {
+                kind: SecureCredentialKind::SqlServer {
+                    username: username.into_owned(),
+                    password,
+                },
+            },
+            Credentials::AzureAccessToken { token } => SecureCredentials {
+                kind: SecureCredentialKind::AzureAccessToken { token },
+            },
             #[cfg(feature = "azure-identity")]
             Credentials::AzureManagedIdentity { client_id } => SecureCredentials {
                 kind: SecureCredentialKind::AzureManagedIdentity {
-                    client_id: client_id.map(|c| c.into_owned()),
+                    client_id: client_id.map(Cow::into_owned),
                 },
             },
             #[cfg(feature = "azure-identity")]
@@ -429,7 +449,7 @@ impl From<Credentials> for SecureCredentials {
                 kind: SecureCredentialKind::AzureServicePrincipal {
                     tenant_id: tenant_id.into_owned(),
                     client_id: client_id.into_owned(),
-                    client_secret: SecretString::new(client_secret.into_owned()),
+                    client_secret,
                 },
             },
             #[cfg(feature = "integrated-auth")]
@@ -443,7 +463,7 @@ impl From<Credentials> for SecureCredentials {
             } => SecureCredentials {
                 kind: SecureCredentialKind::Certificate {
                     cert_path: cert_path.into_owned(),
-                    password: password.map(|p| SecretString::new(p.into_owned())),
+                    password,
                 },
             },
         }
@@ -460,10 +480,10 @@ mod tests {
         let creds = Credentials::sql_server("user", "password");
         assert!(creds.is_sql_auth());
         assert!(!creds.is_azure_ad());
-        match creds {
+        match &creds {
             Credentials::SqlServer { username, password } => {
                 assert_eq!(username.as_ref(), "user");
-                assert_eq!(password.as_ref(), "password");
+                assert_eq!(password.expose_secret(), "password");
             }
             _ => panic!("Expected SqlServer variant"),
         }
@@ -474,14 +494,25 @@ mod tests {
         let creds = Credentials::azure_token("my-token");
         assert!(!creds.is_sql_auth());
         assert!(creds.is_azure_ad());
-        match creds {
+        match &creds {
             Credentials::AzureAccessToken { token } => {
-                assert_eq!(token.as_ref(), "my-token");
+                assert_eq!(token.expose_secret(), "my-token");
             }
             _ => panic!("Expected AzureAccessToken variant"),
         }
     }
 
+    #[test]
+    fn test_credentials_fields_move_out() {
+        // Builds the same with and without the `zeroize` feature
+        let Credentials::SqlServer { username, password } = Credentials::sql_server("user", "pw")
+        else {
+            panic!("Expected SqlServer variant");
+        };
+        assert_eq!(username, "user");
+        assert_eq!(password.expose_secret(), "pw");
+    }
+
     #[test]
     fn test_credentials_debug_redacts_password() {
         let creds = Credentials::sql_server("user", "supersecret");
@@ -499,6 +530,34 @@ mod tests {
         assert!(debug.contains("REDACTED"));
     }
 
+    #[test]
+    fn test_debug_never_prints_secrets() {
+        const SECRET: &str = "hunter2-s3cr3t";
+        #[allow(unused_mut)]
+        let mut all = vec![
+            Credentials::sql_server("user", SECRET),
+            Credentials::sql_server("user", SECRET.to_string()),
+            Credentials::azure_token(SECRET),
+        ];
+        #[cfg(feature = "azure-identity")]
+        all.push(Credentials::AzureServicePrincipal {
+            tenant_id: "tenant".into(),
+            client_id: "client".into(),
+            client_secret: SECRET.into(),
+        });
+        #[cfg(feature = "cert-auth")]
+        all.push(Credentials::Certificate {
+            cert_path: "client.pfx".into(),
+            password: Some(SECRET.into()),
+        });
+
+        for creds in &all {
+            let debug = format!("{creds:?} {creds:#?}");
+            assert!(!debug.contains(SECRET), "{} leaked", creds.method_name());
+            assert!(debug.contains("[REDACTED]"));
+        }
+    }
+
     #[cfg(feature = "zeroize")]
     mod zeroize_tests {
         use super::*;
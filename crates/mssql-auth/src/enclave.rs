@@ -0,0 +1,1076 @@
+//! Secure enclave support for Always Encrypted.
+//!
+//! With secure enclaves, SQL Server evaluates rich computations on encrypted
+//! columns (range comparisons, `LIKE`, sorting) inside a trusted execution
+//! environment. Before the client hands Column Encryption Keys to the
+//! enclave it proves the enclave is genuine (attestation) and agrees on a
+//! session key with it:
+//!
+//! ```text
+//! Client                                          SQL Server enclave
+//!   │                                                     │
+//!   │ sp_describe_parameter_encryption                    │
+//!   │   @attestationParameters: protocol, nonce, DH key ─▶│
+//!   │                                                     │
+//!   │◀─ attestation info: enclave RSA key, evidence,      │
+//!   │   session id, enclave DH key signed by the RSA key  │
+//!   │                                                     │
+//!   │ verify evidence (HGS / Azure Attestation)           │
+//!   │ verify DH signature, derive session key (ECDH P-384)│
+//!   │                                                     │
+//!   │ RPC + enclave package: session id, AEAD(CEKs) ─────▶│
+//! ```
+//!
+//! ## Attestation Protocols
+//!
+//! | Protocol | Evidence | Verification |
+//! |----------|----------|--------------|
+//! | [`AttestationProtocol::AzureAttestation`] | JWT issued by Microsoft Azure Attestation | `AzureAttestationVerifier` (`azure-attestation` feature) |
+//! | [`AttestationProtocol::HostGuardianService`] | HGS health report and enclave report | A custom [`AttestationVerifier`] |
+//! | [`AttestationProtocol::None`] | None (VBS enclaves, development only) | Not required |
+//!
+//! The evidence check is pluggable through [`AttestationVerifier`]; the key
+//! exchange, the DH signature check and the enclave package are handled
+//! here for every protocol.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, Bytes};
+use parking_lot::RwLock;
+use rand::RngCore;
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey};
+use ring::rand::SystemRandom;
+use ring::signature::{self, RsaPublicKeyComponents};
+use sha2::{Digest, Sha256};
+
+use crate::aead::AeadEncryptor;
+use crate::encryption::{EncryptionError, EncryptionType};
+
+/// `BCRYPT_ECDH_PUBLIC_P384_MAGIC` ("ECK3").
+const ECDH_P384_PUBLIC_MAGIC: u32 = 0x334B_4345;
+
+/// Size of one coordinate of a P-384 point in bytes.
+const P384_COORDINATE_SIZE: usize = 48;
+
+/// `BCRYPT_RSAPUBLIC_MAGIC` ("RSA1").
+const RSA_PUBLIC_MAGIC: u32 = 0x3141_5352;
+
+/// Size of the nonce sent with Azure Attestation requests.
+pub const ATTESTATION_NONCE_SIZE: usize = 256;
+
+/// How long an attested enclave session is reused.
+const ENCLAVE_SESSION_TTL: Duration = Duration::from_secs(8 * 60 * 60);
+
+/// Protocol used to attest a secure enclave.
+///
+/// Parses from the connection-string values `HGS`, `AAS` and `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AttestationProtocol {
+    /// Microsoft Azure Attestation (SGX and VBS enclaves in Azure SQL).
+    AzureAttestation,
+    /// No attestation (VBS enclaves only, for development and testing).
+    None,
+    /// Host Guardian Service (VBS enclaves in SQL Server).
+    HostGuardianService,
+}
+
+impl AttestationProtocol {
+    /// The protocol identifier sent in the attestation parameters.
+    #[must_use]
+    pub fn protocol_id(self) -> u32 {
+        match self {
+            Self::AzureAttestation => 1,
+            Self::None => 2,
+            Self::HostGuardianService => 3,
+        }
+    }
+}
+
+impl FromStr for AttestationProtocol {
+    type Err = EncryptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("AAS") {
+            Ok(Self::AzureAttestation)
+        } else if s.eq_ignore_ascii_case("HGS") {
+            Ok(Self::HostGuardianService)
+        } else if s.eq_ignore_ascii_case("None") {
+            Ok(Self::None)
+        } else {
+            Err(EncryptionError::ConfigurationError(format!(
+                "unknown attestation protocol: {s}"
+            )))
+        }
+    }
+}
+
+impl fmt::Display for AttestationProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::AzureAttestation => "AAS",
+            Self::None => "None",
+            Self::HostGuardianService => "HGS",
+        })
+    }
+}
+
+/// Type of a secure enclave, as reported in its attestation information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EnclaveType {
+    /// Virtualization-based security enclave.
+    Vbs,
+    /// Intel Software Guard Extensions enclave.
+    Sgx,
+}
+
+impl EnclaveType {
+    /// Map the wire value used in attestation information.
+    #[must_use]
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            1 => Some(Self::Vbs),
+            2 => Some(Self::Sgx),
+            _ => None,
+        }
+    }
+}
+
+/// Verifies the attestation evidence returned by a secure enclave.
+///
+/// A verifier proves that the enclave identity key in [`AttestationInfo`]
+/// belongs to a genuine, correctly configured enclave. The key exchange
+/// itself is checked against that identity key afterwards, so a verifier
+/// only needs to establish trust in the identity.
+#[async_trait::async_trait]
+pub trait AttestationVerifier: Send + Sync {
+    /// The attestation protocol whose evidence this verifier checks.
+    fn protocol(&self) -> AttestationProtocol;
+
+    /// Verify the enclave's attestation evidence.
+    ///
+    /// # Arguments
+    ///
+    /// * `attestation_url` - The attestation service URL from the configuration
+    /// * `info` - The attestation information returned by the server
+    /// * `nonce` - The nonce sent with the attestation parameters (empty
+    ///   for protocols that do not use one)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncryptionError::AttestationFailed`] if the evidence is not
+    /// trusted or not bound to `info.identity`.
+    async fn verify(
+        &self,
+        attestation_url: &str,
+        info: &AttestationInfo,
+        nonce: &[u8],
+    ) -> Result<(), EncryptionError>;
+}
+
+/// The RSA identity key of a secure enclave.
+///
+/// Received as a `BCRYPT_RSAKEY_BLOB`; the enclave signs its DH public key
+/// with it.
+#[derive(Clone)]
+pub struct EnclavePublicKey {
+    blob: Bytes,
+    exponent: Bytes,
+    modulus: Bytes,
+}
+
+impl EnclavePublicKey {
+    /// Parse a `BCRYPT_RSAKEY_BLOB`.
+    pub fn from_blob(blob: Bytes) -> Result<Self, EncryptionError> {
+        let mut reader = Reader(blob.clone());
+        if reader.u32()? != RSA_PUBLIC_MAGIC {
+            return Err(EncryptionError::AttestationFailed(
+                "enclave public key is not an RSA public key blob".into(),
+            ));
+        }
+        let _bit_length = reader.u32()?;
+        let exponent_len = reader.u32()?;
+        let modulus_len = reader.u32()?;
+        let _prime1_len = reader.u32()?;
+        let _prime2_len = reader.u32()?;
+        let exponent = reader.bytes(exponent_len)?;
+        let modulus = reader.bytes(modulus_len)?;
+
+        Ok(Self {
+            blob,
+            exponent,
+            modulus,
+        })
+    }
+
+    /// The key as received from the server.
+    #[must_use]
+    pub fn as_blob(&self) -> &[u8] {
+        &self.blob
+    }
+
+    /// Verify an RSA PKCS#1 v1.5 SHA-256 signature made with this key.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), EncryptionError> {
+        RsaPublicKeyComponents {
+            n: &self.modulus[..],
+            e: &self.exponent[..],
+        }
+        .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature)
+        .map_err(|_| {
+            EncryptionError::AttestationFailed(
+                "signature does not match the enclave public key".into(),
+            )
+        })
+    }
+}
+
+impl fmt::Debug for EnclavePublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnclavePublicKey")
+            .field("modulus_bits", &(self.modulus.len() * 8))
+            .finish()
+    }
+}
+
+/// The enclave's half of the key exchange.
+#[derive(Debug, Clone)]
+pub struct EnclaveDhInfo {
+    /// Enclave ECDH P-384 public key (`BCRYPT_ECCKEY_BLOB`).
+    pub public_key: Bytes,
+    /// Signature of `public_key` by the enclave identity key.
+    pub signature: Bytes,
+}
+
+impl EnclaveDhInfo {
+    fn read(reader: &mut Reader) -> Result<Self, EncryptionError> {
+        let key_len = reader.u32()?;
+        let signature_len = reader.u32()?;
+        Ok(Self {
+            public_key: reader.bytes(key_len)?,
+            signature: reader.bytes(signature_len)?,
+        })
+    }
+}
+
+/// Protocol-specific evidence that an enclave is genuine.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum AttestationEvidence {
+    /// JSON Web Token issued by Microsoft Azure Attestation.
+    AzureAttestationToken(String),
+    /// Host Guardian Service health report and signed enclave report.
+    HostGuardianService {
+        /// Health certificate of the host, issued by HGS.
+        health_report: Bytes,
+        /// Enclave report package, signed by the host.
+        enclave_report: Bytes,
+    },
+    /// No evidence (attestation protocol `None`).
+    None,
+}
+
+/// Attestation information returned by `sp_describe_parameter_encryption`.
+#[derive(Debug, Clone)]
+pub struct AttestationInfo {
+    /// The enclave identity key (absent with attestation protocol `None`).
+    pub identity: Option<EnclavePublicKey>,
+    /// The enclave type, when reported.
+    pub enclave_type: Option<EnclaveType>,
+    /// Protocol-specific evidence.
+    pub evidence: AttestationEvidence,
+    /// Enclave session identifier.
+    pub session_id: i64,
+    /// The enclave's half of the key exchange.
+    pub dh_info: EnclaveDhInfo,
+}
+
+impl AttestationInfo {
+    /// Parse the attestation information of the given protocol.
+    ///
+    /// ```text
+    /// AAS:  size:u32 identity_len:u32 token_len:u32 enclave_type:u32
+    ///       identity token session_info
+    /// HGS:  size:u32 identity_len:u32 health_len:u32 report_len:u32
+    ///       identity health_report enclave_report enclave_type:u32 session_info
+    /// None: module_info_len:u32 module_info session_info
+    ///
+    /// session_info: size:u32 session_id:i64 key_len:u32 sig_len:u32 key sig
+    /// ```
+    pub fn parse(protocol: AttestationProtocol, data: &[u8]) -> Result<Self, EncryptionError> {
+        let mut reader = Reader(Bytes::copy_from_slice(data));
+
+        let (identity, enclave_type, evidence) = match protocol {
+            AttestationProtocol::AzureAttestation => {
+                let _size = reader.u32()?;
+                let identity_len = reader.u32()?;
+                let token_len = reader.u32()?;
+                let enclave_type = EnclaveType::from_u32(reader.u32()?);
+                let identity = EnclavePublicKey::from_blob(reader.bytes(identity_len)?)?;
+                let token = decode_token(&reader.bytes(token_len)?)?;
+                (
+                    Some(identity),
+                    enclave_type,
+                    AttestationEvidence::AzureAttestationToken(token),
+                )
+            }
+            AttestationProtocol::HostGuardianService => {
+                let _size = reader.u32()?;
+                let identity_len = reader.u32()?;
+                let health_report_len = reader.u32()?;
+                let enclave_report_len = reader.u32()?;
+                let identity = EnclavePublicKey::from_blob(reader.bytes(identity_len)?)?;
+                let health_report = reader.bytes(health_report_len)?;
+                let enclave_report = reader.bytes(enclave_report_len)?;
+                let enclave_type = EnclaveType::from_u32(reader.u32()?);
+                (
+                    Some(identity),
+                    enclave_type,
+                    AttestationEvidence::HostGuardianService {
+                        health_report,
+                        enclave_report,
+                    },
+                )
+            }
+            AttestationProtocol::None => {
+                let module_info_len = reader.u32()?;
+                reader.bytes(module_info_len)?;
+                (None, None, AttestationEvidence::None)
+            }
+        };
+
+        let _session_info_len = reader.u32()?;
+        let session_id = reader.i64()?;
+        let dh_info = EnclaveDhInfo::read(&mut reader)?;
+
+        Ok(Self {
+            identity,
+            enclave_type,
+            evidence,
+            session_id,
+            dh_info,
+        })
+    }
+}
+
+/// Decode an attestation token sent as UTF-16LE or UTF-8 text.
+fn decode_token(raw: &[u8]) -> Result<String, EncryptionError> {
+    let token = if raw.len() >= 2 && raw.len() % 2 == 0 && raw[1] == 0 {
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&units).ok()
+    } else {
+        String::from_utf8(raw.to_vec()).ok()
+    };
+
+    token
+        .map(|t| t.trim_end_matches('\0').to_string())
+        .ok_or_else(|| EncryptionError::AttestationFailed("attestation token is not text".into()))
+}
+
+/// An in-flight attestation: the parameters sent to the server and the
+/// client secrets needed to complete the key exchange.
+pub struct AttestationRequest {
+    protocol: AttestationProtocol,
+    attestation_url: String,
+    nonce: Vec<u8>,
+    private_key: EphemeralPrivateKey,
+    parameters: Vec<u8>,
+}
+
+impl AttestationRequest {
+    /// Start an attestation with a fresh ECDH P-384 key pair.
+    pub fn new(
+        protocol: AttestationProtocol,
+        attestation_url: impl Into<String>,
+    ) -> Result<Self, EncryptionError> {
+        let attestation_url = attestation_url.into();
+        let rng = SystemRandom::new();
+        let private_key = EphemeralPrivateKey::generate(&agreement::ECDH_P384, &rng)
+            .map_err(|_| EncryptionError::AttestationFailed("failed to generate DH key".into()))?;
+        let public_key = private_key
+            .compute_public_key()
+            .map_err(|_| EncryptionError::AttestationFailed("failed to compute DH key".into()))?;
+        let public_key = point_to_ecdh_blob(public_key.as_ref())?;
+
+        let mut nonce = Vec::new();
+        let mut input = Vec::new();
+        if protocol == AttestationProtocol::AzureAttestation {
+            nonce = vec![0u8; ATTESTATION_NONCE_SIZE];
+            rand::thread_rng().fill_bytes(&mut nonce);
+
+            let url: Vec<u8> = attestation_url
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            put_len_prefixed(&mut input, &url);
+            put_len_prefixed(&mut input, &nonce);
+        }
+
+        let mut parameters = Vec::with_capacity(12 + input.len() + public_key.len());
+        parameters.extend_from_slice(&protocol.protocol_id().to_le_bytes());
+        put_len_prefixed(&mut parameters, &input);
+        put_len_prefixed(&mut parameters, &public_key);
+
+        Ok(Self {
+            protocol,
+            attestation_url,
+            nonce,
+            private_key,
+            parameters,
+        })
+    }
+
+    /// The attestation protocol in use.
+    #[must_use]
+    pub fn protocol(&self) -> AttestationProtocol {
+        self.protocol
+    }
+
+    /// The value of `@attestationParameters` for
+    /// `sp_describe_parameter_encryption`.
+    #[must_use]
+    pub fn parameters(&self) -> &[u8] {
+        &self.parameters
+    }
+
+    /// The nonce the evidence must echo (empty unless Azure Attestation).
+    #[must_use]
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+
+    /// Verify the server's attestation information and derive the session.
+    ///
+    /// The evidence is checked by `verifier` (required unless the protocol
+    /// is [`AttestationProtocol::None`]), the enclave DH key must be signed
+    /// by the attested identity key, and the session key is the SHA-256 of
+    /// the ECDH shared secret.
+    pub async fn establish_session(
+        self,
+        attestation_info: &[u8],
+        verifier: Option<&dyn AttestationVerifier>,
+    ) -> Result<EnclaveSession, EncryptionError> {
+        let info = AttestationInfo::parse(self.protocol, attestation_info)?;
+
+        if self.protocol != AttestationProtocol::None {
+            let verifier = verifier
+                .filter(|v| v.protocol() == self.protocol)
+                .ok_or_else(|| {
+                    EncryptionError::ConfigurationError(format!(
+                        "no attestation verifier registered for protocol {}",
+                        self.protocol
+                    ))
+                })?;
+            verifier
+                .verify(&self.attestation_url, &info, &self.nonce)
+                .await?;
+        }
+
+        if let Some(identity) = &info.identity {
+            identity.verify(&info.dh_info.public_key, &info.dh_info.signature)?;
+        }
+
+        let peer = ecdh_blob_to_point(&info.dh_info.public_key)?;
+        let session_key = agreement::agree_ephemeral(
+            self.private_key,
+            &UnparsedPublicKey::new(&agreement::ECDH_P384, peer),
+            |secret| Sha256::digest(secret).to_vec(),
+        )
+        .map_err(|_| EncryptionError::AttestationFailed("key agreement failed".into()))?;
+
+        tracing::debug!(
+            protocol = %self.protocol,
+            session_id = info.session_id,
+            "enclave session established"
+        );
+        EnclaveSession::new(info.session_id, &session_key)
+    }
+}
+
+impl fmt::Debug for AttestationRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttestationRequest")
+            .field("protocol", &self.protocol)
+            .field("attestation_url", &self.attestation_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A Column Encryption Key sent to the enclave.
+#[derive(Clone)]
+pub struct EnclaveKey {
+    /// Database containing the key.
+    pub database_id: u32,
+    /// Key identifier.
+    pub cek_id: u32,
+    /// Key metadata version.
+    pub cek_md_version: u64,
+    /// The plaintext key (zeroized on drop with the `zeroize` feature).
+    pub key: Vec<u8>,
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for EnclaveKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.key);
+    }
+}
+
+impl fmt::Debug for EnclaveKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnclaveKey")
+            .field("database_id", &self.database_id)
+            .field("cek_id", &self.cek_id)
+            .field("cek_md_version", &self.cek_md_version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An attested session with a secure enclave.
+///
+/// Every enclave package carries a counter that the enclave uses to reject
+/// replays, so one session is shared by all requests that use it.
+pub struct EnclaveSession {
+    session_id: i64,
+    encryptor: AeadEncryptor,
+    counter: AtomicI64,
+}
+
+impl EnclaveSession {
+    /// Create a session from its identifier and 32-byte session key.
+    pub fn new(session_id: i64, session_key: &[u8]) -> Result<Self, EncryptionError> {
+        Ok(Self {
+            session_id,
+            encryptor: AeadEncryptor::new(session_key)?,
+            counter: AtomicI64::new(0),
+        })
+    }
+
+    /// The enclave session identifier.
+    #[must_use]
+    pub fn session_id(&self) -> i64 {
+        self.session_id
+    }
+
+    /// Build the enclave package that hands `keys` to the enclave for `query`.
+    ///
+    /// ```text
+    /// session_id:i64 AEAD(request_id:16 counter:i64 SHA256(query) keys...)
+    /// key: database_id:u32 cek_id:u32 md_version:8 key_len:u16 key
+    /// ```
+    pub fn package(&self, query: &str, keys: &[EnclaveKey]) -> Result<Vec<u8>, EncryptionError> {
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let mut request_id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut request_id);
+        let query: Vec<u8> = query.encode_utf16().flat_map(u16::to_le_bytes).collect();
+
+        let mut plaintext = Vec::with_capacity(56 + keys.len() * 50);
+        plaintext.extend_from_slice(&request_id);
+        plaintext.extend_from_slice(&counter.to_le_bytes());
+        plaintext.extend_from_slice(&Sha256::digest(&query));
+        for key in keys {
+            let key_len = u16::try_from(key.key.len()).map_err(|_| {
+                EncryptionError::EncryptionFailed("column encryption key is too long".into())
+            })?;
+            plaintext.extend_from_slice(&key.database_id.to_le_bytes());
+            plaintext.extend_from_slice(&key.cek_id.to_le_bytes());
+            plaintext.extend_from_slice(&key.cek_md_version.to_le_bytes());
+            plaintext.extend_from_slice(&key_len.to_le_bytes());
+            plaintext.extend_from_slice(&key.key);
+        }
+
+        let encrypted = self
+            .encryptor
+            .encrypt(&plaintext, EncryptionType::Randomized);
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut plaintext);
+        let encrypted = encrypted?;
+
+        let mut package = Vec::with_capacity(8 + encrypted.len());
+        package.extend_from_slice(&self.session_id.to_le_bytes());
+        package.extend_from_slice(&encrypted);
+        Ok(package)
+    }
+}
+
+impl fmt::Debug for EnclaveSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnclaveSession")
+            .field("session_id", &self.session_id)
+            .field("counter", &self.counter.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Append a u32 length prefix and the data.
+/// Cache of attested enclave sessions, keyed by server and database.
+///
+/// Attestation costs a round trip to the attestation service, so sessions
+/// are reused by later connections until they expire.
+pub struct EnclaveSessionCache {
+    sessions: RwLock<HashMap<String, (Instant, Arc<EnclaveSession>)>>,
+    ttl: Duration,
+}
+
+impl EnclaveSessionCache {
+    /// Create a cache with the default TTL (8 hours).
+    pub fn new() -> Self {
+        Self::with_ttl(ENCLAVE_SESSION_TTL)
+    }
+
+    /// Create a cache with a custom TTL.
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            sessions: RwLock::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Get the session for `key`, if attested and not expired.
+    pub fn get(&self, key: &str) -> Option<Arc<EnclaveSession>> {
+        let sessions = self.sessions.read();
+        sessions
+            .get(key)
+            .filter(|(created_at, _)| created_at.elapsed() < self.ttl)
+            .map(|(_, session)| Arc::clone(session))
+    }
+
+    /// Cache a newly attested session.
+    pub fn insert(&self, key: impl Into<String>, session: EnclaveSession) -> Arc<EnclaveSession> {
+        let session = Arc::new(session);
+        self.sessions
+            .write()
+            .insert(key.into(), (Instant::now(), Arc::clone(&session)));
+        session
+    }
+
+    /// Forget the session for `key`, e.g. after the enclave rejected it.
+    pub fn remove(&self, key: &str) {
+        self.sessions.write().remove(key);
+    }
+
+    /// Forget all sessions.
+    pub fn clear(&self) {
+        self.sessions.write().clear();
+    }
+
+    /// Number of cached sessions, including expired ones.
+    pub fn len(&self) -> usize {
+        self.sessions.read().len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.read().is_empty()
+    }
+}
+
+impl Default for EnclaveSessionCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EnclaveSessionCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnclaveSessionCache")
+            .field("sessions", &self.len())
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+/// The digest a Column Master Key signs to allow enclave computations.
+///
+/// SQL Server stores the signature with the CMK metadata; the client checks
+/// it with [`KeyStoreProvider::verify_signature`] before sending keys
+/// protected by that CMK to an enclave.
+///
+/// [`KeyStoreProvider::verify_signature`]: crate::KeyStoreProvider::verify_signature
+#[must_use]
+pub fn cmk_metadata_digest(
+    provider_name: &str,
+    cmk_path: &str,
+    allow_enclave_computations: bool,
+) -> [u8; 32] {
+    let metadata = format!(
+        "{}{}{}",
+        provider_name.to_lowercase(),
+        cmk_path.to_lowercase(),
+        allow_enclave_computations
+    );
+    let metadata: Vec<u8> = metadata.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    Sha256::digest(&metadata).into()
+}
+
+fn put_len_prefixed(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+}
+
+/// Convert an uncompressed P-384 point to a `BCRYPT_ECCKEY_BLOB`.
+fn point_to_ecdh_blob(point: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    match point.split_first() {
+        Some((0x04, coordinates)) if coordinates.len() == 2 * P384_COORDINATE_SIZE => {
+            let mut blob = Vec::with_capacity(8 + coordinates.len());
+            blob.extend_from_slice(&ECDH_P384_PUBLIC_MAGIC.to_le_bytes());
+            blob.extend_from_slice(&(P384_COORDINATE_SIZE as u32).to_le_bytes());
+            blob.extend_from_slice(coordinates);
+            Ok(blob)
+        }
+        _ => Err(EncryptionError::AttestationFailed(
+            "unexpected DH public key encoding".into(),
+        )),
+    }
+}
+
+/// Convert a `BCRYPT_ECCKEY_BLOB` to an uncompressed P-384 point.
+fn ecdh_blob_to_point(blob: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let mut reader = Reader(Bytes::copy_from_slice(blob));
+    let magic = reader.u32()?;
+    let coordinate_len = reader.u32()?;
+    if magic != ECDH_P384_PUBLIC_MAGIC || coordinate_len as usize != P384_COORDINATE_SIZE {
+        return Err(EncryptionError::AttestationFailed(
+            "enclave DH key is not an ECDH P-384 public key blob".into(),
+        ));
+    }
+    let coordinates = reader.bytes(coordinate_len * 2)?;
+
+    let mut point = Vec::with_capacity(1 + coordinates.len());
+    point.push(0x04);
+    point.extend_from_slice(&coordinates);
+    Ok(point)
+}
+
+/// Bounds-checked little-endian reader over attestation data.
+struct Reader(Bytes);
+
+impl Reader {
+    fn truncated() -> EncryptionError {
+        EncryptionError::AttestationFailed("attestation information is truncated".into())
+    }
+
+    fn u32(&mut self) -> Result<u32, EncryptionError> {
+        if self.0.remaining() < 4 {
+            return Err(Self::truncated());
+        }
+        Ok(self.0.get_u32_le())
+    }
+
+    fn i64(&mut self) -> Result<i64, EncryptionError> {
+        if self.0.remaining() < 8 {
+            return Err(Self::truncated());
+        }
+        Ok(self.0.get_i64_le())
+    }
+
+    fn bytes(&mut self, len: u32) -> Result<Bytes, EncryptionError> {
+        let len = len as usize;
+        if self.0.remaining() < len {
+            return Err(Self::truncated());
+        }
+        Ok(self.0.split_to(len))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use rsa::traits::PublicKeyParts;
+    use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+
+    /// A simulated enclave: identity key and DH key pair.
+    struct TestEnclave {
+        identity: RsaPrivateKey,
+        dh_key: EphemeralPrivateKey,
+    }
+
+    impl TestEnclave {
+        fn new() -> Self {
+            // Key generation is slow in debug builds, so tests share one key
+            static IDENTITY: std::sync::OnceLock<RsaPrivateKey> = std::sync::OnceLock::new();
+            let identity = IDENTITY
+                .get_or_init(|| RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap())
+                .clone();
+            let dh_key =
+                EphemeralPrivateKey::generate(&agreement::ECDH_P384, &SystemRandom::new()).unwrap();
+            Self { identity, dh_key }
+        }
+
+        fn identity_blob(&self) -> Vec<u8> {
+            let exponent = self.identity.e().to_bytes_be();
+            let modulus = self.identity.n().to_bytes_be();
+            let mut blob = Vec::new();
+            blob.extend_from_slice(&RSA_PUBLIC_MAGIC.to_le_bytes());
+            blob.extend_from_slice(&(modulus.len() as u32 * 8).to_le_bytes());
+            blob.extend_from_slice(&(exponent.len() as u32).to_le_bytes());
+            blob.extend_from_slice(&(modulus.len() as u32).to_le_bytes());
+            blob.extend_from_slice(&0u32.to_le_bytes());
+            blob.extend_from_slice(&0u32.to_le_bytes());
+            blob.extend_from_slice(&exponent);
+            blob.extend_from_slice(&modulus);
+            blob
+        }
+
+        fn session_info(&self, session_id: i64) -> Vec<u8> {
+            let dh_public =
+                point_to_ecdh_blob(self.dh_key.compute_public_key().unwrap().as_ref()).unwrap();
+            let signature = self
+                .identity
+                .sign(
+                    Pkcs1v15Sign::new::<Sha256>(),
+                    Sha256::digest(&dh_public).as_slice(),
+                )
+                .unwrap();
+
+            let mut info = Vec::new();
+            info.extend_from_slice(&0u32.to_le_bytes());
+            info.extend_from_slice(&session_id.to_le_bytes());
+            put_len_prefixed_pair(&mut info, &dh_public, &signature);
+            info
+        }
+
+        fn client_session_key(self, request: &AttestationRequest) -> Vec<u8> {
+            // Client DH key is the last length-prefixed field of the parameters
+            let params = request.parameters();
+            let client_blob = &params[params.len() - 104..];
+            agreement::agree_ephemeral(
+                self.dh_key,
+                &UnparsedPublicKey::new(
+                    &agreement::ECDH_P384,
+                    ecdh_blob_to_point(client_blob).unwrap(),
+                ),
+                |secret| Sha256::digest(secret).to_vec(),
+            )
+            .unwrap()
+        }
+    }
+
+    fn put_len_prefixed_pair(buf: &mut Vec<u8>, first: &[u8], second: &[u8]) {
+        buf.extend_from_slice(&(first.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&(second.len() as u32).to_le_bytes());
+        buf.extend_from_slice(first);
+        buf.extend_from_slice(second);
+    }
+
+    /// Accepts any evidence, for exercising the key exchange.
+    struct AcceptAll(AttestationProtocol);
+
+    #[async_trait::async_trait]
+    impl AttestationVerifier for AcceptAll {
+        fn protocol(&self) -> AttestationProtocol {
+            self.0
+        }
+
+        async fn verify(
+            &self,
+            _attestation_url: &str,
+            info: &AttestationInfo,
+            nonce: &[u8],
+        ) -> Result<(), EncryptionError> {
+            assert_eq!(nonce.len(), ATTESTATION_NONCE_SIZE);
+            assert!(matches!(
+                &info.evidence,
+                AttestationEvidence::AzureAttestationToken(t) if t == "header.claims.sig"
+            ));
+            Ok(())
+        }
+    }
+
+    fn aas_attestation_info(enclave: &TestEnclave, session_id: i64) -> Vec<u8> {
+        let identity = enclave.identity_blob();
+        let token: Vec<u8> = "header.claims.sig\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        let mut info = Vec::new();
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.extend_from_slice(&(identity.len() as u32).to_le_bytes());
+        info.extend_from_slice(&(token.len() as u32).to_le_bytes());
+        info.extend_from_slice(&2u32.to_le_bytes());
+        info.extend_from_slice(&identity);
+        info.extend_from_slice(&token);
+        info.extend_from_slice(&enclave.session_info(session_id));
+        info
+    }
+
+    #[test]
+    fn test_attestation_protocol_parse() {
+        assert_eq!(
+            "aas".parse::<AttestationProtocol>().unwrap(),
+            AttestationProtocol::AzureAttestation
+        );
+        assert_eq!(
+            "HGS".parse::<AttestationProtocol>().unwrap(),
+            AttestationProtocol::HostGuardianService
+        );
+        assert_eq!(
+            "None".parse::<AttestationProtocol>().unwrap(),
+            AttestationProtocol::None
+        );
+        assert!("SIM".parse::<AttestationProtocol>().is_err());
+        assert_eq!(AttestationProtocol::HostGuardianService.protocol_id(), 3);
+    }
+
+    #[test]
+    fn test_attestation_parameters_layout() {
+        let request =
+            AttestationRequest::new(AttestationProtocol::AzureAttestation, "https://a.b").unwrap();
+        let params = request.parameters();
+
+        assert_eq!(&params[0..4], &1u32.to_le_bytes());
+        // url (UTF-16 with terminator) + nonce, each length prefixed
+        let url_len = "https://a.b".len() * 2 + 2;
+        let input_len = 4 + url_len + 4 + ATTESTATION_NONCE_SIZE;
+        assert_eq!(&params[4..8], &(input_len as u32).to_le_bytes());
+        assert_eq!(&params[8..12], &(url_len as u32).to_le_bytes());
+
+        let key = &params[8 + input_len..];
+        assert_eq!(&key[0..4], &104u32.to_le_bytes());
+        assert_eq!(&key[4..8], &ECDH_P384_PUBLIC_MAGIC.to_le_bytes());
+        assert_eq!(key.len(), 4 + 104);
+
+        let hgs = AttestationRequest::new(AttestationProtocol::HostGuardianService, "x").unwrap();
+        assert_eq!(&hgs.parameters()[0..8], &[3, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(hgs.nonce().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_establish_session_and_package() {
+        let enclave = TestEnclave::new();
+        let request =
+            AttestationRequest::new(AttestationProtocol::AzureAttestation, "https://a.b").unwrap();
+        let info = aas_attestation_info(&enclave, 42);
+
+        let verifier = AcceptAll(AttestationProtocol::AzureAttestation);
+        let enclave_key = enclave.client_session_key(&request);
+        let session = request
+            .establish_session(&info, Some(&verifier))
+            .await
+            .unwrap();
+        assert_eq!(session.session_id(), 42);
+
+        let key = EnclaveKey {
+            database_id: 5,
+            cek_id: 7,
+            cek_md_version: 0x0102_0304_0506_0708,
+            key: vec![0xAB; 32],
+        };
+        let package = session.package("SELECT 1", &[key]).unwrap();
+        assert_eq!(&package[..8], &42i64.to_le_bytes());
+
+        // The enclave decrypts the package with its side of the agreement
+        let plaintext = AeadEncryptor::new(&enclave_key)
+            .unwrap()
+            .decrypt(&package[8..])
+            .unwrap();
+        assert_eq!(&plaintext[16..24], &1i64.to_le_bytes());
+        let query: Vec<u8> = "SELECT 1"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(&plaintext[24..56], Sha256::digest(&query).as_slice());
+        assert_eq!(&plaintext[56..60], &5u32.to_le_bytes());
+        assert_eq!(&plaintext[60..64], &7u32.to_le_bytes());
+        assert_eq!(&plaintext[72..74], &32u16.to_le_bytes());
+        assert_eq!(&plaintext[74..], &[0xAB; 32]);
+
+        // The counter advances with every package
+        let second = session.package("SELECT 1", &[]).unwrap();
+        let plaintext = AeadEncryptor::new(&enclave_key)
+            .unwrap()
+            .decrypt(&second[8..])
+            .unwrap();
+        assert_eq!(&plaintext[16..24], &2i64.to_le_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_establish_session_rejects_bad_dh_signature() {
+        let enclave = TestEnclave::new();
+        let request =
+            AttestationRequest::new(AttestationProtocol::AzureAttestation, "https://a.b").unwrap();
+        let mut info = aas_attestation_info(&enclave, 1);
+        let last = info.len() - 1;
+        info[last] ^= 0xFF;
+
+        let verifier = AcceptAll(AttestationProtocol::AzureAttestation);
+        let result = request.establish_session(&info, Some(&verifier)).await;
+        assert!(matches!(result, Err(EncryptionError::AttestationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_establish_session_requires_verifier() {
+        let enclave = TestEnclave::new();
+        let request =
+            AttestationRequest::new(AttestationProtocol::AzureAttestation, "https://a.b").unwrap();
+        let info = aas_attestation_info(&enclave, 1);
+
+        let result = request.establish_session(&info, None).await;
+        assert!(matches!(
+            result,
+            Err(EncryptionError::ConfigurationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_establish_session_without_attestation() {
+        let enclave = TestEnclave::new();
+        let request = AttestationRequest::new(AttestationProtocol::None, "").unwrap();
+
+        let mut info = Vec::new();
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.extend_from_slice(&enclave.session_info(9));
+
+        let session = request.establish_session(&info, None).await.unwrap();
+        assert_eq!(session.session_id(), 9);
+    }
+
+    #[test]
+    fn test_enclave_session_cache() {
+        let cache = EnclaveSessionCache::new();
+        assert!(cache.get("server/db").is_none());
+
+        let session = cache.insert("server/db", EnclaveSession::new(7, &[1u8; 32]).unwrap());
+        assert_eq!(
+            cache.get("server/db").unwrap().session_id(),
+            session.session_id()
+        );
+
+        cache.remove("server/db");
+        assert!(cache.is_empty());
+
+        let expired = EnclaveSessionCache::with_ttl(Duration::ZERO);
+        expired.insert("server/db", EnclaveSession::new(7, &[1u8; 32]).unwrap());
+        assert!(expired.get("server/db").is_none());
+    }
+
+    #[test]
+    fn test_cmk_metadata_digest() {
+        let expected: Vec<u8> = "mssql_certificate_storecurrentuser/my/abctrue"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            cmk_metadata_digest("MSSQL_CERTIFICATE_STORE", "CurrentUser/My/ABC", true),
+            <[u8; 32]>::from(Sha256::digest(&expected))
+        );
+    }
+
+    #[test]
+    fn test_attestation_info_truncated() {
+        let result = AttestationInfo::parse(AttestationProtocol::HostGuardianService, &[0; 10]);
+        assert!(matches!(result, Err(EncryptionError::AttestationFailed(_))));
+    }
+}
@@ -209,6 +209,8 @@ pub enum EncryptionError {
     UnsupportedOperation(String),
     /// Configuration error.
     ConfigurationError(String),
+    /// The secure enclave could not be attested or its session established.
+    AttestationFailed(String),
 }
 
 impl std::error::Error for EncryptionError {}
@@ -240,6 +242,9 @@ impl fmt::Display for EncryptionError {
             EncryptionError::ConfigurationError(msg) => {
                 write!(f, "Encryption configuration error: {}", msg)
             }
+            EncryptionError::AttestationFailed(msg) => {
+                write!(f, "Enclave attestation failed: {}", msg)
+            }
         }
     }
 }
@@ -316,6 +321,22 @@ pub trait KeyStoreProvider: Send + Sync {
         encrypted_cek: &[u8],
     ) -> Result<Vec<u8>, EncryptionError>;
 
+    /// Encrypt a Column Encryption Key (CEK) with the Column Master Key (optional).
+    ///
+    /// Used to rotate CMKs: the result is a signed `ENCRYPTED_VALUE` in the
+    /// format read by [`decrypt_cek`](Self::decrypt_cek).
+    /// Default implementation returns an error indicating it's not supported.
+    async fn encrypt_cek(
+        &self,
+        _cmk_path: &str,
+        _algorithm: &str,
+        _cek: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        Err(EncryptionError::UnsupportedOperation(
+            "CEK encryption not supported by this key store provider".into(),
+        ))
+    }
+
     /// Sign data using the Column Master Key (optional).
     ///
     /// This is used for key attestation in Secure Enclaves.
@@ -388,11 +409,11 @@ impl ColumnEncryptionConfig {
         self
     }
 
-    /// Get a provider by name.
+    /// Get a provider by name, ignoring case.
     pub fn get_provider(&self, name: &str) -> Option<&dyn KeyStoreProvider> {
         self.providers
             .iter()
-            .find(|p| p.provider_name() == name)
+            .find(|p| p.provider_name().eq_ignore_ascii_case(name))
             .map(|p| p.as_ref())
     }
 
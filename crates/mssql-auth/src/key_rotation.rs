@@ -0,0 +1,332 @@
+//! Column Master Key rotation for Always Encrypted.
+//!
+//! Rotating a CMK re-encrypts every Column Encryption Key it protects with
+//! a new CMK. The column data itself is untouched, so rotation is online:
+//!
+//! ```text
+//! 1. CREATE COLUMN MASTER KEY [new]                  ColumnMasterKey::create_statement
+//! 2. ALTER COLUMN ENCRYPTION KEY [cek] ADD VALUE     rotate_column_encryption_key
+//!    (CEK now has two values: old CMK and new CMK)
+//! 3. Refresh clients so they pick up the new value
+//! 4. ALTER COLUMN ENCRYPTION KEY [cek] DROP VALUE    CekRotation::drop_value
+//! 5. DROP COLUMN MASTER KEY [old]                    ColumnMasterKey::drop_statement
+//! ```
+//!
+//! The helpers only build statements; run them with any connection that
+//! has `ALTER ANY COLUMN MASTER KEY` and `ALTER ANY COLUMN ENCRYPTION KEY`.
+//! The encrypted CEK values come from `sys.column_encryption_key_values`.
+//!
+//! The new CMK's provider must implement
+//! [`KeyStoreProvider::encrypt_cek`]; [`InMemoryKeyStore`](crate::InMemoryKeyStore)
+//! and `AzureKeyVaultProvider` do.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use mssql_auth::key_rotation::{ColumnMasterKey, rotate_column_encryption_key};
+//!
+//! let old = ColumnMasterKey::new("CMK1", &key_vault, "https://vault.vault.azure.net/keys/cmk1/v1");
+//! let new = ColumnMasterKey::new("CMK2", &key_vault, "https://vault.vault.azure.net/keys/cmk2/v1");
+//!
+//! client.execute(&new.create_statement(None), &[]).await?;
+//! let rotation = rotate_column_encryption_key("CEK1", &encrypted_value, &old, &new).await?;
+//! client.execute(&rotation.add_value, &[]).await?;
+//! // ... once every client uses the new value:
+//! client.execute(&rotation.drop_value, &[]).await?;
+//! client.execute(&old.drop_statement(), &[]).await?;
+//! ```
+
+use std::fmt;
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+
+use crate::enclave::cmk_metadata_digest;
+use crate::encryption::{EncryptionError, KeyStoreProvider};
+use crate::key_unwrap::split_encrypted_cek_signature;
+
+/// Asymmetric algorithm used to encrypt CEKs.
+const CEK_ALGORITHM: &str = "RSA_OAEP";
+
+/// A Column Master Key: its name in the database and where its key lives.
+pub struct ColumnMasterKey<'a> {
+    /// Name of the CMK in the database.
+    pub name: &'a str,
+    /// Key store holding the key.
+    pub provider: &'a dyn KeyStoreProvider,
+    /// Path of the key in the key store.
+    pub key_path: &'a str,
+}
+
+impl<'a> ColumnMasterKey<'a> {
+    /// Describe a Column Master Key.
+    pub fn new(name: &'a str, provider: &'a dyn KeyStoreProvider, key_path: &'a str) -> Self {
+        Self {
+            name,
+            provider,
+            key_path,
+        }
+    }
+
+    /// Sign the CMK metadata to allow enclave computations.
+    ///
+    /// Pass the signature to [`create_statement`](Self::create_statement).
+    pub async fn sign_enclave_computations(&self) -> Result<Vec<u8>, EncryptionError> {
+        let digest = cmk_metadata_digest(self.provider.provider_name(), self.key_path, true);
+        let signature = self.provider.sign_data(self.key_path, &digest).await?;
+        self.verify_enclave_computations(&signature).await?;
+        Ok(signature)
+    }
+
+    /// Verify the signature allowing enclave computations with this CMK.
+    pub async fn verify_enclave_computations(
+        &self,
+        signature: &[u8],
+    ) -> Result<(), EncryptionError> {
+        let digest = cmk_metadata_digest(self.provider.provider_name(), self.key_path, true);
+        if self
+            .provider
+            .verify_signature(self.key_path, &digest, signature)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(EncryptionError::CmkError(format!(
+                "invalid enclave computations signature for column master key {}",
+                self.name
+            )))
+        }
+    }
+
+    /// The `CREATE COLUMN MASTER KEY` statement for this CMK.
+    ///
+    /// With a signature from
+    /// [`sign_enclave_computations`](Self::sign_enclave_computations) the
+    /// key allows enclave computations.
+    pub fn create_statement(&self, enclave_signature: Option<&[u8]>) -> String {
+        let mut sql = format!(
+            "CREATE COLUMN MASTER KEY {} WITH (KEY_STORE_PROVIDER_NAME = {}, KEY_PATH = {}",
+            quote_name(self.name),
+            quote_string(self.provider.provider_name()),
+            quote_string(self.key_path)
+        );
+        if let Some(signature) = enclave_signature {
+            let _ = write!(
+                sql,
+                ", ENCLAVE_COMPUTATIONS (SIGNATURE = {})",
+                hex_literal(signature)
+            );
+        }
+        sql.push(')');
+        sql
+    }
+
+    /// The `DROP COLUMN MASTER KEY` statement for this CMK.
+    pub fn drop_statement(&self) -> String {
+        format!("DROP COLUMN MASTER KEY {}", quote_name(self.name))
+    }
+}
+
+impl fmt::Debug for ColumnMasterKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColumnMasterKey")
+            .field("name", &self.name)
+            .field("provider", &self.provider.provider_name())
+            .field("key_path", &self.key_path)
+            .finish()
+    }
+}
+
+/// A Column Encryption Key re-encrypted with a new Column Master Key.
+#[derive(Debug, Clone)]
+pub struct CekRotation {
+    /// The CEK value encrypted with the new CMK.
+    pub encrypted_value: Vec<u8>,
+    /// `ALTER COLUMN ENCRYPTION KEY ... ADD VALUE` for the new CMK.
+    pub add_value: String,
+    /// `ALTER COLUMN ENCRYPTION KEY ... DROP VALUE` for the old CMK.
+    pub drop_value: String,
+}
+
+/// Re-encrypt a Column Encryption Key from one CMK to another.
+///
+/// `encrypted_value` is the CEK value encrypted with `from`. The key is
+/// decrypted with `from`, encrypted with `to`, and the signature of the new
+/// value is verified before the statements are built.
+pub async fn rotate_column_encryption_key(
+    cek_name: &str,
+    encrypted_value: &[u8],
+    from: &ColumnMasterKey<'_>,
+    to: &ColumnMasterKey<'_>,
+) -> Result<CekRotation, EncryptionError> {
+    let cek = from
+        .provider
+        .decrypt_cek(from.key_path, CEK_ALGORITHM, encrypted_value)
+        .await?;
+    let encrypted = to
+        .provider
+        .encrypt_cek(to.key_path, CEK_ALGORITHM, &cek)
+        .await;
+    #[cfg(feature = "zeroize")]
+    drop(zeroize::Zeroizing::new(cek));
+    let encrypted_value = encrypted?;
+
+    verify_encrypted_cek(to, &encrypted_value).await?;
+
+    tracing::debug!(
+        cek = cek_name,
+        from = from.name,
+        to = to.name,
+        "column encryption key re-encrypted"
+    );
+    Ok(CekRotation {
+        add_value: add_value_statement(cek_name, to.name, &encrypted_value),
+        drop_value: drop_value_statement(cek_name, from.name),
+        encrypted_value,
+    })
+}
+
+/// Verify that an encrypted CEK value was signed by `cmk`.
+pub async fn verify_encrypted_cek(
+    cmk: &ColumnMasterKey<'_>,
+    encrypted_value: &[u8],
+) -> Result<(), EncryptionError> {
+    let (signed, signature) = split_encrypted_cek_signature(encrypted_value)?;
+    if cmk
+        .provider
+        .verify_signature(cmk.key_path, &Sha256::digest(signed), signature)
+        .await?
+    {
+        Ok(())
+    } else {
+        Err(EncryptionError::CmkError(format!(
+            "encrypted CEK value is not signed by column master key {}",
+            cmk.name
+        )))
+    }
+}
+
+/// `ALTER COLUMN ENCRYPTION KEY ... ADD VALUE` for a CEK value.
+pub fn add_value_statement(cek_name: &str, cmk_name: &str, encrypted_value: &[u8]) -> String {
+    format!(
+        "ALTER COLUMN ENCRYPTION KEY {} ADD VALUE (COLUMN_MASTER_KEY = {}, ALGORITHM = {}, ENCRYPTED_VALUE = {})",
+        quote_name(cek_name),
+        quote_name(cmk_name),
+        quote_string(CEK_ALGORITHM),
+        hex_literal(encrypted_value)
+    )
+}
+
+/// `ALTER COLUMN ENCRYPTION KEY ... DROP VALUE` for the value of a CMK.
+pub fn drop_value_statement(cek_name: &str, cmk_name: &str) -> String {
+    format!(
+        "ALTER COLUMN ENCRYPTION KEY {} DROP VALUE (COLUMN_MASTER_KEY = {})",
+        quote_name(cek_name),
+        quote_name(cmk_name)
+    )
+}
+
+/// Quote an identifier with brackets.
+fn quote_name(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+/// Quote a string literal.
+fn quote_string(value: &str) -> String {
+    format!("N'{}'", value.replace('\'', "''"))
+}
+
+/// Format bytes as a `0x...` binary literal.
+fn hex_literal(bytes: &[u8]) -> String {
+    let mut literal = String::with_capacity(2 + bytes.len() * 2);
+    literal.push_str("0x");
+    for byte in bytes {
+        let _ = write!(literal, "{byte:02X}");
+    }
+    literal
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::key_store::InMemoryKeyStore;
+    use rsa::RsaPrivateKey;
+    use rsa::pkcs8::EncodePrivateKey;
+
+    fn key_store() -> InMemoryKeyStore {
+        let mut rng = rand::thread_rng();
+        let mut store = InMemoryKeyStore::new();
+        for path in ["keys/old", "keys/new"] {
+            let key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+            let der = key.to_pkcs8_der().unwrap();
+            store.add_key_der(path, der.as_bytes()).unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_rotate_column_encryption_key() {
+        let store = key_store();
+        let old = ColumnMasterKey::new("CMK_Old", &store, "keys/old");
+        let new = ColumnMasterKey::new("CMK_New", &store, "keys/new");
+        let cek = [0x24u8; 32];
+        let encrypted_value = store
+            .encrypt_cek("keys/old", CEK_ALGORITHM, &cek)
+            .await
+            .unwrap();
+
+        let rotation = rotate_column_encryption_key("CEK1", &encrypted_value, &old, &new)
+            .await
+            .unwrap();
+
+        let decrypted = store
+            .decrypt_cek("keys/new", CEK_ALGORITHM, &rotation.encrypted_value)
+            .await
+            .unwrap();
+        assert_eq!(decrypted, cek);
+        verify_encrypted_cek(&new, &rotation.encrypted_value)
+            .await
+            .unwrap();
+        assert!(
+            verify_encrypted_cek(&old, &rotation.encrypted_value)
+                .await
+                .is_err()
+        );
+
+        assert!(rotation.add_value.starts_with(
+            "ALTER COLUMN ENCRYPTION KEY [CEK1] ADD VALUE (COLUMN_MASTER_KEY = [CMK_New], ALGORITHM = N'RSA_OAEP', ENCRYPTED_VALUE = 0x01"
+        ));
+        assert_eq!(
+            rotation.drop_value,
+            "ALTER COLUMN ENCRYPTION KEY [CEK1] DROP VALUE (COLUMN_MASTER_KEY = [CMK_Old])"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enclave_computations_signature() {
+        let store = key_store();
+        let cmk = ColumnMasterKey::new("CMK]1", &store, "keys/new");
+
+        let signature = cmk.sign_enclave_computations().await.unwrap();
+        cmk.verify_enclave_computations(&signature).await.unwrap();
+        let other = ColumnMasterKey::new("CMK2", &store, "keys/old");
+        assert!(other.verify_enclave_computations(&signature).await.is_err());
+
+        let sql = cmk.create_statement(Some(&signature));
+        assert!(sql.starts_with(
+            "CREATE COLUMN MASTER KEY [CMK]]1] WITH (KEY_STORE_PROVIDER_NAME = N'IN_MEMORY_KEY_STORE', KEY_PATH = N'keys/new', ENCLAVE_COMPUTATIONS (SIGNATURE = 0x"
+        ));
+        assert!(sql.ends_with("))"));
+        assert_eq!(cmk.drop_statement(), "DROP COLUMN MASTER KEY [CMK]]1]");
+    }
+
+    #[test]
+    fn test_statement_quoting() {
+        assert_eq!(
+            ColumnMasterKey::new("CMK", &InMemoryKeyStore::new(), "it's").create_statement(None),
+            "CREATE COLUMN MASTER KEY [CMK] WITH (KEY_STORE_PROVIDER_NAME = N'IN_MEMORY_KEY_STORE', KEY_PATH = N'it''s')"
+        );
+        assert_eq!(hex_literal(&[0x0A, 0xFF]), "0x0AFF");
+    }
+}
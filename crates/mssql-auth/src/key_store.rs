@@ -27,9 +27,10 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 use crate::aead::AeadEncryptor;
 use crate::encryption::{EncryptionError, KeyStoreProvider};
@@ -105,6 +106,12 @@ impl InMemoryKeyStore {
     pub fn is_empty(&self) -> bool {
         self.keys.is_empty()
     }
+
+    fn unwrapper(&self, key_path: &str) -> Result<&RsaKeyUnwrapper, EncryptionError> {
+        self.keys.get(key_path).ok_or_else(|| {
+            EncryptionError::KeyStoreNotFound(format!("Key not found: {}", key_path))
+        })
+    }
 }
 
 impl Default for InMemoryKeyStore {
@@ -125,11 +132,29 @@ impl KeyStoreProvider for InMemoryKeyStore {
         _algorithm: &str,
         encrypted_cek: &[u8],
     ) -> Result<Vec<u8>, EncryptionError> {
-        let unwrapper = self.keys.get(cmk_path).ok_or_else(|| {
-            EncryptionError::KeyStoreNotFound(format!("Key not found: {}", cmk_path))
-        })?;
+        self.unwrapper(cmk_path)?.decrypt_cek(encrypted_cek)
+    }
+
+    async fn encrypt_cek(
+        &self,
+        cmk_path: &str,
+        _algorithm: &str,
+        cek: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        self.unwrapper(cmk_path)?.encrypt_cek(cmk_path, cek)
+    }
 
-        unwrapper.decrypt_cek(encrypted_cek)
+    async fn sign_data(&self, cmk_path: &str, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        self.unwrapper(cmk_path)?.sign(data)
+    }
+
+    async fn verify_signature(
+        &self,
+        cmk_path: &str,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, EncryptionError> {
+        Ok(self.unwrapper(cmk_path)?.verify(data, signature))
     }
 }
 
@@ -140,8 +165,63 @@ struct CekCacheEntry {
     cek: Vec<u8>,
     /// AEAD encryptor instance (pre-derived keys).
     encryptor: Arc<AeadEncryptor>,
+    /// Path of the Column Master Key protecting the CEK, when known.
+    cmk_path: Option<String>,
     /// When this entry was created.
     created_at: Instant,
+    /// When this entry was last returned from the cache.
+    last_used: Mutex<Instant>,
+}
+
+// Secure zeroization of the decrypted CEK when the `zeroize` feature is enabled.
+// The derived keys in the encryptor zero themselves.
+#[cfg(feature = "zeroize")]
+impl Drop for CekCacheEntry {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.cek);
+    }
+}
+
+/// Policy for choosing the entry to evict when the CEK cache is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CekEvictionPolicy {
+    /// Evict the entry used least recently.
+    #[default]
+    LeastRecentlyUsed,
+    /// Evict the entry cached first.
+    Oldest,
+}
+
+/// Counters describing CEK cache effectiveness.
+///
+/// This struct is marked `#[non_exhaustive]` to allow adding new fields
+/// in future minor versions without breaking changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CekCacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that found no entry or an expired one.
+    pub misses: u64,
+    /// Entries evicted to stay within the size limit.
+    pub evictions: u64,
+    /// Entries removed by [`CekCache::invalidate_cmk`].
+    pub invalidations: u64,
+    /// Entries currently cached.
+    pub entries: usize,
+}
+
+impl CekCacheStats {
+    /// Fraction of lookups answered from the cache (0.0 to 1.0).
+    #[must_use]
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
 }
 
 /// Thread-safe cache for decrypted Column Encryption Keys.
@@ -157,11 +237,30 @@ struct CekCacheEntry {
 ///
 /// Entries expire after a configurable TTL (default: 2 hours).
 /// Expired entries are lazily removed on access.
+///
+/// ## Size Limit
+///
+/// The cache is unbounded by default. With
+/// [`with_max_entries`](Self::with_max_entries), inserting into a full cache
+/// first drops expired entries, then evicts one entry chosen by the
+/// [`CekEvictionPolicy`].
 pub struct CekCache {
     /// Map of cache key to entry.
     entries: RwLock<HashMap<CekCacheKey, CekCacheEntry>>,
     /// Time-to-live for cache entries.
     ttl: Duration,
+    /// Maximum number of entries, if bounded.
+    max_entries: Option<usize>,
+    /// Which entry to evict when the cache is full.
+    eviction_policy: CekEvictionPolicy,
+    /// Lookups answered from the cache.
+    hits: AtomicU64,
+    /// Lookups that missed.
+    misses: AtomicU64,
+    /// Entries evicted for space.
+    evictions: AtomicU64,
+    /// Entries removed by CMK invalidation.
+    invalidations: AtomicU64,
 }
 
 /// Key for CEK cache entries.
@@ -187,9 +286,12 @@ impl CekCacheKey {
 }
 
 impl CekCache {
+    /// Default time-to-live for cache entries (2 hours).
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(2 * 60 * 60);
+
     /// Create a new CEK cache with default TTL (2 hours).
     pub fn new() -> Self {
-        Self::with_ttl(Duration::from_secs(2 * 60 * 60))
+        Self::with_ttl(Self::DEFAULT_TTL)
     }
 
     /// Create a new CEK cache with custom TTL.
@@ -197,9 +299,41 @@ impl CekCache {
         Self {
             entries: RwLock::new(HashMap::new()),
             ttl,
+            max_entries: None,
+            eviction_policy: CekEvictionPolicy::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
         }
     }
 
+    /// Limit the cache to `max_entries` CEKs.
+    ///
+    /// A limit of zero disables caching.
+    #[must_use]
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Set the policy choosing which entry to evict when the cache is full.
+    #[must_use]
+    pub fn with_eviction_policy(mut self, policy: CekEvictionPolicy) -> Self {
+        self.eviction_policy = policy;
+        self
+    }
+
+    /// Time-to-live for cache entries.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Maximum number of entries, if bounded.
+    pub fn max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
     /// Get a cached encryptor for a CEK.
     ///
     /// Returns `None` if the entry doesn't exist or has expired.
@@ -207,9 +341,12 @@ impl CekCache {
         let entries = self.entries.read();
         if let Some(entry) = entries.get(key) {
             if entry.created_at.elapsed() < self.ttl {
+                *entry.last_used.lock() = Instant::now();
+                self.hits.fetch_add(1, Ordering::Relaxed);
                 return Some(Arc::clone(&entry.encryptor));
             }
         }
+        self.misses.fetch_add(1, Ordering::Relaxed);
         None
     }
 
@@ -229,16 +366,65 @@ impl CekCache {
         &self,
         key: CekCacheKey,
         cek: Vec<u8>,
+    ) -> Result<Arc<AeadEncryptor>, EncryptionError> {
+        self.insert_entry(key, None, cek)
+    }
+
+    /// Insert a CEK protected by the Column Master Key at `cmk_path`.
+    ///
+    /// Entries inserted this way are removed by
+    /// [`invalidate_cmk`](Self::invalidate_cmk).
+    pub fn insert_for_cmk(
+        &self,
+        key: CekCacheKey,
+        cmk_path: &str,
+        cek: Vec<u8>,
+    ) -> Result<Arc<AeadEncryptor>, EncryptionError> {
+        self.insert_entry(key, Some(cmk_path.to_string()), cek)
+    }
+
+    fn insert_entry(
+        &self,
+        key: CekCacheKey,
+        cmk_path: Option<String>,
+        cek: Vec<u8>,
     ) -> Result<Arc<AeadEncryptor>, EncryptionError> {
         let encryptor = Arc::new(AeadEncryptor::new(&cek)?);
+        if self.max_entries == Some(0) {
+            return Ok(encryptor);
+        }
 
+        let now = Instant::now();
         let entry = CekCacheEntry {
             cek,
             encryptor: Arc::clone(&encryptor),
-            created_at: Instant::now(),
+            cmk_path,
+            created_at: now,
+            last_used: Mutex::new(now),
         };
 
         let mut entries = self.entries.write();
+        if let Some(max_entries) = self.max_entries {
+            if !entries.contains_key(&key) && entries.len() >= max_entries {
+                entries.retain(|_, entry| entry.created_at.elapsed() < self.ttl);
+            }
+            if !entries.contains_key(&key) && entries.len() >= max_entries {
+                let victim = match self.eviction_policy {
+                    CekEvictionPolicy::LeastRecentlyUsed => entries
+                        .iter()
+                        .min_by_key(|(_, entry)| *entry.last_used.lock())
+                        .map(|(key, _)| key.clone()),
+                    CekEvictionPolicy::Oldest => entries
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.created_at)
+                        .map(|(key, _)| key.clone()),
+                };
+                if let Some(victim) = victim {
+                    entries.remove(&victim);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
         entries.insert(key, entry);
 
         Ok(encryptor)
@@ -281,6 +467,25 @@ impl CekCache {
         entries.remove(key).is_some()
     }
 
+    /// Remove every CEK protected by the Column Master Key at `cmk_path`.
+    ///
+    /// Call this when a CMK is rotated or revoked. Paths are compared
+    /// ignoring case. Returns the number of entries removed.
+    pub fn invalidate_cmk(&self, cmk_path: &str) -> usize {
+        let mut entries = self.entries.write();
+        let before = entries.len();
+        entries.retain(|_, entry| {
+            !entry
+                .cmk_path
+                .as_deref()
+                .is_some_and(|path| path.eq_ignore_ascii_case(cmk_path))
+        });
+        let removed = before - entries.len();
+        self.invalidations
+            .fetch_add(removed as u64, Ordering::Relaxed);
+        removed
+    }
+
     /// Clear all expired entries from the cache.
     pub fn cleanup_expired(&self) {
         let mut entries = self.entries.write();
@@ -302,6 +507,17 @@ impl CekCache {
     pub fn is_empty(&self) -> bool {
         self.entries.read().is_empty()
     }
+
+    /// Snapshot of the cache counters.
+    pub fn stats(&self) -> CekCacheStats {
+        CekCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: self.len(),
+        }
+    }
 }
 
 impl Default for CekCache {
@@ -457,4 +673,79 @@ mod tests {
         assert!(cache.get(&key1).is_none());
         assert!(cache.get(&key2).is_some());
     }
+
+    #[test]
+    fn test_cek_cache_max_entries_evicts_least_recently_used() {
+        let cache = CekCache::new().with_max_entries(2);
+        let key1 = CekCacheKey::new(1, 1, 1);
+        let key2 = CekCacheKey::new(1, 2, 1);
+        let key3 = CekCacheKey::new(1, 3, 1);
+
+        cache.insert(key1.clone(), vec![0x41u8; 32]).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(key2.clone(), vec![0x42u8; 32]).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get(&key1).is_some());
+
+        cache.insert(key3.clone(), vec![0x43u8; 32]).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key1).is_some());
+        assert!(cache.get(&key2).is_none());
+        assert!(cache.get(&key3).is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 2);
+        assert!((stats.hit_rate() - 0.75).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_cek_cache_max_entries_evicts_oldest() {
+        let cache = CekCache::new()
+            .with_max_entries(2)
+            .with_eviction_policy(CekEvictionPolicy::Oldest);
+        let key1 = CekCacheKey::new(1, 1, 1);
+        let key2 = CekCacheKey::new(1, 2, 1);
+
+        cache.insert(key1.clone(), vec![0x41u8; 32]).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(key2.clone(), vec![0x42u8; 32]).unwrap();
+        assert!(cache.get(&key1).is_some());
+
+        cache
+            .insert(CekCacheKey::new(1, 3, 1), vec![0x43u8; 32])
+            .unwrap();
+        assert!(cache.get(&key1).is_none());
+        assert!(cache.get(&key2).is_some());
+
+        // A zero limit disables caching
+        let cache = CekCache::new().with_max_entries(0);
+        cache.insert(key1.clone(), vec![0x41u8; 32]).unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cek_cache_invalidate_cmk() {
+        let cache = CekCache::new();
+        let key1 = CekCacheKey::new(1, 1, 1);
+        let key2 = CekCacheKey::new(1, 2, 1);
+        let key3 = CekCacheKey::new(1, 3, 1);
+
+        cache
+            .insert_for_cmk(key1.clone(), "CurrentUser/My/ABC", vec![0x41u8; 32])
+            .unwrap();
+        cache
+            .insert_for_cmk(key2.clone(), "CurrentUser/My/DEF", vec![0x42u8; 32])
+            .unwrap();
+        cache.insert(key3.clone(), vec![0x43u8; 32]).unwrap();
+
+        assert_eq!(cache.invalidate_cmk("currentuser/my/abc"), 1);
+        assert!(cache.get(&key1).is_none());
+        assert!(cache.get(&key2).is_some());
+        assert!(cache.get(&key3).is_some());
+        assert_eq!(cache.invalidate_cmk("CurrentUser/My/ABC"), 0);
+        assert_eq!(cache.stats().invalidations, 1);
+    }
 }
@@ -12,7 +12,9 @@
 //!               + ciphertext_length (2 bytes, LE) + ciphertext
 //! ```
 //!
-//! The `ciphertext` is the RSA-OAEP encrypted CEK.
+//! The `ciphertext` is the RSA-OAEP encrypted CEK. Values written by
+//! [`RsaKeyUnwrapper::encrypt_cek`] are followed by an RSA-SHA256 signature
+//! of the SHA-256 hash of the preceding bytes, made with the same CMK.
 //!
 //! ## RSA-OAEP Parameters
 //!
@@ -20,11 +22,13 @@
 //! - **MGF**: MGF1-SHA-256
 //! - **Label**: Empty
 
+use std::ops::Range;
+
 use rsa::{
-    Oaep, RsaPrivateKey, pkcs1::DecodeRsaPrivateKey, pkcs8::DecodePrivateKey,
+    Oaep, Pkcs1v15Sign, RsaPrivateKey, pkcs1::DecodeRsaPrivateKey, pkcs8::DecodePrivateKey,
     traits::PublicKeyParts,
 };
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 
 use crate::encryption::EncryptionError;
 
@@ -111,6 +115,49 @@ impl RsaKeyUnwrapper {
         Ok(decrypted)
     }
 
+    /// Encrypt a Column Encryption Key (CEK) for the CMK at `key_path`.
+    ///
+    /// Produces the SQL Server format read by [`decrypt_cek`](Self::decrypt_cek),
+    /// signed with this key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if RSA encryption or signing fails.
+    pub fn encrypt_cek(&self, key_path: &str, cek: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let padding = Oaep::new::<Sha256>();
+        let ciphertext = self
+            .private_key
+            .to_public_key()
+            .encrypt(&mut rand::thread_rng(), padding, cek)
+            .map_err(|e| {
+                EncryptionError::EncryptionFailed(format!("RSA-OAEP encryption failed: {}", e))
+            })?;
+
+        let mut encrypted_cek = encode_encrypted_cek(key_path, &ciphertext)?;
+        let signature = self.sign(&Sha256::digest(&encrypted_cek))?;
+        encrypted_cek.extend_from_slice(&signature);
+        Ok(encrypted_cek)
+    }
+
+    /// Sign a SHA-256 digest with RSA PKCS#1 v1.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub fn sign(&self, digest: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        self.private_key
+            .sign(Pkcs1v15Sign::new::<Sha256>(), digest)
+            .map_err(|e| EncryptionError::CmkError(format!("RSA signing failed: {}", e)))
+    }
+
+    /// Verify an RSA PKCS#1 v1.5 signature of a SHA-256 digest.
+    pub fn verify(&self, digest: &[u8], signature: &[u8]) -> bool {
+        self.private_key
+            .to_public_key()
+            .verify(Pkcs1v15Sign::new::<Sha256>(), digest, signature)
+            .is_ok()
+    }
+
     /// Decrypt raw RSA-OAEP ciphertext (without SQL Server header).
     ///
     /// Use this when you have just the RSA ciphertext without the SQL Server envelope.
@@ -121,56 +168,9 @@ impl RsaKeyUnwrapper {
         })
     }
 
-    /// Parse the SQL Server encrypted CEK format.
-    ///
-    /// Format:
-    /// - Version (1 byte): Must be 0x01
-    /// - Key path length (2 bytes, little-endian)
-    /// - Key path (UTF-16LE encoded string)
-    /// - Ciphertext length (2 bytes, little-endian)
-    /// - Ciphertext (RSA-OAEP encrypted CEK)
+    /// Parse the SQL Server encrypted CEK format, returning the ciphertext.
     fn parse_encrypted_cek<'a>(&self, data: &'a [u8]) -> Result<&'a [u8], EncryptionError> {
-        if data.len() < 5 {
-            return Err(EncryptionError::CekDecryptionFailed(
-                "Encrypted CEK too short".into(),
-            ));
-        }
-
-        // Check version
-        if data[0] != CEK_VERSION_BYTE {
-            return Err(EncryptionError::CekDecryptionFailed(format!(
-                "Invalid CEK version: expected {:#04x}, got {:#04x}",
-                CEK_VERSION_BYTE, data[0]
-            )));
-        }
-
-        // Read key path length (2 bytes, little-endian)
-        let key_path_len = u16::from_le_bytes([data[1], data[2]]) as usize;
-
-        // Calculate offset after key path
-        let ciphertext_len_offset = 3 + key_path_len;
-        if data.len() < ciphertext_len_offset + 2 {
-            return Err(EncryptionError::CekDecryptionFailed(
-                "Encrypted CEK truncated: missing ciphertext length".into(),
-            ));
-        }
-
-        // Read ciphertext length (2 bytes, little-endian)
-        let ciphertext_len =
-            u16::from_le_bytes([data[ciphertext_len_offset], data[ciphertext_len_offset + 1]])
-                as usize;
-
-        // Calculate ciphertext offset
-        let ciphertext_offset = ciphertext_len_offset + 2;
-        if data.len() < ciphertext_offset + ciphertext_len {
-            return Err(EncryptionError::CekDecryptionFailed(format!(
-                "Encrypted CEK truncated: expected {} bytes of ciphertext, got {}",
-                ciphertext_len,
-                data.len() - ciphertext_offset
-            )));
-        }
-
-        Ok(&data[ciphertext_offset..ciphertext_offset + ciphertext_len])
+        Ok(&data[ciphertext_range(data)?])
     }
 
     /// Get the RSA key size in bits.
@@ -179,6 +179,98 @@ impl RsaKeyUnwrapper {
     }
 }
 
+/// Build an unsigned encrypted CEK in SQL Server format.
+///
+/// The key path is stored lowercased, as SQL Server tools write it.
+///
+/// # Errors
+///
+/// Returns an error if the key path or ciphertext is longer than 65535 bytes.
+pub fn encode_encrypted_cek(key_path: &str, ciphertext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let key_path: Vec<u8> = key_path
+        .to_lowercase()
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let too_long = |_| EncryptionError::EncryptionFailed("encrypted CEK field too long".into());
+    let key_path_len = u16::try_from(key_path.len()).map_err(too_long)?;
+    let ciphertext_len = u16::try_from(ciphertext.len()).map_err(too_long)?;
+
+    let mut encrypted_cek = Vec::with_capacity(5 + key_path.len() + ciphertext.len());
+    encrypted_cek.push(CEK_VERSION_BYTE);
+    encrypted_cek.extend_from_slice(&key_path_len.to_le_bytes());
+    encrypted_cek.extend_from_slice(&key_path);
+    encrypted_cek.extend_from_slice(&ciphertext_len.to_le_bytes());
+    encrypted_cek.extend_from_slice(ciphertext);
+    Ok(encrypted_cek)
+}
+
+/// Split a signed encrypted CEK into the signed bytes and the signature.
+///
+/// # Errors
+///
+/// Returns an error if the format is invalid or the signature is missing.
+pub fn split_encrypted_cek_signature(data: &[u8]) -> Result<(&[u8], &[u8]), EncryptionError> {
+    let end = ciphertext_range(data)?.end;
+    if end == data.len() {
+        return Err(EncryptionError::CekDecryptionFailed(
+            "Encrypted CEK is not signed".into(),
+        ));
+    }
+    Ok(data.split_at(end))
+}
+
+/// Locate the ciphertext in an encrypted CEK.
+///
+/// Format:
+/// - Version (1 byte): Must be 0x01
+/// - Key path length (2 bytes, little-endian)
+/// - Key path (UTF-16LE encoded string)
+/// - Ciphertext length (2 bytes, little-endian)
+/// - Ciphertext (RSA-OAEP encrypted CEK)
+fn ciphertext_range(data: &[u8]) -> Result<Range<usize>, EncryptionError> {
+    if data.len() < 5 {
+        return Err(EncryptionError::CekDecryptionFailed(
+            "Encrypted CEK too short".into(),
+        ));
+    }
+
+    // Check version
+    if data[0] != CEK_VERSION_BYTE {
+        return Err(EncryptionError::CekDecryptionFailed(format!(
+            "Invalid CEK version: expected {:#04x}, got {:#04x}",
+            CEK_VERSION_BYTE, data[0]
+        )));
+    }
+
+    // Read key path length (2 bytes, little-endian)
+    let key_path_len = u16::from_le_bytes([data[1], data[2]]) as usize;
+
+    // Calculate offset after key path
+    let ciphertext_len_offset = 3 + key_path_len;
+    if data.len() < ciphertext_len_offset + 2 {
+        return Err(EncryptionError::CekDecryptionFailed(
+            "Encrypted CEK truncated: missing ciphertext length".into(),
+        ));
+    }
+
+    // Read ciphertext length (2 bytes, little-endian)
+    let ciphertext_len =
+        u16::from_le_bytes([data[ciphertext_len_offset], data[ciphertext_len_offset + 1]]) as usize;
+
+    // Calculate ciphertext offset
+    let ciphertext_offset = ciphertext_len_offset + 2;
+    if data.len() < ciphertext_offset + ciphertext_len {
+        return Err(EncryptionError::CekDecryptionFailed(format!(
+            "Encrypted CEK truncated: expected {} bytes of ciphertext, got {}",
+            ciphertext_len,
+            data.len() - ciphertext_offset
+        )));
+    }
+
+    Ok(ciphertext_offset..ciphertext_offset + ciphertext_len)
+}
+
 /// Create an encrypted CEK in SQL Server format for testing.
 ///
 /// This is useful for testing the parsing logic.
@@ -312,6 +404,36 @@ mod tests {
         assert_eq!(decrypted, test_cek);
     }
 
+    #[test]
+    fn test_encrypt_cek_round_trip() {
+        let unwrapper = RsaKeyUnwrapper::from_key(generate_test_key());
+        let cek = [0x33u8; 32];
+
+        let encrypted_cek = unwrapper
+            .encrypt_cek("CurrentUser/My/NewCert", &cek)
+            .unwrap();
+        assert_eq!(unwrapper.decrypt_cek(&encrypted_cek).unwrap(), cek);
+
+        // Key path is stored lowercased, followed by the signature
+        let path_len = u16::from_le_bytes([encrypted_cek[1], encrypted_cek[2]]) as usize;
+        let path: Vec<u16> = encrypted_cek[3..3 + path_len]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&path).unwrap(), "currentuser/my/newcert");
+
+        let (signed, signature) = split_encrypted_cek_signature(&encrypted_cek).unwrap();
+        assert_eq!(signature.len(), 256);
+        assert!(unwrapper.verify(&Sha256::digest(signed), signature));
+        assert!(!unwrapper.verify(&Sha256::digest(b"tampered"), signature));
+    }
+
+    #[test]
+    fn test_split_encrypted_cek_signature_unsigned() {
+        let unsigned = create_test_encrypted_cek("Test", &[0u8; 32]);
+        assert!(split_encrypted_cek_signature(&unsigned).is_err());
+    }
+
     #[test]
     fn test_create_test_encrypted_cek() {
         let ciphertext = vec![0x12, 0x34, 0x56, 0x78];
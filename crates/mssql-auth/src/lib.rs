@@ -78,6 +78,7 @@ pub mod error;
 #[cfg(feature = "integrated-auth")]
 pub mod integrated_auth;
 pub mod provider;
+pub mod provider_registry;
 pub mod sql_auth;
 #[cfg(feature = "sspi-auth")]
 pub mod sspi_auth;
@@ -86,18 +87,26 @@ pub mod sspi_auth;
 #[cfg(feature = "always-encrypted")]
 pub mod aead;
 #[cfg(feature = "always-encrypted")]
+pub mod enclave;
+#[cfg(feature = "always-encrypted")]
+pub mod key_rotation;
+#[cfg(feature = "always-encrypted")]
 pub mod key_store;
 #[cfg(feature = "always-encrypted")]
 pub mod key_unwrap;
 
 // Always Encrypted key providers
+#[cfg(feature = "azure-attestation")]
+pub mod azure_attestation;
 #[cfg(feature = "azure-keyvault")]
 pub mod azure_keyvault;
+#[cfg(feature = "local-keystore")]
+pub mod local_keystore;
 #[cfg(all(windows, feature = "windows-certstore"))]
 pub mod windows_certstore;
 
 // Core types
-pub use credentials::Credentials;
+pub use credentials::{Credentials, SecretString};
 pub use error::AuthError;
 pub use provider::{AsyncAuthProvider, AuthData, AuthMethod, AuthProvider};
 
@@ -111,7 +120,7 @@ pub use sql_auth::SqlAuthenticator;
 
 // Secure credential types (with zeroize feature)
 #[cfg(feature = "zeroize")]
-pub use credentials::{SecretString, SecureCredentials};
+pub use credentials::SecureCredentials;
 
 // Azure Identity authentication (with azure-identity feature)
 #[cfg(feature = "azure-identity")]
@@ -134,17 +143,29 @@ pub use encryption::{
     CekMetadata, ColumnEncryptionConfig, ColumnEncryptionInfo, EncryptedValue, EncryptionError,
     EncryptionType, KeyStoreProvider,
 };
+pub use provider_registry::{KeyStoreProviderRegistry, ProviderFactory};
 
 // Always Encrypted cryptography (with always-encrypted feature)
 #[cfg(feature = "always-encrypted")]
 pub use aead::AeadEncryptor;
 #[cfg(feature = "always-encrypted")]
-pub use key_store::{CekCache, CekCacheKey, InMemoryKeyStore};
+pub use enclave::{
+    AttestationInfo, AttestationProtocol, AttestationRequest, AttestationVerifier, EnclaveKey,
+    EnclaveSession, EnclaveSessionCache,
+};
+#[cfg(feature = "always-encrypted")]
+pub use key_rotation::{CekRotation, ColumnMasterKey, rotate_column_encryption_key};
+#[cfg(feature = "always-encrypted")]
+pub use key_store::{CekCache, CekCacheKey, CekCacheStats, CekEvictionPolicy, InMemoryKeyStore};
 #[cfg(feature = "always-encrypted")]
 pub use key_unwrap::RsaKeyUnwrapper;
 
 // Always Encrypted key providers
+#[cfg(feature = "azure-attestation")]
+pub use azure_attestation::AzureAttestationVerifier;
 #[cfg(feature = "azure-keyvault")]
 pub use azure_keyvault::AzureKeyVaultProvider;
+#[cfg(feature = "local-keystore")]
+pub use local_keystore::LocalFileKeyStoreProvider;
 #[cfg(all(windows, feature = "windows-certstore"))]
 pub use windows_certstore::WindowsCertStoreProvider;
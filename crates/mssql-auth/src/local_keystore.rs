@@ -0,0 +1,304 @@
+//! Local file Column Master Key (CMK) provider for Always Encrypted.
+//!
+//! This module provides a key store backed by RSA private keys in files,
+//! for development and on-premises deployments without Azure Key Vault or
+//! the Windows Certificate Store.
+//!
+//! ## CMK Path Format
+//!
+//! The CMK path is the key file path, relative to the provider's key
+//! directory or absolute within it:
+//!
+//! ```text
+//! cmk1.pem
+//! /etc/mssql/keys/cmk2.pfx
+//! ```
+//!
+//! Supported formats:
+//! - **PEM**: PKCS#1 or PKCS#8 RSA private key
+//! - **PKCS#12** (`.pfx` / `.p12`): decrypted with the configured password
+//! - **DER**: PKCS#1 or PKCS#8 RSA private key (any other extension)
+//!
+//! ## Security Considerations
+//!
+//! - The CMK path comes from database metadata, so paths outside the key
+//!   directory are rejected
+//! - Keys are loaded on first use and kept in memory; restrict file
+//!   permissions to the service account
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use mssql_auth::local_keystore::LocalFileKeyStoreProvider;
+//!
+//! let provider = LocalFileKeyStoreProvider::new("/etc/mssql/keys")
+//!     .with_pfx_password("secret");
+//!
+//! // Create the CMK referencing the file
+//! // CREATE COLUMN MASTER KEY CMK1 WITH (
+//! //     KEY_STORE_PROVIDER_NAME = 'LOCAL_FILE_KEY_STORE', KEY_PATH = 'cmk1.pfx')
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use openssl::pkcs12::Pkcs12;
+use parking_lot::RwLock;
+use tracing::{debug, instrument};
+
+use crate::encryption::{EncryptionError, KeyStoreProvider};
+use crate::key_unwrap::RsaKeyUnwrapper;
+
+/// Provider name used in `KEY_STORE_PROVIDER_NAME`.
+const PROVIDER_NAME: &str = "LOCAL_FILE_KEY_STORE";
+
+/// Column Master Key provider for RSA keys in local PEM, DER or PKCS#12 files.
+pub struct LocalFileKeyStoreProvider {
+    /// Directory containing the key files.
+    key_directory: PathBuf,
+    /// Password for PKCS#12 files.
+    pfx_password: Option<String>,
+    /// Loaded keys by CMK path.
+    keys: RwLock<HashMap<String, Arc<RsaKeyUnwrapper>>>,
+}
+
+impl LocalFileKeyStoreProvider {
+    /// Create a provider for key files in `key_directory`.
+    pub fn new(key_directory: impl Into<PathBuf>) -> Self {
+        Self {
+            key_directory: key_directory.into(),
+            pfx_password: None,
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the password used to decrypt PKCS#12 files.
+    #[must_use]
+    pub fn with_pfx_password(mut self, password: impl Into<String>) -> Self {
+        self.pfx_password = Some(password.into());
+        self
+    }
+
+    /// The directory containing the key files.
+    pub fn key_directory(&self) -> &Path {
+        &self.key_directory
+    }
+
+    /// Forget loaded keys so they are read from disk again.
+    pub fn clear_cache(&self) {
+        self.keys.write().clear();
+    }
+
+    /// Get the key for a CMK path, loading it on first use.
+    fn key(&self, cmk_path: &str) -> Result<Arc<RsaKeyUnwrapper>, EncryptionError> {
+        if let Some(key) = self.keys.read().get(cmk_path) {
+            return Ok(Arc::clone(key));
+        }
+
+        let path = self.resolve(cmk_path)?;
+        let data = fs::read(&path).map_err(|e| {
+            EncryptionError::CmkError(format!("Failed to read key file {}: {}", path.display(), e))
+        })?;
+        let key = Arc::new(self.parse_key(&path, &data)?);
+        debug!(path = %path.display(), bits = key.key_bits(), "loaded column master key");
+
+        self.keys
+            .write()
+            .insert(cmk_path.to_string(), Arc::clone(&key));
+        Ok(key)
+    }
+
+    /// Resolve a CMK path to a file inside the key directory.
+    fn resolve(&self, cmk_path: &str) -> Result<PathBuf, EncryptionError> {
+        let not_found = |e: std::io::Error| {
+            EncryptionError::KeyStoreNotFound(format!("Key file not found: {}: {}", cmk_path, e))
+        };
+        let directory = fs::canonicalize(&self.key_directory).map_err(not_found)?;
+        let path = fs::canonicalize(directory.join(cmk_path)).map_err(not_found)?;
+
+        if !path.starts_with(&directory) {
+            return Err(EncryptionError::CmkError(format!(
+                "Key path {} is outside the key directory",
+                cmk_path
+            )));
+        }
+        Ok(path)
+    }
+
+    /// Parse a key file, choosing the format from its content and extension.
+    fn parse_key(&self, path: &Path, data: &[u8]) -> Result<RsaKeyUnwrapper, EncryptionError> {
+        if data.starts_with(b"-----BEGIN") {
+            let pem = std::str::from_utf8(data)
+                .map_err(|_| EncryptionError::CmkError("Key file is not valid PEM".into()))?;
+            return RsaKeyUnwrapper::from_pem(pem);
+        }
+
+        let is_pkcs12 = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pfx") || ext.eq_ignore_ascii_case("p12"));
+        if !is_pkcs12 {
+            return RsaKeyUnwrapper::from_der(data);
+        }
+
+        let parsed = Pkcs12::from_der(data)
+            .and_then(|pkcs12| pkcs12.parse2(self.pfx_password.as_deref().unwrap_or_default()))
+            .map_err(|e| {
+                EncryptionError::CmkError(format!("Failed to decrypt PKCS#12 file: {}", e))
+            })?;
+        let private_key = parsed.pkey.ok_or_else(|| {
+            EncryptionError::CmkError("PKCS#12 file contains no private key".into())
+        })?;
+        let der = private_key.private_key_to_pkcs8().map_err(|e| {
+            EncryptionError::CmkError(format!("Failed to export private key: {}", e))
+        })?;
+        #[cfg(feature = "zeroize")]
+        let der = zeroize::Zeroizing::new(der);
+        RsaKeyUnwrapper::from_der(&der)
+    }
+}
+
+impl std::fmt::Debug for LocalFileKeyStoreProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalFileKeyStoreProvider")
+            .field("key_directory", &self.key_directory)
+            .field("loaded_keys", &self.keys.read().len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyStoreProvider for LocalFileKeyStoreProvider {
+    fn provider_name(&self) -> &str {
+        PROVIDER_NAME
+    }
+
+    #[instrument(skip(self, encrypted_cek), fields(cmk_path = %cmk_path))]
+    async fn decrypt_cek(
+        &self,
+        cmk_path: &str,
+        _algorithm: &str,
+        encrypted_cek: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        self.key(cmk_path)?.decrypt_cek(encrypted_cek)
+    }
+
+    #[instrument(skip(self, cek), fields(cmk_path = %cmk_path))]
+    async fn encrypt_cek(
+        &self,
+        cmk_path: &str,
+        _algorithm: &str,
+        cek: &[u8],
+    ) -> Result<Vec<u8>, EncryptionError> {
+        self.key(cmk_path)?.encrypt_cek(cmk_path, cek)
+    }
+
+    #[instrument(skip(self, data), fields(cmk_path = %cmk_path))]
+    async fn sign_data(&self, cmk_path: &str, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        self.key(cmk_path)?.sign(data)
+    }
+
+    #[instrument(skip(self, data, signature), fields(cmk_path = %cmk_path))]
+    async fn verify_signature(
+        &self,
+        cmk_path: &str,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, EncryptionError> {
+        Ok(self.key(cmk_path)?.verify(data, signature))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+
+    /// A key directory with the same RSA key as PEM, DER and PKCS#12.
+    fn key_directory(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mssql-local-keystore-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let rsa = Rsa::generate(2048).unwrap();
+        fs::write(dir.join("cmk.pem"), rsa.private_key_to_pem().unwrap()).unwrap();
+        fs::write(dir.join("cmk.der"), rsa.private_key_to_der().unwrap()).unwrap();
+
+        let pkey = PKey::from_rsa(rsa).unwrap();
+        let pfx = Pkcs12::builder()
+            .name("cmk")
+            .pkey(&pkey)
+            .build2("secret")
+            .unwrap();
+        fs::write(dir.join("cmk.pfx"), pfx.to_der().unwrap()).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_local_keystore_formats() {
+        let dir = key_directory("formats");
+        let provider = LocalFileKeyStoreProvider::new(&dir).with_pfx_password("secret");
+        let cek = [0x11u8; 32];
+
+        // A CEK encrypted with one format decrypts with the others
+        let encrypted = provider
+            .encrypt_cek("cmk.pem", "RSA_OAEP", &cek)
+            .await
+            .unwrap();
+        for path in ["cmk.pem", "cmk.der", "cmk.pfx"] {
+            let decrypted = provider
+                .decrypt_cek(path, "RSA_OAEP", &encrypted)
+                .await
+                .unwrap();
+            assert_eq!(decrypted, cek, "{path}");
+        }
+
+        let signature = provider.sign_data("cmk.pfx", &[7u8; 32]).await.unwrap();
+        assert!(
+            provider
+                .verify_signature("cmk.der", &[7u8; 32], &signature)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !provider
+                .verify_signature("cmk.der", &[8u8; 32], &signature)
+                .await
+                .unwrap()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_keystore_wrong_pfx_password() {
+        let dir = key_directory("password");
+        let provider = LocalFileKeyStoreProvider::new(&dir).with_pfx_password("wrong");
+
+        let result = provider.sign_data("cmk.pfx", &[0u8; 32]).await;
+        assert!(matches!(result, Err(EncryptionError::CmkError(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_keystore_rejects_paths_outside_directory() {
+        let dir = key_directory("outside");
+        let provider = LocalFileKeyStoreProvider::new(dir.join("nested"));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let result = provider.sign_data("../cmk.pem", &[0u8; 32]).await;
+        assert!(matches!(result, Err(EncryptionError::CmkError(_))));
+        let result = provider.sign_data("missing.pem", &[0u8; 32]).await;
+        assert!(matches!(result, Err(EncryptionError::KeyStoreNotFound(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
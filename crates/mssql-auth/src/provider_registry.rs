@@ -0,0 +1,258 @@
+//! Registry of Always Encrypted key store providers.
+//!
+//! SQL Server metadata names the key store holding each Column Master Key
+//! (`KEY_STORE_PROVIDER_NAME`). The registry maps those names to
+//! [`KeyStoreProvider`] implementations so the driver can find the right
+//! provider when it needs to decrypt a Column Encryption Key.
+//!
+//! ## Lookup Rules
+//!
+//! - Names are matched case-insensitively, as SQL Server does
+//! - Registered providers are found first
+//! - Otherwise factories are asked in registration order, and the first
+//!   provider returned is kept for later lookups
+//!
+//! Factories let applications plug in key stores that are expensive to
+//! create or only needed by some databases (HSMs, AWS KMS, ...): they run
+//! on the first lookup of a name, not at startup.
+//!
+//! ## Precedence
+//!
+//! As in the .NET driver, providers registered on a connection's encryption
+//! configuration take precedence: when a connection has any, only those are
+//! used. Otherwise the connection falls back to a registry, by default the
+//! process-wide [`KeyStoreProviderRegistry::global`].
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use std::sync::Arc;
+//! use mssql_auth::KeyStoreProviderRegistry;
+//!
+//! let registry = KeyStoreProviderRegistry::global();
+//! registry.register(key_vault_provider);
+//! registry.register_factory(|name| {
+//!     (name == "AWS_KMS").then(|| Arc::new(AwsKmsProvider::from_env()) as _)
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use parking_lot::RwLock;
+
+use crate::encryption::KeyStoreProvider;
+
+/// Factory creating a key store provider for a provider name on demand.
+///
+/// Returns `None` for names the factory does not handle.
+pub type ProviderFactory = dyn Fn(&str) -> Option<Arc<dyn KeyStoreProvider>> + Send + Sync;
+
+/// Registry of key store providers by name.
+#[derive(Default)]
+pub struct KeyStoreProviderRegistry {
+    /// Providers by upper-cased name.
+    providers: RwLock<HashMap<String, Arc<dyn KeyStoreProvider>>>,
+    /// Factories for providers not yet registered, in registration order.
+    factories: RwLock<Vec<Arc<ProviderFactory>>>,
+}
+
+impl KeyStoreProviderRegistry {
+    /// Create an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry used by connections without one of their own.
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<KeyStoreProviderRegistry>> = OnceLock::new();
+        Arc::clone(GLOBAL.get_or_init(|| Arc::new(Self::new())))
+    }
+
+    /// Register a provider under its [`provider_name`](KeyStoreProvider::provider_name).
+    ///
+    /// Returns the provider previously registered under that name, if any.
+    pub fn register(
+        &self,
+        provider: impl KeyStoreProvider + 'static,
+    ) -> Option<Arc<dyn KeyStoreProvider>> {
+        self.register_shared(Arc::new(provider))
+    }
+
+    /// Register a shared provider under its [`provider_name`](KeyStoreProvider::provider_name).
+    ///
+    /// Returns the provider previously registered under that name, if any.
+    pub fn register_shared(
+        &self,
+        provider: Arc<dyn KeyStoreProvider>,
+    ) -> Option<Arc<dyn KeyStoreProvider>> {
+        let key = normalize(provider.provider_name());
+        self.providers.write().insert(key, provider)
+    }
+
+    /// Register a factory consulted for names with no registered provider.
+    pub fn register_factory(
+        &self,
+        factory: impl Fn(&str) -> Option<Arc<dyn KeyStoreProvider>> + Send + Sync + 'static,
+    ) {
+        self.factories.write().push(Arc::new(factory));
+    }
+
+    /// Remove the provider registered under `name`.
+    pub fn unregister(&self, name: &str) -> Option<Arc<dyn KeyStoreProvider>> {
+        self.providers.write().remove(&normalize(name))
+    }
+
+    /// Find the provider for `name`, creating it with a factory if needed.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn KeyStoreProvider>> {
+        let key = normalize(name);
+        if let Some(provider) = self.providers.read().get(&key) {
+            return Some(Arc::clone(provider));
+        }
+
+        // Factories run without holding any lock, so they may use the registry.
+        let factories = self.factories.read().clone();
+        let provider = factories.iter().find_map(|factory| factory(name))?;
+
+        // Keep the first provider created if another lookup raced us
+        let mut providers = self.providers.write();
+        Some(Arc::clone(providers.entry(key).or_insert(provider)))
+    }
+
+    /// Check whether a provider is available for `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Names of the registered providers, including those created by factories.
+    pub fn provider_names(&self) -> Vec<String> {
+        self.providers
+            .read()
+            .values()
+            .map(|p| p.provider_name().to_string())
+            .collect()
+    }
+
+    /// Check whether the registry has no providers and no factories.
+    pub fn is_empty(&self) -> bool {
+        self.providers.read().is_empty() && self.factories.read().is_empty()
+    }
+
+    /// Remove all providers and factories.
+    pub fn clear(&self) {
+        self.providers.write().clear();
+        self.factories.write().clear();
+    }
+}
+
+impl std::fmt::Debug for KeyStoreProviderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyStoreProviderRegistry")
+            .field("providers", &self.provider_names())
+            .field("factory_count", &self.factories.read().len())
+            .finish()
+    }
+}
+
+/// Normalize a provider name for case-insensitive lookup.
+fn normalize(name: &str) -> String {
+    name.to_ascii_uppercase()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::encryption::EncryptionError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct NamedProvider(&'static str);
+
+    #[async_trait::async_trait]
+    impl KeyStoreProvider for NamedProvider {
+        fn provider_name(&self) -> &str {
+            self.0
+        }
+
+        async fn decrypt_cek(
+            &self,
+            _cmk_path: &str,
+            _algorithm: &str,
+            encrypted_cek: &[u8],
+        ) -> Result<Vec<u8>, EncryptionError> {
+            Ok(encrypted_cek.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_registry_case_insensitive() {
+        let registry = KeyStoreProviderRegistry::new();
+        assert!(registry.is_empty());
+        assert!(
+            registry
+                .register(NamedProvider("AZURE_KEY_VAULT"))
+                .is_none()
+        );
+
+        let provider = registry.get("azure_key_vault").unwrap();
+        assert_eq!(provider.provider_name(), "AZURE_KEY_VAULT");
+        assert!(registry.contains("Azure_Key_Vault"));
+        assert!(!registry.contains("MSSQL_CERTIFICATE_STORE"));
+
+        // Registering the same name again replaces the provider
+        assert!(
+            registry
+                .register(NamedProvider("azure_key_vault"))
+                .is_some()
+        );
+        assert_eq!(registry.provider_names(), vec!["azure_key_vault"]);
+
+        assert!(registry.unregister("AZURE_KEY_VAULT").is_some());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_registry_factory_is_lazy() {
+        let registry = KeyStoreProviderRegistry::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&calls);
+        registry.register_factory(move |name| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            name.eq_ignore_ascii_case("HSM_STORE")
+                .then(|| Arc::new(NamedProvider("HSM_STORE")) as Arc<dyn KeyStoreProvider>)
+        });
+        assert!(!registry.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // The factory runs on first lookup and its provider is kept
+        assert!(registry.get("hsm_store").is_some());
+        assert!(registry.get("HSM_STORE").is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Unknown names are asked again each time
+        assert!(registry.get("AWS_KMS").is_none());
+        assert!(registry.get("AWS_KMS").is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_registry_registered_provider_wins_over_factory() {
+        let registry = KeyStoreProviderRegistry::new();
+        registry.register_factory(|_| Some(Arc::new(NamedProvider("FROM_FACTORY")) as _));
+        registry.register(NamedProvider("MY_STORE"));
+
+        assert_eq!(
+            registry.get("MY_STORE").unwrap().provider_name(),
+            "MY_STORE"
+        );
+        assert_eq!(
+            registry.get("OTHER").unwrap().provider_name(),
+            "FROM_FACTORY"
+        );
+
+        registry.clear();
+        assert!(registry.get("MY_STORE").is_none());
+    }
+}
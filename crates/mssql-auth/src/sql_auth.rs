@@ -5,7 +5,7 @@
 
 use std::borrow::Cow;
 
-use crate::credentials::Credentials;
+use crate::credentials::{Credentials, SecretString};
 use crate::error::AuthError;
 use crate::provider::{AuthData, AuthMethod, AuthProvider};
 
@@ -29,7 +29,7 @@ use crate::provider::{AuthData, AuthMethod, AuthProvider};
 #[derive(Clone)]
 pub struct SqlServerAuth {
     username: Cow<'static, str>,
-    password: Cow<'static, str>,
+    password: SecretString,
 }
 
 impl SqlServerAuth {
@@ -40,7 +40,7 @@ impl SqlServerAuth {
     ) -> Self {
         Self {
             username: username.into(),
-            password: password.into(),
+            password: SecretString::from(password.into()),
         }
     }
 
@@ -51,7 +51,7 @@ impl SqlServerAuth {
         match credentials {
             Credentials::SqlServer { username, password } => Ok(Self {
                 username: Cow::Owned(username.to_string()),
-                password: Cow::Owned(password.to_string()),
+                password: password.clone(),
             }),
             _ => Err(AuthError::UnsupportedMethod(
                 "SqlServerAuth requires SQL Server credentials".into(),
@@ -73,9 +73,9 @@ impl SqlServerAuth {
     ///
     /// # Algorithm
     ///
-    /// For each UTF-16 code unit:
-    /// 1. XOR each byte with 0xA5
-    /// 2. Swap the high and low nibbles
+    /// Per MS-TDS 2.2.6.4, for each byte of the UTF-16LE password:
+    /// 1. Swap the high and low nibbles
+    /// 2. XOR with 0xA5
     #[must_use]
     pub fn encode_password(password: &str) -> Vec<u8> {
         password
@@ -84,9 +84,9 @@ impl SqlServerAuth {
                 let byte1 = (c & 0xFF) as u8;
                 let byte2 = (c >> 8) as u8;
 
-                // XOR with 0xA5 and swap nibbles
-                let encoded1 = (byte1 ^ 0xA5).rotate_right(4);
-                let encoded2 = (byte2 ^ 0xA5).rotate_right(4);
+                // Swap nibbles, then XOR with 0xA5
+                let encoded1 = byte1.rotate_right(4) ^ 0xA5;
+                let encoded2 = byte2.rotate_right(4) ^ 0xA5;
 
                 [encoded1, encoded2]
             })
@@ -105,7 +105,7 @@ impl AuthProvider for SqlServerAuth {
             "authenticating with SQL Server credentials"
         );
 
-        let password_bytes = Self::encode_password(&self.password);
+        let password_bytes = Self::encode_password(self.password.expose_secret());
 
         Ok(AuthData::SqlServer {
             username: self.username.to_string(),
@@ -169,10 +169,23 @@ mod tests {
     fn test_password_encoding_known_value() {
         // Test against known encoded value
         // "a" in UTF-16LE is 0x61, 0x00
-        // 0x61 ^ 0xA5 = 0xC4, nibble swap = 0x4C
-        // 0x00 ^ 0xA5 = 0xA5, nibble swap = 0x5A
+        // 0x61 nibble swap = 0x16, ^ 0xA5 = 0xB3
+        // 0x00 nibble swap = 0x00, ^ 0xA5 = 0xA5
         let encoded = SqlServerAuth::encode_password("a");
-        assert_eq!(encoded, vec![0x4C, 0x5A]);
+        assert_eq!(encoded, vec![0xB3, 0xA5]);
+    }
+
+    #[test]
+    fn test_password_encoding_reverses() {
+        // The server undoes the obfuscation with XOR 0xA5, then a nibble swap
+        let encoded = SqlServerAuth::encode_password("Pä$$w0rd");
+        let decoded: Vec<u16> = encoded
+            .chunks(2)
+            .map(|b| {
+                u16::from_le_bytes([(b[0] ^ 0xA5).rotate_left(4), (b[1] ^ 0xA5).rotate_left(4)])
+            })
+            .collect();
+        assert_eq!(String::from_utf16(&decoded).unwrap(), "Pä$$w0rd");
     }
 
     #[test]
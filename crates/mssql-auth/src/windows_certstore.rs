@@ -36,20 +36,35 @@
 //! use mssql_auth::windows_certstore::WindowsCertStoreProvider;
 //! use mssql_auth::ColumnEncryptionConfig;
 //!
-//! // Create provider
-//! let provider = WindowsCertStoreProvider::new();
+//! // Create provider, keeping private key handles open for 10 minutes
+//! let provider = WindowsCertStoreProvider::new()
+//!     .with_key_cache(Duration::from_secs(600));
 //!
 //! // Register with encryption config
 //! let config = ColumnEncryptionConfig::new()
 //!     .with_provider(provider);
 //! ```
 //!
+//! ## Key Caching
+//!
+//! By default the private key is acquired from the certificate store for
+//! every operation. [`WindowsCertStoreProvider::with_key_cache`] keeps the
+//! CNG key handle open per CMK path for a configurable lifetime, avoiding
+//! the store lookup on hot paths such as CEK unwrapping.
+//!
 //! ## Platform Requirements
 //!
 //! This module is only available on Windows and requires the `windows-certstore` feature.
 
+// The certificate store and CNG are only reachable through FFI.
+#![allow(unsafe_code)]
+
+use std::collections::HashMap;
 use std::ffi::c_void;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use parking_lot::Mutex;
 use tracing::{debug, instrument};
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Security::Cryptography::CryptAcquireCertificatePrivateKey;
@@ -97,10 +112,20 @@ const PROVIDER_NAME: &str = "MSSQL_CERTIFICATE_STORE";
 /// ## Thread Safety
 ///
 /// This provider is `Send + Sync` and can be safely shared across threads.
-/// However, the underlying Windows CNG handles are managed per-operation.
-#[derive(Debug, Clone, Default)]
+/// Without a key cache, the underlying Windows CNG handles are managed
+/// per-operation. Clones share the key cache.
+#[derive(Clone, Default)]
 pub struct WindowsCertStoreProvider {
-    _private: (),
+    /// How long acquired private keys are kept, or `None` to not cache them.
+    key_cache_lifetime: Option<Duration>,
+    /// Cached private keys by upper-cased CMK path.
+    keys: Arc<Mutex<HashMap<String, CachedKey>>>,
+}
+
+/// A private key handle kept by the key cache.
+struct CachedKey {
+    handle: Arc<CngKeyHandle>,
+    acquired_at: Instant,
 }
 
 impl WindowsCertStoreProvider {
@@ -113,7 +138,60 @@ impl WindowsCertStoreProvider {
     /// ```
     #[must_use]
     pub fn new() -> Self {
-        Self { _private: () }
+        Self::default()
+    }
+
+    /// Keep private key handles open for `lifetime` after acquiring them.
+    ///
+    /// Keys are cached per CMK path. A key removed from the certificate
+    /// store stays usable until its cached handle expires or
+    /// [`clear_key_cache`](Self::clear_key_cache) is called.
+    #[must_use]
+    pub fn with_key_cache(mut self, lifetime: Duration) -> Self {
+        self.key_cache_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Close all cached private key handles.
+    pub fn clear_key_cache(&self) {
+        self.keys.lock().clear();
+    }
+
+    /// Get the private key for a CMK path, from the key cache if enabled.
+    fn private_key(&self, cmk_path: &str) -> Result<Arc<CngKeyHandle>, EncryptionError> {
+        self.cached_key(cmk_path, || {
+            let (store_location, store_name, thumbprint) = Self::parse_cmk_path(cmk_path)?;
+            Self::get_private_key(store_location, &store_name, &thumbprint)
+        })
+    }
+
+    /// Look up `cmk_path` in the key cache, calling `acquire` on a miss.
+    fn cached_key(
+        &self,
+        cmk_path: &str,
+        acquire: impl FnOnce() -> Result<CngKeyHandle, EncryptionError>,
+    ) -> Result<Arc<CngKeyHandle>, EncryptionError> {
+        let Some(lifetime) = self.key_cache_lifetime else {
+            return acquire().map(Arc::new);
+        };
+
+        let cache_key = cmk_path.to_ascii_uppercase();
+        if let Some(cached) = self.keys.lock().get(&cache_key) {
+            if cached.acquired_at.elapsed() < lifetime {
+                return Ok(Arc::clone(&cached.handle));
+            }
+        }
+
+        // Acquire outside the lock: the store lookup can be slow
+        let handle = Arc::new(acquire()?);
+        self.keys.lock().insert(
+            cache_key,
+            CachedKey {
+                handle: Arc::clone(&handle),
+                acquired_at: Instant::now(),
+            },
+        );
+        Ok(handle)
     }
 
     /// Parse a CMK path into store location, store name, and thumbprint.
@@ -243,6 +321,15 @@ impl WindowsCertStoreProvider {
     }
 }
 
+impl std::fmt::Debug for WindowsCertStoreProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowsCertStoreProvider")
+            .field("key_cache_lifetime", &self.key_cache_lifetime)
+            .field("cached_keys", &self.keys.lock().len())
+            .finish()
+    }
+}
+
 #[async_trait::async_trait]
 impl KeyStoreProvider for WindowsCertStoreProvider {
     fn provider_name(&self) -> &str {
@@ -258,17 +345,14 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
     ) -> Result<Vec<u8>, EncryptionError> {
         debug!("Decrypting CEK using Windows Certificate Store");
 
-        // Parse the CMK path
-        let (store_location, store_name, thumbprint) = Self::parse_cmk_path(cmk_path)?;
-
         // Get the private key handle
-        let key_handle = Self::get_private_key(store_location, &store_name, &thumbprint)?;
+        let key_handle = self.private_key(cmk_path)?;
 
         // Parse the SQL Server encrypted CEK format
         let ciphertext = parse_sql_server_encrypted_cek(encrypted_cek)?;
 
         // Determine padding based on algorithm
-        let (padding_info, flags) = get_padding_info(algorithm)?;
+        let padding_info = PaddingInfo::for_algorithm(algorithm)?;
 
         // First call to get required output size
         let mut result_size = 0u32;
@@ -279,7 +363,7 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
                 Some(padding_info.as_ptr()),
                 None,
                 &mut result_size,
-                flags,
+                padding_info.flags(),
             )
         };
 
@@ -299,7 +383,7 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
                 Some(padding_info.as_ptr()),
                 Some(&mut output),
                 &mut result_size,
-                flags,
+                padding_info.flags(),
             )
         };
 
@@ -319,24 +403,18 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
     async fn sign_data(&self, cmk_path: &str, data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
         debug!("Signing data using Windows Certificate Store");
 
-        // Parse the CMK path
-        let (store_location, store_name, thumbprint) = Self::parse_cmk_path(cmk_path)?;
-
         // Get the private key handle
-        let key_handle = Self::get_private_key(store_location, &store_name, &thumbprint)?;
+        let key_handle = self.private_key(cmk_path)?;
 
         // Use PKCS#1 v1.5 padding with SHA-256 for signing
-        let hash_algorithm: Vec<u16> = "SHA256\0".encode_utf16().collect();
-        let padding_info = BCRYPT_PKCS1_PADDING_INFO {
-            pszAlgId: PCWSTR(hash_algorithm.as_ptr()),
-        };
+        let padding_info = PaddingInfo::pkcs1_sha256();
 
         // First call to get required signature size
         let mut sig_size = 0u32;
         let sign_result = unsafe {
             NCryptSignHash(
                 key_handle.handle,
-                Some(&padding_info as *const _ as *const c_void),
+                Some(padding_info.as_ptr()),
                 data,
                 None,
                 &mut sig_size,
@@ -356,7 +434,7 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
         let sign_result = unsafe {
             NCryptSignHash(
                 key_handle.handle,
-                Some(&padding_info as *const _ as *const c_void),
+                Some(padding_info.as_ptr()),
                 data,
                 Some(&mut signature),
                 &mut sig_size,
@@ -385,23 +463,17 @@ impl KeyStoreProvider for WindowsCertStoreProvider {
     ) -> Result<bool, EncryptionError> {
         debug!("Verifying signature using Windows Certificate Store");
 
-        // Parse the CMK path
-        let (store_location, store_name, thumbprint) = Self::parse_cmk_path(cmk_path)?;
-
         // Get the private key handle (we'll use it for verification too)
-        let key_handle = Self::get_private_key(store_location, &store_name, &thumbprint)?;
+        let key_handle = self.private_key(cmk_path)?;
 
         // Use PKCS#1 v1.5 padding with SHA-256 for verification
-        let hash_algorithm: Vec<u16> = "SHA256\0".encode_utf16().collect();
-        let padding_info = BCRYPT_PKCS1_PADDING_INFO {
-            pszAlgId: PCWSTR(hash_algorithm.as_ptr()),
-        };
+        let padding_info = PaddingInfo::pkcs1_sha256();
 
         // Perform verification
         let verify_result = unsafe {
             NCryptVerifySignature(
                 key_handle.handle,
-                Some(&padding_info as *const _ as *const c_void),
+                Some(padding_info.as_ptr()),
                 data,
                 signature,
                 BCRYPT_PAD_PKCS1,
@@ -465,60 +537,82 @@ impl Drop for CngKeyHandle {
     }
 }
 
-/// Padding info wrapper that can hold either OAEP or PKCS1 padding.
-enum PaddingInfo {
+/// CNG padding info, owning the hash algorithm name it points to.
+struct PaddingInfo {
+    info: PaddingKind,
+    flags: NCRYPT_FLAGS,
+    /// Null-terminated UTF-16 hash algorithm name referenced by `info`.
+    ///
+    /// Boxed so the pointer stays valid when the `PaddingInfo` moves.
+    _hash_algorithm: Box<[u16]>,
+}
+
+/// OAEP or PKCS1 padding parameters.
+enum PaddingKind {
     Oaep(BCRYPT_OAEP_PADDING_INFO),
-    #[allow(dead_code)]
     Pkcs1(BCRYPT_PKCS1_PADDING_INFO),
 }
 
 impl PaddingInfo {
-    fn as_ptr(&self) -> *const c_void {
-        match self {
-            PaddingInfo::Oaep(info) => info as *const _ as *const c_void,
-            PaddingInfo::Pkcs1(info) => info as *const _ as *const c_void,
+    /// OAEP padding with SHA-256, used to decrypt CEKs.
+    fn oaep_sha256() -> Self {
+        let hash_algorithm = sha256_algorithm_name();
+        let info = BCRYPT_OAEP_PADDING_INFO {
+            pszAlgId: PCWSTR(hash_algorithm.as_ptr()),
+            pbLabel: std::ptr::null_mut(),
+            cbLabel: 0,
+        };
+        Self {
+            info: PaddingKind::Oaep(info),
+            flags: NCRYPT_FLAGS(BCRYPT_PAD_OAEP.0 | NCRYPT_SILENT_FLAG.0),
+            _hash_algorithm: hash_algorithm,
         }
     }
-}
 
-/// Get padding info based on algorithm name.
-fn get_padding_info(algorithm: &str) -> Result<(PaddingInfo, NCRYPT_FLAGS), EncryptionError> {
-    // SHA-256 hash algorithm string (null-terminated UTF-16)
-    static SHA256_ALG: &str = "SHA256\0";
-
-    match algorithm.to_uppercase().as_str() {
-        "RSA_OAEP" | "RSA-OAEP" | "RSA_OAEP_256" | "RSA-OAEP-256" => {
-            let hash_alg: Vec<u16> = SHA256_ALG.encode_utf16().collect();
-            // Note: We need to leak this to keep the pointer valid
-            let hash_alg_ptr = Box::leak(hash_alg.into_boxed_slice());
-
-            let info = BCRYPT_OAEP_PADDING_INFO {
-                pszAlgId: PCWSTR(hash_alg_ptr.as_ptr()),
-                pbLabel: std::ptr::null_mut(),
-                cbLabel: 0,
-            };
-            Ok((
-                PaddingInfo::Oaep(info),
-                NCRYPT_FLAGS(BCRYPT_PAD_OAEP.0 | NCRYPT_SILENT_FLAG.0),
-            ))
+    /// PKCS#1 v1.5 padding with SHA-256, used for signatures.
+    fn pkcs1_sha256() -> Self {
+        let hash_algorithm = sha256_algorithm_name();
+        let info = BCRYPT_PKCS1_PADDING_INFO {
+            pszAlgId: PCWSTR(hash_algorithm.as_ptr()),
+        };
+        Self {
+            info: PaddingKind::Pkcs1(info),
+            flags: BCRYPT_PAD_PKCS1,
+            _hash_algorithm: hash_algorithm,
         }
-        "RSA1_5" | "RSA-1_5" | "RSA_PKCS1" | "RSA-PKCS1" => {
-            let hash_alg: Vec<u16> = SHA256_ALG.encode_utf16().collect();
-            let hash_alg_ptr = Box::leak(hash_alg.into_boxed_slice());
-
-            let info = BCRYPT_PKCS1_PADDING_INFO {
-                pszAlgId: PCWSTR(hash_alg_ptr.as_ptr()),
-            };
-            Ok((
-                PaddingInfo::Pkcs1(info),
-                NCRYPT_FLAGS(BCRYPT_PAD_PKCS1.0 | NCRYPT_SILENT_FLAG.0),
-            ))
+    }
+
+    /// Get padding info based on the key encryption algorithm name.
+    fn for_algorithm(algorithm: &str) -> Result<Self, EncryptionError> {
+        match algorithm.to_uppercase().as_str() {
+            "RSA_OAEP" | "RSA-OAEP" | "RSA_OAEP_256" | "RSA-OAEP-256" => Ok(Self::oaep_sha256()),
+            "RSA1_5" | "RSA-1_5" | "RSA_PKCS1" | "RSA-PKCS1" => {
+                let mut padding = Self::pkcs1_sha256();
+                padding.flags = NCRYPT_FLAGS(BCRYPT_PAD_PKCS1.0 | NCRYPT_SILENT_FLAG.0);
+                Ok(padding)
+            }
+            _ => Err(EncryptionError::ConfigurationError(format!(
+                "Unsupported key encryption algorithm: {}. Expected RSA_OAEP, RSA_OAEP_256, or RSA1_5",
+                algorithm
+            ))),
+        }
+    }
+
+    fn as_ptr(&self) -> *const c_void {
+        match &self.info {
+            PaddingKind::Oaep(info) => info as *const _ as *const c_void,
+            PaddingKind::Pkcs1(info) => info as *const _ as *const c_void,
         }
-        _ => Err(EncryptionError::ConfigurationError(format!(
-            "Unsupported key encryption algorithm: {}. Expected RSA_OAEP, RSA_OAEP_256, or RSA1_5",
-            algorithm
-        ))),
     }
+
+    fn flags(&self) -> NCRYPT_FLAGS {
+        self.flags
+    }
+}
+
+/// The null-terminated UTF-16 name of the SHA-256 hash algorithm.
+fn sha256_algorithm_name() -> Box<[u16]> {
+    "SHA256\0".encode_utf16().collect()
 }
 
 /// Parse the SQL Server encrypted CEK format to extract the raw ciphertext.
@@ -599,6 +693,7 @@ fn bytes_to_hex(bytes: &[u8]) -> String {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
 
@@ -687,6 +782,60 @@ mod tests {
         assert!(parse_sql_server_encrypted_cek(&[0x02, 0x00, 0x00, 0x00, 0x00]).is_err());
     }
 
+    #[test]
+    fn test_padding_info_owns_hash_algorithm() {
+        let padding = PaddingInfo::for_algorithm("RSA_OAEP").unwrap();
+        assert_eq!(
+            padding.flags(),
+            NCRYPT_FLAGS(BCRYPT_PAD_OAEP.0 | NCRYPT_SILENT_FLAG.0)
+        );
+
+        // The algorithm name stays readable after the padding info moves
+        let moved = vec![padding];
+        assert!(matches!(moved[0].info, PaddingKind::Oaep(_)));
+        if let PaddingKind::Oaep(info) = &moved[0].info {
+            assert_eq!(unsafe { info.pszAlgId.to_string() }.unwrap(), "SHA256");
+        }
+
+        assert_eq!(PaddingInfo::pkcs1_sha256().flags(), BCRYPT_PAD_PKCS1);
+        assert!(PaddingInfo::for_algorithm("AES").is_err());
+    }
+
+    #[test]
+    fn test_key_cache() {
+        let dummy = || {
+            Ok(CngKeyHandle {
+                handle: NCRYPT_KEY_HANDLE::default(),
+                should_free: false,
+            })
+        };
+
+        // Without a cache every lookup acquires the key
+        let provider = WindowsCertStoreProvider::new();
+        let first = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        let second = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+
+        let provider = WindowsCertStoreProvider::new().with_key_cache(Duration::from_secs(60));
+        let first = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        let second = provider
+            .cached_key("currentuser/my/ab", || {
+                Err(EncryptionError::CmkError("key should be cached".into()))
+            })
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        provider.clear_key_cache();
+        let third = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        assert!(!Arc::ptr_eq(&first, &third));
+
+        // Expired handles are acquired again
+        let provider = WindowsCertStoreProvider::new().with_key_cache(Duration::ZERO);
+        let first = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        let second = provider.cached_key("CurrentUser/My/AB", dummy).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+    }
+
     #[test]
     fn test_store_location_flags() {
         assert_eq!(StoreLocation::CurrentUser.to_flags(), 0x00010000);
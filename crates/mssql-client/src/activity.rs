@@ -0,0 +1,221 @@
+//! What the server is doing right now.
+//!
+//! [`Client::activity_snapshot`](crate::Client::activity_snapshot) lists the
+//! user sessions that are running a request or holding a transaction open,
+//! in the spirit of `sp_WhoIsActive`: the statement being run, what it waits
+//! for and who blocks it, its CPU, reads and duration, and the state of its
+//! transaction. The calling session is left out.
+//!
+//! ```rust,ignore
+//! for session in client.activity_snapshot().await? {
+//!     println!(
+//!         "{} {:?} {} {:?}",
+//!         session.session_id,
+//!         session.elapsed,
+//!         session.status,
+//!         session.statement.as_deref().unwrap_or(""),
+//!     );
+//! }
+//! ```
+//!
+//! Seeing other sessions needs the `VIEW SERVER STATE` permission (`VIEW
+//! SERVER PERFORMANCE STATE` on SQL Server 2022); without it only the
+//! caller's own sessions are listed.
+
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::row::Row;
+
+/// Running requests and idle sessions with open transactions, longest
+/// running first.
+pub(crate) const ACTIVITY_SQL: &str = "\
+SELECT s.session_id, s.login_name, s.host_name, s.program_name, \
+    DB_NAME(COALESCE(r.database_id, s.database_id)), COALESCE(r.status, s.status), \
+    r.command, r.wait_type, r.wait_time, r.wait_resource, ISNULL(r.blocking_session_id, 0), \
+    COALESCE(r.cpu_time, s.cpu_time), COALESCE(r.logical_reads, s.logical_reads), \
+    COALESCE(r.reads, s.reads), COALESCE(r.writes, s.writes), \
+    COALESCE(CAST(r.total_elapsed_time AS BIGINT), \
+        CAST(DATEDIFF(SECOND, s.last_request_end_time, GETDATE()) AS BIGINT) * 1000), \
+    r.percent_complete, s.open_transaction_count, tx.transaction_state, \
+    DATEDIFF(SECOND, tx.transaction_begin_time, GETDATE()), \
+    SUBSTRING(rt.text, r.statement_start_offset / 2 + 1, \
+        (CASE r.statement_end_offset WHEN -1 THEN DATALENGTH(rt.text) \
+            ELSE r.statement_end_offset END - r.statement_start_offset) / 2 + 1), \
+    COALESCE(rt.text, ct.text) \
+FROM sys.dm_exec_sessions s \
+LEFT JOIN sys.dm_exec_requests r ON r.session_id = s.session_id \
+LEFT JOIN sys.dm_exec_connections c ON c.session_id = s.session_id AND c.parent_connection_id IS NULL \
+OUTER APPLY sys.dm_exec_sql_text(r.sql_handle) rt \
+OUTER APPLY sys.dm_exec_sql_text(c.most_recent_sql_handle) ct \
+OUTER APPLY ( \
+    SELECT TOP (1) at.transaction_state, at.transaction_begin_time \
+    FROM sys.dm_tran_session_transactions st \
+    JOIN sys.dm_tran_active_transactions at ON at.transaction_id = st.transaction_id \
+    WHERE st.session_id = s.session_id \
+    ORDER BY at.transaction_begin_time \
+) tx \
+WHERE s.is_user_process = 1 AND s.session_id <> @@SPID \
+    AND (r.session_id IS NOT NULL OR s.open_transaction_count > 0) \
+ORDER BY 16 DESC";
+
+/// State of a session's transaction, from `sys.dm_tran_active_transactions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransactionState {
+    /// Begun, but no work done yet.
+    Initialized,
+    /// Active.
+    Active,
+    /// A read-only transaction that has ended.
+    Ended,
+    /// A distributed transaction whose commit has started.
+    Committing,
+    /// Prepared, waiting for the distributed commit decision.
+    Prepared,
+    /// Committed.
+    Committed,
+    /// Rolling back.
+    RollingBack,
+    /// Rolled back.
+    RolledBack,
+}
+
+impl TransactionState {
+    fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 | 1 => Some(Self::Initialized),
+            2 => Some(Self::Active),
+            3 => Some(Self::Ended),
+            4 => Some(Self::Committing),
+            5 => Some(Self::Prepared),
+            6 => Some(Self::Committed),
+            7 => Some(Self::RollingBack),
+            8 => Some(Self::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+/// A session that is running a request or holds a transaction open.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ActiveSession {
+    /// Session ID (`@@SPID`).
+    pub session_id: i16,
+    /// Login name.
+    pub login_name: String,
+    /// Client host name.
+    pub host_name: Option<String>,
+    /// Client program name.
+    pub program_name: Option<String>,
+    /// Database of the request, or of the session when idle.
+    pub database: Option<String>,
+    /// Request status (`running`, `runnable`, `suspended`), or `sleeping`
+    /// for an idle session.
+    pub status: String,
+    /// Command of the active request, e.g. `SELECT`.
+    pub command: Option<String>,
+    /// Current wait type, e.g. `PAGEIOLATCH_SH`.
+    pub wait_type: Option<String>,
+    /// Time spent in the current wait.
+    pub wait_time: Duration,
+    /// Resource waited for.
+    pub wait_resource: Option<String>,
+    /// Session blocking this one, or 0.
+    pub blocking_session_id: i16,
+    /// CPU time of the request, or of the session when idle.
+    pub cpu_time: Duration,
+    /// Pages read from the buffer pool.
+    pub logical_reads: i64,
+    /// Pages read from disk.
+    pub physical_reads: i64,
+    /// Pages written.
+    pub writes: i64,
+    /// Time the request has run, or the time since the last request ended
+    /// for an idle session.
+    pub elapsed: Duration,
+    /// Progress of commands that report it, such as `BACKUP` or `DBCC`.
+    pub percent_complete: Option<f32>,
+    /// Number of open transactions.
+    pub open_transactions: i32,
+    /// State of the session's transaction.
+    pub transaction_state: Option<TransactionState>,
+    /// Time since the transaction began.
+    pub transaction_duration: Option<Duration>,
+    /// The statement being run.
+    pub statement: Option<String>,
+    /// The whole batch being run, or the last batch of an idle session.
+    pub batch_text: Option<String>,
+}
+
+impl ActiveSession {
+    /// Check whether another session blocks this one.
+    #[must_use]
+    pub fn is_blocked(&self) -> bool {
+        self.blocking_session_id != 0
+    }
+
+    /// Check whether the session is idle with a transaction open, which
+    /// usually means an application forgot to commit.
+    #[must_use]
+    pub fn is_idle_in_transaction(&self) -> bool {
+        self.command.is_none() && self.open_transactions > 0
+    }
+}
+
+fn millis(value: i64) -> Duration {
+    Duration::from_millis(u64::try_from(value).unwrap_or(0))
+}
+
+pub(crate) fn session_from_row(row: &Row) -> Result<ActiveSession> {
+    let wait_ms: Option<i32> = row.get(8)?;
+    let cpu_ms: i32 = row.get(11)?;
+    let elapsed_ms: Option<i64> = row.get(15)?;
+    let transaction_state: Option<i32> = row.get(18)?;
+    let transaction_secs: Option<i32> = row.get(19)?;
+    Ok(ActiveSession {
+        session_id: row.get(0)?,
+        login_name: row.get(1)?,
+        host_name: row.get(2)?,
+        program_name: row.get(3)?,
+        database: row.get(4)?,
+        status: row.get(5)?,
+        command: row.get(6)?,
+        wait_type: row.get(7)?,
+        wait_time: millis(wait_ms.unwrap_or(0).into()),
+        wait_resource: row.get::<Option<String>>(9)?.filter(|s| !s.is_empty()),
+        blocking_session_id: row.get(10)?,
+        cpu_time: millis(cpu_ms.into()),
+        logical_reads: row.get(12)?,
+        physical_reads: row.get(13)?,
+        writes: row.get(14)?,
+        elapsed: millis(elapsed_ms.unwrap_or(0)),
+        percent_complete: row.get::<Option<f32>>(16)?.filter(|p| *p > 0.0),
+        open_transactions: row.get(17)?,
+        transaction_state: transaction_state.and_then(TransactionState::from_code),
+        transaction_duration: transaction_secs
+            .and_then(|s| u64::try_from(s).ok())
+            .map(Duration::from_secs),
+        statement: row.get(20)?,
+        batch_text: row.get(21)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_state_from_code() {
+        assert_eq!(
+            TransactionState::from_code(2),
+            Some(TransactionState::Active)
+        );
+        assert_eq!(
+            TransactionState::from_code(7),
+            Some(TransactionState::RollingBack)
+        );
+        assert_eq!(TransactionState::from_code(42), None);
+    }
+}
@@ -0,0 +1,762 @@
+//! Database, login, and permission administration.
+//!
+//! [`Client::admin`](crate::Client::admin) returns an [`Admin`] handle with
+//! typed wrappers for the DDL that provisioning tools otherwise build by
+//! string concatenation: `CREATE`/`ALTER`/`DROP DATABASE`, logins, users,
+//! roles, role membership, and permission grants.
+//!
+//! Names are validated (1-128 characters, no control characters) and
+//! bracket-quoted, so any name SQL Server accepts can be used without opening
+//! an injection hole. Passwords are sent as parameters and the statement
+//! setting them is built on the server, so they never appear in the SQL text
+//! that is logged or traced.
+//!
+//! The `ensure_*` methods check for the object first and create it only if
+//! it is missing, in a single batch, returning whether they created it.
+//! They make provisioning scripts safe to run again:
+//!
+//! ```rust,ignore
+//! use mssql_client::admin::{LoginAuth, Securable, UserSource};
+//!
+//! let mut admin = client.admin();
+//! admin.ensure_database("Orders", &[]).await?;
+//! admin
+//!     .ensure_login("orders_app", &LoginAuth::Password(password))
+//!     .await?;
+//!
+//! // Users, roles and grants apply to the current database
+//! client.execute("USE [Orders]", &[]).await?;
+//! let mut admin = client.admin();
+//! admin
+//!     .ensure_user("orders_app", &UserSource::Login("orders_app".into()))
+//!     .await?;
+//! admin.ensure_role_member("db_datareader", "orders_app").await?;
+//! admin
+//!     .grant("EXECUTE", &Securable::Schema("api".into()), "orders_app")
+//!     .await?;
+//! ```
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::identifier::quote_identifier;
+use crate::state::Ready;
+
+/// Database recovery model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryModel {
+    /// Full logging; log backups required.
+    Full,
+    /// Minimal logging for bulk operations.
+    BulkLogged,
+    /// Log truncated at checkpoints; no log backups.
+    Simple,
+}
+
+impl RecoveryModel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::Full => "FULL",
+            Self::BulkLogged => "BULK_LOGGED",
+            Self::Simple => "SIMPLE",
+        }
+    }
+}
+
+/// A database setting for [`Admin::create_database`] and
+/// [`Admin::alter_database`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DatabaseOption {
+    /// Default collation, e.g. `Latin1_General_100_CI_AS_SC_UTF8`.
+    Collation(String),
+    /// Recovery model.
+    RecoveryModel(RecoveryModel),
+    /// Compatibility level, e.g. 160 for SQL Server 2022.
+    CompatibilityLevel(u16),
+    /// Make the database read-only or read-write.
+    ReadOnly(bool),
+    /// Allow snapshot isolation.
+    AllowSnapshotIsolation(bool),
+    /// Use row versioning for read committed.
+    ReadCommittedSnapshot(bool),
+    /// Restrict access to one connection, rolling back other sessions'
+    /// transactions (`SINGLE_USER WITH ROLLBACK IMMEDIATE`).
+    SingleUser,
+    /// Allow all connections again.
+    MultiUser,
+}
+
+impl DatabaseOption {
+    fn alter_sql(&self, database: &str) -> Result<String> {
+        let clause = match self {
+            Self::Collation(collation) => format!("COLLATE {}", collation_name(collation)?),
+            Self::RecoveryModel(model) => format!("SET RECOVERY {}", model.as_sql()),
+            Self::CompatibilityLevel(level) => format!("SET COMPATIBILITY_LEVEL = {level}"),
+            Self::ReadOnly(true) => "SET READ_ONLY".to_string(),
+            Self::ReadOnly(false) => "SET READ_WRITE".to_string(),
+            Self::AllowSnapshotIsolation(on) => {
+                format!("SET ALLOW_SNAPSHOT_ISOLATION {}", on_off(*on))
+            }
+            Self::ReadCommittedSnapshot(on) => {
+                format!("SET READ_COMMITTED_SNAPSHOT {}", on_off(*on))
+            }
+            Self::SingleUser => "SET SINGLE_USER WITH ROLLBACK IMMEDIATE".to_string(),
+            Self::MultiUser => "SET MULTI_USER".to_string(),
+        };
+        Ok(format!("ALTER DATABASE {database} {clause}"))
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on { "ON" } else { "OFF" }
+}
+
+/// How a login authenticates.
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoginAuth {
+    /// SQL Server authentication with a password.
+    Password(String),
+    /// A Windows user or group (`DOMAIN\name`).
+    Windows,
+    /// A Microsoft Entra ID principal.
+    ExternalProvider,
+}
+
+impl std::fmt::Debug for LoginAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Password(_) => f.write_str("Password([REDACTED])"),
+            Self::Windows => f.write_str("Windows"),
+            Self::ExternalProvider => f.write_str("ExternalProvider"),
+        }
+    }
+}
+
+/// What a database user is created from.
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UserSource {
+    /// Map the user to a server login.
+    Login(String),
+    /// A user with no login, for impersonation and signing.
+    WithoutLogin,
+    /// A Microsoft Entra ID principal.
+    ExternalProvider,
+    /// A contained database user with its own password.
+    Password(String),
+}
+
+impl std::fmt::Debug for UserSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Login(login) => f.debug_tuple("Login").field(login).finish(),
+            Self::WithoutLogin => f.write_str("WithoutLogin"),
+            Self::ExternalProvider => f.write_str("ExternalProvider"),
+            Self::Password(_) => f.write_str("Password([REDACTED])"),
+        }
+    }
+}
+
+/// What a permission applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Securable {
+    /// The current database, or the server for server permissions such as
+    /// `VIEW SERVER STATE` granted to a login.
+    Database,
+    /// A schema in the current database.
+    Schema(String),
+    /// A table, view, procedure or function, optionally schema-qualified
+    /// (`dbo.Orders`).
+    Object(String),
+}
+
+impl Securable {
+    fn on_clause(&self) -> Result<String> {
+        match self {
+            Self::Database => Ok(String::new()),
+            Self::Schema(schema) => Ok(format!(" ON SCHEMA::{}", quote_name("schema", schema)?)),
+            Self::Object(object) => {
+                let parts = object
+                    .splitn(2, '.')
+                    .map(|part| quote_name("object", part))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(format!(" ON OBJECT::{}", parts.join(".")))
+            }
+        }
+    }
+}
+
+/// Validate a name and bracket-quote it.
+fn quote_name(kind: &str, name: &str) -> Result<String> {
+    if name.is_empty() || name.chars().count() > 128 {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid {kind} name '{name}': must be 1-128 characters"
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid {kind} name '{}': must not contain control characters",
+            name.escape_debug()
+        )));
+    }
+    Ok(quote_identifier(name))
+}
+
+/// Build a batch running `{head} <name> WITH PASSWORD = '<password>'`
+/// with the name in `@p1` and the password in `@p2`.
+///
+/// DDL cannot take parameters, so the statement is assembled and run with
+/// `EXEC` on the server; the password is never part of the SQL text.
+fn password_sql(head: &str) -> String {
+    format!(
+        "DECLARE @ddl nvarchar(max) = N'{head} ' + QUOTENAME(@p1) + \
+         N' WITH PASSWORD = N''' + REPLACE(@p2, N'''', N'''''') + N''''; EXEC (@ddl)"
+    )
+}
+
+/// A statement creating a principal, and the password it binds as `@p2`
+/// if it was built by [`password_sql`].
+struct PrincipalDdl<'a> {
+    sql: String,
+    password: Option<&'a str>,
+}
+
+fn collation_name(collation: &str) -> Result<&str> {
+    if collation.is_empty()
+        || !collation
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid collation '{collation}'"
+        )));
+    }
+    Ok(collation)
+}
+
+/// Validate a permission such as `SELECT` or `VIEW DEFINITION`.
+fn permission_name(permission: &str) -> Result<String> {
+    let words: Vec<&str> = permission.split_whitespace().collect();
+    if words.is_empty()
+        || !words
+            .iter()
+            .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid permission '{permission}'"
+        )));
+    }
+    Ok(words.join(" ").to_ascii_uppercase())
+}
+
+/// Wrap a statement so it runs only when `exists` is false, selecting
+/// whether it ran.
+fn ensure_sql(exists: &str, statement: &str) -> String {
+    format!(
+        "IF NOT EXISTS ({exists}) BEGIN {statement}; SELECT CAST(1 AS BIT) END \
+         ELSE SELECT CAST(0 AS BIT)"
+    )
+}
+
+/// Wrap a statement so it runs only when `exists` is true, selecting
+/// whether it ran.
+fn if_exists_sql(exists: &str, statement: &str) -> String {
+    format!(
+        "IF EXISTS ({exists}) BEGIN {statement}; SELECT CAST(1 AS BIT) END \
+         ELSE SELECT CAST(0 AS BIT)"
+    )
+}
+
+const DATABASE_EXISTS: &str = "SELECT 1 WHERE DB_ID(@p1) IS NOT NULL";
+const LOGIN_EXISTS: &str = "SELECT 1 FROM sys.server_principals WHERE name = @p1";
+const PRINCIPAL_EXISTS: &str = "SELECT 1 WHERE DATABASE_PRINCIPAL_ID(@p1) IS NOT NULL";
+const ROLE_MEMBER_EXISTS: &str = "\
+SELECT 1 FROM sys.database_role_members \
+WHERE role_principal_id = DATABASE_PRINCIPAL_ID(@p1) \
+    AND member_principal_id = DATABASE_PRINCIPAL_ID(@p2)";
+const SERVER_ROLE_MEMBER_EXISTS: &str = "\
+SELECT 1 FROM sys.server_role_members rm \
+JOIN sys.server_principals r ON r.principal_id = rm.role_principal_id \
+JOIN sys.server_principals m ON m.principal_id = rm.member_principal_id \
+WHERE r.name = @p1 AND m.name = @p2";
+
+fn create_database_sql(name: &str, options: &[DatabaseOption]) -> Result<Vec<String>> {
+    let database = quote_name("database", name)?;
+    let mut create = format!("CREATE DATABASE {database}");
+    let mut statements = Vec::new();
+    for option in options {
+        match option {
+            DatabaseOption::Collation(collation) => {
+                create.push_str(&format!(" COLLATE {}", collation_name(collation)?));
+            }
+            other => statements.push(other.alter_sql(&database)?),
+        }
+    }
+    statements.insert(0, create);
+    Ok(statements)
+}
+
+fn create_login_sql<'a>(name: &str, auth: &'a LoginAuth) -> Result<PrincipalDdl<'a>> {
+    let login = quote_name("login", name)?;
+    let (sql, password) = match auth {
+        LoginAuth::Password(password) => (password_sql("CREATE LOGIN"), Some(password.as_str())),
+        LoginAuth::Windows => (format!("CREATE LOGIN {login} FROM WINDOWS"), None),
+        LoginAuth::ExternalProvider => {
+            (format!("CREATE LOGIN {login} FROM EXTERNAL PROVIDER"), None)
+        }
+    };
+    Ok(PrincipalDdl { sql, password })
+}
+
+fn create_user_sql<'a>(name: &str, source: &'a UserSource) -> Result<PrincipalDdl<'a>> {
+    let user = quote_name("user", name)?;
+    let (sql, password) = match source {
+        UserSource::Login(login) => (
+            format!(
+                "CREATE USER {user} FOR LOGIN {}",
+                quote_name("login", login)?
+            ),
+            None,
+        ),
+        UserSource::WithoutLogin => (format!("CREATE USER {user} WITHOUT LOGIN"), None),
+        UserSource::ExternalProvider => {
+            (format!("CREATE USER {user} FROM EXTERNAL PROVIDER"), None)
+        }
+        UserSource::Password(password) => (password_sql("CREATE USER"), Some(password.as_str())),
+    };
+    Ok(PrincipalDdl { sql, password })
+}
+
+fn permission_sql(
+    verb: &str,
+    permission: &str,
+    securable: &Securable,
+    preposition: &str,
+    principal: &str,
+) -> Result<String> {
+    Ok(format!(
+        "{verb} {}{} {preposition} {}",
+        permission_name(permission)?,
+        securable.on_clause()?,
+        quote_name("principal", principal)?
+    ))
+}
+
+/// Runs administrative DDL through a client.
+///
+/// Created with [`Client::admin`](crate::Client::admin). See the
+/// [module documentation](self).
+pub struct Admin<'a> {
+    client: &'a mut Client<Ready>,
+}
+
+impl<'a> Admin<'a> {
+    pub(crate) fn new(client: &'a mut Client<Ready>) -> Self {
+        Self { client }
+    }
+
+    /// Run and log a statement.
+    async fn run(&mut self, sql: &str) -> Result<()> {
+        tracing::info!(sql, "running administrative statement");
+        self.client.execute(sql, &[]).await?;
+        Ok(())
+    }
+
+    /// Run a statement creating or altering the principal `name`, binding
+    /// its password if it has one. Statements setting a password are only
+    /// logged by name.
+    async fn run_principal_ddl(&mut self, name: &str, ddl: &PrincipalDdl<'_>) -> Result<()> {
+        match ddl.password {
+            Some(password) => {
+                tracing::info!(name, "running administrative statement with a password");
+                self.client.execute(&ddl.sql, &[&name, &password]).await?;
+                Ok(())
+            }
+            None => self.run(&ddl.sql).await,
+        }
+    }
+
+    /// Create the principal `name` with `ddl` unless `exists` finds it,
+    /// returning whether it was created.
+    async fn ensure_principal(
+        &mut self,
+        exists: &str,
+        name: &str,
+        ddl: &PrincipalDdl<'_>,
+    ) -> Result<bool> {
+        let sql = ensure_sql(exists, &ddl.sql);
+        match ddl.password {
+            Some(password) => self.run_if(&sql, &[&name, &password]).await,
+            None => self.run_if(&sql, &[&name]).await,
+        }
+    }
+
+    /// Run a conditional batch built by [`ensure_sql`] or [`if_exists_sql`].
+    async fn run_if(&mut self, sql: &str, params: &[&(dyn crate::ToSql + Sync)]) -> Result<bool> {
+        let rows = self.client.fetch_rows(sql, params).await?;
+        Ok(rows
+            .first()
+            .map(|row| row.get::<bool>(0))
+            .transpose()?
+            .unwrap_or(false))
+    }
+
+    /// Check whether a database exists.
+    pub async fn database_exists(&mut self, name: &str) -> Result<bool> {
+        let rows = self.client.fetch_rows(DATABASE_EXISTS, &[&name]).await?;
+        Ok(!rows.is_empty())
+    }
+
+    /// Create a database.
+    ///
+    /// [`DatabaseOption::Collation`] is applied by `CREATE DATABASE`; the
+    /// other options are applied with `ALTER DATABASE` afterwards.
+    pub async fn create_database(&mut self, name: &str, options: &[DatabaseOption]) -> Result<()> {
+        for sql in create_database_sql(name, options)? {
+            self.run(&sql).await?;
+        }
+        Ok(())
+    }
+
+    /// Create a database unless it exists, returning whether it was
+    /// created.
+    ///
+    /// The options are only applied to a database this call creates.
+    pub async fn ensure_database(
+        &mut self,
+        name: &str,
+        options: &[DatabaseOption],
+    ) -> Result<bool> {
+        let mut statements = create_database_sql(name, options)?.into_iter();
+        let create = statements.next().unwrap_or_default();
+        let created = self
+            .run_if(&ensure_sql(DATABASE_EXISTS, &create), &[&name])
+            .await?;
+        if created {
+            for sql in statements {
+                self.run(&sql).await?;
+            }
+        }
+        Ok(created)
+    }
+
+    /// Change a database setting.
+    pub async fn alter_database(&mut self, name: &str, option: &DatabaseOption) -> Result<()> {
+        let sql = option.alter_sql(&quote_name("database", name)?)?;
+        self.run(&sql).await
+    }
+
+    /// Rename a database.
+    pub async fn rename_database(&mut self, name: &str, new_name: &str) -> Result<()> {
+        let sql = format!(
+            "ALTER DATABASE {} MODIFY NAME = {}",
+            quote_name("database", name)?,
+            quote_name("database", new_name)?
+        );
+        self.run(&sql).await
+    }
+
+    /// Drop a database.
+    ///
+    /// Fails while other sessions use the database; set
+    /// [`DatabaseOption::SingleUser`] first to disconnect them.
+    pub async fn drop_database(&mut self, name: &str) -> Result<()> {
+        let sql = format!("DROP DATABASE {}", quote_name("database", name)?);
+        self.run(&sql).await
+    }
+
+    /// Drop a database if it exists, returning whether it was dropped.
+    pub async fn drop_database_if_exists(&mut self, name: &str) -> Result<bool> {
+        let sql = format!("DROP DATABASE {}", quote_name("database", name)?);
+        self.run_if(&if_exists_sql(DATABASE_EXISTS, &sql), &[&name])
+            .await
+    }
+
+    /// Create a server login.
+    pub async fn create_login(&mut self, name: &str, auth: &LoginAuth) -> Result<()> {
+        let ddl = create_login_sql(name, auth)?;
+        self.run_principal_ddl(name, &ddl).await
+    }
+
+    /// Create a server login unless it exists, returning whether it was
+    /// created. An existing login's password is left unchanged.
+    pub async fn ensure_login(&mut self, name: &str, auth: &LoginAuth) -> Result<bool> {
+        let ddl = create_login_sql(name, auth)?;
+        self.ensure_principal(LOGIN_EXISTS, name, &ddl).await
+    }
+
+    /// Change the password of a SQL Server login.
+    pub async fn alter_login_password(&mut self, name: &str, password: &str) -> Result<()> {
+        // Validated here; QUOTENAME quotes it on the server
+        quote_name("login", name)?;
+        let ddl = PrincipalDdl {
+            sql: password_sql("ALTER LOGIN"),
+            password: Some(password),
+        };
+        self.run_principal_ddl(name, &ddl).await
+    }
+
+    /// Enable or disable a login.
+    pub async fn set_login_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        let sql = format!(
+            "ALTER LOGIN {} {}",
+            quote_name("login", name)?,
+            if enabled { "ENABLE" } else { "DISABLE" }
+        );
+        self.run(&sql).await
+    }
+
+    /// Drop a server login.
+    pub async fn drop_login(&mut self, name: &str) -> Result<()> {
+        let sql = format!("DROP LOGIN {}", quote_name("login", name)?);
+        self.run(&sql).await
+    }
+
+    /// Drop a server login if it exists, returning whether it was dropped.
+    pub async fn drop_login_if_exists(&mut self, name: &str) -> Result<bool> {
+        let sql = format!("DROP LOGIN {}", quote_name("login", name)?);
+        self.run_if(&if_exists_sql(LOGIN_EXISTS, &sql), &[&name])
+            .await
+    }
+
+    /// Create a user in the current database.
+    pub async fn create_user(&mut self, name: &str, source: &UserSource) -> Result<()> {
+        let ddl = create_user_sql(name, source)?;
+        self.run_principal_ddl(name, &ddl).await
+    }
+
+    /// Create a user in the current database unless a user or role with
+    /// that name exists, returning whether it was created.
+    pub async fn ensure_user(&mut self, name: &str, source: &UserSource) -> Result<bool> {
+        let ddl = create_user_sql(name, source)?;
+        self.ensure_principal(PRINCIPAL_EXISTS, name, &ddl).await
+    }
+
+    /// Drop a user from the current database.
+    pub async fn drop_user(&mut self, name: &str) -> Result<()> {
+        let sql = format!("DROP USER {}", quote_name("user", name)?);
+        self.run(&sql).await
+    }
+
+    /// Drop a user from the current database if it exists, returning
+    /// whether it was dropped.
+    pub async fn drop_user_if_exists(&mut self, name: &str) -> Result<bool> {
+        let sql = format!("DROP USER {}", quote_name("user", name)?);
+        self.run_if(&if_exists_sql(PRINCIPAL_EXISTS, &sql), &[&name])
+            .await
+    }
+
+    /// Create a role in the current database.
+    pub async fn create_role(&mut self, name: &str) -> Result<()> {
+        let sql = format!("CREATE ROLE {}", quote_name("role", name)?);
+        self.run(&sql).await
+    }
+
+    /// Create a role in the current database unless a user or role with
+    /// that name exists, returning whether it was created.
+    pub async fn ensure_role(&mut self, name: &str) -> Result<bool> {
+        let sql = format!("CREATE ROLE {}", quote_name("role", name)?);
+        self.run_if(&ensure_sql(PRINCIPAL_EXISTS, &sql), &[&name])
+            .await
+    }
+
+    /// Drop a role from the current database.
+    pub async fn drop_role(&mut self, name: &str) -> Result<()> {
+        let sql = format!("DROP ROLE {}", quote_name("role", name)?);
+        self.run(&sql).await
+    }
+
+    /// Add a user or role to a database role.
+    pub async fn add_role_member(&mut self, role: &str, member: &str) -> Result<()> {
+        let sql = role_member_sql("ROLE", role, "ADD", member)?;
+        self.run(&sql).await
+    }
+
+    /// Add a user or role to a database role unless it is a member,
+    /// returning whether it was added.
+    pub async fn ensure_role_member(&mut self, role: &str, member: &str) -> Result<bool> {
+        let sql = role_member_sql("ROLE", role, "ADD", member)?;
+        self.run_if(&ensure_sql(ROLE_MEMBER_EXISTS, &sql), &[&role, &member])
+            .await
+    }
+
+    /// Remove a user or role from a database role.
+    pub async fn drop_role_member(&mut self, role: &str, member: &str) -> Result<()> {
+        let sql = role_member_sql("ROLE", role, "DROP", member)?;
+        self.run(&sql).await
+    }
+
+    /// Add a login to a server role such as `dbcreator`.
+    pub async fn add_server_role_member(&mut self, role: &str, login: &str) -> Result<()> {
+        let sql = role_member_sql("SERVER ROLE", role, "ADD", login)?;
+        self.run(&sql).await
+    }
+
+    /// Add a login to a server role unless it is a member, returning
+    /// whether it was added.
+    pub async fn ensure_server_role_member(&mut self, role: &str, login: &str) -> Result<bool> {
+        let sql = role_member_sql("SERVER ROLE", role, "ADD", login)?;
+        self.run_if(
+            &ensure_sql(SERVER_ROLE_MEMBER_EXISTS, &sql),
+            &[&role, &login],
+        )
+        .await
+    }
+
+    /// Remove a login from a server role.
+    pub async fn drop_server_role_member(&mut self, role: &str, login: &str) -> Result<()> {
+        let sql = role_member_sql("SERVER ROLE", role, "DROP", login)?;
+        self.run(&sql).await
+    }
+
+    /// Grant a permission, such as `SELECT` or `VIEW DEFINITION`, to a
+    /// user, role, or login.
+    ///
+    /// Granting a permission that is already granted has no effect, so
+    /// this needs no `ensure` variant.
+    pub async fn grant(
+        &mut self,
+        permission: &str,
+        securable: &Securable,
+        principal: &str,
+    ) -> Result<()> {
+        let sql = permission_sql("GRANT", permission, securable, "TO", principal)?;
+        self.run(&sql).await
+    }
+
+    /// Deny a permission to a user, role, or login.
+    pub async fn deny(
+        &mut self,
+        permission: &str,
+        securable: &Securable,
+        principal: &str,
+    ) -> Result<()> {
+        let sql = permission_sql("DENY", permission, securable, "TO", principal)?;
+        self.run(&sql).await
+    }
+
+    /// Remove a granted or denied permission.
+    pub async fn revoke(
+        &mut self,
+        permission: &str,
+        securable: &Securable,
+        principal: &str,
+    ) -> Result<()> {
+        let sql = permission_sql("REVOKE", permission, securable, "FROM", principal)?;
+        self.run(&sql).await
+    }
+}
+
+fn role_member_sql(kind: &str, role: &str, action: &str, member: &str) -> Result<String> {
+    Ok(format!(
+        "ALTER {kind} {} {action} MEMBER {}",
+        quote_name("role", role)?,
+        quote_name("member", member)?
+    ))
+}
+
+impl std::fmt::Debug for Admin<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Admin").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_name() {
+        assert_eq!(
+            quote_name("login", "CONTOSO\\svc").unwrap(),
+            "[CONTOSO\\svc]"
+        );
+        assert_eq!(quote_name("database", "a]b").unwrap(), "[a]]b]");
+        assert!(quote_name("database", "").is_err());
+        assert!(quote_name("database", "bad\nname").is_err());
+        assert!(quote_name("database", &"x".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn test_create_database_sql() {
+        let statements = create_database_sql(
+            "Orders",
+            &[
+                DatabaseOption::RecoveryModel(RecoveryModel::Simple),
+                DatabaseOption::Collation("Latin1_General_100_CI_AS_SC_UTF8".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            statements,
+            [
+                "CREATE DATABASE [Orders] COLLATE Latin1_General_100_CI_AS_SC_UTF8",
+                "ALTER DATABASE [Orders] SET RECOVERY SIMPLE",
+            ]
+        );
+        assert!(
+            create_database_sql("Orders", &[DatabaseOption::Collation("x; DROP".into())]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_login_and_user_sql() {
+        let auth = LoginAuth::Password("it's-s3cret".into());
+        let ddl = create_login_sql("app", &auth).unwrap();
+        assert_eq!(
+            ddl.sql,
+            "DECLARE @ddl nvarchar(max) = N'CREATE LOGIN ' + QUOTENAME(@p1) + \
+             N' WITH PASSWORD = N''' + REPLACE(@p2, N'''', N'''''') + N''''; EXEC (@ddl)"
+        );
+        assert_eq!(ddl.password, Some("it's-s3cret"));
+
+        let source = UserSource::Password("it's-s3cret".into());
+        let ddl = create_user_sql("app", &source).unwrap();
+        assert!(
+            ddl.sql
+                .starts_with("DECLARE @ddl nvarchar(max) = N'CREATE USER '")
+        );
+        assert!(!ensure_sql(PRINCIPAL_EXISTS, &ddl.sql).contains("s3cret"));
+
+        let source = UserSource::Login("app".into());
+        let ddl = create_user_sql("app", &source).unwrap();
+        assert_eq!(ddl.sql, "CREATE USER [app] FOR LOGIN [app]");
+        assert_eq!(ddl.password, None);
+        assert_eq!(
+            format!("{:?}", LoginAuth::Password("secret".into())),
+            "Password([REDACTED])"
+        );
+    }
+
+    #[test]
+    fn test_permission_sql() {
+        assert_eq!(
+            permission_sql(
+                "GRANT",
+                "view  definition",
+                &Securable::Object("dbo.Orders".into()),
+                "TO",
+                "reporting",
+            )
+            .unwrap(),
+            "GRANT VIEW DEFINITION ON OBJECT::[dbo].[Orders] TO [reporting]"
+        );
+        assert_eq!(
+            permission_sql("REVOKE", "CONNECT", &Securable::Database, "FROM", "app").unwrap(),
+            "REVOKE CONNECT FROM [app]"
+        );
+        assert!(permission_sql("GRANT", "SELECT; --", &Securable::Database, "TO", "app").is_err());
+    }
+
+    #[test]
+    fn test_ensure_sql() {
+        assert_eq!(
+            ensure_sql(DATABASE_EXISTS, "CREATE DATABASE [x]"),
+            "IF NOT EXISTS (SELECT 1 WHERE DB_ID(@p1) IS NOT NULL) BEGIN CREATE DATABASE [x]; \
+             SELECT CAST(1 AS BIT) END ELSE SELECT CAST(0 AS BIT)"
+        );
+    }
+}
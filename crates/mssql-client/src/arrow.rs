@@ -0,0 +1,607 @@
+//! Apache Arrow export of query results.
+//!
+//! [`ArrowBatches`] converts the rows of a result set into Arrow
+//! [`RecordBatch`]es of a configurable number of rows, so results can be
+//! handed to Arrow-based engines such as polars or DataFusion without a
+//! per-row conversion in application code. It implements both
+//! `Iterator` and [`RecordBatchReader`].
+//!
+//! ## Type Mapping
+//!
+//! | SQL Server type | Arrow type |
+//! |-----------------|------------|
+//! | `BIT` | `Boolean` |
+//! | `TINYINT` | `UInt8` |
+//! | `SMALLINT` / `INT` / `BIGINT` | `Int16` / `Int32` / `Int64` |
+//! | `REAL` / `FLOAT` | `Float32` / `Float64` |
+//! | `DECIMAL(p,s)` / `NUMERIC(p,s)` | `Decimal128(p, s)` |
+//! | `MONEY` / `SMALLMONEY` | `Decimal128(19, 4)` / `Decimal128(10, 4)` |
+//! | `CHAR` / `VARCHAR` / `NCHAR` / `NVARCHAR` / `TEXT` / `NTEXT` / `XML` | `Utf8` |
+//! | `BINARY` / `VARBINARY` / `IMAGE` / UDTs | `Binary` |
+//! | `UNIQUEIDENTIFIER` | `Utf8` (hyphenated) |
+//! | `DATE` | `Date32` |
+//! | `TIME` | `Time64(Nanosecond)` |
+//! | `DATETIME` / `SMALLDATETIME` / `DATETIME2` | `Timestamp(Microsecond, None)` |
+//! | `DATETIMEOFFSET` | `Timestamp(Microsecond, "+00:00")` |
+//!
+//! Timestamps use microseconds so the full `0001-9999` range of
+//! `DATETIME2` fits; the seventh fractional digit of `DATETIME2(7)` is
+//! truncated. `DATETIMEOFFSET` values are normalized to UTC. Any other
+//! type is exported as `Utf8`.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! let batches = client
+//!     .query_arrow("SELECT id, name, created_at FROM users", &[])
+//!     .await?
+//!     .with_batch_size(50_000);
+//!
+//! let schema = batches.schema();
+//! let batches: Vec<RecordBatch> = batches.collect::<Result<_, _>>()?;
+//! let df = polars::prelude::DataFrame::try_from((batches, schema.as_ref()))?;
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Date32Builder, Decimal128Builder, Float32Builder,
+    Float64Builder, Int16Builder, Int32Builder, Int64Builder, StringBuilder,
+    Time64NanosecondBuilder, TimestampMicrosecondBuilder, UInt8Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions, RecordBatchReader};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use mssql_types::SqlValue;
+use rust_decimal::Decimal;
+
+use crate::row::{Column, Row};
+use crate::stream::QueryStream;
+
+/// Default number of rows per record batch.
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// Time zone of exported `DATETIMEOFFSET` columns.
+const UTC: &str = "+00:00";
+
+/// Iterator of Arrow record batches over a result set.
+///
+/// Created by [`Client::query_arrow`](crate::Client::query_arrow) or
+/// from any [`QueryStream`]. Each batch holds up to
+/// [`batch_size`](Self::with_batch_size) rows; the schema is derived from
+/// the column metadata and is the same for every batch, even when the
+/// result set is empty.
+pub struct ArrowBatches {
+    schema: SchemaRef,
+    rows: VecDeque<Row>,
+    batch_size: usize,
+}
+
+impl ArrowBatches {
+    /// Create batches over the given columns and rows.
+    pub fn new(columns: &[Column], rows: impl IntoIterator<Item = Row>) -> Self {
+        Self {
+            schema: Arc::new(arrow_schema(columns)),
+            rows: rows.into_iter().collect(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Set the maximum number of rows per record batch (at least 1).
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Get the maximum number of rows per record batch.
+    #[must_use]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Get the Arrow schema of the batches.
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    /// Get the number of rows not yet converted.
+    #[must_use]
+    pub fn rows_remaining(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Convert the next chunk of rows into a record batch.
+    fn next_batch(&mut self) -> Result<RecordBatch, ArrowError> {
+        let count = self.batch_size.min(self.rows.len());
+        let rows: Vec<Row> = self.rows.drain(..count).collect();
+
+        let arrays = self
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| build_array(field, &rows, index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The row count must be explicit for result sets without columns
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        RecordBatch::try_new_with_options(self.schema(), arrays, &options)
+    }
+}
+
+impl From<QueryStream<'_>> for ArrowBatches {
+    fn from(stream: QueryStream<'_>) -> Self {
+        let columns = stream.columns().to_vec();
+        Self::new(&columns, stream.filter_map(std::result::Result::ok))
+    }
+}
+
+impl Iterator for ArrowBatches {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rows.is_empty() {
+            return None;
+        }
+        Some(self.next_batch())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let batches = self.rows.len().div_ceil(self.batch_size);
+        (batches, Some(batches))
+    }
+}
+
+impl RecordBatchReader for ArrowBatches {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
+
+impl std::fmt::Debug for ArrowBatches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArrowBatches")
+            .field("schema", &self.schema)
+            .field("rows_remaining", &self.rows.len())
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+/// Build the Arrow schema for a result set.
+#[must_use]
+pub fn arrow_schema(columns: &[Column]) -> Schema {
+    Schema::new(
+        columns
+            .iter()
+            .map(|column| Field::new(&column.name, arrow_type(column), column.nullable))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Get the Arrow type a column is exported as.
+///
+/// Accepts both the TDS type names reported by the server (`Int4`,
+/// `NVarChar`, `DateTime2`, ...) and SQL type names (`INT`, `NVARCHAR`, ...).
+#[must_use]
+pub fn arrow_type(column: &Column) -> DataType {
+    match column.type_name.to_uppercase().as_str() {
+        "BIT" | "BITN" => DataType::Boolean,
+        "INT1" | "TINYINT" => DataType::UInt8,
+        "INT2" | "SMALLINT" => DataType::Int16,
+        "INT4" | "INT" | "INTEGER" => DataType::Int32,
+        "INT8" | "BIGINT" => DataType::Int64,
+        "INTN" => match column.max_length {
+            Some(1) => DataType::UInt8,
+            Some(2) => DataType::Int16,
+            Some(4) => DataType::Int32,
+            _ => DataType::Int64,
+        },
+        "FLOAT4" | "REAL" => DataType::Float32,
+        "FLOAT8" | "FLOAT" => DataType::Float64,
+        "FLOATN" => match column.max_length {
+            Some(4) => DataType::Float32,
+            _ => DataType::Float64,
+        },
+        "MONEY4" | "SMALLMONEY" => DataType::Decimal128(10, 4),
+        "MONEYN" if column.max_length == Some(4) => DataType::Decimal128(10, 4),
+        "MONEY" | "MONEYN" => DataType::Decimal128(19, 4),
+        "DECIMAL" | "NUMERIC" | "DECIMALN" | "NUMERICN" => {
+            let precision = column
+                .precision
+                .filter(|p| (1..=38).contains(p))
+                .unwrap_or(38);
+            let scale = column.scale.unwrap_or(0).min(precision);
+            DataType::Decimal128(precision, scale as i8)
+        }
+        "BINARY" | "VARBINARY" | "BIGBINARY" | "BIGVARBINARY" | "IMAGE" | "UDT" => DataType::Binary,
+        "DATE" => DataType::Date32,
+        "TIME" => DataType::Time64(TimeUnit::Nanosecond),
+        "DATETIME" | "DATETIME4" | "DATETIMEN" | "SMALLDATETIME" | "DATETIME2" => {
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        }
+        "DATETIMEOFFSET" => DataType::Timestamp(TimeUnit::Microsecond, Some(UTC.into())),
+        _ => DataType::Utf8,
+    }
+}
+
+/// Build the array of one column of a batch.
+fn build_array(field: &Field, rows: &[Row], index: usize) -> Result<ArrayRef, ArrowError> {
+    let values = rows.iter().map(|row| {
+        row.get_raw(index)
+            .map(unwrap_typed)
+            .unwrap_or(SqlValue::Null)
+    });
+    let mismatch = |value: &SqlValue| {
+        ArrowError::CastError(format!(
+            "column '{}': cannot convert {value:?} to {}",
+            field.name(),
+            field.data_type()
+        ))
+    };
+
+    macro_rules! build {
+        ($builder:expr, $convert:expr) => {{
+            let mut builder = $builder;
+            for value in values {
+                if value.is_null() {
+                    builder.append_null();
+                } else {
+                    let converted = $convert(&value).ok_or_else(|| mismatch(&value))?;
+                    builder.append_value(converted);
+                }
+            }
+            Arc::new(builder.finish()) as ArrayRef
+        }};
+    }
+
+    let array = match field.data_type() {
+        DataType::Boolean => build!(BooleanBuilder::with_capacity(rows.len()), to_bool),
+        DataType::UInt8 => build!(UInt8Builder::with_capacity(rows.len()), |v| to_i64(v)
+            .and_then(|i| u8::try_from(i).ok())),
+        DataType::Int16 => build!(Int16Builder::with_capacity(rows.len()), |v| to_i64(v)
+            .and_then(|i| i16::try_from(i).ok())),
+        DataType::Int32 => build!(Int32Builder::with_capacity(rows.len()), |v| to_i64(v)
+            .and_then(|i| i32::try_from(i).ok())),
+        DataType::Int64 => build!(Int64Builder::with_capacity(rows.len()), to_i64),
+        DataType::Float32 => build!(Float32Builder::with_capacity(rows.len()), |v| to_f64(v)
+            .map(|f| f as f32)),
+        DataType::Float64 => build!(Float64Builder::with_capacity(rows.len()), to_f64),
+        DataType::Decimal128(precision, scale) => {
+            let precision = *precision;
+            let scale = *scale;
+            build!(
+                Decimal128Builder::with_capacity(rows.len())
+                    .with_precision_and_scale(precision, scale)?,
+                |v| to_decimal128(v, precision, scale)
+            )
+        }
+        DataType::Binary => build!(BinaryBuilder::new(), |v: &SqlValue| match v {
+            SqlValue::Binary(bytes) => Some(bytes.to_vec()),
+            _ => None,
+        }),
+        DataType::Date32 => build!(Date32Builder::with_capacity(rows.len()), to_date32),
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            build!(
+                Time64NanosecondBuilder::with_capacity(rows.len()),
+                to_time64
+            )
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, None) => build!(
+            TimestampMicrosecondBuilder::with_capacity(rows.len()),
+            to_timestamp
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, Some(_)) => build!(
+            TimestampMicrosecondBuilder::with_capacity(rows.len()).with_timezone(UTC),
+            to_utc_timestamp
+        ),
+        _ => build!(StringBuilder::new(), to_text),
+    };
+    Ok(array)
+}
+
+/// Strip the parameter type wrapper from a value.
+fn unwrap_typed(value: SqlValue) -> SqlValue {
+    match value {
+        SqlValue::Typed(_, inner) => unwrap_typed(*inner),
+        other => other,
+    }
+}
+
+fn to_bool(value: &SqlValue) -> Option<bool> {
+    match value {
+        SqlValue::Bool(b) => Some(*b),
+        _ => to_i64(value).map(|i| i != 0),
+    }
+}
+
+fn to_i64(value: &SqlValue) -> Option<i64> {
+    match value {
+        SqlValue::Bool(b) => Some(i64::from(*b)),
+        SqlValue::TinyInt(i) => Some(i64::from(*i)),
+        SqlValue::SmallInt(i) => Some(i64::from(*i)),
+        SqlValue::Int(i) => Some(i64::from(*i)),
+        SqlValue::BigInt(i) => Some(*i),
+        _ => None,
+    }
+}
+
+fn to_f64(value: &SqlValue) -> Option<f64> {
+    match value {
+        SqlValue::Float(f) => Some(f64::from(*f)),
+        SqlValue::Double(f) => Some(*f),
+        SqlValue::Decimal(d) => rust_decimal::prelude::ToPrimitive::to_f64(d),
+        _ => to_i64(value).map(|i| i as f64),
+    }
+}
+
+fn to_decimal128(value: &SqlValue, precision: u8, scale: i8) -> Option<i128> {
+    let mut decimal = match value {
+        SqlValue::Decimal(d) => *d,
+        // Values that overflow `Decimal` are decoded as floats
+        SqlValue::Float(_) | SqlValue::Double(_) => Decimal::try_from(to_f64(value)?).ok()?,
+        _ => Decimal::from(to_i64(value)?),
+    };
+    decimal.rescale(u32::try_from(scale).ok()?);
+    let mantissa = decimal.mantissa();
+    (mantissa.unsigned_abs() < 10u128.pow(u32::from(precision))).then_some(mantissa)
+}
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default()
+}
+
+fn to_date32(value: &SqlValue) -> Option<i32> {
+    let date = match value {
+        SqlValue::Date(d) => *d,
+        _ => to_naive_datetime(value)?.date(),
+    };
+    i32::try_from(date.signed_duration_since(epoch()).num_days()).ok()
+}
+
+fn to_time64(value: &SqlValue) -> Option<i64> {
+    let time: NaiveTime = match value {
+        SqlValue::Time(t) => *t,
+        _ => to_naive_datetime(value)?.time(),
+    };
+    Some(i64::from(time.num_seconds_from_midnight()) * 1_000_000_000 + i64::from(time.nanosecond()))
+}
+
+fn to_naive_datetime(value: &SqlValue) -> Option<NaiveDateTime> {
+    match value {
+        SqlValue::DateTime(dt) => Some(*dt),
+        SqlValue::ScaledDateTime2(dt) => Some(NaiveDateTime::from(*dt)),
+        SqlValue::Date(d) => d.and_hms_opt(0, 0, 0),
+        _ => None,
+    }
+}
+
+fn to_timestamp(value: &SqlValue) -> Option<i64> {
+    Some(to_naive_datetime(value)?.and_utc().timestamp_micros())
+}
+
+fn to_utc_timestamp(value: &SqlValue) -> Option<i64> {
+    match value {
+        SqlValue::DateTimeOffset(dt) => Some(dt.timestamp_micros()),
+        SqlValue::ScaledDateTimeOffset(dt) => {
+            Some(chrono::DateTime::<chrono::FixedOffset>::from(*dt).timestamp_micros())
+        }
+        _ => to_timestamp(value),
+    }
+}
+
+fn to_text(value: &SqlValue) -> Option<String> {
+    Some(match value {
+        SqlValue::String(s) | SqlValue::Xml(s) => s.clone(),
+        SqlValue::Uuid(u) => u.hyphenated().to_string(),
+        SqlValue::Bool(b) => b.to_string(),
+        SqlValue::TinyInt(i) => i.to_string(),
+        SqlValue::SmallInt(i) => i.to_string(),
+        SqlValue::Int(i) => i.to_string(),
+        SqlValue::BigInt(i) => i.to_string(),
+        SqlValue::Float(f) => f.to_string(),
+        SqlValue::Double(f) => f.to_string(),
+        SqlValue::Decimal(d) => d.to_string(),
+        SqlValue::Date(d) => d.to_string(),
+        SqlValue::Time(t) => t.to_string(),
+        SqlValue::DateTime(dt) => dt.to_string(),
+        SqlValue::DateTimeOffset(dt) => dt.to_rfc3339(),
+        SqlValue::ScaledDateTime2(dt) => NaiveDateTime::from(*dt).to_string(),
+        SqlValue::ScaledDateTimeOffset(dt) => {
+            chrono::DateTime::<chrono::FixedOffset>::from(*dt).to_rfc3339()
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Date32Type, Decimal128Type, Int32Type, Time64NanosecondType, TimestampMicrosecondType,
+        UInt8Type,
+    };
+
+    fn column(name: &str, index: usize, type_name: &str) -> Column {
+        Column::new(name, index, type_name)
+    }
+
+    #[test]
+    fn test_arrow_type_mapping() {
+        assert_eq!(arrow_type(&column("a", 0, "Int4")), DataType::Int32);
+        assert_eq!(arrow_type(&column("a", 0, "BIGINT")), DataType::Int64);
+        assert_eq!(
+            arrow_type(&column("a", 0, "IntN").with_max_length(2)),
+            DataType::Int16
+        );
+        assert_eq!(
+            arrow_type(&column("a", 0, "FloatN").with_max_length(4)),
+            DataType::Float32
+        );
+        assert_eq!(arrow_type(&column("a", 0, "BitN")), DataType::Boolean);
+        assert_eq!(
+            arrow_type(&column("a", 0, "NumericN").with_precision_scale(18, 2)),
+            DataType::Decimal128(18, 2)
+        );
+        assert_eq!(
+            arrow_type(&column("a", 0, "MoneyN").with_max_length(8)),
+            DataType::Decimal128(19, 4)
+        );
+        assert_eq!(arrow_type(&column("a", 0, "NVarChar")), DataType::Utf8);
+        assert_eq!(
+            arrow_type(&column("a", 0, "BigVarBinary")),
+            DataType::Binary
+        );
+        assert_eq!(arrow_type(&column("a", 0, "Guid")), DataType::Utf8);
+        assert_eq!(arrow_type(&column("a", 0, "Date")), DataType::Date32);
+        assert_eq!(
+            arrow_type(&column("a", 0, "DateTime2")),
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        );
+        assert_eq!(
+            arrow_type(&column("a", 0, "DateTimeOffset")),
+            DataType::Timestamp(TimeUnit::Microsecond, Some(UTC.into()))
+        );
+    }
+
+    #[test]
+    fn test_batches_are_chunked() {
+        let columns = vec![column("id", 0, "Int4").with_nullable(false)];
+        let rows: Vec<Row> = (0..5)
+            .map(|i| Row::from_values(columns.clone(), vec![SqlValue::Int(i)]))
+            .collect();
+
+        let batches = ArrowBatches::new(&columns, rows).with_batch_size(2);
+        assert_eq!(batches.size_hint(), (3, Some(3)));
+
+        let batches: Vec<RecordBatch> = batches.collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let ids = batches[1].column(0).as_primitive::<Int32Type>();
+        assert_eq!(ids.values(), &[2, 3]);
+        assert!(!batches[0].schema().field(0).is_nullable());
+    }
+
+    #[test]
+    fn test_empty_result_has_schema() {
+        let columns = vec![column("id", 0, "Int4")];
+        let mut batches = ArrowBatches::new(&columns, Vec::new());
+        assert_eq!(batches.schema().fields().len(), 1);
+        assert!(batches.next().is_none());
+    }
+
+    #[test]
+    fn test_value_conversion() {
+        let columns = vec![
+            column("tiny", 0, "IntN").with_max_length(1),
+            column("price", 1, "DecimalN").with_precision_scale(10, 2),
+            column("name", 2, "NVarChar"),
+            column("day", 3, "Date"),
+            column("at", 4, "Time"),
+            column("created", 5, "DateTime2"),
+            column("seen", 6, "DateTimeOffset"),
+            column("guid", 7, "Guid"),
+        ];
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let time = NaiveTime::from_hms_micro_opt(12, 30, 0, 250).unwrap();
+        let offset = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let seen = date.and_time(time).and_local_timezone(offset).unwrap();
+        let guid = uuid::Uuid::from_u128(1);
+        let rows = vec![
+            Row::from_values(
+                columns.clone(),
+                vec![
+                    SqlValue::TinyInt(7),
+                    SqlValue::Decimal(Decimal::new(12344, 3)),
+                    SqlValue::String("Ann".into()),
+                    SqlValue::Date(date),
+                    SqlValue::Time(time),
+                    SqlValue::DateTime(date.and_time(time)),
+                    SqlValue::DateTimeOffset(seen),
+                    SqlValue::Uuid(guid),
+                ],
+            ),
+            Row::from_values(columns.clone(), vec![SqlValue::Null; 8]),
+        ];
+
+        let batch = ArrowBatches::new(&columns, rows).next().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        assert_eq!(batch.column(0).as_primitive::<UInt8Type>().value(0), 7);
+        // 12.344 is rounded to the column scale
+        assert_eq!(
+            batch.column(1).as_primitive::<Decimal128Type>().value(0),
+            1234
+        );
+        assert_eq!(batch.column(2).as_string::<i32>().value(0), "Ann");
+        assert_eq!(
+            batch
+                .column(3)
+                .as_primitive::<Date32Type>()
+                .value_as_date(0),
+            Some(date)
+        );
+        assert_eq!(
+            batch
+                .column(4)
+                .as_primitive::<Time64NanosecondType>()
+                .value_as_time(0),
+            Some(time)
+        );
+        assert_eq!(
+            batch
+                .column(5)
+                .as_primitive::<TimestampMicrosecondType>()
+                .value_as_datetime(0),
+            Some(date.and_time(time))
+        );
+        // DATETIMEOFFSET is normalized to UTC
+        assert_eq!(
+            batch
+                .column(6)
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(0),
+            seen.timestamp_micros()
+        );
+        assert_eq!(
+            batch.column(7).as_string::<i32>().value(0),
+            guid.hyphenated().to_string()
+        );
+
+        for index in 0..columns.len() {
+            assert!(batch.column(index).is_null(1));
+        }
+    }
+
+    #[test]
+    fn test_value_mismatch_errors() {
+        let columns = vec![column("id", 0, "Int4")];
+        let rows = vec![Row::from_values(
+            columns.clone(),
+            vec![SqlValue::String("x".into())],
+        )];
+        let result = ArrowBatches::new(&columns, rows).next().unwrap();
+        assert!(matches!(result, Err(ArrowError::CastError(_))));
+    }
+
+    #[test]
+    fn test_decimal_overflow_errors() {
+        let columns = vec![column("d", 0, "Decimal").with_precision_scale(3, 0)];
+        let rows = vec![Row::from_values(
+            columns.clone(),
+            vec![SqlValue::Decimal(Decimal::from(1000))],
+        )];
+        assert!(ArrowBatches::new(&columns, rows).next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,377 @@
+//! Always On availability group health.
+//!
+//! [`Client::availability_groups`](crate::Client::availability_groups)
+//! returns an [`AvailabilityGroups`] inspector over the `sys.dm_hadr_*`
+//! views: the role and health of each replica, and the log send and redo
+//! queues of each database replica. Failover tooling typically checks
+//! [`AvailabilityGroups::replicas`] and then waits for the secondaries
+//! with [`AvailabilityGroups::wait_for_secondary_catchup`] before a planned
+//! failover:
+//!
+//! ```rust,ignore
+//! let mut ag = client.availability_groups();
+//! for replica in ag.replicas().await? {
+//!     println!("{} {:?} {:?}", replica.server, replica.role, replica.health);
+//! }
+//! if !ag.wait_for_secondary_catchup(Duration::from_secs(30)).await? {
+//!     for db in ag.databases().await?.iter().filter(|db| !db.is_caught_up()) {
+//!         println!("{} on {} is behind", db.database, db.server);
+//!     }
+//! }
+//! ```
+//!
+//! Only the primary replica sees the state of the other replicas; a
+//! secondary reports only itself. Reading the views needs the `VIEW SERVER
+//! STATE` permission.
+
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::row::Row;
+use crate::state::Ready;
+
+/// Interval between checks in
+/// [`AvailabilityGroups::wait_for_secondary_catchup`].
+const CATCHUP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const REPLICAS_SQL: &str = "\
+SELECT ag.name, ar.replica_server_name, rs.is_local, rs.role_desc, \
+    rs.connected_state_desc, rs.synchronization_health_desc, \
+    ar.availability_mode_desc, ar.failover_mode_desc \
+FROM sys.availability_groups ag \
+JOIN sys.availability_replicas ar ON ar.group_id = ag.group_id \
+LEFT JOIN sys.dm_hadr_availability_replica_states rs ON rs.replica_id = ar.replica_id \
+ORDER BY ag.name, ar.replica_server_name";
+
+const DATABASES_SQL: &str = "\
+SELECT ag.name, ar.replica_server_name, DB_NAME(drs.database_id), drs.is_local, \
+    drs.is_primary_replica, drs.synchronization_state_desc, drs.synchronization_health_desc, \
+    drs.is_suspended, drs.log_send_queue_size, drs.log_send_rate, \
+    drs.redo_queue_size, drs.redo_rate \
+FROM sys.dm_hadr_database_replica_states drs \
+JOIN sys.availability_replicas ar ON ar.replica_id = drs.replica_id \
+JOIN sys.availability_groups ag ON ag.group_id = drs.group_id \
+ORDER BY ag.name, DB_NAME(drs.database_id), ar.replica_server_name";
+
+/// The current role of a replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplicaRole {
+    /// Accepts writes.
+    Primary,
+    /// Receives log from the primary.
+    Secondary,
+    /// Changing roles, or lost contact with the cluster.
+    Resolving,
+}
+
+impl ReplicaRole {
+    fn parse(desc: &str) -> Option<Self> {
+        match desc {
+            "PRIMARY" => Some(Self::Primary),
+            "SECONDARY" => Some(Self::Secondary),
+            "RESOLVING" => Some(Self::Resolving),
+            _ => None,
+        }
+    }
+}
+
+/// Rolled-up synchronization health.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SynchronizationHealth {
+    /// Everything is synchronizing as configured.
+    Healthy,
+    /// Some, but not all, members are healthy.
+    PartiallyHealthy,
+    /// Not synchronizing.
+    NotHealthy,
+}
+
+impl SynchronizationHealth {
+    fn parse(desc: &str) -> Option<Self> {
+        match desc {
+            "HEALTHY" => Some(Self::Healthy),
+            "PARTIALLY_HEALTHY" => Some(Self::PartiallyHealthy),
+            "NOT_HEALTHY" => Some(Self::NotHealthy),
+            _ => None,
+        }
+    }
+}
+
+/// Data movement state of a database replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SynchronizationState {
+    /// Not moving data: suspended, disconnected, or not joined.
+    NotSynchronizing,
+    /// Moving data, but not (yet) committed in step with the primary.
+    Synchronizing,
+    /// Committed in step with the primary (synchronous commit).
+    Synchronized,
+    /// Undoing changes after a failover.
+    Reverting,
+    /// Catching up after a failover.
+    Initializing,
+}
+
+impl SynchronizationState {
+    fn parse(desc: &str) -> Option<Self> {
+        match desc {
+            "NOT SYNCHRONIZING" => Some(Self::NotSynchronizing),
+            "SYNCHRONIZING" => Some(Self::Synchronizing),
+            "SYNCHRONIZED" => Some(Self::Synchronized),
+            "REVERTING" => Some(Self::Reverting),
+            "INITIALIZING" => Some(Self::Initializing),
+            _ => None,
+        }
+    }
+}
+
+/// An availability replica and its state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaState {
+    /// Availability group name.
+    pub availability_group: String,
+    /// Server instance hosting the replica.
+    pub server: String,
+    /// Whether this is the replica the client is connected to.
+    pub is_local: bool,
+    /// Current role, or `None` if the state is not known here.
+    pub role: Option<ReplicaRole>,
+    /// Whether the replica is connected to the primary.
+    pub connected: bool,
+    /// Rolled-up health of the replica's databases.
+    pub health: Option<SynchronizationHealth>,
+    /// Whether commits wait for this replica.
+    pub synchronous_commit: bool,
+    /// Whether the replica is a target for automatic failover.
+    pub automatic_failover: bool,
+}
+
+/// The state of one database on one replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseReplicaState {
+    /// Availability group name.
+    pub availability_group: String,
+    /// Server instance hosting the replica.
+    pub server: String,
+    /// Database name.
+    pub database: String,
+    /// Whether this is the replica the client is connected to.
+    pub is_local: bool,
+    /// Whether this is the primary copy of the database.
+    pub is_primary: bool,
+    /// Data movement state.
+    pub synchronization_state: Option<SynchronizationState>,
+    /// Synchronization health.
+    pub health: Option<SynchronizationHealth>,
+    /// Whether data movement is suspended.
+    pub is_suspended: bool,
+    /// Log not yet sent to the secondary, in KB.
+    pub log_send_queue_kb: Option<i64>,
+    /// Rate log is sent to the secondary, in KB/s.
+    pub log_send_rate_kb: Option<i64>,
+    /// Log received but not yet redone on the secondary, in KB.
+    pub redo_queue_kb: Option<i64>,
+    /// Rate log is redone on the secondary, in KB/s.
+    pub redo_rate_kb: Option<i64>,
+}
+
+impl DatabaseReplicaState {
+    /// Check whether a secondary has received and redone all log.
+    ///
+    /// The primary copy is always caught up. A secondary whose queue sizes
+    /// are unknown, for example because it is disconnected, is not.
+    #[must_use]
+    pub fn is_caught_up(&self) -> bool {
+        self.is_primary
+            || (!self.is_suspended
+                && self.log_send_queue_kb == Some(0)
+                && self.redo_queue_kb == Some(0))
+    }
+
+    /// Estimate how long the secondary needs to redo its queue, from the
+    /// current redo rate.
+    #[must_use]
+    pub fn estimated_redo_time(&self) -> Option<Duration> {
+        let queue = u64::try_from(self.redo_queue_kb?).ok()?;
+        match u64::try_from(self.redo_rate_kb?).ok()? {
+            0 if queue == 0 => Some(Duration::ZERO),
+            0 => None,
+            rate => Some(Duration::from_secs_f64(queue as f64 / rate as f64)),
+        }
+    }
+}
+
+/// Reads availability group state through a client.
+///
+/// Created with
+/// [`Client::availability_groups`](crate::Client::availability_groups).
+pub struct AvailabilityGroups<'a> {
+    client: &'a mut Client<Ready>,
+}
+
+impl<'a> AvailabilityGroups<'a> {
+    pub(crate) fn new(client: &'a mut Client<Ready>) -> Self {
+        Self { client }
+    }
+
+    /// List the replicas of every availability group on the instance.
+    ///
+    /// Returns an empty list when Always On is not enabled.
+    pub async fn replicas(&mut self) -> Result<Vec<ReplicaState>> {
+        let rows = self.client.fetch_rows(REPLICAS_SQL, &[]).await?;
+        rows.iter().map(replica_from_row).collect()
+    }
+
+    /// List the database replicas visible from this instance.
+    pub async fn databases(&mut self) -> Result<Vec<DatabaseReplicaState>> {
+        let rows = self.client.fetch_rows(DATABASES_SQL, &[]).await?;
+        rows.iter().map(database_from_row).collect()
+    }
+
+    /// Get the local replica's role in an availability group, or `None`
+    /// if the group is not hosted here.
+    pub async fn local_role(&mut self, availability_group: &str) -> Result<Option<ReplicaRole>> {
+        Ok(self
+            .replicas()
+            .await?
+            .into_iter()
+            .find(|r| {
+                r.is_local
+                    && r.availability_group
+                        .eq_ignore_ascii_case(availability_group)
+            })
+            .and_then(|r| r.role))
+    }
+
+    /// Wait until every secondary database replica has received and redone
+    /// all log, checking twice a second.
+    ///
+    /// Returns `true` once all are caught up (see
+    /// [`DatabaseReplicaState::is_caught_up`]), or `false` if some are still
+    /// behind when `timeout` expires. Run on the primary replica; writes
+    /// made meanwhile keep the queues from draining.
+    pub async fn wait_for_secondary_catchup(&mut self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let databases = self.databases().await?;
+            let behind = databases.iter().filter(|db| !db.is_caught_up()).count();
+            if behind == 0 {
+                return Ok(true);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                tracing::warn!(behind, "secondary replicas did not catch up in time");
+                return Ok(false);
+            }
+            tracing::debug!(behind, "waiting for secondary replicas to catch up");
+            tokio::time::sleep(CATCHUP_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for AvailabilityGroups<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AvailabilityGroups").finish_non_exhaustive()
+    }
+}
+
+fn replica_from_row(row: &Row) -> Result<ReplicaState> {
+    let role: Option<String> = row.get(3)?;
+    let connected: Option<String> = row.get(4)?;
+    let health: Option<String> = row.get(5)?;
+    let mode: String = row.get(6)?;
+    let failover: String = row.get(7)?;
+    Ok(ReplicaState {
+        availability_group: row.get(0)?,
+        server: row.get(1)?,
+        is_local: row.get::<Option<bool>>(2)?.unwrap_or(false),
+        role: role.as_deref().and_then(ReplicaRole::parse),
+        connected: connected.as_deref() == Some("CONNECTED"),
+        health: health.as_deref().and_then(SynchronizationHealth::parse),
+        synchronous_commit: mode == "SYNCHRONOUS_COMMIT",
+        automatic_failover: failover == "AUTOMATIC",
+    })
+}
+
+fn database_from_row(row: &Row) -> Result<DatabaseReplicaState> {
+    let state: Option<String> = row.get(5)?;
+    let health: Option<String> = row.get(6)?;
+    Ok(DatabaseReplicaState {
+        availability_group: row.get(0)?,
+        server: row.get(1)?,
+        database: row.get::<Option<String>>(2)?.unwrap_or_default(),
+        is_local: row.get(3)?,
+        is_primary: row.get::<Option<bool>>(4)?.unwrap_or(false),
+        synchronization_state: state.as_deref().and_then(SynchronizationState::parse),
+        health: health.as_deref().and_then(SynchronizationHealth::parse),
+        is_suspended: row.get::<Option<bool>>(7)?.unwrap_or(false),
+        log_send_queue_kb: row.get(8)?,
+        log_send_rate_kb: row.get(9)?,
+        redo_queue_kb: row.get(10)?,
+        redo_rate_kb: row.get(11)?,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn secondary(log_send: Option<i64>, redo: Option<i64>) -> DatabaseReplicaState {
+        DatabaseReplicaState {
+            availability_group: "ag1".into(),
+            server: "sql02".into(),
+            database: "Sales".into(),
+            is_local: false,
+            is_primary: false,
+            synchronization_state: Some(SynchronizationState::Synchronizing),
+            health: Some(SynchronizationHealth::Healthy),
+            is_suspended: false,
+            log_send_queue_kb: log_send,
+            log_send_rate_kb: Some(100),
+            redo_queue_kb: redo,
+            redo_rate_kb: Some(200),
+        }
+    }
+
+    #[test]
+    fn test_is_caught_up() {
+        assert!(secondary(Some(0), Some(0)).is_caught_up());
+        assert!(!secondary(Some(12), Some(0)).is_caught_up());
+        assert!(!secondary(Some(0), Some(40)).is_caught_up());
+        assert!(!secondary(None, None).is_caught_up());
+
+        let primary = DatabaseReplicaState {
+            is_primary: true,
+            ..secondary(None, None)
+        };
+        assert!(primary.is_caught_up());
+    }
+
+    #[test]
+    fn test_estimated_redo_time() {
+        assert_eq!(
+            secondary(Some(0), Some(400)).estimated_redo_time(),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(secondary(Some(0), None).estimated_redo_time(), None);
+    }
+
+    #[test]
+    fn test_parse_states() {
+        assert_eq!(ReplicaRole::parse("PRIMARY"), Some(ReplicaRole::Primary));
+        assert_eq!(
+            SynchronizationState::parse("NOT SYNCHRONIZING"),
+            Some(SynchronizationState::NotSynchronizing)
+        );
+        assert_eq!(
+            SynchronizationHealth::parse("PARTIALLY_HEALTHY"),
+            Some(SynchronizationHealth::PartiallyHealthy)
+        );
+        assert_eq!(ReplicaRole::parse(""), None);
+    }
+}
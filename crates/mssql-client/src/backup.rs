@@ -0,0 +1,496 @@
+//! Database backup and restore with progress reporting.
+//!
+//! [`Client::backup_database`](crate::Client::backup_database) and
+//! [`Client::restore_database`](crate::Client::restore_database) start a
+//! `BACKUP DATABASE` or `RESTORE DATABASE` and return a
+//! [`BackupOperation`]. With `WITH STATS` the server reports progress as
+//! "N percent processed." INFO messages while the statement runs; the
+//! operation reads the response packet by packet so each one is available
+//! as soon as it arrives.
+//!
+//! ```rust,ignore
+//! use mssql_client::BackupOptions;
+//!
+//! let options = BackupOptions::new().copy_only(true).compression(true);
+//! let mut backup = client
+//!     .backup_database("Sales", "/var/opt/mssql/backup/Sales.bak", &options)
+//!     .await?;
+//! while let Some(progress) = backup.next_progress().await? {
+//!     println!("{}%", progress.percent);
+//! }
+//! let summary = backup.wait().await?;
+//! println!("{:?} pages in {:?}", summary.pages, summary.duration);
+//! ```
+//!
+//! A target starting with `https://` (Azure Blob Storage) or `s3://` is
+//! written with `TO URL`, anything else with `TO DISK`. URL targets need a
+//! server credential named after the container URL (shared access
+//! signature) or one passed with [`BackupOptions::credential`] (storage
+//! account key).
+//!
+//! A backup can run for hours, so no command timeout applies. The
+//! operation must be read to the end: dropping it early closes the
+//! connection, since the rest of the response can no longer be read.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bytes::BytesMut;
+
+use crate::client::{Client, validated_identifier};
+use crate::error::{DatabaseError, Result};
+use crate::message::ServerMessage;
+use crate::state::Ready;
+
+/// Message number of "N percent processed."
+const PERCENT_PROCESSED: i32 = 3211;
+
+/// Message number of "BACKUP/RESTORE DATABASE successfully processed N
+/// pages in S seconds (R MB/sec)."
+const SUCCESSFULLY_PROCESSED: i32 = 3014;
+
+/// Default progress reporting interval, in percent.
+const DEFAULT_STATS: u8 = 10;
+
+/// Options for [`Client::backup_database`](crate::Client::backup_database).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// Take a copy-only backup that does not affect the backup chain.
+    pub copy_only: bool,
+    /// Progress reporting interval in percent; 0 reports no progress.
+    pub stats: u8,
+    /// Compress the backup, or `None` for the server default.
+    pub compression: Option<bool>,
+    /// Verify page checksums and add a backup checksum.
+    pub checksum: bool,
+    /// Overwrite existing backup sets in the target instead of appending.
+    pub init: bool,
+    /// Credential for a URL target.
+    pub credential: Option<String>,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        Self {
+            copy_only: false,
+            stats: DEFAULT_STATS,
+            compression: None,
+            checksum: false,
+            init: false,
+            credential: None,
+        }
+    }
+}
+
+impl BackupOptions {
+    /// Create default options: a full backup reporting every 10 percent.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a copy-only backup.
+    #[must_use]
+    pub fn copy_only(mut self, copy_only: bool) -> Self {
+        self.copy_only = copy_only;
+        self
+    }
+
+    /// Set the progress reporting interval in percent (`WITH STATS`).
+    ///
+    /// Values above 100 are treated as 100; 0 disables progress messages.
+    #[must_use]
+    pub fn stats(mut self, percent: u8) -> Self {
+        self.stats = percent.min(100);
+        self
+    }
+
+    /// Enable or disable backup compression.
+    #[must_use]
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Verify page checksums while backing up.
+    #[must_use]
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Overwrite existing backup sets in the target.
+    #[must_use]
+    pub fn init(mut self, init: bool) -> Self {
+        self.init = init;
+        self
+    }
+
+    /// Set the server credential used to access a URL target.
+    #[must_use]
+    pub fn credential(mut self, credential: impl Into<String>) -> Self {
+        self.credential = Some(credential.into());
+        self
+    }
+}
+
+/// Options for [`Client::restore_database`](crate::Client::restore_database).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreOptions {
+    /// Overwrite an existing database.
+    pub replace: bool,
+    /// Bring the database online after the restore. Set to `false`
+    /// (`NORECOVERY`) to restore further log backups.
+    pub recovery: bool,
+    /// Progress reporting interval in percent; 0 reports no progress.
+    pub stats: u8,
+    /// Logical file names and the physical paths to restore them to.
+    pub moves: Vec<(String, String)>,
+    /// Credential for a URL source.
+    pub credential: Option<String>,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            replace: false,
+            recovery: true,
+            stats: DEFAULT_STATS,
+            moves: Vec::new(),
+            credential: None,
+        }
+    }
+}
+
+impl RestoreOptions {
+    /// Create default options: restore with recovery, reporting every 10
+    /// percent.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overwrite an existing database.
+    #[must_use]
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Leave the database restoring (`NORECOVERY`) so log backups can be
+    /// applied.
+    #[must_use]
+    pub fn no_recovery(mut self) -> Self {
+        self.recovery = false;
+        self
+    }
+
+    /// Set the progress reporting interval in percent (`WITH STATS`).
+    ///
+    /// Values above 100 are treated as 100; 0 disables progress messages.
+    #[must_use]
+    pub fn stats(mut self, percent: u8) -> Self {
+        self.stats = percent.min(100);
+        self
+    }
+
+    /// Restore the file with logical name `logical` to `physical`.
+    #[must_use]
+    pub fn move_file(mut self, logical: impl Into<String>, physical: impl Into<String>) -> Self {
+        self.moves.push((logical.into(), physical.into()));
+        self
+    }
+
+    /// Set the server credential used to access a URL source.
+    #[must_use]
+    pub fn credential(mut self, credential: impl Into<String>) -> Self {
+        self.credential = Some(credential.into());
+        self
+    }
+}
+
+/// A progress report from a running backup or restore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupProgress {
+    /// Percent of the operation completed.
+    pub percent: u8,
+    /// The server's message, e.g. `30 percent processed.`
+    pub message: String,
+}
+
+/// Outcome of a completed backup or restore.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackupSummary {
+    /// Number of pages processed.
+    pub pages: Option<u64>,
+    /// Time the server spent.
+    pub duration: Option<Duration>,
+    /// Throughput in MB/s.
+    pub megabytes_per_second: Option<f64>,
+    /// The other informational messages, such as the pages processed per
+    /// file.
+    pub messages: Vec<ServerMessage>,
+}
+
+/// Token data of a response being read packet by packet.
+#[derive(Debug, Default)]
+pub(crate) struct PartialResponse {
+    /// Bytes of a token split across packets.
+    pub(crate) buffer: BytesMut,
+    /// First server error, returned once the response is complete.
+    pub(crate) error: Option<DatabaseError>,
+    /// Whether the last packet was read.
+    pub(crate) complete: bool,
+}
+
+/// A running backup or restore.
+///
+/// Created by [`Client::backup_database`](crate::Client::backup_database)
+/// and [`Client::restore_database`](crate::Client::restore_database). See
+/// the [module documentation](self).
+pub struct BackupOperation<'a> {
+    client: &'a mut Client<Ready>,
+    response: PartialResponse,
+    pending: VecDeque<ServerMessage>,
+    summary: BackupSummary,
+}
+
+impl<'a> BackupOperation<'a> {
+    pub(crate) fn new(client: &'a mut Client<Ready>) -> Self {
+        Self {
+            client,
+            response: PartialResponse::default(),
+            pending: VecDeque::new(),
+            summary: BackupSummary::default(),
+        }
+    }
+
+    /// Wait for the next progress report.
+    ///
+    /// Returns `None` once the operation has finished; call
+    /// [`wait`](Self::wait) to get its summary. A failed backup or restore
+    /// returns its server error.
+    pub async fn next_progress(&mut self) -> Result<Option<BackupProgress>> {
+        loop {
+            while let Some(message) = self.pending.pop_front() {
+                if message.number == PERCENT_PROCESSED {
+                    if let Some(percent) = numbers(&message.message).first() {
+                        return Ok(Some(BackupProgress {
+                            percent: percent.min(100.0) as u8,
+                            message: message.message,
+                        }));
+                    }
+                }
+                self.record(message);
+            }
+            if self.response.complete {
+                return Ok(None);
+            }
+            let messages = self.client.read_response_packet(&mut self.response).await?;
+            self.pending.extend(messages);
+        }
+    }
+
+    /// Wait for the operation to finish, skipping any remaining progress
+    /// reports.
+    pub async fn wait(mut self) -> Result<BackupSummary> {
+        while self.next_progress().await?.is_some() {}
+        Ok(std::mem::take(&mut self.summary))
+    }
+
+    fn record(&mut self, message: ServerMessage) {
+        if message.number == SUCCESSFULLY_PROCESSED {
+            let numbers = numbers(&message.message);
+            self.summary.pages = numbers.first().map(|&n| n as u64);
+            self.summary.duration = numbers
+                .get(1)
+                .and_then(|&s| Duration::try_from_secs_f64(s).ok());
+            self.summary.megabytes_per_second = numbers.get(2).copied();
+        }
+        self.summary.messages.push(message);
+    }
+}
+
+impl Drop for BackupOperation<'_> {
+    fn drop(&mut self) {
+        if !self.response.complete {
+            self.client
+                .abandon_connection("backup or restore dropped before completion");
+        }
+    }
+}
+
+impl std::fmt::Debug for BackupOperation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackupOperation")
+            .field("complete", &self.response.complete)
+            .field("summary", &self.summary)
+            .finish()
+    }
+}
+
+/// Extract the numbers in a message, accepting `.` or `,` as the decimal
+/// separator.
+fn numbers(message: &str) -> Vec<f64> {
+    message
+        .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .map(|part| part.trim_matches(|c| c == '.' || c == ','))
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.replace(',', ".").parse().ok())
+        .collect()
+}
+
+/// Quote a string as an `N'...'` literal.
+fn literal(value: &str) -> String {
+    format!("N'{}'", value.replace('\'', "''"))
+}
+
+/// Build the `TO`/`FROM` clause for a path or URL.
+fn device(target: &str) -> String {
+    let lower = target.to_ascii_lowercase();
+    let kind = if lower.starts_with("https://") || lower.starts_with("s3://") {
+        "URL"
+    } else {
+        "DISK"
+    };
+    format!("{kind} = {}", literal(target))
+}
+
+fn with_clause(options: Vec<String>) -> String {
+    if options.is_empty() {
+        String::new()
+    } else {
+        format!(" WITH {}", options.join(", "))
+    }
+}
+
+/// Build a `BACKUP DATABASE` statement.
+pub(crate) fn backup_sql(database: &str, target: &str, options: &BackupOptions) -> Result<String> {
+    let mut with = Vec::new();
+    if options.copy_only {
+        with.push("COPY_ONLY".to_string());
+    }
+    match options.compression {
+        Some(true) => with.push("COMPRESSION".to_string()),
+        Some(false) => with.push("NO_COMPRESSION".to_string()),
+        None => {}
+    }
+    if options.checksum {
+        with.push("CHECKSUM".to_string());
+    }
+    if options.init {
+        with.push("INIT".to_string());
+    }
+    if let Some(credential) = &options.credential {
+        with.push(format!("CREDENTIAL = {}", literal(credential)));
+    }
+    if options.stats > 0 {
+        with.push(format!("STATS = {}", options.stats));
+    }
+    Ok(format!(
+        "BACKUP DATABASE {} TO {}{}",
+        validated_identifier(database)?,
+        device(target),
+        with_clause(with)
+    ))
+}
+
+/// Build a `RESTORE DATABASE` statement.
+pub(crate) fn restore_sql(
+    database: &str,
+    source: &str,
+    options: &RestoreOptions,
+) -> Result<String> {
+    let mut with = Vec::new();
+    if options.replace {
+        with.push("REPLACE".to_string());
+    }
+    with.push(
+        if options.recovery {
+            "RECOVERY"
+        } else {
+            "NORECOVERY"
+        }
+        .to_string(),
+    );
+    for (logical, physical) in &options.moves {
+        with.push(format!(
+            "MOVE {} TO {}",
+            literal(logical),
+            literal(physical)
+        ));
+    }
+    if let Some(credential) = &options.credential {
+        with.push(format!("CREDENTIAL = {}", literal(credential)));
+    }
+    if options.stats > 0 {
+        with.push(format!("STATS = {}", options.stats));
+    }
+    Ok(format!(
+        "RESTORE DATABASE {} FROM {}{}",
+        validated_identifier(database)?,
+        device(source),
+        with_clause(with)
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_sql() {
+        let sql = backup_sql(
+            "Sales",
+            "/backup/O'Brien.bak",
+            &BackupOptions::new().copy_only(true).compression(true),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "BACKUP DATABASE [Sales] TO DISK = N'/backup/O''Brien.bak' \
+             WITH COPY_ONLY, COMPRESSION, STATS = 10"
+        );
+
+        let sql = backup_sql(
+            "Sales",
+            "https://acct.blob.core.windows.net/backups/Sales.bak",
+            &BackupOptions::new().stats(0),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "BACKUP DATABASE [Sales] TO URL = \
+             N'https://acct.blob.core.windows.net/backups/Sales.bak'"
+        );
+
+        assert!(backup_sql("Sales]; DROP TABLE x", "a.bak", &BackupOptions::new()).is_err());
+    }
+
+    #[test]
+    fn test_restore_sql() {
+        let options = RestoreOptions::new()
+            .replace(true)
+            .no_recovery()
+            .move_file("Sales", "/data/Sales2.mdf")
+            .stats(5);
+        assert_eq!(
+            restore_sql("Sales2", "/backup/Sales.bak", &options).unwrap(),
+            "RESTORE DATABASE [Sales2] FROM DISK = N'/backup/Sales.bak' \
+             WITH REPLACE, NORECOVERY, MOVE N'Sales' TO N'/data/Sales2.mdf', STATS = 5"
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(numbers("30 percent processed."), vec![30.0]);
+        assert_eq!(
+            numbers(
+                "BACKUP DATABASE successfully processed 465 pages in 0.057 seconds (63.661 MB/sec)."
+            ),
+            vec![465.0, 0.057, 63.661]
+        );
+        assert_eq!(numbers("465 Seiten in 0,057 Sekunden"), vec![465.0, 0.057]);
+    }
+}
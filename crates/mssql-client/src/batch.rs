@@ -0,0 +1,339 @@
+//! Batched statement execution.
+//!
+//! A [`Batch`] queues several statements, each with its own parameters, and
+//! sends them to the server in a single round trip. This cuts latency for
+//! chatty write workloads where every statement would otherwise wait for
+//! the previous one to complete.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! let results = client
+//!     .batch()
+//!     .add("UPDATE accounts SET balance = balance - @p1 WHERE id = @p2", &[&100, &1])
+//!     .add("UPDATE accounts SET balance = balance + @p1 WHERE id = @p2", &[&100, &2])
+//!     .add("INSERT INTO audit (message) VALUES (@p1)", &[&"transfer"])
+//!     .execute()
+//!     .await?;
+//!
+//! for (i, result) in results.iter().enumerate() {
+//!     match result {
+//!         Ok(r) => println!("statement {i}: {} rows", r.rows_affected),
+//!         Err(e) => println!("statement {i} failed: {e}"),
+//!     }
+//! }
+//! ```
+//!
+//! ## Modes
+//!
+//! - [`BatchMode::Rpc`] (default) sends every statement as its own
+//!   `sp_executesql` call, all packed into one RPC message. Each call is
+//!   executed independently, so an error in one statement does not prevent
+//!   the others from running.
+//! - [`BatchMode::SqlBatch`] joins parameterless statements into a single SQL
+//!   batch. Results are matched to statements by the order of their DONE
+//!   tokens, so each statement should be a single DML or DDL statement;
+//!   control-of-flow statements and `DECLARE` shift the mapping.
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::state::ConnectionState;
+use crate::stream::ExecuteResult;
+
+/// How the statements of a [`Batch`] are sent to the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BatchMode {
+    /// Send each statement as a separate RPC call within one RPC message.
+    #[default]
+    Rpc,
+    /// Join the statements into a single SQL batch.
+    ///
+    /// Statements may not have parameters in this mode.
+    SqlBatch,
+}
+
+/// A statement queued in a [`Batch`].
+pub(crate) struct BatchStatement<'a> {
+    pub(crate) sql: String,
+    pub(crate) params: Vec<&'a (dyn crate::ToSql + Sync)>,
+}
+
+/// A builder for statements sent to the server in a single round trip.
+///
+/// Created with [`Client::batch`](crate::Client::batch).
+#[must_use = "a batch does nothing until `execute` is called"]
+pub struct Batch<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+    statements: Vec<BatchStatement<'a>>,
+    mode: BatchMode,
+}
+
+impl<'a, S: ConnectionState> Batch<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self {
+            client,
+            statements: Vec::new(),
+            mode: BatchMode::default(),
+        }
+    }
+
+    /// Queue a statement with its parameters.
+    ///
+    /// Parameters are referenced as `@p1`, `@p2`, etc. and are independent
+    /// of the other statements in the batch.
+    pub fn add(mut self, sql: impl Into<String>, params: &[&'a (dyn crate::ToSql + Sync)]) -> Self {
+        self.statements.push(BatchStatement {
+            sql: sql.into(),
+            params: params.to_vec(),
+        });
+        self
+    }
+
+    /// Set how the statements are sent to the server.
+    pub fn mode(mut self, mode: BatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Get the number of queued statements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Check if no statements have been queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Send the queued statements and wait for all of their results.
+    ///
+    /// The returned error covers failures of the batch as a whole, such as
+    /// a lost connection or parameters used with [`BatchMode::SqlBatch`].
+    /// Errors raised by individual statements are reported in the
+    /// corresponding entry of the [`BatchResult`].
+    pub async fn execute(self) -> Result<BatchResult> {
+        if self.statements.is_empty() {
+            return Ok(BatchResult::default());
+        }
+        self.client.execute_batch(&self.statements, self.mode).await
+    }
+}
+
+impl<S: ConnectionState> std::fmt::Debug for Batch<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch")
+            .field("statements", &self.statements.len())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// Per-statement results of an executed [`Batch`].
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    results: Vec<Result<ExecuteResult>>,
+}
+
+impl BatchResult {
+    pub(crate) fn new(results: Vec<Result<ExecuteResult>>) -> Self {
+        Self { results }
+    }
+
+    /// Get the number of statement results.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Check if the batch had no statements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Get the result of the statement at `index`.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Result<ExecuteResult>> {
+        self.results.get(index)
+    }
+
+    /// Iterate over the statement results in the order they were queued.
+    pub fn iter(&self) -> impl Iterator<Item = &Result<ExecuteResult>> {
+        self.results.iter()
+    }
+
+    /// Check if every statement succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// Iterate over the failed statements as `(index, error)` pairs.
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &Error)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.as_ref().err().map(|e| (i, e)))
+    }
+
+    /// Get the total number of rows affected by the successful statements.
+    #[must_use]
+    pub fn total_rows_affected(&self) -> u64 {
+        self.results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|r| r.rows_affected)
+            .sum()
+    }
+
+    /// Consume the batch result, returning the per-statement results.
+    #[must_use]
+    pub fn into_results(self) -> Vec<Result<ExecuteResult>> {
+        self.results
+    }
+}
+
+impl IntoIterator for BatchResult {
+    type Item = Result<ExecuteResult>;
+    type IntoIter = std::vec::IntoIter<Result<ExecuteResult>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+/// Collects the results of each statement while a batch response is parsed.
+pub(crate) struct BatchCollector {
+    expected: usize,
+    results: Vec<Result<ExecuteResult>>,
+    current: ExecuteResult,
+    error: Option<Error>,
+}
+
+impl BatchCollector {
+    pub(crate) fn new(expected: usize) -> Self {
+        Self {
+            expected,
+            results: Vec::with_capacity(expected),
+            current: ExecuteResult::new(0),
+            error: None,
+        }
+    }
+
+    /// Record a row count for the statement in progress.
+    pub(crate) fn record_done(&mut self, row_count: u64, count_valid: bool) {
+        self.current.record_done(row_count, count_valid);
+    }
+
+    /// Record an error raised by the statement in progress.
+    ///
+    /// Only the first error is kept; later ones are usually follow-ups such
+    /// as "The statement has been terminated."
+    pub(crate) fn record_error(&mut self, error: Error) {
+        self.error.get_or_insert(error);
+    }
+
+    /// Finish the statement in progress.
+    pub(crate) fn finish_statement(&mut self, failed: bool) {
+        let result = std::mem::replace(&mut self.current, ExecuteResult::new(0));
+        let outcome = match self.error.take() {
+            Some(error) => Err(error),
+            None if failed => Err(Error::Query("execution failed".to_string())),
+            None => Ok(result),
+        };
+        self.results.push(outcome);
+    }
+
+    /// Finish the batch, marking statements the server never reached as failed.
+    pub(crate) fn finish(mut self) -> BatchResult {
+        if let Some(error) = self.error.take() {
+            // An error raised after the last DONE belongs to the next statement
+            self.results.push(Err(error));
+        }
+        while self.results.len() < self.expected {
+            self.results.push(Err(Error::Query(
+                "statement was not executed because the batch was aborted".to_string(),
+            )));
+        }
+        BatchResult::new(self.results)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collector_per_statement_results() {
+        let mut collector = BatchCollector::new(3);
+
+        collector.record_done(2, true);
+        collector.finish_statement(false);
+
+        collector.record_error(Error::Query("constraint violation".into()));
+        collector.record_error(Error::Query("statement terminated".into()));
+        collector.finish_statement(true);
+
+        collector.record_done(1, true);
+        collector.record_done(4, true);
+        collector.finish_statement(false);
+
+        let result = collector.finish();
+        assert_eq!(result.len(), 3);
+        assert!(!result.is_success());
+        assert_eq!(result.total_rows_affected(), 7);
+        assert_eq!(result.get(2).unwrap().as_ref().unwrap().row_counts.len(), 2);
+
+        let errors: Vec<_> = result.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 1);
+        assert!(errors[0].1.to_string().contains("constraint violation"));
+    }
+
+    #[test]
+    fn test_collector_failed_without_error_token() {
+        let mut collector = BatchCollector::new(1);
+        collector.finish_statement(true);
+
+        let result = collector.finish();
+        assert!(result.get(0).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_collector_aborted_batch() {
+        let mut collector = BatchCollector::new(3);
+        collector.record_done(1, true);
+        collector.finish_statement(false);
+        collector.record_error(Error::Query("batch aborted".into()));
+
+        let results = collector.finish().into_results();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(
+            results[1]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("batch aborted")
+        );
+        assert!(
+            results[2]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("not executed")
+        );
+    }
+
+    #[test]
+    fn test_empty_batch_result() {
+        let result = BatchResult::default();
+        assert!(result.is_empty());
+        assert!(result.is_success());
+        assert_eq!(result.total_rows_affected(), 0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Lock contention diagnostics.
+//!
+//! [`Client::current_blocking_tree`](crate::Client::current_blocking_tree)
+//! reads `sys.dm_exec_requests`, `sys.dm_exec_sessions` and
+//! `sys.dm_tran_locks` and arranges the sessions involved in blocking into
+//! chains rooted at the head blockers.
+//! [`Client::recent_deadlocks`](crate::Client::recent_deadlocks) reads the
+//! deadlock graphs recorded by the built-in `system_health` Extended Events
+//! session.
+//!
+//! Both need the `VIEW SERVER STATE` permission (`VIEW SERVER PERFORMANCE
+//! STATE` on SQL Server 2022). `system_health` is not available on Azure
+//! SQL Database.
+//!
+//! ```rust,ignore
+//! for chain in client.current_blocking_tree().await? {
+//!     println!(
+//!         "session {} blocks {} sessions",
+//!         chain.session.session_id,
+//!         chain.blocked_count()
+//!     );
+//! }
+//!
+//! for deadlock in client.recent_deadlocks(10).await? {
+//!     println!("{}: {}", deadlock.timestamp, deadlock.graph);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::row::Row;
+
+/// Sessions that are blocked or block others, with the lock they wait for.
+pub(crate) const BLOCKING_SQL: &str = "\
+SELECT s.session_id, ISNULL(r.blocking_session_id, 0), s.login_name, s.host_name, \
+    s.program_name, DB_NAME(COALESCE(r.database_id, s.database_id)), \
+    COALESCE(r.status, s.status), r.command, r.wait_type, r.wait_time, r.wait_resource, \
+    wl.resource_type, wl.request_mode, s.open_transaction_count, \
+    COALESCE(rt.text, ct.text) \
+FROM sys.dm_exec_sessions s \
+LEFT JOIN sys.dm_exec_requests r ON r.session_id = s.session_id \
+LEFT JOIN sys.dm_exec_connections c ON c.session_id = s.session_id \
+OUTER APPLY sys.dm_exec_sql_text(r.sql_handle) rt \
+OUTER APPLY sys.dm_exec_sql_text(c.most_recent_sql_handle) ct \
+OUTER APPLY ( \
+    SELECT TOP (1) l.resource_type, l.request_mode FROM sys.dm_tran_locks l \
+    WHERE l.request_session_id = s.session_id AND l.request_status = 'WAIT' \
+) wl \
+WHERE r.blocking_session_id > 0 \
+    OR s.session_id IN (SELECT blocking_session_id FROM sys.dm_exec_requests \
+        WHERE blocking_session_id > 0) \
+ORDER BY s.session_id";
+
+/// Deadlock graphs from the `system_health` event files, newest first.
+pub(crate) const DEADLOCKS_SQL: &str = "\
+SELECT TOP (@p1) CONVERT(NVARCHAR(33), d.ts, 126), CAST(d.graph AS NVARCHAR(MAX)) \
+FROM ( \
+    SELECT x.event_xml.value('(event/@timestamp)[1]', 'datetime2') AS ts, \
+        x.event_xml.query('(event/data/value/deadlock)[1]') AS graph \
+    FROM sys.fn_xe_file_target_read_file(N'system_health*.xel', NULL, NULL, NULL) f \
+    CROSS APPLY (SELECT CAST(f.event_data AS XML) AS event_xml) x \
+    WHERE f.object_name = N'xml_deadlock_report' \
+) d \
+ORDER BY d.ts DESC";
+
+/// A session taking part in blocking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BlockingSession {
+    /// Session ID (`@@SPID`).
+    pub session_id: i16,
+    /// Session this one waits for, or 0 for a head blocker.
+    pub blocking_session_id: i16,
+    /// Login name.
+    pub login_name: String,
+    /// Client host name.
+    pub host_name: Option<String>,
+    /// Client program name.
+    pub program_name: Option<String>,
+    /// Database of the request, or of the session when idle.
+    pub database: Option<String>,
+    /// Request status (`suspended`, `running`), or session status
+    /// (`sleeping`) when no request is active.
+    pub status: String,
+    /// Command of the active request, e.g. `UPDATE`.
+    pub command: Option<String>,
+    /// Wait type, e.g. `LCK_M_X`.
+    pub wait_type: Option<String>,
+    /// Time spent in the current wait.
+    pub wait_time: Duration,
+    /// Resource waited for, e.g. `KEY: 5:72057594043170816 (8194443284a0)`.
+    pub wait_resource: Option<String>,
+    /// Type of the lock waited for, e.g. `KEY` or `OBJECT`.
+    pub lock_resource_type: Option<String>,
+    /// Mode of the lock waited for, e.g. `X` or `S`.
+    pub lock_mode: Option<String>,
+    /// Number of open transactions.
+    pub open_transactions: i32,
+    /// Text of the running batch, or the last batch of an idle session.
+    pub sql_text: Option<String>,
+}
+
+/// A session and the sessions waiting on it, recursively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockingNode {
+    /// The blocking session.
+    pub session: BlockingSession,
+    /// Sessions blocked directly by this one.
+    pub blocked: Vec<BlockingNode>,
+}
+
+impl BlockingNode {
+    /// Count the sessions blocked directly or indirectly by this one.
+    #[must_use]
+    pub fn blocked_count(&self) -> usize {
+        self.blocked
+            .iter()
+            .map(|node| 1 + node.blocked_count())
+            .sum()
+    }
+
+    /// Get the length of the longest wait chain below this session.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.blocked
+            .iter()
+            .map(|node| 1 + node.depth())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A deadlock recorded by the `system_health` session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlockReport {
+    /// UTC time of the deadlock in ISO 8601, e.g. `2024-03-01T12:30:00.123`.
+    pub timestamp: String,
+    /// The `<deadlock>` graph XML, as shown by SQL Server Management Studio
+    /// when saved as `.xdl`.
+    pub graph: String,
+}
+
+pub(crate) fn session_from_row(row: &Row) -> crate::error::Result<BlockingSession> {
+    let wait_ms: Option<i32> = row.get(9)?;
+    Ok(BlockingSession {
+        session_id: row.get(0)?,
+        blocking_session_id: row.get(1)?,
+        login_name: row.get(2)?,
+        host_name: row.get(3)?,
+        program_name: row.get(4)?,
+        database: row.get(5)?,
+        status: row.get(6)?,
+        command: row.get(7)?,
+        wait_type: row.get(8)?,
+        wait_time: Duration::from_millis(u64::try_from(wait_ms.unwrap_or(0)).unwrap_or(0)),
+        wait_resource: row.get::<Option<String>>(10)?.filter(|s| !s.is_empty()),
+        lock_resource_type: row.get(11)?,
+        lock_mode: row.get(12)?,
+        open_transactions: row.get(13)?,
+        sql_text: row.get(14)?,
+    })
+}
+
+pub(crate) fn deadlock_from_row(row: &Row) -> crate::error::Result<DeadlockReport> {
+    Ok(DeadlockReport {
+        timestamp: row.get(0)?,
+        graph: row.get(1)?,
+    })
+}
+
+/// Arrange sessions into trees rooted at their head blockers.
+///
+/// Sessions waiting in a cycle, which the deadlock monitor has not broken
+/// yet, have no head blocker; the lowest session ID of each cycle is used as
+/// its root.
+pub(crate) fn build_tree(sessions: Vec<BlockingSession>) -> Vec<BlockingNode> {
+    let ids: HashSet<i16> = sessions.iter().map(|s| s.session_id).collect();
+    let mut children: HashMap<i16, Vec<i16>> = HashMap::new();
+    for session in &sessions {
+        if ids.contains(&session.blocking_session_id) {
+            children
+                .entry(session.blocking_session_id)
+                .or_default()
+                .push(session.session_id);
+        }
+    }
+    let mut by_id: HashMap<i16, BlockingSession> =
+        sessions.into_iter().map(|s| (s.session_id, s)).collect();
+
+    let mut roots: Vec<i16> = by_id
+        .values()
+        .filter(|s| !ids.contains(&s.blocking_session_id))
+        .map(|s| s.session_id)
+        .collect();
+    roots.sort_unstable();
+
+    let mut trees = Vec::new();
+    for root in roots {
+        if let Some(node) = take_node(root, &mut by_id, &children) {
+            trees.push(node);
+        }
+    }
+    // Whatever is left waits in a cycle
+    while let Some(&root) = by_id.keys().min() {
+        if let Some(node) = take_node(root, &mut by_id, &children) {
+            trees.push(node);
+        }
+    }
+    trees
+}
+
+fn take_node(
+    id: i16,
+    by_id: &mut HashMap<i16, BlockingSession>,
+    children: &HashMap<i16, Vec<i16>>,
+) -> Option<BlockingNode> {
+    let session = by_id.remove(&id)?;
+    let blocked = children
+        .get(&id)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| take_node(*child, by_id, children))
+        .collect();
+    Some(BlockingNode { session, blocked })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn session(id: i16, blocked_by: i16) -> BlockingSession {
+        BlockingSession {
+            session_id: id,
+            blocking_session_id: blocked_by,
+            login_name: "app".into(),
+            host_name: None,
+            program_name: None,
+            database: Some("Sales".into()),
+            status: if blocked_by == 0 {
+                "sleeping".into()
+            } else {
+                "suspended".into()
+            },
+            command: None,
+            wait_type: None,
+            wait_time: Duration::ZERO,
+            wait_resource: None,
+            lock_resource_type: None,
+            lock_mode: None,
+            open_transactions: 1,
+            sql_text: None,
+        }
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let trees = build_tree(vec![
+            session(51, 0),
+            session(52, 51),
+            session(53, 52),
+            session(54, 51),
+            session(60, 0),
+            session(61, 60),
+        ]);
+
+        assert_eq!(trees.len(), 2);
+        assert_eq!(trees[0].session.session_id, 51);
+        assert_eq!(trees[0].blocked_count(), 3);
+        assert_eq!(trees[0].depth(), 2);
+        assert_eq!(trees[1].session.session_id, 60);
+        assert_eq!(trees[1].blocked[0].session.session_id, 61);
+    }
+
+    #[test]
+    fn test_build_tree_with_cycle() {
+        let trees = build_tree(vec![session(70, 71), session(71, 70), session(72, 71)]);
+
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0].session.session_id, 70);
+        assert_eq!(trees[0].blocked_count(), 2);
+    }
+}
@@ -0,0 +1,248 @@
+//! Browse-mode result sets and statements that edit their rows.
+//!
+//! [`Client::query_browse`](crate::Client::query_browse) runs a query with
+//! `SET NO_BROWSETABLE ON`. The server then reports the base table and
+//! column behind every result column ([`Column::base_table`],
+//! [`Column::base_column`]), marks the key columns of those tables
+//! ([`Column::key`]), and adds key columns that were not selected to the
+//! result as hidden columns ([`Column::hidden`]). That is enough to address
+//! a row again, so grid editors can write changes back with
+//! [`update_row`] and [`delete_row`]:
+//!
+//! ```rust,ignore
+//! use mssql_client::browse::update_row;
+//!
+//! let rows = client
+//!     .query_browse("SELECT Name, Price FROM dbo.Products", &[])
+//!     .await?
+//!     .collect_all()
+//!     .await?;
+//!
+//! let edit = update_row(&rows[0], &[("Price", &19.99)])?;
+//! client.execute(edit.sql(), &edit.params()).await?;
+//! ```
+//!
+//! Hidden columns are part of every row; skip them when displaying the
+//! result.
+
+use mssql_types::{SqlValue, ToSql};
+
+use crate::error::{Error, Result};
+use crate::identifier::{quote_identifier, quote_schema_qualified};
+use crate::row::{Column, Row};
+
+/// A statement built for one row of a browse-mode result set.
+#[derive(Debug, Clone)]
+pub struct RowEdit {
+    sql: String,
+    params: Vec<SqlValue>,
+}
+
+impl RowEdit {
+    /// Get the SQL text, which refers to the parameters as `@p1`, `@p2`, ...
+    #[must_use]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Get the parameters to execute the statement with.
+    #[must_use]
+    pub fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params
+            .iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+/// Get the key columns of a browse-mode result set.
+#[must_use]
+pub fn key_columns(columns: &[Column]) -> Vec<&Column> {
+    columns
+        .iter()
+        .filter(|c| c.key && c.base_table.is_some())
+        .collect()
+}
+
+/// Build an `UPDATE` that sets `changes` on the base table row `row` was
+/// read from.
+///
+/// `changes` name result columns, which must all come from the same base
+/// table and must not be identity or computed columns. The row is located
+/// by the key columns of that table.
+pub fn update_row(row: &Row, changes: &[(&str, &(dyn ToSql + Sync))]) -> Result<RowEdit> {
+    if changes.is_empty() {
+        return Err(Error::Query("no columns to update".to_string()));
+    }
+
+    let mut table = None;
+    let mut assignments = Vec::with_capacity(changes.len());
+    let mut params = Vec::new();
+    for (name, value) in changes {
+        let column = row
+            .column_index(name)
+            .and_then(|i| row.columns().get(i))
+            .ok_or_else(|| Error::Query(format!("no column named '{name}' in the row")))?;
+        let (Some(base_table), Some(base_column)) = (&column.base_table, &column.base_column)
+        else {
+            return Err(Error::Query(format!(
+                "column '{name}' is not a base table column"
+            )));
+        };
+        if column.identity || column.computed {
+            return Err(Error::Query(format!(
+                "column '{name}' is an identity or computed column"
+            )));
+        }
+        match table {
+            None => table = Some(base_table.as_str()),
+            Some(t) if t == base_table => {}
+            Some(_) => {
+                return Err(Error::Query(
+                    "updated columns come from more than one table".to_string(),
+                ));
+            }
+        }
+        params.push(value.to_sql()?);
+        assignments.push(format!(
+            "{} = @p{}",
+            quote_identifier(base_column),
+            params.len()
+        ));
+    }
+
+    let table = table.unwrap_or_default();
+    let filter = key_filter(row, table, &mut params)?;
+    Ok(RowEdit {
+        sql: format!(
+            "UPDATE {} SET {} WHERE {filter}",
+            quote_schema_qualified(table),
+            assignments.join(", ")
+        ),
+        params,
+    })
+}
+
+/// Build a `DELETE` of the base table row `row` was read from.
+///
+/// The key columns of the result set must all belong to one table; for a
+/// join, select the other tables' columns without browse mode.
+pub fn delete_row(row: &Row) -> Result<RowEdit> {
+    let keys = key_columns(row.columns());
+    let mut tables = keys.iter().filter_map(|c| c.base_table.as_deref());
+    let table = tables.next().ok_or_else(no_keys)?;
+    if tables.any(|t| t != table) {
+        return Err(Error::Query(
+            "key columns come from more than one table".to_string(),
+        ));
+    }
+
+    let mut params = Vec::new();
+    let filter = key_filter(row, table, &mut params)?;
+    Ok(RowEdit {
+        sql: format!(
+            "DELETE FROM {} WHERE {filter}",
+            quote_schema_qualified(table)
+        ),
+        params,
+    })
+}
+
+fn no_keys() -> Error {
+    Error::Query(
+        "result set has no key columns; run it with query_browse against a table \
+         with a primary key or unique index"
+            .to_string(),
+    )
+}
+
+/// Build the `WHERE` condition matching the key columns of `table`.
+fn key_filter(row: &Row, table: &str, params: &mut Vec<SqlValue>) -> Result<String> {
+    let mut conditions = Vec::new();
+    for (index, column) in row.columns().iter().enumerate() {
+        if !column.key || column.base_table.as_deref() != Some(table) {
+            continue;
+        }
+        let name = quote_identifier(column.base_column.as_deref().unwrap_or(&column.name));
+        match row.get_raw(index) {
+            Some(value) if !value.is_null() => {
+                params.push(value);
+                conditions.push(format!("{name} = @p{}", params.len()));
+            }
+            _ => conditions.push(format!("{name} IS NULL")),
+        }
+    }
+    if conditions.is_empty() {
+        return Err(no_keys());
+    }
+    Ok(conditions.join(" AND "))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, base: Option<(&str, &str)>, key: bool) -> Column {
+        let mut column = Column::new(name, 0, "Int4");
+        column.base_table = base.map(|(t, _)| t.to_string());
+        column.base_column = base.map(|(_, c)| c.to_string());
+        column.key = key;
+        column
+    }
+
+    fn order_row() -> Row {
+        let mut hidden = column("OrderId", Some(("dbo.Orders", "OrderId")), true);
+        hidden.hidden = true;
+        Row::from_values(
+            vec![
+                column("Customer", Some(("dbo.Orders", "CustomerName")), false),
+                column("Total", None, false),
+                hidden,
+            ],
+            vec![
+                SqlValue::String("Contoso".into()),
+                SqlValue::Int(10),
+                SqlValue::Int(7),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_update_row() {
+        let edit = update_row(&order_row(), &[("customer", &"Fabrikam")]).unwrap();
+        assert_eq!(
+            edit.sql(),
+            "UPDATE [dbo].[Orders] SET [CustomerName] = @p1 WHERE [OrderId] = @p2"
+        );
+        assert_eq!(edit.params().len(), 2);
+
+        assert!(update_row(&order_row(), &[("Total", &1)]).is_err());
+        assert!(update_row(&order_row(), &[("Missing", &1)]).is_err());
+    }
+
+    #[test]
+    fn test_delete_row() {
+        let edit = delete_row(&order_row()).unwrap();
+        assert_eq!(
+            edit.sql(),
+            "DELETE FROM [dbo].[Orders] WHERE [OrderId] = @p1"
+        );
+
+        let row = Row::from_values(vec![column("Total", None, false)], vec![SqlValue::Int(1)]);
+        assert!(delete_row(&row).is_err());
+    }
+
+    #[test]
+    fn test_dotted_table_name() {
+        let row = Row::from_values(
+            vec![column("Id", Some(("[dbo].[Order.Archive]", "Id")), true)],
+            vec![SqlValue::Int(3)],
+        );
+        let edit = delete_row(&row).unwrap();
+        assert_eq!(
+            edit.sql(),
+            "DELETE FROM [dbo].[Order.Archive] WHERE [Id] = @p1"
+        );
+    }
+}
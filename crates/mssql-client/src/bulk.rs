@@ -165,6 +165,24 @@ impl BulkColumn {
         self.nullable = nullable;
         self
     }
+
+    /// TDS type ID parsed from the SQL type.
+    #[cfg(feature = "ingest")]
+    pub(crate) fn type_id(&self) -> u8 {
+        self.type_id
+    }
+
+    /// Maximum length in bytes (0xFFFF for MAX), for variable-length types.
+    #[cfg(feature = "ingest")]
+    pub(crate) fn max_length(&self) -> Option<u32> {
+        self.max_length
+    }
+
+    /// Precision and scale, for decimal types.
+    #[cfg(feature = "ingest")]
+    pub(crate) fn precision_scale(&self) -> (Option<u8>, Option<u8>) {
+        (self.precision, self.scale)
+    }
 }
 
 /// Parse SQL type string into TDS type information.
@@ -577,7 +595,12 @@ impl BulkInsert {
     }
 
     /// Write a ROW token to the buffer.
+    ///
+    /// A row that fails to encode is removed from the buffer again, so the
+    /// caller can skip it and continue.
     fn write_row(&mut self, values: &[SqlValue]) -> Result<(), Error> {
+        let start = self.buffer.len();
+
         // ROW token type
         self.buffer.put_u8(TokenType::Row as u8);
 
@@ -586,8 +609,13 @@ impl BulkInsert {
 
         // Write each column value
         for (i, (col, value)) in columns.iter().zip(values.iter()).enumerate() {
-            self.encode_column_value(col, value)
-                .map_err(|e| Error::Config(format!("failed to encode column {}: {}", i, e)))?;
+            if let Err(e) = self.encode_column_value(col, value) {
+                self.buffer.truncate(start);
+                return Err(Error::Config(format!(
+                    "failed to encode column {}: {}",
+                    i, e
+                )));
+            }
         }
 
         Ok(())
@@ -762,15 +790,27 @@ impl BulkInsert {
                 let time_len = time_byte_length(scale);
                 let total_len = time_len + 3 + 2;
                 buf.put_u8(total_len);
-                // Use mssql_types encode
-                encode_time_with_scale(dto.time(), scale, buf);
-                mssql_types::encode::encode_date(dto.date_naive(), buf);
+                // Time and date are sent in UTC
+                let utc = dto.naive_utc();
+                encode_time_with_scale(utc.time(), scale, buf);
+                mssql_types::encode::encode_date(utc.date(), buf);
                 // Timezone offset in minutes
                 use chrono::Offset;
                 let offset_minutes = (dto.offset().fix().local_minus_utc() / 60) as i16;
                 buf.put_i16_le(offset_minutes);
             }
 
+            // The bulk column metadata declares the scale
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTime2(v) => {
+                return self.encode_column_value(col, &SqlValue::DateTime(v.value));
+            }
+
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTimeOffset(v) => {
+                return self.encode_column_value(col, &SqlValue::DateTimeOffset(v.value));
+            }
+
             #[cfg(feature = "json")]
             SqlValue::Json(j) => {
                 let s = j.to_string();
@@ -911,11 +951,17 @@ impl BulkInsert {
     /// Get the buffered data as packets ready to send.
     ///
     /// Returns a vector of complete TDS packets with BulkLoad packet type (0x07).
+    /// The rows taken count as one batch, and a new batch starts.
     pub fn take_packets(&mut self) -> Vec<BytesMut> {
         const MAX_PACKET_SIZE: usize = 4096;
         const HEADER_SIZE: usize = 8;
         const MAX_PAYLOAD: usize = MAX_PACKET_SIZE - HEADER_SIZE;
 
+        if self.rows_in_batch > 0 {
+            self.batches_committed += 1;
+            self.rows_in_batch = 0;
+        }
+
         let data = self.buffer.split();
         let mut packets = Vec::new();
         let mut offset = 0;
@@ -1090,6 +1136,35 @@ mod tests {
         assert!(!bulk.should_flush());
     }
 
+    #[test]
+    fn test_bulk_insert_take_packets_starts_new_batch() {
+        let mut bulk = BulkInsert::new(vec![BulkColumn::new("id", "INT", 0)], 2);
+        bulk.send_row_values(&[SqlValue::Int(1)]).unwrap();
+        bulk.send_row_values(&[SqlValue::Int(2)]).unwrap();
+        assert!(bulk.should_flush());
+
+        assert!(!bulk.take_packets().is_empty());
+        assert!(!bulk.should_flush());
+        assert_eq!(bulk.rows_in_batch(), 0);
+        assert_eq!(bulk.result().batches_committed, 1);
+        assert_eq!(bulk.total_rows(), 2);
+    }
+
+    #[test]
+    fn test_bulk_insert_failed_row_is_discarded() {
+        let columns = vec![
+            BulkColumn::new("id", "INT", 0),
+            BulkColumn::new("name", "NVARCHAR(10)", 1),
+        ];
+        let mut bulk = BulkInsert::new(columns, 0);
+        let before = bulk.buffer.len();
+
+        let tvp = SqlValue::Tvp(Box::new(mssql_types::TvpData::new("dbo", "T")));
+        assert!(bulk.send_row_values(&[SqlValue::Int(1), tvp]).is_err());
+        assert_eq!(bulk.buffer.len(), before);
+        assert_eq!(bulk.total_rows(), 0);
+    }
+
     #[test]
     fn test_decimal_byte_length() {
         assert_eq!(decimal_byte_length(5), 5);
@@ -43,20 +43,20 @@ use std::sync::Arc;
 
 use mssql_codec::connection::CancelHandle as CodecCancelHandle;
 use mssql_tls::TlsStream;
-use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 
 use crate::error::{Error, Result};
+use crate::transport::Transport;
 
 /// Type alias for the TLS cancel handle.
-type TlsCancelHandle = CodecCancelHandle<TlsStream<TcpStream>>;
+type TlsCancelHandle = CodecCancelHandle<TlsStream<Transport>>;
 
 /// Type alias for the PreLogin wrapper cancel handle.
 type TlsPreloginCancelHandle =
-    CodecCancelHandle<TlsStream<mssql_tls::TlsPreloginWrapper<TcpStream>>>;
+    CodecCancelHandle<TlsStream<mssql_tls::TlsPreloginWrapper<Transport>>>;
 
-/// Type alias for the plain TCP cancel handle.
-type PlainCancelHandle = CodecCancelHandle<TcpStream>;
+/// Type alias for the plain cancel handle.
+type PlainCancelHandle = CodecCancelHandle<Transport>;
 
 /// Handle for cancelling the current query on a connection.
 ///
@@ -151,6 +151,20 @@ impl CancelHandle {
         }
     }
 
+    /// Cancel the current query without waiting for the Attention packet
+    /// to be sent; see [`CodecCancelHandle::cancel_in_background`].
+    pub(crate) fn cancel_in_background(&self) -> bool {
+        // Locked only while another task is cancelling
+        let Ok(inner) = self.inner.try_lock() else {
+            return true;
+        };
+        match &*inner {
+            CancelHandleInner::Tls(h) => h.cancel_in_background(),
+            CancelHandleInner::TlsPrelogin(h) => h.cancel_in_background(),
+            CancelHandleInner::Plain(h) => h.cancel_in_background(),
+        }
+    }
+
     /// Check if a cancellation is currently in progress.
     ///
     /// Returns `true` if `cancel()` has been called but the server has not
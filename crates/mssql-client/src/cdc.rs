@@ -0,0 +1,641 @@
+//! SQL Server Change Data Capture (CDC) support.
+//!
+//! Where [`change_tracking`](crate::change_tracking) only records *which*
+//! rows changed, CDC reads the transaction log into change tables that hold
+//! the column values of every insert, update, and delete. This module wraps
+//! the CDC query functions with typed results.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use mssql_client::cdc::RowFilter;
+//!
+//! let cdc = client.cdc();
+//! let from = cdc.min_lsn("dbo_Orders").await?.expect("capture instance exists");
+//! let to = cdc.max_lsn().await?;
+//!
+//! let changes: Vec<CdcChange<Order>> = client
+//!     .cdc()
+//!     .all_changes("dbo_Orders", from, to, RowFilter::All)
+//!     .await?;
+//! for change in changes {
+//!     println!("{} {:?}", change.operation, change.data);
+//! }
+//! ```
+//!
+//! For continuous processing, [`CdcReader`] polls a capture instance and
+//! yields each change once, remembering the last LSN it read:
+//!
+//! ```rust,ignore
+//! let mut reader = client.cdc().reader::<Order>("dbo_Orders");
+//! loop {
+//!     let change = reader.next_change().await?;
+//!     apply(change);
+//!     save_position(reader.position());
+//! }
+//! ```
+//!
+//! ## Prerequisites
+//!
+//! ```sql
+//! EXEC sys.sp_cdc_enable_db;
+//! EXEC sys.sp_cdc_enable_table
+//!     @source_schema = N'dbo', @source_name = N'Orders',
+//!     @role_name = NULL, @supports_net_changes = 1;
+//! ```
+//!
+//! ## References
+//!
+//! - [About Change Data Capture](https://learn.microsoft.com/en-us/sql/relational-databases/track-changes/about-change-data-capture-sql-server)
+//! - [cdc.fn_cdc_get_all_changes](https://learn.microsoft.com/en-us/sql/relational-databases/system-functions/cdc-fn-cdc-get-all-changes-capture-instance-transact-sql)
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::client::{Client, validate_identifier};
+use crate::error::{Error, Result};
+use crate::from_row::FromRow;
+use crate::row::Row;
+use crate::state::ConnectionState;
+
+/// A log sequence number, the position of a change in the transaction log.
+///
+/// LSNs are `binary(10)` values that compare byte-wise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Lsn([u8; 10]);
+
+impl Lsn {
+    /// The zero LSN, which CDC returns for unknown capture instances.
+    pub const ZERO: Self = Self([0; 10]);
+
+    /// Create an LSN from its 10 bytes.
+    #[must_use]
+    pub const fn new(bytes: [u8; 10]) -> Self {
+        Self(bytes)
+    }
+
+    /// Create an LSN from a `binary(10)` column value.
+    ///
+    /// Returns `None` if the value is not 10 bytes long.
+    #[must_use]
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Self)
+    }
+
+    /// Get the LSN bytes.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 10] {
+        &self.0
+    }
+
+    /// Check if this is the zero LSN.
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Get the next LSN, as `sys.fn_cdc_increment_lsn` would.
+    ///
+    /// Used to start reading just after the last change already processed.
+    #[must_use]
+    pub fn next(&self) -> Self {
+        let mut bytes = self.0;
+        for byte in bytes.iter_mut().rev() {
+            let (value, overflow) = byte.overflowing_add(1);
+            *byte = value;
+            if !overflow {
+                break;
+            }
+        }
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for Lsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("0x")?;
+        for byte in &self.0 {
+            write!(f, "{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The kind of change in a CDC change row.
+///
+/// This corresponds to the `__$operation` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CdcOperation {
+    /// A row was deleted (1).
+    Delete,
+    /// A row was inserted (2).
+    Insert,
+    /// Column values before an update (3), returned with [`RowFilter::AllUpdateOld`].
+    UpdateBefore,
+    /// Column values after an update (4).
+    UpdateAfter,
+    /// An insert or update (5), returned with [`NetRowFilter::AllWithMerge`].
+    Merge,
+}
+
+impl CdcOperation {
+    /// Parse an operation from its `__$operation` code.
+    #[must_use]
+    pub const fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(Self::Delete),
+            2 => Some(Self::Insert),
+            3 => Some(Self::UpdateBefore),
+            4 => Some(Self::UpdateAfter),
+            5 => Some(Self::Merge),
+            _ => None,
+        }
+    }
+
+    /// Get the `__$operation` code.
+    #[must_use]
+    pub const fn code(&self) -> i32 {
+        match self {
+            Self::Delete => 1,
+            Self::Insert => 2,
+            Self::UpdateBefore => 3,
+            Self::UpdateAfter => 4,
+            Self::Merge => 5,
+        }
+    }
+}
+
+impl fmt::Display for CdcOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delete => write!(f, "DELETE"),
+            Self::Insert => write!(f, "INSERT"),
+            Self::UpdateBefore => write!(f, "UPDATE (before)"),
+            Self::UpdateAfter => write!(f, "UPDATE (after)"),
+            Self::Merge => write!(f, "MERGE"),
+        }
+    }
+}
+
+/// Row filter for `cdc.fn_cdc_get_all_changes_<capture_instance>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowFilter {
+    /// One row per change; updates return only the new values.
+    #[default]
+    All,
+    /// Like `All`, but updates also return a row with the old values.
+    AllUpdateOld,
+}
+
+impl RowFilter {
+    /// Get the `@row_filter_option` value.
+    #[must_use]
+    pub const fn as_sql(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::AllUpdateOld => "all update old",
+        }
+    }
+}
+
+/// Row filter for `cdc.fn_cdc_get_net_changes_<capture_instance>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetRowFilter {
+    /// The final state of each changed row.
+    #[default]
+    All,
+    /// Like `All`, with `__$update_mask` set for updates.
+    AllWithMask,
+    /// Inserts and updates are both reported as [`CdcOperation::Merge`].
+    AllWithMerge,
+}
+
+impl NetRowFilter {
+    /// Get the `@row_filter_option` value.
+    #[must_use]
+    pub const fn as_sql(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::AllWithMask => "all with mask",
+            Self::AllWithMerge => "all with merge",
+        }
+    }
+}
+
+/// How [`ChangeDataCapture::map_time_to_lsn`] picks an LSN for a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LsnBoundary {
+    /// The first LSN committed after the time.
+    SmallestGreaterThan,
+    /// The first LSN committed at or after the time.
+    SmallestGreaterThanOrEqual,
+    /// The last LSN committed before the time.
+    LargestLessThan,
+    /// The last LSN committed at or before the time.
+    LargestLessThanOrEqual,
+}
+
+impl LsnBoundary {
+    /// Get the `@relational_operator` value of `sys.fn_cdc_map_time_to_lsn`.
+    #[must_use]
+    pub const fn as_sql(&self) -> &'static str {
+        match self {
+            Self::SmallestGreaterThan => "smallest greater than",
+            Self::SmallestGreaterThanOrEqual => "smallest greater than or equal",
+            Self::LargestLessThan => "largest less than",
+            Self::LargestLessThanOrEqual => "largest less than or equal",
+        }
+    }
+}
+
+/// A change row read from a CDC query function.
+#[derive(Debug, Clone)]
+pub struct CdcChange<T> {
+    /// Commit LSN of the transaction that made the change.
+    pub start_lsn: Lsn,
+    /// Order of the change within its transaction (`None` for net changes).
+    pub seqval: Option<Lsn>,
+    /// The kind of change.
+    pub operation: CdcOperation,
+    /// Bit mask of the captured columns that changed.
+    pub update_mask: Option<Vec<u8>>,
+    /// The captured column values.
+    pub data: T,
+}
+
+impl<T: FromRow> CdcChange<T> {
+    /// Read a change from a row returned by a CDC query function.
+    pub fn from_row(row: &Row) -> Result<Self> {
+        let start_lsn: Vec<u8> = row.get_by_name("__$start_lsn")?;
+        let seqval: Option<Vec<u8>> = row.try_get_by_name("__$seqval");
+        let code: i32 = row.get_by_name("__$operation")?;
+
+        Ok(Self {
+            start_lsn: lsn_from_bytes(&start_lsn)?,
+            seqval: seqval.as_deref().map(lsn_from_bytes).transpose()?,
+            operation: CdcOperation::from_code(code)
+                .ok_or_else(|| Error::Protocol(format!("unknown CDC operation {code}")))?,
+            update_mask: row.get_by_name("__$update_mask")?,
+            data: T::from_row(row)?,
+        })
+    }
+}
+
+/// Builds the SQL for the CDC query functions.
+///
+/// # Example
+///
+/// ```rust
+/// use mssql_client::cdc::{CdcQuery, RowFilter};
+///
+/// let sql = CdcQuery::all_changes("dbo_Orders", RowFilter::All).to_sql().unwrap();
+/// assert_eq!(
+///     sql,
+///     "SELECT * FROM cdc.fn_cdc_get_all_changes_dbo_Orders(@p1, @p2, N'all')"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CdcQuery {
+    capture_instance: String,
+    function: &'static str,
+    row_filter: &'static str,
+}
+
+impl CdcQuery {
+    /// Query every change in an LSN range.
+    #[must_use]
+    pub fn all_changes(capture_instance: impl Into<String>, row_filter: RowFilter) -> Self {
+        Self {
+            capture_instance: capture_instance.into(),
+            function: "fn_cdc_get_all_changes_",
+            row_filter: row_filter.as_sql(),
+        }
+    }
+
+    /// Query the net change to each row in an LSN range.
+    ///
+    /// The capture instance must have been enabled with `@supports_net_changes = 1`.
+    #[must_use]
+    pub fn net_changes(capture_instance: impl Into<String>, row_filter: NetRowFilter) -> Self {
+        Self {
+            capture_instance: capture_instance.into(),
+            function: "fn_cdc_get_net_changes_",
+            row_filter: row_filter.as_sql(),
+        }
+    }
+
+    /// Generate the query; `@p1` and `@p2` are the first and last LSN.
+    ///
+    /// Fails if the capture instance name is not a plain identifier, since
+    /// it becomes part of the function name.
+    pub fn to_sql(&self) -> Result<String> {
+        validate_identifier(&self.capture_instance)?;
+        Ok(format!(
+            "SELECT * FROM cdc.{}{}(@p1, @p2, N'{}')",
+            self.function, self.capture_instance, self.row_filter
+        ))
+    }
+}
+
+/// Reads Change Data Capture data through a client.
+///
+/// Created with [`Client::cdc`](crate::Client::cdc).
+pub struct ChangeDataCapture<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+}
+
+impl<'a, S: ConnectionState> ChangeDataCapture<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self { client }
+    }
+
+    /// Get the lowest LSN still available for a capture instance.
+    ///
+    /// Returns `None` if the capture instance does not exist.
+    pub async fn min_lsn(&mut self, capture_instance: &str) -> Result<Option<Lsn>> {
+        let lsn = self
+            .fetch_lsn("SELECT sys.fn_cdc_get_min_lsn(@p1)", &[&capture_instance])
+            .await?;
+        Ok(lsn.filter(|lsn| !lsn.is_zero()))
+    }
+
+    /// Get the highest LSN processed by the capture job.
+    pub async fn max_lsn(&mut self) -> Result<Lsn> {
+        self.fetch_lsn("SELECT sys.fn_cdc_get_max_lsn()", &[])
+            .await?
+            .ok_or_else(|| Error::Query("change data capture is not enabled".into()))
+    }
+
+    /// Map a commit time to an LSN.
+    ///
+    /// Returns `None` if no transaction matches.
+    #[cfg(feature = "chrono")]
+    pub async fn map_time_to_lsn(
+        &mut self,
+        boundary: LsnBoundary,
+        time: chrono::NaiveDateTime,
+    ) -> Result<Option<Lsn>> {
+        self.fetch_lsn(
+            "SELECT sys.fn_cdc_map_time_to_lsn(@p1, @p2)",
+            &[&boundary.as_sql(), &time],
+        )
+        .await
+    }
+
+    /// Read every change to a capture instance between two LSNs, inclusive.
+    pub async fn all_changes<T: FromRow>(
+        &mut self,
+        capture_instance: &str,
+        from: Lsn,
+        to: Lsn,
+        row_filter: RowFilter,
+    ) -> Result<Vec<CdcChange<T>>> {
+        let query = CdcQuery::all_changes(capture_instance, row_filter);
+        self.changes(&query, from, to).await
+    }
+
+    /// Read the net change to each row of a capture instance between two LSNs.
+    pub async fn net_changes<T: FromRow>(
+        &mut self,
+        capture_instance: &str,
+        from: Lsn,
+        to: Lsn,
+        row_filter: NetRowFilter,
+    ) -> Result<Vec<CdcChange<T>>> {
+        let query = CdcQuery::net_changes(capture_instance, row_filter);
+        self.changes(&query, from, to).await
+    }
+
+    /// Create a reader that polls a capture instance for new changes.
+    pub fn reader<T: FromRow>(self, capture_instance: impl Into<String>) -> CdcReader<'a, S, T> {
+        CdcReader {
+            cdc: self,
+            capture_instance: capture_instance.into(),
+            row_filter: RowFilter::All,
+            position: None,
+            poll_interval: CdcReader::<S, T>::DEFAULT_POLL_INTERVAL,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    async fn changes<T: FromRow>(
+        &mut self,
+        query: &CdcQuery,
+        from: Lsn,
+        to: Lsn,
+    ) -> Result<Vec<CdcChange<T>>> {
+        let sql = query.to_sql()?;
+        let rows = self
+            .client
+            .fetch_rows(
+                &sql,
+                &[&from.as_bytes().as_slice(), &to.as_bytes().as_slice()],
+            )
+            .await?;
+        rows.iter().map(CdcChange::from_row).collect()
+    }
+
+    async fn fetch_lsn(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Option<Lsn>> {
+        let rows = self.client.fetch_rows(sql, params).await?;
+        let Some(row) = rows.first() else {
+            return Ok(None);
+        };
+        let bytes: Option<Vec<u8>> = row.get(0)?;
+        bytes.as_deref().map(lsn_from_bytes).transpose()
+    }
+}
+
+impl<S: ConnectionState> fmt::Debug for ChangeDataCapture<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeDataCapture").finish_non_exhaustive()
+    }
+}
+
+/// Polls a capture instance and yields each change once, in LSN order.
+///
+/// Each poll reads the changes between the last LSN read and
+/// `sys.fn_cdc_get_max_lsn()`. Persist [`position`](Self::position) and pass
+/// it to [`start_after`](Self::start_after) to resume after a restart.
+pub struct CdcReader<'a, S: ConnectionState, T> {
+    cdc: ChangeDataCapture<'a, S>,
+    capture_instance: String,
+    row_filter: RowFilter,
+    position: Option<Lsn>,
+    poll_interval: Duration,
+    buffer: VecDeque<CdcChange<T>>,
+}
+
+impl<S: ConnectionState, T: FromRow> CdcReader<'_, S, T> {
+    /// Default delay between polls that found no changes.
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Resume after a previously read LSN instead of the oldest available change.
+    #[must_use]
+    pub fn start_after(mut self, lsn: Lsn) -> Self {
+        self.position = Some(lsn);
+        self
+    }
+
+    /// Set the delay between polls that found no changes.
+    #[must_use]
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set the row filter; the default is [`RowFilter::All`].
+    #[must_use]
+    pub fn row_filter(mut self, row_filter: RowFilter) -> Self {
+        self.row_filter = row_filter;
+        self
+    }
+
+    /// Get the last LSN read, or `None` before the first change.
+    #[must_use]
+    pub fn position(&self) -> Option<Lsn> {
+        self.position
+    }
+
+    /// Read the changes committed since the last poll.
+    ///
+    /// Returns an empty list if there are none. Fails if changes after the
+    /// current position were already removed by the CDC cleanup job.
+    pub async fn poll(&mut self) -> Result<Vec<CdcChange<T>>> {
+        let min = self
+            .cdc
+            .min_lsn(&self.capture_instance)
+            .await?
+            .ok_or_else(|| {
+                Error::Query(format!(
+                    "CDC capture instance '{}' does not exist",
+                    self.capture_instance
+                ))
+            })?;
+        let max = self.cdc.max_lsn().await?;
+
+        let from = match self.position {
+            Some(position) if position.next() < min => {
+                return Err(Error::Query(format!(
+                    "CDC changes after {position} for '{}' were cleaned up; oldest available is {min}",
+                    self.capture_instance
+                )));
+            }
+            Some(position) => position.next(),
+            None => min,
+        };
+        if from > max {
+            return Ok(Vec::new());
+        }
+
+        let changes = self
+            .cdc
+            .all_changes(&self.capture_instance, from, max, self.row_filter)
+            .await?;
+        self.position = Some(max);
+        Ok(changes)
+    }
+
+    /// Wait for the next change, polling at the configured interval.
+    pub async fn next_change(&mut self) -> Result<CdcChange<T>> {
+        loop {
+            if let Some(change) = self.buffer.pop_front() {
+                return Ok(change);
+            }
+
+            let changes = self.poll().await?;
+            if changes.is_empty() {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+            self.buffer.extend(changes);
+        }
+    }
+}
+
+impl<S: ConnectionState, T> fmt::Debug for CdcReader<'_, S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CdcReader")
+            .field("capture_instance", &self.capture_instance)
+            .field("position", &self.position)
+            .field("buffered", &self.buffer.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn lsn_from_bytes(bytes: &[u8]) -> Result<Lsn> {
+    Lsn::from_slice(bytes)
+        .ok_or_else(|| Error::Protocol(format!("invalid LSN length {}", bytes.len())))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsn_next_carries() {
+        let lsn = Lsn::new([0, 0, 0, 0x2A, 0, 0, 0x01, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(lsn.next(), Lsn::new([0, 0, 0, 0x2A, 0, 0, 0x02, 0, 0, 0]));
+        assert!(lsn.next() > lsn);
+        assert_eq!(Lsn::ZERO.next().as_bytes()[9], 1);
+    }
+
+    #[test]
+    fn test_lsn_display_and_parse() {
+        let lsn = Lsn::from_slice(&[0, 0, 0, 0x2A, 0, 0, 0x01, 0xA0, 0, 0x03]).unwrap();
+        assert_eq!(lsn.to_string(), "0x0000002A000001A00003");
+        assert!(Lsn::from_slice(&[0; 9]).is_none());
+        assert!(Lsn::ZERO.is_zero());
+    }
+
+    #[test]
+    fn test_cdc_operation_codes() {
+        for code in 1..=5 {
+            assert_eq!(CdcOperation::from_code(code).unwrap().code(), code);
+        }
+        assert_eq!(CdcOperation::from_code(4), Some(CdcOperation::UpdateAfter));
+        assert_eq!(CdcOperation::from_code(0), None);
+    }
+
+    #[test]
+    fn test_cdc_query_sql() {
+        let sql = CdcQuery::all_changes("dbo_Orders", RowFilter::AllUpdateOld)
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM cdc.fn_cdc_get_all_changes_dbo_Orders(@p1, @p2, N'all update old')"
+        );
+
+        let sql = CdcQuery::net_changes("dbo_Orders", NetRowFilter::AllWithMerge)
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM cdc.fn_cdc_get_net_changes_dbo_Orders(@p1, @p2, N'all with merge')"
+        );
+
+        assert!(
+            CdcQuery::all_changes("x(@p1); DROP TABLE t; --", RowFilter::All)
+                .to_sql()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lsn_boundary_sql() {
+        assert_eq!(
+            LsnBoundary::LargestLessThanOrEqual.as_sql(),
+            "largest less than or equal"
+        );
+        assert_eq!(
+            LsnBoundary::SmallestGreaterThan.as_sql(),
+            "smallest greater than"
+        );
+    }
+}
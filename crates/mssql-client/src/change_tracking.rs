@@ -23,17 +23,18 @@
 //!     .and_then(|r| r.try_get(0))
 //!     .unwrap_or(0);
 //!
-//! // Later, query for changes since that version
-//! let query = ChangeTrackingQuery::changes("Products", last_sync_version);
-//! let changes: Vec<ChangedRow> = client.query(&query.to_sql()).await?
-//!     .map(|row| ChangedRow::from_row(&row))
-//!     .collect();
+//! // Later, query for changes since that version; the version is bound as
+//! // a parameter and the table and column names are bracket-quoted
+//! let query = ChangeTrackingQuery::changes("Products", last_sync_version)
+//!     .with_primary_keys(&["ProductId"]);
+//! let changes: Vec<ChangedRow<ProductKey>> =
+//!     client.change_tracking().query(&query).await?;
 //!
 //! for change in changes {
-//!     match change.operation {
-//!         ChangeOperation::Insert => println!("New row: {:?}", change.primary_key),
-//!         ChangeOperation::Update => println!("Updated row: {:?}", change.primary_key),
-//!         ChangeOperation::Delete => println!("Deleted row: {:?}", change.primary_key),
+//!     match change.metadata.operation {
+//!         ChangeOperation::Insert => println!("New row: {:?}", change.data),
+//!         ChangeOperation::Update => println!("Updated row: {:?}", change.data),
+//!         ChangeOperation::Delete => println!("Deleted row: {:?}", change.data),
 //!     }
 //! }
 //! ```
@@ -64,10 +65,19 @@
 //! - [About Change Tracking](https://learn.microsoft.com/en-us/sql/relational-databases/track-changes/about-change-tracking-sql-server)
 //! - [CHANGETABLE function](https://learn.microsoft.com/en-us/sql/relational-databases/system-functions/changetable-transact-sql)
 
+use std::collections::HashMap;
 use std::fmt;
 
 use bytes::Bytes;
 
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::from_row::FromRow;
+use crate::identifier::{quote_identifier, quote_schema_qualified};
+use crate::row::Row;
+use crate::state::{ConnectionState, InTransaction, Ready};
+use crate::transaction::IsolationLevel;
+
 /// The type of change operation tracked by SQL Server Change Tracking.
 ///
 /// This corresponds to the `SYS_CHANGE_OPERATION` column in `CHANGETABLE` results.
@@ -214,6 +224,43 @@ impl ChangeMetadata {
             context: None,
         }
     }
+
+    /// Read the change tracking system columns from a `CHANGETABLE` row.
+    pub fn from_row(row: &Row) -> Result<Self> {
+        let operation: String = row.get_by_name("SYS_CHANGE_OPERATION")?;
+        let operation = ChangeOperation::from_sql(&operation).ok_or_else(|| {
+            Error::Protocol(format!("unknown change tracking operation '{operation}'"))
+        })?;
+        let changed_columns: Option<Vec<u8>> = row.get_by_name("SYS_CHANGE_COLUMNS")?;
+        let context: Option<Vec<u8>> = row.get_by_name("SYS_CHANGE_CONTEXT")?;
+
+        Ok(Self {
+            version: row.get_by_name("SYS_CHANGE_VERSION")?,
+            creation_version: row.get_by_name("SYS_CHANGE_CREATION_VERSION")?,
+            operation,
+            changed_columns: changed_columns.map(Bytes::from),
+            context: context.map(Bytes::from),
+        })
+    }
+}
+
+/// A row returned by [`ChangeTrackingClient::changes_since`].
+#[derive(Debug, Clone)]
+pub struct ChangedRow<T> {
+    /// Change tracking system columns.
+    pub metadata: ChangeMetadata,
+    /// The row's primary key columns, mapped with [`FromRow`].
+    pub data: T,
+}
+
+impl<T: FromRow> ChangedRow<T> {
+    /// Read a changed row from a `CHANGETABLE` row.
+    pub fn from_row(row: &Row) -> Result<Self> {
+        Ok(Self {
+            metadata: ChangeMetadata::from_row(row)?,
+            data: T::from_row(row)?,
+        })
+    }
 }
 
 /// Query builder for Change Tracking operations.
@@ -255,6 +302,10 @@ impl ChangeTrackingQuery {
     /// * `table_name` - The name of the table to query changes for
     /// * `last_sync_version` - The version from the previous sync (0 for initial)
     ///
+    /// The table name may be schema-qualified; it, the alias and all column
+    /// names are bracket-quoted with [`quote_schema_qualified`] and
+    /// [`quote_identifier`] in the generated SQL.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -313,19 +364,53 @@ impl ChangeTrackingQuery {
         self
     }
 
+    /// Get the version changes are queried since.
+    #[must_use]
+    pub fn last_sync_version(&self) -> i64 {
+        self.last_sync_version
+    }
+
     /// Generate the SQL query string.
     ///
-    /// This returns a query that can be executed directly.
+    /// This returns a query that can be executed directly, with the version
+    /// written into the text. Prefer [`to_parameterized_sql`] or
+    /// [`ChangeTrackingClient::query`], which bind it as a parameter so the
+    /// statement is cached once for all versions.
+    ///
+    /// [`to_parameterized_sql`]: Self::to_parameterized_sql
     #[must_use]
     pub fn to_sql(&self) -> String {
+        self.build_sql(&self.last_sync_version.to_string())
+    }
+
+    /// Generate the SQL query with the version as the `@p1` parameter.
+    ///
+    /// Bind [`last_sync_version`](Self::last_sync_version) as `@p1`:
+    ///
+    /// ```rust,ignore
+    /// let query = ChangeTrackingQuery::changes("dbo.Products", last_sync);
+    /// let rows = client
+    ///     .query(&query.to_parameterized_sql(), &[&query.last_sync_version()])
+    ///     .await?;
+    /// ```
+    #[must_use]
+    pub fn to_parameterized_sql(&self) -> String {
+        self.build_sql("@p1")
+    }
+
+    fn build_sql(&self, version: &str) -> String {
         let force_seek = if self.force_seek { ", FORCESEEK" } else { "" };
 
         // Build the SELECT column list
         let select_cols = self.build_select_columns();
 
         format!(
-            "SELECT {} FROM CHANGETABLE(CHANGES {}, {}{})",
-            select_cols, self.table_name, self.last_sync_version, force_seek
+            "SELECT {} FROM CHANGETABLE(CHANGES {}, {}{}) AS {}",
+            select_cols,
+            quote_schema_qualified(&self.table_name),
+            version,
+            force_seek,
+            quote_identifier(&self.alias)
         )
     }
 
@@ -350,8 +435,22 @@ impl ChangeTrackingQuery {
     /// ```
     #[must_use]
     pub fn to_sql_with_data(&self, data_columns: &[&str]) -> String {
+        self.build_sql_with_data(data_columns, &self.last_sync_version.to_string())
+    }
+
+    /// Generate the joined SQL query with the version as the `@p1`
+    /// parameter.
+    ///
+    /// See [`to_sql_with_data`](Self::to_sql_with_data) and
+    /// [`to_parameterized_sql`](Self::to_parameterized_sql).
+    #[must_use]
+    pub fn to_parameterized_sql_with_data(&self, data_columns: &[&str]) -> String {
+        self.build_sql_with_data(data_columns, "@p1")
+    }
+
+    fn build_sql_with_data(&self, data_columns: &[&str], version: &str) -> String {
         let force_seek = if self.force_seek { ", FORCESEEK" } else { "" };
-        let alias = &self.alias;
+        let alias = &quote_identifier(&self.alias);
 
         // Build change tracking columns
         let ct_cols = format!(
@@ -362,7 +461,7 @@ impl ChangeTrackingQuery {
         // Build data columns (prefixed with table alias)
         let data_cols: String = data_columns
             .iter()
-            .map(|c| format!("T.{c}"))
+            .map(|c| format!("T.{}", quote_identifier(c)))
             .collect::<Vec<_>>()
             .join(", ");
 
@@ -372,7 +471,7 @@ impl ChangeTrackingQuery {
             .as_ref()
             .map(|pks| {
                 pks.iter()
-                    .map(|pk| format!("{alias}.{pk}"))
+                    .map(|pk| format!("{alias}.{}", quote_identifier(pk)))
                     .collect::<Vec<_>>()
                     .join(", ")
             })
@@ -384,6 +483,7 @@ impl ChangeTrackingQuery {
             .as_ref()
             .map(|pks| {
                 pks.iter()
+                    .map(|pk| quote_identifier(pk))
                     .map(|pk| format!("{alias}.{pk} = T.{pk}"))
                     .collect::<Vec<_>>()
                     .join(" AND ")
@@ -400,13 +500,12 @@ impl ChangeTrackingQuery {
             "SELECT {select_cols} \
              FROM CHANGETABLE(CHANGES {table}, {version}{force_seek}) AS {alias} \
              LEFT OUTER JOIN {table} AS T ON {join_condition}",
-            table = self.table_name,
-            version = self.last_sync_version,
+            table = quote_schema_qualified(&self.table_name),
         )
     }
 
     fn build_select_columns(&self) -> String {
-        let alias = &self.alias;
+        let alias = &quote_identifier(&self.alias);
 
         // Always include change tracking system columns
         let mut cols = vec![
@@ -420,14 +519,14 @@ impl ChangeTrackingQuery {
         // Add primary key columns if specified
         if let Some(ref pks) = self.primary_keys {
             for pk in pks {
-                cols.push(format!("{alias}.{pk}"));
+                cols.push(format!("{alias}.{}", quote_identifier(pk)));
             }
         }
 
         // Add data columns if specified
         if let Some(ref data_cols) = self.columns {
             for col in data_cols {
-                cols.push(format!("{alias}.{col}"));
+                cols.push(format!("{alias}.{}", quote_identifier(col)));
             }
         }
 
@@ -456,8 +555,42 @@ impl ChangeTracking {
         "SELECT CHANGE_TRACKING_CURRENT_VERSION()"
     }
 
+    /// Get the parameterized query for the minimum valid version of a table.
+    ///
+    /// Bind the table name, bracket-quoted with
+    /// [`quote_schema_qualified`], as `@p1`. This is what
+    /// [`ChangeTrackingClient::min_valid_version`] runs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mssql_client::change_tracking::ChangeTracking;
+    ///
+    /// let sql = ChangeTracking::min_valid_version_query();
+    /// assert!(sql.contains("OBJECT_ID(@p1)"));
+    /// ```
+    #[must_use]
+    pub const fn min_valid_version_query() -> &'static str {
+        "SELECT CHANGE_TRACKING_MIN_VALID_VERSION(OBJECT_ID(@p1))"
+    }
+
+    /// Get the parameterized query checking whether a column is in a change
+    /// mask.
+    ///
+    /// Bind the bracket-quoted table name as `@p1`, the column name as `@p2`
+    /// and the `SYS_CHANGE_COLUMNS` mask as `@p3`. This is what
+    /// [`ChangeTrackingClient::is_column_in_mask`] runs.
+    #[must_use]
+    pub const fn column_in_mask_query() -> &'static str {
+        "SELECT CHANGE_TRACKING_IS_COLUMN_IN_MASK(\
+         COLUMNPROPERTY(OBJECT_ID(@p1), @p2, 'ColumnId'), @p3)"
+    }
+
     /// Generate SQL to get the minimum valid version for a table.
     ///
+    /// The table name is written into the text as a quoted literal; prefer
+    /// [`min_valid_version_query`](Self::min_valid_version_query).
+    ///
     /// If a client's last sync version is less than this, it must
     /// perform a full re-sync instead of incremental sync.
     ///
@@ -475,12 +608,17 @@ impl ChangeTracking {
     /// ```
     #[must_use]
     pub fn min_valid_version_sql(table_name: &str) -> String {
-        format!("SELECT CHANGE_TRACKING_MIN_VALID_VERSION(OBJECT_ID(N'{table_name}'))")
+        format!(
+            "SELECT CHANGE_TRACKING_MIN_VALID_VERSION(OBJECT_ID({}))",
+            name_literal(&quote_schema_qualified(table_name))
+        )
     }
 
     /// Generate SQL to check if a column is in a change mask.
     ///
     /// Used to determine which specific columns changed in an update operation.
+    /// The names are written into the text as quoted literals; prefer
+    /// [`column_in_mask_query`](Self::column_in_mask_query).
     ///
     /// # Arguments
     ///
@@ -500,8 +638,10 @@ impl ChangeTracking {
     pub fn column_in_mask_sql(table_name: &str, column_name: &str, mask_variable: &str) -> String {
         format!(
             "SELECT CHANGE_TRACKING_IS_COLUMN_IN_MASK(\
-             COLUMNPROPERTY(OBJECT_ID(N'{table_name}'), N'{column_name}', 'ColumnId'), \
-             {mask_variable})"
+             COLUMNPROPERTY(OBJECT_ID({}), {}, 'ColumnId'), \
+             {mask_variable})",
+            name_literal(&quote_schema_qualified(table_name)),
+            name_literal(column_name)
         )
     }
 
@@ -529,8 +669,9 @@ impl ChangeTracking {
     ) -> String {
         let cleanup = if auto_cleanup { "ON" } else { "OFF" };
         format!(
-            "ALTER DATABASE [{database_name}] SET CHANGE_TRACKING = ON \
-             (CHANGE_RETENTION = {retention_days} DAYS, AUTO_CLEANUP = {cleanup})"
+            "ALTER DATABASE {} SET CHANGE_TRACKING = ON \
+             (CHANGE_RETENTION = {retention_days} DAYS, AUTO_CLEANUP = {cleanup})",
+            quote_identifier(database_name)
         )
     }
 
@@ -553,21 +694,28 @@ impl ChangeTracking {
     pub fn enable_table_sql(table_name: &str, track_columns_updated: bool) -> String {
         let track_cols = if track_columns_updated { "ON" } else { "OFF" };
         format!(
-            "ALTER TABLE [{table_name}] ENABLE CHANGE_TRACKING \
-             WITH (TRACK_COLUMNS_UPDATED = {track_cols})"
+            "ALTER TABLE {} ENABLE CHANGE_TRACKING \
+             WITH (TRACK_COLUMNS_UPDATED = {track_cols})",
+            quote_schema_qualified(table_name)
         )
     }
 
     /// Generate SQL to disable change tracking on a table.
     #[must_use]
     pub fn disable_table_sql(table_name: &str) -> String {
-        format!("ALTER TABLE [{table_name}] DISABLE CHANGE_TRACKING")
+        format!(
+            "ALTER TABLE {} DISABLE CHANGE_TRACKING",
+            quote_schema_qualified(table_name)
+        )
     }
 
     /// Generate SQL to disable change tracking on a database.
     #[must_use]
     pub fn disable_database_sql(database_name: &str) -> String {
-        format!("ALTER DATABASE [{database_name}] SET CHANGE_TRACKING = OFF")
+        format!(
+            "ALTER DATABASE {} SET CHANGE_TRACKING = OFF",
+            quote_identifier(database_name)
+        )
     }
 }
 
@@ -615,7 +763,384 @@ impl SyncVersionStatus {
     }
 }
 
+/// Runs change tracking queries through a client.
+///
+/// Created with [`Client::change_tracking`](crate::Client::change_tracking).
+/// Table names may be schema-qualified, e.g. `dbo.Products`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let mut ct = client.change_tracking();
+/// let next_sync = ct.current_version().await?.unwrap_or(0);
+/// if ct.sync_status("dbo.Products", last_sync).await?.can_sync_incrementally() {
+///     let changes: Vec<ChangedRow<ProductKey>> =
+///         ct.changes_since("dbo.Products", last_sync).await?;
+/// }
+/// ```
+pub struct ChangeTrackingClient<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+}
+
+impl<'a, S: ConnectionState> ChangeTrackingClient<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self { client }
+    }
+
+    /// Get the current change tracking version of the database.
+    ///
+    /// Returns `None` if change tracking is not enabled on the database.
+    pub async fn current_version(&mut self) -> Result<Option<i64>> {
+        self.fetch_version(ChangeTracking::current_version_sql(), &[])
+            .await
+    }
+
+    /// Get the minimum version still available for a table.
+    ///
+    /// Returns `None` if the table does not exist or is not tracked.
+    pub async fn min_valid_version(&mut self, table: &str) -> Result<Option<i64>> {
+        let table = quote_schema_qualified(table);
+        self.fetch_version(ChangeTracking::min_valid_version_query(), &[&table])
+            .await
+    }
+
+    /// Check whether changes since `last_sync_version` can still be read.
+    pub async fn sync_status(
+        &mut self,
+        table: &str,
+        last_sync_version: i64,
+    ) -> Result<SyncVersionStatus> {
+        let min_valid_version = self.min_valid_version(table).await?;
+        Ok(SyncVersionStatus::check(
+            last_sync_version,
+            min_valid_version,
+        ))
+    }
+
+    /// Get the rows of a table changed since a version.
+    ///
+    /// Each row carries the change tracking metadata and the primary key
+    /// columns returned by `CHANGETABLE`, which `T` is mapped from. Check
+    /// [`sync_status`](Self::sync_status) first: changes older than the
+    /// minimum valid version have been cleaned up.
+    pub async fn changes_since<T: FromRow>(
+        &mut self,
+        table: &str,
+        last_sync_version: i64,
+    ) -> Result<Vec<ChangedRow<T>>> {
+        let sql = changes_since_sql(table);
+        let rows = self.client.fetch_rows(&sql, &[&last_sync_version]).await?;
+        rows.iter().map(ChangedRow::from_row).collect()
+    }
+
+    /// Run a [`ChangeTrackingQuery`], binding its version as a parameter.
+    ///
+    /// Each row carries the change tracking metadata, and `T` is mapped from
+    /// the primary key and data columns the query selects.
+    pub async fn query<T: FromRow>(
+        &mut self,
+        query: &ChangeTrackingQuery,
+    ) -> Result<Vec<ChangedRow<T>>> {
+        let sql = query.to_parameterized_sql();
+        let rows = self
+            .client
+            .fetch_rows(&sql, &[&query.last_sync_version()])
+            .await?;
+        rows.iter().map(ChangedRow::from_row).collect()
+    }
+
+    /// Run a [`ChangeTrackingQuery`] joined with the current table data,
+    /// binding its version as a parameter.
+    ///
+    /// See [`ChangeTrackingQuery::to_sql_with_data`]. The data columns are
+    /// `NULL` for deleted rows.
+    pub async fn query_with_data<T: FromRow>(
+        &mut self,
+        query: &ChangeTrackingQuery,
+        data_columns: &[&str],
+    ) -> Result<Vec<ChangedRow<T>>> {
+        let sql = query.to_parameterized_sql_with_data(data_columns);
+        let rows = self
+            .client
+            .fetch_rows(&sql, &[&query.last_sync_version()])
+            .await?;
+        rows.iter().map(ChangedRow::from_row).collect()
+    }
+
+    /// Check whether `column` of `table` changed according to a
+    /// `SYS_CHANGE_COLUMNS` mask.
+    ///
+    /// The table, column and mask are bound as parameters.
+    pub async fn is_column_in_mask(
+        &mut self,
+        table: &str,
+        column: &str,
+        mask: &[u8],
+    ) -> Result<bool> {
+        let table = quote_schema_qualified(table);
+        let rows = self
+            .client
+            .fetch_rows(
+                ChangeTracking::column_in_mask_query(),
+                &[&table, &column, &mask],
+            )
+            .await?;
+        match rows.first() {
+            Some(row) => Ok(row.get::<Option<i32>>(0)? == Some(1)),
+            None => Ok(false),
+        }
+    }
+
+    async fn fetch_version(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Option<i64>> {
+        let rows = self.client.fetch_rows(sql, params).await?;
+        match rows.first() {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<S: ConnectionState> fmt::Debug for ChangeTrackingClient<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeTrackingClient")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Persists the last synchronized change tracking version per table.
+///
+/// Implement this over whatever the application already uses for sync
+/// state: a local file, a key-value store, or a table on the client side.
+#[allow(async_fn_in_trait)]
+pub trait SyncVersionStore {
+    /// Load the last synchronized version, or `None` before the first sync.
+    async fn load(&mut self, table: &str) -> Result<Option<i64>>;
+
+    /// Save the version a sync has caught up to.
+    async fn save(&mut self, table: &str, version: i64) -> Result<()>;
+}
+
+/// A [`SyncVersionStore`] that keeps versions in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryVersionStore {
+    versions: HashMap<String, i64>,
+}
+
+impl MemoryVersionStore {
+    /// Create an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the stored version for a table.
+    #[must_use]
+    pub fn get(&self, table: &str) -> Option<i64> {
+        self.versions.get(table).copied()
+    }
+}
+
+impl SyncVersionStore for MemoryVersionStore {
+    async fn load(&mut self, table: &str) -> Result<Option<i64>> {
+        Ok(self.get(table))
+    }
+
+    async fn save(&mut self, table: &str, version: i64) -> Result<()> {
+        self.versions.insert(table.to_string(), version);
+        Ok(())
+    }
+}
+
+/// The rows read by one [`SyncSession::sync`].
+#[derive(Debug, Clone)]
+pub enum SyncData<T> {
+    /// Rows changed since the last sync.
+    Changes(Vec<ChangedRow<T>>),
+    /// Every row of the table, because there was no usable previous version.
+    ///
+    /// Replace the local copy with these rows.
+    Snapshot(Vec<T>),
+}
+
+/// The result of one [`SyncSession::sync`].
+#[derive(Debug, Clone)]
+pub struct SyncOutcome<T> {
+    /// Version stored before this sync, if any.
+    pub previous_version: Option<i64>,
+    /// Version this sync caught up to, now saved in the store.
+    pub version: i64,
+    /// The rows read.
+    pub data: SyncData<T>,
+}
+
+impl<T> SyncOutcome<T> {
+    /// Check if this sync fell back to a full snapshot.
+    #[must_use]
+    pub fn is_full_sync(&self) -> bool {
+        matches!(self.data, SyncData::Snapshot(_))
+    }
+}
+
+/// Incremental change tracking sync for one table.
+///
+/// Each [`sync`](Self::sync) runs in a snapshot isolation transaction, so the
+/// version it saves matches exactly the rows it read. When there is no stored
+/// version, or the stored one is older than
+/// `CHANGE_TRACKING_MIN_VALID_VERSION`, the session reads a full snapshot
+/// instead of changes.
+///
+/// Snapshot isolation must be allowed on the database
+/// (`ALTER DATABASE ... SET ALLOW_SNAPSHOT_ISOLATION ON`).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use mssql_client::change_tracking::{MemoryVersionStore, SyncData, SyncSession};
+///
+/// let mut session = SyncSession::new("dbo.Products", MemoryVersionStore::new());
+/// let (client, outcome) = session.sync::<ProductKey>(client).await?;
+/// match outcome.data {
+///     SyncData::Changes(changes) => apply_changes(changes),
+///     SyncData::Snapshot(rows) => replace_all(rows),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SyncSession<St: SyncVersionStore> {
+    table: String,
+    store: St,
+    snapshot_sql: Option<String>,
+}
+
+impl<St: SyncVersionStore> SyncSession<St> {
+    /// Create a session for a table, which may be schema-qualified.
+    pub fn new(table: impl Into<String>, store: St) -> Self {
+        Self {
+            table: table.into(),
+            store,
+            snapshot_sql: None,
+        }
+    }
+
+    /// Set the query used for a full sync.
+    ///
+    /// Defaults to `SELECT * FROM <table>`.
+    #[must_use]
+    pub fn with_snapshot_query(mut self, sql: impl Into<String>) -> Self {
+        self.snapshot_sql = Some(sql.into());
+        self
+    }
+
+    /// Get the version store.
+    pub fn store(&self) -> &St {
+        &self.store
+    }
+
+    /// Read what changed since the last sync and save the new version.
+    ///
+    /// The version is saved only after the transaction commits. On error
+    /// the transaction is rolled back and the client is dropped.
+    pub async fn sync<T: FromRow>(
+        &mut self,
+        client: Client<Ready>,
+    ) -> Result<(Client<Ready>, SyncOutcome<T>)> {
+        let previous_version = self.store.load(&self.table).await?;
+        let mut tx = client
+            .begin_transaction_with_isolation(IsolationLevel::Snapshot)
+            .await?;
+
+        let result = self.read(&mut tx, previous_version).await;
+        let (version, data) = match result {
+            Ok(read) => read,
+            Err(e) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    tracing::warn!(error = %rollback_err, "rollback after failed sync failed");
+                }
+                return Err(e);
+            }
+        };
+        let client = tx.commit().await?;
+
+        self.store.save(&self.table, version).await?;
+        tracing::debug!(
+            table = %self.table,
+            previous_version,
+            version,
+            full_sync = matches!(data, SyncData::Snapshot(_)),
+            "change tracking sync complete"
+        );
+
+        Ok((
+            client,
+            SyncOutcome {
+                previous_version,
+                version,
+                data,
+            },
+        ))
+    }
+
+    async fn read<T: FromRow>(
+        &self,
+        tx: &mut Client<InTransaction>,
+        previous_version: Option<i64>,
+    ) -> Result<(i64, SyncData<T>)> {
+        let mut ct = tx.change_tracking();
+        let version = ct
+            .current_version()
+            .await?
+            .ok_or_else(|| Error::Query("change tracking is not enabled on the database".into()))?;
+
+        if let Some(previous) = previous_version {
+            match ct.sync_status(&self.table, previous).await? {
+                SyncVersionStatus::Valid => {
+                    let changes = ct.changes_since(&self.table, previous).await?;
+                    return Ok((version, SyncData::Changes(changes)));
+                }
+                SyncVersionStatus::TooOld => {
+                    tracing::info!(
+                        table = %self.table,
+                        previous_version = previous,
+                        "sync version too old, falling back to full sync"
+                    );
+                }
+                SyncVersionStatus::NotEnabled => {
+                    return Err(Error::Query(format!(
+                        "change tracking is not enabled on table '{}'",
+                        self.table
+                    )));
+                }
+            }
+        }
+
+        let sql = self
+            .snapshot_sql
+            .clone()
+            .unwrap_or_else(|| format!("SELECT * FROM {}", quote_schema_qualified(&self.table)));
+        let rows = tx.fetch_rows(&sql, &[]).await?;
+        let rows = rows.iter().map(T::from_row).collect::<Result<_>>()?;
+        Ok((version, SyncData::Snapshot(rows)))
+    }
+}
+
+/// Quote a name as an `N'...'` string literal.
+fn name_literal(name: &str) -> String {
+    format!("N'{}'", name.replace('\'', "''"))
+}
+
+fn changes_since_sql(table: &str) -> String {
+    format!(
+        "SELECT CT.* FROM CHANGETABLE(CHANGES {}, @p1) AS CT \
+         ORDER BY CT.SYS_CHANGE_VERSION",
+        quote_schema_qualified(table)
+    )
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -690,7 +1215,7 @@ mod tests {
         let query = ChangeTrackingQuery::changes("Products", 42);
         let sql = query.to_sql();
 
-        assert!(sql.contains("CHANGETABLE(CHANGES Products, 42)"));
+        assert!(sql.contains("CHANGETABLE(CHANGES [Products], 42) AS [CT]"));
         assert!(sql.contains("SYS_CHANGE_VERSION"));
         assert!(sql.contains("SYS_CHANGE_OPERATION"));
     }
@@ -700,8 +1225,8 @@ mod tests {
         let query = ChangeTrackingQuery::changes("Products", 42).with_columns(&["Name", "Price"]);
         let sql = query.to_sql();
 
-        assert!(sql.contains("CT.Name"));
-        assert!(sql.contains("CT.Price"));
+        assert!(sql.contains("[CT].[Name]"));
+        assert!(sql.contains("[CT].[Price]"));
     }
 
     #[test]
@@ -709,7 +1234,28 @@ mod tests {
         let query = ChangeTrackingQuery::changes("Products", 42).with_primary_keys(&["ProductId"]);
         let sql = query.to_sql();
 
-        assert!(sql.contains("CT.ProductId"));
+        assert!(sql.contains("[CT].[ProductId]"));
+    }
+
+    #[test]
+    fn test_change_tracking_query_parameterized() {
+        let query = ChangeTrackingQuery::changes("Products", 42).with_primary_keys(&["ProductId"]);
+        assert_eq!(query.last_sync_version(), 42);
+
+        let sql = query.to_parameterized_sql();
+        assert!(sql.contains("CHANGETABLE(CHANGES [Products], @p1) AS [CT]"));
+        assert!(!sql.contains("42"));
+        assert_eq!(sql.replace("@p1", "42"), query.to_sql());
+
+        let sql = query.to_parameterized_sql_with_data(&["Name"]);
+        assert!(sql.contains("CHANGETABLE(CHANGES [Products], @p1) AS [CT]"));
+        assert_eq!(sql.replace("@p1", "42"), query.to_sql_with_data(&["Name"]));
+
+        assert_eq!(
+            ChangeTracking::column_in_mask_query(),
+            "SELECT CHANGE_TRACKING_IS_COLUMN_IN_MASK(\
+             COLUMNPROPERTY(OBJECT_ID(@p1), @p2, 'ColumnId'), @p3)"
+        );
     }
 
     #[test]
@@ -725,10 +1271,24 @@ mod tests {
         let query = ChangeTrackingQuery::changes("Products", 42).with_primary_keys(&["ProductId"]);
         let sql = query.to_sql_with_data(&["Name", "Price"]);
 
-        assert!(sql.contains("LEFT OUTER JOIN Products AS T"));
-        assert!(sql.contains("CT.ProductId = T.ProductId"));
-        assert!(sql.contains("T.Name"));
-        assert!(sql.contains("T.Price"));
+        assert!(sql.contains("LEFT OUTER JOIN [Products] AS T"));
+        assert!(sql.contains("[CT].[ProductId] = T.[ProductId]"));
+        assert!(sql.contains("T.[Name]"));
+        assert!(sql.contains("T.[Price]"));
+    }
+
+    #[test]
+    fn test_change_tracking_query_quotes_names() {
+        let query = ChangeTrackingQuery::changes("sales.Order Details", 1)
+            .with_primary_keys(&["Order]Id"])
+            .with_columns(&["Unit Price"]);
+        let sql = query.to_sql();
+        assert!(sql.contains("CHANGETABLE(CHANGES [sales].[Order Details], 1)"));
+        assert!(sql.contains("[CT].[Order]]Id]"));
+        assert!(sql.contains("[CT].[Unit Price]"));
+
+        let sql = query.to_sql_with_data(&["x]; DROP TABLE t; --"]);
+        assert!(sql.contains("T.[x]]; DROP TABLE t; --]"));
     }
 
     #[test]
@@ -740,11 +1300,14 @@ mod tests {
 
         let min_sql = ChangeTracking::min_valid_version_sql("Products");
         assert!(min_sql.contains("CHANGE_TRACKING_MIN_VALID_VERSION"));
-        assert!(min_sql.contains("Products"));
+        assert!(min_sql.contains("OBJECT_ID(N'[Products]')"));
+
+        let min_sql = ChangeTracking::min_valid_version_sql("O'Brien.Orders");
+        assert!(min_sql.contains("OBJECT_ID(N'[O''Brien].[Orders]')"));
 
         let mask_sql = ChangeTracking::column_in_mask_sql("Products", "Price", "@mask");
         assert!(mask_sql.contains("CHANGE_TRACKING_IS_COLUMN_IN_MASK"));
-        assert!(mask_sql.contains("Price"));
+        assert!(mask_sql.contains("N'Price'"));
         assert!(mask_sql.contains("@mask"));
     }
 
@@ -758,6 +1321,15 @@ mod tests {
         let table_sql = ChangeTracking::enable_table_sql("Products", true);
         assert!(table_sql.contains("[Products]"));
         assert!(table_sql.contains("TRACK_COLUMNS_UPDATED = ON"));
+
+        assert_eq!(
+            ChangeTracking::disable_table_sql("dbo.Odd]Name"),
+            "ALTER TABLE [dbo].[Odd]]Name] DISABLE CHANGE_TRACKING"
+        );
+        assert_eq!(
+            ChangeTracking::disable_database_sql("My DB"),
+            "ALTER DATABASE [My DB] SET CHANGE_TRACKING = OFF"
+        );
     }
 
     #[test]
@@ -774,4 +1346,23 @@ mod tests {
         assert_eq!(status, SyncVersionStatus::NotEnabled);
         assert!(!status.can_sync_incrementally());
     }
+
+    #[test]
+    fn test_changes_since_sql() {
+        assert_eq!(
+            changes_since_sql("dbo.Products"),
+            "SELECT CT.* FROM CHANGETABLE(CHANGES [dbo].[Products], @p1) AS CT \
+             ORDER BY CT.SYS_CHANGE_VERSION"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_version_store() {
+        let mut store = MemoryVersionStore::new();
+        assert_eq!(store.load("dbo.Products").await.unwrap(), None);
+
+        store.save("dbo.Products", 42).await.unwrap();
+        assert_eq!(store.load("dbo.Products").await.unwrap(), Some(42));
+        assert_eq!(store.get("dbo.Orders"), None);
+    }
 }
@@ -6,35 +6,79 @@
 
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
 
 use bytes::BytesMut;
 use mssql_codec::connection::Connection;
+use mssql_codec::{AsyncTransport, BufferPool, TdsCodec};
 use mssql_tls::{TlsConfig, TlsConnector, TlsNegotiationMode, TlsStream};
-use tds_protocol::login7::Login7;
+use tds_protocol::login7::{FeatureExtension, Login7};
 use tds_protocol::packet::{MAX_PACKET_SIZE, PacketType};
 use tds_protocol::prelogin::{EncryptionLevel, PreLogin};
-use tds_protocol::rpc::{RpcParam, RpcRequest, TypeInfo as RpcTypeInfo};
+use tds_protocol::rpc::{ParamDirection, RpcParam, RpcRequest, TypeInfo as RpcTypeInfo};
 use tds_protocol::token::{
-    ColMetaData, Collation, ColumnData, EnvChange, EnvChangeType, NbcRow, RawRow, Token,
-    TokenParser,
+    ColInfo, ColMetaData, Collation, ColumnData, ColumnEncryptionAck, EnvChange, EnvChangeType,
+    FeatureExtAck, NbcRow, RawRow, ReturnValue, TabName, Token, TokenParser,
 };
 #[cfg(feature = "decimal")]
 use tds_protocol::tvp::encode_tvp_decimal;
 use tds_protocol::tvp::{
-    TvpColumnDef as TvpWireColumnDef, TvpColumnFlags, TvpEncoder, TvpWireType, encode_tvp_bit,
-    encode_tvp_float, encode_tvp_int, encode_tvp_null, encode_tvp_nvarchar, encode_tvp_varbinary,
+    DEFAULT_COLLATION, TVP_ORDER_ASC, TVP_ORDER_DESC, TVP_UNIQUE, TvpColumnDef as TvpWireColumnDef,
+    TvpColumnFlags, TvpEncoder, TvpOrderUnique, TvpWireType, encode_tvp_bit, encode_tvp_float,
+    encode_tvp_int, encode_tvp_null, encode_tvp_nvarchar, encode_tvp_varbinary,
 };
 use tokio::net::TcpStream;
 use tokio::time::timeout;
 
-use crate::config::Config;
-use crate::error::{Error, Result};
+use crate::admin::Admin;
+use crate::availability::AvailabilityGroups;
+use crate::backup::{BackupOperation, BackupOptions, RestoreOptions};
+use crate::batch::{Batch, BatchCollector, BatchMode, BatchResult, BatchStatement};
+use crate::cdc::ChangeDataCapture;
+use crate::change_tracking::ChangeTrackingClient;
+use crate::config::{Config, ProxyConfig};
+use crate::cursor::{Cursor, CursorOptions, CursorResponse};
+use crate::diagnostics::Diagnostics;
+use crate::dns_cache::DnsCache;
+use crate::encryption::ColumnDecryptor;
+use crate::error::{DatabaseError, Error, Result};
+use crate::in_list::{ExpandedQuery, MAX_PARAMS, expand_in, expand_in_tvp};
+use crate::instrumentation::ConnectTimings;
 #[cfg(feature = "otel")]
 use crate::instrumentation::InstrumentationContext;
+use crate::merge::MergeBuilder;
+use crate::message::{MessageHandler, ServerMessage};
+use crate::query::QueryOptions;
+use crate::schema::SchemaInspector;
+use crate::script::{Script, ScriptBatchResult, ScriptResult};
+use crate::service_broker::ServiceBroker;
 use crate::state::{ConnectionState, Disconnected, InTransaction, Ready};
-use crate::statement_cache::StatementCache;
-use crate::stream::{MultiResultStream, QueryStream};
+use crate::statement_cache::{PreparedStatement, StatementCache};
+use crate::statistics::QueryStatistics;
+use crate::stream::{
+    ExecuteResult, MultiResultStream, OutputParam, QueryStream, RowStream, RowStreamState,
+};
+use crate::temp_table::TempTable;
 use crate::transaction::SavePoint;
+use crate::transport::Transport;
+
+/// Statement appended by `execute_with_identity` to read the generated identity.
+///
+/// `SCOPE_IDENTITY()` is limited to the current scope, so identities generated
+/// by triggers on the target table are not returned.
+const IDENTITY_QUERY: &str = "SELECT CAST(SCOPE_IDENTITY() AS BIGINT)";
+
+/// Batch run by `execute_in` before the statements of a split list.
+///
+/// The nested `BEGIN` leaves an outer transaction open on `COMMIT`, and the
+/// savepoint lets a failure undo only these statements.
+const IN_LIST_BEGIN: &str = "BEGIN TRANSACTION; SAVE TRANSACTION mssql_in_list";
+
+/// Batch run by `execute_in` when a statement of a split list fails.
+const IN_LIST_ROLLBACK: &str = "IF XACT_STATE() = 1 \
+     BEGIN ROLLBACK TRANSACTION mssql_in_list; COMMIT TRANSACTION; END \
+     ELSE IF XACT_STATE() = -1 ROLLBACK TRANSACTION";
 
 /// SQL Server client with type-state connection management.
 ///
@@ -48,8 +92,8 @@ pub struct Client<S: ConnectionState> {
     connection: Option<ConnectionHandle>,
     /// Server version from LoginAck (raw u32 TDS version)
     server_version: Option<u32>,
-    /// Current database from EnvChange
-    current_database: Option<String>,
+    /// Session settings from EnvChange
+    session: SessionEnv,
     /// Prepared statement cache for query optimization
     statement_cache: StatementCache,
     /// Transaction descriptor from BeginTransaction EnvChange.
@@ -60,11 +104,131 @@ pub struct Client<S: ConnectionState> {
     /// Set by connection pool on checkin, cleared after first query/execute.
     /// When true, the RESETCONNECTION flag is set on the first TDS packet.
     needs_reset: bool,
+    /// Statements to run before the next request, such as dropping the
+    /// table of a dropped [`TempTable`] guard.
+    deferred_cleanup: Vec<String>,
+    /// Impersonations of `execute_as_*` futures dropped before their
+    /// `REVERT`, dealt with before the next request.
+    abandoned_impersonations: Arc<AbandonedImpersonations>,
+    /// Per-query [`QueryOptions::max_lob_size`] for the response to the
+    /// request in flight, overriding [`Config::max_lob_size()`].
+    request_max_lob_size: Option<u64>,
+    /// How far the current request got, for recovering from a request
+    /// whose future was dropped.
+    request_state: RequestState,
+    /// Duplicate of the TCP socket, for noticing a closed connection
+    /// without a round trip.
+    socket_probe: Option<crate::socket::SocketProbe>,
+    /// Whether reading or writing the connection failed; the stream is then
+    /// in an unknown state.
+    broken: bool,
+    /// Callback for informational messages (PRINT, RAISERROR WITH NOWAIT)
+    message_handler: Option<MessageHandler>,
+    /// Informational messages received during the most recent request
+    messages: Vec<ServerMessage>,
+    /// Column encryption acknowledged by the server, if negotiated. Once
+    /// acknowledged, COLMETADATA carries crypto metadata.
+    column_encryption: Option<ColumnEncryptionAck>,
+    /// Phase durations of opening this connection.
+    connect_timings: ConnectTimings,
+    /// Enclave package for the next RPC request, set when its parameters
+    /// are encrypted for a query that needs keys inside the enclave.
+    #[cfg(feature = "always-encrypted")]
+    enclave_package: Option<bytes::Bytes>,
     /// OpenTelemetry instrumentation context (when otel feature is enabled)
     #[cfg(feature = "otel")]
     instrumentation: InstrumentationContext,
 }
 
+/// Session settings reported by the server at login and in ENVCHANGE tokens.
+#[derive(Debug, Clone, Default)]
+struct SessionEnv {
+    /// Current database.
+    database: Option<String>,
+    /// Current language.
+    language: Option<String>,
+    /// Default collation of the current database.
+    collation: Option<Collation>,
+    /// Whether the server acknowledged AZURESQLDNSCACHING at login.
+    dns_caching: bool,
+}
+
+/// Progress of the request on a connection.
+///
+/// A request future dropped part-way through leaves the connection in
+/// `Sending` or `AwaitingResponse`, and the next request recovers from it
+/// first; see [`Client::drain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RequestState {
+    /// No request in flight.
+    Idle,
+    /// A request is being written.
+    Sending,
+    /// A request was sent and its response not yet fully read.
+    AwaitingResponse,
+}
+
+/// Sends an Attention packet when dropped, unless disarmed.
+///
+/// Held while a response is read, so a dropped read cancels the request
+/// on the server instead of leaving it to run.
+struct AttentionOnDrop(Option<crate::cancel::CancelHandle>);
+
+impl AttentionOnDrop {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for AttentionOnDrop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            if handle.cancel_in_background() {
+                tracing::debug!("response read dropped, cancelling request");
+            }
+        }
+    }
+}
+
+/// Impersonations left behind by dropped `execute_as_*` futures.
+#[derive(Debug, Default)]
+struct AbandonedImpersonations {
+    /// `EXECUTE AS` contexts known to be in effect.
+    reverts: AtomicU32,
+    /// Whether a future was dropped with its `EXECUTE AS` or `REVERT` in
+    /// flight, so whether the session is impersonating is unknown.
+    unknown: AtomicBool,
+}
+
+/// Records an impersonation as abandoned if the `execute_as_*` future is
+/// dropped before it reverts.
+struct RevertOnDrop {
+    abandoned: Option<Arc<AbandonedImpersonations>>,
+    /// Whether the `EXECUTE AS` completed and the `REVERT` was not sent.
+    in_effect: bool,
+}
+
+impl RevertOnDrop {
+    fn disarm(mut self) {
+        self.abandoned = None;
+    }
+}
+
+impl Drop for RevertOnDrop {
+    fn drop(&mut self) {
+        let Some(abandoned) = self.abandoned.take() else {
+            return;
+        };
+        if self.in_effect {
+            tracing::debug!("impersonation dropped before REVERT, reverting before next request");
+            abandoned.reverts.fetch_add(1, Ordering::AcqRel);
+        } else {
+            tracing::debug!("impersonation dropped with a request in flight");
+            abandoned.unknown.store(true, Ordering::Release);
+        }
+    }
+}
+
 /// Internal connection handle wrapping the actual connection.
 ///
 /// This is an enum to support different connection types:
@@ -74,11 +238,165 @@ pub struct Client<S: ConnectionState> {
 #[allow(dead_code)] // Connection will be used once query execution is implemented
 enum ConnectionHandle {
     /// TLS connection (TDS 8.0 strict mode - TLS before any TDS traffic)
-    Tls(Connection<TlsStream<TcpStream>>),
+    Tls(Connection<TlsStream<Transport>>),
     /// TLS connection with PreLogin wrapping (TDS 7.x style)
-    TlsPrelogin(Connection<TlsStream<mssql_tls::TlsPreloginWrapper<TcpStream>>>),
-    /// Plain TCP connection (rare, for testing or internal networks)
-    Plain(Connection<TcpStream>),
+    TlsPrelogin(Connection<TlsStream<mssql_tls::TlsPreloginWrapper<Transport>>>),
+    /// Plain connection (rare, for testing or internal networks)
+    Plain(Connection<Transport>),
+}
+
+impl ConnectionHandle {
+    /// Get the negotiated packet size.
+    fn packet_size(&self) -> usize {
+        match self {
+            Self::Tls(conn) => conn.packet_size(),
+            Self::TlsPrelogin(conn) => conn.packet_size(),
+            Self::Plain(conn) => conn.packet_size(),
+        }
+    }
+
+    /// Send a request message.
+    async fn send_request(
+        &mut self,
+        packet_type: PacketType,
+        payload: bytes::Bytes,
+        reset: bool,
+    ) -> Result<()> {
+        let max_packet = self.packet_size();
+        match self {
+            Self::Tls(conn) => {
+                conn.send_message_with_reset(packet_type, payload, max_packet, reset)
+                    .await
+            }
+            Self::TlsPrelogin(conn) => {
+                conn.send_message_with_reset(packet_type, payload, max_packet, reset)
+                    .await
+            }
+            Self::Plain(conn) => {
+                conn.send_message_with_reset(packet_type, payload, max_packet, reset)
+                    .await
+            }
+        }
+        .map_err(|e| Error::Protocol(e.to_string()))
+    }
+
+    /// Read the next complete message.
+    async fn read_message(&mut self) -> Result<Option<mssql_codec::Message>> {
+        match self {
+            Self::Tls(conn) => conn.read_message().await,
+            Self::TlsPrelogin(conn) => conn.read_message().await,
+            Self::Plain(conn) => conn.read_message().await,
+        }
+        .map_err(|e| Error::Protocol(e.to_string()))
+    }
+
+    /// Read the next packet.
+    async fn read_packet(&mut self) -> Result<Option<mssql_codec::Packet>> {
+        match self {
+            Self::Tls(conn) => conn.read_packet().await,
+            Self::TlsPrelogin(conn) => conn.read_packet().await,
+            Self::Plain(conn) => conn.read_packet().await,
+        }
+        .map_err(|e| Error::Protocol(e.to_string()))
+    }
+
+    /// Check whether an Attention packet was sent and not yet acknowledged.
+    fn is_cancelling(&self) -> bool {
+        match self {
+            Self::Tls(conn) => conn.is_cancelling(),
+            Self::TlsPrelogin(conn) => conn.is_cancelling(),
+            Self::Plain(conn) => conn.is_cancelling(),
+        }
+    }
+
+    /// Get a type-erased cancel handle.
+    fn cancel_handle(&self) -> crate::cancel::CancelHandle {
+        match self {
+            Self::Tls(conn) => crate::cancel::CancelHandle::from_tls(conn.cancel_handle()),
+            Self::TlsPrelogin(conn) => {
+                crate::cancel::CancelHandle::from_tls_prelogin(conn.cancel_handle())
+            }
+            Self::Plain(conn) => crate::cancel::CancelHandle::from_plain(conn.cancel_handle()),
+        }
+    }
+
+    /// Apply a packet size renegotiated by the server.
+    async fn set_packet_size(&mut self, size: usize) {
+        match self {
+            Self::Tls(conn) => conn.set_packet_size(size).await,
+            Self::TlsPrelogin(conn) => conn.set_packet_size(size).await,
+            Self::Plain(conn) => conn.set_packet_size(size).await,
+        }
+    }
+}
+
+/// Get the packet size from a PacketSize EnvChange token.
+fn negotiated_packet_size(env: &EnvChange) -> Option<usize> {
+    use tds_protocol::token::EnvChangeValue;
+
+    match (&env.env_type, &env.new_value) {
+        (EnvChangeType::PacketSize, EnvChangeValue::String(value)) => value.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Get the COLUMNENCRYPTION version to request at login, if any.
+fn requested_column_encryption(config: &Config) -> Option<u8> {
+    #[cfg(feature = "always-encrypted")]
+    if let Some(context) = &config.column_encryption {
+        return Some(if context.enclave_attestation().is_some() {
+            tds_protocol::login7::COLUMN_ENCRYPTION_VERSION_2
+        } else {
+            tds_protocol::login7::COLUMN_ENCRYPTION_VERSION_1
+        });
+    }
+
+    #[cfg(not(feature = "always-encrypted"))]
+    let _ = config;
+    None
+}
+
+/// Get the column encryption version agreed in a FEATUREEXTACK token.
+///
+/// The server may acknowledge a lower version than requested, never a
+/// higher one or one that was not requested.
+fn negotiated_column_encryption(
+    requested: Option<u8>,
+    ack: &FeatureExtAck,
+) -> Result<Option<ColumnEncryptionAck>> {
+    let Some(ack) = ack
+        .column_encryption()
+        .map_err(|e| Error::Protocol(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+
+    match requested {
+        Some(requested) if ack.version <= requested => {
+            tracing::debug!(
+                version = ack.version,
+                enclave_type = ?ack.enclave_type,
+                "column encryption negotiated"
+            );
+            Ok(Some(ack))
+        }
+        _ => Err(Error::Protocol(format!(
+            "server acknowledged unrequested column encryption version {}",
+            ack.version
+        ))),
+    }
+}
+
+/// Wrap a transport in a connection whose codecs pool buffers, trace, and
+/// capture packets as configured.
+fn new_connection<T: AsyncTransport>(transport: T, config: &Config) -> Connection<T> {
+    let mut codec = TdsCodec::new()
+        .with_buffer_pool(BufferPool::new(config.buffer_pool_size, MAX_PACKET_SIZE))
+        .with_packet_tracing(config.trace_packets);
+    if let Some(capture) = &config.packet_capture {
+        codec = codec.with_capture(capture.clone());
+    }
+    Connection::with_codecs(transport, codec.clone(), codec)
 }
 
 impl Client<Disconnected> {
@@ -93,6 +411,36 @@ impl Client<Disconnected> {
     /// let client = Client::connect(config).await?;
     /// ```
     pub async fn connect(config: Config) -> Result<Client<Ready>> {
+        let started = Instant::now();
+
+        #[cfg(feature = "otel")]
+        let instrumentation = InstrumentationContext::new(config.host.clone(), config.port);
+        #[cfg(feature = "otel")]
+        let mut span = instrumentation.connection_span();
+
+        let result = Self::connect_with_redirects(config).await;
+
+        #[cfg(feature = "otel")]
+        match &result {
+            Ok(client) => {
+                InstrumentationContext::record_connect_timings(&mut span, &client.connect_timings);
+                InstrumentationContext::record_success(&mut span, None);
+            }
+            Err(e) => InstrumentationContext::record_error(&mut span, e),
+        }
+
+        let mut client = result?;
+        if let Some(sql) = client.config.session_sql() {
+            tracing::debug!(sql = %sql, "applying session options");
+            client.simple_query(&sql).await?;
+        }
+        client.connect_timings.total = started.elapsed();
+        tracing::debug!(timings = ?client.connect_timings, "connection opened");
+        Ok(client)
+    }
+
+    /// Connect, following Azure SQL routing redirects.
+    async fn connect_with_redirects(config: Config) -> Result<Client<Ready>> {
         let max_redirects = config.redirect.max_redirects;
         let follow_redirects = config.redirect.follow_redirects;
         let mut attempts = 0;
@@ -105,7 +453,10 @@ impl Client<Disconnected> {
             }
 
             match Self::try_connect(&current_config).await {
-                Ok(client) => return Ok(client),
+                Ok(mut client) => {
+                    client.connect_timings.redirects = u32::from(attempts - 1);
+                    return Ok(client);
+                }
                 Err(Error::Routing { host, port }) => {
                     if !follow_redirects {
                         return Err(Error::Routing { host, port });
@@ -133,71 +484,280 @@ impl Client<Disconnected> {
             "connecting to SQL Server"
         );
 
-        let addr = format!("{}:{}", config.host, config.port);
+        let mut timings = ConnectTimings::default();
 
-        // Step 1: Establish TCP connection
-        tracing::debug!("establishing TCP connection to {}", addr);
-        let tcp_stream = timeout(config.timeouts.connect_timeout, TcpStream::connect(&addr))
+        // Step 1: Establish TCP or named pipe connection
+        let stream = Self::open_transport(config, &mut timings).await?;
+        // Through a proxy the peer is the proxy, not the server
+        let peer_addr = stream.peer_addr().filter(|_| config.proxy.is_none());
+        let socket_probe = stream.probe();
+
+        // Determine TLS negotiation mode
+        let tls_mode = TlsNegotiationMode::from_encrypt_mode(config.strict_mode);
+
+        let handshake_started = Instant::now();
+        let mut client = if tls_mode.is_tls_first() {
+            // Step 2: Handle TDS 8.0 strict mode (TLS before any TDS traffic)
+            Self::connect_tds_8(config, stream, &mut timings).await?
+        } else {
+            // Step 3: TDS 7.x flow - PreLogin first, then TLS, then Login7
+            Self::connect_tds_7x(config, stream, &mut timings).await?
+        };
+
+        // Login is whatever the handshake spent outside TLS and PreLogin
+        timings.login = handshake_started
+            .elapsed()
+            .saturating_sub(timings.tls.unwrap_or_default())
+            .saturating_sub(timings.prelogin);
+        client.connect_timings = timings;
+        client.socket_probe = socket_probe;
+
+        // Remember the address for reconnects if the server allows it
+        if client.session.dns_caching {
+            if let Some(addr) = peer_addr {
+                tracing::debug!(host = %config.host, %addr, "caching server address");
+                DnsCache::global().insert(&config.host, config.port, addr);
+            }
+        }
+
+        Ok(client)
+    }
+
+    /// Open the transport to the configured server.
+    ///
+    /// Uses the [`Config::transport`] factory, the named pipe in
+    /// [`Config::named_pipe`] or that of the LocalDB instance in
+    /// [`Config::localdb`] if set, TCP otherwise.
+    async fn open_transport(config: &Config, timings: &mut ConnectTimings) -> Result<Transport> {
+        if let Some(factory) = &config.transport {
+            tracing::debug!(host = %config.host, port = config.port, "opening custom transport");
+            let started = Instant::now();
+            let stream = timeout(
+                config.timeouts.connect_timeout,
+                factory.connect(&config.host, config.port),
+            )
             .await
             .map_err(|_| Error::ConnectTimeout)?
             .map_err(|e| Error::Io(Arc::new(e)))?;
+            timings.tcp = started.elapsed();
+            return Ok(Transport::Custom(stream));
+        }
 
-        // Enable TCP nodelay for better latency
-        tcp_stream
-            .set_nodelay(true)
+        let path = match (&config.named_pipe, &config.localdb) {
+            (Some(path), _) => path.clone(),
+            (None, Some(instance)) => Self::localdb_pipe(instance, timings).await?,
+            (None, None) => return Ok(Self::connect_tcp(config, timings).await?.into()),
+        };
+
+        #[cfg(all(windows, feature = "named-pipes"))]
+        {
+            tracing::debug!(pipe = %path, "opening named pipe");
+            let started = Instant::now();
+            let pipe = timeout(
+                config.timeouts.connect_timeout,
+                crate::transport::open_named_pipe(path),
+            )
+            .await
+            .map_err(|_| Error::ConnectTimeout)?
             .map_err(|e| Error::Io(Arc::new(e)))?;
+            timings.tcp = started.elapsed();
+            Ok(Transport::NamedPipe(pipe))
+        }
 
-        // Determine TLS negotiation mode
-        let tls_mode = TlsNegotiationMode::from_encrypt_mode(config.strict_mode);
+        #[cfg(not(all(windows, feature = "named-pipes")))]
+        {
+            let _ = timings;
+            Err(Error::Config(format!(
+                "cannot connect to named pipe {path}: named pipes require the `named-pipes` feature on Windows"
+            )))
+        }
+    }
+
+    /// Start a LocalDB instance and get its pipe name, timed as DNS.
+    async fn localdb_pipe(instance: &str, timings: &mut ConnectTimings) -> Result<String> {
+        #[cfg(all(windows, feature = "named-pipes"))]
+        {
+            tracing::debug!(instance, "resolving LocalDB instance");
+            let started = Instant::now();
+            let pipe = crate::localdb::resolve_pipe_name(instance).await?;
+            timings.dns = started.elapsed();
+            Ok(pipe)
+        }
+
+        #[cfg(not(all(windows, feature = "named-pipes")))]
+        {
+            let _ = timings;
+            Err(Error::Config(format!(
+                "cannot connect to LocalDB instance {instance}: LocalDB requires the `named-pipes` feature on Windows"
+            )))
+        }
+    }
+
+    /// Open the TCP connection to the configured server.
+    ///
+    /// The socket is created with the options in [`Config::socket`], and
+    /// tunneled through [`Config::proxy`] if one is set.
+    /// If the connection through DNS fails and DNS caching is enabled, the
+    /// address cached by an earlier login is tried; a cached address that
+    /// cannot be reached either is evicted and the original error returned.
+    async fn connect_tcp(config: &Config, timings: &mut ConnectTimings) -> Result<TcpStream> {
+        if let Some(proxy) = &config.proxy {
+            return Self::connect_proxy(config, proxy, timings).await;
+        }
+
+        let addr = format!("{}:{}", config.host, config.port);
+        tracing::debug!("establishing TCP connection to {}", addr);
+
+        // DNS and TCP share the connect timeout
+        let deadline = tokio::time::Instant::now() + config.timeouts.connect_timeout;
+
+        let started = Instant::now();
+        let resolved = tokio::time::timeout_at(deadline, tokio::net::lookup_host(&addr)).await;
+        timings.dns = started.elapsed();
+
+        let started = Instant::now();
+        let error = match resolved {
+            Ok(Ok(addrs)) => {
+                let addrs: Vec<_> = addrs.collect();
+                match tokio::time::timeout_at(
+                    deadline,
+                    crate::socket::connect_any(&addrs, &config.socket),
+                )
+                .await
+                {
+                    Ok(Ok(stream)) => {
+                        timings.tcp = started.elapsed();
+                        return Ok(stream);
+                    }
+                    Ok(Err(e)) => Error::Io(Arc::new(e)),
+                    Err(_) => Error::ConnectTimeout,
+                }
+            }
+            Ok(Err(e)) => Error::Io(Arc::new(e)),
+            Err(_) => Error::ConnectTimeout,
+        };
+
+        let cached = config
+            .dns_caching
+            .then(|| DnsCache::global().get(&config.host, config.port))
+            .flatten();
+        let Some(cached) = cached else {
+            return Err(error);
+        };
 
-        // Step 2: Handle TDS 8.0 strict mode (TLS before any TDS traffic)
-        if tls_mode.is_tls_first() {
-            return Self::connect_tds_8(config, tcp_stream).await;
+        tracing::info!(
+            host = %config.host,
+            cached = %cached,
+            error = %error,
+            "connection through DNS failed, trying cached server address"
+        );
+        let started = Instant::now();
+        match timeout(
+            config.timeouts.connect_timeout,
+            crate::socket::connect(cached, &config.socket),
+        )
+        .await
+        {
+            Ok(Ok(stream)) => {
+                timings.tcp = started.elapsed();
+                Ok(stream)
+            }
+            _ => {
+                DnsCache::global().remove(&config.host, config.port);
+                Err(error)
+            }
         }
+    }
+
+    /// Open a tunnel to the configured server through a proxy.
+    ///
+    /// DNS covers resolving the proxy; the server name is sent to the proxy
+    /// as is. TCP covers connecting to the proxy and the tunnel handshake.
+    async fn connect_proxy(
+        config: &Config,
+        proxy: &ProxyConfig,
+        timings: &mut ConnectTimings,
+    ) -> Result<TcpStream> {
+        tracing::debug!(
+            proxy = %format!("{}:{}", proxy.host, proxy.port),
+            kind = ?proxy.kind,
+            "establishing TCP connection through proxy"
+        );
+        let deadline = tokio::time::Instant::now() + config.timeouts.connect_timeout;
 
-        // Step 3: TDS 7.x flow - PreLogin first, then TLS, then Login7
-        Self::connect_tds_7x(config, tcp_stream).await
+        let started = Instant::now();
+        let addrs: Vec<_> = tokio::time::timeout_at(
+            deadline,
+            tokio::net::lookup_host((proxy.host.as_str(), proxy.port)),
+        )
+        .await
+        .map_err(|_| Error::ConnectTimeout)?
+        .map_err(|e| Error::Io(Arc::new(e)))?
+        .collect();
+        timings.dns = started.elapsed();
+
+        let started = Instant::now();
+        let tunnel = async {
+            let mut stream = crate::socket::connect_any(&addrs, &config.socket).await?;
+            crate::proxy::handshake(&mut stream, proxy, &config.host, config.port).await?;
+            Ok::<_, std::io::Error>(stream)
+        };
+        let stream = tokio::time::timeout_at(deadline, tunnel)
+            .await
+            .map_err(|_| Error::ConnectTimeout)?
+            .map_err(|e| Error::Io(Arc::new(e)))?;
+        timings.tcp = started.elapsed();
+        Ok(stream)
     }
 
     /// Connect using TDS 8.0 strict mode.
     ///
     /// Flow: TCP -> TLS -> PreLogin (encrypted) -> Login7 (encrypted)
-    async fn connect_tds_8(config: &Config, tcp_stream: TcpStream) -> Result<Client<Ready>> {
+    async fn connect_tds_8(
+        config: &Config,
+        stream: Transport,
+        timings: &mut ConnectTimings,
+    ) -> Result<Client<Ready>> {
         tracing::debug!("using TDS 8.0 strict mode (TLS first)");
 
         // Build TLS configuration
         let tls_config = TlsConfig::new()
             .strict_mode(true)
-            .trust_server_certificate(config.trust_server_certificate);
+            .trust_server_certificate(config.trust_server_certificate)
+            .backend(config.tls.backend);
 
         let tls_connector = TlsConnector::new(tls_config).map_err(|e| Error::Tls(e.to_string()))?;
 
         // Perform TLS handshake before any TDS traffic
+        let started = Instant::now();
         let tls_stream = timeout(
             config.timeouts.tls_timeout,
-            tls_connector.connect(tcp_stream, &config.host),
+            tls_connector.connect(stream, &config.host),
         )
         .await
         .map_err(|_| Error::TlsTimeout)?
         .map_err(|e| Error::Tls(e.to_string()))?;
 
+        timings.tls = Some(started.elapsed());
         tracing::debug!("TLS handshake completed (strict mode)");
 
         // Create connection wrapper
-        let mut connection = Connection::new(tls_stream);
+        let mut connection = new_connection(tls_stream, config);
 
         // Send PreLogin (encrypted in strict mode)
+        let started = Instant::now();
         let prelogin = Self::build_prelogin(config, EncryptionLevel::Required);
         Self::send_prelogin(&mut connection, &prelogin).await?;
         let _prelogin_response = Self::receive_prelogin(&mut connection).await?;
+        timings.prelogin = started.elapsed();
 
         // Send Login7
         let login = Self::build_login7(config);
         Self::send_login7(&mut connection, &login).await?;
 
         // Process login response
-        let (server_version, current_database, routing) =
-            Self::process_login_response(&mut connection).await?;
+        let (server_version, session, routing, column_encryption) =
+            Self::process_login_response(&mut connection, config).await?;
 
         // Handle routing redirect
         if let Some((host, port)) = routing {
@@ -209,13 +769,25 @@ impl Client<Disconnected> {
             _state: PhantomData,
             connection: Some(ConnectionHandle::Tls(connection)),
             server_version,
-            current_database: current_database.clone(),
+            session: session.clone(),
             statement_cache: StatementCache::with_default_size(),
             transaction_descriptor: 0, // Auto-commit mode initially
             needs_reset: false,        // Fresh connection, no reset needed
+            deferred_cleanup: Vec::new(),
+            abandoned_impersonations: Arc::default(),
+            request_max_lob_size: None,
+            request_state: RequestState::Idle,
+            socket_probe: None,
+            broken: false,
+            message_handler: None,
+            messages: Vec::new(),
+            column_encryption,
+            connect_timings: ConnectTimings::default(),
+            #[cfg(feature = "always-encrypted")]
+            enclave_package: None,
             #[cfg(feature = "otel")]
             instrumentation: InstrumentationContext::new(config.host.clone(), config.port)
-                .with_database(current_database.unwrap_or_default()),
+                .with_database(session.database.unwrap_or_default()),
         })
     }
 
@@ -226,7 +798,11 @@ impl Client<Disconnected> {
     /// Note: For TDS 7.x, the PreLogin exchange happens over raw TCP before
     /// upgrading to TLS. We use low-level I/O for this initial exchange
     /// since the Connection struct splits the stream immediately.
-    async fn connect_tds_7x(config: &Config, mut tcp_stream: TcpStream) -> Result<Client<Ready>> {
+    async fn connect_tds_7x(
+        config: &Config,
+        mut stream: Transport,
+        timings: &mut ConnectTimings,
+    ) -> Result<Client<Ready>> {
         use bytes::BufMut;
         use tds_protocol::packet::{PACKET_HEADER_SIZE, PacketHeader, PacketStatus};
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -248,6 +824,7 @@ impl Client<Disconnected> {
         } else {
             EncryptionLevel::Off
         };
+        let prelogin_started = Instant::now();
         let prelogin = Self::build_prelogin(config, client_encryption);
         tracing::debug!(encryption = ?client_encryption, "sending PreLogin");
         let prelogin_bytes = prelogin.encode();
@@ -263,14 +840,14 @@ impl Client<Disconnected> {
         header.encode(&mut packet_buf);
         packet_buf.put_slice(&prelogin_bytes);
 
-        tcp_stream
+        stream
             .write_all(&packet_buf)
             .await
             .map_err(|e| Error::Io(Arc::new(e)))?;
 
         // Read PreLogin response
         let mut header_buf = [0u8; PACKET_HEADER_SIZE];
-        tcp_stream
+        stream
             .read_exact(&mut header_buf)
             .await
             .map_err(|e| Error::Io(Arc::new(e)))?;
@@ -279,13 +856,14 @@ impl Client<Disconnected> {
         let payload_length = response_length.saturating_sub(PACKET_HEADER_SIZE);
 
         let mut response_buf = vec![0u8; payload_length];
-        tcp_stream
+        stream
             .read_exact(&mut response_buf)
             .await
             .map_err(|e| Error::Io(Arc::new(e)))?;
 
         let prelogin_response =
             PreLogin::decode(&response_buf[..]).map_err(|e| Error::Protocol(e.to_string()))?;
+        timings.prelogin = prelogin_started.elapsed();
 
         // Log PreLogin response
         // Note: The server sends its SQL Server product version in PreLogin,
@@ -359,21 +937,24 @@ impl Client<Disconnected> {
         if use_tls {
             // Upgrade to TLS with PreLogin wrapping (TDS 7.x style)
             // In TDS 7.x, the TLS handshake is wrapped inside TDS PreLogin packets
-            let tls_config =
-                TlsConfig::new().trust_server_certificate(config.trust_server_certificate);
+            let tls_config = TlsConfig::new()
+                .trust_server_certificate(config.trust_server_certificate)
+                .backend(config.tls.backend);
 
             let tls_connector =
                 TlsConnector::new(tls_config).map_err(|e| Error::Tls(e.to_string()))?;
 
             // Use PreLogin-wrapped TLS connection for TDS 7.x
+            let started = Instant::now();
             let mut tls_stream = timeout(
                 config.timeouts.tls_timeout,
-                tls_connector.connect_with_prelogin(tcp_stream, &config.host),
+                tls_connector.connect_with_prelogin(stream, &config.host),
             )
             .await
             .map_err(|_| Error::TlsTimeout)?
             .map_err(|e| Error::Tls(e.to_string()))?;
 
+            timings.tls = Some(started.elapsed());
             tracing::debug!("TLS handshake completed (PreLogin wrapped)");
 
             // Check if we need full encryption or login-only encryption
@@ -432,17 +1013,18 @@ impl Client<Disconnected> {
                 tracing::debug!("Login7 sent through TLS, switching to plaintext for response");
 
                 // Extract the underlying TCP stream from the TLS layer
-                // TlsStream::into_inner() returns (IO, ClientConnection)
-                // where IO is our TlsPreloginWrapper<TcpStream>
-                let (wrapper, _client_conn) = tls_stream.into_inner();
-                let tcp_stream = wrapper.into_inner();
+                // TlsStream::into_inner() returns our TlsPreloginWrapper<TcpStream>
+                let wrapper = tls_stream
+                    .into_inner()
+                    .ok_or_else(|| Error::Tls("TLS transport unavailable after Login7".into()))?;
+                let stream = wrapper.into_inner();
 
                 // Create Connection from plain TCP for reading response
-                let mut connection = Connection::new(tcp_stream);
+                let mut connection = new_connection(stream, config);
 
                 // Process login response (comes in plaintext)
-                let (server_version, current_database, routing) =
-                    Self::process_login_response(&mut connection).await?;
+                let (server_version, session, routing, column_encryption) =
+                    Self::process_login_response(&mut connection, config).await?;
 
                 // Handle routing redirect
                 if let Some((host, port)) = routing {
@@ -455,26 +1037,38 @@ impl Client<Disconnected> {
                     _state: PhantomData,
                     connection: Some(ConnectionHandle::Plain(connection)),
                     server_version,
-                    current_database: current_database.clone(),
+                    session: session.clone(),
                     statement_cache: StatementCache::with_default_size(),
                     transaction_descriptor: 0, // Auto-commit mode initially
                     needs_reset: false,        // Fresh connection, no reset needed
+                    deferred_cleanup: Vec::new(),
+                    abandoned_impersonations: Arc::default(),
+                    request_max_lob_size: None,
+                    request_state: RequestState::Idle,
+                    socket_probe: None,
+                    broken: false,
+                    message_handler: None,
+                    messages: Vec::new(),
+                    column_encryption,
+                    connect_timings: ConnectTimings::default(),
+                    #[cfg(feature = "always-encrypted")]
+                    enclave_package: None,
                     #[cfg(feature = "otel")]
                     instrumentation: InstrumentationContext::new(config.host.clone(), config.port)
-                        .with_database(current_database.unwrap_or_default()),
+                        .with_database(session.database.unwrap_or_default()),
                 })
             } else {
                 // Full Encryption (ENCRYPT_ON per MS-TDS spec):
                 // - All communication after TLS handshake goes through TLS
-                let mut connection = Connection::new(tls_stream);
+                let mut connection = new_connection(tls_stream, config);
 
                 // Send Login7
                 let login = Self::build_login7(config);
                 Self::send_login7(&mut connection, &login).await?;
 
                 // Process login response
-                let (server_version, current_database, routing) =
-                    Self::process_login_response(&mut connection).await?;
+                let (server_version, session, routing, column_encryption) =
+                    Self::process_login_response(&mut connection, config).await?;
 
                 // Handle routing redirect
                 if let Some((host, port)) = routing {
@@ -486,13 +1080,25 @@ impl Client<Disconnected> {
                     _state: PhantomData,
                     connection: Some(ConnectionHandle::TlsPrelogin(connection)),
                     server_version,
-                    current_database: current_database.clone(),
+                    session: session.clone(),
                     statement_cache: StatementCache::with_default_size(),
                     transaction_descriptor: 0, // Auto-commit mode initially
                     needs_reset: false,        // Fresh connection, no reset needed
+                    deferred_cleanup: Vec::new(),
+                    abandoned_impersonations: Arc::default(),
+                    request_max_lob_size: None,
+                    request_state: RequestState::Idle,
+                    socket_probe: None,
+                    broken: false,
+                    message_handler: None,
+                    messages: Vec::new(),
+                    column_encryption,
+                    connect_timings: ConnectTimings::default(),
+                    #[cfg(feature = "always-encrypted")]
+                    enclave_package: None,
                     #[cfg(feature = "otel")]
                     instrumentation: InstrumentationContext::new(config.host.clone(), config.port)
-                        .with_database(current_database.unwrap_or_default()),
+                        .with_database(session.database.unwrap_or_default()),
                 })
             }
         } else {
@@ -537,19 +1143,16 @@ impl Client<Disconnected> {
                 login_packet_buf.len(),
                 &login_packet_buf[..PACKET_HEADER_SIZE]
             );
-            tcp_stream
+            stream
                 .write_all(&login_packet_buf)
                 .await
                 .map_err(|e| Error::Io(Arc::new(e)))?;
-            tcp_stream
-                .flush()
-                .await
-                .map_err(|e| Error::Io(Arc::new(e)))?;
+            stream.flush().await.map_err(|e| Error::Io(Arc::new(e)))?;
             tracing::debug!("Login7 sent and flushed over raw TCP");
 
             // Read login response header
             let mut response_header_buf = [0u8; PACKET_HEADER_SIZE];
-            tcp_stream
+            stream
                 .read_exact(&mut response_header_buf)
                 .await
                 .map_err(|e| Error::Io(Arc::new(e)))?;
@@ -566,7 +1169,7 @@ impl Client<Disconnected> {
             // Read response payload
             let payload_length = response_length.saturating_sub(PACKET_HEADER_SIZE);
             let mut response_payload = vec![0u8; payload_length];
-            tcp_stream
+            stream
                 .read_exact(&mut response_payload)
                 .await
                 .map_err(|e| Error::Io(Arc::new(e)))?;
@@ -577,14 +1180,16 @@ impl Client<Disconnected> {
             );
 
             // Now create Connection for further communication
-            let connection = Connection::new(tcp_stream);
+            let mut connection = new_connection(stream, config);
 
             // Parse login response
             let response_bytes = bytes::Bytes::from(response_payload);
             let mut parser = TokenParser::new(response_bytes);
             let mut server_version = None;
-            let mut current_database = None;
-            let routing = None;
+            let mut session = SessionEnv::default();
+            let mut routing = None;
+            let mut packet_size = usize::from(config.packet_size);
+            let mut column_encryption = None;
 
             while let Some(token) = parser
                 .next_token()
@@ -601,26 +1206,20 @@ impl Client<Disconnected> {
                         server_version = Some(ack.tds_version);
                     }
                     Token::EnvChange(env) => {
-                        Self::process_env_change(&env, &mut current_database, &mut None);
+                        if let Some(size) = negotiated_packet_size(&env) {
+                            packet_size = size;
+                        }
+                        Self::process_env_change(&env, &mut session, &mut routing);
+                    }
+                    Token::FeatureExtAck(ack) => {
+                        column_encryption = negotiated_column_encryption(
+                            requested_column_encryption(config),
+                            &ack,
+                        )?;
+                        session.dns_caching = ack.azure_sql_dns_caching();
                     }
                     Token::Error(err) => {
-                        return Err(Error::Server {
-                            number: err.number,
-                            state: err.state,
-                            class: err.class,
-                            message: err.message.clone(),
-                            server: if err.server.is_empty() {
-                                None
-                            } else {
-                                Some(err.server.clone())
-                            },
-                            procedure: if err.procedure.is_empty() {
-                                None
-                            } else {
-                                Some(err.procedure.clone())
-                            },
-                            line: err.line as u32,
-                        });
+                        return Err(DatabaseError::from(&err).into());
                     }
                     Token::Info(info) => {
                         tracing::info!(
@@ -639,6 +1238,8 @@ impl Client<Disconnected> {
                 }
             }
 
+            connection.set_packet_size(packet_size).await;
+
             // Handle routing redirect
             if let Some((host, port)) = routing {
                 return Err(Error::Routing { host, port });
@@ -649,13 +1250,25 @@ impl Client<Disconnected> {
                 _state: PhantomData,
                 connection: Some(ConnectionHandle::Plain(connection)),
                 server_version,
-                current_database: current_database.clone(),
+                session: session.clone(),
                 statement_cache: StatementCache::with_default_size(),
                 transaction_descriptor: 0, // Auto-commit mode initially
                 needs_reset: false,        // Fresh connection, no reset needed
+                deferred_cleanup: Vec::new(),
+                abandoned_impersonations: Arc::default(),
+                request_max_lob_size: None,
+                request_state: RequestState::Idle,
+                socket_probe: None,
+                broken: false,
+                message_handler: None,
+                messages: Vec::new(),
+                column_encryption,
+                connect_timings: ConnectTimings::default(),
+                #[cfg(feature = "always-encrypted")]
+                enclave_package: None,
                 #[cfg(feature = "otel")]
                 instrumentation: InstrumentationContext::new(config.host.clone(), config.port)
-                    .with_database(current_database.unwrap_or_default()),
+                    .with_database(session.database.unwrap_or_default()),
             })
         }
     }
@@ -681,6 +1294,14 @@ impl Client<Disconnected> {
             prelogin = prelogin.with_instance(instance);
         }
 
+        #[cfg(feature = "otel")]
+        if let Some(span_context) = crate::instrumentation::current_span_context() {
+            prelogin = prelogin.with_trace_id(crate::instrumentation::prelogin_trace_id(
+                span_context.trace_id().to_bytes(),
+                span_context.span_id().to_bytes(),
+            ));
+        }
+
         prelogin
     }
 
@@ -707,12 +1328,22 @@ impl Client<Disconnected> {
         // Set credentials
         match &config.credentials {
             mssql_auth::Credentials::SqlServer { username, password } => {
-                login = login.with_sql_auth(username.as_ref(), password.as_ref());
+                login = login.with_sql_auth(username.as_ref(), password.expose_secret());
             }
             // Other credential types would be handled here
             _ => {}
         }
 
+        if let Some(version) = requested_column_encryption(config) {
+            login = login.with_feature(FeatureExtension::column_encryption(version));
+        }
+
+        if config.dns_caching {
+            login = login
+                .with_feature(FeatureExtension::azure_sql_support())
+                .with_feature(FeatureExtension::azure_sql_dns_caching());
+        }
+
         login
     }
 
@@ -760,10 +1391,16 @@ impl Client<Disconnected> {
 
     /// Process the login response tokens.
     ///
-    /// Returns: (server_version, database, routing_info)
+    /// Returns: (server_version, session, routing_info, column_encryption)
     async fn process_login_response<T>(
         connection: &mut Connection<T>,
-    ) -> Result<(Option<u32>, Option<String>, Option<(String, u16)>)>
+        config: &Config,
+    ) -> Result<(
+        Option<u32>,
+        SessionEnv,
+        Option<(String, u16)>,
+        Option<ColumnEncryptionAck>,
+    )>
     where
         T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
     {
@@ -777,8 +1414,11 @@ impl Client<Disconnected> {
 
         let mut parser = TokenParser::new(response_bytes);
         let mut server_version = None;
-        let mut database = None;
+        let mut session = SessionEnv::default();
         let mut routing = None;
+        let mut packet_size = usize::from(config.packet_size);
+        let requested_encryption = requested_column_encryption(config);
+        let mut column_encryption = None;
 
         while let Some(token) = parser
             .next_token()
@@ -795,26 +1435,17 @@ impl Client<Disconnected> {
                     server_version = Some(ack.tds_version);
                 }
                 Token::EnvChange(env) => {
-                    Self::process_env_change(&env, &mut database, &mut routing);
+                    if let Some(size) = negotiated_packet_size(&env) {
+                        packet_size = size;
+                    }
+                    Self::process_env_change(&env, &mut session, &mut routing);
+                }
+                Token::FeatureExtAck(ack) => {
+                    column_encryption = negotiated_column_encryption(requested_encryption, &ack)?;
+                    session.dns_caching = ack.azure_sql_dns_caching();
                 }
                 Token::Error(err) => {
-                    return Err(Error::Server {
-                        number: err.number,
-                        state: err.state,
-                        class: err.class,
-                        message: err.message.clone(),
-                        server: if err.server.is_empty() {
-                            None
-                        } else {
-                            Some(err.server.clone())
-                        },
-                        procedure: if err.procedure.is_empty() {
-                            None
-                        } else {
-                            Some(err.procedure.clone())
-                        },
-                        line: err.line as u32,
-                    });
+                    return Err(DatabaseError::from(&err).into());
                 }
                 Token::Info(info) => {
                     tracing::info!(
@@ -833,13 +1464,71 @@ impl Client<Disconnected> {
             }
         }
 
-        Ok((server_version, database, routing))
+        connection.set_packet_size(packet_size).await;
+
+        if requested_encryption.is_some() && column_encryption.is_none() {
+            tracing::warn!(
+                "server does not support column encryption; Always Encrypted is disabled"
+            );
+        }
+
+        Ok((server_version, session, routing, column_encryption))
+    }
+}
+
+// Private helper methods available to all connection states
+impl<S: ConnectionState> Client<S> {
+    /// Register a callback for informational messages from the server.
+    ///
+    /// The callback is invoked for every INFO token (severity 10 or lower),
+    /// which includes `PRINT` output and `RAISERROR ... WITH NOWAIT` messages.
+    /// It replaces any previously registered callback and is carried across
+    /// transaction state transitions.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// client.on_message(|msg| println!("server: {}", msg.message));
+    /// client.execute("PRINT 'hello'", &[]).await?;
+    /// ```
+    pub fn on_message<F>(&mut self, callback: F)
+    where
+        F: Fn(&ServerMessage) + Send + Sync + 'static,
+    {
+        self.message_handler = Some(Arc::new(callback));
+    }
+
+    /// Remove the callback registered with [`on_message`](Self::on_message).
+    pub fn clear_message_handler(&mut self) {
+        self.message_handler = None;
+    }
+
+    /// Get the informational messages received during the most recent request.
+    ///
+    /// Messages are cleared when the next request's response is read.
+    #[must_use]
+    pub fn messages(&self) -> &[ServerMessage] {
+        &self.messages
+    }
+
+    /// Buffer an INFO token and pass it to the registered callback.
+    fn handle_info(&mut self, info: &tds_protocol::token::ServerInfo) {
+        tracing::debug!(
+            number = info.number,
+            message = %info.message,
+            "server info message"
+        );
+        let message = ServerMessage::from(info);
+        if let Some(handler) = &self.message_handler {
+            handler(&message);
+        }
+        self.messages.push(message);
     }
 
-    /// Process an EnvChange token.
+    /// Process an EnvChange token describing the session.
     fn process_env_change(
         env: &EnvChange,
-        database: &mut Option<String>,
+        session: &mut SessionEnv,
         routing: &mut Option<(String, u16)>,
     ) {
         use tds_protocol::token::EnvChangeValue;
@@ -848,7 +1537,23 @@ impl Client<Disconnected> {
             EnvChangeType::Database => {
                 if let EnvChangeValue::String(ref new_value) = env.new_value {
                     tracing::debug!(database = %new_value, "database changed");
-                    *database = Some(new_value.clone());
+                    session.database = Some(new_value.clone());
+                }
+            }
+            EnvChangeType::Language => {
+                if let EnvChangeValue::String(ref new_value) = env.new_value {
+                    tracing::debug!(language = %new_value, "language changed");
+                    session.language = Some(new_value.clone());
+                }
+            }
+            EnvChangeType::SqlCollation => {
+                // 5 bytes: LCID (4) + sort ID (1); empty when the collation is unset
+                if let EnvChangeValue::Binary(ref data) = env.new_value {
+                    session.collation = (data.len() >= 5).then(|| Collation {
+                        lcid: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+                        sort_id: data[4],
+                    });
+                    tracing::debug!(collation = ?session.collation, "collation changed");
                 }
             }
             EnvChangeType::Routing => {
@@ -868,10 +1573,7 @@ impl Client<Disconnected> {
             }
         }
     }
-}
 
-// Private helper methods available to all connection states
-impl<S: ConnectionState> Client<S> {
     /// Process transaction-related EnvChange tokens.
     ///
     /// This handles BeginTransaction, CommitTransaction, and RollbackTransaction
@@ -894,1977 +1596,3874 @@ impl<S: ConnectionState> Client<S> {
                     }
                 }
             }
-            EnvChangeType::CommitTransaction | EnvChangeType::RollbackTransaction => {
+            EnvChangeType::CommitTransaction
+            | EnvChangeType::RollbackTransaction
+            | EnvChangeType::TransactionEnded => {
                 tracing::debug!(
                     env_type = ?env.env_type,
                     "transaction ended via raw SQL"
                 );
                 *transaction_descriptor = 0;
             }
+            EnvChangeType::ResetConnectionCompletionAck => {
+                // Resetting the connection rolls back any open transaction
+                tracing::debug!("connection reset acknowledged");
+                *transaction_descriptor = 0;
+            }
             _ => {}
         }
     }
 
-    /// Send a SQL batch to the server.
+    /// Apply an EnvChange token received after login.
     ///
-    /// Uses the client's current transaction descriptor in ALL_HEADERS.
-    /// Per MS-TDS spec, when in an explicit transaction, the descriptor
-    /// returned by BeginTransaction must be included.
-    ///
-    /// If `needs_reset` is set (from pool return), the RESETCONNECTION flag
-    /// is included in the first packet to reset connection state.
-    async fn send_sql_batch(&mut self, sql: &str) -> Result<()> {
-        let payload =
-            tds_protocol::encode_sql_batch_with_transaction(sql, self.transaction_descriptor);
-        let max_packet = self.config.packet_size as usize;
-
-        // Check if we need to reset the connection on this request
-        let reset = self.needs_reset;
-        if reset {
-            self.needs_reset = false; // Clear flag before sending
-            tracing::debug!("sending SQL batch with RESETCONNECTION flag");
-        }
-
-        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
-
-        match connection {
-            ConnectionHandle::Tls(conn) => {
-                conn.send_message_with_reset(PacketType::SqlBatch, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
-            }
-            ConnectionHandle::TlsPrelogin(conn) => {
-                conn.send_message_with_reset(PacketType::SqlBatch, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
-            }
-            ConnectionHandle::Plain(conn) => {
-                conn.send_message_with_reset(PacketType::SqlBatch, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
+    /// Tracks the session's database, language and collation and the
+    /// transaction descriptor, and resizes the connection's codecs if the
+    /// server renegotiates the packet size.
+    async fn handle_env_change(&mut self, env: &EnvChange) {
+        Self::process_env_change(env, &mut self.session, &mut None);
+        Self::process_transaction_env_change(env, &mut self.transaction_descriptor);
+
+        if let Some(size) = negotiated_packet_size(env) {
+            if let Some(connection) = self.connection.as_mut() {
+                connection.set_packet_size(size).await;
             }
         }
-
-        Ok(())
     }
 
-    /// Send an RPC request to the server.
+    /// Turn the first ERROR token of a response into the request's error.
     ///
-    /// Uses the client's current transaction descriptor in ALL_HEADERS.
-    ///
-    /// If `needs_reset` is set (from pool return), the RESETCONNECTION flag
-    /// is included in the first packet to reset connection state.
-    async fn send_rpc(&mut self, rpc: &RpcRequest) -> Result<()> {
-        let payload = rpc.encode_with_transaction(self.transaction_descriptor);
-        let max_packet = self.config.packet_size as usize;
-
-        // Check if we need to reset the connection on this request
-        let reset = self.needs_reset;
-        if reset {
-            self.needs_reset = false; // Clear flag before sending
-            tracing::debug!("sending RPC with RESETCONNECTION flag");
+    /// Called once the whole response has been read, so the transaction
+    /// descriptor already reflects any rollback the error caused.
+    /// `in_transaction` is whether a transaction was open when the request
+    /// was sent.
+    pub(crate) fn server_error(&mut self, err: DatabaseError, in_transaction: bool) -> Error {
+        if err.is_fatal() {
+            self.close_after_fatal_error(&err);
+            return err.into();
+        }
+        if in_transaction && self.transaction_descriptor == 0 {
+            tracing::debug!(
+                number = err.number,
+                "server error rolled back the transaction"
+            );
+            return Error::TransactionDoomed(err);
         }
+        err.into()
+    }
 
-        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+    /// Drop the connection after a fatal server error (class 20 and above).
+    ///
+    /// The server closes the connection after sending such an error, so
+    /// later requests fail with [`Error::ConnectionClosed`] instead of
+    /// writing to a dead socket.
+    fn close_after_fatal_error(&mut self, err: &DatabaseError) {
+        tracing::warn!(
+            number = err.number,
+            class = err.class,
+            message = %err.message,
+            "fatal server error, closing connection"
+        );
+        self.connection = None;
+        self.transaction_descriptor = 0;
+    }
 
-        match connection {
-            ConnectionHandle::Tls(conn) => {
-                conn.send_message_with_reset(PacketType::Rpc, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
-            }
-            ConnectionHandle::TlsPrelogin(conn) => {
-                conn.send_message_with_reset(PacketType::Rpc, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
-            }
-            ConnectionHandle::Plain(conn) => {
-                conn.send_message_with_reset(PacketType::Rpc, payload, max_packet, reset)
-                    .await
-                    .map_err(|e| Error::Protocol(e.to_string()))?;
-            }
-        }
+    /// Drop a connection whose response was abandoned part-way through.
+    pub(crate) fn abandon_connection(&mut self, reason: &str) {
+        tracing::warn!(reason, "abandoning connection with unread response");
+        self.connection = None;
+        self.transaction_descriptor = 0;
+        self.request_state = RequestState::Idle;
+    }
 
+    /// Set a key-value pair in the session context.
+    ///
+    /// Wraps `sp_set_session_context`. The value can be read back with
+    /// [`get_session_context`](Self::get_session_context) or by
+    /// `SESSION_CONTEXT(N'key')` in row-level security predicates, triggers,
+    /// and procedures. Setting a key to NULL (`&None::<String>`) removes it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// client.set_session_context("tenant_id", &42).await?;
+    /// ```
+    pub async fn set_session_context(
+        &mut self,
+        key: &str,
+        value: &(dyn crate::ToSql + Sync),
+    ) -> Result<()> {
+        self.fetch_rows(
+            "EXEC sp_set_session_context @key = @p1, @value = @p2",
+            &[&key, value],
+        )
+        .await?;
         Ok(())
     }
 
-    /// Convert ToSql parameters to RPC parameters.
-    fn convert_params(params: &[&(dyn crate::ToSql + Sync)]) -> Result<Vec<RpcParam>> {
-        use bytes::{BufMut, BytesMut};
-        use mssql_types::SqlValue;
-
-        params
-            .iter()
-            .enumerate()
-            .map(|(i, p)| {
-                let sql_value = p.to_sql()?;
-                let name = format!("@p{}", i + 1);
+    /// Set a key-value pair in the session context that cannot be changed
+    /// for the rest of the session.
+    ///
+    /// Use this for identity that security policies rely on, so code running
+    /// later on the connection cannot overwrite it. A pooled connection keeps
+    /// read-only keys until it is reset.
+    pub async fn set_session_context_read_only(
+        &mut self,
+        key: &str,
+        value: &(dyn crate::ToSql + Sync),
+    ) -> Result<()> {
+        self.fetch_rows(
+            "EXEC sp_set_session_context @key = @p1, @value = @p2, @read_only = 1",
+            &[&key, value],
+        )
+        .await?;
+        Ok(())
+    }
 
-                Ok(match sql_value {
-                    SqlValue::Null => RpcParam::null(&name, RpcTypeInfo::nvarchar(1)),
-                    SqlValue::Bool(v) => {
-                        let mut buf = BytesMut::with_capacity(1);
-                        buf.put_u8(if v { 1 } else { 0 });
-                        RpcParam::new(&name, RpcTypeInfo::bit(), buf.freeze())
-                    }
-                    SqlValue::TinyInt(v) => {
-                        let mut buf = BytesMut::with_capacity(1);
-                        buf.put_u8(v);
-                        RpcParam::new(&name, RpcTypeInfo::tinyint(), buf.freeze())
-                    }
-                    SqlValue::SmallInt(v) => {
-                        let mut buf = BytesMut::with_capacity(2);
-                        buf.put_i16_le(v);
-                        RpcParam::new(&name, RpcTypeInfo::smallint(), buf.freeze())
-                    }
-                    SqlValue::Int(v) => RpcParam::int(&name, v),
-                    SqlValue::BigInt(v) => RpcParam::bigint(&name, v),
-                    SqlValue::Float(v) => {
-                        let mut buf = BytesMut::with_capacity(4);
-                        buf.put_f32_le(v);
-                        RpcParam::new(&name, RpcTypeInfo::real(), buf.freeze())
-                    }
-                    SqlValue::Double(v) => {
-                        let mut buf = BytesMut::with_capacity(8);
-                        buf.put_f64_le(v);
-                        RpcParam::new(&name, RpcTypeInfo::float(), buf.freeze())
-                    }
-                    SqlValue::String(ref s) => RpcParam::nvarchar(&name, s),
-                    SqlValue::Binary(ref b) => {
-                        RpcParam::new(&name, RpcTypeInfo::varbinary(b.len() as u16), b.clone())
-                    }
-                    SqlValue::Xml(ref s) => RpcParam::nvarchar(&name, s),
-                    #[cfg(feature = "uuid")]
-                    SqlValue::Uuid(u) => {
-                        // UUID is stored in a specific byte order for SQL Server
-                        let bytes = u.as_bytes();
-                        let mut buf = BytesMut::with_capacity(16);
-                        // SQL Server stores GUIDs in mixed-endian format
-                        buf.put_u32_le(u32::from_be_bytes([
-                            bytes[0], bytes[1], bytes[2], bytes[3],
-                        ]));
-                        buf.put_u16_le(u16::from_be_bytes([bytes[4], bytes[5]]));
-                        buf.put_u16_le(u16::from_be_bytes([bytes[6], bytes[7]]));
-                        buf.put_slice(&bytes[8..16]);
-                        RpcParam::new(&name, RpcTypeInfo::uniqueidentifier(), buf.freeze())
-                    }
-                    #[cfg(feature = "decimal")]
-                    SqlValue::Decimal(d) => {
-                        // Decimal encoding is complex; use string representation for now
-                        RpcParam::nvarchar(&name, &d.to_string())
-                    }
-                    #[cfg(feature = "chrono")]
-                    SqlValue::Date(_)
-                    | SqlValue::Time(_)
-                    | SqlValue::DateTime(_)
-                    | SqlValue::DateTimeOffset(_) => {
-                        // For date/time types, use string representation for simplicity
-                        // A full implementation would encode these properly
-                        let s = match &sql_value {
-                            SqlValue::Date(d) => d.to_string(),
-                            SqlValue::Time(t) => t.to_string(),
-                            SqlValue::DateTime(dt) => dt.to_string(),
-                            SqlValue::DateTimeOffset(dto) => dto.to_rfc3339(),
-                            _ => unreachable!(),
-                        };
-                        RpcParam::nvarchar(&name, &s)
-                    }
-                    #[cfg(feature = "json")]
-                    SqlValue::Json(ref j) => RpcParam::nvarchar(&name, &j.to_string()),
-                    SqlValue::Tvp(ref tvp_data) => {
-                        // Encode TVP using the wire format
-                        Self::encode_tvp_param(&name, tvp_data)?
-                    }
-                    // Handle future SqlValue variants
-                    _ => {
-                        return Err(Error::Type(mssql_types::TypeError::UnsupportedConversion {
-                            from: sql_value.type_name().to_string(),
-                            to: "RPC parameter",
-                        }));
-                    }
-                })
-            })
-            .collect()
+    /// Get a value from the session context.
+    ///
+    /// Returns `None` if the key is not set.
+    pub async fn get_session_context<T: mssql_types::FromSql>(
+        &mut self,
+        key: &str,
+    ) -> Result<Option<T>> {
+        let rows = self
+            .fetch_rows("SELECT SESSION_CONTEXT(@p1)", &[&key])
+            .await?;
+        match rows.first() {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(None),
+        }
     }
 
-    /// Encode a TVP parameter for RPC.
+    /// Set the session's `CONTEXT_INFO`.
     ///
-    /// This encodes the complete TVP structure including metadata and row data
-    /// into the TDS wire format.
-    fn encode_tvp_param(name: &str, tvp_data: &mssql_types::TvpData) -> Result<RpcParam> {
-        // Convert mssql-types column definitions to wire format
-        let wire_columns: Vec<TvpWireColumnDef> = tvp_data
-            .columns
-            .iter()
-            .map(|col| {
-                let wire_type = Self::convert_tvp_column_type(&col.column_type);
-                TvpWireColumnDef {
-                    wire_type,
-                    flags: TvpColumnFlags {
-                        nullable: col.nullable,
-                    },
-                }
-            })
-            .collect();
+    /// `CONTEXT_INFO` is a single binary value of up to 128 bytes, visible
+    /// to other sessions through `sys.dm_exec_sessions`. Prefer
+    /// [`set_session_context`](Self::set_session_context) for new code.
+    pub async fn set_context_info(&mut self, value: &[u8]) -> Result<()> {
+        if value.len() > MAX_CONTEXT_INFO_LEN {
+            return Err(Error::Query(format!(
+                "CONTEXT_INFO is limited to {MAX_CONTEXT_INFO_LEN} bytes, got {}",
+                value.len()
+            )));
+        }
 
-        // Create encoder
-        let encoder = TvpEncoder::new(&tvp_data.schema, &tvp_data.type_name, &wire_columns);
+        self.fetch_rows(
+            "DECLARE @ci VARBINARY(128) = @p1; SET CONTEXT_INFO @ci",
+            &[&value],
+        )
+        .await?;
+        Ok(())
+    }
 
-        // Encode to buffer
-        let mut buf = BytesMut::with_capacity(256);
+    /// Get the session's `CONTEXT_INFO`, or `None` if it was never set.
+    pub async fn context_info(&mut self) -> Result<Option<Vec<u8>>> {
+        let rows = self.fetch_rows("SELECT CONTEXT_INFO()", &[]).await?;
+        match rows.first() {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(None),
+        }
+    }
 
-        // Encode metadata
-        encoder.encode_metadata(&mut buf);
+    /// Run `f` while impersonating a database user.
+    ///
+    /// Issues `EXECUTE AS USER` before calling `f` and `REVERT` after it,
+    /// whether `f` succeeds or fails, so row-level security policies and
+    /// permission checks apply as that user. If the `REVERT` itself fails the
+    /// connection is closed rather than left running under the wrong
+    /// identity.
+    ///
+    /// If the returned future is dropped while `f` runs (a timeout,
+    /// `select!` or an aborted task), the `REVERT` is sent before the next
+    /// request on this client instead, and the connection is closed if
+    /// that fails. If it is dropped with the `EXECUTE AS` or `REVERT` in
+    /// flight, whether the session is still impersonating is unknown, so the
+    /// connection is closed and the next request fails with
+    /// [`Error::ConnectionClosed`].
+    ///
+    /// The statements are sent as plain batches because impersonation started
+    /// inside `sp_executesql` would end with it; `user` is validated and
+    /// quoted as a string literal.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let orders = client
+    ///     .execute_as_user("tenant_reader", async |c| {
+    ///         c.query("SELECT * FROM dbo.Orders", &[]).await?.collect_all().await
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn execute_as_user<T, F>(&mut self, user: &str, f: F) -> Result<T>
+    where
+        F: AsyncFnOnce(&mut Self) -> Result<T>,
+    {
+        self.impersonate("USER", user, f).await
+    }
 
-        // Encode each row
-        for row in &tvp_data.rows {
-            encoder.encode_row(&mut buf, |row_buf| {
-                for (col_idx, value) in row.iter().enumerate() {
-                    let wire_type = &wire_columns[col_idx].wire_type;
-                    Self::encode_tvp_value(value, wire_type, row_buf);
-                }
-            });
+    /// Run `f` while impersonating a server login.
+    ///
+    /// See [`execute_as_user`](Self::execute_as_user) for the revert guarantees.
+    pub async fn execute_as_login<T, F>(&mut self, login: &str, f: F) -> Result<T>
+    where
+        F: AsyncFnOnce(&mut Self) -> Result<T>,
+    {
+        self.impersonate("LOGIN", login, f).await
+    }
+
+    async fn impersonate<T, F>(&mut self, kind: &str, principal: &str, f: F) -> Result<T>
+    where
+        F: AsyncFnOnce(&mut Self) -> Result<T>,
+    {
+        validate_principal(principal)?;
+        let sql = format!("EXECUTE AS {kind} = N'{}'", principal.replace('\'', "''"));
+        // Armed before EXECUTE AS, which may take effect even if its
+        // response is never read
+        let mut guard = RevertOnDrop {
+            abandoned: Some(Arc::clone(&self.abandoned_impersonations)),
+            in_effect: false,
+        };
+        if let Err(e) = self.fetch_rows(&sql, &[]).await {
+            guard.disarm();
+            return Err(e);
+        }
+        tracing::debug!(kind, principal, "impersonating principal");
+
+        guard.in_effect = true;
+        let result = f(&mut *self).await;
+        guard.in_effect = false;
+
+        let reverted = self.fetch_rows("REVERT", &[]).await;
+        guard.disarm();
+        if let Err(e) = reverted {
+            tracing::error!(error = %e, principal, "REVERT failed, closing connection");
+            self.abandon_connection("REVERT after EXECUTE AS failed");
+            return Err(e);
+        }
+        result
+    }
+
+    /// Revert the impersonations of dropped `execute_as_*` futures.
+    ///
+    /// Closes the connection if the `REVERT` fails or whether the session
+    /// is impersonating is unknown, since it would otherwise keep running
+    /// as the impersonated principal.
+    async fn revert_abandoned_impersonations(&mut self) -> Result<()> {
+        if self
+            .abandoned_impersonations
+            .unknown
+            .swap(false, Ordering::AcqRel)
+        {
+            self.abandoned_impersonations
+                .reverts
+                .store(0, Ordering::Release);
+            self.abandon_connection("impersonation dropped with a request in flight");
+            return Err(Error::ConnectionClosed);
+        }
+        let count = self
+            .abandoned_impersonations
+            .reverts
+            .load(Ordering::Acquire);
+        if count == 0 {
+            return Ok(());
         }
 
-        // Encode end marker
-        encoder.encode_end(&mut buf);
+        tracing::warn!(
+            count,
+            "reverting impersonation of a dropped execute_as future"
+        );
+        let sql = vec!["REVERT"; count as usize].join("; ");
+        let payload =
+            tds_protocol::encode_sql_batch_with_transaction(&sql, self.transaction_descriptor);
+        let mut result = self
+            .send_request(PacketType::SqlBatch, payload, false)
+            .await;
+        if result.is_ok() {
+            result = self.read_execute_result().await.map(|_| ());
+        }
+        if let Err(e) = result {
+            tracing::error!(error = %e, "REVERT failed, closing connection");
+            self.abandon_connection("REVERT of a dropped impersonation failed");
+            return Err(e);
+        }
+        self.abandoned_impersonations
+            .reverts
+            .fetch_sub(count, Ordering::AcqRel);
+        Ok(())
+    }
 
-        // Build the full TVP type name (schema.TypeName)
-        let full_type_name = if tvp_data.schema.is_empty() {
-            tvp_data.type_name.clone()
-        } else {
-            format!("{}.{}", tvp_data.schema, tvp_data.type_name)
+    /// Publish the current span's trace context to the session.
+    ///
+    /// Stores the W3C `traceparent` in the session context under
+    /// [`TRACEPARENT_SESSION_KEY`](crate::instrumentation::TRACEPARENT_SESSION_KEY)
+    /// and the 16-byte trace ID followed by the 8-byte span ID in
+    /// `CONTEXT_INFO`, so triggers, audit tables and Extended Events sessions
+    /// can be joined with the distributed trace. Returns `false` without a
+    /// round trip if there is no active span.
+    #[cfg(feature = "otel")]
+    pub async fn set_trace_context(&mut self) -> Result<bool> {
+        let Some(span_context) = crate::instrumentation::current_span_context() else {
+            return Ok(false);
         };
 
-        // Create RPC param with TVP type info
-        // The type info includes the TVP type name for parameter declarations
-        let type_info = RpcTypeInfo::tvp(&full_type_name);
+        let trace_id = span_context.trace_id().to_bytes();
+        let span_id = span_context.span_id().to_bytes();
+        let traceparent = crate::instrumentation::format_traceparent(
+            trace_id,
+            span_id,
+            span_context.is_sampled(),
+        );
+        let mut context_info = Vec::with_capacity(24);
+        context_info.extend_from_slice(&trace_id);
+        context_info.extend_from_slice(&span_id);
+
+        self.fetch_rows(
+            "EXEC sp_set_session_context @key = @p1, @value = @p2; \
+             DECLARE @ci VARBINARY(128) = @p3; SET CONTEXT_INFO @ci",
+            &[
+                &crate::instrumentation::TRACEPARENT_SESSION_KEY,
+                &traceparent.as_str(),
+                &context_info.as_slice(),
+            ],
+        )
+        .await?;
+        Ok(true)
+    }
 
-        Ok(RpcParam {
-            name: name.to_string(),
-            flags: tds_protocol::rpc::ParamFlags::default(),
-            type_info,
-            value: Some(buf.freeze()),
+    /// Run a `FOR JSON` query and return its JSON text.
+    ///
+    /// SQL Server splits `FOR JSON` output into rows of about 2,000
+    /// characters; they are joined back into one string here. A query that
+    /// matches no rows returns an empty string.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let json = client
+    ///     .query_json("SELECT id, name FROM Customers FOR JSON PATH", &[])
+    ///     .await?;
+    /// ```
+    pub async fn query_json(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<String> {
+        let rows = self.fetch_rows(sql, params).await?;
+        join_json_rows(&rows)
+    }
+
+    /// Run a `FOR JSON` query and deserialize its JSON text into `T`.
+    ///
+    /// See [`query_json`](Self::query_json). A query that matches no rows
+    /// is deserialized from `[]`, or from `null` when it ends in
+    /// `WITHOUT_ARRAY_WRAPPER`, so `Vec<T>` and `Option<T>` targets work for
+    /// empty results.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// #[derive(serde::Deserialize)]
+    /// struct Customer { id: i32, name: String }
+    ///
+    /// let customers: Vec<Customer> = client
+    ///     .query_json_as("SELECT id, name FROM Customers FOR JSON PATH", &[])
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "json")]
+    pub async fn query_json_as<T: serde::de::DeserializeOwned>(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<T> {
+        let mut json = self.query_json(sql, params).await?;
+        if json.is_empty() {
+            let unwrapped = sql
+                .to_ascii_uppercase()
+                .split_whitespace()
+                .any(|word| word.trim_end_matches(';') == "WITHOUT_ARRAY_WRAPPER");
+            json = if unwrapped { "null" } else { "[]" }.to_string();
+        }
+        serde_json::from_str(&json).map_err(|e| {
+            Error::Type(mssql_types::TypeError::Conversion {
+                target_type: std::any::type_name::<T>(),
+                message: e.to_string(),
+            })
         })
     }
 
-    /// Convert mssql-types TvpColumnType to wire TvpWireType.
-    fn convert_tvp_column_type(col_type: &mssql_types::TvpColumnType) -> TvpWireType {
-        match col_type {
-            mssql_types::TvpColumnType::Bit => TvpWireType::Bit,
-            mssql_types::TvpColumnType::TinyInt => TvpWireType::Int { size: 1 },
-            mssql_types::TvpColumnType::SmallInt => TvpWireType::Int { size: 2 },
-            mssql_types::TvpColumnType::Int => TvpWireType::Int { size: 4 },
-            mssql_types::TvpColumnType::BigInt => TvpWireType::Int { size: 8 },
-            mssql_types::TvpColumnType::Real => TvpWireType::Float { size: 4 },
-            mssql_types::TvpColumnType::Float => TvpWireType::Float { size: 8 },
-            mssql_types::TvpColumnType::Decimal { precision, scale } => TvpWireType::Decimal {
-                precision: *precision,
-                scale: *scale,
-            },
-            mssql_types::TvpColumnType::NVarChar { max_length } => TvpWireType::NVarChar {
-                max_length: *max_length,
-            },
-            mssql_types::TvpColumnType::VarChar { max_length } => TvpWireType::VarChar {
-                max_length: *max_length,
-            },
-            mssql_types::TvpColumnType::VarBinary { max_length } => TvpWireType::VarBinary {
-                max_length: *max_length,
-            },
-            mssql_types::TvpColumnType::UniqueIdentifier => TvpWireType::Guid,
-            mssql_types::TvpColumnType::Date => TvpWireType::Date,
-            mssql_types::TvpColumnType::Time { scale } => TvpWireType::Time { scale: *scale },
-            mssql_types::TvpColumnType::DateTime2 { scale } => {
-                TvpWireType::DateTime2 { scale: *scale }
-            }
-            mssql_types::TvpColumnType::DateTimeOffset { scale } => {
-                TvpWireType::DateTimeOffset { scale: *scale }
-            }
-            mssql_types::TvpColumnType::Xml => TvpWireType::Xml,
-        }
+    /// List the user sessions running a request or holding a transaction
+    /// open, longest running first.
+    ///
+    /// Each row has the statement, wait type, blocking session, CPU, reads,
+    /// duration, and transaction state. See the
+    /// [`activity`](crate::activity) module for details.
+    pub async fn activity_snapshot(&mut self) -> Result<Vec<crate::activity::ActiveSession>> {
+        let rows = self.fetch_rows(crate::activity::ACTIVITY_SQL, &[]).await?;
+        rows.iter().map(crate::activity::session_from_row).collect()
     }
 
-    /// Encode a single TVP column value.
-    fn encode_tvp_value(
-        value: &mssql_types::SqlValue,
-        wire_type: &TvpWireType,
-        buf: &mut BytesMut,
-    ) {
-        use mssql_types::SqlValue;
+    /// Get the current blocking chains, one tree per head blocker.
+    ///
+    /// Each node holds a session that blocks the sessions below it, with
+    /// the wait type, the lock it waits for and its SQL text. Returns an
+    /// empty list when nothing is blocked. See the
+    /// [`blocking`](crate::blocking) module for the required permissions.
+    pub async fn current_blocking_tree(&mut self) -> Result<Vec<crate::blocking::BlockingNode>> {
+        let rows = self.fetch_rows(crate::blocking::BLOCKING_SQL, &[]).await?;
+        let sessions = rows
+            .iter()
+            .map(crate::blocking::session_from_row)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(crate::blocking::build_tree(sessions))
+    }
 
-        match value {
-            SqlValue::Null => {
-                encode_tvp_null(wire_type, buf);
-            }
-            SqlValue::Bool(v) => {
-                encode_tvp_bit(*v, buf);
-            }
-            SqlValue::TinyInt(v) => {
-                encode_tvp_int(*v as i64, 1, buf);
-            }
-            SqlValue::SmallInt(v) => {
-                encode_tvp_int(*v as i64, 2, buf);
-            }
-            SqlValue::Int(v) => {
-                encode_tvp_int(*v as i64, 4, buf);
-            }
-            SqlValue::BigInt(v) => {
-                encode_tvp_int(*v, 8, buf);
-            }
-            SqlValue::Float(v) => {
-                encode_tvp_float(*v as f64, 4, buf);
-            }
-            SqlValue::Double(v) => {
-                encode_tvp_float(*v, 8, buf);
-            }
-            SqlValue::String(s) => {
-                let max_len = match wire_type {
-                    TvpWireType::NVarChar { max_length } => *max_length,
-                    _ => 4000,
-                };
-                encode_tvp_nvarchar(s, max_len, buf);
-            }
-            SqlValue::Binary(b) => {
-                let max_len = match wire_type {
-                    TvpWireType::VarBinary { max_length } => *max_length,
-                    _ => 8000,
-                };
-                encode_tvp_varbinary(b, max_len, buf);
-            }
-            #[cfg(feature = "decimal")]
-            SqlValue::Decimal(d) => {
-                let sign = if d.is_sign_negative() { 0u8 } else { 1u8 };
-                let mantissa = d.mantissa().unsigned_abs();
-                encode_tvp_decimal(sign, mantissa, buf);
-            }
-            #[cfg(feature = "uuid")]
-            SqlValue::Uuid(u) => {
-                let bytes = u.as_bytes();
-                tds_protocol::tvp::encode_tvp_guid(bytes, buf);
-            }
-            #[cfg(feature = "chrono")]
-            SqlValue::Date(d) => {
-                // Calculate days since 0001-01-01
-                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                let days = d.signed_duration_since(base).num_days() as u32;
-                tds_protocol::tvp::encode_tvp_date(days, buf);
-            }
-            #[cfg(feature = "chrono")]
-            SqlValue::Time(t) => {
-                use chrono::Timelike;
-                let nanos =
-                    t.num_seconds_from_midnight() as u64 * 1_000_000_000 + t.nanosecond() as u64;
-                let intervals = nanos / 100;
-                let scale = match wire_type {
-                    TvpWireType::Time { scale } => *scale,
-                    _ => 7,
-                };
-                tds_protocol::tvp::encode_tvp_time(intervals, scale, buf);
-            }
-            #[cfg(feature = "chrono")]
-            SqlValue::DateTime(dt) => {
-                use chrono::Timelike;
-                // Time component
-                let nanos = dt.time().num_seconds_from_midnight() as u64 * 1_000_000_000
-                    + dt.time().nanosecond() as u64;
-                let intervals = nanos / 100;
-                // Date component
-                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                let days = dt.date().signed_duration_since(base).num_days() as u32;
-                let scale = match wire_type {
-                    TvpWireType::DateTime2 { scale } => *scale,
-                    _ => 7,
-                };
-                tds_protocol::tvp::encode_tvp_datetime2(intervals, days, scale, buf);
-            }
-            #[cfg(feature = "chrono")]
-            SqlValue::DateTimeOffset(dto) => {
-                use chrono::{Offset, Timelike};
-                // Time component (in 100-nanosecond intervals)
-                let nanos = dto.time().num_seconds_from_midnight() as u64 * 1_000_000_000
-                    + dto.time().nanosecond() as u64;
-                let intervals = nanos / 100;
-                // Date component (days since 0001-01-01)
-                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                let days = dto.date_naive().signed_duration_since(base).num_days() as u32;
-                // Timezone offset in minutes
-                let offset_minutes = (dto.offset().fix().local_minus_utc() / 60) as i16;
-                let scale = match wire_type {
-                    TvpWireType::DateTimeOffset { scale } => *scale,
-                    _ => 7,
-                };
-                tds_protocol::tvp::encode_tvp_datetimeoffset(
-                    intervals,
-                    days,
-                    offset_minutes,
-                    scale,
-                    buf,
-                );
-            }
-            #[cfg(feature = "json")]
-            SqlValue::Json(j) => {
-                // JSON is encoded as NVARCHAR
-                encode_tvp_nvarchar(&j.to_string(), 0xFFFF, buf);
-            }
-            SqlValue::Xml(s) => {
-                // XML is encoded as NVARCHAR for TVP
-                encode_tvp_nvarchar(s, 0xFFFF, buf);
-            }
-            SqlValue::Tvp(_) => {
-                // Nested TVPs are not supported
-                encode_tvp_null(wire_type, buf);
-            }
-            // Handle future SqlValue variants as NULL
-            _ => {
-                encode_tvp_null(wire_type, buf);
-            }
-        }
+    /// Get up to `limit` of the most recent deadlocks recorded by the
+    /// `system_health` Extended Events session, newest first.
+    pub async fn recent_deadlocks(
+        &mut self,
+        limit: u32,
+    ) -> Result<Vec<crate::blocking::DeadlockReport>> {
+        let limit = i64::from(limit);
+        let rows = self
+            .fetch_rows(crate::blocking::DEADLOCKS_SQL, &[&limit])
+            .await?;
+        rows.iter()
+            .map(crate::blocking::deadlock_from_row)
+            .collect()
     }
 
-    /// Read complete query response including columns and rows.
-    async fn read_query_response(
+    /// Get the durations of the phases of opening this connection (DNS,
+    /// TCP, TLS handshake, PreLogin and login).
+    #[must_use]
+    pub fn connect_timings(&self) -> &ConnectTimings {
+        &self.connect_timings
+    }
+
+    /// Check if the connection was closed, for example by a fatal server error.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.connection.is_none()
+    }
+
+    /// Get the current database name.
+    ///
+    /// Follows `USE` statements and other database changes reported by the
+    /// server, falling back to the configured database before login.
+    #[must_use]
+    pub fn database(&self) -> Option<&str> {
+        self.session
+            .database
+            .as_deref()
+            .or(self.config.database.as_deref())
+    }
+
+    /// Get the session language reported by the server (e.g. `us_english`).
+    #[must_use]
+    pub fn language(&self) -> Option<&str> {
+        self.session.language.as_deref()
+    }
+
+    /// Get the default collation of the current database.
+    #[must_use]
+    pub fn collation(&self) -> Option<Collation> {
+        self.session.collation
+    }
+
+    /// Get the packet size negotiated with the server.
+    ///
+    /// This is the size requested in [`Config`] unless the server chose a
+    /// different one during login or changed it afterwards.
+    #[must_use]
+    pub fn packet_size(&self) -> usize {
+        self.connection.as_ref().map_or(
+            usize::from(self.config.packet_size),
+            ConnectionHandle::packet_size,
+        )
+    }
+
+    /// Send a request, first recovering from an abandoned one.
+    async fn send_request(
         &mut self,
-    ) -> Result<(Vec<crate::row::Column>, Vec<crate::row::Row>)> {
+        packet_type: PacketType,
+        payload: bytes::Bytes,
+        reset: bool,
+    ) -> Result<()> {
+        self.recover_abandoned_request().await?;
+        self.request_max_lob_size = None;
+        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+        self.request_state = RequestState::Sending;
+        if let Err(e) = connection.send_request(packet_type, payload, reset).await {
+            self.broken = true;
+            return Err(e);
+        }
+        self.request_state = RequestState::AwaitingResponse;
+        Ok(())
+    }
+
+    /// Read the complete response to the request in flight.
+    ///
+    /// If the returned future is dropped before the response is read, an
+    /// Attention packet is sent to cancel the request, and the rest of the
+    /// response is drained before the next request.
+    async fn read_response(&mut self) -> Result<mssql_codec::Message> {
         let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+        let guard = AttentionOnDrop(Some(connection.cancel_handle()));
+        let message = connection.read_message().await;
+        guard.disarm();
+        self.request_state = RequestState::Idle;
+        // End of stream also ends a cancelled request, so only errors mark
+        // the connection broken; a closed socket is seen by the probe
+        message
+            .inspect_err(|_| self.broken = true)?
+            .ok_or(Error::ConnectionClosed)
+    }
 
-        let message = match connection {
-            ConnectionHandle::Tls(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::TlsPrelogin(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::Plain(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
+    /// Check whether the connection is known to be unusable; see
+    /// [`Client<Ready>::is_broken`].
+    fn connection_is_broken(&self) -> bool {
+        self.connection.is_none()
+            || self.broken
+            || self.request_state == RequestState::Sending
+            || self
+                .socket_probe
+                .as_ref()
+                .is_some_and(crate::socket::SocketProbe::is_closed)
+    }
+
+    /// Bring the connection back to idle after a request whose future was
+    /// dropped.
+    ///
+    /// A request dropped while being written cannot be completed, so the
+    /// connection is closed. A request dropped while its response was read
+    /// is cancelled with an Attention packet, if that was not done when it
+    /// was dropped, and its response is drained up to the server's
+    /// acknowledgement.
+    async fn recover_abandoned_request(&mut self) -> Result<()> {
+        match self.request_state {
+            RequestState::Idle => return Ok(()),
+            RequestState::Sending => {
+                self.abandon_connection("request dropped while it was being sent");
+                return Err(Error::ConnectionClosed);
+            }
+            RequestState::AwaitingResponse => {}
         }
-        .ok_or(Error::ConnectionClosed)?;
 
-        let mut parser = TokenParser::new(message.payload);
-        let mut columns: Vec<crate::row::Column> = Vec::new();
-        let mut rows: Vec<crate::row::Row> = Vec::new();
-        let mut protocol_metadata: Option<ColMetaData> = None;
+        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+        tracing::debug!("draining response of an abandoned request");
+        if !connection.is_cancelling() && !connection.cancel_handle().cancel_in_background() {
+            // Not on a Tokio runtime
+            connection.cancel_handle().cancel().await?;
+        }
+        // Returns once the Attention acknowledgement has been read
+        connection
+            .read_message()
+            .await
+            .inspect_err(|_| self.broken = true)?;
+        self.request_state = RequestState::Idle;
+        self.messages.clear();
+        Ok(())
+    }
 
-        loop {
-            // Use next_token_with_metadata to properly parse Row/NbcRow tokens
-            let token = parser
-                .next_token_with_metadata(protocol_metadata.as_ref())
-                .map_err(|e| Error::Protocol(e.to_string()))?;
+    /// Consume a pending connection reset.
+    ///
+    /// Returns whether the next request must carry the RESETCONNECTION
+    /// flag. With [`SessionOptions`](crate::SessionOptions) or
+    /// [`Config::max_lob_size()`] configured, the reset is sent right away
+    /// with a batch restoring them, since the
+    /// reset returns the session to its login defaults, and the next
+    /// request goes without the flag.
+    async fn take_reset(&mut self) -> Result<bool> {
+        if !self.needs_reset {
+            return Ok(false);
+        }
+        self.needs_reset = false;
+        let Some(sql) = self.config.session_sql() else {
+            return Ok(true);
+        };
 
-            let Some(token) = token else {
-                break;
-            };
+        tracing::debug!("resetting connection and restoring session options");
+        let payload =
+            tds_protocol::encode_sql_batch_with_transaction(&sql, self.transaction_descriptor);
+        self.send_request(PacketType::SqlBatch, payload, true)
+            .await?;
+        self.read_execute_result().await?;
+        Ok(false)
+    }
 
-            match token {
-                Token::ColMetaData(meta) => {
-                    // New result set starting - clear previous rows
-                    // This enables multi-statement batches to return the last result set
-                    rows.clear();
+    /// Queue a statement to run before the next request.
+    ///
+    /// Used from `Drop` implementations, which cannot send anything
+    /// themselves.
+    pub(crate) fn defer_cleanup(&mut self, sql: String) {
+        self.deferred_cleanup.push(sql);
+    }
 
-                    columns = meta
-                        .columns
-                        .iter()
-                        .enumerate()
-                        .map(|(i, col)| {
-                            let type_name = format!("{:?}", col.type_id);
-                            let mut column = crate::row::Column::new(&col.name, i, type_name)
-                                .with_nullable(col.flags & 0x01 != 0);
-
-                            if let Some(max_len) = col.type_info.max_length {
-                                column = column.with_max_length(max_len);
-                            }
-                            if let (Some(prec), Some(scale)) =
-                                (col.type_info.precision, col.type_info.scale)
-                            {
-                                column = column.with_precision_scale(prec, scale);
-                            }
-                            // Store collation for VARCHAR/CHAR types to enable
-                            // collation-aware string decoding
-                            if let Some(collation) = col.type_info.collation {
-                                column = column.with_collation(collation);
-                            }
-                            column
-                        })
-                        .collect();
+    /// Run the statements queued with [`defer_cleanup`](Self::defer_cleanup).
+    ///
+    /// A pending connection reset discards session objects anyway, so the
+    /// queue is dropped instead.
+    async fn run_deferred_cleanup(&mut self) -> Result<()> {
+        self.revert_abandoned_impersonations().await?;
+        if self.deferred_cleanup.is_empty() {
+            return Ok(());
+        }
+        let sql = std::mem::take(&mut self.deferred_cleanup).join("; ");
+        if self.needs_reset {
+            return Ok(());
+        }
 
-                    tracing::debug!(columns = columns.len(), "received column metadata");
-                    protocol_metadata = Some(meta);
-                }
-                Token::Row(raw_row) => {
-                    if let Some(ref meta) = protocol_metadata {
-                        let row = Self::convert_raw_row(&raw_row, meta, &columns)?;
-                        rows.push(row);
-                    }
-                }
-                Token::NbcRow(nbc_row) => {
-                    if let Some(ref meta) = protocol_metadata {
-                        let row = Self::convert_nbc_row(&nbc_row, meta, &columns)?;
-                        rows.push(row);
-                    }
-                }
-                Token::Error(err) => {
-                    return Err(Error::Server {
-                        number: err.number,
-                        state: err.state,
-                        class: err.class,
-                        message: err.message.clone(),
-                        server: if err.server.is_empty() {
-                            None
-                        } else {
-                            Some(err.server.clone())
-                        },
-                        procedure: if err.procedure.is_empty() {
-                            None
-                        } else {
-                            Some(err.procedure.clone())
-                        },
-                        line: err.line as u32,
-                    });
-                }
-                Token::Done(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
-                    tracing::debug!(
-                        row_count = done.row_count,
-                        has_more = done.status.more,
-                        "query complete"
-                    );
-                    // Only break if there are no more result sets
-                    // This enables multi-statement batches to process all results
-                    if !done.status.more {
-                        break;
-                    }
-                }
-                Token::DoneProc(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
-                }
-                Token::DoneInProc(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
-                }
-                Token::Info(info) => {
-                    tracing::debug!(
-                        number = info.number,
-                        message = %info.message,
-                        "server info message"
-                    );
-                }
-                Token::EnvChange(env) => {
-                    // Process transaction-related EnvChange tokens.
-                    // This allows BEGIN TRANSACTION, COMMIT, ROLLBACK via raw SQL
-                    // to properly update the transaction descriptor.
-                    Self::process_transaction_env_change(&env, &mut self.transaction_descriptor);
-                }
-                _ => {}
+        tracing::debug!(sql = %sql, "running deferred cleanup");
+        let payload =
+            tds_protocol::encode_sql_batch_with_transaction(&sql, self.transaction_descriptor);
+        self.send_request(PacketType::SqlBatch, payload, false)
+            .await?;
+        match self.read_execute_result().await {
+            Ok(_) => Ok(()),
+            // Leftover objects are not worth failing the request for
+            Err(Error::Server {
+                number, message, ..
+            }) => {
+                tracing::warn!(error = number, message = %message, "deferred cleanup failed");
+                Ok(())
             }
+            Err(e) => Err(e),
         }
+    }
 
-        tracing::debug!(
-            columns = columns.len(),
-            rows = rows.len(),
-            "query response parsed"
+    /// Send a SQL batch to the server.
+    ///
+    /// Uses the client's current transaction descriptor in ALL_HEADERS.
+    /// Per MS-TDS spec, when in an explicit transaction, the descriptor
+    /// returned by BeginTransaction must be included.
+    ///
+    /// If `needs_reset` is set (from pool return), the RESETCONNECTION flag
+    /// is included in the first packet to reset connection state.
+    async fn send_sql_batch(&mut self, sql: &str) -> Result<()> {
+        let enclave_package = self.take_enclave_package();
+        let payload = tds_protocol::encode_sql_batch_with_enclave_package(
+            sql,
+            self.transaction_descriptor,
+            enclave_package.as_deref(),
         );
-        Ok((columns, rows))
+        self.run_deferred_cleanup().await?;
+        // Check if we need to reset the connection on this request
+        let reset = self.take_reset().await?;
+        if reset {
+            tracing::debug!("sending SQL batch with RESETCONNECTION flag");
+        }
+
+        self.send_request(PacketType::SqlBatch, payload, reset)
+            .await?;
+
+        Ok(())
     }
 
-    /// Convert a RawRow to a client Row.
+    /// Send an RPC request to the server.
     ///
-    /// This parses the raw bytes back into SqlValue types based on column metadata.
-    fn convert_raw_row(
-        raw: &RawRow,
-        meta: &ColMetaData,
-        columns: &[crate::row::Column],
-    ) -> Result<crate::row::Row> {
-        let mut values = Vec::with_capacity(meta.columns.len());
-        let mut buf = raw.data.as_ref();
-
-        for col in &meta.columns {
-            let value = Self::parse_column_value(&mut buf, col)?;
-            values.push(value);
+    /// Uses the client's current transaction descriptor in ALL_HEADERS.
+    ///
+    /// If `needs_reset` is set (from pool return), the RESETCONNECTION flag
+    /// is included in the first packet to reset connection state.
+    async fn send_rpc(&mut self, rpc: &RpcRequest) -> Result<()> {
+        let enclave_package = self.take_enclave_package();
+        let payload = rpc
+            .encode_with_enclave_package(self.transaction_descriptor, enclave_package.as_deref());
+        self.run_deferred_cleanup().await?;
+        // Check if we need to reset the connection on this request
+        let reset = self.take_reset().await?;
+        if reset {
+            tracing::debug!("sending RPC with RESETCONNECTION flag");
         }
 
-        Ok(crate::row::Row::from_values(columns.to_vec(), values))
+        self.send_request(PacketType::Rpc, payload, reset).await?;
+
+        Ok(())
     }
 
-    /// Convert an NbcRow to a client Row.
+    /// Send a statement, running the cached prepared statement for it when
+    /// there is one.
     ///
-    /// NbcRow has a null bitmap followed by only non-null values.
-    fn convert_nbc_row(
-        nbc: &NbcRow,
-        meta: &ColMetaData,
-        columns: &[crate::row::Column],
-    ) -> Result<crate::row::Row> {
-        let mut values = Vec::with_capacity(meta.columns.len());
-        let mut buf = nbc.data.as_ref();
-
-        for (i, col) in meta.columns.iter().enumerate() {
-            if nbc.is_null(i) {
-                values.push(mssql_types::SqlValue::Null);
+    /// Statements without parameters go as a SQL batch, others through
+    /// `sp_executesql`, unless [`prepare`](Self::prepare) cached a handle for
+    /// the same SQL text and parameter types; then `sp_execute` runs it.
+    async fn send_statement(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<()> {
+        if params.is_empty() {
+            let handle = if self.statement_cache.is_empty() {
+                None
             } else {
-                let value = Self::parse_column_value(&mut buf, col)?;
-                values.push(value);
-            }
+                self.statement_cache.get(sql)
+            };
+            return match handle {
+                Some(handle) => {
+                    self.send_rpc(&RpcRequest::execute(handle, Vec::new()))
+                        .await
+                }
+                None => self.send_sql_batch(sql).await,
+            };
         }
 
-        Ok(crate::row::Row::from_values(columns.to_vec(), values))
+        let rpc_params = self.convert_params(params)?;
+        let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+        let handle = if self.statement_cache.is_empty() {
+            None
+        } else {
+            let key = prepared_key(sql, &RpcRequest::build_param_declarations(&rpc_params));
+            self.statement_cache.get(&key)
+        };
+        let rpc = match handle {
+            Some(handle) => RpcRequest::execute(handle, rpc_params),
+            None => RpcRequest::execute_sql(sql, rpc_params),
+        };
+        self.send_rpc(&rpc).await
     }
 
-    /// Parse money value from buffer and convert to appropriate type.
+    /// Prepare a statement with `sp_prepare` and cache its handle.
     ///
-    /// Money is stored as fixed-point with 4 decimal places.
-    /// - 4 bytes: SMALLMONEY
-    /// - 8 bytes: MONEY
-    fn parse_money_value(buf: &mut &[u8], bytes: usize) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
-
-        if bytes == 0 {
-            return Ok(SqlValue::Null);
+    /// `params` are sample values: only their types matter. Afterwards,
+    /// [`query`](Client::query) and [`execute`](Client::execute) with the
+    /// same SQL text and parameters of the same types run the prepared
+    /// statement with `sp_execute` instead of compiling it through
+    /// `sp_executesql`.
+    ///
+    /// Statements already in the cache are not prepared again. When the
+    /// cache is full, the least recently used statement is evicted and
+    /// unprepared before the next request. A connection reset
+    /// ([`mark_needs_reset`](Client::mark_needs_reset)) discards all
+    /// prepared statements.
+    ///
+    /// ```rust,ignore
+    /// client
+    ///     .prepare("SELECT Name FROM dbo.Users WHERE Id = @p1", &[&0i32])
+    ///     .await?;
+    /// // Runs with sp_execute
+    /// let rows = client
+    ///     .query("SELECT Name FROM dbo.Users WHERE Id = @p1", &[&42i32])
+    ///     .await?;
+    /// ```
+    pub async fn prepare(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<()> {
+        let rpc_params = self.convert_params(params)?;
+        let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+        let key = prepared_key(sql, &RpcRequest::build_param_declarations(&rpc_params));
+        if self.statement_cache.peek(&key).is_some() {
+            return Ok(());
         }
 
-        let cents = match bytes {
-            4 => buf.get_i32_le() as i64,
-            8 => {
-                let high = buf.get_i32_le();
-                let low = buf.get_u32_le();
-                ((high as i64) << 32) | (low as i64)
-            }
-            _ => return Err(Error::Protocol(format!("invalid money length: {bytes}"))),
-        };
+        self.send_rpc(&RpcRequest::prepare(sql, &rpc_params))
+            .await?;
+        let result = self.read_execute_response(false).await?;
+        let handle = result
+            .output_params
+            .iter()
+            .find_map(|p| match p.value {
+                mssql_types::SqlValue::Int(handle) => Some(handle),
+                _ => None,
+            })
+            .ok_or_else(|| Error::Protocol("sp_prepare returned no handle".to_string()))?;
+        tracing::debug!(sql = sql, handle = handle, "prepared statement");
 
-        #[cfg(feature = "decimal")]
+        if let Some(evicted) = self
+            .statement_cache
+            .insert(PreparedStatement::new(handle, key))
         {
-            use rust_decimal::Decimal;
-            Ok(SqlValue::Decimal(Decimal::from_i128_with_scale(
-                cents as i128,
-                4,
-            )))
+            self.defer_cleanup(format!("EXEC sp_unprepare {}", evicted.handle()));
         }
+        Ok(())
+    }
 
-        #[cfg(not(feature = "decimal"))]
-        {
-            Ok(SqlValue::Double((cents as f64) / 10000.0))
-        }
+    /// Get the cache of statements prepared with [`prepare`](Self::prepare).
+    #[must_use]
+    pub fn statement_cache(&self) -> &StatementCache {
+        &self.statement_cache
     }
 
-    /// Parse a single column value from a buffer based on column metadata.
-    fn parse_column_value(buf: &mut &[u8], col: &ColumnData) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
-        use tds_protocol::types::TypeId;
+    /// Take the enclave package to send with the next request.
+    ///
+    /// Once enclave support is negotiated every request carries one: the
+    /// package prepared by [`encrypt_params`](Self::encrypt_params), or an
+    /// empty one. Returns `None` on other connections.
+    fn take_enclave_package(&mut self) -> Option<bytes::Bytes> {
+        let ack = self.column_encryption.as_ref()?;
+        if ack.version < tds_protocol::login7::COLUMN_ENCRYPTION_VERSION_2 {
+            return None;
+        }
 
-        let value = match col.type_id {
-            // Fixed-length null type
-            TypeId::Null => SqlValue::Null,
+        #[cfg(feature = "always-encrypted")]
+        if let Some(package) = self.enclave_package.take() {
+            return Some(package);
+        }
+        Some(bytes::Bytes::new())
+    }
 
-            // 1-byte types
-            TypeId::Int1 => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading TINYINT".into()));
-                }
-                SqlValue::TinyInt(buf.get_u8())
-            }
-            TypeId::Bit => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading BIT".into()));
-                }
-                SqlValue::Bool(buf.get_u8() != 0)
+    /// Encrypt parameters that target Always Encrypted columns.
+    ///
+    /// Without column encryption configured and acknowledged by the server,
+    /// the parameters are returned unchanged. Otherwise `sp_describe_parameter_encryption` is called to
+    /// learn which parameters need encrypting and with which keys.
+    ///
+    /// When the server has a secure enclave and enclave attestation is
+    /// configured, the enclave is attested on first use and the keys it
+    /// requests are packaged for the next RPC request.
+    async fn encrypt_params(&mut self, sql: &str, params: Vec<RpcParam>) -> Result<Vec<RpcParam>> {
+        #[cfg(feature = "always-encrypted")]
+        if let Some(context) = self.config.column_encryption.clone() {
+            self.enclave_package = None;
+
+            // Without server support, parameters are sent unencrypted
+            let Some(ack) = self.column_encryption.clone() else {
+                return Ok(params);
+            };
+            if params.is_empty() {
+                return Ok(params);
             }
 
-            // 2-byte types
-            TypeId::Int2 => {
-                if buf.remaining() < 2 {
-                    return Err(Error::Protocol("unexpected EOF reading SMALLINT".into()));
+            // Attest the enclave unless a session with it is cached
+            let server = format!(
+                "{}:{}/{}",
+                self.config.host,
+                self.config.port,
+                self.session.database.as_deref().unwrap_or_default()
+            );
+            let mut enclave_session = None;
+            let mut attestation = None;
+            if ack.enclave_type.is_some() {
+                enclave_session = context.enclave_session(&server);
+                if enclave_session.is_none() {
+                    attestation = context.attestation_request()?;
                 }
-                SqlValue::SmallInt(buf.get_i16_le())
             }
 
-            // 4-byte types
-            TypeId::Int4 => {
-                if buf.remaining() < 4 {
-                    return Err(Error::Protocol("unexpected EOF reading INT".into()));
-                }
-                SqlValue::Int(buf.get_i32_le())
+            let mut describe = RpcRequest::named("sp_describe_parameter_encryption")
+                .param(RpcParam::nvarchar("@tsql", sql))
+                .param(RpcParam::nvarchar(
+                    "@params",
+                    &RpcRequest::build_param_declarations(&params),
+                ));
+            if let Some(request) = &attestation {
+                describe = describe.param(RpcParam::new(
+                    "@attestationParameters",
+                    RpcTypeInfo::varbinary(8000),
+                    bytes::Bytes::copy_from_slice(request.parameters()),
+                ));
             }
-            TypeId::Float4 => {
-                if buf.remaining() < 4 {
-                    return Err(Error::Protocol("unexpected EOF reading REAL".into()));
-                }
-                SqlValue::Float(buf.get_f32_le())
+            self.send_rpc(&describe).await?;
+
+            let mut result_sets = self.read_multi_result_response().await?;
+            let expected = if attestation.is_some() { 3 } else { 2 };
+            if result_sets.len() < expected {
+                return Err(Error::Protocol(format!(
+                    "sp_describe_parameter_encryption returned {} result sets, expected {expected}",
+                    result_sets.len()
+                )));
             }
+            let cek_rows = result_sets[0].collect_all();
+            let param_rows = result_sets[1].collect_all();
+            let info = crate::encryption::ParameterEncryptionInfo::from_describe_results(
+                &cek_rows,
+                &param_rows,
+            )?;
 
-            // 8-byte types
-            TypeId::Int8 => {
-                if buf.remaining() < 8 {
-                    return Err(Error::Protocol("unexpected EOF reading BIGINT".into()));
-                }
-                SqlValue::BigInt(buf.get_i64_le())
+            tracing::debug!(
+                encrypted_params = info.parameters.len(),
+                ceks = info.cek_table.len(),
+                enclave_ceks = info.enclave_ceks.len(),
+                "described parameter encryption"
+            );
+
+            if let Some(request) = attestation {
+                let attestation_info: Vec<u8> = result_sets[2]
+                    .collect_all()
+                    .first()
+                    .ok_or_else(|| {
+                        Error::Protocol(
+                            "sp_describe_parameter_encryption returned no attestation information"
+                                .into(),
+                        )
+                    })?
+                    .get(0)?;
+                enclave_session = Some(
+                    context
+                        .establish_enclave_session(&server, request, &attestation_info)
+                        .await?,
+                );
             }
-            TypeId::Float8 => {
-                if buf.remaining() < 8 {
-                    return Err(Error::Protocol("unexpected EOF reading FLOAT".into()));
-                }
-                SqlValue::Double(buf.get_f64_le())
+
+            if !info.enclave_ceks.is_empty() {
+                let session = enclave_session.ok_or_else(|| {
+                    Error::Config(
+                        "query requires enclave computations but enclave attestation is not configured"
+                            .into(),
+                    )
+                })?;
+                self.enclave_package = context
+                    .enclave_package(&session, &info, sql)
+                    .await?
+                    .map(bytes::Bytes::from);
             }
 
-            // Money types (fixed-point with 4 decimal places)
-            TypeId::Money | TypeId::Money4 | TypeId::MoneyN => {
-                let bytes = match col.type_id {
-                    TypeId::Money => 8,
-                    TypeId::Money4 => 4,
-                    TypeId::MoneyN => {
-                        if buf.remaining() < 1 {
-                            return Err(Error::Protocol(
-                                "unexpected EOF reading MoneyN length".into(),
-                            ));
-                        }
-                        buf.get_u8() as usize
-                    }
-                    _ => unreachable!(),
-                };
+            if info.parameters.is_empty() {
+                return Ok(params);
+            }
+            return Ok(context.encrypt_parameters(&info, params).await?);
+        }
 
-                if buf.remaining() < bytes {
-                    return Err(Error::Protocol(format!(
-                        "unexpected EOF reading money data ({bytes} bytes)"
-                    )));
-                }
+        #[cfg(not(feature = "always-encrypted"))]
+        let _ = sql;
+        Ok(params)
+    }
 
-                Self::parse_money_value(buf, bytes)?
+    /// Create a token parser for a response on this connection.
+    fn token_parser(&self, payload: bytes::Bytes) -> TokenParser {
+        let parser = TokenParser::new(payload)
+            .with_column_encryption(self.column_encryption.is_some())
+            .with_max_lob_size(self.request_max_lob_size.or(self.config.max_lob_size));
+        match self.server_version {
+            Some(version) => {
+                parser.with_tds_version(tds_protocol::version::TdsVersion::new(version))
             }
+            None => parser,
+        }
+    }
 
-            // Variable-length nullable types (IntN, FloatN, etc.)
-            TypeId::IntN => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading IntN length".into()));
-                }
-                let len = buf.get_u8();
-                match len {
-                    0 => SqlValue::Null,
-                    1 => SqlValue::TinyInt(buf.get_u8()),
-                    2 => SqlValue::SmallInt(buf.get_i16_le()),
-                    4 => SqlValue::Int(buf.get_i32_le()),
-                    8 => SqlValue::BigInt(buf.get_i64_le()),
-                    _ => {
-                        return Err(Error::Protocol(format!("invalid IntN length: {len}")));
+    /// Resolve the keys for a result set's Always Encrypted columns.
+    ///
+    /// Returns `None` when there is nothing to decrypt.
+    async fn column_decryptor(&self, meta: &ColMetaData) -> Result<Option<ColumnDecryptor>> {
+        #[cfg(feature = "always-encrypted")]
+        if let Some(context) = &self.config.column_encryption {
+            return Ok(context.column_decryptor(meta).await?);
+        }
+
+        #[cfg(not(feature = "always-encrypted"))]
+        let _ = meta;
+        Ok(None)
+    }
+
+    /// Decrypt a row whose result set has encrypted columns.
+    fn decrypt_row(
+        decryptor: Option<&ColumnDecryptor>,
+        row: crate::row::Row,
+    ) -> Result<crate::row::Row> {
+        match decryptor {
+            Some(decryptor) => decryptor.decrypt_row(row),
+            None => Ok(row),
+        }
+    }
+
+    /// Convert ToSql parameters to RPC parameters.
+    fn convert_params(&self, params: &[&(dyn crate::ToSql + Sync)]) -> Result<Vec<RpcParam>> {
+        use bytes::{BufMut, BytesMut};
+        use mssql_types::SqlValue;
+
+        params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let sql_value = p.to_sql()?;
+                let name = format!("@p{}", i + 1);
+                let direction = if !p.is_output() {
+                    ParamDirection::Input
+                } else if sql_value.is_null() {
+                    ParamDirection::Output
+                } else {
+                    ParamDirection::InputOutput
+                };
+
+                let param = match sql_value {
+                    SqlValue::Null => RpcParam::null(&name, RpcTypeInfo::nvarchar(1)),
+                    SqlValue::TypedNull(ty) => RpcParam::null(&name, Self::rpc_type_info(ty)?),
+                    SqlValue::Typed(
+                        ty @ (mssql_types::SqlType::VarChar(_) | mssql_types::SqlType::VarCharMax),
+                        ref value,
+                    ) => {
+                        let s = value.as_str().ok_or_else(|| {
+                            Error::Type(mssql_types::TypeError::UnsupportedConversion {
+                                from: value.type_name().to_string(),
+                                to: "VARCHAR",
+                            })
+                        })?;
+                        let bytes = self.encode_varchar_string(s)?;
+                        if let mssql_types::SqlType::VarChar(n) = ty {
+                            if bytes.len() > usize::from(n) {
+                                return Err(Error::Type(mssql_types::TypeError::Truncation(
+                                    format!(
+                                        "{} bytes do not fit {}",
+                                        bytes.len(),
+                                        ty.declaration()
+                                    ),
+                                )));
+                            }
+                        }
+                        self.varchar_param(&name, bytes, ty == mssql_types::SqlType::VarCharMax)
                     }
-                }
-            }
-            TypeId::FloatN => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading FloatN length".into(),
-                    ));
-                }
-                let len = buf.get_u8();
-                match len {
-                    0 => SqlValue::Null,
-                    4 => SqlValue::Float(buf.get_f32_le()),
-                    8 => SqlValue::Double(buf.get_f64_le()),
-                    _ => {
-                        return Err(Error::Protocol(format!("invalid FloatN length: {len}")));
+                    SqlValue::Typed(ty, ref value) => {
+                        let mut buf = BytesMut::new();
+                        ty.encode_value(value, &mut buf)?;
+                        RpcParam::new(&name, Self::rpc_type_info(ty)?, buf.freeze())
                     }
-                }
-            }
-            TypeId::BitN => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading BitN length".into()));
-                }
-                let len = buf.get_u8();
-                match len {
-                    0 => SqlValue::Null,
-                    1 => SqlValue::Bool(buf.get_u8() != 0),
+                    SqlValue::Bool(v) => {
+                        let mut buf = BytesMut::with_capacity(1);
+                        buf.put_u8(if v { 1 } else { 0 });
+                        RpcParam::new(&name, RpcTypeInfo::bit(), buf.freeze())
+                    }
+                    SqlValue::TinyInt(v) => {
+                        let mut buf = BytesMut::with_capacity(1);
+                        buf.put_u8(v);
+                        RpcParam::new(&name, RpcTypeInfo::tinyint(), buf.freeze())
+                    }
+                    SqlValue::SmallInt(v) => {
+                        let mut buf = BytesMut::with_capacity(2);
+                        buf.put_i16_le(v);
+                        RpcParam::new(&name, RpcTypeInfo::smallint(), buf.freeze())
+                    }
+                    SqlValue::Int(v) => RpcParam::int(&name, v),
+                    SqlValue::BigInt(v) => RpcParam::bigint(&name, v),
+                    SqlValue::Float(v) => {
+                        let mut buf = BytesMut::with_capacity(4);
+                        buf.put_f32_le(v);
+                        RpcParam::new(&name, RpcTypeInfo::real(), buf.freeze())
+                    }
+                    SqlValue::Double(v) => {
+                        let mut buf = BytesMut::with_capacity(8);
+                        buf.put_f64_le(v);
+                        RpcParam::new(&name, RpcTypeInfo::float(), buf.freeze())
+                    }
+                    SqlValue::String(ref s) if self.config.varchar_params => {
+                        match self.encode_varchar_string(s) {
+                            Ok(bytes) => {
+                                let max = bytes.len()
+                                    > usize::from(mssql_types::sql_type::MAX_VARCHAR_LEN);
+                                self.varchar_param(&name, bytes, max)
+                            }
+                            Err(e) => {
+                                tracing::debug!(error = %e, "sending string parameter as NVARCHAR");
+                                RpcParam::nvarchar(&name, s)
+                            }
+                        }
+                    }
+                    SqlValue::String(ref s) => RpcParam::nvarchar(&name, s),
+                    SqlValue::Binary(ref b) => {
+                        RpcParam::new(&name, RpcTypeInfo::varbinary(b.len() as u16), b.clone())
+                    }
+                    SqlValue::Xml(ref s) => RpcParam::nvarchar(&name, s),
+                    #[cfg(feature = "uuid")]
+                    SqlValue::Uuid(u) => {
+                        // UUID is stored in a specific byte order for SQL Server
+                        let bytes = u.as_bytes();
+                        let mut buf = BytesMut::with_capacity(16);
+                        // SQL Server stores GUIDs in mixed-endian format
+                        buf.put_u32_le(u32::from_be_bytes([
+                            bytes[0], bytes[1], bytes[2], bytes[3],
+                        ]));
+                        buf.put_u16_le(u16::from_be_bytes([bytes[4], bytes[5]]));
+                        buf.put_u16_le(u16::from_be_bytes([bytes[6], bytes[7]]));
+                        buf.put_slice(&bytes[8..16]);
+                        RpcParam::new(&name, RpcTypeInfo::uniqueidentifier(), buf.freeze())
+                    }
+                    #[cfg(feature = "decimal")]
+                    SqlValue::Decimal(d) => {
+                        // Decimal encoding is complex; use string representation for now
+                        RpcParam::nvarchar(&name, &d.to_string())
+                    }
+                    #[cfg(feature = "chrono")]
+                    SqlValue::Date(_)
+                    | SqlValue::Time(_)
+                    | SqlValue::DateTime(_)
+                    | SqlValue::DateTimeOffset(_) => {
+                        // For date/time types, use string representation for simplicity
+                        // A full implementation would encode these properly
+                        let s = match &sql_value {
+                            SqlValue::Date(d) => d.to_string(),
+                            SqlValue::Time(t) => t.to_string(),
+                            SqlValue::DateTime(dt) => dt.to_string(),
+                            SqlValue::DateTimeOffset(dto) => dto.to_rfc3339(),
+                            _ => unreachable!(),
+                        };
+                        RpcParam::nvarchar(&name, &s)
+                    }
+                    #[cfg(feature = "chrono")]
+                    SqlValue::ScaledDateTime2(v) => {
+                        let mut buf =
+                            BytesMut::with_capacity(mssql_types::datetime::datetime2_len(v.scale));
+                        mssql_types::datetime::encode_datetime2(v.value, v.scale, &mut buf);
+                        RpcParam::new(&name, RpcTypeInfo::datetime2(v.scale), buf.freeze())
+                    }
+                    #[cfg(feature = "chrono")]
+                    SqlValue::ScaledDateTimeOffset(v) => {
+                        let mut buf = BytesMut::with_capacity(
+                            mssql_types::datetime::datetimeoffset_len(v.scale),
+                        );
+                        mssql_types::datetime::encode_datetimeoffset(v.value, v.scale, &mut buf);
+                        RpcParam::new(&name, RpcTypeInfo::datetimeoffset(v.scale), buf.freeze())
+                    }
+                    #[cfg(feature = "json")]
+                    SqlValue::Json(ref j) => RpcParam::nvarchar(&name, &j.to_string()),
+                    SqlValue::Tvp(ref tvp_data) => {
+                        // Encode TVP using the wire format
+                        Self::encode_tvp_param(&name, tvp_data)?
+                    }
+                    // Handle future SqlValue variants
                     _ => {
-                        return Err(Error::Protocol(format!("invalid BitN length: {len}")));
+                        return Err(Error::Type(mssql_types::TypeError::UnsupportedConversion {
+                            from: sql_value.type_name().to_string(),
+                            to: "RPC parameter",
+                        }));
                     }
+                };
+                Ok(param.with_direction(direction))
+            })
+            .collect()
+    }
+
+    /// Build a VARCHAR parameter from text already encoded in the session
+    /// collation.
+    ///
+    /// Short values are all declared `VARCHAR(8000)` so they share a plan.
+    fn varchar_param(&self, name: &str, bytes: Vec<u8>, max: bool) -> RpcParam {
+        let collation = self.session.collation.map_or(DEFAULT_COLLATION, |c| {
+            let lcid = c.lcid.to_le_bytes();
+            [lcid[0], lcid[1], lcid[2], lcid[3], c.sort_id]
+        });
+        let type_info = if max {
+            RpcTypeInfo::varchar_max(collation)
+        } else {
+            RpcTypeInfo::varchar(mssql_types::sql_type::MAX_VARCHAR_LEN, collation)
+        };
+        RpcParam::new(name, type_info, bytes::Bytes::from(bytes))
+    }
+
+    /// Encode text in the code page of the session collation.
+    ///
+    /// Fails if the collation is unknown or cannot represent every character,
+    /// instead of substituting `?` as the server would.
+    fn encode_varchar_string(&self, s: &str) -> Result<Vec<u8>> {
+        if s.is_ascii() {
+            return Ok(s.as_bytes().to_vec());
+        }
+
+        #[cfg(feature = "encoding")]
+        if let Some(collation) = self.session.collation {
+            if collation.is_utf8() {
+                return Ok(s.as_bytes().to_vec());
+            }
+            if let Some(encoding) = collation.encoding() {
+                let (encoded, _, had_errors) = encoding.encode(s);
+                if !had_errors {
+                    return Ok(encoded.into_owned());
                 }
+                return Err(Error::Type(mssql_types::TypeError::InvalidEncoding(
+                    format!(
+                        "text cannot be represented in {}",
+                        collation.encoding_name()
+                    ),
+                )));
+            }
+        }
+
+        Err(Error::Type(mssql_types::TypeError::InvalidEncoding(
+            "non-ASCII VARCHAR parameters need a known collation and the `encoding` feature"
+                .to_string(),
+        )))
+    }
+
+    /// Map an explicit parameter type to its RPC type info.
+    fn rpc_type_info(ty: mssql_types::SqlType) -> Result<RpcTypeInfo> {
+        use mssql_types::SqlType;
+
+        ty.validate()?;
+        Ok(match ty {
+            SqlType::Bit => RpcTypeInfo::bit(),
+            SqlType::TinyInt => RpcTypeInfo::tinyint(),
+            SqlType::SmallInt => RpcTypeInfo::smallint(),
+            SqlType::Int => RpcTypeInfo::int(),
+            SqlType::BigInt => RpcTypeInfo::bigint(),
+            SqlType::Real => RpcTypeInfo::real(),
+            SqlType::Float => RpcTypeInfo::float(),
+            SqlType::Decimal { precision, scale } => {
+                let mut info = RpcTypeInfo::decimal(precision, scale);
+                // Sign byte plus the magnitude width for this precision
+                info.max_length = Some(match precision {
+                    0..=9 => 5,
+                    10..=19 => 9,
+                    20..=28 => 13,
+                    _ => 17,
+                });
+                info
             }
+            SqlType::NVarChar(n) => RpcTypeInfo::nvarchar(n),
+            SqlType::NVarCharMax => RpcTypeInfo::nvarchar_max(),
+            SqlType::NChar(n) => RpcTypeInfo::nchar(n),
+            SqlType::VarBinary(n) => RpcTypeInfo::varbinary(n),
+            SqlType::VarBinaryMax => RpcTypeInfo::varbinary(0xFFFF),
+            SqlType::UniqueIdentifier => RpcTypeInfo::uniqueidentifier(),
+            SqlType::Date => RpcTypeInfo::date(),
+            SqlType::Time(scale) => RpcTypeInfo::time(scale),
+            SqlType::DateTime => RpcTypeInfo::datetime(),
+            SqlType::SmallDateTime => RpcTypeInfo::smalldatetime(),
+            SqlType::DateTime2(scale) => RpcTypeInfo::datetime2(scale),
+            SqlType::DateTimeOffset(scale) => RpcTypeInfo::datetimeoffset(scale),
+            _ => {
+                return Err(Error::Type(mssql_types::TypeError::UnsupportedConversion {
+                    from: ty.declaration(),
+                    to: "RPC parameter",
+                }));
+            }
+        })
+    }
 
-            // DECIMAL/NUMERIC types (1-byte length prefix)
-            TypeId::Decimal | TypeId::Numeric | TypeId::DecimalN | TypeId::NumericN => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading DECIMAL/NUMERIC length".into(),
-                    ));
+    /// Encode a TVP parameter for RPC.
+    ///
+    /// This encodes the complete TVP structure including metadata and row data
+    /// into the TDS wire format.
+    fn encode_tvp_param(name: &str, tvp_data: &mssql_types::TvpData) -> Result<RpcParam> {
+        // Convert mssql-types column definitions to wire format
+        let wire_columns: Vec<TvpWireColumnDef> = tvp_data
+            .columns
+            .iter()
+            .map(|col| {
+                let wire_type = Self::convert_tvp_column_type(&col.column_type);
+                TvpWireColumnDef {
+                    wire_type,
+                    flags: TvpColumnFlags {
+                        nullable: col.nullable,
+                        default: col.default,
+                    },
                 }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
-                    SqlValue::Null
-                } else {
-                    if buf.remaining() < len {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading DECIMAL/NUMERIC data".into(),
-                        ));
-                    }
+            })
+            .collect();
 
-                    // First byte is sign: 0 = negative, 1 = positive
-                    let sign = buf.get_u8();
-                    let mantissa_len = len - 1;
+        // Convert sort order and uniqueness hints
+        let order_unique = tvp_data
+            .order_hints
+            .iter()
+            .map(|hint| {
+                if hint.column >= wire_columns.len() {
+                    return Err(Error::Config(format!(
+                        "TVP order hint refers to column {} but '{}' has {} columns",
+                        hint.column,
+                        tvp_data.type_name,
+                        wire_columns.len()
+                    )));
+                }
+                let mut flags = match hint.order {
+                    Some(mssql_types::TvpSortOrder::Ascending) => TVP_ORDER_ASC,
+                    Some(mssql_types::TvpSortOrder::Descending) => TVP_ORDER_DESC,
+                    None => 0,
+                };
+                if hint.unique {
+                    flags |= TVP_UNIQUE;
+                }
+                Ok(TvpOrderUnique::new(hint.column as u16, flags))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-                    // Read mantissa as little-endian integer (up to 16 bytes for max precision 38)
-                    let mut mantissa_bytes = [0u8; 16];
-                    for i in 0..mantissa_len.min(16) {
-                        mantissa_bytes[i] = buf.get_u8();
-                    }
-                    // Skip any excess bytes (shouldn't happen with valid data)
-                    for _ in 16..mantissa_len {
-                        buf.get_u8();
-                    }
+        // Create encoder
+        let encoder = TvpEncoder::new(&tvp_data.schema, &tvp_data.type_name, &wire_columns)
+            .with_order_unique(&order_unique);
 
-                    let mantissa = u128::from_le_bytes(mantissa_bytes);
-                    let scale = col.type_info.scale.unwrap_or(0) as u32;
+        // Encode to buffer
+        let mut buf = BytesMut::with_capacity(256);
 
-                    #[cfg(feature = "decimal")]
-                    {
-                        use rust_decimal::Decimal;
-                        // rust_decimal supports max scale of 28
-                        // For scales > 28, fall back to f64 to avoid overflow/hang
-                        if scale > 28 {
-                            // Fall back to f64 for high-scale decimals
-                            let divisor = 10f64.powi(scale as i32);
-                            let value = (mantissa as f64) / divisor;
-                            let value = if sign == 0 { -value } else { value };
-                            SqlValue::Double(value)
-                        } else {
-                            let mut decimal =
-                                Decimal::from_i128_with_scale(mantissa as i128, scale);
-                            if sign == 0 {
-                                decimal.set_sign_negative(true);
-                            }
-                            SqlValue::Decimal(decimal)
-                        }
-                    }
+        // Encode metadata
+        encoder.encode_metadata(&mut buf);
 
-                    #[cfg(not(feature = "decimal"))]
-                    {
-                        // Without the decimal feature, convert to f64
-                        let divisor = 10f64.powi(scale as i32);
-                        let value = (mantissa as f64) / divisor;
-                        let value = if sign == 0 { -value } else { value };
-                        SqlValue::Double(value)
+        // Encode each row
+        for row in &tvp_data.rows {
+            encoder.encode_row(&mut buf, |row_buf| {
+                // Default columns send no data
+                for (value, column) in row.iter().zip(&wire_columns) {
+                    if !column.is_default() {
+                        Self::encode_tvp_value(value, &column.wire_type, row_buf);
+                    }
+                }
+            });
+        }
+
+        // Encode end marker
+        encoder.encode_end(&mut buf);
+
+        // Build the full TVP type name (schema.TypeName)
+        let full_type_name = if tvp_data.schema.is_empty() {
+            tvp_data.type_name.clone()
+        } else {
+            format!("{}.{}", tvp_data.schema, tvp_data.type_name)
+        };
+
+        // Create RPC param with TVP type info
+        // The type info includes the TVP type name for parameter declarations
+        let type_info = RpcTypeInfo::tvp(&full_type_name);
+
+        Ok(RpcParam {
+            name: name.to_string(),
+            flags: tds_protocol::rpc::ParamFlags::default(),
+            type_info,
+            value: Some(buf.freeze()),
+            cipher_info: None,
+        })
+    }
+
+    /// Convert mssql-types TvpColumnType to wire TvpWireType.
+    fn convert_tvp_column_type(col_type: &mssql_types::TvpColumnType) -> TvpWireType {
+        match col_type {
+            mssql_types::TvpColumnType::Bit => TvpWireType::Bit,
+            mssql_types::TvpColumnType::TinyInt => TvpWireType::Int { size: 1 },
+            mssql_types::TvpColumnType::SmallInt => TvpWireType::Int { size: 2 },
+            mssql_types::TvpColumnType::Int => TvpWireType::Int { size: 4 },
+            mssql_types::TvpColumnType::BigInt => TvpWireType::Int { size: 8 },
+            mssql_types::TvpColumnType::Real => TvpWireType::Float { size: 4 },
+            mssql_types::TvpColumnType::Float => TvpWireType::Float { size: 8 },
+            mssql_types::TvpColumnType::Decimal { precision, scale } => TvpWireType::Decimal {
+                precision: *precision,
+                scale: *scale,
+            },
+            mssql_types::TvpColumnType::NVarChar { max_length } => TvpWireType::NVarChar {
+                max_length: *max_length,
+            },
+            mssql_types::TvpColumnType::VarChar { max_length } => TvpWireType::VarChar {
+                max_length: *max_length,
+            },
+            mssql_types::TvpColumnType::VarBinary { max_length } => TvpWireType::VarBinary {
+                max_length: *max_length,
+            },
+            mssql_types::TvpColumnType::UniqueIdentifier => TvpWireType::Guid,
+            mssql_types::TvpColumnType::Date => TvpWireType::Date,
+            mssql_types::TvpColumnType::Time { scale } => TvpWireType::Time { scale: *scale },
+            mssql_types::TvpColumnType::DateTime2 { scale } => {
+                TvpWireType::DateTime2 { scale: *scale }
+            }
+            mssql_types::TvpColumnType::DateTimeOffset { scale } => {
+                TvpWireType::DateTimeOffset { scale: *scale }
+            }
+            mssql_types::TvpColumnType::Xml => TvpWireType::Xml,
+        }
+    }
+
+    /// Encode a single TVP column value.
+    fn encode_tvp_value(
+        value: &mssql_types::SqlValue,
+        wire_type: &TvpWireType,
+        buf: &mut BytesMut,
+    ) {
+        use mssql_types::SqlValue;
+
+        match value {
+            SqlValue::Null => {
+                encode_tvp_null(wire_type, buf);
+            }
+            SqlValue::Bool(v) => {
+                encode_tvp_bit(*v, buf);
+            }
+            SqlValue::TinyInt(v) => {
+                encode_tvp_int(*v as i64, 1, buf);
+            }
+            SqlValue::SmallInt(v) => {
+                encode_tvp_int(*v as i64, 2, buf);
+            }
+            SqlValue::Int(v) => {
+                encode_tvp_int(*v as i64, 4, buf);
+            }
+            SqlValue::BigInt(v) => {
+                encode_tvp_int(*v, 8, buf);
+            }
+            SqlValue::Float(v) => {
+                encode_tvp_float(*v as f64, 4, buf);
+            }
+            SqlValue::Double(v) => {
+                encode_tvp_float(*v, 8, buf);
+            }
+            SqlValue::String(s) => {
+                let max_len = match wire_type {
+                    TvpWireType::NVarChar { max_length } => *max_length,
+                    _ => 4000,
+                };
+                encode_tvp_nvarchar(s, max_len, buf);
+            }
+            SqlValue::Binary(b) => {
+                let max_len = match wire_type {
+                    TvpWireType::VarBinary { max_length } => *max_length,
+                    _ => 8000,
+                };
+                encode_tvp_varbinary(b, max_len, buf);
+            }
+            #[cfg(feature = "decimal")]
+            SqlValue::Decimal(d) => {
+                let sign = if d.is_sign_negative() { 0u8 } else { 1u8 };
+                let mantissa = d.mantissa().unsigned_abs();
+                encode_tvp_decimal(sign, mantissa, buf);
+            }
+            #[cfg(feature = "uuid")]
+            SqlValue::Uuid(u) => {
+                let bytes = u.as_bytes();
+                tds_protocol::tvp::encode_tvp_guid(bytes, buf);
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::Date(d) => {
+                // Calculate days since 0001-01-01
+                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                let days = d.signed_duration_since(base).num_days() as u32;
+                tds_protocol::tvp::encode_tvp_date(days, buf);
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::Time(t) => {
+                use chrono::Timelike;
+                let nanos =
+                    t.num_seconds_from_midnight() as u64 * 1_000_000_000 + t.nanosecond() as u64;
+                let intervals = nanos / 100;
+                let scale = match wire_type {
+                    TvpWireType::Time { scale } => *scale,
+                    _ => 7,
+                };
+                tds_protocol::tvp::encode_tvp_time(intervals, scale, buf);
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::DateTime(dt) => {
+                use chrono::Timelike;
+                // Time component
+                let nanos = dt.time().num_seconds_from_midnight() as u64 * 1_000_000_000
+                    + dt.time().nanosecond() as u64;
+                let intervals = nanos / 100;
+                // Date component
+                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                let days = dt.date().signed_duration_since(base).num_days() as u32;
+                let scale = match wire_type {
+                    TvpWireType::DateTime2 { scale } => *scale,
+                    _ => 7,
+                };
+                tds_protocol::tvp::encode_tvp_datetime2(intervals, days, scale, buf);
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::DateTimeOffset(dto) => {
+                use chrono::{Offset, Timelike};
+                // Time and date are sent in UTC
+                let utc = dto.naive_utc();
+                // Time component (in 100-nanosecond intervals)
+                let nanos = utc.time().num_seconds_from_midnight() as u64 * 1_000_000_000
+                    + utc.time().nanosecond() as u64;
+                let intervals = nanos / 100;
+                // Date component (days since 0001-01-01)
+                let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                let days = utc.date().signed_duration_since(base).num_days() as u32;
+                // Timezone offset in minutes
+                let offset_minutes = (dto.offset().fix().local_minus_utc() / 60) as i16;
+                let scale = match wire_type {
+                    TvpWireType::DateTimeOffset { scale } => *scale,
+                    _ => 7,
+                };
+                tds_protocol::tvp::encode_tvp_datetimeoffset(
+                    intervals,
+                    days,
+                    offset_minutes,
+                    scale,
+                    buf,
+                );
+            }
+            // The TVP column definition declares the scale
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTime2(v) => {
+                Self::encode_tvp_value(&SqlValue::DateTime(v.value), wire_type, buf);
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTimeOffset(v) => {
+                Self::encode_tvp_value(&SqlValue::DateTimeOffset(v.value), wire_type, buf);
+            }
+            #[cfg(feature = "json")]
+            SqlValue::Json(j) => {
+                // JSON is encoded as NVARCHAR
+                encode_tvp_nvarchar(&j.to_string(), 0xFFFF, buf);
+            }
+            SqlValue::Xml(s) => {
+                // XML is encoded as NVARCHAR for TVP
+                encode_tvp_nvarchar(s, 0xFFFF, buf);
+            }
+            SqlValue::Tvp(_) => {
+                // Nested TVPs are not supported
+                encode_tvp_null(wire_type, buf);
+            }
+            // Handle future SqlValue variants as NULL
+            _ => {
+                encode_tvp_null(wire_type, buf);
+            }
+        }
+    }
+
+    /// Read complete query response including columns and rows.
+    async fn read_query_response(
+        &mut self,
+    ) -> Result<(Vec<crate::row::Column>, Vec<crate::row::Row>)> {
+        self.messages.clear();
+        let message = self.read_response().await?;
+
+        let mut parser = self.token_parser(message.payload);
+        let mut columns: Vec<crate::row::Column> = Vec::new();
+        let mut row_metadata = None;
+        let mut rows: Vec<crate::row::Row> = Vec::new();
+        let mut protocol_metadata: Option<ColMetaData> = None;
+        let mut decryptor: Option<ColumnDecryptor> = None;
+        let mut tab_name: Option<TabName> = None;
+        let in_transaction = self.transaction_descriptor != 0;
+        let mut server_error: Option<DatabaseError> = None;
+
+        loop {
+            // Use next_token_with_metadata to properly parse Row/NbcRow tokens
+            let token = parser
+                .next_token_with_metadata(protocol_metadata.as_ref())
+                .map_err(|e| Error::Protocol(e.to_string()))?;
+
+            let Some(token) = token else {
+                break;
+            };
+
+            match token {
+                Token::ColMetaData(meta) => {
+                    // New result set starting - clear previous rows
+                    // This enables multi-statement batches to return the last result set
+                    rows.clear();
+
+                    columns = Self::build_columns(&meta, self.config.time_zone);
+                    row_metadata = None;
+                    decryptor = self.column_decryptor(&meta).await?;
+
+                    tracing::debug!(columns = columns.len(), "received column metadata");
+                    protocol_metadata = Some(meta);
+                }
+                Token::TabName(tables) => {
+                    tab_name = Some(tables);
+                }
+                Token::ColInfo(info) => {
+                    Self::apply_col_info(&mut columns, tab_name.as_ref(), &info);
+                    row_metadata = None;
+                }
+                Token::Row(raw_row) => {
+                    if let Some(ref meta) = protocol_metadata {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &columns);
+                        let row = Self::convert_raw_row(&raw_row, meta, shared)?;
+                        rows.push(Self::decrypt_row(decryptor.as_ref(), row)?);
+                    }
+                }
+                Token::NbcRow(nbc_row) => {
+                    if let Some(ref meta) = protocol_metadata {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &columns);
+                        let row = Self::convert_nbc_row(&nbc_row, meta, shared)?;
+                        rows.push(Self::decrypt_row(decryptor.as_ref(), row)?);
+                    }
+                }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    server_error.get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::Done(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                    tracing::debug!(
+                        row_count = done.row_count,
+                        has_more = done.status.more,
+                        "query complete"
+                    );
+                    // Only break if there are no more result sets
+                    // This enables multi-statement batches to process all results
+                    if !done.status.more {
+                        break;
+                    }
+                }
+                Token::DoneProc(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                }
+                Token::DoneInProc(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                }
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                }
+                Token::EnvChange(env) => {
+                    // Process transaction-related EnvChange tokens.
+                    // This allows BEGIN TRANSACTION, COMMIT, ROLLBACK via raw SQL
+                    // to properly update the transaction descriptor.
+                    self.handle_env_change(&env).await;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(err) = server_error {
+            return Err(self.server_error(err, in_transaction));
+        }
+
+        tracing::debug!(
+            columns = columns.len(),
+            rows = rows.len(),
+            "query response parsed"
+        );
+        Ok((columns, rows))
+    }
+
+    /// Build the public column descriptions from protocol column metadata.
+    pub(crate) fn build_columns(
+        meta: &ColMetaData,
+        time_zone: mssql_types::TimeZonePolicy,
+    ) -> Vec<crate::row::Column> {
+        meta.columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| {
+                // Encrypted columns are described by the type they decrypt to
+                let plaintext;
+                let col = if col.is_encrypted() {
+                    plaintext = col.plaintext_column();
+                    &plaintext
+                } else {
+                    col
+                };
+                let type_name = format!("{:?}", col.type_id);
+                let mut column = crate::row::Column::new(&col.name, i, type_name)
+                    .with_nullable(col.flags & 0x01 != 0);
+
+                if let Some(max_len) = col.type_info.max_length {
+                    column = column.with_max_length(max_len);
+                }
+                if let (Some(prec), Some(scale)) = (col.type_info.precision, col.type_info.scale) {
+                    column = column.with_precision_scale(prec, scale);
+                }
+                // Store collation for VARCHAR/CHAR types to enable
+                // collation-aware string decoding
+                if let Some(collation) = col.type_info.collation {
+                    column = column.with_collation(collation);
+                }
+                column.case_sensitive = col.is_case_sensitive();
+                column.identity = col.is_identity();
+                column.computed = col.is_computed();
+                column.updatable = col.is_updatable();
+                column.time_zone = time_zone;
+                column
+            })
+            .collect()
+    }
+
+    /// Record the base table and column names of a browse-mode result set.
+    pub(crate) fn apply_col_info(
+        columns: &mut [crate::row::Column],
+        tab_name: Option<&TabName>,
+        col_info: &ColInfo,
+    ) {
+        for property in &col_info.columns {
+            let Some(column) = usize::from(property.column)
+                .checked_sub(1)
+                .and_then(|i| columns.get_mut(i))
+            else {
+                continue;
+            };
+            column.key = property.is_key();
+            column.hidden = property.is_hidden();
+            let table = usize::from(property.table)
+                .checked_sub(1)
+                .and_then(|i| tab_name?.tables.get(i));
+            match table {
+                Some(parts) if !property.is_expression() => {
+                    // Quoted part by part, as parts may contain dots
+                    column.base_table = Some(
+                        parts
+                            .iter()
+                            .map(|part| crate::quote_identifier(part))
+                            .collect::<Vec<_>>()
+                            .join("."),
+                    );
+                    column.base_column = Some(
+                        property
+                            .base_name
+                            .clone()
+                            .unwrap_or_else(|| column.name.clone()),
+                    );
+                }
+                _ => {
+                    column.base_table = None;
+                    column.base_column = None;
+                }
+            }
+        }
+    }
+
+    /// Get the row metadata shared by all rows of the current result set,
+    /// building it from `columns` for the first row.
+    pub(crate) fn shared_row_metadata<'m>(
+        shared: &'m mut Option<Arc<crate::row::ColMetaData>>,
+        columns: &[crate::row::Column],
+    ) -> &'m Arc<crate::row::ColMetaData> {
+        shared.get_or_insert_with(|| Arc::new(crate::row::ColMetaData::new(columns.to_vec())))
+    }
+
+    /// Convert a RawRow to a client Row.
+    ///
+    /// This parses the raw bytes back into SqlValue types based on column metadata.
+    pub(crate) fn convert_raw_row(
+        raw: &RawRow,
+        meta: &ColMetaData,
+        columns: &Arc<crate::row::ColMetaData>,
+    ) -> Result<crate::row::Row> {
+        let mut values = Vec::with_capacity(meta.columns.len());
+        let mut buf = raw.data.as_ref();
+
+        for col in &meta.columns {
+            let value = Self::parse_column_value(&mut buf, col)?;
+            values.push(value);
+        }
+
+        Ok(crate::row::Row::from_shared_values(
+            Arc::clone(columns),
+            values,
+        ))
+    }
+
+    /// Convert an NbcRow to a client Row.
+    ///
+    /// NbcRow has a null bitmap followed by only non-null values.
+    pub(crate) fn convert_nbc_row(
+        nbc: &NbcRow,
+        meta: &ColMetaData,
+        columns: &Arc<crate::row::ColMetaData>,
+    ) -> Result<crate::row::Row> {
+        let mut values = Vec::with_capacity(meta.columns.len());
+        let mut buf = nbc.data.as_ref();
+
+        for (i, col) in meta.columns.iter().enumerate() {
+            if nbc.is_null(i) {
+                values.push(mssql_types::SqlValue::Null);
+            } else {
+                let value = Self::parse_column_value(&mut buf, col)?;
+                values.push(value);
+            }
+        }
+
+        Ok(crate::row::Row::from_shared_values(
+            Arc::clone(columns),
+            values,
+        ))
+    }
+
+    /// Parse money value from buffer and convert to appropriate type.
+    ///
+    /// Money is stored as fixed-point with 4 decimal places.
+    /// - 4 bytes: SMALLMONEY
+    /// - 8 bytes: MONEY
+    fn parse_money_value(buf: &mut &[u8], bytes: usize) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+
+        if bytes == 0 {
+            return Ok(SqlValue::Null);
+        }
+
+        let cents = match bytes {
+            4 => buf.get_i32_le() as i64,
+            8 => {
+                let high = buf.get_i32_le();
+                let low = buf.get_u32_le();
+                ((high as i64) << 32) | (low as i64)
+            }
+            _ => return Err(Error::Protocol(format!("invalid money length: {bytes}"))),
+        };
+
+        #[cfg(feature = "decimal")]
+        {
+            use rust_decimal::Decimal;
+            Ok(SqlValue::Decimal(Decimal::from_i128_with_scale(
+                cents as i128,
+                4,
+            )))
+        }
+
+        #[cfg(not(feature = "decimal"))]
+        {
+            Ok(SqlValue::Double((cents as f64) / 10000.0))
+        }
+    }
+
+    /// Parse a single column value from a buffer based on column metadata.
+    pub(crate) fn parse_column_value(
+        buf: &mut &[u8],
+        col: &ColumnData,
+    ) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+        use tds_protocol::types::TypeId;
+
+        let value = match col.type_id {
+            // Fixed-length null type
+            TypeId::Null => SqlValue::Null,
+
+            // 1-byte types
+            TypeId::Int1 => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading TINYINT".into()));
+                }
+                SqlValue::TinyInt(buf.get_u8())
+            }
+            TypeId::Bit => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading BIT".into()));
+                }
+                SqlValue::Bool(buf.get_u8() != 0)
+            }
+
+            // 2-byte types
+            TypeId::Int2 => {
+                if buf.remaining() < 2 {
+                    return Err(Error::Protocol("unexpected EOF reading SMALLINT".into()));
+                }
+                SqlValue::SmallInt(buf.get_i16_le())
+            }
+
+            // 4-byte types
+            TypeId::Int4 => {
+                if buf.remaining() < 4 {
+                    return Err(Error::Protocol("unexpected EOF reading INT".into()));
+                }
+                SqlValue::Int(buf.get_i32_le())
+            }
+            TypeId::Float4 => {
+                if buf.remaining() < 4 {
+                    return Err(Error::Protocol("unexpected EOF reading REAL".into()));
+                }
+                SqlValue::Float(buf.get_f32_le())
+            }
+
+            // 8-byte types
+            TypeId::Int8 => {
+                if buf.remaining() < 8 {
+                    return Err(Error::Protocol("unexpected EOF reading BIGINT".into()));
+                }
+                SqlValue::BigInt(buf.get_i64_le())
+            }
+            TypeId::Float8 => {
+                if buf.remaining() < 8 {
+                    return Err(Error::Protocol("unexpected EOF reading FLOAT".into()));
+                }
+                SqlValue::Double(buf.get_f64_le())
+            }
+
+            // Money types (fixed-point with 4 decimal places)
+            TypeId::Money | TypeId::Money4 | TypeId::MoneyN => {
+                let bytes = match col.type_id {
+                    TypeId::Money => 8,
+                    TypeId::Money4 => 4,
+                    TypeId::MoneyN => {
+                        if buf.remaining() < 1 {
+                            return Err(Error::Protocol(
+                                "unexpected EOF reading MoneyN length".into(),
+                            ));
+                        }
+                        buf.get_u8() as usize
+                    }
+                    _ => unreachable!(),
+                };
+
+                if buf.remaining() < bytes {
+                    return Err(Error::Protocol(format!(
+                        "unexpected EOF reading money data ({bytes} bytes)"
+                    )));
+                }
+
+                Self::parse_money_value(buf, bytes)?
+            }
+
+            // Variable-length nullable types (IntN, FloatN, etc.)
+            TypeId::IntN => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading IntN length".into()));
+                }
+                let len = buf.get_u8();
+                match len {
+                    0 => SqlValue::Null,
+                    1 => SqlValue::TinyInt(buf.get_u8()),
+                    2 => SqlValue::SmallInt(buf.get_i16_le()),
+                    4 => SqlValue::Int(buf.get_i32_le()),
+                    8 => SqlValue::BigInt(buf.get_i64_le()),
+                    _ => {
+                        return Err(Error::Protocol(format!("invalid IntN length: {len}")));
+                    }
+                }
+            }
+            TypeId::FloatN => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading FloatN length".into(),
+                    ));
+                }
+                let len = buf.get_u8();
+                match len {
+                    0 => SqlValue::Null,
+                    4 => SqlValue::Float(buf.get_f32_le()),
+                    8 => SqlValue::Double(buf.get_f64_le()),
+                    _ => {
+                        return Err(Error::Protocol(format!("invalid FloatN length: {len}")));
+                    }
+                }
+            }
+            TypeId::BitN => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading BitN length".into()));
+                }
+                let len = buf.get_u8();
+                match len {
+                    0 => SqlValue::Null,
+                    1 => SqlValue::Bool(buf.get_u8() != 0),
+                    _ => {
+                        return Err(Error::Protocol(format!("invalid BitN length: {len}")));
+                    }
+                }
+            }
+
+            // DECIMAL/NUMERIC types (1-byte length prefix)
+            TypeId::Decimal | TypeId::Numeric | TypeId::DecimalN | TypeId::NumericN => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading DECIMAL/NUMERIC length".into(),
+                    ));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else {
+                    if buf.remaining() < len {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading DECIMAL/NUMERIC data".into(),
+                        ));
+                    }
+
+                    // First byte is sign: 0 = negative, 1 = positive
+                    let sign = buf.get_u8();
+                    let mantissa_len = len - 1;
+
+                    // Read mantissa as little-endian integer (up to 16 bytes for max precision 38)
+                    let mut mantissa_bytes = [0u8; 16];
+                    for i in 0..mantissa_len.min(16) {
+                        mantissa_bytes[i] = buf.get_u8();
+                    }
+                    // Skip any excess bytes (shouldn't happen with valid data)
+                    for _ in 16..mantissa_len {
+                        buf.get_u8();
+                    }
+
+                    let mantissa = u128::from_le_bytes(mantissa_bytes);
+                    let scale = col.type_info.scale.unwrap_or(0) as u32;
+
+                    #[cfg(feature = "decimal")]
+                    {
+                        use rust_decimal::Decimal;
+                        // rust_decimal supports max scale of 28
+                        // For scales > 28, fall back to f64 to avoid overflow/hang
+                        if scale > 28 {
+                            // Fall back to f64 for high-scale decimals
+                            let divisor = 10f64.powi(scale as i32);
+                            let value = (mantissa as f64) / divisor;
+                            let value = if sign == 0 { -value } else { value };
+                            SqlValue::Double(value)
+                        } else {
+                            let mut decimal =
+                                Decimal::from_i128_with_scale(mantissa as i128, scale);
+                            if sign == 0 {
+                                decimal.set_sign_negative(true);
+                            }
+                            SqlValue::Decimal(decimal)
+                        }
+                    }
+
+                    #[cfg(not(feature = "decimal"))]
+                    {
+                        // Without the decimal feature, convert to f64
+                        let divisor = 10f64.powi(scale as i32);
+                        let value = (mantissa as f64) / divisor;
+                        let value = if sign == 0 { -value } else { value };
+                        SqlValue::Double(value)
+                    }
+                }
+            }
+
+            // DATETIME/SMALLDATETIME nullable (1-byte length prefix)
+            TypeId::DateTimeN => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading DateTimeN length".into(),
+                    ));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else if buf.remaining() < len {
+                    return Err(Error::Protocol("unexpected EOF reading DateTimeN".into()));
+                } else {
+                    match len {
+                        4 => {
+                            // SMALLDATETIME: 2 bytes days + 2 bytes minutes
+                            let days = buf.get_u16_le() as i64;
+                            let minutes = buf.get_u16_le() as u32;
+                            #[cfg(feature = "chrono")]
+                            {
+                                let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+                                let date = base + chrono::Duration::days(days);
+                                let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(
+                                    minutes * 60,
+                                    0,
+                                )
+                                .unwrap();
+                                SqlValue::DateTime(date.and_time(time))
+                            }
+                            #[cfg(not(feature = "chrono"))]
+                            {
+                                SqlValue::String(format!("SMALLDATETIME({days},{minutes})"))
+                            }
+                        }
+                        8 => {
+                            // DATETIME: 4 bytes days + 4 bytes 1/300ths of second
+                            let days = buf.get_i32_le() as i64;
+                            let time_300ths = buf.get_u32_le() as u64;
+                            #[cfg(feature = "chrono")]
+                            {
+                                let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+                                let date = base + chrono::Duration::days(days);
+                                // Convert 300ths of second to nanoseconds
+                                let total_ms = (time_300ths * 1000) / 300;
+                                let secs = (total_ms / 1000) as u32;
+                                let nanos = ((total_ms % 1000) * 1_000_000) as u32;
+                                let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(
+                                    secs, nanos,
+                                )
+                                .unwrap();
+                                SqlValue::DateTime(date.and_time(time))
+                            }
+                            #[cfg(not(feature = "chrono"))]
+                            {
+                                SqlValue::String(format!("DATETIME({days},{time_300ths})"))
+                            }
+                        }
+                        _ => {
+                            return Err(Error::Protocol(format!(
+                                "invalid DateTimeN length: {len}"
+                            )));
+                        }
+                    }
+                }
+            }
+
+            // Fixed DATETIME (8 bytes)
+            TypeId::DateTime => {
+                if buf.remaining() < 8 {
+                    return Err(Error::Protocol("unexpected EOF reading DATETIME".into()));
+                }
+                let days = buf.get_i32_le() as i64;
+                let time_300ths = buf.get_u32_le() as u64;
+                #[cfg(feature = "chrono")]
+                {
+                    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+                    let date = base + chrono::Duration::days(days);
+                    let total_ms = (time_300ths * 1000) / 300;
+                    let secs = (total_ms / 1000) as u32;
+                    let nanos = ((total_ms % 1000) * 1_000_000) as u32;
+                    let time =
+                        chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).unwrap();
+                    SqlValue::DateTime(date.and_time(time))
+                }
+                #[cfg(not(feature = "chrono"))]
+                {
+                    SqlValue::String(format!("DATETIME({days},{time_300ths})"))
+                }
+            }
+
+            // Fixed SMALLDATETIME (4 bytes)
+            TypeId::DateTime4 => {
+                if buf.remaining() < 4 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading SMALLDATETIME".into(),
+                    ));
+                }
+                let days = buf.get_u16_le() as i64;
+                let minutes = buf.get_u16_le() as u32;
+                #[cfg(feature = "chrono")]
+                {
+                    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+                    let date = base + chrono::Duration::days(days);
+                    let time =
+                        chrono::NaiveTime::from_num_seconds_from_midnight_opt(minutes * 60, 0)
+                            .unwrap();
+                    SqlValue::DateTime(date.and_time(time))
+                }
+                #[cfg(not(feature = "chrono"))]
+                {
+                    SqlValue::String(format!("SMALLDATETIME({days},{minutes})"))
+                }
+            }
+
+            // DATE (3 bytes, nullable with 1-byte length prefix)
+            TypeId::Date => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading DATE length".into()));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else if len != 3 {
+                    return Err(Error::Protocol(format!("invalid DATE length: {len}")));
+                } else if buf.remaining() < 3 {
+                    return Err(Error::Protocol("unexpected EOF reading DATE".into()));
+                } else {
+                    // 3 bytes little-endian days since 0001-01-01
+                    let days = buf.get_u8() as u32
+                        | ((buf.get_u8() as u32) << 8)
+                        | ((buf.get_u8() as u32) << 16);
+                    #[cfg(feature = "chrono")]
+                    {
+                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                        let date = base + chrono::Duration::days(days as i64);
+                        SqlValue::Date(date)
+                    }
+                    #[cfg(not(feature = "chrono"))]
+                    {
+                        SqlValue::String(format!("DATE({days})"))
+                    }
+                }
+            }
+
+            // TIME (variable length with scale, 1-byte length prefix)
+            TypeId::Time => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading TIME length".into()));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else if buf.remaining() < len {
+                    return Err(Error::Protocol("unexpected EOF reading TIME".into()));
+                } else {
+                    let mut time_bytes = [0u8; 8];
+                    for byte in time_bytes.iter_mut().take(len) {
+                        *byte = buf.get_u8();
+                    }
+                    let intervals = u64::from_le_bytes(time_bytes);
+                    #[cfg(feature = "chrono")]
+                    {
+                        let scale = col.type_info.scale.unwrap_or(7);
+                        let time = Self::intervals_to_time(intervals, scale);
+                        SqlValue::Time(time)
+                    }
+                    #[cfg(not(feature = "chrono"))]
+                    {
+                        SqlValue::String(format!("TIME({intervals})"))
+                    }
+                }
+            }
+
+            // DATETIME2 (variable length: TIME bytes + 3 bytes date, 1-byte length prefix)
+            TypeId::DateTime2 => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading DATETIME2 length".into(),
+                    ));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else if buf.remaining() < len {
+                    return Err(Error::Protocol("unexpected EOF reading DATETIME2".into()));
+                } else {
+                    let scale = col.type_info.scale.unwrap_or(7);
+                    let time_len = Self::time_bytes_for_scale(scale);
+
+                    // Read time
+                    let mut time_bytes = [0u8; 8];
+                    for byte in time_bytes.iter_mut().take(time_len) {
+                        *byte = buf.get_u8();
+                    }
+                    let intervals = u64::from_le_bytes(time_bytes);
+
+                    // Read date (3 bytes)
+                    let days = buf.get_u8() as u32
+                        | ((buf.get_u8() as u32) << 8)
+                        | ((buf.get_u8() as u32) << 16);
+
+                    #[cfg(feature = "chrono")]
+                    {
+                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                        let date = base + chrono::Duration::days(days as i64);
+                        let time = Self::intervals_to_time(intervals, scale);
+                        SqlValue::ScaledDateTime2(mssql_types::SqlDateTime2 {
+                            value: date.and_time(time),
+                            scale,
+                        })
+                    }
+                    #[cfg(not(feature = "chrono"))]
+                    {
+                        SqlValue::String(format!("DATETIME2({days},{intervals})"))
+                    }
+                }
+            }
+
+            // DATETIMEOFFSET (variable length: TIME bytes + 3 bytes date + 2 bytes offset)
+            TypeId::DateTimeOffset => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading DATETIMEOFFSET length".into(),
+                    ));
+                }
+                let len = buf.get_u8() as usize;
+                if len == 0 {
+                    SqlValue::Null
+                } else if buf.remaining() < len {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading DATETIMEOFFSET".into(),
+                    ));
+                } else {
+                    let scale = col.type_info.scale.unwrap_or(7);
+                    let time_len = Self::time_bytes_for_scale(scale);
+
+                    // Read time
+                    let mut time_bytes = [0u8; 8];
+                    for byte in time_bytes.iter_mut().take(time_len) {
+                        *byte = buf.get_u8();
+                    }
+                    let intervals = u64::from_le_bytes(time_bytes);
+
+                    // Read date (3 bytes)
+                    let days = buf.get_u8() as u32
+                        | ((buf.get_u8() as u32) << 8)
+                        | ((buf.get_u8() as u32) << 16);
+
+                    // Read offset in minutes (2 bytes, signed)
+                    let offset_minutes = buf.get_i16_le();
+
+                    #[cfg(feature = "chrono")]
+                    {
+                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                        let date = base + chrono::Duration::days(days as i64);
+                        let time = Self::intervals_to_time(intervals, scale);
+                        let offset = chrono::FixedOffset::east_opt((offset_minutes as i32) * 60)
+                            .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+                        // Time and date are sent in UTC
+                        SqlValue::ScaledDateTimeOffset(mssql_types::SqlDateTimeOffset {
+                            value: chrono::DateTime::from_naive_utc_and_offset(
+                                date.and_time(time),
+                                offset,
+                            ),
+                            scale,
+                        })
+                    }
+                    #[cfg(not(feature = "chrono"))]
+                    {
+                        SqlValue::String(format!(
+                            "DATETIMEOFFSET({days},{intervals},{offset_minutes})"
+                        ))
+                    }
+                }
+            }
+
+            // TEXT type - always uses PLP encoding (deprecated LOB type)
+            TypeId::Text => Self::parse_plp_varchar(buf, col.type_info.collation.as_ref())?,
+
+            // Legacy byte-length string types (Char, VarChar) - 1-byte length prefix
+            TypeId::Char | TypeId::VarChar => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading legacy varchar length".into(),
+                    ));
+                }
+                let len = buf.get_u8();
+                if len == 0xFF {
+                    SqlValue::Null
+                } else if len == 0 {
+                    SqlValue::String(String::new())
+                } else if buf.remaining() < len as usize {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading legacy varchar data".into(),
+                    ));
+                } else {
+                    let data = &buf[..len as usize];
+                    // Use collation-aware decoding for non-ASCII text
+                    let s = Self::decode_varchar_string(data, col.type_info.collation.as_ref());
+                    buf.advance(len as usize);
+                    SqlValue::String(s)
+                }
+            }
+
+            // Variable-length string types (BigVarChar, BigChar)
+            TypeId::BigVarChar | TypeId::BigChar => {
+                // Check if this is a MAX type (uses PLP encoding)
+                if col.type_info.max_length == Some(0xFFFF) {
+                    // PLP format: 8-byte total length, then chunks
+                    Self::parse_plp_varchar(buf, col.type_info.collation.as_ref())?
+                } else {
+                    // 2-byte length prefix for non-MAX types
+                    if buf.remaining() < 2 {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading varchar length".into(),
+                        ));
+                    }
+                    let len = buf.get_u16_le();
+                    if len == 0xFFFF {
+                        SqlValue::Null
+                    } else if buf.remaining() < len as usize {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading varchar data".into(),
+                        ));
+                    } else {
+                        let data = &buf[..len as usize];
+                        // Use collation-aware decoding for non-ASCII text
+                        let s = Self::decode_varchar_string(data, col.type_info.collation.as_ref());
+                        buf.advance(len as usize);
+                        SqlValue::String(s)
                     }
                 }
             }
 
-            // DATETIME/SMALLDATETIME nullable (1-byte length prefix)
-            TypeId::DateTimeN => {
+            // NTEXT type - always uses PLP encoding (deprecated LOB type)
+            TypeId::NText => Self::parse_plp_nvarchar(buf)?,
+
+            // Variable-length Unicode string types (NVarChar, NChar)
+            TypeId::NVarChar | TypeId::NChar => {
+                // Check if this is a MAX type (uses PLP encoding)
+                if col.type_info.max_length == Some(0xFFFF) {
+                    // PLP format: 8-byte total length, then chunks
+                    Self::parse_plp_nvarchar(buf)?
+                } else {
+                    // 2-byte length prefix (in bytes, not chars) for non-MAX types
+                    if buf.remaining() < 2 {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading nvarchar length".into(),
+                        ));
+                    }
+                    let len = buf.get_u16_le();
+                    if len == 0xFFFF {
+                        SqlValue::Null
+                    } else if buf.remaining() < len as usize {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading nvarchar data".into(),
+                        ));
+                    } else {
+                        let data = &buf[..len as usize];
+                        // UTF-16LE to String
+                        let utf16: Vec<u16> = data
+                            .chunks_exact(2)
+                            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                            .collect();
+                        let s = String::from_utf16(&utf16)
+                            .map_err(|_| Error::Protocol("invalid UTF-16 in nvarchar".into()))?;
+                        buf.advance(len as usize);
+                        SqlValue::String(s)
+                    }
+                }
+            }
+
+            // IMAGE type - always uses PLP encoding (deprecated LOB type)
+            TypeId::Image => Self::parse_plp_varbinary(buf)?,
+
+            // Legacy byte-length binary types (Binary, VarBinary) - 1-byte length prefix
+            TypeId::Binary | TypeId::VarBinary => {
                 if buf.remaining() < 1 {
                     return Err(Error::Protocol(
-                        "unexpected EOF reading DateTimeN length".into(),
+                        "unexpected EOF reading legacy varbinary length".into(),
                     ));
                 }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
+                let len = buf.get_u8();
+                if len == 0xFF {
                     SqlValue::Null
-                } else if buf.remaining() < len {
-                    return Err(Error::Protocol("unexpected EOF reading DateTimeN".into()));
+                } else if len == 0 {
+                    SqlValue::Binary(bytes::Bytes::new())
+                } else if buf.remaining() < len as usize {
+                    return Err(Error::Protocol(
+                        "unexpected EOF reading legacy varbinary data".into(),
+                    ));
                 } else {
-                    match len {
-                        4 => {
-                            // SMALLDATETIME: 2 bytes days + 2 bytes minutes
-                            let days = buf.get_u16_le() as i64;
-                            let minutes = buf.get_u16_le() as u32;
-                            #[cfg(feature = "chrono")]
-                            {
-                                let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
-                                let date = base + chrono::Duration::days(days);
-                                let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(
-                                    minutes * 60,
-                                    0,
-                                )
-                                .unwrap();
-                                SqlValue::DateTime(date.and_time(time))
-                            }
-                            #[cfg(not(feature = "chrono"))]
-                            {
-                                SqlValue::String(format!("SMALLDATETIME({days},{minutes})"))
-                            }
-                        }
-                        8 => {
-                            // DATETIME: 4 bytes days + 4 bytes 1/300ths of second
-                            let days = buf.get_i32_le() as i64;
-                            let time_300ths = buf.get_u32_le() as u64;
-                            #[cfg(feature = "chrono")]
-                            {
-                                let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
-                                let date = base + chrono::Duration::days(days);
-                                // Convert 300ths of second to nanoseconds
-                                let total_ms = (time_300ths * 1000) / 300;
-                                let secs = (total_ms / 1000) as u32;
-                                let nanos = ((total_ms % 1000) * 1_000_000) as u32;
-                                let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(
-                                    secs, nanos,
-                                )
-                                .unwrap();
-                                SqlValue::DateTime(date.and_time(time))
-                            }
-                            #[cfg(not(feature = "chrono"))]
-                            {
-                                SqlValue::String(format!("DATETIME({days},{time_300ths})"))
-                            }
-                        }
-                        _ => {
-                            return Err(Error::Protocol(format!(
-                                "invalid DateTimeN length: {len}"
-                            )));
-                        }
+                    let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
+                    buf.advance(len as usize);
+                    SqlValue::Binary(data)
+                }
+            }
+
+            // Variable-length binary types (BigVarBinary, BigBinary)
+            TypeId::BigVarBinary | TypeId::BigBinary => {
+                // Check if this is a MAX type (uses PLP encoding)
+                if col.type_info.max_length == Some(0xFFFF) {
+                    // PLP format: 8-byte total length, then chunks
+                    Self::parse_plp_varbinary(buf)?
+                } else {
+                    if buf.remaining() < 2 {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading varbinary length".into(),
+                        ));
+                    }
+                    let len = buf.get_u16_le();
+                    if len == 0xFFFF {
+                        SqlValue::Null
+                    } else if buf.remaining() < len as usize {
+                        return Err(Error::Protocol(
+                            "unexpected EOF reading varbinary data".into(),
+                        ));
+                    } else {
+                        let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
+                        buf.advance(len as usize);
+                        SqlValue::Binary(data)
+                    }
+                }
+            }
+
+            // XML type - always uses PLP encoding
+            TypeId::Xml => {
+                // Parse as PLP NVARCHAR (XML is UTF-16 encoded in TDS)
+                match Self::parse_plp_nvarchar(buf)? {
+                    SqlValue::Null => SqlValue::Null,
+                    SqlValue::String(s) => SqlValue::Xml(s),
+                    _ => {
+                        return Err(Error::Protocol(
+                            "unexpected value type when parsing XML".into(),
+                        ));
                     }
                 }
             }
 
-            // Fixed DATETIME (8 bytes)
-            TypeId::DateTime => {
-                if buf.remaining() < 8 {
-                    return Err(Error::Protocol("unexpected EOF reading DATETIME".into()));
-                }
-                let days = buf.get_i32_le() as i64;
-                let time_300ths = buf.get_u32_le() as u64;
-                #[cfg(feature = "chrono")]
-                {
-                    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
-                    let date = base + chrono::Duration::days(days);
-                    let total_ms = (time_300ths * 1000) / 300;
-                    let secs = (total_ms / 1000) as u32;
-                    let nanos = ((total_ms % 1000) * 1_000_000) as u32;
-                    let time =
-                        chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).unwrap();
-                    SqlValue::DateTime(date.and_time(time))
-                }
-                #[cfg(not(feature = "chrono"))]
-                {
-                    SqlValue::String(format!("DATETIME({days},{time_300ths})"))
+            // GUID/UniqueIdentifier
+            TypeId::Guid => {
+                if buf.remaining() < 1 {
+                    return Err(Error::Protocol("unexpected EOF reading GUID length".into()));
+                }
+                let len = buf.get_u8();
+                if len == 0 {
+                    SqlValue::Null
+                } else if len != 16 {
+                    return Err(Error::Protocol(format!("invalid GUID length: {len}")));
+                } else if buf.remaining() < 16 {
+                    return Err(Error::Protocol("unexpected EOF reading GUID".into()));
+                } else {
+                    // SQL Server stores GUIDs in mixed-endian format
+                    let data = bytes::Bytes::copy_from_slice(&buf[..16]);
+                    buf.advance(16);
+                    SqlValue::Binary(data)
+                }
+            }
+
+            // SQL_VARIANT - contains embedded type info
+            TypeId::Variant => Self::parse_sql_variant(buf)?,
+
+            // UDT (User-Defined Type) - uses PLP encoding, return as binary
+            TypeId::Udt => Self::parse_plp_varbinary(buf)?,
+
+            // Default: treat as binary with 2-byte length prefix
+            _ => {
+                // Try to read as variable-length with 2-byte length
+                if buf.remaining() < 2 {
+                    return Err(Error::Protocol(format!(
+                        "unexpected EOF reading {:?}",
+                        col.type_id
+                    )));
+                }
+                let len = buf.get_u16_le();
+                if len == 0xFFFF {
+                    SqlValue::Null
+                } else if buf.remaining() < len as usize {
+                    return Err(Error::Protocol(format!(
+                        "unexpected EOF reading {:?} data",
+                        col.type_id
+                    )));
+                } else {
+                    let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
+                    buf.advance(len as usize);
+                    SqlValue::Binary(data)
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Parse PLP-encoded NVARCHAR(MAX) data.
+    ///
+    /// PLP format stored by decode_plp_type:
+    /// - 8-byte total length (0xFFFFFFFFFFFFFFFF = NULL)
+    /// - Chunks: 4-byte chunk length + chunk data, terminated by 0 length
+    fn parse_plp_nvarchar(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+
+        if buf.remaining() < 8 {
+            return Err(Error::Protocol(
+                "unexpected EOF reading PLP total length".into(),
+            ));
+        }
+
+        let total_len = buf.get_u64_le();
+        if total_len == 0xFFFFFFFFFFFFFFFF {
+            return Ok(SqlValue::Null);
+        }
+
+        // Read all chunks and concatenate the data
+        let mut all_data = Vec::new();
+        loop {
+            if buf.remaining() < 4 {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk length".into(),
+                ));
+            }
+            let chunk_len = buf.get_u32_le() as usize;
+            if chunk_len == 0 {
+                break; // End of PLP data
+            }
+            if buf.remaining() < chunk_len {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk data".into(),
+                ));
+            }
+            all_data.extend_from_slice(&buf[..chunk_len]);
+            buf.advance(chunk_len);
+        }
+
+        // Convert UTF-16LE to String
+        let utf16: Vec<u16> = all_data
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        let s = String::from_utf16(&utf16)
+            .map_err(|_| Error::Protocol("invalid UTF-16 in PLP nvarchar".into()))?;
+        Ok(SqlValue::String(s))
+    }
+
+    /// Decode VARCHAR bytes to a String using collation-aware encoding.
+    ///
+    /// When the `encoding` feature is enabled and a collation is provided,
+    /// this decodes the bytes using the appropriate character encoding based
+    /// on the collation's LCID. Otherwise falls back to UTF-8 lossy conversion.
+    #[allow(unused_variables)]
+    fn decode_varchar_string(data: &[u8], collation: Option<&Collation>) -> String {
+        // Remove this initial UTF-8 check first. Some non-UTF8 encoding strings incorrectly return Ok(s) when calling std::str::from_utf8
+        //   for example, the GBK-encoded Chinese characters "肖英" would return Ok("Ф?")
+        // if let Ok(s) = std::str::from_utf8(data) {
+        //     return s.to_owned();
+        // }
+
+        // If UTF-8 fails, try collation-aware decoding
+        #[cfg(feature = "encoding")]
+        if let Some(coll) = collation {
+            if let Some(encoding) = coll.encoding() {
+                let (decoded, _, had_errors) = encoding.decode(data);
+                if !had_errors {
+                    return decoded.into_owned();
                 }
             }
+        }
 
-            // Fixed SMALLDATETIME (4 bytes)
-            TypeId::DateTime4 => {
-                if buf.remaining() < 4 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading SMALLDATETIME".into(),
-                    ));
-                }
-                let days = buf.get_u16_le() as i64;
-                let minutes = buf.get_u16_le() as u32;
-                #[cfg(feature = "chrono")]
-                {
-                    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
-                    let date = base + chrono::Duration::days(days);
-                    let time =
-                        chrono::NaiveTime::from_num_seconds_from_midnight_opt(minutes * 60, 0)
-                            .unwrap();
-                    SqlValue::DateTime(date.and_time(time))
-                }
-                #[cfg(not(feature = "chrono"))]
-                {
-                    SqlValue::String(format!("SMALLDATETIME({days},{minutes})"))
-                }
+        // Fallback: lossy UTF-8 conversion
+        String::from_utf8_lossy(data).into_owned()
+    }
+
+    /// Parse PLP-encoded VARCHAR(MAX) data.
+    fn parse_plp_varchar(
+        buf: &mut &[u8],
+        collation: Option<&Collation>,
+    ) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+
+        if buf.remaining() < 8 {
+            return Err(Error::Protocol(
+                "unexpected EOF reading PLP total length".into(),
+            ));
+        }
+
+        let total_len = buf.get_u64_le();
+        if total_len == 0xFFFFFFFFFFFFFFFF {
+            return Ok(SqlValue::Null);
+        }
+
+        // Read all chunks and concatenate the data
+        let mut all_data = Vec::new();
+        loop {
+            if buf.remaining() < 4 {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk length".into(),
+                ));
+            }
+            let chunk_len = buf.get_u32_le() as usize;
+            if chunk_len == 0 {
+                break; // End of PLP data
+            }
+            if buf.remaining() < chunk_len {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk data".into(),
+                ));
             }
+            all_data.extend_from_slice(&buf[..chunk_len]);
+            buf.advance(chunk_len);
+        }
 
-            // DATE (3 bytes, nullable with 1-byte length prefix)
-            TypeId::Date => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading DATE length".into()));
-                }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
-                    SqlValue::Null
-                } else if len != 3 {
-                    return Err(Error::Protocol(format!("invalid DATE length: {len}")));
-                } else if buf.remaining() < 3 {
-                    return Err(Error::Protocol("unexpected EOF reading DATE".into()));
-                } else {
-                    // 3 bytes little-endian days since 0001-01-01
-                    let days = buf.get_u8() as u32
-                        | ((buf.get_u8() as u32) << 8)
-                        | ((buf.get_u8() as u32) << 16);
-                    #[cfg(feature = "chrono")]
-                    {
-                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                        let date = base + chrono::Duration::days(days as i64);
-                        SqlValue::Date(date)
-                    }
-                    #[cfg(not(feature = "chrono"))]
-                    {
-                        SqlValue::String(format!("DATE({days})"))
-                    }
+        // Decode using collation-aware encoding
+        let s = Self::decode_varchar_string(&all_data, collation);
+        Ok(SqlValue::String(s))
+    }
+
+    /// Parse PLP-encoded VARBINARY(MAX) data.
+    fn parse_plp_varbinary(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+
+        if buf.remaining() < 8 {
+            return Err(Error::Protocol(
+                "unexpected EOF reading PLP total length".into(),
+            ));
+        }
+
+        let total_len = buf.get_u64_le();
+        if total_len == 0xFFFFFFFFFFFFFFFF {
+            return Ok(SqlValue::Null);
+        }
+
+        // Read all chunks and concatenate the data
+        let mut all_data = Vec::new();
+        loop {
+            if buf.remaining() < 4 {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk length".into(),
+                ));
+            }
+            let chunk_len = buf.get_u32_le() as usize;
+            if chunk_len == 0 {
+                break; // End of PLP data
+            }
+            if buf.remaining() < chunk_len {
+                return Err(Error::Protocol(
+                    "unexpected EOF reading PLP chunk data".into(),
+                ));
+            }
+            all_data.extend_from_slice(&buf[..chunk_len]);
+            buf.advance(chunk_len);
+        }
+
+        Ok(SqlValue::Binary(bytes::Bytes::from(all_data)))
+    }
+
+    /// Parse SQL_VARIANT data which contains embedded type information.
+    ///
+    /// SQL_VARIANT format:
+    /// - 4 bytes: total length (0 = NULL)
+    /// - 1 byte: base type ID
+    /// - 1 byte: property byte count
+    /// - N bytes: type-specific properties
+    /// - Remaining bytes: actual data
+    fn parse_sql_variant(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
+        use bytes::Buf;
+        use mssql_types::SqlValue;
+
+        // Read 4-byte length
+        if buf.remaining() < 4 {
+            return Err(Error::Protocol(
+                "unexpected EOF reading SQL_VARIANT length".into(),
+            ));
+        }
+        let total_len = buf.get_u32_le() as usize;
+
+        if total_len == 0 {
+            return Ok(SqlValue::Null);
+        }
+
+        if buf.remaining() < total_len {
+            return Err(Error::Protocol(
+                "unexpected EOF reading SQL_VARIANT data".into(),
+            ));
+        }
+
+        // Read type info
+        if total_len < 2 {
+            return Err(Error::Protocol(
+                "SQL_VARIANT too short for type info".into(),
+            ));
+        }
+
+        let base_type = buf.get_u8();
+        let prop_count = buf.get_u8() as usize;
+
+        if buf.remaining() < prop_count {
+            return Err(Error::Protocol(
+                "unexpected EOF reading SQL_VARIANT properties".into(),
+            ));
+        }
+
+        // Data length is total_len - 2 (type, prop_count) - prop_count
+        let data_len = total_len.saturating_sub(2).saturating_sub(prop_count);
+
+        // Parse based on base type
+        // See MS-TDS SQL_VARIANT specification for type mappings
+        match base_type {
+            // Integer types (no properties)
+            0x30 => {
+                // TINYINT
+                buf.advance(prop_count);
+                if data_len < 1 {
+                    return Ok(SqlValue::Null);
                 }
+                let v = buf.get_u8();
+                Ok(SqlValue::TinyInt(v))
             }
-
-            // TIME (variable length with scale, 1-byte length prefix)
-            TypeId::Time => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading TIME length".into()));
+            0x32 => {
+                // BIT
+                buf.advance(prop_count);
+                if data_len < 1 {
+                    return Ok(SqlValue::Null);
                 }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
-                    SqlValue::Null
-                } else if buf.remaining() < len {
-                    return Err(Error::Protocol("unexpected EOF reading TIME".into()));
-                } else {
-                    let mut time_bytes = [0u8; 8];
-                    for byte in time_bytes.iter_mut().take(len) {
-                        *byte = buf.get_u8();
-                    }
-                    let intervals = u64::from_le_bytes(time_bytes);
-                    #[cfg(feature = "chrono")]
-                    {
-                        let scale = col.type_info.scale.unwrap_or(7);
-                        let time = Self::intervals_to_time(intervals, scale);
-                        SqlValue::Time(time)
-                    }
-                    #[cfg(not(feature = "chrono"))]
-                    {
-                        SqlValue::String(format!("TIME({intervals})"))
-                    }
+                let v = buf.get_u8();
+                Ok(SqlValue::Bool(v != 0))
+            }
+            0x34 => {
+                // SMALLINT
+                buf.advance(prop_count);
+                if data_len < 2 {
+                    return Ok(SqlValue::Null);
                 }
+                let v = buf.get_i16_le();
+                Ok(SqlValue::SmallInt(v))
             }
-
-            // DATETIME2 (variable length: TIME bytes + 3 bytes date, 1-byte length prefix)
-            TypeId::DateTime2 => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading DATETIME2 length".into(),
-                    ));
+            0x38 => {
+                // INT
+                buf.advance(prop_count);
+                if data_len < 4 {
+                    return Ok(SqlValue::Null);
                 }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
-                    SqlValue::Null
-                } else if buf.remaining() < len {
-                    return Err(Error::Protocol("unexpected EOF reading DATETIME2".into()));
-                } else {
-                    let scale = col.type_info.scale.unwrap_or(7);
-                    let time_len = Self::time_bytes_for_scale(scale);
-
-                    // Read time
-                    let mut time_bytes = [0u8; 8];
-                    for byte in time_bytes.iter_mut().take(time_len) {
-                        *byte = buf.get_u8();
-                    }
-                    let intervals = u64::from_le_bytes(time_bytes);
-
-                    // Read date (3 bytes)
-                    let days = buf.get_u8() as u32
-                        | ((buf.get_u8() as u32) << 8)
-                        | ((buf.get_u8() as u32) << 16);
-
-                    #[cfg(feature = "chrono")]
-                    {
-                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                        let date = base + chrono::Duration::days(days as i64);
-                        let time = Self::intervals_to_time(intervals, scale);
-                        SqlValue::DateTime(date.and_time(time))
-                    }
-                    #[cfg(not(feature = "chrono"))]
-                    {
-                        SqlValue::String(format!("DATETIME2({days},{intervals})"))
-                    }
+                let v = buf.get_i32_le();
+                Ok(SqlValue::Int(v))
+            }
+            0x7F => {
+                // BIGINT
+                buf.advance(prop_count);
+                if data_len < 8 {
+                    return Ok(SqlValue::Null);
                 }
+                let v = buf.get_i64_le();
+                Ok(SqlValue::BigInt(v))
             }
+            0x6D => {
+                // FLOATN - 1 prop byte (length)
+                let float_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
+                buf.advance(prop_count.saturating_sub(1));
 
-            // DATETIMEOFFSET (variable length: TIME bytes + 3 bytes date + 2 bytes offset)
-            TypeId::DateTimeOffset => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading DATETIMEOFFSET length".into(),
-                    ));
-                }
-                let len = buf.get_u8() as usize;
-                if len == 0 {
-                    SqlValue::Null
-                } else if buf.remaining() < len {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading DATETIMEOFFSET".into(),
-                    ));
+                if float_len == 4 && data_len >= 4 {
+                    let v = buf.get_f32_le();
+                    Ok(SqlValue::Float(v))
+                } else if data_len >= 8 {
+                    let v = buf.get_f64_le();
+                    Ok(SqlValue::Double(v))
                 } else {
-                    let scale = col.type_info.scale.unwrap_or(7);
-                    let time_len = Self::time_bytes_for_scale(scale);
-
-                    // Read time
-                    let mut time_bytes = [0u8; 8];
-                    for byte in time_bytes.iter_mut().take(time_len) {
-                        *byte = buf.get_u8();
-                    }
-                    let intervals = u64::from_le_bytes(time_bytes);
-
-                    // Read date (3 bytes)
-                    let days = buf.get_u8() as u32
-                        | ((buf.get_u8() as u32) << 8)
-                        | ((buf.get_u8() as u32) << 16);
-
-                    // Read offset in minutes (2 bytes, signed)
-                    let offset_minutes = buf.get_i16_le();
-
-                    #[cfg(feature = "chrono")]
-                    {
-                        use chrono::TimeZone;
-                        let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                        let date = base + chrono::Duration::days(days as i64);
-                        let time = Self::intervals_to_time(intervals, scale);
-                        let offset = chrono::FixedOffset::east_opt((offset_minutes as i32) * 60)
-                            .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
-                        let datetime = offset
-                            .from_local_datetime(&date.and_time(time))
-                            .single()
-                            .unwrap_or_else(|| offset.from_utc_datetime(&date.and_time(time)));
-                        SqlValue::DateTimeOffset(datetime)
-                    }
-                    #[cfg(not(feature = "chrono"))]
-                    {
-                        SqlValue::String(format!(
-                            "DATETIMEOFFSET({days},{intervals},{offset_minutes})"
-                        ))
-                    }
+                    Ok(SqlValue::Null)
                 }
             }
+            0x6E => {
+                // MONEYN - 1 prop byte (length)
+                let money_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
+                buf.advance(prop_count.saturating_sub(1));
 
-            // TEXT type - always uses PLP encoding (deprecated LOB type)
-            TypeId::Text => Self::parse_plp_varchar(buf, col.type_info.collation.as_ref())?,
-
-            // Legacy byte-length string types (Char, VarChar) - 1-byte length prefix
-            TypeId::Char | TypeId::VarChar => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading legacy varchar length".into(),
-                    ));
-                }
-                let len = buf.get_u8();
-                if len == 0xFF {
-                    SqlValue::Null
-                } else if len == 0 {
-                    SqlValue::String(String::new())
-                } else if buf.remaining() < len as usize {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading legacy varchar data".into(),
-                    ));
+                if money_len == 0 || data_len == 0 {
+                    Ok(SqlValue::Null)
+                } else if (money_len == 4 && data_len >= 4) || (money_len == 8 && data_len >= 8) {
+                    Self::parse_money_value(buf, money_len as usize)
                 } else {
-                    let data = &buf[..len as usize];
-                    // Use collation-aware decoding for non-ASCII text
-                    let s = Self::decode_varchar_string(data, col.type_info.collation.as_ref());
-                    buf.advance(len as usize);
-                    SqlValue::String(s)
+                    buf.advance(data_len);
+                    Ok(SqlValue::Null)
                 }
             }
+            0x6F => {
+                // DATETIMEN - 1 prop byte (length)
+                #[cfg(feature = "chrono")]
+                let dt_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
+                #[cfg(not(feature = "chrono"))]
+                if prop_count >= 1 {
+                    buf.get_u8();
+                }
+                buf.advance(prop_count.saturating_sub(1));
 
-            // Variable-length string types (BigVarChar, BigChar)
-            TypeId::BigVarChar | TypeId::BigChar => {
-                // Check if this is a MAX type (uses PLP encoding)
-                if col.type_info.max_length == Some(0xFFFF) {
-                    // PLP format: 8-byte total length, then chunks
-                    Self::parse_plp_varchar(buf, col.type_info.collation.as_ref())?
-                } else {
-                    // 2-byte length prefix for non-MAX types
-                    if buf.remaining() < 2 {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading varchar length".into(),
-                        ));
-                    }
-                    let len = buf.get_u16_le();
-                    if len == 0xFFFF {
-                        SqlValue::Null
-                    } else if buf.remaining() < len as usize {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading varchar data".into(),
-                        ));
-                    } else {
-                        let data = &buf[..len as usize];
-                        // Use collation-aware decoding for non-ASCII text
-                        let s = Self::decode_varchar_string(data, col.type_info.collation.as_ref());
-                        buf.advance(len as usize);
-                        SqlValue::String(s)
+                #[cfg(feature = "chrono")]
+                {
+                    use chrono::NaiveDate;
+                    if dt_len == 4 && data_len >= 4 {
+                        // SMALLDATETIME
+                        let days = buf.get_u16_le() as i64;
+                        let mins = buf.get_u16_le() as u32;
+                        let base = NaiveDate::from_ymd_opt(1900, 1, 1)
+                            .unwrap()
+                            .and_hms_opt(0, 0, 0)
+                            .unwrap();
+                        let dt = base
+                            + chrono::Duration::days(days)
+                            + chrono::Duration::minutes(mins as i64);
+                        Ok(SqlValue::DateTime(dt))
+                    } else if data_len >= 8 {
+                        // DATETIME
+                        let days = buf.get_i32_le() as i64;
+                        let ticks = buf.get_u32_le() as i64;
+                        let base = NaiveDate::from_ymd_opt(1900, 1, 1)
+                            .unwrap()
+                            .and_hms_opt(0, 0, 0)
+                            .unwrap();
+                        let millis = (ticks * 10) / 3;
+                        let dt = base
+                            + chrono::Duration::days(days)
+                            + chrono::Duration::milliseconds(millis);
+                        Ok(SqlValue::DateTime(dt))
+                    } else {
+                        Ok(SqlValue::Null)
                     }
                 }
+                #[cfg(not(feature = "chrono"))]
+                {
+                    buf.advance(data_len);
+                    Ok(SqlValue::Null)
+                }
             }
+            0x6A | 0x6C => {
+                // DECIMALN/NUMERICN - 2 prop bytes (precision, scale)
+                let _precision = if prop_count >= 1 { buf.get_u8() } else { 18 };
+                let scale = if prop_count >= 2 { buf.get_u8() } else { 0 };
+                buf.advance(prop_count.saturating_sub(2));
 
-            // NTEXT type - always uses PLP encoding (deprecated LOB type)
-            TypeId::NText => Self::parse_plp_nvarchar(buf)?,
-
-            // Variable-length Unicode string types (NVarChar, NChar)
-            TypeId::NVarChar | TypeId::NChar => {
-                // Check if this is a MAX type (uses PLP encoding)
-                if col.type_info.max_length == Some(0xFFFF) {
-                    // PLP format: 8-byte total length, then chunks
-                    Self::parse_plp_nvarchar(buf)?
-                } else {
-                    // 2-byte length prefix (in bytes, not chars) for non-MAX types
-                    if buf.remaining() < 2 {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading nvarchar length".into(),
-                        ));
-                    }
-                    let len = buf.get_u16_le();
-                    if len == 0xFFFF {
-                        SqlValue::Null
-                    } else if buf.remaining() < len as usize {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading nvarchar data".into(),
-                        ));
-                    } else {
-                        let data = &buf[..len as usize];
-                        // UTF-16LE to String
-                        let utf16: Vec<u16> = data
-                            .chunks_exact(2)
-                            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                            .collect();
-                        let s = String::from_utf16(&utf16)
-                            .map_err(|_| Error::Protocol("invalid UTF-16 in nvarchar".into()))?;
-                        buf.advance(len as usize);
-                        SqlValue::String(s)
-                    }
+                if data_len < 1 {
+                    return Ok(SqlValue::Null);
                 }
-            }
 
-            // IMAGE type - always uses PLP encoding (deprecated LOB type)
-            TypeId::Image => Self::parse_plp_varbinary(buf)?,
+                let sign = buf.get_u8();
+                let mantissa_len = data_len - 1;
 
-            // Legacy byte-length binary types (Binary, VarBinary) - 1-byte length prefix
-            TypeId::Binary | TypeId::VarBinary => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading legacy varbinary length".into(),
-                    ));
+                if mantissa_len > 16 {
+                    // Too large, skip and return null
+                    buf.advance(mantissa_len);
+                    return Ok(SqlValue::Null);
                 }
-                let len = buf.get_u8();
-                if len == 0xFF {
-                    SqlValue::Null
-                } else if len == 0 {
-                    SqlValue::Binary(bytes::Bytes::new())
-                } else if buf.remaining() < len as usize {
-                    return Err(Error::Protocol(
-                        "unexpected EOF reading legacy varbinary data".into(),
-                    ));
-                } else {
-                    let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
-                    buf.advance(len as usize);
-                    SqlValue::Binary(data)
+
+                let mut mantissa_bytes = [0u8; 16];
+                for i in 0..mantissa_len.min(16) {
+                    mantissa_bytes[i] = buf.get_u8();
                 }
-            }
+                let mantissa = u128::from_le_bytes(mantissa_bytes);
 
-            // Variable-length binary types (BigVarBinary, BigBinary)
-            TypeId::BigVarBinary | TypeId::BigBinary => {
-                // Check if this is a MAX type (uses PLP encoding)
-                if col.type_info.max_length == Some(0xFFFF) {
-                    // PLP format: 8-byte total length, then chunks
-                    Self::parse_plp_varbinary(buf)?
-                } else {
-                    if buf.remaining() < 2 {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading varbinary length".into(),
-                        ));
-                    }
-                    let len = buf.get_u16_le();
-                    if len == 0xFFFF {
-                        SqlValue::Null
-                    } else if buf.remaining() < len as usize {
-                        return Err(Error::Protocol(
-                            "unexpected EOF reading varbinary data".into(),
-                        ));
+                #[cfg(feature = "decimal")]
+                {
+                    use rust_decimal::Decimal;
+                    if scale > 28 {
+                        // Fall back to f64
+                        let divisor = 10f64.powi(scale as i32);
+                        let value = (mantissa as f64) / divisor;
+                        let value = if sign == 0 { -value } else { value };
+                        Ok(SqlValue::Double(value))
                     } else {
-                        let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
-                        buf.advance(len as usize);
-                        SqlValue::Binary(data)
+                        let mut decimal =
+                            Decimal::from_i128_with_scale(mantissa as i128, scale as u32);
+                        if sign == 0 {
+                            decimal.set_sign_negative(true);
+                        }
+                        Ok(SqlValue::Decimal(decimal))
                     }
                 }
+                #[cfg(not(feature = "decimal"))]
+                {
+                    let divisor = 10f64.powi(scale as i32);
+                    let value = (mantissa as f64) / divisor;
+                    let value = if sign == 0 { -value } else { value };
+                    Ok(SqlValue::Double(value))
+                }
             }
-
-            // XML type - always uses PLP encoding
-            TypeId::Xml => {
-                // Parse as PLP NVARCHAR (XML is UTF-16 encoded in TDS)
-                match Self::parse_plp_nvarchar(buf)? {
-                    SqlValue::Null => SqlValue::Null,
-                    SqlValue::String(s) => SqlValue::Xml(s),
-                    _ => {
-                        return Err(Error::Protocol(
-                            "unexpected value type when parsing XML".into(),
-                        ));
+            0x24 => {
+                // UNIQUEIDENTIFIER (no properties)
+                buf.advance(prop_count);
+                if data_len < 16 {
+                    return Ok(SqlValue::Null);
+                }
+                let mut guid_bytes = [0u8; 16];
+                for byte in &mut guid_bytes {
+                    *byte = buf.get_u8();
+                }
+                Ok(SqlValue::Binary(bytes::Bytes::copy_from_slice(&guid_bytes)))
+            }
+            0x28 => {
+                // DATE (no properties)
+                buf.advance(prop_count);
+                #[cfg(feature = "chrono")]
+                {
+                    if data_len < 3 {
+                        return Ok(SqlValue::Null);
                     }
+                    let mut date_bytes = [0u8; 4];
+                    date_bytes[0] = buf.get_u8();
+                    date_bytes[1] = buf.get_u8();
+                    date_bytes[2] = buf.get_u8();
+                    let days = u32::from_le_bytes(date_bytes);
+                    let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+                    let date = base + chrono::Duration::days(days as i64);
+                    Ok(SqlValue::Date(date))
+                }
+                #[cfg(not(feature = "chrono"))]
+                {
+                    buf.advance(data_len);
+                    Ok(SqlValue::Null)
+                }
+            }
+            0xA7 | 0x2F | 0x27 => {
+                // BigVarChar/BigChar/VarChar/Char - 7 prop bytes (collation 5 + maxlen 2)
+                // Parse collation from property bytes (5 bytes: 4 LCID + 1 sort_id)
+                let collation = if prop_count >= 5 && buf.remaining() >= 5 {
+                    let lcid = buf.get_u32_le();
+                    let sort_id = buf.get_u8();
+                    buf.advance(prop_count.saturating_sub(5)); // Skip remaining props (max_length)
+                    Some(Collation { lcid, sort_id })
+                } else {
+                    buf.advance(prop_count);
+                    None
+                };
+                if data_len == 0 {
+                    return Ok(SqlValue::String(String::new()));
+                }
+                let data = &buf[..data_len];
+                // Use collation-aware decoding for non-ASCII text
+                let s = Self::decode_varchar_string(data, collation.as_ref());
+                buf.advance(data_len);
+                Ok(SqlValue::String(s))
+            }
+            0xE7 | 0xEF => {
+                // NVarChar/NChar - 7 prop bytes (collation 5 + maxlen 2)
+                buf.advance(prop_count);
+                if data_len == 0 {
+                    return Ok(SqlValue::String(String::new()));
                 }
+                // UTF-16LE encoded
+                let utf16: Vec<u16> = buf[..data_len]
+                    .chunks_exact(2)
+                    .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                    .collect();
+                buf.advance(data_len);
+                let s = String::from_utf16(&utf16).map_err(|_| {
+                    Error::Protocol("invalid UTF-16 in SQL_VARIANT nvarchar".into())
+                })?;
+                Ok(SqlValue::String(s))
+            }
+            0xA5 | 0x2D | 0x25 => {
+                // BigVarBinary/BigBinary/Binary/VarBinary - 2 prop bytes (maxlen)
+                buf.advance(prop_count);
+                let data = bytes::Bytes::copy_from_slice(&buf[..data_len]);
+                buf.advance(data_len);
+                Ok(SqlValue::Binary(data))
+            }
+            _ => {
+                // Unknown type - return as binary
+                buf.advance(prop_count);
+                let data = bytes::Bytes::copy_from_slice(&buf[..data_len]);
+                buf.advance(data_len);
+                Ok(SqlValue::Binary(data))
             }
+        }
+    }
+
+    /// Calculate number of bytes needed for TIME based on scale.
+    fn time_bytes_for_scale(scale: u8) -> usize {
+        match scale {
+            0..=2 => 3,
+            3..=4 => 4,
+            5..=7 => 5,
+            _ => 5, // Default to max precision
+        }
+    }
+
+    /// Convert 100-nanosecond intervals to NaiveTime.
+    #[cfg(feature = "chrono")]
+    fn intervals_to_time(intervals: u64, scale: u8) -> chrono::NaiveTime {
+        // Scale determines the unit:
+        // scale 0: seconds
+        // scale 1: 100ms
+        // scale 2: 10ms
+        // scale 3: 1ms
+        // scale 4: 100us
+        // scale 5: 10us
+        // scale 6: 1us
+        // scale 7: 100ns
+        let nanos = match scale {
+            0 => intervals * 1_000_000_000,
+            1 => intervals * 100_000_000,
+            2 => intervals * 10_000_000,
+            3 => intervals * 1_000_000,
+            4 => intervals * 100_000,
+            5 => intervals * 10_000,
+            6 => intervals * 1_000,
+            7 => intervals * 100,
+            _ => intervals * 100,
+        };
 
-            // GUID/UniqueIdentifier
-            TypeId::Guid => {
-                if buf.remaining() < 1 {
-                    return Err(Error::Protocol("unexpected EOF reading GUID length".into()));
-                }
-                let len = buf.get_u8();
-                if len == 0 {
-                    SqlValue::Null
-                } else if len != 16 {
-                    return Err(Error::Protocol(format!("invalid GUID length: {len}")));
-                } else if buf.remaining() < 16 {
-                    return Err(Error::Protocol("unexpected EOF reading GUID".into()));
-                } else {
-                    // SQL Server stores GUIDs in mixed-endian format
-                    let data = bytes::Bytes::copy_from_slice(&buf[..16]);
-                    buf.advance(16);
-                    SqlValue::Binary(data)
-                }
-            }
+        let secs = (nanos / 1_000_000_000) as u32;
+        let nano_part = (nanos % 1_000_000_000) as u32;
 
-            // SQL_VARIANT - contains embedded type info
-            TypeId::Variant => Self::parse_sql_variant(buf)?,
+        chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nano_part)
+            .unwrap_or_else(|| chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+    }
 
-            // UDT (User-Defined Type) - uses PLP encoding, return as binary
-            TypeId::Udt => Self::parse_plp_varbinary(buf)?,
+    /// Decode the value of an output parameter.
+    fn decode_return_value(ret: &ReturnValue) -> Result<mssql_types::SqlValue> {
+        let column = ColumnData {
+            name: ret.param_name.clone(),
+            type_id: ret.type_id,
+            col_type: ret.col_type,
+            flags: ret.flags,
+            user_type: ret.user_type,
+            type_info: ret.type_info.clone(),
+            encryption: None,
+        };
+        let mut buf = ret.value.as_ref();
+        Self::parse_column_value(&mut buf, &column)
+    }
 
-            // Default: treat as binary with 2-byte length prefix
-            _ => {
-                // Try to read as variable-length with 2-byte length
-                if buf.remaining() < 2 {
-                    return Err(Error::Protocol(format!(
-                        "unexpected EOF reading {:?}",
-                        col.type_id
-                    )));
-                }
-                let len = buf.get_u16_le();
-                if len == 0xFFFF {
-                    SqlValue::Null
-                } else if buf.remaining() < len as usize {
-                    return Err(Error::Protocol(format!(
-                        "unexpected EOF reading {:?} data",
-                        col.type_id
-                    )));
-                } else {
-                    let data = bytes::Bytes::copy_from_slice(&buf[..len as usize]);
-                    buf.advance(len as usize);
-                    SqlValue::Binary(data)
+    /// Hand returned output parameter values to the parameters they belong to.
+    ///
+    /// Parameters are sent as `@p1`, `@p2`, ... so the name locates the
+    /// parameter.
+    fn set_output_params(params: &[&(dyn crate::ToSql + Sync)], outputs: &[OutputParam]) {
+        for output in outputs {
+            let index = output
+                .name
+                .strip_prefix("@p")
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| n.checked_sub(1));
+            if let Some(param) = index.and_then(|i| params.get(i)) {
+                if param.is_output() {
+                    param.set_output(output.value.clone());
                 }
             }
-        };
+        }
+    }
 
-        Ok(value)
+    /// Read execute result (row count) from the response.
+    async fn read_execute_result(&mut self) -> Result<u64> {
+        Ok(self.read_execute_response(false).await?.rows_affected)
     }
 
-    /// Parse PLP-encoded NVARCHAR(MAX) data.
+    /// Read the per-statement execution results from the response.
     ///
-    /// PLP format stored by decode_plp_type:
-    /// - 8-byte total length (0xFFFFFFFFFFFFFFFF = NULL)
-    /// - Chunks: 4-byte chunk length + chunk data, terminated by 0 length
-    fn parse_plp_nvarchar(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
+    /// When `capture_identity` is set, the request must end with the
+    /// [`IDENTITY_QUERY`] statement; its row is decoded into
+    /// `last_identity` and its DONE token is excluded from the counts.
+    async fn read_execute_response(&mut self, capture_identity: bool) -> Result<ExecuteResult> {
+        self.messages.clear();
+        let message = self.read_response().await?;
+
+        let mut parser = self.token_parser(message.payload);
+        let mut result = ExecuteResult::new(0);
+        let mut current_metadata: Option<ColMetaData> = None;
+        let mut last_row: Option<crate::row::Row> = None;
+        let mut row_metadata = None;
+        // Index in `row_counts` of the last statement's DONE or DONEINPROC
+        let mut last_statement: Option<usize> = None;
+        let in_transaction = self.transaction_descriptor != 0;
+        let mut server_error: Option<DatabaseError> = None;
 
-        if buf.remaining() < 8 {
-            return Err(Error::Protocol(
-                "unexpected EOF reading PLP total length".into(),
-            ));
+        loop {
+            // Use metadata-aware parsing to handle Row tokens from SELECT statements
+            let token = parser
+                .next_token_with_metadata(current_metadata.as_ref())
+                .map_err(|e| Error::Protocol(e.to_string()))?;
+
+            let Some(token) = token else {
+                break;
+            };
+
+            match token {
+                Token::ColMetaData(meta) => {
+                    // Store metadata for subsequent Row token parsing
+                    current_metadata = Some(meta);
+                }
+                Token::Row(raw_row) => {
+                    // Rows are only decoded when the trailing identity SELECT
+                    // needs to be captured; execute() otherwise skips them
+                    if let (true, Some(meta)) = (capture_identity, &current_metadata) {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &[]);
+                        last_row = Some(Self::convert_raw_row(&raw_row, meta, shared)?);
+                    }
+                }
+                Token::NbcRow(nbc_row) => {
+                    if let (true, Some(meta)) = (capture_identity, &current_metadata) {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &[]);
+                        last_row = Some(Self::convert_nbc_row(&nbc_row, meta, shared)?);
+                    }
+                }
+                Token::Done(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("execution failed".to_string()));
+                    }
+                    last_statement = Some(result.row_counts.len());
+                    result.record_done(done.row_count, done.status.count);
+                    // Only break if there are no more result sets
+                    // This enables multi-statement batches to report total affected rows
+                    if !done.status.more {
+                        break;
+                    }
+                }
+                Token::DoneProc(done) => {
+                    // The DONEPROC of every RPC (including sp_executesql)
+                    // would add a statement, so only one with a row count
+                    // is recorded
+                    if done.status.count {
+                        result.record_done(done.row_count, true);
+                    }
+                }
+                Token::DoneInProc(done) => {
+                    last_statement = Some(result.row_counts.len());
+                    result.record_done(done.row_count, done.status.count);
+                }
+                Token::ReturnValue(ret) => {
+                    result.output_params.push(OutputParam {
+                        name: ret.param_name.clone(),
+                        value: Self::decode_return_value(&ret)?,
+                    });
+                }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    server_error.get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                }
+                Token::EnvChange(env) => {
+                    // Process transaction-related EnvChange tokens.
+                    // This allows BEGIN TRANSACTION, COMMIT, ROLLBACK via raw SQL
+                    // to properly update the transaction descriptor.
+                    self.handle_env_change(&env).await;
+                }
+                _ => {}
+            }
         }
 
-        let total_len = buf.get_u64_le();
-        if total_len == 0xFFFFFFFFFFFFFFFF {
-            return Ok(SqlValue::Null);
+        if let Some(err) = server_error {
+            return Err(self.server_error(err, in_transaction));
         }
 
-        // Read all chunks and concatenate the data
-        let mut all_data = Vec::new();
-        loop {
-            if buf.remaining() < 4 {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk length".into(),
-                ));
-            }
-            let chunk_len = buf.get_u32_le() as usize;
-            if chunk_len == 0 {
-                break; // End of PLP data
-            }
-            if buf.remaining() < chunk_len {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk data".into(),
-                ));
+        if capture_identity {
+            // Drop the DONE reported by the identity SELECT itself
+            if let Some(index) = last_statement {
+                let count = result.row_counts.remove(index);
+                if count.count_valid {
+                    result.rows_affected -= count.rows_affected;
+                }
             }
-            all_data.extend_from_slice(&buf[..chunk_len]);
-            buf.advance(chunk_len);
+            result.last_identity = last_row.and_then(|row| row.try_get::<i64>(0));
         }
 
-        // Convert UTF-16LE to String
-        let utf16: Vec<u16> = all_data
-            .chunks_exact(2)
-            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-            .collect();
-        let s = String::from_utf16(&utf16)
-            .map_err(|_| Error::Protocol("invalid UTF-16 in PLP nvarchar".into()))?;
-        Ok(SqlValue::String(s))
+        Ok(result)
     }
 
-    /// Decode VARCHAR bytes to a String using collation-aware encoding.
+    /// Send a statement and read its per-statement execution results.
     ///
-    /// When the `encoding` feature is enabled and a collation is provided,
-    /// this decodes the bytes using the appropriate character encoding based
-    /// on the collation's LCID. Otherwise falls back to UTF-8 lossy conversion.
-    #[allow(unused_variables)]
-    fn decode_varchar_string(data: &[u8], collation: Option<&Collation>) -> String {
-        // Remove this initial UTF-8 check first. Some non-UTF8 encoding strings incorrectly return Ok(s) when calling std::str::from_utf8
-        //   for example, the GBK-encoded Chinese characters "肖英" would return Ok("Ф?")
-        // if let Ok(s) = std::str::from_utf8(data) {
-        //     return s.to_owned();
-        // }
+    /// Shared by the `execute_detailed` and `execute_with_identity` methods
+    /// of both `Ready` and `InTransaction` clients.
+    async fn execute_statement(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        capture_identity: bool,
+    ) -> Result<ExecuteResult> {
+        let sql = if capture_identity {
+            std::borrow::Cow::Owned(format!("{sql}\n;{IDENTITY_QUERY}"))
+        } else {
+            std::borrow::Cow::Borrowed(sql)
+        };
 
-        // If UTF-8 fails, try collation-aware decoding
-        #[cfg(feature = "encoding")]
-        if let Some(coll) = collation {
-            if let Some(encoding) = coll.encoding() {
-                let (decoded, _, had_errors) = encoding.decode(data);
-                if !had_errors {
-                    return decoded.into_owned();
-                }
-            }
-        }
+        tracing::debug!(
+            sql = %sql,
+            params_count = params.len(),
+            "executing statement"
+        );
 
-        // Fallback: lossy UTF-8 conversion
-        String::from_utf8_lossy(data).into_owned()
-    }
+        #[cfg(feature = "otel")]
+        let instrumentation = self.instrumentation.clone();
+        #[cfg(feature = "otel")]
+        let mut span = instrumentation.query_span(&sql);
 
-    /// Parse PLP-encoded VARCHAR(MAX) data.
-    fn parse_plp_varchar(
-        buf: &mut &[u8],
-        collation: Option<&Collation>,
-    ) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
+        let result = async {
+            if params.is_empty() {
+                // Simple statement without parameters - use SQL batch
+                self.send_sql_batch(&sql).await?;
+            } else {
+                // Parameterized statement - use sp_executesql via RPC
+                let rpc_params = self.convert_params(params)?;
+                let rpc_params = self.encrypt_params(&sql, rpc_params).await?;
+                let rpc = RpcRequest::execute_sql(&sql, rpc_params);
+                self.send_rpc(&rpc).await?;
+            }
 
-        if buf.remaining() < 8 {
-            return Err(Error::Protocol(
-                "unexpected EOF reading PLP total length".into(),
-            ));
+            let result = self.read_execute_response(capture_identity).await?;
+            Self::set_output_params(params, &result.output_params);
+            Ok(result)
         }
+        .await;
 
-        let total_len = buf.get_u64_le();
-        if total_len == 0xFFFFFFFFFFFFFFFF {
-            return Ok(SqlValue::Null);
+        #[cfg(feature = "otel")]
+        match &result {
+            Ok(r) => InstrumentationContext::record_success(&mut span, Some(r.rows_affected)),
+            Err(e) => InstrumentationContext::record_error(&mut span, e),
         }
 
-        // Read all chunks and concatenate the data
-        let mut all_data = Vec::new();
-        loop {
-            if buf.remaining() < 4 {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk length".into(),
-                ));
-            }
-            let chunk_len = buf.get_u32_le() as usize;
-            if chunk_len == 0 {
-                break; // End of PLP data
-            }
-            if buf.remaining() < chunk_len {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk data".into(),
-                ));
+        // Drop the span before returning
+        #[cfg(feature = "otel")]
+        drop(span);
+
+        result
+    }
+
+    /// Send the statements of a [`Batch`](crate::batch::Batch) in one round trip.
+    pub(crate) async fn execute_batch(
+        &mut self,
+        statements: &[BatchStatement<'_>],
+        mode: BatchMode,
+    ) -> Result<BatchResult> {
+        tracing::debug!(
+            statements = statements.len(),
+            mode = ?mode,
+            "executing batch"
+        );
+
+        #[cfg(feature = "otel")]
+        let instrumentation = self.instrumentation.clone();
+        #[cfg(feature = "otel")]
+        let batch_sql = statements
+            .iter()
+            .map(|s| s.sql.as_str())
+            .collect::<Vec<_>>()
+            .join(";\n");
+        #[cfg(feature = "otel")]
+        let mut span = instrumentation.query_span(&batch_sql);
+
+        let result = async {
+            match mode {
+                BatchMode::Rpc => {
+                    let mut requests = Vec::with_capacity(statements.len());
+                    for s in statements {
+                        let rpc_params = self.convert_params(&s.params)?;
+                        let rpc_params = self.encrypt_params(&s.sql, rpc_params).await?;
+                        // An RPC message carries one enclave package, bound to one query
+                        #[cfg(feature = "always-encrypted")]
+                        if self.enclave_package.is_some() && statements.len() > 1 {
+                            self.enclave_package = None;
+                            return Err(Error::Config(
+                                "enclave computations are not supported in batches".into(),
+                            ));
+                        }
+                        requests.push(RpcRequest::execute_sql(&s.sql, rpc_params));
+                    }
+                    self.send_rpc_batch(&requests).await?;
+                }
+                BatchMode::SqlBatch => {
+                    if statements.iter().any(|s| !s.params.is_empty()) {
+                        return Err(Error::Config(
+                            "parameters are not supported in SQL batch mode".into(),
+                        ));
+                    }
+                    let sql = statements
+                        .iter()
+                        .map(|s| s.sql.trim().trim_end_matches(';'))
+                        .collect::<Vec<_>>()
+                        .join(";\n");
+                    self.send_sql_batch(&sql).await?;
+                }
             }
-            all_data.extend_from_slice(&buf[..chunk_len]);
-            buf.advance(chunk_len);
+
+            self.read_batch_response(statements.len(), mode).await
         }
+        .await;
 
-        // Decode using collation-aware encoding
-        let s = Self::decode_varchar_string(&all_data, collation);
-        Ok(SqlValue::String(s))
-    }
+        #[cfg(feature = "otel")]
+        match &result {
+            Ok(r) => {
+                InstrumentationContext::record_success(&mut span, Some(r.total_rows_affected()))
+            }
+            Err(e) => InstrumentationContext::record_error(&mut span, e),
+        }
 
-    /// Parse PLP-encoded VARBINARY(MAX) data.
-    fn parse_plp_varbinary(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
+        // Drop the span before returning
+        #[cfg(feature = "otel")]
+        drop(span);
 
-        if buf.remaining() < 8 {
-            return Err(Error::Protocol(
-                "unexpected EOF reading PLP total length".into(),
-            ));
-        }
+        result
+    }
 
-        let total_len = buf.get_u64_le();
-        if total_len == 0xFFFFFFFFFFFFFFFF {
-            return Ok(SqlValue::Null);
+    /// Send several RPC calls packed into a single RPC message.
+    async fn send_rpc_batch(&mut self, requests: &[RpcRequest]) -> Result<()> {
+        let enclave_package = self.take_enclave_package();
+        let payload = tds_protocol::encode_rpc_batch_with_enclave_package(
+            requests,
+            self.transaction_descriptor,
+            enclave_package.as_deref(),
+        );
+        self.run_deferred_cleanup().await?;
+        // Check if we need to reset the connection on this request
+        let reset = self.take_reset().await?;
+        if reset {
+            tracing::debug!("sending RPC batch with RESETCONNECTION flag");
         }
 
-        // Read all chunks and concatenate the data
-        let mut all_data = Vec::new();
-        loop {
-            if buf.remaining() < 4 {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk length".into(),
-                ));
-            }
-            let chunk_len = buf.get_u32_le() as usize;
-            if chunk_len == 0 {
-                break; // End of PLP data
-            }
-            if buf.remaining() < chunk_len {
-                return Err(Error::Protocol(
-                    "unexpected EOF reading PLP chunk data".into(),
-                ));
-            }
-            all_data.extend_from_slice(&buf[..chunk_len]);
-            buf.advance(chunk_len);
-        }
+        self.send_request(PacketType::Rpc, payload, reset).await?;
 
-        Ok(SqlValue::Binary(bytes::Bytes::from(all_data)))
+        Ok(())
     }
 
-    /// Parse SQL_VARIANT data which contains embedded type information.
+    /// Read the response to a batch, splitting it into per-statement results.
     ///
-    /// SQL_VARIANT format:
-    /// - 4 bytes: total length (0 = NULL)
-    /// - 1 byte: base type ID
-    /// - 1 byte: property byte count
-    /// - N bytes: type-specific properties
-    /// - Remaining bytes: actual data
-    fn parse_sql_variant(buf: &mut &[u8]) -> Result<mssql_types::SqlValue> {
-        use bytes::Buf;
-        use mssql_types::SqlValue;
+    /// In RPC mode each call ends with a DONEPROC token; in SQL batch mode
+    /// each statement ends with a DONE token. Errors are attributed to the
+    /// statement in progress when they are raised.
+    async fn read_batch_response(
+        &mut self,
+        expected: usize,
+        mode: BatchMode,
+    ) -> Result<BatchResult> {
+        self.messages.clear();
+        let message = self.read_response().await?;
+
+        let mut parser = self.token_parser(message.payload);
+        let mut collector = BatchCollector::new(expected);
+        let mut current_metadata: Option<ColMetaData> = None;
+        let mut fatal_error: Option<DatabaseError> = None;
 
-        // Read 4-byte length
-        if buf.remaining() < 4 {
-            return Err(Error::Protocol(
-                "unexpected EOF reading SQL_VARIANT length".into(),
-            ));
+        loop {
+            // Rows from SELECT statements must be parsed to reach the DONE tokens
+            let token = parser
+                .next_token_with_metadata(current_metadata.as_ref())
+                .map_err(|e| Error::Protocol(e.to_string()))?;
+
+            let Some(token) = token else {
+                break;
+            };
+
+            match token {
+                Token::ColMetaData(meta) => {
+                    current_metadata = Some(meta);
+                }
+                Token::Done(done) => match mode {
+                    BatchMode::SqlBatch => {
+                        collector.record_done(done.row_count, done.status.count);
+                        collector.finish_statement(done.status.error);
+                        if !done.status.more {
+                            break;
+                        }
+                    }
+                    BatchMode::Rpc => {
+                        collector.record_done(done.row_count, done.status.count);
+                    }
+                },
+                Token::DoneInProc(done) => {
+                    collector.record_done(done.row_count, done.status.count);
+                }
+                Token::DoneProc(done) => {
+                    if mode == BatchMode::Rpc {
+                        collector.finish_statement(done.status.error);
+                        if !done.status.more {
+                            break;
+                        }
+                    }
+                }
+                Token::Error(err) => {
+                    let err = DatabaseError::from(&err);
+                    if err.is_fatal() {
+                        fatal_error.get_or_insert_with(|| err.clone());
+                    }
+                    collector.record_error(err.into());
+                }
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                }
+                Token::EnvChange(env) => {
+                    self.handle_env_change(&env).await;
+                }
+                _ => {}
+            }
         }
-        let total_len = buf.get_u32_le() as usize;
 
-        if total_len == 0 {
-            return Ok(SqlValue::Null);
+        if let Some(err) = fatal_error {
+            self.close_after_fatal_error(&err);
         }
 
-        if buf.remaining() < total_len {
-            return Err(Error::Protocol(
-                "unexpected EOF reading SQL_VARIANT data".into(),
-            ));
+        Ok(collector.finish())
+    }
+
+    /// Run a query and collect all of its rows.
+    pub(crate) async fn fetch_rows(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Vec<crate::row::Row>> {
+        if params.is_empty() {
+            self.send_sql_batch(sql).await?;
+        } else {
+            let rpc_params = self.convert_params(params)?;
+            let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+            let rpc = RpcRequest::execute_sql(sql, rpc_params);
+            self.send_rpc(&rpc).await?;
         }
 
-        // Read type info
-        if total_len < 2 {
-            return Err(Error::Protocol(
-                "SQL_VARIANT too short for type info".into(),
-            ));
+        let (_, rows) = self.read_query_response().await?;
+        Ok(rows)
+    }
+
+    /// Execute a statement and return the number of affected rows.
+    ///
+    /// Like [`fetch_rows`](Self::fetch_rows), a statement without
+    /// parameters goes as a plain batch, so temporary tables it creates
+    /// outlive it.
+    pub(crate) async fn execute_rows(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        if params.is_empty() {
+            self.send_sql_batch(sql).await?;
+        } else {
+            let rpc_params = self.convert_params(params)?;
+            let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+            let rpc = RpcRequest::execute_sql(sql, rpc_params);
+            self.send_rpc(&rpc).await?;
         }
+        self.read_execute_result().await
+    }
 
-        let base_type = buf.get_u8();
-        let prop_count = buf.get_u8() as usize;
+    /// Insert `rows` into `table` from a TVP, creating the table type of
+    /// `T` first if needed.
+    pub(crate) async fn insert_tvp_rows<T: crate::tvp::Tvp>(
+        &mut self,
+        table: &str,
+        rows: &[T],
+    ) -> Result<u64> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let insert = crate::tvp::insert_select_sql::<T>(table)?;
+        let tvp = crate::tvp::TvpValue::new(rows)?;
+        self.execute_rows(&crate::tvp::create_type_sql::<T>()?, &[])
+            .await?;
+        self.execute_rows(&insert, &[&tvp]).await
+    }
 
-        if buf.remaining() < prop_count {
-            return Err(Error::Protocol(
-                "unexpected EOF reading SQL_VARIANT properties".into(),
-            ));
+    /// Create the temporary table `name` for [`TempTable`] and fill it.
+    async fn create_temp_table<T: crate::tvp::Tvp>(
+        &mut self,
+        name: &str,
+        rows: &[T],
+    ) -> Result<()> {
+        let create = crate::temp_table::create_sql::<T>(name)?;
+        self.execute_rows(&create, &[]).await?;
+        if let Err(e) = self.insert_tvp_rows(name, rows).await {
+            self.defer_cleanup(crate::temp_table::drop_sql(name)?);
+            return Err(e);
         }
+        tracing::debug!(table = name, rows = rows.len(), "created temporary table");
+        Ok(())
+    }
 
-        // Data length is total_len - 2 (type, prop_count) - prop_count
-        let data_len = total_len.saturating_sub(2).saturating_sub(prop_count);
+    /// Send a statement rewritten with per-statement [`QueryOptions`].
+    async fn send_with_options(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<()> {
+        let sql = options.apply(sql)?;
+        tracing::debug!(
+            sql = %sql,
+            params_count = params.len(),
+            "executing statement with options"
+        );
 
-        // Parse based on base type
-        // See MS-TDS SQL_VARIANT specification for type mappings
-        match base_type {
-            // Integer types (no properties)
-            0x30 => {
-                // TINYINT
-                buf.advance(prop_count);
-                if data_len < 1 {
-                    return Ok(SqlValue::Null);
-                }
-                let v = buf.get_u8();
-                Ok(SqlValue::TinyInt(v))
-            }
-            0x32 => {
-                // BIT
-                buf.advance(prop_count);
-                if data_len < 1 {
-                    return Ok(SqlValue::Null);
-                }
-                let v = buf.get_u8();
-                Ok(SqlValue::Bool(v != 0))
-            }
-            0x34 => {
-                // SMALLINT
-                buf.advance(prop_count);
-                if data_len < 2 {
-                    return Ok(SqlValue::Null);
-                }
-                let v = buf.get_i16_le();
-                Ok(SqlValue::SmallInt(v))
-            }
-            0x38 => {
-                // INT
-                buf.advance(prop_count);
-                if data_len < 4 {
-                    return Ok(SqlValue::Null);
-                }
-                let v = buf.get_i32_le();
-                Ok(SqlValue::Int(v))
-            }
-            0x7F => {
-                // BIGINT
-                buf.advance(prop_count);
-                if data_len < 8 {
-                    return Ok(SqlValue::Null);
-                }
-                let v = buf.get_i64_le();
-                Ok(SqlValue::BigInt(v))
-            }
-            0x6D => {
-                // FLOATN - 1 prop byte (length)
-                let float_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
-                buf.advance(prop_count.saturating_sub(1));
+        if params.is_empty() && !options.needs_scope() {
+            self.send_sql_batch(&sql).await?;
+        } else {
+            let rpc_params = self.convert_params(params)?;
+            let rpc_params = self.encrypt_params(&sql, rpc_params).await?;
+            let rpc = RpcRequest::execute_sql(&sql, rpc_params);
+            self.send_rpc(&rpc).await?;
+        }
+        self.request_max_lob_size = options.lob_limit();
+        Ok(())
+    }
 
-                if float_len == 4 && data_len >= 4 {
-                    let v = buf.get_f32_le();
-                    Ok(SqlValue::Float(v))
-                } else if data_len >= 8 {
-                    let v = buf.get_f64_le();
-                    Ok(SqlValue::Double(v))
-                } else {
-                    Ok(SqlValue::Null)
-                }
-            }
-            0x6E => {
-                // MONEYN - 1 prop byte (length)
-                let money_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
-                buf.advance(prop_count.saturating_sub(1));
+    /// Send a query with `SET NO_BROWSETABLE ON` and read its rows.
+    async fn browse_response(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<(Vec<crate::row::Column>, Vec<crate::row::Row>)> {
+        // Runs through sp_executesql so the setting ends with the query
+        let sql = format!("SET NO_BROWSETABLE ON; {sql}");
+        let rpc_params = self.convert_params(params)?;
+        let rpc_params = self.encrypt_params(&sql, rpc_params).await?;
+        let rpc = RpcRequest::execute_sql(&sql, rpc_params);
+        self.send_rpc(&rpc).await?;
+        self.read_query_response().await
+    }
 
-                if money_len == 0 || data_len == 0 {
-                    Ok(SqlValue::Null)
-                } else if (money_len == 4 && data_len >= 4) || (money_len == 8 && data_len >= 8) {
-                    Self::parse_money_value(buf, money_len as usize)
-                } else {
-                    buf.advance(data_len);
-                    Ok(SqlValue::Null)
-                }
-            }
-            0x6F => {
-                // DATETIMEN - 1 prop byte (length)
-                #[cfg(feature = "chrono")]
-                let dt_len = if prop_count >= 1 { buf.get_u8() } else { 8 };
-                #[cfg(not(feature = "chrono"))]
-                if prop_count >= 1 {
-                    buf.get_u8();
-                }
-                buf.advance(prop_count.saturating_sub(1));
+    /// Send one statement produced by
+    /// [`expand_in`](crate::in_list::expand_in).
+    async fn send_expanded(&mut self, query: &ExpandedQuery) -> Result<()> {
+        let params = query.params();
+        let rpc_params = self.convert_params(&params)?;
+        let rpc_params = self.encrypt_params(query.sql(), rpc_params).await?;
+        let rpc = RpcRequest::execute_sql(query.sql(), rpc_params);
+        self.send_rpc(&rpc).await
+    }
 
-                #[cfg(feature = "chrono")]
-                {
-                    use chrono::NaiveDate;
-                    if dt_len == 4 && data_len >= 4 {
-                        // SMALLDATETIME
-                        let days = buf.get_u16_le() as i64;
-                        let mins = buf.get_u16_le() as u32;
-                        let base = NaiveDate::from_ymd_opt(1900, 1, 1)
-                            .unwrap()
-                            .and_hms_opt(0, 0, 0)
-                            .unwrap();
-                        let dt = base
-                            + chrono::Duration::days(days)
-                            + chrono::Duration::minutes(mins as i64);
-                        Ok(SqlValue::DateTime(dt))
-                    } else if data_len >= 8 {
-                        // DATETIME
-                        let days = buf.get_i32_le() as i64;
-                        let ticks = buf.get_u32_le() as i64;
-                        let base = NaiveDate::from_ymd_opt(1900, 1, 1)
-                            .unwrap()
-                            .and_hms_opt(0, 0, 0)
-                            .unwrap();
-                        let millis = (ticks * 10) / 3;
-                        let dt = base
-                            + chrono::Duration::days(days)
-                            + chrono::Duration::milliseconds(millis);
-                        Ok(SqlValue::DateTime(dt))
-                    } else {
-                        Ok(SqlValue::Null)
-                    }
+    /// Expand an `IN` list, sending it as a table-valued parameter when it
+    /// does not fit into one statement and the configuration allows it.
+    ///
+    /// A list that cannot be sent as a table-valued parameter is split only
+    /// with [`Config::in_list_split_fallback`](crate::Config::in_list_split_fallback).
+    async fn plan_in_list<T: crate::ToSql + Sync>(
+        &mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Vec<ExpandedQuery>> {
+        if self.config.in_list_tvp && params.len() + values.len() > MAX_PARAMS {
+            let Some(tvp_list) = expand_in_tvp(sql, list, values, params)? else {
+                if !self.config.in_list_split_fallback {
+                    return Err(Error::Config(format!(
+                        "{} values for {list} do not fit into one statement and cannot be \
+                         sent as a table-valued parameter; enable \
+                         Config::in_list_split_fallback to split them",
+                        values.len()
+                    )));
                 }
-                #[cfg(not(feature = "chrono"))]
-                {
-                    buf.advance(data_len);
-                    Ok(SqlValue::Null)
+                tracing::debug!("IN list cannot be a table-valued parameter, splitting it");
+                return expand_in(sql, list, values, params);
+            };
+            self.send_sql_batch(&tvp_list.create_type).await?;
+            match self.read_execute_response(false).await {
+                Ok(_) => return Ok(vec![tvp_list.query]),
+                // The table type could not be created; split the list
+                Err(Error::Server { number, .. }) if self.config.in_list_split_fallback => {
+                    tracing::debug!(
+                        error = number,
+                        "cannot create IN list table type, splitting the list"
+                    );
                 }
+                Err(e) => return Err(e),
             }
-            0x6A | 0x6C => {
-                // DECIMALN/NUMERICN - 2 prop bytes (precision, scale)
-                let _precision = if prop_count >= 1 { buf.get_u8() } else { 18 };
-                let scale = if prop_count >= 2 { buf.get_u8() } else { 0 };
-                buf.advance(prop_count.saturating_sub(2));
+        }
+        expand_in(sql, list, values, params)
+    }
 
-                if data_len < 1 {
-                    return Ok(SqlValue::Null);
-                }
+    /// Run every statement of an expanded `IN` list and combine their rows.
+    async fn query_in_response<T: crate::ToSql + Sync>(
+        &mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<(Vec<crate::row::Column>, Vec<crate::row::Row>)> {
+        let mut columns = Vec::new();
+        let mut rows = Vec::new();
+        for query in self.plan_in_list(sql, list, values, params).await? {
+            self.send_expanded(&query).await?;
+            let (chunk_columns, chunk_rows) = self.read_query_response().await?;
+            if columns.is_empty() {
+                columns = chunk_columns;
+            }
+            rows.extend(chunk_rows);
+        }
+        Ok((columns, rows))
+    }
 
-                let sign = buf.get_u8();
-                let mantissa_len = data_len - 1;
+    /// Run every statement of an expanded `IN` list and add up the rows
+    /// they affect.
+    ///
+    /// Several statements run in a nested transaction with a savepoint, so
+    /// either all of them take effect or, when one fails, none does.
+    async fn execute_in_response<T: crate::ToSql + Sync>(
+        &mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        let queries = self.plan_in_list(sql, list, values, params).await?;
+        if let [query] = queries.as_slice() {
+            self.send_expanded(query).await?;
+            return Ok(self.read_execute_response(false).await?.rows_affected);
+        }
 
-                if mantissa_len > 16 {
-                    // Too large, skip and return null
-                    buf.advance(mantissa_len);
-                    return Ok(SqlValue::Null);
-                }
+        let in_transaction = self.transaction_descriptor != 0;
+        self.send_sql_batch(IN_LIST_BEGIN).await?;
+        self.read_execute_response(false).await?;
 
-                let mut mantissa_bytes = [0u8; 16];
-                for i in 0..mantissa_len.min(16) {
-                    mantissa_bytes[i] = buf.get_u8();
+        let mut total = 0;
+        for query in &queries {
+            let result = match self.send_expanded(query).await {
+                Ok(()) => self.read_execute_response(false).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(result) => total += result.rows_affected,
+                Err(e) => {
+                    self.rollback_in_list().await;
+                    return Err(match e {
+                        // The transaction that was rolled back was our own
+                        Error::TransactionDoomed(err) if !in_transaction => err.into(),
+                        e => e,
+                    });
                 }
-                let mantissa = u128::from_le_bytes(mantissa_bytes);
+            }
+        }
 
-                #[cfg(feature = "decimal")]
-                {
-                    use rust_decimal::Decimal;
-                    if scale > 28 {
-                        // Fall back to f64
-                        let divisor = 10f64.powi(scale as i32);
-                        let value = (mantissa as f64) / divisor;
-                        let value = if sign == 0 { -value } else { value };
-                        Ok(SqlValue::Double(value))
-                    } else {
-                        let mut decimal =
-                            Decimal::from_i128_with_scale(mantissa as i128, scale as u32);
-                        if sign == 0 {
-                            decimal.set_sign_negative(true);
+        self.send_sql_batch("COMMIT TRANSACTION").await?;
+        self.read_execute_response(false).await?;
+        Ok(total)
+    }
+
+    /// Undo the statements of a failed `execute_in`, keeping any outer
+    /// transaction open unless the server already doomed it.
+    async fn rollback_in_list(&mut self) {
+        if self.connection.is_none() {
+            return;
+        }
+        let result = match self.send_sql_batch(IN_LIST_ROLLBACK).await {
+            Ok(()) => self.read_execute_response(false).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::debug!(error = %e, "failed to roll back IN list statements");
+        }
+    }
+
+    /// Run a query with `SET STATISTICS IO, TIME ON` and parse the statistics.
+    ///
+    /// The query always goes through `sp_executesql`, so the settings only
+    /// apply to it and revert when it returns.
+    async fn query_stats_response(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<(
+        Vec<crate::row::Column>,
+        Vec<crate::row::Row>,
+        QueryStatistics,
+    )> {
+        let sql = format!("SET STATISTICS IO, TIME ON; {sql}");
+        let rpc_params = self.convert_params(params)?;
+        let rpc_params = self.encrypt_params(&sql, rpc_params).await?;
+        let rpc = RpcRequest::execute_sql(&sql, rpc_params);
+        self.send_rpc(&rpc).await?;
+
+        let (columns, rows) = self.read_query_response().await?;
+        let stats = QueryStatistics::from_messages(&self.messages);
+        Ok((columns, rows, stats))
+    }
+
+    /// Execute the batches of a [`Script`] in order.
+    pub(crate) async fn run_script(&mut self, script: &Script) -> Result<ScriptResult> {
+        let mut result = ScriptResult::default();
+
+        for (index, batch) in script.batches().iter().enumerate() {
+            tracing::debug!(
+                batch = index,
+                line = batch.line(),
+                repeat = batch.repeat(),
+                "executing script batch"
+            );
+
+            let mut outcome = Ok(0);
+            for _ in 0..batch.repeat() {
+                match self.execute_statement(batch.sql(), &[], false).await {
+                    Ok(r) => {
+                        if let Ok(total) = outcome.as_mut() {
+                            *total += r.rows_affected;
                         }
-                        Ok(SqlValue::Decimal(decimal))
                     }
-                }
-                #[cfg(not(feature = "decimal"))]
-                {
-                    let divisor = 10f64.powi(scale as i32);
-                    let value = (mantissa as f64) / divisor;
-                    let value = if sign == 0 { -value } else { value };
-                    Ok(SqlValue::Double(value))
-                }
-            }
-            0x24 => {
-                // UNIQUEIDENTIFIER (no properties)
-                buf.advance(prop_count);
-                if data_len < 16 {
-                    return Ok(SqlValue::Null);
-                }
-                let mut guid_bytes = [0u8; 16];
-                for byte in &mut guid_bytes {
-                    *byte = buf.get_u8();
-                }
-                Ok(SqlValue::Binary(bytes::Bytes::copy_from_slice(&guid_bytes)))
-            }
-            0x28 => {
-                // DATE (no properties)
-                buf.advance(prop_count);
-                #[cfg(feature = "chrono")]
-                {
-                    if data_len < 3 {
-                        return Ok(SqlValue::Null);
+                    Err(e) => {
+                        outcome = Err(e);
+                        break;
                     }
-                    let mut date_bytes = [0u8; 4];
-                    date_bytes[0] = buf.get_u8();
-                    date_bytes[1] = buf.get_u8();
-                    date_bytes[2] = buf.get_u8();
-                    let days = u32::from_le_bytes(date_bytes);
-                    let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-                    let date = base + chrono::Duration::days(days as i64);
-                    Ok(SqlValue::Date(date))
-                }
-                #[cfg(not(feature = "chrono"))]
-                {
-                    buf.advance(data_len);
-                    Ok(SqlValue::Null)
-                }
-            }
-            0xA7 | 0x2F | 0x27 => {
-                // BigVarChar/BigChar/VarChar/Char - 7 prop bytes (collation 5 + maxlen 2)
-                // Parse collation from property bytes (5 bytes: 4 LCID + 1 sort_id)
-                let collation = if prop_count >= 5 && buf.remaining() >= 5 {
-                    let lcid = buf.get_u32_le();
-                    let sort_id = buf.get_u8();
-                    buf.advance(prop_count.saturating_sub(5)); // Skip remaining props (max_length)
-                    Some(Collation { lcid, sort_id })
-                } else {
-                    buf.advance(prop_count);
-                    None
-                };
-                if data_len == 0 {
-                    return Ok(SqlValue::String(String::new()));
-                }
-                let data = &buf[..data_len];
-                // Use collation-aware decoding for non-ASCII text
-                let s = Self::decode_varchar_string(data, collation.as_ref());
-                buf.advance(data_len);
-                Ok(SqlValue::String(s))
-            }
-            0xE7 | 0xEF => {
-                // NVarChar/NChar - 7 prop bytes (collation 5 + maxlen 2)
-                buf.advance(prop_count);
-                if data_len == 0 {
-                    return Ok(SqlValue::String(String::new()));
                 }
-                // UTF-16LE encoded
-                let utf16: Vec<u16> = buf[..data_len]
-                    .chunks_exact(2)
-                    .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                    .collect();
-                buf.advance(data_len);
-                let s = String::from_utf16(&utf16).map_err(|_| {
-                    Error::Protocol("invalid UTF-16 in SQL_VARIANT nvarchar".into())
-                })?;
-                Ok(SqlValue::String(s))
             }
-            0xA5 | 0x2D | 0x25 => {
-                // BigVarBinary/BigBinary/Binary/VarBinary - 2 prop bytes (maxlen)
-                buf.advance(prop_count);
-                let data = bytes::Bytes::copy_from_slice(&buf[..data_len]);
-                buf.advance(data_len);
-                Ok(SqlValue::Binary(data))
+
+            let failed = outcome.is_err();
+            if let Err(e) = &outcome {
+                tracing::warn!(batch = index, line = batch.line(), error = %e, "script batch failed");
             }
-            _ => {
-                // Unknown type - return as binary
-                buf.advance(prop_count);
-                let data = bytes::Bytes::copy_from_slice(&buf[..data_len]);
-                buf.advance(data_len);
-                Ok(SqlValue::Binary(data))
+            result.push(ScriptBatchResult {
+                index,
+                line: batch.line(),
+                result: outcome,
+            });
+
+            if failed && !script.continues_on_error() {
+                break;
             }
         }
-    }
 
-    /// Calculate number of bytes needed for TIME based on scale.
-    fn time_bytes_for_scale(scale: u8) -> usize {
-        match scale {
-            0..=2 => 3,
-            3..=4 => 4,
-            5..=7 => 5,
-            _ => 5, // Default to max precision
-        }
+        Ok(result)
     }
 
-    /// Convert 100-nanosecond intervals to NaiveTime.
-    #[cfg(feature = "chrono")]
-    fn intervals_to_time(intervals: u64, scale: u8) -> chrono::NaiveTime {
-        // Scale determines the unit:
-        // scale 0: seconds
-        // scale 1: 100ms
-        // scale 2: 10ms
-        // scale 3: 1ms
-        // scale 4: 100us
-        // scale 5: 10us
-        // scale 6: 1us
-        // scale 7: 100ns
-        let nanos = match scale {
-            0 => intervals * 1_000_000_000,
-            1 => intervals * 100_000_000,
-            2 => intervals * 10_000_000,
-            3 => intervals * 1_000_000,
-            4 => intervals * 100_000,
-            5 => intervals * 10_000,
-            6 => intervals * 1_000,
-            7 => intervals * 100,
-            _ => intervals * 100,
-        };
-
-        let secs = (nanos / 1_000_000_000) as u32;
-        let nano_part = (nanos % 1_000_000_000) as u32;
+    /// Open a server-side cursor with `sp_cursoropen`.
+    pub(crate) async fn open_cursor_with_options(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: CursorOptions,
+    ) -> Result<Cursor<'_, S>> {
+        tracing::debug!(
+            sql = sql,
+            params_count = params.len(),
+            cursor_type = ?options.cursor_type,
+            "opening server cursor"
+        );
 
-        chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nano_part)
-            .unwrap_or_else(|| chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+        let rpc_params = self.convert_params(params)?;
+        let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+        let rpc = RpcRequest::cursor_open(
+            sql,
+            rpc_params,
+            options.cursor_type.scroll_option(),
+            options.concurrency.concurrency_option(),
+        );
+        let response = self.cursor_rpc(&rpc).await?;
+        Cursor::from_open_response(self, response, options)
     }
 
-    /// Read execute result (row count) from the response.
-    async fn read_execute_result(&mut self) -> Result<u64> {
-        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
-
-        let message = match connection {
-            ConnectionHandle::Tls(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::TlsPrelogin(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::Plain(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-        }
-        .ok_or(Error::ConnectionClosed)?;
+    /// Send a cursor procedure call and read its rows and output parameters.
+    pub(crate) async fn cursor_rpc(&mut self, rpc: &RpcRequest) -> Result<CursorResponse> {
+        self.send_rpc(rpc).await?;
+        self.read_cursor_response().await
+    }
 
-        let mut parser = TokenParser::new(message.payload);
-        let mut rows_affected = 0u64;
-        let mut current_metadata: Option<ColMetaData> = None;
+    /// Read the response to an `sp_cursor*` call.
+    ///
+    /// Result sets returned by `sp_cursorfetch` carry a trailing `ROWSTAT`
+    /// column; it is removed, and rows reported as missing (deleted from a
+    /// keyset cursor) are skipped.
+    async fn read_cursor_response(&mut self) -> Result<CursorResponse> {
+        self.messages.clear();
+        let message = self.read_response().await?;
+
+        let mut parser = self.token_parser(message.payload);
+        let mut response = CursorResponse {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            return_values: Vec::new(),
+        };
+        let mut all_columns: Vec<crate::row::Column> = Vec::new();
+        let mut row_metadata = None;
+        // Metadata of the rows returned without the ROWSTAT column
+        let mut visible_metadata = Arc::new(crate::row::ColMetaData::new(Vec::new()));
+        let mut has_rowstat = false;
+        let mut protocol_metadata: Option<ColMetaData> = None;
+        let mut decryptor: Option<ColumnDecryptor> = None;
+        let in_transaction = self.transaction_descriptor != 0;
+        let mut server_error: Option<DatabaseError> = None;
 
         loop {
-            // Use metadata-aware parsing to handle Row tokens from SELECT statements
             let token = parser
-                .next_token_with_metadata(current_metadata.as_ref())
+                .next_token_with_metadata(protocol_metadata.as_ref())
                 .map_err(|e| Error::Protocol(e.to_string()))?;
 
             let Some(token) = token else {
                 break;
             };
 
-            match token {
+            let row = match token {
                 Token::ColMetaData(meta) => {
-                    // Store metadata for subsequent Row token parsing
-                    current_metadata = Some(meta);
+                    all_columns = Self::build_columns(&meta, self.config.time_zone);
+                    has_rowstat = all_columns
+                        .last()
+                        .is_some_and(|c| c.name.eq_ignore_ascii_case("ROWSTAT"));
+                    response.columns = all_columns.clone();
+                    if has_rowstat {
+                        response.columns.pop();
+                    }
+                    row_metadata = None;
+                    visible_metadata =
+                        Arc::new(crate::row::ColMetaData::new(response.columns.clone()));
+                    decryptor = self.column_decryptor(&meta).await?;
+                    protocol_metadata = Some(meta);
+                    None
                 }
-                Token::Row(_) | Token::NbcRow(_) => {
-                    // Skip row data for execute() - we only care about row count
-                    // The rows are parsed but we don't process them
+                Token::Row(raw_row) => match &protocol_metadata {
+                    Some(meta) => Some(Self::decrypt_row(
+                        decryptor.as_ref(),
+                        Self::convert_raw_row(
+                            &raw_row,
+                            meta,
+                            Self::shared_row_metadata(&mut row_metadata, &all_columns),
+                        )?,
+                    )?),
+                    None => None,
+                },
+                Token::NbcRow(nbc_row) => match &protocol_metadata {
+                    Some(meta) => Some(Self::decrypt_row(
+                        decryptor.as_ref(),
+                        Self::convert_nbc_row(
+                            &nbc_row,
+                            meta,
+                            Self::shared_row_metadata(&mut row_metadata, &all_columns),
+                        )?,
+                    )?),
+                    None => None,
+                },
+                Token::ReturnValue(ret) => {
+                    let value = Self::decode_return_value(&ret)?;
+                    response.return_values.push((ret.param_ordinal, value));
+                    None
                 }
-                Token::Done(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("execution failed".to_string()));
-                    }
-                    if done.status.count {
-                        // Accumulate row counts from all statements in a batch
-                        rows_affected += done.row_count;
-                    }
-                    // Only break if there are no more result sets
-                    // This enables multi-statement batches to report total affected rows
-                    if !done.status.more {
-                        break;
-                    }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    server_error.get_or_insert_with(|| DatabaseError::from(&err));
+                    None
                 }
-                Token::DoneProc(done) => {
-                    if done.status.count {
-                        rows_affected += done.row_count;
-                    }
+                Token::Done(done) if done.status.error && server_error.is_none() => {
+                    return Err(Error::Query("cursor operation failed".to_string()));
                 }
-                Token::DoneInProc(done) => {
-                    if done.status.count {
-                        rows_affected += done.row_count;
-                    }
+                Token::DoneProc(done) if done.status.error && server_error.is_none() => {
+                    return Err(Error::Query("cursor operation failed".to_string()));
                 }
-                Token::Error(err) => {
-                    return Err(Error::Server {
-                        number: err.number,
-                        state: err.state,
-                        class: err.class,
-                        message: err.message.clone(),
-                        server: if err.server.is_empty() {
-                            None
-                        } else {
-                            Some(err.server.clone())
-                        },
-                        procedure: if err.procedure.is_empty() {
-                            None
-                        } else {
-                            Some(err.procedure.clone())
-                        },
-                        line: err.line as u32,
-                    });
+                Token::DoneInProc(done) if done.status.error && server_error.is_none() => {
+                    return Err(Error::Query("cursor operation failed".to_string()));
                 }
                 Token::Info(info) => {
-                    tracing::info!(
-                        number = info.number,
-                        message = %info.message,
-                        "server info message"
-                    );
+                    self.handle_info(&info);
+                    None
                 }
                 Token::EnvChange(env) => {
-                    // Process transaction-related EnvChange tokens.
-                    // This allows BEGIN TRANSACTION, COMMIT, ROLLBACK via raw SQL
-                    // to properly update the transaction descriptor.
-                    Self::process_transaction_env_change(&env, &mut self.transaction_descriptor);
+                    self.handle_env_change(&env).await;
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(row) = row {
+                if has_rowstat {
+                    let rowstat_index = all_columns.len() - 1;
+                    // SQL_ROW_DELETED: the row was removed after the keyset was built
+                    if row.try_get::<i32>(rowstat_index) == Some(2) {
+                        continue;
+                    }
+                    let values = (0..rowstat_index)
+                        .map(|i| row.get_raw(i).unwrap_or(mssql_types::SqlValue::Null))
+                        .collect();
+                    response.rows.push(crate::row::Row::from_shared_values(
+                        Arc::clone(&visible_metadata),
+                        values,
+                    ));
+                } else {
+                    response.rows.push(row);
                 }
-                _ => {}
             }
         }
 
-        Ok(rows_affected)
+        if let Some(err) = server_error {
+            return Err(self.server_error(err, in_transaction));
+        }
+
+        Ok(response)
     }
 
     /// Read the response from BEGIN TRANSACTION and extract the transaction descriptor.
@@ -2873,26 +5472,12 @@ impl<S: ConnectionState> Client<S> {
     /// the transaction descriptor (8-byte value) that must be included in subsequent
     /// ALL_HEADERS sections for requests within this transaction.
     async fn read_transaction_begin_result(&mut self) -> Result<u64> {
-        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
-
-        let message = match connection {
-            ConnectionHandle::Tls(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::TlsPrelogin(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::Plain(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-        }
-        .ok_or(Error::ConnectionClosed)?;
+        self.messages.clear();
+        let message = self.read_response().await?;
 
-        let mut parser = TokenParser::new(message.payload);
+        let mut parser = self.token_parser(message.payload);
         let mut transaction_descriptor: u64 = 0;
+        let mut server_error: Option<DatabaseError> = None;
 
         loop {
             let token = parser
@@ -2925,42 +5510,384 @@ impl<S: ConnectionState> Client<S> {
                     }
                 }
                 Token::Done(done) => {
-                    if done.status.error {
+                    if done.status.error && server_error.is_none() {
                         return Err(Error::Query("BEGIN TRANSACTION failed".to_string()));
                     }
-                    break;
+                    break;
+                }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    server_error.get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(err) = server_error {
+            return Err(self.server_error(err, false));
+        }
+
+        Ok(transaction_descriptor)
+    }
+
+    /// Read one packet of the current response and handle the complete
+    /// tokens in it.
+    ///
+    /// Used for statements that report progress while they run, such as
+    /// `BACKUP ... WITH STATS`, whose INFO messages would otherwise only be
+    /// seen once the whole response has arrived. Token data split across
+    /// packets is kept in `response` until the rest arrives. The INFO
+    /// messages are buffered and passed to the callback as usual, and also
+    /// returned. Once the last packet is read, `response.complete` is set and
+    /// any server error is returned.
+    pub(crate) async fn read_response_packet(
+        &mut self,
+        response: &mut crate::backup::PartialResponse,
+    ) -> Result<Vec<ServerMessage>> {
+        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+        let packet = connection
+            .read_packet()
+            .await?
+            .ok_or(Error::ConnectionClosed)?;
+
+        let end_of_message = packet.header.is_end_of_message();
+        if end_of_message {
+            self.request_state = RequestState::Idle;
+        }
+        response.buffer.extend_from_slice(&packet.payload);
+
+        let data = response.buffer.split().freeze();
+        let mut parser = self.token_parser(data.clone());
+        let mut consumed = 0;
+        let mut infos = Vec::new();
+        loop {
+            let token = match parser.next_token() {
+                Ok(Some(token)) => token,
+                Ok(None) => break,
+                // The rest of the token is in a later packet
+                Err(_) if !end_of_message => break,
+                Err(e) => return Err(Error::Protocol(e.to_string())),
+            };
+            consumed = parser.position();
+
+            match token {
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                    infos.push(ServerMessage::from(&info));
+                }
+                Token::Error(err) => {
+                    response
+                        .error
+                        .get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::EnvChange(env) => {
+                    self.handle_env_change(&env).await;
+                }
+                _ => {}
+            }
+        }
+        if !end_of_message {
+            // Keep the unparsed tail for the next packet
+            response.buffer.extend_from_slice(&data[consumed..]);
+            return Ok(infos);
+        }
+
+        response.complete = true;
+        if let Some(err) = response.error.take() {
+            let in_transaction = self.transaction_descriptor != 0;
+            return Err(self.server_error(err, in_transaction));
+        }
+        Ok(infos)
+    }
+
+    /// Decode more of the response to a [`RowStream`] query.
+    ///
+    /// Decodes the tokens already read until `state` holds as many rows as
+    /// it may buffer, and reads the next packet only if they run out. Token
+    /// data split across packets is kept in `state` until the rest arrives.
+    /// A server error is kept in `state` for the stream to return once the
+    /// rows before it are consumed.
+    pub(crate) async fn read_row_stream(&mut self, state: &mut RowStreamState) -> Result<()> {
+        self.decode_row_stream(state).await?;
+        if state.complete || state.is_full() {
+            return Ok(());
+        }
+
+        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+        let packet = connection
+            .read_packet()
+            .await
+            .inspect_err(|_| self.broken = true)?
+            .ok_or(Error::ConnectionClosed)?;
+        if packet.header.is_end_of_message() {
+            self.request_state = RequestState::Idle;
+            state.end_of_message = true;
+        }
+        state.buffer.extend_from_slice(&packet.payload);
+        self.decode_row_stream(state).await
+    }
+
+    /// Decode the buffered tokens of a [`RowStream`] response until the
+    /// rows waiting in `state` reach its limits.
+    async fn decode_row_stream(&mut self, state: &mut RowStreamState) -> Result<()> {
+        if state.buffer.len() < state.retry_len && !state.end_of_message {
+            return Ok(());
+        }
+
+        let data = state.buffer.split().freeze();
+        let mut parser = self.token_parser(data.clone());
+        let mut consumed = 0;
+        let mut incomplete = false;
+        while !state.is_full() {
+            let token = match parser.next_token_with_metadata(state.metadata.as_ref()) {
+                Ok(Some(token)) => token,
+                Ok(None) => break,
+                Err(e @ tds_protocol::ProtocolError::LobTooLarge { .. }) => {
+                    return Err(Error::Protocol(e.to_string()));
+                }
+                // The rest of the token is in a later packet
+                Err(_) if !state.end_of_message => {
+                    incomplete = true;
+                    break;
+                }
+                Err(e) => return Err(Error::Protocol(e.to_string())),
+            };
+            consumed = parser.position();
+
+            match token {
+                Token::ColMetaData(meta) => {
+                    state.columns = Self::build_columns(&meta, self.config.time_zone);
+                    state.row_metadata = None;
+                    state.decryptor = self.column_decryptor(&meta).await?;
+                    state.tab_name = None;
+                    tracing::debug!(
+                        columns = state.columns.len(),
+                        "received column metadata for stream"
+                    );
+                    state.metadata = Some(meta);
+                }
+                Token::TabName(tables) => {
+                    state.tab_name = Some(tables);
+                }
+                Token::ColInfo(info) => {
+                    Self::apply_col_info(&mut state.columns, state.tab_name.as_ref(), &info);
+                    state.row_metadata = None;
+                }
+                Token::Row(raw_row) => {
+                    if let Some(ref meta) = state.metadata {
+                        let shared =
+                            Self::shared_row_metadata(&mut state.row_metadata, &state.columns);
+                        let row = Self::convert_raw_row(&raw_row, meta, shared)?;
+                        let row = Self::decrypt_row(state.decryptor.as_ref(), row)?;
+                        state.push_row(row, raw_row.data.len());
+                    }
+                }
+                Token::NbcRow(nbc_row) => {
+                    if let Some(ref meta) = state.metadata {
+                        let shared =
+                            Self::shared_row_metadata(&mut state.row_metadata, &state.columns);
+                        let row = Self::convert_nbc_row(&nbc_row, meta, shared)?;
+                        let row = Self::decrypt_row(state.decryptor.as_ref(), row)?;
+                        state.push_row(row, nbc_row.data.len());
+                    }
+                }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    state.error.get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::Done(done) => {
+                    if done.status.error && state.error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                }
+                Token::DoneProc(done) => {
+                    if done.status.error && state.error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                }
+                Token::DoneInProc(done) => {
+                    if done.status.error && state.error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                }
+                Token::Info(info) => {
+                    self.handle_info(&info);
+                }
+                Token::EnvChange(env) => {
+                    self.handle_env_change(&env).await;
+                }
+                _ => {}
+            }
+        }
+
+        // Keep the undecoded tail for the next call
+        state.buffer.extend_from_slice(&data[consumed..]);
+        // Parse a split token again only once the buffer has doubled
+        state.retry_len = if incomplete {
+            state.buffer.len() * 2
+        } else {
+            0
+        };
+        state.complete = state.end_of_message && state.buffer.is_empty();
+        Ok(())
+    }
+
+    /// Cancel the query of a [`RowStream`] dropped before the end of its
+    /// response.
+    ///
+    /// The rest of the response is drained before the next request.
+    pub(crate) fn cancel_abandoned_stream(&mut self) {
+        if self.request_state != RequestState::AwaitingResponse {
+            return;
+        }
+        if let Some(connection) = self.connection.as_ref() {
+            if connection.cancel_handle().cancel_in_background() {
+                tracing::debug!("row stream dropped, cancelling query");
+            }
+        }
+    }
+
+    /// Read multiple result sets from a batch response.
+    async fn read_multi_result_response(&mut self) -> Result<Vec<crate::stream::ResultSet>> {
+        self.messages.clear();
+        let message = self.read_response().await?;
+
+        let mut parser = self.token_parser(message.payload);
+        let mut result_sets: Vec<crate::stream::ResultSet> = Vec::new();
+        let mut current_columns: Vec<crate::row::Column> = Vec::new();
+        let mut row_metadata = None;
+        let mut current_rows: Vec<crate::row::Row> = Vec::new();
+        let mut protocol_metadata: Option<ColMetaData> = None;
+        let mut decryptor: Option<ColumnDecryptor> = None;
+        let mut tab_name: Option<TabName> = None;
+        let in_transaction = self.transaction_descriptor != 0;
+        let mut server_error: Option<DatabaseError> = None;
+
+        loop {
+            let token = parser
+                .next_token_with_metadata(protocol_metadata.as_ref())
+                .map_err(|e| Error::Protocol(e.to_string()))?;
+
+            let Some(token) = token else {
+                break;
+            };
+
+            match token {
+                Token::ColMetaData(meta) => {
+                    // New result set starting - save the previous one if it has columns
+                    if !current_columns.is_empty() {
+                        result_sets.push(crate::stream::ResultSet::new(
+                            std::mem::take(&mut current_columns),
+                            std::mem::take(&mut current_rows),
+                        ));
+                    }
+
+                    current_columns = Self::build_columns(&meta, self.config.time_zone);
+                    row_metadata = None;
+                    decryptor = self.column_decryptor(&meta).await?;
+                    // Table names from an earlier result set don't apply to this one
+                    tab_name = None;
+
+                    tracing::debug!(
+                        columns = current_columns.len(),
+                        result_set = result_sets.len(),
+                        "received column metadata for result set"
+                    );
+                    protocol_metadata = Some(meta);
+                }
+                Token::TabName(tables) => {
+                    tab_name = Some(tables);
+                }
+                Token::ColInfo(info) => {
+                    Self::apply_col_info(&mut current_columns, tab_name.as_ref(), &info);
+                    row_metadata = None;
+                }
+                Token::Row(raw_row) => {
+                    if let Some(ref meta) = protocol_metadata {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &current_columns);
+                        let row = Self::convert_raw_row(&raw_row, meta, shared)?;
+                        current_rows.push(Self::decrypt_row(decryptor.as_ref(), row)?);
+                    }
+                }
+                Token::NbcRow(nbc_row) => {
+                    if let Some(ref meta) = protocol_metadata {
+                        let shared = Self::shared_row_metadata(&mut row_metadata, &current_columns);
+                        let row = Self::convert_nbc_row(&nbc_row, meta, shared)?;
+                        current_rows.push(Self::decrypt_row(decryptor.as_ref(), row)?);
+                    }
+                }
+                Token::Error(err) => {
+                    // Keep reading so a rollback reported after the error is applied
+                    server_error.get_or_insert_with(|| DatabaseError::from(&err));
+                }
+                Token::Done(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+
+                    // Save the current result set if we have columns
+                    if !current_columns.is_empty() {
+                        result_sets.push(crate::stream::ResultSet::new(
+                            std::mem::take(&mut current_columns),
+                            std::mem::take(&mut current_rows),
+                        ));
+                        protocol_metadata = None;
+                    }
+
+                    // Check if there are more result sets
+                    if !done.status.more {
+                        tracing::debug!(result_sets = result_sets.len(), "all result sets parsed");
+                        break;
+                    }
+                }
+                Token::DoneInProc(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+
+                    // Save the current result set if we have columns (within stored proc)
+                    if !current_columns.is_empty() {
+                        result_sets.push(crate::stream::ResultSet::new(
+                            std::mem::take(&mut current_columns),
+                            std::mem::take(&mut current_rows),
+                        ));
+                        protocol_metadata = None;
+                    }
+
+                    // DoneInProc may indicate more results within the batch
+                    if !done.status.more {
+                        // No more results from this statement, but batch may continue
+                    }
                 }
-                Token::Error(err) => {
-                    return Err(Error::Server {
-                        number: err.number,
-                        state: err.state,
-                        class: err.class,
-                        message: err.message.clone(),
-                        server: if err.server.is_empty() {
-                            None
-                        } else {
-                            Some(err.server.clone())
-                        },
-                        procedure: if err.procedure.is_empty() {
-                            None
-                        } else {
-                            Some(err.procedure.clone())
-                        },
-                        line: err.line as u32,
-                    });
+                Token::DoneProc(done) => {
+                    if done.status.error && server_error.is_none() {
+                        return Err(Error::Query("query failed".to_string()));
+                    }
+                    // DoneProc marks end of stored procedure, not necessarily end of results
                 }
                 Token::Info(info) => {
-                    tracing::info!(
-                        number = info.number,
-                        message = %info.message,
-                        "server info message"
-                    );
+                    self.handle_info(&info);
+                }
+                Token::EnvChange(env) => {
+                    self.handle_env_change(&env).await;
                 }
                 _ => {}
             }
         }
 
-        Ok(transaction_descriptor)
+        if let Some(err) = server_error {
+            return Err(self.server_error(err, in_transaction));
+        }
+
+        // Don't forget any remaining result set that wasn't followed by Done
+        if !current_columns.is_empty() {
+            result_sets.push(crate::stream::ResultSet::new(current_columns, current_rows));
+        }
+
+        Ok(result_sets)
     }
 }
 
@@ -2977,6 +5904,8 @@ impl Client<Ready> {
     /// separate command because it's handled at the TDS protocol level.
     pub fn mark_needs_reset(&mut self) {
         self.needs_reset = true;
+        // The reset unprepares every statement on the server
+        self.statement_cache.clear().for_each(drop);
     }
 
     /// Check if this connection needs a reset.
@@ -3025,15 +5954,7 @@ impl Client<Ready> {
         let mut span = instrumentation.query_span(sql);
 
         let result = async {
-            if params.is_empty() {
-                // Simple query without parameters - use SQL batch
-                self.send_sql_batch(sql).await?;
-            } else {
-                // Parameterized query - use sp_executesql via RPC
-                let rpc_params = Self::convert_params(params)?;
-                let rpc = RpcRequest::execute_sql(sql, rpc_params);
-                self.send_rpc(&rpc).await?;
-            }
+            self.send_statement(sql, params).await?;
 
             // Read complete response including columns and rows
             self.read_query_response().await
@@ -3051,14 +5972,42 @@ impl Client<Ready> {
         drop(span);
 
         let (columns, rows) = result?;
-        Ok(QueryStream::new(columns, rows))
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query and return its rows as Arrow record batches.
+    ///
+    /// Batches hold up to [`DEFAULT_BATCH_SIZE`](crate::arrow::DEFAULT_BATCH_SIZE)
+    /// rows unless changed with [`ArrowBatches::with_batch_size`](crate::ArrowBatches::with_batch_size).
+    /// See the [`arrow`](crate::arrow) module for the type mapping.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let batches = client
+    ///     .query_arrow("SELECT * FROM Orders WHERE Year = @p1", &[&2024])
+    ///     .await?
+    ///     .with_batch_size(10_000);
+    /// for batch in batches {
+    ///     let batch = batch?;
+    ///     println!("{} rows", batch.num_rows());
+    /// }
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub async fn query_arrow(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<crate::ArrowBatches> {
+        Ok(self.query(sql, params).await?.into())
     }
 
     /// Execute a query with a specific timeout.
     ///
     /// This overrides the default `command_timeout` from the connection configuration
     /// for this specific query. If the query does not complete within the specified
-    /// duration, an error is returned.
+    /// duration, it is cancelled and [`Error::CommandTimeout`] is returned; the
+    /// connection stays usable (see [`drain`](Self::drain)).
     ///
     /// # Arguments
     ///
@@ -3071,335 +6020,758 @@ impl Client<Ready> {
     /// ```rust,ignore
     /// use std::time::Duration;
     ///
-    /// // Execute with a 5-second timeout
-    /// let rows = client
-    ///     .query_with_timeout(
-    ///         "SELECT * FROM large_table",
-    ///         &[],
-    ///         Duration::from_secs(5),
+    /// // Execute with a 5-second timeout
+    /// let rows = client
+    ///     .query_with_timeout(
+    ///         "SELECT * FROM large_table",
+    ///         &[],
+    ///         Duration::from_secs(5),
+    ///     )
+    ///     .await?;
+    /// ```
+    pub async fn query_with_timeout<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        timeout_duration: std::time::Duration,
+    ) -> Result<QueryStream<'a>> {
+        timeout(timeout_duration, self.query(sql, params))
+            .await
+            .map_err(|_| Error::CommandTimeout)?
+    }
+
+    /// Execute a query with per-statement options.
+    ///
+    /// Adds a lock timeout, `MAXDOP`, `RECOMPILE`, `OPTIMIZE FOR` or table
+    /// hints such as `NOLOCK` and `READPAST` to this statement only. See
+    /// [`QueryOptions`] for how they are applied.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    /// use mssql_client::{QueryOptions, TableHint};
+    ///
+    /// let options = QueryOptions::new()
+    ///     .lock_timeout(Duration::from_secs(1))
+    ///     .table_hint("q", TableHint::ReadPast);
+    /// let rows = client
+    ///     .query_with_options("SELECT TOP (10) * FROM WorkQueue q", &[], &options)
+    ///     .await?
+    ///     .collect_all()
+    ///     .await?;
+    /// ```
+    pub async fn query_with_options<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<QueryStream<'a>> {
+        self.send_with_options(sql, params, options).await?;
+        let (columns, rows) = self.read_query_response().await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query and read its rows as they are consumed.
+    ///
+    /// Unlike [`query`](Self::query), which reads the whole response before
+    /// returning, the [`RowStream`] decodes rows only a bounded distance
+    /// ahead of the consumer, so a large result does not have to fit in
+    /// memory. See [`query_stream_with_options`](Self::query_stream_with_options)
+    /// to tune how far.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut rows = client.query_stream("SELECT * FROM large_table", &[]).await?;
+    /// while let Some(row) = rows.next_row().await? {
+    ///     process_row(&row);
+    /// }
+    /// ```
+    pub async fn query_stream<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<RowStream<'a>> {
+        self.query_stream_with_options(sql, params, &QueryOptions::default())
+            .await
+    }
+
+    /// Execute a query with per-statement options and read its rows as
+    /// they are consumed.
+    ///
+    /// [`QueryOptions::prefetch_rows`] and
+    /// [`QueryOptions::max_buffered_bytes`] bound how many decoded rows
+    /// wait for the consumer; the other options apply as in
+    /// [`query_with_options`](Self::query_with_options).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let options = QueryOptions::new()
+    ///     .prefetch_rows(100)
+    ///     .max_buffered_bytes(1024 * 1024);
+    /// let mut rows = client
+    ///     .query_stream_with_options("SELECT id, payload FROM Events", &[], &options)
+    ///     .await?;
+    /// while let Some(row) = rows.next_row().await? {
+    ///     slow_sink.send(row).await?;
+    /// }
+    /// ```
+    pub async fn query_stream_with_options<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<RowStream<'a>> {
+        self.messages.clear();
+        self.send_with_options(sql, params, options).await?;
+        RowStream::start(self, options).await
+    }
+
+    /// Execute a statement with per-statement options.
+    ///
+    /// See [`query_with_options`](Self::query_with_options) for details.
+    pub async fn execute_with_options(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64> {
+        self.send_with_options(sql, params, options).await?;
+        let result = self.read_execute_response(false).await?;
+        Self::set_output_params(params, &result.output_params);
+        Ok(result.rows_affected)
+    }
+
+    /// Execute a query with a value list expanded into `IN (...)`
+    /// parameters.
+    ///
+    /// Every occurrence of the placeholder `list` (e.g. `@ids`) in `sql` is
+    /// replaced by one parameter per value, numbered after `params`. Lists
+    /// too long for one statement are sent as a table-valued parameter, or,
+    /// when that is turned off or allowed to fall back, split with the rows
+    /// of all parts returned together; see the [`in_list`](crate::in_list)
+    /// module for the conditions.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let ids = vec![3, 5, 8];
+    /// let rows = client
+    ///     .query_in(
+    ///         "SELECT * FROM dbo.Orders WHERE CustomerId = @p1 AND Id IN (@ids)",
+    ///         "@ids",
+    ///         &ids,
+    ///         &[&42],
+    ///     )
+    ///     .await?
+    ///     .collect_all()
+    ///     .await?;
+    /// ```
+    pub async fn query_in<'a, T: crate::ToSql + Sync>(
+        &'a mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<QueryStream<'a>> {
+        let (columns, rows) = self.query_in_response(sql, list, values, params).await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a statement with a value list expanded into `IN (...)`
+    /// parameters, returning the total number of affected rows.
+    ///
+    /// A split list runs as several statements in one nested transaction,
+    /// so a failing statement leaves none of them applied. See
+    /// [`query_in`](Self::query_in) for details.
+    pub async fn execute_in<T: crate::ToSql + Sync>(
+        &mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        self.execute_in_response(sql, list, values, params).await
+    }
+
+    /// Execute a query in browse mode, describing the base table and key
+    /// of every column.
+    ///
+    /// Key columns that were not selected are added to the result as hidden
+    /// columns, so every row can be addressed again with
+    /// [`browse::update_row`](crate::browse::update_row) and
+    /// [`browse::delete_row`](crate::browse::delete_row). See the
+    /// [`browse`](crate::browse) module for details.
+    pub async fn query_browse<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<QueryStream<'a>> {
+        let (columns, rows) = self.browse_response(sql, params).await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query and collect its I/O and timing statistics.
+    ///
+    /// Runs the query with `SET STATISTICS IO, TIME ON` and parses the
+    /// informational messages into a [`QueryStatistics`]. The settings are
+    /// scoped to this query and do not stay on for the connection. See the
+    /// [`statistics`](crate::statistics) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (rows, stats) = client
+    ///     .query_with_stats("SELECT * FROM Orders WHERE CustomerId = @p1", &[&42])
+    ///     .await?;
+    /// tracing::info!(reads = stats.logical_reads(), cpu_ms = stats.execution.cpu_ms);
+    /// ```
+    pub async fn query_with_stats<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<(QueryStream<'a>, QueryStatistics)> {
+        let (columns, rows, stats) = self.query_stats_response(sql, params).await?;
+        let stream = QueryStream::new(columns, rows).with_messages(self.messages.clone());
+        Ok((stream, stats))
+    }
+
+    /// Execute a batch that may return multiple result sets.
+    ///
+    /// This is useful for stored procedures or SQL batches that contain
+    /// multiple SELECT statements.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Execute a batch with multiple SELECTs
+    /// let mut results = client.query_multiple(
+    ///     "SELECT 1 AS a; SELECT 2 AS b, 3 AS c;",
+    ///     &[]
+    /// ).await?;
+    ///
+    /// // Process first result set
+    /// while let Some(row) = results.next_row().await? {
+    ///     println!("Result 1: {:?}", row);
+    /// }
+    ///
+    /// // Move to second result set
+    /// if results.next_result().await? {
+    ///     while let Some(row) = results.next_row().await? {
+    ///         println!("Result 2: {:?}", row);
+    ///     }
+    /// }
+    /// ```
+    pub async fn query_multiple<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<MultiResultStream<'a>> {
+        tracing::debug!(
+            sql = sql,
+            params_count = params.len(),
+            "executing multi-result query"
+        );
+
+        if params.is_empty() {
+            // Simple batch without parameters - use SQL batch
+            self.send_sql_batch(sql).await?;
+        } else {
+            // Parameterized query - use sp_executesql via RPC
+            let rpc_params = self.convert_params(params)?;
+            let rpc_params = self.encrypt_params(sql, rpc_params).await?;
+            let rpc = RpcRequest::execute_sql(sql, rpc_params);
+            self.send_rpc(&rpc).await?;
+        }
+
+        // Read all result sets
+        let result_sets = self.read_multi_result_response().await?;
+        Ok(MultiResultStream::new(result_sets).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query that doesn't return rows.
+    ///
+    /// Returns the number of affected rows. [`OutParam`](crate::OutParam)
+    /// parameters receive the values the server returns for them.
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        tracing::debug!(
+            sql = sql,
+            params_count = params.len(),
+            "executing statement"
+        );
+
+        #[cfg(feature = "otel")]
+        let instrumentation = self.instrumentation.clone();
+        #[cfg(feature = "otel")]
+        let mut span = instrumentation.query_span(sql);
+
+        let result = async {
+            self.send_statement(sql, params).await?;
+
+            // Read response and get row count
+            let result = self.read_execute_response(false).await?;
+            Self::set_output_params(params, &result.output_params);
+            Ok(result.rows_affected)
+        }
+        .await;
+
+        #[cfg(feature = "otel")]
+        match &result {
+            Ok(rows) => InstrumentationContext::record_success(&mut span, Some(*rows)),
+            Err(e) => InstrumentationContext::record_error(&mut span, e),
+        }
+
+        // Drop the span before returning
+        #[cfg(feature = "otel")]
+        drop(span);
+
+        result
+    }
+
+    /// Execute a statement with a specific timeout.
+    ///
+    /// This overrides the default `command_timeout` from the connection configuration
+    /// for this specific statement. If the statement does not complete within the
+    /// specified duration, it is cancelled and [`Error::CommandTimeout`] is
+    /// returned; the connection stays usable (see [`drain`](Self::drain)).
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL statement to execute
+    /// * `params` - Statement parameters
+    /// * `timeout_duration` - Maximum time to wait for the statement to complete
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    ///
+    /// // Execute with a 10-second timeout
+    /// let rows_affected = client
+    ///     .execute_with_timeout(
+    ///         "UPDATE large_table SET status = @p1",
+    ///         &[&"processed"],
+    ///         Duration::from_secs(10),
     ///     )
     ///     .await?;
     /// ```
-    pub async fn query_with_timeout<'a>(
-        &'a mut self,
+    pub async fn execute_with_timeout(
+        &mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
         timeout_duration: std::time::Duration,
-    ) -> Result<QueryStream<'a>> {
-        timeout(timeout_duration, self.query(sql, params))
+    ) -> Result<u64> {
+        timeout(timeout_duration, self.execute(sql, params))
             .await
             .map_err(|_| Error::CommandTimeout)?
     }
 
-    /// Execute a batch that may return multiple result sets.
+    /// Execute a statement and return per-statement execution details.
     ///
-    /// This is useful for stored procedures or SQL batches that contain
-    /// multiple SELECT statements.
+    /// Unlike [`execute`](Self::execute), which returns only the total row
+    /// count, this reports the row count of every statement in the batch and
+    /// whether each count was valid (not suppressed by `SET NOCOUNT ON`).
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// // Execute a batch with multiple SELECTs
-    /// let mut results = client.query_multiple(
-    ///     "SELECT 1 AS a; SELECT 2 AS b, 3 AS c;",
-    ///     &[]
-    /// ).await?;
+    /// let result = client
+    ///     .execute_detailed("UPDATE a SET x = 1; DELETE FROM b WHERE y = 2", &[])
+    ///     .await?;
     ///
-    /// // Process first result set
-    /// while let Some(row) = results.next_row().await? {
-    ///     println!("Result 1: {:?}", row);
+    /// for (i, count) in result.row_counts.iter().enumerate() {
+    ///     println!("statement {i}: {} rows", count.rows_affected);
     /// }
+    /// println!("total: {}", result.total_rows_affected());
+    /// ```
+    pub async fn execute_detailed(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<ExecuteResult> {
+        self.execute_statement(sql, params, false).await
+    }
+
+    /// Execute a statement and capture the identity value it generated.
     ///
-    /// // Move to second result set
-    /// if results.next_result().await? {
-    ///     while let Some(row) = results.next_row().await? {
-    ///         println!("Result 2: {:?}", row);
-    ///     }
+    /// Appends `SELECT CAST(SCOPE_IDENTITY() AS BIGINT)` to the statement and
+    /// stores the result in [`ExecuteResult::last_identity`]. The extra
+    /// statement is not included in the returned row counts.
+    pub async fn execute_with_identity(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<ExecuteResult> {
+        self.execute_statement(sql, params, true).await
+    }
+
+    /// Execute an INSERT and return the identity value it generated.
+    ///
+    /// Uses `SCOPE_IDENTITY()`, which is safe on tables with triggers that
+    /// insert into other identity tables. Returns `None` if no identity value
+    /// was generated. See [`execute_with_identity`](Self::execute_with_identity)
+    /// to also get row counts.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let id = client
+    ///     .insert_returning_identity("INSERT INTO users (name) VALUES (@p1)", &[&"Alice"])
+    ///     .await?
+    ///     .expect("users has an identity column");
+    /// ```
+    pub async fn insert_returning_identity(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Option<i64>> {
+        Ok(self.execute_with_identity(sql, params).await?.last_identity)
+    }
+
+    /// Execute an INSERT with an `OUTPUT INSERTED` clause for `columns`.
+    ///
+    /// The clause is added to `sql` by
+    /// [`with_output_inserted`](crate::returning::with_output_inserted), and
+    /// the returned stream yields one row of generated values per inserted
+    /// row. Pass `&["*"]` to return all columns.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut rows = client
+    ///     .insert_returning(
+    ///         "INSERT INTO users (name) VALUES (@p1), (@p2)",
+    ///         &[&"Alice", &"Bob"],
+    ///         &["Id", "CreatedAt"],
+    ///     )
+    ///     .await?;
+    ///
+    /// while let Some(row) = rows.try_next() {
+    ///     let id: i32 = row.get(0)?;
     /// }
     /// ```
-    pub async fn query_multiple<'a>(
+    pub async fn insert_returning<'a>(
         &'a mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
-    ) -> Result<MultiResultStream<'a>> {
-        tracing::debug!(
-            sql = sql,
-            params_count = params.len(),
-            "executing multi-result query"
-        );
-
-        if params.is_empty() {
-            // Simple batch without parameters - use SQL batch
-            self.send_sql_batch(sql).await?;
-        } else {
-            // Parameterized query - use sp_executesql via RPC
-            let rpc_params = Self::convert_params(params)?;
-            let rpc = RpcRequest::execute_sql(sql, rpc_params);
-            self.send_rpc(&rpc).await?;
-        }
-
-        // Read all result sets
-        let result_sets = self.read_multi_result_response().await?;
-        Ok(MultiResultStream::new(result_sets))
+        columns: &[&str],
+    ) -> Result<QueryStream<'a>> {
+        let sql = crate::returning::with_output_inserted(sql, columns)?;
+        self.query(&sql, params).await
     }
 
-    /// Read multiple result sets from a batch response.
-    async fn read_multi_result_response(&mut self) -> Result<Vec<crate::stream::ResultSet>> {
-        let connection = self.connection.as_mut().ok_or(Error::ConnectionClosed)?;
+    /// Create a temporary table with the columns of `T` and insert `rows`
+    /// into it.
+    ///
+    /// `name` must start with `#`. The returned guard borrows the client,
+    /// since the table only exists on this connection; run statements that
+    /// use it through [`TempTable::client`]. The table is dropped by
+    /// [`TempTable::drop_table`], or before the next request once the guard
+    /// is dropped. See the [`temp_table`](crate::temp_table) module for
+    /// details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut keys = client.temp_table("#keys", &order_keys).await?;
+    /// let count = keys
+    ///     .client()
+    ///     .execute("DELETE o FROM dbo.Orders o JOIN #keys k ON k.order_id = o.Id", &[])
+    ///     .await?;
+    /// ```
+    pub async fn temp_table<T: crate::tvp::Tvp>(
+        &mut self,
+        name: &str,
+        rows: &[T],
+    ) -> Result<TempTable<'_, Ready>> {
+        self.create_temp_table(name, rows).await?;
+        TempTable::new(self, name)
+    }
 
-        let message = match connection {
-            ConnectionHandle::Tls(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::TlsPrelogin(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
-            ConnectionHandle::Plain(conn) => conn
-                .read_message()
-                .await
-                .map_err(|e| Error::Protocol(e.to_string()))?,
+    /// Insert a slice of rows with `INSERT ... SELECT` from a TVP.
+    ///
+    /// Creates the table type named by `T::type_name()` from `T::columns()`
+    /// if it doesn't exist, then inserts every row in one statement. An
+    /// existing type is reused as is, so its columns must match `T`. Suited
+    /// to batches of hundreds to tens of thousands of rows; use
+    /// [`BulkInsert`](crate::bulk::BulkInsert) for larger loads.
+    ///
+    /// Returns the number of inserted rows.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// #[derive(Tvp)]
+    /// #[mssql(type_name = "dbo.OrderRows")]
+    /// struct Order { customer_id: i32, total: Decimal }
+    ///
+    /// let inserted = client.insert_many("dbo.Orders", &orders).await?;
+    /// ```
+    pub async fn insert_many<T: crate::tvp::Tvp>(
+        &mut self,
+        table: &str,
+        rows: &[T],
+    ) -> Result<u64> {
+        if rows.is_empty() {
+            return Ok(0);
         }
-        .ok_or(Error::ConnectionClosed)?;
-
-        let mut parser = TokenParser::new(message.payload);
-        let mut result_sets: Vec<crate::stream::ResultSet> = Vec::new();
-        let mut current_columns: Vec<crate::row::Column> = Vec::new();
-        let mut current_rows: Vec<crate::row::Row> = Vec::new();
-        let mut protocol_metadata: Option<ColMetaData> = None;
-
-        loop {
-            let token = parser
-                .next_token_with_metadata(protocol_metadata.as_ref())
-                .map_err(|e| Error::Protocol(e.to_string()))?;
-
-            let Some(token) = token else {
-                break;
-            };
-
-            match token {
-                Token::ColMetaData(meta) => {
-                    // New result set starting - save the previous one if it has columns
-                    if !current_columns.is_empty() {
-                        result_sets.push(crate::stream::ResultSet::new(
-                            std::mem::take(&mut current_columns),
-                            std::mem::take(&mut current_rows),
-                        ));
-                    }
-
-                    // Parse the new column metadata
-                    current_columns = meta
-                        .columns
-                        .iter()
-                        .enumerate()
-                        .map(|(i, col)| {
-                            let type_name = format!("{:?}", col.type_id);
-                            let mut column = crate::row::Column::new(&col.name, i, type_name)
-                                .with_nullable(col.flags & 0x01 != 0);
-
-                            if let Some(max_len) = col.type_info.max_length {
-                                column = column.with_max_length(max_len);
-                            }
-                            if let (Some(prec), Some(scale)) =
-                                (col.type_info.precision, col.type_info.scale)
-                            {
-                                column = column.with_precision_scale(prec, scale);
-                            }
-                            // Store collation for VARCHAR/CHAR types to enable
-                            // collation-aware string decoding
-                            if let Some(collation) = col.type_info.collation {
-                                column = column.with_collation(collation);
-                            }
-                            column
-                        })
-                        .collect();
-
-                    tracing::debug!(
-                        columns = current_columns.len(),
-                        result_set = result_sets.len(),
-                        "received column metadata for result set"
-                    );
-                    protocol_metadata = Some(meta);
-                }
-                Token::Row(raw_row) => {
-                    if let Some(ref meta) = protocol_metadata {
-                        let row = Self::convert_raw_row(&raw_row, meta, &current_columns)?;
-                        current_rows.push(row);
-                    }
-                }
-                Token::NbcRow(nbc_row) => {
-                    if let Some(ref meta) = protocol_metadata {
-                        let row = Self::convert_nbc_row(&nbc_row, meta, &current_columns)?;
-                        current_rows.push(row);
-                    }
-                }
-                Token::Error(err) => {
-                    return Err(Error::Server {
-                        number: err.number,
-                        state: err.state,
-                        class: err.class,
-                        message: err.message.clone(),
-                        server: if err.server.is_empty() {
-                            None
-                        } else {
-                            Some(err.server.clone())
-                        },
-                        procedure: if err.procedure.is_empty() {
-                            None
-                        } else {
-                            Some(err.procedure.clone())
-                        },
-                        line: err.line as u32,
-                    });
-                }
-                Token::Done(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
-
-                    // Save the current result set if we have columns
-                    if !current_columns.is_empty() {
-                        result_sets.push(crate::stream::ResultSet::new(
-                            std::mem::take(&mut current_columns),
-                            std::mem::take(&mut current_rows),
-                        ));
-                        protocol_metadata = None;
-                    }
+        let insert = crate::tvp::insert_select_sql::<T>(table)?;
+        let tvp = crate::tvp::TvpValue::new(rows)?;
+        self.execute(&crate::tvp::create_type_sql::<T>()?, &[])
+            .await?;
+        self.execute(&insert, &[&tvp]).await
+    }
 
-                    // Check if there are more result sets
-                    if !done.status.more {
-                        tracing::debug!(result_sets = result_sets.len(), "all result sets parsed");
-                        break;
-                    }
-                }
-                Token::DoneInProc(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
+    /// Start a batch of statements sent to the server in a single round trip.
+    ///
+    /// Each statement keeps its own parameters and reports its own row
+    /// counts or error. See the [`batch`](crate::batch) module for the
+    /// available modes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let results = client
+    ///     .batch()
+    ///     .add("INSERT INTO logs (message) VALUES (@p1)", &[&"started"])
+    ///     .add("UPDATE jobs SET status = @p1 WHERE id = @p2", &[&"running", &42])
+    ///     .execute()
+    ///     .await?;
+    ///
+    /// assert!(results.is_success());
+    /// ```
+    pub fn batch(&mut self) -> Batch<'_, Ready> {
+        Batch::new(self)
+    }
 
-                    // Save the current result set if we have columns (within stored proc)
-                    if !current_columns.is_empty() {
-                        result_sets.push(crate::stream::ResultSet::new(
-                            std::mem::take(&mut current_columns),
-                            std::mem::take(&mut current_rows),
-                        ));
-                        protocol_metadata = None;
-                    }
+    /// Inspect the database schema: tables, columns, and indexes.
+    ///
+    /// See the [`schema`](crate::schema) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let columns = client.schema().columns("dbo", "Users").await?;
+    /// let key: Vec<_> = columns.iter().filter(|c| c.is_primary_key).collect();
+    /// ```
+    pub fn schema(&mut self) -> SchemaInspector<'_, Ready> {
+        SchemaInspector::new(self)
+    }
 
-                    // DoneInProc may indicate more results within the batch
-                    if !done.status.more {
-                        // No more results from this statement, but batch may continue
-                    }
-                }
-                Token::DoneProc(done) => {
-                    if done.status.error {
-                        return Err(Error::Query("query failed".to_string()));
-                    }
-                    // DoneProc marks end of stored procedure, not necessarily end of results
-                }
-                Token::Info(info) => {
-                    tracing::debug!(
-                        number = info.number,
-                        message = %info.message,
-                        "server info message"
-                    );
-                }
-                _ => {}
-            }
-        }
+    /// Administer databases, logins, users, roles, and permissions.
+    ///
+    /// See the [`admin`](crate::admin) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if client.admin().ensure_database("Orders", &[]).await? {
+    ///     println!("created Orders");
+    /// }
+    /// ```
+    pub fn admin(&mut self) -> Admin<'_> {
+        Admin::new(self)
+    }
 
-        // Don't forget any remaining result set that wasn't followed by Done
-        if !current_columns.is_empty() {
-            result_sets.push(crate::stream::ResultSet::new(current_columns, current_rows));
-        }
+    /// Read Always On availability group state: replica roles, health,
+    /// and log send and redo queues.
+    ///
+    /// See the [`availability`](crate::availability) module for details.
+    pub fn availability_groups(&mut self) -> AvailabilityGroups<'_> {
+        AvailabilityGroups::new(self)
+    }
 
-        Ok(result_sets)
+    /// Start a `BACKUP DATABASE` of `database` to a file path or URL.
+    ///
+    /// Returns once the statement is sent; read its progress and outcome
+    /// from the returned [`BackupOperation`]. See the
+    /// [`backup`](crate::backup) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let summary = client
+    ///     .backup_database("Sales", "/backup/Sales.bak", &BackupOptions::new())
+    ///     .await?
+    ///     .wait()
+    ///     .await?;
+    /// ```
+    pub async fn backup_database(
+        &mut self,
+        database: &str,
+        target: &str,
+        options: &BackupOptions,
+    ) -> Result<BackupOperation<'_>> {
+        let sql = crate::backup::backup_sql(database, target, options)?;
+        tracing::info!(database, target, "starting backup");
+        self.messages.clear();
+        self.send_sql_batch(&sql).await?;
+        Ok(BackupOperation::new(self))
     }
 
-    /// Execute a query that doesn't return rows.
+    /// Start a `RESTORE DATABASE` of `database` from a file path or URL.
     ///
-    /// Returns the number of affected rows.
-    pub async fn execute(
+    /// Returns once the statement is sent; read its progress and outcome
+    /// from the returned [`BackupOperation`]. See the
+    /// [`backup`](crate::backup) module for details.
+    pub async fn restore_database(
         &mut self,
-        sql: &str,
-        params: &[&(dyn crate::ToSql + Sync)],
-    ) -> Result<u64> {
-        tracing::debug!(
-            sql = sql,
-            params_count = params.len(),
-            "executing statement"
-        );
-
-        #[cfg(feature = "otel")]
-        let instrumentation = self.instrumentation.clone();
-        #[cfg(feature = "otel")]
-        let mut span = instrumentation.query_span(sql);
+        database: &str,
+        source: &str,
+        options: &RestoreOptions,
+    ) -> Result<BackupOperation<'_>> {
+        let sql = crate::backup::restore_sql(database, source, options)?;
+        tracing::info!(database, source, "starting restore");
+        self.messages.clear();
+        self.send_sql_batch(&sql).await?;
+        Ok(BackupOperation::new(self))
+    }
 
-        let result = async {
-            if params.is_empty() {
-                // Simple statement without parameters - use SQL batch
-                self.send_sql_batch(sql).await?;
-            } else {
-                // Parameterized statement - use sp_executesql via RPC
-                let rpc_params = Self::convert_params(params)?;
-                let rpc = RpcRequest::execute_sql(sql, rpc_params);
-                self.send_rpc(&rpc).await?;
-            }
+    /// Read server performance counters: wait statistics, query statistics,
+    /// and index usage.
+    ///
+    /// See the [`diagnostics`](crate::diagnostics) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let top = client.diagnostics().query_stats(10).await?;
+    /// for query in &top.rows {
+    ///     println!("{:?} avg {:?}", query.statement, query.avg_elapsed_time());
+    /// }
+    /// ```
+    pub fn diagnostics(&mut self) -> Diagnostics<'_, Ready> {
+        Diagnostics::new(self)
+    }
 
-            // Read response and get row count
-            self.read_execute_result().await
-        }
-        .await;
+    /// Read Change Data Capture data: LSN ranges, change rows, and pollers.
+    ///
+    /// See the [`cdc`](crate::cdc) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let to = client.cdc().max_lsn().await?;
+    /// let changes: Vec<CdcChange<Order>> = client
+    ///     .cdc()
+    ///     .net_changes("dbo_Orders", from, to, NetRowFilter::All)
+    ///     .await?;
+    /// ```
+    pub fn cdc(&mut self) -> ChangeDataCapture<'_, Ready> {
+        ChangeDataCapture::new(self)
+    }
 
-        #[cfg(feature = "otel")]
-        match &result {
-            Ok(rows) => InstrumentationContext::record_success(&mut span, Some(*rows)),
-            Err(e) => InstrumentationContext::record_error(&mut span, e),
-        }
+    /// Run change tracking queries: versions and changed rows.
+    ///
+    /// See the [`change_tracking`](crate::change_tracking) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let version = client.change_tracking().current_version().await?;
+    /// ```
+    pub fn change_tracking(&mut self) -> ChangeTrackingClient<'_, Ready> {
+        ChangeTrackingClient::new(self)
+    }
 
-        // Drop the span before returning
-        #[cfg(feature = "otel")]
-        drop(span);
+    /// Upsert a slice of rows into a table with `MERGE` and a TVP.
+    ///
+    /// See the [`merge`](crate::merge) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let result = client
+    ///     .merge_into("dbo.Products")
+    ///     .on(&["Sku"])
+    ///     .execute(&products)
+    ///     .await?;
+    /// ```
+    pub fn merge_into(&mut self, table: &str) -> MergeBuilder<'_, Ready> {
+        MergeBuilder::new(self, table)
+    }
 
-        result
+    /// Send and receive Service Broker messages.
+    ///
+    /// See the [`service_broker`](crate::service_broker) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    ///
+    /// if let Some(message) = client
+    ///     .service_broker()
+    ///     .receive_one("TargetQueue", Duration::from_secs(10))
+    ///     .await?
+    /// {
+    ///     println!("{}: {:?}", message.message_type, message.body_str());
+    /// }
+    /// ```
+    pub fn service_broker(&mut self) -> ServiceBroker<'_, Ready> {
+        ServiceBroker::new(self)
     }
 
-    /// Execute a statement with a specific timeout.
+    /// Execute a sqlcmd-style script, batch by batch.
     ///
-    /// This overrides the default `command_timeout` from the connection configuration
-    /// for this specific statement. If the statement does not complete within the
-    /// specified duration, an error is returned.
+    /// The script is split on `GO` separators by [`Script::parse`], and each
+    /// batch is sent as its own SQL batch, repeated as requested by `GO n`.
+    /// Execution stops at the first failed batch unless
+    /// [`Script::continue_on_error`] is set; the error is reported in the
+    /// corresponding [`ScriptBatchResult`] rather than returned directly.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `sql` - The SQL statement to execute
-    /// * `params` - Statement parameters
-    /// * `timeout_duration` - Maximum time to wait for the statement to complete
+    /// ```rust,ignore
+    /// use mssql_client::Script;
+    ///
+    /// let script = Script::parse(&std::fs::read_to_string("schema.sql")?)?;
+    /// let result = client.execute_script(&script).await?;
+    /// if !result.is_success() {
+    ///     for (batch, error) in result.errors() {
+    ///         eprintln!("line {}: {error}", batch.line);
+    ///     }
+    /// }
+    /// ```
+    pub async fn execute_script(&mut self, script: &Script) -> Result<ScriptResult> {
+        self.run_script(script).await
+    }
+
+    /// Open a server-side cursor over the results of a query.
+    ///
+    /// Rows are fetched in pages of [`CursorOptions::fetch_size`] rows with
+    /// `sp_cursorfetch`, so arbitrarily large result sets can be read with
+    /// bounded memory. See the [`cursor`](crate::cursor) module for details.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// use std::time::Duration;
+    /// use mssql_client::{CursorOptions, CursorType, FetchDirection};
     ///
-    /// // Execute with a 10-second timeout
-    /// let rows_affected = client
-    ///     .execute_with_timeout(
-    ///         "UPDATE large_table SET status = @p1",
-    ///         &[&"processed"],
-    ///         Duration::from_secs(10),
+    /// let mut cursor = client
+    ///     .open_cursor(
+    ///         "SELECT id, name FROM users ORDER BY id",
+    ///         &[],
+    ///         CursorOptions::new().cursor_type(CursorType::Static).fetch_size(500),
     ///     )
     ///     .await?;
+    ///
+    /// let last_page = cursor.fetch(FetchDirection::Last, 500).await?;
+    /// cursor.close().await?;
     /// ```
-    pub async fn execute_with_timeout(
+    pub async fn open_cursor(
         &mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
-        timeout_duration: std::time::Duration,
-    ) -> Result<u64> {
-        timeout(timeout_duration, self.execute(sql, params))
-            .await
-            .map_err(|_| Error::CommandTimeout)?
+        options: CursorOptions,
+    ) -> Result<Cursor<'_, Ready>> {
+        self.open_cursor_with_options(sql, params, options).await
     }
 
     /// Begin a transaction.
@@ -3440,10 +6812,22 @@ impl Client<Ready> {
             _state: PhantomData,
             connection: self.connection,
             server_version: self.server_version,
-            current_database: self.current_database,
+            session: self.session,
             statement_cache: self.statement_cache,
             transaction_descriptor, // Store the descriptor from server
             needs_reset: self.needs_reset,
+            deferred_cleanup: self.deferred_cleanup,
+            abandoned_impersonations: self.abandoned_impersonations,
+            request_max_lob_size: self.request_max_lob_size,
+            request_state: self.request_state,
+            socket_probe: self.socket_probe,
+            broken: self.broken,
+            message_handler: self.message_handler,
+            messages: self.messages,
+            column_encryption: self.column_encryption,
+            connect_timings: self.connect_timings,
+            #[cfg(feature = "always-encrypted")]
+            enclave_package: self.enclave_package,
             #[cfg(feature = "otel")]
             instrumentation: self.instrumentation,
         })
@@ -3504,10 +6888,22 @@ impl Client<Ready> {
             _state: PhantomData,
             connection: self.connection,
             server_version: self.server_version,
-            current_database: self.current_database,
+            session: self.session,
             statement_cache: self.statement_cache,
             transaction_descriptor,
             needs_reset: self.needs_reset,
+            deferred_cleanup: self.deferred_cleanup,
+            abandoned_impersonations: self.abandoned_impersonations,
+            request_max_lob_size: self.request_max_lob_size,
+            request_state: self.request_state,
+            socket_probe: self.socket_probe,
+            broken: self.broken,
+            message_handler: self.message_handler,
+            messages: self.messages,
+            column_encryption: self.column_encryption,
+            connect_timings: self.connect_timings,
+            #[cfg(feature = "always-encrypted")]
+            enclave_package: self.enclave_package,
             #[cfg(feature = "otel")]
             instrumentation: self.instrumentation,
         })
@@ -3535,12 +6931,6 @@ impl Client<Ready> {
         Ok(())
     }
 
-    /// Get the current database name.
-    #[must_use]
-    pub fn database(&self) -> Option<&str> {
-        self.config.database.as_deref()
-    }
-
     /// Get the server host.
     #[must_use]
     pub fn host(&self) -> &str {
@@ -3599,21 +6989,73 @@ impl Client<Ready> {
     /// ```
     #[must_use]
     pub fn cancel_handle(&self) -> crate::cancel::CancelHandle {
-        let connection = self
-            .connection
+        self.connection
             .as_ref()
-            .expect("connection should be present");
-        match connection {
-            ConnectionHandle::Tls(conn) => {
-                crate::cancel::CancelHandle::from_tls(conn.cancel_handle())
-            }
-            ConnectionHandle::TlsPrelogin(conn) => {
-                crate::cancel::CancelHandle::from_tls_prelogin(conn.cancel_handle())
-            }
-            ConnectionHandle::Plain(conn) => {
-                crate::cancel::CancelHandle::from_plain(conn.cancel_handle())
-            }
-        }
+            .expect("connection should be present")
+            .cancel_handle()
+    }
+
+    /// Check, without a round trip, whether the connection is known to be
+    /// unusable.
+    ///
+    /// Returns `true` once the connection was closed after a fatal error,
+    /// reading or writing it failed, a request was dropped while being
+    /// written, or the server closed or reset the TCP socket, which is
+    /// checked by peeking at it. A `false` result does not prove the server
+    /// is still there; run a query such as `SELECT 1` for that. Connections
+    /// over custom transports and named pipes are not probed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if client.is_broken() {
+    ///     client = Client::connect(config.clone()).await?;
+    /// }
+    /// ```
+    #[must_use]
+    pub fn is_broken(&self) -> bool {
+        self.connection_is_broken()
+    }
+
+    /// Check whether a dropped request left response data to drain.
+    ///
+    /// Dropping the future of a query or other request before it finishes,
+    /// for example when it loses a `tokio::time::timeout` or `select!`, sends
+    /// an Attention packet to cancel the request on the server. The rest of
+    /// its response is still on the connection; the next request drains it
+    /// automatically before sending anything, or it can be drained up front
+    /// with [`drain`](Self::drain).
+    #[must_use]
+    pub fn needs_drain(&self) -> bool {
+        self.request_state != RequestState::Idle
+    }
+
+    /// Drain the response of a dropped request.
+    ///
+    /// Cancels the request if that has not happened yet and reads the rest
+    /// of its response, up to the server's acknowledgement of the
+    /// cancellation. Does nothing if [`needs_drain`](Self::needs_drain) is
+    /// `false`.
+    ///
+    /// # Errors
+    ///
+    /// A request dropped while it was still being written cannot be
+    /// recovered: the connection is closed and [`Error::ConnectionClosed`]
+    /// is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    ///
+    /// let slow = client.query("WAITFOR DELAY '00:01:00'", &[]);
+    /// if tokio::time::timeout(Duration::from_secs(1), slow).await.is_err() {
+    ///     // The query was cancelled; this waits for the server to confirm
+    ///     client.drain().await?;
+    /// }
+    /// ```
+    pub async fn drain(&mut self) -> Result<()> {
+        self.recover_abandoned_request().await
     }
 }
 
@@ -3638,15 +7080,7 @@ impl Client<InTransaction> {
         let mut span = instrumentation.query_span(sql);
 
         let result = async {
-            if params.is_empty() {
-                // Simple query without parameters - use SQL batch
-                self.send_sql_batch(sql).await?;
-            } else {
-                // Parameterized query - use sp_executesql via RPC
-                let rpc_params = Self::convert_params(params)?;
-                let rpc = RpcRequest::execute_sql(sql, rpc_params);
-                self.send_rpc(&rpc).await?;
-            }
+            self.send_statement(sql, params).await?;
 
             // Read complete response including columns and rows
             self.read_query_response().await
@@ -3664,91 +7098,339 @@ impl Client<InTransaction> {
         drop(span);
 
         let (columns, rows) = result?;
-        Ok(QueryStream::new(columns, rows))
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query within the transaction and return its rows as Arrow
+    /// record batches.
+    ///
+    /// See the [`arrow`](crate::arrow) module for the type mapping.
+    #[cfg(feature = "arrow")]
+    pub async fn query_arrow(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<crate::ArrowBatches> {
+        Ok(self.query(sql, params).await?.into())
+    }
+
+    /// Execute a statement within the transaction.
+    ///
+    /// Returns the number of affected rows.
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        tracing::debug!(
+            sql = sql,
+            params_count = params.len(),
+            "executing statement in transaction"
+        );
+
+        #[cfg(feature = "otel")]
+        let instrumentation = self.instrumentation.clone();
+        #[cfg(feature = "otel")]
+        let mut span = instrumentation.query_span(sql);
+
+        let result = async {
+            self.send_statement(sql, params).await?;
+
+            // Read response and get row count
+            let result = self.read_execute_response(false).await?;
+            Self::set_output_params(params, &result.output_params);
+            Ok(result.rows_affected)
+        }
+        .await;
+
+        #[cfg(feature = "otel")]
+        match &result {
+            Ok(rows) => InstrumentationContext::record_success(&mut span, Some(*rows)),
+            Err(e) => InstrumentationContext::record_error(&mut span, e),
+        }
+
+        // Drop the span before returning
+        #[cfg(feature = "otel")]
+        drop(span);
+
+        result
+    }
+
+    /// Execute a query within the transaction with a specific timeout.
+    ///
+    /// See [`Client<Ready>::query_with_timeout`] for details.
+    pub async fn query_with_timeout<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        timeout_duration: std::time::Duration,
+    ) -> Result<QueryStream<'a>> {
+        timeout(timeout_duration, self.query(sql, params))
+            .await
+            .map_err(|_| Error::CommandTimeout)?
+    }
+
+    /// Execute a query within the transaction with per-statement options.
+    ///
+    /// See [`Client<Ready>::query_with_options`] for details.
+    pub async fn query_with_options<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<QueryStream<'a>> {
+        self.send_with_options(sql, params, options).await?;
+        let (columns, rows) = self.read_query_response().await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a statement within the transaction with per-statement options.
+    ///
+    /// See [`Client<Ready>::query_with_options`] for details.
+    pub async fn execute_with_options(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64> {
+        self.send_with_options(sql, params, options).await?;
+        let result = self.read_execute_response(false).await?;
+        Self::set_output_params(params, &result.output_params);
+        Ok(result.rows_affected)
+    }
+
+    /// Execute a query within the transaction with a value list expanded
+    /// into `IN (...)` parameters.
+    ///
+    /// See [`Client<Ready>::query_in`] for details.
+    pub async fn query_in<'a, T: crate::ToSql + Sync>(
+        &'a mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<QueryStream<'a>> {
+        let (columns, rows) = self.query_in_response(sql, list, values, params).await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a statement within the transaction with a value list
+    /// expanded into `IN (...)` parameters.
+    ///
+    /// See [`Client<Ready>::query_in`] for details.
+    pub async fn execute_in<T: crate::ToSql + Sync>(
+        &mut self,
+        sql: &str,
+        list: &str,
+        values: &[T],
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<u64> {
+        self.execute_in_response(sql, list, values, params).await
+    }
+
+    /// Execute a query in browse mode within the transaction.
+    ///
+    /// See [`Client<Ready>::query_browse`] for details.
+    pub async fn query_browse<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<QueryStream<'a>> {
+        let (columns, rows) = self.browse_response(sql, params).await?;
+        Ok(QueryStream::new(columns, rows).with_messages(self.messages.clone()))
+    }
+
+    /// Execute a query within the transaction and collect its statistics.
+    ///
+    /// See [`Client<Ready>::query_with_stats`] for details.
+    pub async fn query_with_stats<'a>(
+        &'a mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<(QueryStream<'a>, QueryStatistics)> {
+        let (columns, rows, stats) = self.query_stats_response(sql, params).await?;
+        let stream = QueryStream::new(columns, rows).with_messages(self.messages.clone());
+        Ok((stream, stats))
     }
 
-    /// Execute a statement within the transaction.
+    /// Execute a statement within the transaction with a specific timeout.
     ///
-    /// Returns the number of affected rows.
-    pub async fn execute(
+    /// See [`Client<Ready>::execute_with_timeout`] for details.
+    pub async fn execute_with_timeout(
         &mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
+        timeout_duration: std::time::Duration,
     ) -> Result<u64> {
-        tracing::debug!(
-            sql = sql,
-            params_count = params.len(),
-            "executing statement in transaction"
-        );
-
-        #[cfg(feature = "otel")]
-        let instrumentation = self.instrumentation.clone();
-        #[cfg(feature = "otel")]
-        let mut span = instrumentation.query_span(sql);
+        timeout(timeout_duration, self.execute(sql, params))
+            .await
+            .map_err(|_| Error::CommandTimeout)?
+    }
 
-        let result = async {
-            if params.is_empty() {
-                // Simple statement without parameters - use SQL batch
-                self.send_sql_batch(sql).await?;
-            } else {
-                // Parameterized statement - use sp_executesql via RPC
-                let rpc_params = Self::convert_params(params)?;
-                let rpc = RpcRequest::execute_sql(sql, rpc_params);
-                self.send_rpc(&rpc).await?;
-            }
+    /// Execute a statement within the transaction and return per-statement details.
+    ///
+    /// See [`Client<Ready>::execute_detailed`] for details.
+    pub async fn execute_detailed(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<ExecuteResult> {
+        self.execute_statement(sql, params, false).await
+    }
 
-            // Read response and get row count
-            self.read_execute_result().await
-        }
-        .await;
+    /// Execute a statement within the transaction and capture the generated identity.
+    ///
+    /// See [`Client<Ready>::execute_with_identity`] for details.
+    pub async fn execute_with_identity(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<ExecuteResult> {
+        self.execute_statement(sql, params, true).await
+    }
 
-        #[cfg(feature = "otel")]
-        match &result {
-            Ok(rows) => InstrumentationContext::record_success(&mut span, Some(*rows)),
-            Err(e) => InstrumentationContext::record_error(&mut span, e),
-        }
+    /// Execute an INSERT within the transaction and return the generated identity.
+    ///
+    /// See [`Client<Ready>::insert_returning_identity`] for details.
+    pub async fn insert_returning_identity(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+    ) -> Result<Option<i64>> {
+        Ok(self.execute_with_identity(sql, params).await?.last_identity)
+    }
 
-        // Drop the span before returning
-        #[cfg(feature = "otel")]
-        drop(span);
+    /// Create a temporary table within the transaction and insert `rows`
+    /// into it.
+    ///
+    /// See [`Client<Ready>::temp_table`] for details.
+    pub async fn temp_table<T: crate::tvp::Tvp>(
+        &mut self,
+        name: &str,
+        rows: &[T],
+    ) -> Result<TempTable<'_, InTransaction>> {
+        self.create_temp_table(name, rows).await?;
+        TempTable::new(self, name)
+    }
 
-        result
+    /// Insert a slice of rows from a TVP within the transaction.
+    ///
+    /// See [`Client<Ready>::insert_many`] for details.
+    pub async fn insert_many<T: crate::tvp::Tvp>(
+        &mut self,
+        table: &str,
+        rows: &[T],
+    ) -> Result<u64> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        let insert = crate::tvp::insert_select_sql::<T>(table)?;
+        let tvp = crate::tvp::TvpValue::new(rows)?;
+        self.execute(&crate::tvp::create_type_sql::<T>()?, &[])
+            .await?;
+        self.execute(&insert, &[&tvp]).await
     }
 
-    /// Execute a query within the transaction with a specific timeout.
+    /// Execute an INSERT within the transaction with an `OUTPUT INSERTED` clause.
     ///
-    /// See [`Client<Ready>::query_with_timeout`] for details.
-    pub async fn query_with_timeout<'a>(
+    /// See [`Client<Ready>::insert_returning`] for details.
+    pub async fn insert_returning<'a>(
         &'a mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
-        timeout_duration: std::time::Duration,
+        columns: &[&str],
     ) -> Result<QueryStream<'a>> {
-        timeout(timeout_duration, self.query(sql, params))
-            .await
-            .map_err(|_| Error::CommandTimeout)?
+        let sql = crate::returning::with_output_inserted(sql, columns)?;
+        self.query(&sql, params).await
     }
 
-    /// Execute a statement within the transaction with a specific timeout.
+    /// Start a batch of statements executed within the transaction.
     ///
-    /// See [`Client<Ready>::execute_with_timeout`] for details.
-    pub async fn execute_with_timeout(
+    /// See [`Client<Ready>::batch`] for details.
+    pub fn batch(&mut self) -> Batch<'_, InTransaction> {
+        Batch::new(self)
+    }
+
+    /// Inspect the database schema within the transaction.
+    ///
+    /// See [`Client<Ready>::schema`] for details.
+    pub fn schema(&mut self) -> SchemaInspector<'_, InTransaction> {
+        SchemaInspector::new(self)
+    }
+
+    /// Read Change Data Capture data within the transaction.
+    ///
+    /// See [`Client<Ready>::cdc`] for details.
+    pub fn cdc(&mut self) -> ChangeDataCapture<'_, InTransaction> {
+        ChangeDataCapture::new(self)
+    }
+
+    /// Run change tracking queries within the transaction.
+    ///
+    /// See [`Client<Ready>::change_tracking`] for details.
+    pub fn change_tracking(&mut self) -> ChangeTrackingClient<'_, InTransaction> {
+        ChangeTrackingClient::new(self)
+    }
+
+    /// Upsert a slice of rows into a table within the transaction.
+    ///
+    /// See [`Client<Ready>::merge_into`] for details.
+    pub fn merge_into(&mut self, table: &str) -> MergeBuilder<'_, InTransaction> {
+        MergeBuilder::new(self, table)
+    }
+
+    /// Send and receive Service Broker messages within the transaction.
+    ///
+    /// Messages received here go back to the queue if the transaction rolls back.
+    /// See [`Client<Ready>::service_broker`] for details.
+    pub fn service_broker(&mut self) -> ServiceBroker<'_, InTransaction> {
+        ServiceBroker::new(self)
+    }
+
+    /// Execute a sqlcmd-style script within the transaction.
+    ///
+    /// See [`Client<Ready>::execute_script`] for details.
+    pub async fn execute_script(&mut self, script: &Script) -> Result<ScriptResult> {
+        self.run_script(script).await
+    }
+
+    /// Open a server-side cursor within the transaction.
+    ///
+    /// See [`Client<Ready>::open_cursor`] for details.
+    pub async fn open_cursor(
         &mut self,
         sql: &str,
         params: &[&(dyn crate::ToSql + Sync)],
-        timeout_duration: std::time::Duration,
-    ) -> Result<u64> {
-        timeout(timeout_duration, self.execute(sql, params))
-            .await
-            .map_err(|_| Error::CommandTimeout)?
+        options: CursorOptions,
+    ) -> Result<Cursor<'_, InTransaction>> {
+        self.open_cursor_with_options(sql, params, options).await
+    }
+
+    /// Check if the transaction already ended on the server.
+    ///
+    /// This happens when a server error rolls the transaction back, or when
+    /// raw SQL commits or rolls it back. The transaction can then only be
+    /// rolled back; requests sent before that run in auto-commit mode.
+    #[must_use]
+    pub fn is_doomed(&self) -> bool {
+        self.transaction_descriptor == 0
     }
 
     /// Commit the transaction.
     ///
     /// This transitions the client back to `Ready` state.
+    ///
+    /// Fails with [`Error::Transaction`] if the transaction already ended on
+    /// the server, for example after [`Error::TransactionDoomed`].
     pub async fn commit(mut self) -> Result<Client<Ready>> {
         tracing::debug!("committing transaction");
 
+        if self.is_doomed() {
+            return Err(Error::Transaction(
+                "transaction already ended on the server".to_string(),
+            ));
+        }
+
         #[cfg(feature = "otel")]
         let instrumentation = self.instrumentation.clone();
         #[cfg(feature = "otel")]
@@ -3778,10 +7460,22 @@ impl Client<InTransaction> {
             _state: PhantomData,
             connection: self.connection,
             server_version: self.server_version,
-            current_database: self.current_database,
+            session: self.session,
             statement_cache: self.statement_cache,
             transaction_descriptor: 0, // Reset to auto-commit mode
             needs_reset: self.needs_reset,
+            deferred_cleanup: self.deferred_cleanup,
+            abandoned_impersonations: self.abandoned_impersonations,
+            request_max_lob_size: self.request_max_lob_size,
+            request_state: self.request_state,
+            socket_probe: self.socket_probe,
+            broken: self.broken,
+            message_handler: self.message_handler,
+            messages: self.messages,
+            column_encryption: self.column_encryption,
+            connect_timings: self.connect_timings,
+            #[cfg(feature = "always-encrypted")]
+            enclave_package: self.enclave_package,
             #[cfg(feature = "otel")]
             instrumentation: self.instrumentation,
         })
@@ -3789,7 +7483,8 @@ impl Client<InTransaction> {
 
     /// Rollback the transaction.
     ///
-    /// This transitions the client back to `Ready` state.
+    /// This transitions the client back to `Ready` state. If a server error
+    /// already rolled the transaction back, nothing is sent to the server.
     pub async fn rollback(mut self) -> Result<Client<Ready>> {
         tracing::debug!("rolling back transaction");
 
@@ -3798,8 +7493,11 @@ impl Client<InTransaction> {
         #[cfg(feature = "otel")]
         let mut span = instrumentation.transaction_span("ROLLBACK");
 
-        // Execute ROLLBACK TRANSACTION
+        // Execute ROLLBACK TRANSACTION unless the server already rolled back
         let result = async {
+            if self.is_doomed() {
+                return Ok(0);
+            }
             self.send_sql_batch("ROLLBACK TRANSACTION").await?;
             self.read_execute_result().await
         }
@@ -3822,10 +7520,22 @@ impl Client<InTransaction> {
             _state: PhantomData,
             connection: self.connection,
             server_version: self.server_version,
-            current_database: self.current_database,
+            session: self.session,
             statement_cache: self.statement_cache,
             transaction_descriptor: 0, // Reset to auto-commit mode
             needs_reset: self.needs_reset,
+            deferred_cleanup: self.deferred_cleanup,
+            abandoned_impersonations: self.abandoned_impersonations,
+            request_max_lob_size: self.request_max_lob_size,
+            request_state: self.request_state,
+            socket_probe: self.socket_probe,
+            broken: self.broken,
+            message_handler: self.message_handler,
+            messages: self.messages,
+            column_encryption: self.column_encryption,
+            connect_timings: self.connect_timings,
+            #[cfg(feature = "always-encrypted")]
+            enclave_package: self.enclave_package,
             #[cfg(feature = "otel")]
             instrumentation: self.instrumentation,
         })
@@ -3906,26 +7616,111 @@ impl Client<InTransaction> {
     /// See [`Client<Ready>::cancel_handle`] for usage examples.
     #[must_use]
     pub fn cancel_handle(&self) -> crate::cancel::CancelHandle {
-        let connection = self
-            .connection
+        self.connection
             .as_ref()
-            .expect("connection should be present");
-        match connection {
-            ConnectionHandle::Tls(conn) => {
-                crate::cancel::CancelHandle::from_tls(conn.cancel_handle())
-            }
-            ConnectionHandle::TlsPrelogin(conn) => {
-                crate::cancel::CancelHandle::from_tls_prelogin(conn.cancel_handle())
-            }
-            ConnectionHandle::Plain(conn) => {
-                crate::cancel::CancelHandle::from_plain(conn.cancel_handle())
-            }
+            .expect("connection should be present")
+            .cancel_handle()
+    }
+
+    /// Check, without a round trip, whether the connection is known to be
+    /// unusable.
+    ///
+    /// See [`Client<Ready>::is_broken`] for details.
+    #[must_use]
+    pub fn is_broken(&self) -> bool {
+        self.connection_is_broken()
+    }
+
+    /// Check whether a dropped request left response data to drain.
+    ///
+    /// See [`Client<Ready>::needs_drain`] for details.
+    #[must_use]
+    pub fn needs_drain(&self) -> bool {
+        self.request_state != RequestState::Idle
+    }
+
+    /// Drain the response of a dropped request.
+    ///
+    /// See [`Client<Ready>::drain`] for details.
+    pub async fn drain(&mut self) -> Result<()> {
+        self.recover_abandoned_request().await
+    }
+}
+
+/// Validate a user or login name for `EXECUTE AS`.
+///
+/// Unlike [`validate_identifier`], this allows the characters found in
+/// Windows principals (`DOMAIN\user`) and contained users
+/// (`user@contoso.com`); the name is sent as a quoted literal.
+pub(crate) fn validate_principal(name: &str) -> Result<()> {
+    if name.is_empty() || name.chars().count() > 128 {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid principal '{name}': must be 1-128 characters"
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid principal '{}': must not contain control characters",
+            name.escape_debug()
+        )));
+    }
+    Ok(())
+}
+
+/// Maximum length of the `CONTEXT_INFO` value.
+const MAX_CONTEXT_INFO_LEN: usize = 128;
+
+/// Validate and bracket-quote a column or other unqualified name.
+///
+/// Stricter than the public [`quote_identifier`](crate::quote_identifier),
+/// for names in generated DDL.
+pub(crate) fn validated_identifier(name: &str) -> Result<String> {
+    validate_identifier(name)?;
+    Ok(format!("[{name}]"))
+}
+
+/// Build the statement cache key of `sql` prepared with the parameter
+/// declarations `declarations`.
+fn prepared_key(sql: &str, declarations: &str) -> String {
+    if declarations.is_empty() {
+        sql.to_string()
+    } else {
+        format!("{declarations}\n{sql}")
+    }
+}
+
+/// Validate and bracket-quote a possibly schema-qualified object name.
+///
+/// Temporary tables (`#name`, `##name`) are allowed.
+pub(crate) fn quote_object_name(name: &str) -> Result<String> {
+    let parts = name
+        .split('.')
+        .map(|part| {
+            validate_identifier(part.trim_start_matches('#'))?;
+            Ok(format!("[{part}]"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if parts.len() > 3 {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid object name '{name}': too many parts"
+        )));
+    }
+    Ok(parts.join("."))
+}
+
+/// Join the text fragments of a `FOR JSON` result.
+fn join_json_rows(rows: &[crate::row::Row]) -> Result<String> {
+    let mut json = String::new();
+    for row in rows {
+        if let Some(fragment) = row.get::<Option<String>>(0)? {
+            json.push_str(&fragment);
         }
     }
+    Ok(json)
 }
 
 /// Validate an identifier (table name, savepoint name, etc.) to prevent SQL injection.
-fn validate_identifier(name: &str) -> Result<()> {
+pub(crate) fn validate_identifier(name: &str) -> Result<()> {
     use once_cell::sync::Lazy;
     use regex::Regex;
 
@@ -3964,6 +7759,16 @@ impl<S: ConnectionState> std::fmt::Debug for Client<S> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_prepared_key() {
+        assert_eq!(prepared_key("SELECT 1", ""), "SELECT 1");
+        assert_eq!(prepared_key("SELECT @p1", "@p1 int"), "@p1 int\nSELECT @p1");
+        assert_ne!(
+            prepared_key("SELECT @p1", "@p1 int"),
+            prepared_key("SELECT @p1", "@p1 bigint")
+        );
+    }
+
     #[test]
     fn test_validate_identifier_valid() {
         assert!(validate_identifier("my_table").is_ok());
@@ -3972,6 +7777,82 @@ mod tests {
         assert!(validate_identifier("sp_test").is_ok());
     }
 
+    #[test]
+    fn test_validate_principal() {
+        assert!(validate_principal("app_user").is_ok());
+        assert!(validate_principal("CONTOSO\\svc-orders").is_ok());
+        assert!(validate_principal("reader@contoso.com").is_ok());
+        assert!(validate_principal("O'Brien").is_ok());
+        assert!(validate_principal("").is_err());
+        assert!(validate_principal(&"x".repeat(129)).is_err());
+        assert!(validate_principal("user\0").is_err());
+    }
+
+    #[test]
+    fn test_apply_col_info() {
+        use tds_protocol::token::ColumnProperty;
+
+        let mut columns = vec![
+            crate::row::Column::new("OrderId", 0, "Int4"),
+            crate::row::Column::new("Customer", 1, "NVarChar"),
+            crate::row::Column::new("Total", 2, "Int4"),
+        ];
+        let tab_name = TabName {
+            tables: vec![vec!["dbo".to_string(), "Orders".to_string()]],
+        };
+        let property = |column, table, status, base_name: Option<&str>| ColumnProperty {
+            column,
+            table,
+            status,
+            base_name: base_name.map(str::to_string),
+        };
+        let col_info = ColInfo {
+            columns: vec![
+                property(1, 1, ColumnProperty::KEY, None),
+                property(2, 1, ColumnProperty::DIFFERENT_NAME, Some("CustomerName")),
+                property(3, 0, ColumnProperty::EXPRESSION, None),
+            ],
+        };
+
+        Client::<Ready>::apply_col_info(&mut columns, Some(&tab_name), &col_info);
+        assert_eq!(columns[0].base_table.as_deref(), Some("[dbo].[Orders]"));
+        assert_eq!(columns[0].base_column.as_deref(), Some("OrderId"));
+        assert!(columns[0].key);
+        assert_eq!(columns[1].base_column.as_deref(), Some("CustomerName"));
+        assert_eq!(columns[2].base_table, None);
+        assert_eq!(columns[2].base_column, None);
+
+        // Dots inside a part stay inside it
+        let tab_name = TabName {
+            tables: vec![vec!["sales.eu".to_string(), "Order]s.2024".to_string()]],
+        };
+        Client::<Ready>::apply_col_info(&mut columns, Some(&tab_name), &col_info);
+        assert_eq!(
+            columns[0].base_table.as_deref(),
+            Some("[sales.eu].[Order]]s.2024]")
+        );
+    }
+
+    #[test]
+    fn test_join_json_rows() {
+        let columns = vec![crate::row::Column::new(
+            "JSON_F52E2B61-18A1-11d1-B105-00805F49916B",
+            0,
+            "NVarChar",
+        )];
+        let rows: Vec<_> = [Some("[{\"id\":1},"), Some("{\"id\":2}]"), None]
+            .into_iter()
+            .map(|fragment| {
+                let value = fragment.map_or(mssql_types::SqlValue::Null, |f| {
+                    mssql_types::SqlValue::String(f.to_string())
+                });
+                crate::row::Row::from_values(columns.clone(), vec![value])
+            })
+            .collect();
+        assert_eq!(join_json_rows(&rows).unwrap(), r#"[{"id":1},{"id":2}]"#);
+        assert_eq!(join_json_rows(&[]).unwrap(), "");
+    }
+
     #[test]
     fn test_validate_identifier_invalid() {
         assert!(validate_identifier("").is_err());
@@ -3981,6 +7862,259 @@ mod tests {
         assert!(validate_identifier("table;DROP TABLE users").is_err());
     }
 
+    #[tokio::test]
+    async fn test_connect_tcp_falls_back_to_cached_address() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = "dns-cache-fallback.invalid";
+
+        let config = Config::new().with_host(host).with_port(addr.port());
+        assert!(
+            Client::connect_tcp(&config, &mut ConnectTimings::default())
+                .await
+                .is_err()
+        );
+
+        DnsCache::global().insert(host, addr.port(), addr);
+        let stream = Client::connect_tcp(&config, &mut ConnectTimings::default())
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+
+        // Disabled caching does not use the cached address
+        assert!(
+            Client::connect_tcp(
+                &config.clone().dns_caching(false),
+                &mut ConnectTimings::default()
+            )
+            .await
+            .is_err()
+        );
+        DnsCache::global().remove(host, addr.port());
+    }
+
+    #[cfg(not(all(windows, feature = "named-pipes")))]
+    #[tokio::test]
+    async fn test_named_pipe_requires_feature() {
+        let config = Config::new().named_pipe(r"\\.\pipe\sql\query");
+        let result = Client::open_transport(&config, &mut ConnectTimings::default()).await;
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+
+    #[cfg(not(all(windows, feature = "named-pipes")))]
+    #[tokio::test]
+    async fn test_localdb_requires_feature() {
+        let config = Config::new().localdb("MSSQLLocalDB");
+        let result = Client::open_transport(&config, &mut ConnectTimings::default()).await;
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_tcp_through_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let proxy = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 256];
+            let n = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        // The server name is resolved by the proxy, not the client
+        let config = Config::new()
+            .with_host("behind-proxy.invalid")
+            .proxy(crate::ProxyConfig::http_connect("127.0.0.1", addr.port()));
+        let stream = Client::connect_tcp(&config, &mut ConnectTimings::default())
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        assert!(
+            proxy
+                .await
+                .unwrap()
+                .starts_with("CONNECT behind-proxy.invalid:1433 HTTP/1.1\r\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport_skips_tcp_connect() {
+        use crate::transport::BoxedTransport;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // The configured host is unresolvable; only the factory can connect
+        let config = Config::new()
+            .with_host("custom-transport.invalid")
+            .with_transport(move |host: String, port: u16| async move {
+                assert_eq!((host.as_str(), port), ("custom-transport.invalid", 1433));
+                let stream = TcpStream::connect(addr).await?;
+                Ok(Box::new(stream) as BoxedTransport)
+            });
+        let transport = Client::open_transport(&config, &mut ConnectTimings::default())
+            .await
+            .unwrap();
+        assert!(matches!(transport, Transport::Custom(_)));
+        assert!(transport.peer_addr().is_none());
+        listener.accept().await.unwrap();
+    }
+
+    #[test]
+    fn test_negotiated_column_encryption() {
+        use tds_protocol::token::FeatureAck;
+
+        let ack = |version: u8| FeatureExtAck {
+            features: vec![FeatureAck {
+                feature_id: 0x04,
+                data: bytes::Bytes::copy_from_slice(&[version]),
+            }],
+        };
+
+        let version = |requested: Option<u8>, ack: &FeatureExtAck| {
+            negotiated_column_encryption(requested, ack).map(|ack| ack.map(|a| a.version))
+        };
+
+        assert_eq!(version(Some(1), &ack(1)).unwrap(), Some(1));
+        assert_eq!(version(Some(3), &ack(2)).unwrap(), Some(2));
+        assert!(version(Some(1), &ack(2)).is_err());
+        assert!(version(None, &ack(1)).is_err());
+
+        let empty = FeatureExtAck { features: vec![] };
+        assert_eq!(version(Some(1), &empty).unwrap(), None);
+
+        // Version 2 and later name the server's enclave type
+        let vbs = FeatureExtAck {
+            features: vec![FeatureAck {
+                feature_id: 0x04,
+                data: bytes::Bytes::from_static(&[2, 3, b'V', 0, b'B', 0, b'S', 0]),
+            }],
+        };
+        let negotiated = negotiated_column_encryption(Some(2), &vbs)
+            .unwrap()
+            .unwrap();
+        assert_eq!(negotiated.enclave_type.as_deref(), Some("VBS"));
+    }
+
+    #[test]
+    fn test_negotiated_packet_size() {
+        use tds_protocol::token::EnvChangeValue;
+
+        let env = EnvChange {
+            env_type: EnvChangeType::PacketSize,
+            new_value: EnvChangeValue::String("8000".into()),
+            old_value: EnvChangeValue::String("4096".into()),
+        };
+        assert_eq!(negotiated_packet_size(&env), Some(8000));
+
+        let env = EnvChange {
+            env_type: EnvChangeType::Database,
+            new_value: EnvChangeValue::String("8000".into()),
+            old_value: EnvChangeValue::String(String::new()),
+        };
+        assert_eq!(negotiated_packet_size(&env), None);
+    }
+
+    #[test]
+    fn test_process_env_change_session() {
+        use tds_protocol::token::EnvChangeValue;
+
+        let mut session = SessionEnv::default();
+        let mut routing = None;
+        let changes = [
+            (
+                EnvChangeType::Database,
+                EnvChangeValue::String("app".into()),
+            ),
+            (
+                EnvChangeType::Language,
+                EnvChangeValue::String("us_english".into()),
+            ),
+            (
+                EnvChangeType::SqlCollation,
+                EnvChangeValue::Binary(bytes::Bytes::from_static(&[0x09, 0x04, 0xD0, 0x00, 0x34])),
+            ),
+        ];
+        for (env_type, new_value) in changes {
+            let env = EnvChange {
+                env_type,
+                new_value,
+                old_value: EnvChangeValue::String(String::new()),
+            };
+            Client::<Ready>::process_env_change(&env, &mut session, &mut routing);
+        }
+
+        assert_eq!(session.database.as_deref(), Some("app"));
+        assert_eq!(session.language.as_deref(), Some("us_english"));
+        let collation = session.collation.unwrap();
+        assert_eq!(collation.lcid, 0x00D0_0409);
+        assert_eq!(collation.sort_id, 0x34);
+        assert!(routing.is_none());
+    }
+
+    #[test]
+    fn test_process_env_change_routing() {
+        use tds_protocol::token::EnvChangeValue;
+
+        let env = EnvChange {
+            env_type: EnvChangeType::Routing,
+            new_value: EnvChangeValue::Routing {
+                host: "replica.database.windows.net".into(),
+                port: 11000,
+            },
+            old_value: EnvChangeValue::Binary(bytes::Bytes::new()),
+        };
+        let mut session = SessionEnv::default();
+        let mut routing = None;
+        Client::<Ready>::process_env_change(&env, &mut session, &mut routing);
+
+        assert_eq!(
+            routing,
+            Some(("replica.database.windows.net".to_string(), 11000))
+        );
+        assert!(session.database.is_none());
+    }
+
+    #[test]
+    fn test_process_transaction_env_change() {
+        use tds_protocol::token::EnvChangeValue;
+
+        let env = |env_type, new_value| EnvChange {
+            env_type,
+            new_value,
+            old_value: EnvChangeValue::Binary(bytes::Bytes::new()),
+        };
+        let begin = env(
+            EnvChangeType::BeginTransaction,
+            EnvChangeValue::Binary(bytes::Bytes::from_static(&[1, 0, 0, 0, 0, 0, 0, 0])),
+        );
+
+        let mut descriptor = 0;
+        Client::<Ready>::process_transaction_env_change(&begin, &mut descriptor);
+        assert_eq!(descriptor, 1);
+
+        // A connection reset rolls back the open transaction
+        let reset = env(
+            EnvChangeType::ResetConnectionCompletionAck,
+            EnvChangeValue::String(String::new()),
+        );
+        Client::<Ready>::process_transaction_env_change(&reset, &mut descriptor);
+        assert_eq!(descriptor, 0);
+
+        Client::<Ready>::process_transaction_env_change(&begin, &mut descriptor);
+        let rollback = env(
+            EnvChangeType::RollbackTransaction,
+            EnvChangeValue::Binary(bytes::Bytes::new()),
+        );
+        Client::<Ready>::process_transaction_env_change(&rollback, &mut descriptor);
+        assert_eq!(descriptor, 0);
+    }
+
     // ========================================================================
     // PLP (Partially Length-Prefixed) Parsing Tests
     // ========================================================================
@@ -4171,6 +8305,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
 
         let col1 = ColumnData {
@@ -4185,6 +8320,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
 
         let mut buf: &[u8] = &raw_data;
@@ -4242,6 +8378,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
         let col1 = ColumnData {
             name: "col1".to_string(),
@@ -4255,6 +8392,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
         let col2 = col0.clone();
         let col3 = col1.clone();
@@ -4319,6 +8457,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
         let col1 = ColumnData {
             name: "num".to_string(),
@@ -4332,6 +8471,7 @@ mod tests {
                 scale: None,
                 collation: None,
             },
+            encryption: None,
         };
 
         let mut buf: &[u8] = &data;
@@ -1,11 +1,18 @@
 //! Client configuration.
 
+use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use mssql_auth::Credentials;
-use mssql_tls::TlsConfig;
+use mssql_codec::PacketCapture;
+use mssql_tls::{TlsBackend, TlsConfig};
+use mssql_types::TimeZonePolicy;
 use tds_protocol::version::TdsVersion;
 
+use crate::transaction::IsolationLevel;
+use crate::transport::TransportFactory;
+
 /// Configuration for Azure SQL redirect handling.
 ///
 /// Azure SQL Gateway may redirect connections to different backend servers.
@@ -157,6 +164,189 @@ impl TimeoutConfig {
     }
 }
 
+/// TCP socket options applied when the connection's socket is created.
+///
+/// TCP keepalive is on by default so that connections left idle behind a
+/// firewall or NAT gateway that drops silent flows are detected as broken
+/// instead of hanging on the next request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketConfig {
+    /// Disable Nagle's algorithm (`TCP_NODELAY`) (default: true).
+    pub nodelay: bool,
+    /// Idle time before the first keepalive probe is sent; `None` disables
+    /// TCP keepalive (default: 30s).
+    pub keepalive_time: Option<Duration>,
+    /// Time between unanswered keepalive probes; `None` keeps the operating
+    /// system default (default: 1s).
+    pub keepalive_interval: Option<Duration>,
+    /// Unanswered probes before the connection is dropped; `None` keeps the
+    /// operating system default. Ignored on Windows, which always uses 10.
+    pub keepalive_retries: Option<u32>,
+    /// Receive buffer size (`SO_RCVBUF`) in bytes; `None` keeps the
+    /// operating system default.
+    pub recv_buffer_size: Option<u32>,
+    /// Send buffer size (`SO_SNDBUF`) in bytes; `None` keeps the operating
+    /// system default.
+    pub send_buffer_size: Option<u32>,
+    /// Local address to bind the socket to before connecting. Only server
+    /// addresses of the same family are tried when set.
+    pub local_address: Option<IpAddr>,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive_time: Some(Duration::from_secs(30)),
+            keepalive_interval: Some(Duration::from_secs(1)),
+            keepalive_retries: None,
+            recv_buffer_size: None,
+            send_buffer_size: None,
+            local_address: None,
+        }
+    }
+}
+
+impl SocketConfig {
+    /// Create a new socket configuration with defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable `TCP_NODELAY`.
+    #[must_use]
+    pub fn nodelay(mut self, enabled: bool) -> Self {
+        self.nodelay = enabled;
+        self
+    }
+
+    /// Set the idle time before keepalive probes start.
+    #[must_use]
+    pub fn keepalive_time(mut self, time: Duration) -> Self {
+        self.keepalive_time = Some(time);
+        self
+    }
+
+    /// Set the time between keepalive probes.
+    #[must_use]
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Set the number of unanswered keepalive probes tolerated.
+    #[must_use]
+    pub fn keepalive_retries(mut self, retries: u32) -> Self {
+        self.keepalive_retries = Some(retries);
+        self
+    }
+
+    /// Disable TCP keepalive.
+    #[must_use]
+    pub fn no_keepalive(mut self) -> Self {
+        self.keepalive_time = None;
+        self
+    }
+
+    /// Set the socket receive buffer size in bytes.
+    #[must_use]
+    pub fn recv_buffer_size(mut self, size: u32) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the socket send buffer size in bytes.
+    #[must_use]
+    pub fn send_buffer_size(mut self, size: u32) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Bind the socket to a local address before connecting.
+    #[must_use]
+    pub fn local_address(mut self, addr: IpAddr) -> Self {
+        self.local_address = Some(addr);
+        self
+    }
+}
+
+/// Proxy protocol used to tunnel the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProxyKind {
+    /// SOCKS5 (RFC 1928). The proxy resolves the server host name.
+    Socks5,
+    /// HTTP `CONNECT` tunnel.
+    HttpConnect,
+}
+
+/// Outbound proxy the TCP connection is tunneled through.
+///
+/// The tunnel is set up before PreLogin, so TLS and TDS run end to end
+/// between the client and the server.
+#[derive(Clone)]
+pub struct ProxyConfig {
+    /// Proxy protocol.
+    pub kind: ProxyKind,
+    /// Proxy host name or IP address.
+    pub host: String,
+    /// Proxy port.
+    pub port: u16,
+    /// User name for proxy authentication.
+    pub username: Option<String>,
+    /// Password for proxy authentication.
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Tunnel through a SOCKS5 proxy.
+    #[must_use]
+    pub fn socks5(host: impl Into<String>, port: u16) -> Self {
+        Self::new(ProxyKind::Socks5, host.into(), port)
+    }
+
+    /// Tunnel through an HTTP proxy with `CONNECT`.
+    #[must_use]
+    pub fn http_connect(host: impl Into<String>, port: u16) -> Self {
+        Self::new(ProxyKind::HttpConnect, host.into(), port)
+    }
+
+    fn new(kind: ProxyKind, host: String, port: u16) -> Self {
+        Self {
+            kind,
+            host,
+            port,
+            username: None,
+            password: None,
+        }
+    }
+
+    /// Authenticate to the proxy with a user name and password.
+    ///
+    /// SOCKS5 uses username/password authentication (RFC 1929); HTTP sends
+    /// `Proxy-Authorization: Basic`.
+    #[must_use]
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never expose the password in debug output
+        f.debug_struct("ProxyConfig")
+            .field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "[REDACTED]"))
+            .finish()
+    }
+}
+
 /// Retry policy for transient error handling.
 ///
 /// Per ADR-009, the driver can automatically retry operations that fail
@@ -269,6 +459,147 @@ impl RetryPolicy {
     }
 }
 
+/// `SET DEADLOCK_PRIORITY` value: which session is chosen as the victim
+/// when two sessions deadlock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlockPriority {
+    /// Priority -5.
+    Low,
+    /// Priority 0 (the server default).
+    Normal,
+    /// Priority 5.
+    High,
+    /// A numeric priority from -10 to 10; values outside are clamped.
+    Numeric(i8),
+}
+
+impl DeadlockPriority {
+    fn as_sql(self) -> String {
+        match self {
+            Self::Low => "LOW".to_string(),
+            Self::Normal => "NORMAL".to_string(),
+            Self::High => "HIGH".to_string(),
+            Self::Numeric(n) => n.clamp(-10, 10).to_string(),
+        }
+    }
+}
+
+/// Session `SET` options applied after login and after every connection
+/// reset.
+///
+/// Each option left as `None` keeps the server's default for the session.
+/// Applications and SQL Server Management Studio connect with different
+/// defaults for `ARITHABORT` (off and on), and because SET options are part
+/// of the plan cache key, the same query can get a different, sometimes
+/// much slower, plan in the application than in SSMS. Setting `ARITHABORT`
+/// on makes them share plans:
+///
+/// ```rust
+/// use mssql_client::{Config, SessionOptions};
+///
+/// let config = Config::new().session_options(SessionOptions::new().arithabort(true));
+/// ```
+///
+/// A pooled connection is reset (`sp_reset_connection`) before reuse, which
+/// restores the login defaults; the options are then sent again with the
+/// reset, before the next request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionOptions {
+    /// `SET ANSI_NULLS`.
+    pub ansi_nulls: Option<bool>,
+    /// `SET ARITHABORT`.
+    pub arithabort: Option<bool>,
+    /// `SET QUOTED_IDENTIFIER`.
+    pub quoted_identifier: Option<bool>,
+    /// `SET DEADLOCK_PRIORITY`.
+    pub deadlock_priority: Option<DeadlockPriority>,
+    /// `SET LOCK_TIMEOUT`: how long a statement waits for a lock before
+    /// failing with error 1222. The server default waits indefinitely.
+    pub lock_timeout: Option<Duration>,
+    /// Default `SET TRANSACTION ISOLATION LEVEL`.
+    pub isolation_level: Option<IsolationLevel>,
+}
+
+impl SessionOptions {
+    /// Create options that keep every server default.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `ANSI_NULLS`.
+    #[must_use]
+    pub fn ansi_nulls(mut self, on: bool) -> Self {
+        self.ansi_nulls = Some(on);
+        self
+    }
+
+    /// Set `ARITHABORT`.
+    #[must_use]
+    pub fn arithabort(mut self, on: bool) -> Self {
+        self.arithabort = Some(on);
+        self
+    }
+
+    /// Set `QUOTED_IDENTIFIER`.
+    #[must_use]
+    pub fn quoted_identifier(mut self, on: bool) -> Self {
+        self.quoted_identifier = Some(on);
+        self
+    }
+
+    /// Set `DEADLOCK_PRIORITY`.
+    #[must_use]
+    pub fn deadlock_priority(mut self, priority: DeadlockPriority) -> Self {
+        self.deadlock_priority = Some(priority);
+        self
+    }
+
+    /// Set `LOCK_TIMEOUT`, with millisecond precision.
+    #[must_use]
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the default transaction isolation level.
+    #[must_use]
+    pub fn isolation_level(mut self, level: IsolationLevel) -> Self {
+        self.isolation_level = Some(level);
+        self
+    }
+
+    /// Build the batch of `SET` statements, or `None` if no option is set.
+    #[must_use]
+    pub fn to_sql(&self) -> Option<String> {
+        fn on_off(on: bool) -> &'static str {
+            if on { "ON" } else { "OFF" }
+        }
+
+        let mut sql = Vec::new();
+        if let Some(on) = self.ansi_nulls {
+            sql.push(format!("SET ANSI_NULLS {}", on_off(on)));
+        }
+        if let Some(on) = self.arithabort {
+            sql.push(format!("SET ARITHABORT {}", on_off(on)));
+        }
+        if let Some(on) = self.quoted_identifier {
+            sql.push(format!("SET QUOTED_IDENTIFIER {}", on_off(on)));
+        }
+        if let Some(priority) = self.deadlock_priority {
+            sql.push(format!("SET DEADLOCK_PRIORITY {}", priority.as_sql()));
+        }
+        if let Some(timeout) = self.lock_timeout {
+            let millis = timeout.as_millis().min(i32::MAX as u128);
+            sql.push(format!("SET LOCK_TIMEOUT {millis}"));
+        }
+        if let Some(level) = self.isolation_level {
+            sql.push(level.as_sql().to_string());
+        }
+        (!sql.is_empty()).then(|| sql.join("; "))
+    }
+}
+
 /// Configuration for connecting to SQL Server.
 ///
 /// This struct is marked `#[non_exhaustive]` to allow adding new fields
@@ -304,6 +635,16 @@ pub struct Config {
     /// TDS packet size.
     pub packet_size: u16,
 
+    /// Number of payload buffers each direction of the connection keeps for
+    /// reuse (0 disables pooling).
+    pub buffer_pool_size: usize,
+
+    /// Log the header of every TDS packet at `trace` level.
+    pub trace_packets: bool,
+
+    /// Record every TDS packet to a pcap capture for protocol debugging.
+    pub packet_capture: Option<PacketCapture>,
+
     /// Whether to use TDS 8.0 strict mode.
     pub strict_mode: bool,
 
@@ -313,6 +654,26 @@ pub struct Config {
     /// Instance name (for named instances).
     pub instance: Option<String>,
 
+    /// Named pipe path (`\\server\pipe\sql\query`) to connect through
+    /// instead of TCP.
+    ///
+    /// Set by an `np:` server prefix in the connection string. Connecting
+    /// through a pipe requires the `named-pipes` feature on Windows.
+    pub named_pipe: Option<String>,
+
+    /// SQL Server Express LocalDB instance to start and connect to.
+    ///
+    /// Set by a `(localdb)\\instance` server in the connection string. The
+    /// instance's named pipe is looked up at connect time; see the
+    /// [`localdb`](crate::localdb) module.
+    pub localdb: Option<String>,
+
+    /// Factory for caller-supplied streams, used instead of TCP, named pipes
+    /// and LocalDB when set.
+    ///
+    /// See the [`transport`](crate::transport) module.
+    pub transport: Option<Arc<dyn TransportFactory>>,
+
     /// Whether to enable MARS (Multiple Active Result Sets).
     pub mars: bool,
 
@@ -350,6 +711,16 @@ pub struct Config {
     /// Timeout configuration for various connection phases.
     pub timeouts: TimeoutConfig,
 
+    /// TCP socket options (keepalive, `TCP_NODELAY`, buffer sizes, local address).
+    pub socket: SocketConfig,
+
+    /// Proxy to tunnel the TCP connection through (default: none).
+    pub proxy: Option<ProxyConfig>,
+
+    /// Session `SET` options applied after login and after each connection
+    /// reset (default: none).
+    pub session: SessionOptions,
+
     /// Requested TDS protocol version.
     ///
     /// This specifies which TDS protocol version to request during connection.
@@ -363,6 +734,56 @@ pub struct Config {
     ///
     /// Note: When `strict_mode` is enabled, this is ignored and TDS 8.0 is used.
     pub tds_version: TdsVersion,
+
+    /// Send `&str` and `String` parameters as `VARCHAR` in the database
+    /// collation instead of `NVARCHAR`.
+    ///
+    /// Values the collation's code page cannot represent are still sent as
+    /// `NVARCHAR`. Equivalent to `sendStringParametersAsUnicode=false` in
+    /// other drivers.
+    pub varchar_params: bool,
+
+    /// How `DATETIME` and `DATETIME2` values convert into `DateTime<Utc>`
+    /// (default: [`TimeZonePolicy::AssumeUtc`]).
+    pub time_zone: TimeZonePolicy,
+
+    /// Send `IN` lists too long for one statement as a table-valued
+    /// parameter in [`Client::query_in`](crate::Client::query_in) and
+    /// [`Client::execute_in`](crate::Client::execute_in) instead of
+    /// splitting them (default: `false`).
+    ///
+    /// **This creates permanent schema objects:** on first use, a
+    /// one-column table type such as `dbo.MssqlClientIntList` is created in
+    /// the current database, which needs `CREATE TYPE` permission and stays
+    /// after the connection closes.
+    pub in_list_tvp: bool,
+
+    /// Split `IN` lists that cannot be sent as a table-valued parameter
+    /// although [`in_list_tvp`](Self#structfield.in_list_tvp) is enabled,
+    /// instead of failing the query (default: `false`).
+    pub in_list_split_fallback: bool,
+
+    /// Largest value, in bytes, accepted from `(max)`, XML, UDT, `TEXT`,
+    /// `NTEXT` and `IMAGE` columns (default: no limit).
+    ///
+    /// Longer values fail the query with a protocol error instead of being
+    /// decoded. The session also runs `SET TEXTSIZE` one byte above the
+    /// limit, so the server stops sending a `(max)` or legacy LOB value just
+    /// past it; XML and UDT values are not truncated by the server.
+    pub max_lob_size: Option<u64>,
+
+    /// Request Azure SQL DNS caching and fall back to the cached server
+    /// address when DNS fails (default: true).
+    ///
+    /// See the [`dns_cache`](crate::dns_cache) module.
+    pub dns_caching: bool,
+
+    /// Always Encrypted state shared by connections made with this config.
+    ///
+    /// When set, parameters targeting encrypted columns are encrypted
+    /// client-side before being sent.
+    #[cfg(feature = "always-encrypted")]
+    pub column_encryption: Option<Arc<crate::encryption::EncryptionContext>>,
 }
 
 impl Default for Config {
@@ -378,16 +799,33 @@ impl Default for Config {
             connect_timeout: timeouts.connect_timeout,
             command_timeout: timeouts.command_timeout,
             packet_size: 4096,
+            buffer_pool_size: mssql_codec::DEFAULT_POOL_BUFFERS,
+            trace_packets: false,
+            packet_capture: None,
             strict_mode: false,
             trust_server_certificate: false,
             instance: None,
+            named_pipe: None,
+            localdb: None,
+            transport: None,
             mars: false,
             encrypt: true, // Default to encrypted for security
             no_tls: false, // Never plaintext by default
             redirect: RedirectConfig::default(),
             retry: RetryPolicy::default(),
             timeouts,
+            socket: SocketConfig::default(),
+            proxy: None,
+            session: SessionOptions::default(),
             tds_version: TdsVersion::V7_4, // Default to TDS 7.4 for broad compatibility
+            varchar_params: false,
+            time_zone: TimeZonePolicy::default(),
+            in_list_tvp: false,
+            in_list_split_fallback: false,
+            max_lob_size: None,
+            dns_caching: true,
+            #[cfg(feature = "always-encrypted")]
+            column_encryption: None,
         }
     }
 }
@@ -408,21 +846,44 @@ impl Config {
     pub fn from_connection_string(conn_str: &str) -> Result<Self, crate::error::Error> {
         let mut config = Self::default();
 
-        for part in conn_str.split(';') {
+        for (index, part) in conn_str.split(';').enumerate() {
             let part = part.trim();
             if part.is_empty() {
                 continue;
             }
 
-            let (key, value) = part
-                .split_once('=')
-                .ok_or_else(|| crate::error::Error::Config(format!("invalid key-value: {part}")))?;
+            // The part is not echoed: it may be the tail of a password
+            // containing ';'
+            let (key, value) = part.split_once('=').ok_or_else(|| {
+                crate::error::Error::Config(format!(
+                    "invalid key-value pair at position {}: missing '='",
+                    index + 1
+                ))
+            })?;
 
             let key = key.trim().to_lowercase();
             let value = value.trim();
 
             match key.as_str() {
                 "server" | "data source" | "host" => {
+                    // An explicit protocol prefix selects TCP or named pipes
+                    if let Some(pipe) = strip_prefix_ignore_case(value, "np:") {
+                        let (host, path) = crate::transport::named_pipe_path(pipe);
+                        config.host = host;
+                        config.named_pipe = Some(path);
+                        config.localdb = None;
+                        continue;
+                    }
+                    if let Some(instance) = strip_prefix_ignore_case(value, "(localdb)\\") {
+                        config.host = "localhost".to_string();
+                        config.localdb = Some(instance.to_string());
+                        config.named_pipe = None;
+                        continue;
+                    }
+                    let value = strip_prefix_ignore_case(value, "tcp:").unwrap_or(value);
+                    config.named_pipe = None;
+                    config.localdb = None;
+
                     // Handle host:port or host\instance format
                     if let Some((host, port_or_instance)) = value.split_once(',') {
                         config.host = host.to_string();
@@ -447,8 +908,10 @@ impl Config {
                 "user id" | "uid" | "user" => {
                     // Update credentials with new username
                     if let Credentials::SqlServer { password, .. } = &config.credentials {
-                        config.credentials =
-                            Credentials::sql_server(value.to_string(), password.clone());
+                        config.credentials = Credentials::SqlServer {
+                            username: value.to_string().into(),
+                            password: password.clone(),
+                        };
                     }
                 }
                 "password" | "pwd" => {
@@ -478,6 +941,12 @@ impl Config {
                         || value.eq_ignore_ascii_case("yes")
                         || value == "1";
                 }
+                "tlsprovider" | "tls provider" => {
+                    let backend: TlsBackend = value.parse().map_err(|e: mssql_tls::TlsError| {
+                        crate::error::Error::Config(e.to_string())
+                    })?;
+                    config.tls = config.tls.backend(backend);
+                }
                 "encrypt" => {
                     // Handle encryption levels: strict, true, false, yes, no, 1, 0, no_tls
                     if value.eq_ignore_ascii_case("strict") {
@@ -508,6 +977,11 @@ impl Config {
                         || value.eq_ignore_ascii_case("yes")
                         || value == "1";
                 }
+                "sendstringparametersasunicode" | "send string parameters as unicode" => {
+                    config.varchar_params = value.eq_ignore_ascii_case("false")
+                        || value.eq_ignore_ascii_case("no")
+                        || value == "0";
+                }
                 "packet size" => {
                     config.packet_size = value.parse().map_err(|_| {
                         crate::error::Error::Config(format!("invalid packet size: {value}"))
@@ -547,6 +1021,37 @@ impl Config {
         self
     }
 
+    /// Connect through a named pipe instead of TCP.
+    ///
+    /// Requires the `named-pipes` feature on Windows. The host is still used
+    /// as the server name during login and TLS certificate validation.
+    #[must_use]
+    pub fn named_pipe(mut self, path: impl Into<String>) -> Self {
+        self.named_pipe = Some(path.into());
+        self
+    }
+
+    /// Connect to a SQL Server Express LocalDB instance, such as
+    /// `MSSQLLocalDB`.
+    ///
+    /// Requires the `named-pipes` feature on Windows.
+    #[must_use]
+    pub fn localdb(mut self, instance: impl Into<String>) -> Self {
+        self.host = "localhost".to_string();
+        self.localdb = Some(instance.into());
+        self
+    }
+
+    /// Connect over streams opened by `factory` instead of TCP.
+    ///
+    /// The client skips its own TCP connect phase and runs the TDS handshake,
+    /// including TLS unless disabled, over the returned stream.
+    #[must_use]
+    pub fn with_transport(mut self, factory: impl TransportFactory) -> Self {
+        self.transport = Some(Arc::new(factory));
+        self
+    }
+
     /// Set the server port.
     #[must_use]
     pub fn port(mut self, port: u16) -> Self {
@@ -590,6 +1095,64 @@ impl Config {
         self
     }
 
+    /// Select the TLS implementation.
+    ///
+    /// [`TlsBackend::NativeTls`] uses the operating system's TLS stack and
+    /// certificate store (SChannel on Windows) and requires the `native-tls`
+    /// feature. The connection string equivalent is `TlsProvider=native`.
+    #[must_use]
+    pub fn tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.tls = self.tls.backend(backend);
+        self
+    }
+
+    /// Set the number of packet payload buffers kept for reuse.
+    ///
+    /// Pooling cuts allocations when streaming large result sets; each kept
+    /// buffer holds up to one packet. Use 0 to allocate per packet.
+    #[must_use]
+    pub fn buffer_pool_size(mut self, buffers: usize) -> Self {
+        self.buffer_pool_size = buffers;
+        self
+    }
+
+    /// Enable or disable Azure SQL DNS caching.
+    ///
+    /// When enabled (the default), servers that allow it have their address
+    /// cached after login, and a later connection that cannot reach the
+    /// server through DNS retries the cached address. See the
+    /// [`dns_cache`](crate::dns_cache) module.
+    #[must_use]
+    pub fn dns_caching(mut self, enabled: bool) -> Self {
+        self.dns_caching = enabled;
+        self
+    }
+
+    /// Log the header (type, status, length, SPID, packet ID) of every TDS
+    /// packet sent or received at `trace` level.
+    #[must_use]
+    pub fn trace_packets(mut self, enabled: bool) -> Self {
+        self.trace_packets = enabled;
+        self
+    }
+
+    /// Record every TDS packet sent or received to a pcap capture.
+    ///
+    /// Packets are captured after TLS decryption and can be opened in
+    /// Wireshark. Captures contain credentials and data in the clear.
+    ///
+    /// ```rust,ignore
+    /// use mssql_client::{Config, PacketCapture};
+    ///
+    /// let config = Config::from_connection_string(conn_str)?
+    ///     .packet_capture(PacketCapture::create("session.pcap")?);
+    /// ```
+    #[must_use]
+    pub fn packet_capture(mut self, capture: PacketCapture) -> Self {
+        self.packet_capture = Some(capture);
+        self
+    }
+
     /// Enable TDS 8.0 strict mode.
     #[must_use]
     pub fn strict_mode(mut self, enabled: bool) -> Self {
@@ -635,6 +1198,16 @@ impl Config {
         self
     }
 
+    /// Send string parameters as `VARCHAR` by default.
+    ///
+    /// Use this when queries mostly compare against `VARCHAR` columns; wrap
+    /// individual values in [`Varchar`](mssql_types::Varchar) otherwise.
+    #[must_use]
+    pub fn varchar_params(mut self, enabled: bool) -> Self {
+        self.varchar_params = enabled;
+        self
+    }
+
     /// Enable or disable TLS encryption.
     ///
     /// When `true` (default), the connection will use TLS encryption.
@@ -705,6 +1278,21 @@ impl Config {
         self
     }
 
+    /// Enable Always Encrypted with the given key store providers.
+    ///
+    /// The COLUMNENCRYPTION feature is requested at login. Once the server
+    /// acknowledges it, parameterized queries run
+    /// `sp_describe_parameter_encryption` first and encrypt parameters that
+    /// target encrypted columns, and encrypted result columns are decrypted.
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_encryption(mut self, config: crate::encryption::EncryptionConfig) -> Self {
+        self.column_encryption = config
+            .enabled
+            .then(|| Arc::new(crate::encryption::EncryptionContext::new(config)));
+        self
+    }
+
     /// Set the redirect handling configuration.
     #[must_use]
     pub fn redirect(mut self, redirect: RedirectConfig) -> Self {
@@ -742,6 +1330,101 @@ impl Config {
         self.timeouts = timeouts;
         self
     }
+
+    /// Set the TCP socket options.
+    #[must_use]
+    pub fn socket(mut self, socket: SocketConfig) -> Self {
+        self.socket = socket;
+        self
+    }
+
+    /// Tunnel the TCP connection through a SOCKS5 or HTTP `CONNECT` proxy.
+    #[must_use]
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Set the session `SET` options applied after login and after each
+    /// connection reset.
+    #[must_use]
+    pub fn session_options(mut self, session: SessionOptions) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Set how `DATETIME`, `DATETIME2` and `SMALLDATETIME` values, which
+    /// carry no time zone, convert into `DateTime<Utc>`.
+    ///
+    /// Reading them as `NaiveDateTime` is unaffected.
+    #[must_use]
+    pub fn time_zone_policy(mut self, policy: TimeZonePolicy) -> Self {
+        self.time_zone = policy;
+        self
+    }
+
+    /// Enable or disable sending long `IN` lists as a table-valued
+    /// parameter (disabled by default).
+    ///
+    /// **Enabling it creates a table type such as `dbo.MssqlClientIntList`
+    /// in the current database on first use.** The type is permanent and
+    /// needs `CREATE TYPE` permission. Only lists over the 2100-parameter
+    /// limit of a request are rewritten; the length of the SQL text is not
+    /// checked. While disabled, long lists are split into several
+    /// statements; see the [`in_list`](crate::in_list) module.
+    #[must_use]
+    pub fn in_list_tvp(mut self, enabled: bool) -> Self {
+        self.in_list_tvp = enabled;
+        self
+    }
+
+    /// Split long `IN` lists when they cannot be sent as a table-valued
+    /// parameter.
+    ///
+    /// By default such a list fails the query: its values have no table
+    /// type, or the login may not create one. Statements that would change
+    /// meaning when split are rejected either way; see the
+    /// [`in_list`](crate::in_list) module.
+    #[must_use]
+    pub fn in_list_split_fallback(mut self, enabled: bool) -> Self {
+        self.in_list_split_fallback = enabled;
+        self
+    }
+
+    /// Fail queries returning a large value longer than `bytes`.
+    ///
+    /// See [`Config::max_lob_size`](Self#structfield.max_lob_size).
+    #[must_use]
+    pub fn max_lob_size(mut self, bytes: u64) -> Self {
+        self.max_lob_size = Some(bytes);
+        self
+    }
+
+    /// Build the batch run after login and after each connection reset:
+    /// the [`SessionOptions`] and the `SET TEXTSIZE` for
+    /// [`max_lob_size`](Self#structfield.max_lob_size).
+    pub(crate) fn session_sql(&self) -> Option<String> {
+        let text_size = self.max_lob_size.map(text_size_sql);
+        match (self.session.to_sql(), text_size) {
+            (Some(options), Some(text_size)) => Some(format!("{options}; {text_size}")),
+            (options, text_size) => options.or(text_size),
+        }
+    }
+}
+
+/// `SET TEXTSIZE` letting the server send one byte more than `max_lob_size`,
+/// so a longer value is detected instead of silently truncated.
+pub(crate) fn text_size_sql(max_lob_size: u64) -> String {
+    let text_size = max_lob_size.saturating_add(1).min(i32::MAX as u64);
+    format!("SET TEXTSIZE {text_size}")
+}
+
+/// Strip an ASCII prefix regardless of case.
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    value
+        .get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &value[prefix.len()..])
 }
 
 #[cfg(test)]
@@ -760,6 +1443,24 @@ mod tests {
         assert_eq!(config.database, Some("test".to_string()));
     }
 
+    #[test]
+    fn test_connection_string_secrets_redacted() {
+        let config =
+            Config::from_connection_string("Server=localhost;User Id=sa;Password=hunter2-s3cr3t;")
+                .unwrap()
+                .proxy(ProxyConfig::socks5("proxy", 1080).credentials("u", "pr0xy-s3cr3t"));
+        let debug = format!("{config:?}");
+        assert!(debug.contains("sa"));
+        assert!(!debug.contains("hunter2-s3cr3t"));
+        assert!(!debug.contains("pr0xy-s3cr3t"));
+
+        // An unquoted ';' splits the password; the error must not echo it
+        let err = Config::from_connection_string("Server=localhost;Password=abc;s3cr3t-tail;")
+            .unwrap_err();
+        assert!(!err.to_string().contains("s3cr3t-tail"));
+        assert!(!format!("{err:?}").contains("s3cr3t-tail"));
+    }
+
     #[test]
     fn test_connection_string_with_port() {
         let config =
@@ -778,6 +1479,30 @@ mod tests {
         assert_eq!(config.instance, Some("SQLEXPRESS".to_string()));
     }
 
+    #[test]
+    fn test_connection_string_protocol_prefix() {
+        let config = Config::from_connection_string(r"Server=np:\\db01\pipe\sql\query;").unwrap();
+        assert_eq!(config.host, "db01");
+        assert_eq!(config.named_pipe.as_deref(), Some(r"\\db01\pipe\sql\query"));
+
+        let config = Config::from_connection_string(r"Server=NP:.\SQLEXPRESS;").unwrap();
+        assert_eq!(config.host, "localhost");
+        assert_eq!(
+            config.named_pipe.as_deref(),
+            Some(r"\\.\pipe\MSSQL$SQLEXPRESS\sql\query")
+        );
+
+        let config = Config::from_connection_string(r"Server=(LocalDB)\MSSQLLocalDB;").unwrap();
+        assert_eq!(config.host, "localhost");
+        assert_eq!(config.localdb.as_deref(), Some("MSSQLLocalDB"));
+        assert_eq!(config.named_pipe, None);
+
+        let config = Config::from_connection_string("Server=tcp:db01,1444;").unwrap();
+        assert_eq!(config.host, "db01");
+        assert_eq!(config.port, 1444);
+        assert_eq!(config.named_pipe, None);
+    }
+
     #[test]
     fn test_redirect_config_defaults() {
         let config = RedirectConfig::default();
@@ -923,6 +1648,52 @@ mod tests {
         assert_eq!(config.keepalive_interval, None);
     }
 
+    #[test]
+    fn test_socket_config_defaults() {
+        let config = Config::new();
+        assert!(config.socket.nodelay);
+        assert_eq!(config.socket.keepalive_time, Some(Duration::from_secs(30)));
+        assert_eq!(config.socket.local_address, None);
+
+        let socket = SocketConfig::new()
+            .keepalive_retries(3)
+            .send_buffer_size(1 << 20)
+            .no_keepalive();
+        let config = Config::new().socket(socket.clone());
+        assert_eq!(config.socket, socket);
+        assert_eq!(config.socket.keepalive_time, None);
+        assert_eq!(config.socket.keepalive_retries, Some(3));
+    }
+
+    #[test]
+    fn test_session_options_sql() {
+        assert_eq!(SessionOptions::new().to_sql(), None);
+
+        let session = SessionOptions::new()
+            .arithabort(true)
+            .ansi_nulls(true)
+            .deadlock_priority(DeadlockPriority::Numeric(-20))
+            .lock_timeout(Duration::from_secs(5))
+            .isolation_level(IsolationLevel::Snapshot);
+        assert_eq!(
+            session.to_sql().as_deref(),
+            Some(
+                "SET ANSI_NULLS ON; SET ARITHABORT ON; SET DEADLOCK_PRIORITY -10; \
+                 SET LOCK_TIMEOUT 5000; SET TRANSACTION ISOLATION LEVEL SNAPSHOT"
+            )
+        );
+    }
+
+    #[test]
+    fn test_proxy_config_redacts_password() {
+        let proxy = ProxyConfig::socks5("proxy.local", 1080).credentials("svc", "hunter2");
+        let config = Config::new().proxy(proxy);
+        let proxy = config.proxy.as_ref().unwrap();
+        assert_eq!(proxy.kind, ProxyKind::Socks5);
+        assert_eq!(proxy.username.as_deref(), Some("svc"));
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+
     #[test]
     fn test_timeout_config_total_connect() {
         let config = TimeoutConfig::new()
@@ -948,6 +1719,15 @@ mod tests {
         assert_eq!(config.command_timeout, Duration::from_secs(60));
     }
 
+    #[test]
+    fn test_buffer_pool_size() {
+        let config = Config::default();
+        assert_eq!(config.buffer_pool_size, mssql_codec::DEFAULT_POOL_BUFFERS);
+
+        let config = Config::new().buffer_pool_size(0);
+        assert_eq!(config.buffer_pool_size, 0);
+    }
+
     #[test]
     fn test_tds_version_default() {
         let config = Config::default();
@@ -1016,6 +1796,18 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_tls_backend() {
+        let config = Config::from_connection_string("Server=db;TlsProvider=native;").unwrap();
+        assert_eq!(config.tls.backend, TlsBackend::NativeTls);
+
+        let config = Config::new().tls_backend(TlsBackend::NativeTls);
+        assert_eq!(config.tls.backend, TlsBackend::NativeTls);
+        assert_eq!(Config::new().tls.backend, TlsBackend::Rustls);
+
+        assert!(Config::from_connection_string("Server=db;TlsProvider=gnutls;").is_err());
+    }
+
     #[test]
     fn test_connection_string_no_tls() {
         // no_tls should disable TLS entirely
@@ -1051,4 +1843,38 @@ mod tests {
         let config = Config::new().no_tls(true).no_tls(false);
         assert!(!config.no_tls);
     }
+
+    #[test]
+    fn test_max_lob_size_session_sql() {
+        let config = Config::new();
+        assert_eq!(config.max_lob_size, None);
+        assert_eq!(config.session_sql(), None);
+
+        let config = config.max_lob_size(1024 * 1024);
+        assert_eq!(config.session_sql().unwrap(), "SET TEXTSIZE 1048577");
+
+        let config = config.session_options(SessionOptions::new().arithabort(true));
+        assert_eq!(
+            config.session_sql().unwrap(),
+            "SET ARITHABORT ON; SET TEXTSIZE 1048577"
+        );
+
+        // TEXTSIZE is an int
+        assert_eq!(text_size_sql(u64::MAX), "SET TEXTSIZE 2147483647");
+    }
+
+    #[test]
+    fn test_varchar_params() {
+        assert!(!Config::new().varchar_params);
+        assert!(Config::new().varchar_params(true).varchar_params);
+
+        let config =
+            Config::from_connection_string("Server=localhost;SendStringParametersAsUnicode=false")
+                .unwrap();
+        assert!(config.varchar_params);
+        let config =
+            Config::from_connection_string("Server=localhost;SendStringParametersAsUnicode=true")
+                .unwrap();
+        assert!(!config.varchar_params);
+    }
 }
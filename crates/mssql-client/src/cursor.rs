@@ -0,0 +1,463 @@
+//! Server-side cursors.
+//!
+//! A [`Cursor`] is opened with `sp_cursoropen` and read in pages with
+//! `sp_cursorfetch`, so only one page of rows is held in memory at a time.
+//! This suits ETL jobs that page through very large tables without
+//! rewriting their queries with `OFFSET ... FETCH`.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use mssql_client::{CursorOptions, CursorType};
+//!
+//! let mut cursor = client
+//!     .open_cursor(
+//!         "SELECT id, payload FROM events WHERE created_at > @p1",
+//!         &[&since],
+//!         CursorOptions::new().fetch_size(5_000),
+//!     )
+//!     .await?;
+//!
+//! loop {
+//!     let rows = cursor.fetch_next().await?;
+//!     if rows.is_empty() {
+//!         break;
+//!     }
+//!     for row in rows {
+//!         // process row
+//!     }
+//! }
+//!
+//! cursor.close().await?;
+//! ```
+//!
+//! Scrollable cursors ([`CursorType::Static`], [`CursorType::Keyset`],
+//! [`CursorType::Dynamic`]) also support [`Cursor::fetch`] with any
+//! [`FetchDirection`].
+//!
+//! Cursors must be closed with [`Cursor::close`]. A cursor that is dropped
+//! without being closed stays open on the server until the connection is
+//! closed or reset.
+
+use mssql_types::SqlValue;
+use tds_protocol::rpc::RpcRequest;
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::row::{Column, Row};
+use crate::state::ConnectionState;
+
+/// Default number of rows fetched per round trip.
+pub const DEFAULT_FETCH_SIZE: u32 = 1000;
+
+/// The type of server-side cursor, mapped to the `@scrollopt` flags of
+/// `sp_cursoropen`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CursorType {
+    /// Forward-only cursor; rows can only be fetched with [`FetchDirection::Next`].
+    #[default]
+    ForwardOnly,
+    /// Forward-only, read-only cursor with performance optimizations.
+    FastForward,
+    /// Scrollable snapshot of the result set, insensitive to changes.
+    Static,
+    /// Scrollable cursor with fixed membership that sees updates to rows.
+    Keyset,
+    /// Scrollable cursor that sees all changes, including inserts.
+    Dynamic,
+}
+
+impl CursorType {
+    /// Get the `@scrollopt` flag for this cursor type.
+    #[must_use]
+    pub fn scroll_option(self) -> i32 {
+        match self {
+            Self::Keyset => 0x0001,
+            Self::Dynamic => 0x0002,
+            Self::ForwardOnly => 0x0004,
+            Self::Static => 0x0008,
+            Self::FastForward => 0x0010,
+        }
+    }
+
+    /// Get the cursor type from a `@scrollopt` value returned by the server.
+    #[must_use]
+    pub fn from_scroll_option(value: i32) -> Option<Self> {
+        match value & 0x001F {
+            0x0001 => Some(Self::Keyset),
+            0x0002 => Some(Self::Dynamic),
+            0x0004 => Some(Self::ForwardOnly),
+            0x0008 => Some(Self::Static),
+            0x0010 => Some(Self::FastForward),
+            _ => None,
+        }
+    }
+
+    /// Check if the cursor supports fetching in any direction.
+    #[must_use]
+    pub fn is_scrollable(self) -> bool {
+        matches!(self, Self::Static | Self::Keyset | Self::Dynamic)
+    }
+}
+
+/// Cursor concurrency control, mapped to the `@ccopt` flags of `sp_cursoropen`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CursorConcurrency {
+    /// Rows cannot be updated through the cursor.
+    #[default]
+    ReadOnly,
+    /// Rows are locked as they are fetched.
+    ScrollLocks,
+    /// Optimistic concurrency using row versions or checksums.
+    Optimistic,
+    /// Optimistic concurrency comparing all values.
+    OptimisticValues,
+}
+
+impl CursorConcurrency {
+    /// Get the `@ccopt` flag for this concurrency mode.
+    #[must_use]
+    pub fn concurrency_option(self) -> i32 {
+        match self {
+            Self::ReadOnly => 0x0001,
+            Self::ScrollLocks => 0x0002,
+            Self::Optimistic => 0x0004,
+            Self::OptimisticValues => 0x0008,
+        }
+    }
+}
+
+/// Options for opening a server-side cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorOptions {
+    /// Cursor type.
+    pub cursor_type: CursorType,
+    /// Concurrency control.
+    pub concurrency: CursorConcurrency,
+    /// Number of rows fetched per round trip.
+    pub fetch_size: u32,
+}
+
+impl Default for CursorOptions {
+    fn default() -> Self {
+        Self {
+            cursor_type: CursorType::default(),
+            concurrency: CursorConcurrency::default(),
+            fetch_size: DEFAULT_FETCH_SIZE,
+        }
+    }
+}
+
+impl CursorOptions {
+    /// Create default cursor options (forward-only, read-only).
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cursor type.
+    #[must_use]
+    pub fn cursor_type(mut self, cursor_type: CursorType) -> Self {
+        self.cursor_type = cursor_type;
+        self
+    }
+
+    /// Set the concurrency control.
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: CursorConcurrency) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Set the number of rows fetched per round trip.
+    ///
+    /// A value of 0 is treated as 1.
+    #[must_use]
+    pub fn fetch_size(mut self, fetch_size: u32) -> Self {
+        self.fetch_size = fetch_size.max(1);
+        self
+    }
+}
+
+/// Position to fetch from, mapped to the `@fetchtype` of `sp_cursorfetch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchDirection {
+    /// The rows after the current position.
+    Next,
+    /// The rows before the current position.
+    Prior,
+    /// The first rows of the result set.
+    First,
+    /// The last rows of the result set.
+    Last,
+    /// The rows starting at a 1-based row number.
+    Absolute(i32),
+    /// The rows starting at an offset from the current position.
+    Relative(i32),
+}
+
+impl FetchDirection {
+    /// Get the `@fetchtype` flag and `@rownum` value for this direction.
+    #[must_use]
+    pub fn fetch_type(self) -> (i32, i32) {
+        match self {
+            Self::First => (0x0001, 0),
+            Self::Next => (0x0002, 0),
+            Self::Prior => (0x0004, 0),
+            Self::Last => (0x0008, 0),
+            Self::Absolute(row) => (0x0010, row),
+            Self::Relative(offset) => (0x0020, offset),
+        }
+    }
+}
+
+/// Rows and output parameters from a cursor procedure call.
+pub(crate) struct CursorResponse {
+    pub(crate) columns: Vec<Column>,
+    pub(crate) rows: Vec<Row>,
+    /// Output parameter values keyed by parameter ordinal.
+    pub(crate) return_values: Vec<(u16, SqlValue)>,
+}
+
+impl CursorResponse {
+    pub(crate) fn return_value(&self, ordinal: u16) -> Option<&SqlValue> {
+        self.return_values
+            .iter()
+            .find(|(o, _)| *o == ordinal)
+            .map(|(_, v)| v)
+    }
+
+    pub(crate) fn return_int(&self, ordinal: u16) -> Option<i32> {
+        match self.return_value(ordinal) {
+            Some(SqlValue::Int(v)) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// An open server-side cursor.
+///
+/// Created with [`Client::open_cursor`](crate::Client::open_cursor). The
+/// cursor borrows the client mutably, so no other requests can be made on
+/// the connection until it is closed.
+pub struct Cursor<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+    handle: i32,
+    cursor_type: Option<CursorType>,
+    fetch_size: u32,
+    columns: Vec<Column>,
+    row_count: Option<u64>,
+    exhausted: bool,
+    closed: bool,
+}
+
+impl<'a, S: ConnectionState> Cursor<'a, S> {
+    /// Open a cursor from the `sp_cursoropen` response.
+    pub(crate) fn from_open_response(
+        client: &'a mut Client<S>,
+        response: CursorResponse,
+        options: CursorOptions,
+    ) -> Result<Self> {
+        // Ordinals follow the sp_cursoropen parameter order:
+        // @cursor, @stmt, @scrollopt, @ccopt, @rowcount
+        let handle = response.return_int(0).ok_or_else(|| {
+            Error::Protocol("sp_cursoropen did not return a cursor handle".into())
+        })?;
+        let cursor_type = response
+            .return_int(2)
+            .and_then(CursorType::from_scroll_option);
+        let row_count = response
+            .return_int(4)
+            .and_then(|count| u64::try_from(count).ok());
+
+        if cursor_type.is_some_and(|t| t != options.cursor_type) {
+            tracing::debug!(
+                requested = ?options.cursor_type,
+                actual = ?cursor_type,
+                "server converted cursor type"
+            );
+        }
+
+        Ok(Self {
+            client,
+            handle,
+            cursor_type,
+            fetch_size: options.fetch_size,
+            columns: response.columns,
+            row_count,
+            exhausted: false,
+            closed: false,
+        })
+    }
+
+    /// Get the server-assigned cursor handle.
+    #[must_use]
+    pub fn handle(&self) -> i32 {
+        self.handle
+    }
+
+    /// Get the cursor type the server actually opened.
+    ///
+    /// The server may convert the requested type, e.g. to a static cursor
+    /// when the query cannot be served by a keyset cursor.
+    #[must_use]
+    pub fn cursor_type(&self) -> Option<CursorType> {
+        self.cursor_type
+    }
+
+    /// Get the columns of the cursor's result set.
+    ///
+    /// Populated once the first page has been fetched if the server did not
+    /// send metadata when the cursor was opened.
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Get the total number of rows in the cursor, if known.
+    ///
+    /// Only static and keyset cursors report a row count; it is `None` for
+    /// forward-only and dynamic cursors, and for cursors that are still
+    /// being populated asynchronously.
+    #[must_use]
+    pub fn row_count(&self) -> Option<u64> {
+        self.row_count
+    }
+
+    /// Get the number of rows fetched per round trip.
+    #[must_use]
+    pub fn fetch_size(&self) -> u32 {
+        self.fetch_size
+    }
+
+    /// Fetch the next page of rows.
+    ///
+    /// Returns an empty vector once the end of the result set is reached.
+    pub async fn fetch_next(&mut self) -> Result<Vec<Row>> {
+        if self.exhausted {
+            return Ok(Vec::new());
+        }
+        let rows = self.fetch(FetchDirection::Next, self.fetch_size).await?;
+        if rows.is_empty() {
+            self.exhausted = true;
+        }
+        Ok(rows)
+    }
+
+    /// Fetch up to `n_rows` rows from the given position.
+    ///
+    /// Directions other than [`FetchDirection::Next`] require a scrollable
+    /// cursor type.
+    pub async fn fetch(&mut self, direction: FetchDirection, n_rows: u32) -> Result<Vec<Row>> {
+        if self.closed {
+            return Err(Error::Config("cursor is closed".into()));
+        }
+
+        let (fetch_type, row_num) = direction.fetch_type();
+        let n_rows = i32::try_from(n_rows.max(1)).unwrap_or(i32::MAX);
+        let rpc = RpcRequest::cursor_fetch(self.handle, fetch_type, row_num, n_rows);
+        let response = self.client.cursor_rpc(&rpc).await?;
+
+        if !response.columns.is_empty() {
+            self.columns = response.columns;
+        }
+        if direction != FetchDirection::Next {
+            self.exhausted = false;
+        }
+        Ok(response.rows)
+    }
+
+    /// Close the cursor, releasing its server resources.
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+        let rpc = RpcRequest::cursor_close(self.handle);
+        self.client.cursor_rpc(&rpc).await?;
+        tracing::debug!(handle = self.handle, "closed server cursor");
+        Ok(())
+    }
+}
+
+impl<S: ConnectionState> Drop for Cursor<'_, S> {
+    fn drop(&mut self) {
+        if !self.closed {
+            tracing::warn!(
+                handle = self.handle,
+                "server cursor dropped without close(); it remains open until the connection is reset"
+            );
+        }
+    }
+}
+
+impl<S: ConnectionState> std::fmt::Debug for Cursor<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cursor")
+            .field("handle", &self.handle)
+            .field("cursor_type", &self.cursor_type)
+            .field("fetch_size", &self.fetch_size)
+            .field("row_count", &self.row_count)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_type_scroll_option_roundtrip() {
+        for cursor_type in [
+            CursorType::ForwardOnly,
+            CursorType::FastForward,
+            CursorType::Static,
+            CursorType::Keyset,
+            CursorType::Dynamic,
+        ] {
+            let option = cursor_type.scroll_option();
+            assert_eq!(CursorType::from_scroll_option(option), Some(cursor_type));
+            // Server echoes additional flags such as PARAMETERIZED_STMT
+            assert_eq!(
+                CursorType::from_scroll_option(option | 0x1000),
+                Some(cursor_type)
+            );
+        }
+        assert!(CursorType::Keyset.is_scrollable());
+        assert!(!CursorType::ForwardOnly.is_scrollable());
+    }
+
+    #[test]
+    fn test_fetch_direction_fetch_type() {
+        assert_eq!(FetchDirection::Next.fetch_type(), (0x0002, 0));
+        assert_eq!(FetchDirection::Absolute(42).fetch_type(), (0x0010, 42));
+        assert_eq!(FetchDirection::Relative(-5).fetch_type(), (0x0020, -5));
+    }
+
+    #[test]
+    fn test_cursor_options_builder() {
+        let options = CursorOptions::new()
+            .cursor_type(CursorType::Keyset)
+            .concurrency(CursorConcurrency::Optimistic)
+            .fetch_size(0);
+
+        assert_eq!(options.cursor_type, CursorType::Keyset);
+        assert_eq!(options.concurrency.concurrency_option(), 0x0004);
+        assert_eq!(options.fetch_size, 1);
+        assert_eq!(CursorOptions::default().fetch_size, DEFAULT_FETCH_SIZE);
+    }
+
+    #[test]
+    fn test_cursor_response_return_values() {
+        let response = CursorResponse {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            return_values: vec![(0, SqlValue::Int(180_150_003)), (4, SqlValue::Null)],
+        };
+
+        assert_eq!(response.return_int(0), Some(180_150_003));
+        assert_eq!(response.return_int(4), None);
+        assert_eq!(response.return_int(2), None);
+    }
+}
@@ -0,0 +1,588 @@
+//! Server performance counters from the dynamic management views.
+//!
+//! [`Client::diagnostics`](crate::Client::diagnostics) returns a
+//! [`Diagnostics`] inspector that reads `sys.dm_os_wait_stats`,
+//! `sys.dm_exec_query_stats` and `sys.dm_db_index_usage_stats` into typed
+//! rows.
+//!
+//! These views hold counters that only grow from server start (or from the
+//! time a plan was cached), so a single read says little about what the
+//! server is doing now. Each read is returned as a [`Snapshot`], and
+//! [`Snapshot::delta`] subtracts an earlier snapshot to get the activity in
+//! between:
+//!
+//! ```rust,ignore
+//! let before = client.diagnostics().wait_stats().await?;
+//! tokio::time::sleep(Duration::from_secs(60)).await;
+//! let after = client.diagnostics().wait_stats().await?;
+//!
+//! let delta = after.delta(&before);
+//! for wait in delta.rows.iter().take(5) {
+//!     println!("{}: {:?} over {:?}", wait.wait_type, wait.wait_time, delta.elapsed);
+//! }
+//! ```
+//!
+//! Reading the views needs the `VIEW SERVER STATE` permission (`VIEW
+//! SERVER PERFORMANCE STATE` on SQL Server 2022).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::row::Row;
+use crate::state::ConnectionState;
+
+/// Wait types that accumulate while the server is idle, excluded from
+/// [`Diagnostics::wait_stats`].
+pub const BENIGN_WAIT_TYPES: &[&str] = &[
+    "BROKER_EVENTHANDLER",
+    "BROKER_RECEIVE_WAITFOR",
+    "BROKER_TASK_STOP",
+    "BROKER_TO_FLUSH",
+    "BROKER_TRANSMITTER",
+    "CHECKPOINT_QUEUE",
+    "CHKPT",
+    "CLR_AUTO_EVENT",
+    "CLR_MANUAL_EVENT",
+    "CLR_SEMAPHORE",
+    "DIRTY_PAGE_POLL",
+    "DISPATCHER_QUEUE_SEMAPHORE",
+    "FSAGENT",
+    "FT_IFTS_SCHEDULER_IDLE_WAIT",
+    "FT_IFTSHC_MUTEX",
+    "HADR_CLUSAPI_CALL",
+    "HADR_FILESTREAM_IOMGR_IOCOMPLETION",
+    "HADR_LOGCAPTURE_WAIT",
+    "HADR_NOTIFICATION_DEQUEUE",
+    "HADR_TIMER_TASK",
+    "HADR_WORK_QUEUE",
+    "LAZYWRITER_SLEEP",
+    "LOGMGR_QUEUE",
+    "MEMORY_ALLOCATION_EXT",
+    "ONDEMAND_TASK_QUEUE",
+    "PREEMPTIVE_XE_GETTARGETSTATE",
+    "PWAIT_ALL_COMPONENTS_INITIALIZED",
+    "PWAIT_DIRECTLOGCONSUMER_GETNEXT",
+    "QDS_ASYNC_QUEUE",
+    "QDS_CLEANUP_STALE_QUERIES_TASK_MAIN_LOOP_SLEEP",
+    "QDS_PERSIST_TASK_MAIN_LOOP_SLEEP",
+    "QDS_SHUTDOWN_QUEUE",
+    "REDO_THREAD_PENDING_WORK",
+    "REQUEST_FOR_DEADLOCK_SEARCH",
+    "RESOURCE_QUEUE",
+    "SERVER_IDLE_CHECK",
+    "SLEEP_BPOOL_FLUSH",
+    "SLEEP_DBSTARTUP",
+    "SLEEP_DCOMSTARTUP",
+    "SLEEP_MASTERDBREADY",
+    "SLEEP_MASTERMDREADY",
+    "SLEEP_MASTERUPGRADED",
+    "SLEEP_MSDBSTARTUP",
+    "SLEEP_SYSTEMTASK",
+    "SLEEP_TASK",
+    "SLEEP_TEMPDBSTARTUP",
+    "SNI_HTTP_ACCEPT",
+    "SOS_WORK_DISPATCHER",
+    "SP_SERVER_DIAGNOSTICS_SLEEP",
+    "SQLTRACE_BUFFER_FLUSH",
+    "SQLTRACE_INCREMENTAL_FLUSH_SLEEP",
+    "SQLTRACE_WAIT_ENTRIES",
+    "WAIT_FOR_RESULTS",
+    "WAIT_XTP_CKPT_CLOSE",
+    "WAIT_XTP_HOST_WAIT",
+    "WAIT_XTP_OFFLINE_CKPT_NEW_LOG",
+    "WAIT_XTP_RECOVERY",
+    "WAITFOR",
+    "WAITFOR_TASKSHUTDOWN",
+    "XE_DISPATCHER_JOIN",
+    "XE_DISPATCHER_WAIT",
+    "XE_LIVE_TARGET_TVF",
+    "XE_TIMER_EVENT",
+];
+
+const WAIT_STATS_SQL: &str = "\
+SELECT wait_type, waiting_tasks_count, wait_time_ms, max_wait_time_ms, signal_wait_time_ms \
+FROM sys.dm_os_wait_stats \
+WHERE waiting_tasks_count > 0";
+
+const QUERY_STATS_SQL: &str = "\
+SELECT TOP (@p1) CONVERT(VARCHAR(18), qs.query_hash, 1), CONVERT(VARCHAR(130), qs.plan_handle, 1), \
+    qs.statement_start_offset, DB_NAME(t.dbid), \
+    SUBSTRING(t.text, qs.statement_start_offset / 2 + 1, \
+        (CASE qs.statement_end_offset WHEN -1 THEN DATALENGTH(t.text) \
+            ELSE qs.statement_end_offset END - qs.statement_start_offset) / 2 + 1), \
+    qs.execution_count, qs.total_worker_time, qs.total_elapsed_time, \
+    qs.total_logical_reads, qs.total_logical_writes, qs.total_physical_reads, qs.total_rows \
+FROM sys.dm_exec_query_stats qs \
+OUTER APPLY sys.dm_exec_sql_text(qs.sql_handle) t \
+ORDER BY qs.total_worker_time DESC";
+
+const INDEX_USAGE_SQL: &str = "\
+SELECT s.name, o.name, i.name, i.index_id, \
+    ISNULL(u.user_seeks, 0), ISNULL(u.user_scans, 0), \
+    ISNULL(u.user_lookups, 0), ISNULL(u.user_updates, 0) \
+FROM sys.indexes i \
+JOIN sys.objects o ON o.object_id = i.object_id \
+JOIN sys.schemas s ON s.schema_id = o.schema_id \
+LEFT JOIN sys.dm_db_index_usage_stats u \
+    ON u.database_id = DB_ID() AND u.object_id = i.object_id AND u.index_id = i.index_id \
+WHERE o.type = 'U' AND o.is_ms_shipped = 0 \
+ORDER BY s.name, o.name, i.index_id";
+
+/// A row of cumulative counters that can be diffed between snapshots.
+pub trait Counters: Clone {
+    /// Identity of the row across snapshots.
+    type Key: Eq + Hash;
+
+    /// Get the key identifying this row.
+    fn key(&self) -> Self::Key;
+
+    /// Subtract the counters of an earlier reading of the same row.
+    ///
+    /// Returns `None` if a counter went down, meaning the counters were
+    /// reset in between (for example by `DBCC SQLPERF` or a plan
+    /// recompile).
+    fn since(&self, earlier: &Self) -> Option<Self>;
+
+    /// Check whether all counters are zero.
+    fn is_zero(&self) -> bool;
+}
+
+/// Counters read at one point in time.
+#[derive(Debug, Clone)]
+pub struct Snapshot<T> {
+    /// When the snapshot was taken.
+    pub taken_at: Instant,
+    /// The rows read.
+    pub rows: Vec<T>,
+}
+
+/// The change in counters between two snapshots.
+#[derive(Debug, Clone)]
+pub struct Delta<T> {
+    /// Time between the two snapshots.
+    pub elapsed: Duration,
+    /// Rows whose counters changed, in the order of the later snapshot.
+    pub rows: Vec<T>,
+}
+
+impl<T: Counters> Snapshot<T> {
+    /// Get the activity since an `earlier` snapshot.
+    ///
+    /// Rows missing from the earlier snapshot, or whose counters were reset
+    /// since, are counted in full. Rows with no activity are left out.
+    #[must_use]
+    pub fn delta(&self, earlier: &Snapshot<T>) -> Delta<T> {
+        let before: HashMap<T::Key, &T> = earlier.rows.iter().map(|r| (r.key(), r)).collect();
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| match before.get(&row.key()) {
+                Some(old) => row.since(old).unwrap_or_else(|| row.clone()),
+                None => row.clone(),
+            })
+            .filter(|row| !row.is_zero())
+            .collect();
+        Delta {
+            elapsed: self.taken_at.saturating_duration_since(earlier.taken_at),
+            rows,
+        }
+    }
+}
+
+/// Accumulated waits of one type, from `sys.dm_os_wait_stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitStat {
+    /// Wait type, e.g. `PAGEIOLATCH_SH`.
+    pub wait_type: String,
+    /// Number of waits.
+    pub waiting_tasks: i64,
+    /// Total time waited, including signal wait time.
+    pub wait_time: Duration,
+    /// Longest single wait. Not cumulative: a delta keeps the later value.
+    pub max_wait_time: Duration,
+    /// Time between the wait ending and the task getting a CPU.
+    pub signal_wait_time: Duration,
+}
+
+impl WaitStat {
+    /// Get the time spent waiting for the resource itself.
+    #[must_use]
+    pub fn resource_wait_time(&self) -> Duration {
+        self.wait_time.saturating_sub(self.signal_wait_time)
+    }
+}
+
+impl Counters for WaitStat {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self.wait_type.clone()
+    }
+
+    fn since(&self, earlier: &Self) -> Option<Self> {
+        let waiting_tasks = self.waiting_tasks - earlier.waiting_tasks;
+        if waiting_tasks < 0 {
+            return None;
+        }
+        Some(Self {
+            wait_type: self.wait_type.clone(),
+            waiting_tasks,
+            wait_time: self.wait_time.checked_sub(earlier.wait_time)?,
+            max_wait_time: self.max_wait_time,
+            signal_wait_time: self
+                .signal_wait_time
+                .checked_sub(earlier.signal_wait_time)?,
+        })
+    }
+
+    fn is_zero(&self) -> bool {
+        self.waiting_tasks == 0 && self.wait_time.is_zero()
+    }
+}
+
+/// Accumulated statistics of one cached statement, from
+/// `sys.dm_exec_query_stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryStat {
+    /// Hash shared by statements differing only in literal values, as hex.
+    pub query_hash: String,
+    /// Handle of the cached plan, as hex.
+    pub plan_handle: String,
+    /// Byte offset of the statement within its batch.
+    pub statement_start_offset: i32,
+    /// Database the batch was compiled in, if known.
+    pub database: Option<String>,
+    /// Text of the statement, if the batch is still cached.
+    pub statement: Option<String>,
+    /// Number of executions.
+    pub executions: i64,
+    /// CPU time.
+    pub worker_time: Duration,
+    /// Elapsed time.
+    pub elapsed_time: Duration,
+    /// Pages read from the buffer pool.
+    pub logical_reads: i64,
+    /// Pages written to the buffer pool.
+    pub logical_writes: i64,
+    /// Pages read from disk.
+    pub physical_reads: i64,
+    /// Rows returned.
+    pub rows: i64,
+}
+
+impl QueryStat {
+    /// Get the average elapsed time per execution.
+    #[must_use]
+    pub fn avg_elapsed_time(&self) -> Duration {
+        average(self.elapsed_time, self.executions)
+    }
+
+    /// Get the average CPU time per execution.
+    #[must_use]
+    pub fn avg_worker_time(&self) -> Duration {
+        average(self.worker_time, self.executions)
+    }
+}
+
+fn average(total: Duration, count: i64) -> Duration {
+    if count <= 0 {
+        return Duration::ZERO;
+    }
+    u32::try_from(count).map_or_else(
+        |_| Duration::from_secs_f64(total.as_secs_f64() / count as f64),
+        |count| total / count,
+    )
+}
+
+impl Counters for QueryStat {
+    type Key = (String, i32);
+
+    fn key(&self) -> (String, i32) {
+        (self.plan_handle.clone(), self.statement_start_offset)
+    }
+
+    fn since(&self, earlier: &Self) -> Option<Self> {
+        let delta = Self {
+            executions: self.executions - earlier.executions,
+            worker_time: self.worker_time.checked_sub(earlier.worker_time)?,
+            elapsed_time: self.elapsed_time.checked_sub(earlier.elapsed_time)?,
+            logical_reads: self.logical_reads - earlier.logical_reads,
+            logical_writes: self.logical_writes - earlier.logical_writes,
+            physical_reads: self.physical_reads - earlier.physical_reads,
+            rows: self.rows - earlier.rows,
+            ..self.clone()
+        };
+        let counts = [
+            delta.executions,
+            delta.logical_reads,
+            delta.logical_writes,
+            delta.physical_reads,
+            delta.rows,
+        ];
+        counts.iter().all(|&n| n >= 0).then_some(delta)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.executions == 0
+    }
+}
+
+/// How often an index was used since the server started, from
+/// `sys.dm_db_index_usage_stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// Schema name.
+    pub schema: String,
+    /// Table name.
+    pub table: String,
+    /// Index name, or `None` for a heap.
+    pub index: Option<String>,
+    /// Index ID (0 for a heap, 1 for a clustered index).
+    pub index_id: i32,
+    /// Seeks by user queries.
+    pub user_seeks: i64,
+    /// Scans by user queries.
+    pub user_scans: i64,
+    /// Key or RID lookups by user queries.
+    pub user_lookups: i64,
+    /// Inserts, updates and deletes by user queries.
+    pub user_updates: i64,
+}
+
+impl IndexUsage {
+    /// Get the number of user reads (seeks, scans, and lookups).
+    #[must_use]
+    pub fn user_reads(&self) -> i64 {
+        self.user_seeks + self.user_scans + self.user_lookups
+    }
+}
+
+impl Counters for IndexUsage {
+    type Key = (String, String, i32);
+
+    fn key(&self) -> (String, String, i32) {
+        (self.schema.clone(), self.table.clone(), self.index_id)
+    }
+
+    fn since(&self, earlier: &Self) -> Option<Self> {
+        let delta = Self {
+            user_seeks: self.user_seeks - earlier.user_seeks,
+            user_scans: self.user_scans - earlier.user_scans,
+            user_lookups: self.user_lookups - earlier.user_lookups,
+            user_updates: self.user_updates - earlier.user_updates,
+            ..self.clone()
+        };
+        let counts = [
+            delta.user_seeks,
+            delta.user_scans,
+            delta.user_lookups,
+            delta.user_updates,
+        ];
+        counts.iter().all(|&n| n >= 0).then_some(delta)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.user_reads() == 0 && self.user_updates == 0
+    }
+}
+
+/// Reads performance counters through a client.
+///
+/// Created with [`Client::diagnostics`](crate::Client::diagnostics).
+pub struct Diagnostics<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+}
+
+impl<'a, S: ConnectionState> Diagnostics<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self { client }
+    }
+
+    /// Read the server's wait statistics, most waited first.
+    ///
+    /// Wait types in [`BENIGN_WAIT_TYPES`] are left out.
+    pub async fn wait_stats(&mut self) -> Result<Snapshot<WaitStat>> {
+        let excluded = BENIGN_WAIT_TYPES
+            .iter()
+            .map(|w| format!("N'{w}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "{WAIT_STATS_SQL} AND wait_type NOT IN ({excluded}) ORDER BY wait_time_ms DESC"
+        );
+        let taken_at = Instant::now();
+        let rows = self.client.fetch_rows(&sql, &[]).await?;
+        Ok(Snapshot {
+            taken_at,
+            rows: rows.iter().map(wait_stat_from_row).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Read the `top` cached statements using the most CPU time.
+    ///
+    /// Statements outside the top of an earlier snapshot are counted in
+    /// full by [`Snapshot::delta`], so diff snapshots taken with the same
+    /// generous `top`.
+    pub async fn query_stats(&mut self, top: u32) -> Result<Snapshot<QueryStat>> {
+        let top = i64::from(top);
+        let taken_at = Instant::now();
+        let rows = self.client.fetch_rows(QUERY_STATS_SQL, &[&top]).await?;
+        Ok(Snapshot {
+            taken_at,
+            rows: rows
+                .iter()
+                .map(query_stat_from_row)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Read the usage of every index on user tables in the current
+    /// database, including indexes not used since the server started.
+    pub async fn index_usage(&mut self) -> Result<Snapshot<IndexUsage>> {
+        let taken_at = Instant::now();
+        let rows = self.client.fetch_rows(INDEX_USAGE_SQL, &[]).await?;
+        Ok(Snapshot {
+            taken_at,
+            rows: rows
+                .iter()
+                .map(index_usage_from_row)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+fn millis(value: i64) -> Duration {
+    Duration::from_millis(u64::try_from(value).unwrap_or(0))
+}
+
+fn micros(value: i64) -> Duration {
+    Duration::from_micros(u64::try_from(value).unwrap_or(0))
+}
+
+fn wait_stat_from_row(row: &Row) -> Result<WaitStat> {
+    Ok(WaitStat {
+        wait_type: row.get(0)?,
+        waiting_tasks: row.get(1)?,
+        wait_time: millis(row.get(2)?),
+        max_wait_time: millis(row.get(3)?),
+        signal_wait_time: millis(row.get(4)?),
+    })
+}
+
+fn query_stat_from_row(row: &Row) -> Result<QueryStat> {
+    Ok(QueryStat {
+        query_hash: row.get(0)?,
+        plan_handle: row.get(1)?,
+        statement_start_offset: row.get(2)?,
+        database: row.get(3)?,
+        statement: row.get(4)?,
+        executions: row.get(5)?,
+        worker_time: micros(row.get(6)?),
+        elapsed_time: micros(row.get(7)?),
+        logical_reads: row.get(8)?,
+        logical_writes: row.get(9)?,
+        physical_reads: row.get(10)?,
+        rows: row.get(11)?,
+    })
+}
+
+fn index_usage_from_row(row: &Row) -> Result<IndexUsage> {
+    Ok(IndexUsage {
+        schema: row.get(0)?,
+        table: row.get(1)?,
+        index: row.get(2)?,
+        index_id: row.get(3)?,
+        user_seeks: row.get(4)?,
+        user_scans: row.get(5)?,
+        user_lookups: row.get(6)?,
+        user_updates: row.get(7)?,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn wait(wait_type: &str, tasks: i64, ms: u64) -> WaitStat {
+        WaitStat {
+            wait_type: wait_type.into(),
+            waiting_tasks: tasks,
+            wait_time: Duration::from_millis(ms),
+            max_wait_time: Duration::from_millis(ms),
+            signal_wait_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_wait_stats_delta() {
+        let start = Instant::now();
+        let before = Snapshot {
+            taken_at: start,
+            rows: vec![wait("LCK_M_X", 10, 500), wait("PAGEIOLATCH_SH", 4, 40)],
+        };
+        let after = Snapshot {
+            taken_at: start + Duration::from_secs(60),
+            rows: vec![
+                wait("LCK_M_X", 12, 900),
+                wait("PAGEIOLATCH_SH", 4, 40),
+                wait("WRITELOG", 3, 9),
+            ],
+        };
+
+        let delta = after.delta(&before);
+        assert_eq!(delta.elapsed, Duration::from_secs(60));
+        assert_eq!(delta.rows.len(), 2);
+        assert_eq!(delta.rows[0].wait_type, "LCK_M_X");
+        assert_eq!(delta.rows[0].waiting_tasks, 2);
+        assert_eq!(delta.rows[0].wait_time, Duration::from_millis(400));
+        assert_eq!(delta.rows[0].max_wait_time, Duration::from_millis(900));
+        assert_eq!(delta.rows[1], wait("WRITELOG", 3, 9));
+    }
+
+    #[test]
+    fn test_delta_after_reset() {
+        let start = Instant::now();
+        let before = Snapshot {
+            taken_at: start,
+            rows: vec![wait("LCK_M_X", 10, 500)],
+        };
+        let after = Snapshot {
+            taken_at: start + Duration::from_secs(1),
+            rows: vec![wait("LCK_M_X", 2, 30)],
+        };
+        assert_eq!(after.delta(&before).rows, vec![wait("LCK_M_X", 2, 30)]);
+    }
+
+    #[test]
+    fn test_index_usage_delta() {
+        let usage = |seeks| IndexUsage {
+            schema: "dbo".into(),
+            table: "Orders".into(),
+            index: Some("IX_Orders_Customer".into()),
+            index_id: 2,
+            user_seeks: seeks,
+            user_scans: 1,
+            user_lookups: 0,
+            user_updates: 5,
+        };
+        let delta = usage(30).since(&usage(10)).unwrap();
+        assert_eq!(delta.user_seeks, 20);
+        assert_eq!(delta.user_reads(), 20);
+        assert!(!delta.is_zero());
+        assert!(usage(10).since(&usage(10)).unwrap().is_zero());
+        assert_eq!(usage(10).since(&usage(30)), None);
+    }
+
+    #[test]
+    fn test_average() {
+        assert_eq!(average(Duration::from_secs(3), 0), Duration::ZERO);
+        assert_eq!(
+            average(Duration::from_secs(3), 2),
+            Duration::from_millis(1500)
+        );
+    }
+}
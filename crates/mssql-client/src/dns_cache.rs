@@ -0,0 +1,108 @@
+//! Server address cache for Azure SQL DNS caching.
+//!
+//! During Azure SQL failovers the DNS record of a server can be briefly
+//! unresolvable or stale. When a server acknowledges the AZURESQLDNSCACHING
+//! login feature, the client records the address it connected to in the
+//! process-wide [`DnsCache`]. If a later connection to the same host and
+//! port cannot be established through DNS, the cached address is tried
+//! before giving up.
+//!
+//! Caching is controlled with [`Config::dns_caching`](crate::Config::dns_caching)
+//! and is on by default. Servers that do not acknowledge the feature (SQL
+//! Server on-premises) are never cached.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+static GLOBAL: Lazy<DnsCache> = Lazy::new(DnsCache::new);
+
+/// Cache of server addresses keyed by host and port.
+#[derive(Debug, Default)]
+pub struct DnsCache {
+    entries: Mutex<HashMap<(String, u16), SocketAddr>>,
+}
+
+impl DnsCache {
+    /// Create an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the process-wide cache used by [`Client::connect`](crate::Client::connect).
+    #[must_use]
+    pub fn global() -> &'static DnsCache {
+        &GLOBAL
+    }
+
+    /// Get the cached address of a server.
+    #[must_use]
+    pub fn get(&self, host: &str, port: u16) -> Option<SocketAddr> {
+        self.lock().get(&Self::key(host, port)).copied()
+    }
+
+    /// Record the address of a server.
+    pub fn insert(&self, host: &str, port: u16, addr: SocketAddr) {
+        self.lock().insert(Self::key(host, port), addr);
+    }
+
+    /// Forget the address of a server, returning it if it was cached.
+    pub fn remove(&self, host: &str, port: u16) -> Option<SocketAddr> {
+        self.lock().remove(&Self::key(host, port))
+    }
+
+    /// Forget all addresses.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Get the number of cached servers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if no servers are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn key(host: &str, port: u16) -> (String, u16) {
+        (host.to_ascii_lowercase(), port)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, u16), SocketAddr>> {
+        // The map stays consistent even if a holder panicked
+        self.entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_by_host_and_port() {
+        let cache = DnsCache::new();
+        let addr: SocketAddr = "10.0.0.5:1433".parse().unwrap();
+        assert!(cache.is_empty());
+
+        cache.insert("MyServer.database.windows.net", 1433, addr);
+        assert_eq!(cache.get("myserver.database.windows.net", 1433), Some(addr));
+        assert_eq!(cache.get("myserver.database.windows.net", 11000), None);
+        assert_eq!(cache.len(), 1);
+
+        assert_eq!(
+            cache.remove("myserver.database.windows.net", 1433),
+            Some(addr)
+        );
+        assert!(cache.is_empty());
+    }
+}
@@ -27,9 +27,7 @@
 //! let mut key_store = InMemoryKeyStore::new();
 //! key_store.add_key("MyKey", &pem)?;
 //!
-//! let encryption_config = EncryptionConfig::new()
-//!     .with_provider(key_store)
-//!     .build();
+//! let encryption_config = EncryptionConfig::new().with_provider(key_store);
 //!
 //! // Connect with encryption enabled
 //! let config = Config::from_connection_string(conn_str)?
@@ -38,6 +36,42 @@
 //! let client = Client::connect(config).await?;
 //! ```
 //!
+//! ## Parameter Encryption
+//!
+//! With encryption configured, every parameterized query is preceded by a
+//! call to `sp_describe_parameter_encryption`. Parameters that target
+//! encrypted columns are encrypted client-side before the query is sent.
+//! The server only accepts an encrypted parameter whose declared type
+//! matches the column type exactly, so bind values of the column's type
+//! (e.g. `i32` for an `int` column, `String` for `nvarchar`).
+//!
+//! ## Secure Enclaves
+//!
+//! Columns encrypted with enclave-enabled keys support range comparisons,
+//! `LIKE` and other rich computations when the connection attests the
+//! server's enclave:
+//!
+//! ```rust,ignore
+//! use mssql_auth::AttestationProtocol;
+//!
+//! let encryption_config = EncryptionConfig::new()
+//!     .with_provider(key_store)
+//!     .with_enclave_attestation(
+//!         AttestationProtocol::AzureAttestation,
+//!         "https://myattestation.eus.attest.azure.net/attest/SgxEnclave",
+//!     );
+//! ```
+//!
+//! `sp_describe_parameter_encryption` then also returns the enclave's
+//! attestation information. Once verified, an attested session is cached
+//! and the keys the enclave asks for are sent to it with the query.
+//!
+//! ## Result Decryption
+//!
+//! Encrypted result columns are decrypted as rows are read and expose their
+//! plaintext type, so they are read with [`Row::get`](crate::Row::get) like
+//! any other column.
+//!
 //! ## Security Model
 //!
 //! - **Client-only decryption**: SQL Server never sees plaintext data
@@ -45,14 +79,31 @@
 //! - **Key separation**: CMK stays in secure key store, never transmitted
 
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use mssql_auth::{EncryptionError, KeyStoreProvider, KeyStoreProviderRegistry};
+use tds_protocol::crypto::{
+    CekTable, CekTableEntry, CekValue, CryptoMetadata, EncryptionTypeWire,
+    NORMALIZATION_RULE_VERSION,
+};
+use tds_protocol::token::ColMetaData;
 
-use mssql_auth::KeyStoreProvider;
-use tds_protocol::crypto::{CekTable, CekTableEntry, CryptoMetadata, EncryptionTypeWire};
+use crate::row::Row;
 
 #[cfg(feature = "always-encrypted")]
-use mssql_auth::{AeadEncryptor, CekCache, CekCacheKey, EncryptionError};
+use mssql_auth::enclave::cmk_metadata_digest;
 #[cfg(feature = "always-encrypted")]
-use std::sync::Arc;
+use mssql_auth::{
+    AeadEncryptor, AttestationProtocol, AttestationRequest, AttestationVerifier, CekCache,
+    CekCacheKey, CekCacheStats, CekEvictionPolicy, EnclaveKey, EnclaveSession, EnclaveSessionCache,
+};
+#[cfg(feature = "always-encrypted")]
+use std::time::Duration;
+#[cfg(feature = "always-encrypted")]
+use tds_protocol::rpc::{ParamCipherInfo, RpcParam};
+#[cfg(feature = "always-encrypted")]
+use tds_protocol::token::ColumnData;
 
 /// Configuration for Always Encrypted feature.
 #[derive(Default)]
@@ -61,8 +112,25 @@ pub struct EncryptionConfig {
     pub enabled: bool,
     /// Registered key store providers.
     providers: Vec<Box<dyn KeyStoreProvider>>,
+    /// Registry used when no providers are registered, or `None` for the global one.
+    registry: Option<Arc<KeyStoreProviderRegistry>>,
     /// Whether to cache decrypted CEKs for performance.
     pub cache_ceks: bool,
+    /// Time-to-live for cached CEKs, or `None` for the default.
+    #[cfg(feature = "always-encrypted")]
+    cek_cache_ttl: Option<Duration>,
+    /// Maximum number of cached CEKs, or `None` for no limit.
+    #[cfg(feature = "always-encrypted")]
+    cek_cache_max_entries: Option<usize>,
+    /// Which cached CEK to evict when the cache is full.
+    #[cfg(feature = "always-encrypted")]
+    cek_eviction_policy: CekEvictionPolicy,
+    /// Enclave attestation protocol and URL, when enclave computations are enabled.
+    #[cfg(feature = "always-encrypted")]
+    enclave_attestation: Option<(AttestationProtocol, String)>,
+    /// Verifier for enclave attestation evidence.
+    #[cfg(feature = "always-encrypted")]
+    attestation_verifier: Option<Arc<dyn AttestationVerifier>>,
 }
 
 impl EncryptionConfig {
@@ -72,11 +140,26 @@ impl EncryptionConfig {
         Self {
             enabled: true,
             providers: Vec::new(),
+            registry: None,
             cache_ceks: true,
+            #[cfg(feature = "always-encrypted")]
+            cek_cache_ttl: None,
+            #[cfg(feature = "always-encrypted")]
+            cek_cache_max_entries: None,
+            #[cfg(feature = "always-encrypted")]
+            cek_eviction_policy: CekEvictionPolicy::default(),
+            #[cfg(feature = "always-encrypted")]
+            enclave_attestation: None,
+            #[cfg(feature = "always-encrypted")]
+            attestation_verifier: None,
         }
     }
 
-    /// Register a key store provider.
+    /// Register a key store provider for this connection.
+    ///
+    /// As in the .NET driver, providers registered on the connection take
+    /// precedence: when any are registered, the provider registry is not
+    /// consulted.
     pub fn register_provider(&mut self, provider: impl KeyStoreProvider + 'static) {
         self.providers.push(Box::new(provider));
     }
@@ -88,6 +171,22 @@ impl EncryptionConfig {
         self
     }
 
+    /// Use `registry` instead of the global registry when no providers are
+    /// registered on the connection.
+    #[must_use]
+    pub fn with_provider_registry(mut self, registry: Arc<KeyStoreProviderRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// The registry consulted when no providers are registered on the connection.
+    #[must_use]
+    pub fn provider_registry(&self) -> Arc<KeyStoreProviderRegistry> {
+        self.registry
+            .clone()
+            .unwrap_or_else(KeyStoreProviderRegistry::global)
+    }
+
     /// Enable or disable CEK caching.
     #[must_use]
     pub fn with_cek_caching(mut self, enabled: bool) -> Self {
@@ -95,18 +194,72 @@ impl EncryptionConfig {
         self
     }
 
-    /// Get a provider by name.
+    /// Set how long decrypted CEKs stay cached (default: 2 hours).
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_cek_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cek_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Limit the number of cached CEKs (default: unlimited).
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_cek_cache_max_entries(mut self, max_entries: usize) -> Self {
+        self.cek_cache_max_entries = Some(max_entries);
+        self
+    }
+
+    /// Set which cached CEK is evicted when the cache is full.
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_cek_eviction_policy(mut self, policy: CekEvictionPolicy) -> Self {
+        self.cek_eviction_policy = policy;
+        self
+    }
+
+    /// Enable secure enclave computations, attested with `protocol` at `url`.
+    ///
+    /// The COLUMNENCRYPTION feature is then requested with enclave support.
+    /// Attestation evidence is checked by the verifier set with
+    /// [`with_attestation_verifier`](Self::with_attestation_verifier); with
+    /// the `azure-attestation` feature, Azure Attestation tokens are
+    /// verified without one. [`AttestationProtocol::None`] skips attestation
+    /// and is meant for development only.
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_enclave_attestation(
+        mut self,
+        protocol: AttestationProtocol,
+        url: impl Into<String>,
+    ) -> Self {
+        self.enclave_attestation = Some((protocol, url.into()));
+        self
+    }
+
+    /// Set the verifier for enclave attestation evidence.
+    #[cfg(feature = "always-encrypted")]
+    #[must_use]
+    pub fn with_attestation_verifier(
+        mut self,
+        verifier: impl AttestationVerifier + 'static,
+    ) -> Self {
+        self.attestation_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    /// Get a provider registered on the connection by name, ignoring case.
     pub fn get_provider(&self, name: &str) -> Option<&dyn KeyStoreProvider> {
         self.providers
             .iter()
-            .find(|p| p.provider_name() == name)
+            .find(|p| p.provider_name().eq_ignore_ascii_case(name))
             .map(|p| p.as_ref())
     }
 
     /// Check if encryption is ready (enabled and has providers).
     #[must_use]
     pub fn is_ready(&self) -> bool {
-        self.enabled && !self.providers.is_empty()
+        self.enabled && (!self.providers.is_empty() || !self.provider_registry().is_empty())
     }
 }
 
@@ -116,7 +269,7 @@ impl std::fmt::Debug for EncryptionConfig {
             .field("enabled", &self.enabled)
             .field("provider_count", &self.providers.len())
             .field("cache_ceks", &self.cache_ceks)
-            .finish()
+            .finish_non_exhaustive()
     }
 }
 
@@ -126,28 +279,62 @@ impl std::fmt::Debug for EncryptionConfig {
 /// including resolved CEKs and encryptors.
 #[cfg(feature = "always-encrypted")]
 pub struct EncryptionContext {
-    /// Key store providers by name.
-    providers: HashMap<String, Box<dyn KeyStoreProvider>>,
+    /// Key store providers registered on the connection, by upper-cased name.
+    providers: HashMap<String, Arc<dyn KeyStoreProvider>>,
+    /// Registry used when no providers are registered on the connection.
+    registry: Arc<KeyStoreProviderRegistry>,
     /// Cache for decrypted CEKs.
     cek_cache: CekCache,
     /// Whether caching is enabled.
     cache_enabled: bool,
+    /// Enclave attestation protocol and URL, when enclave computations are enabled.
+    enclave_attestation: Option<(AttestationProtocol, String)>,
+    /// Verifier for enclave attestation evidence.
+    attestation_verifier: Option<Arc<dyn AttestationVerifier>>,
+    /// Attested enclave sessions by server and database.
+    enclave_sessions: EnclaveSessionCache,
 }
 
 #[cfg(feature = "always-encrypted")]
 impl EncryptionContext {
     /// Create a new encryption context from configuration.
     pub fn new(config: EncryptionConfig) -> Self {
+        let registry = config.provider_registry();
         let providers = config
             .providers
             .into_iter()
-            .map(|p| (p.provider_name().to_string(), p))
+            .map(|p| (p.provider_name().to_ascii_uppercase(), Arc::from(p)))
             .collect();
 
+        #[cfg(feature = "azure-attestation")]
+        let attestation_verifier = config.attestation_verifier.or_else(|| {
+            matches!(
+                config.enclave_attestation,
+                Some((AttestationProtocol::AzureAttestation, _))
+            )
+            .then(|| {
+                Arc::new(mssql_auth::AzureAttestationVerifier::new())
+                    as Arc<dyn AttestationVerifier>
+            })
+        });
+        #[cfg(not(feature = "azure-attestation"))]
+        let attestation_verifier = config.attestation_verifier;
+
+        let mut cek_cache =
+            CekCache::with_ttl(config.cek_cache_ttl.unwrap_or(CekCache::DEFAULT_TTL))
+                .with_eviction_policy(config.cek_eviction_policy);
+        if let Some(max_entries) = config.cek_cache_max_entries {
+            cek_cache = cek_cache.with_max_entries(max_entries);
+        }
+
         Self {
             providers,
-            cek_cache: CekCache::new(),
+            registry,
+            cek_cache,
             cache_enabled: config.cache_ceks,
+            enclave_attestation: config.enclave_attestation,
+            attestation_verifier,
+            enclave_sessions: EnclaveSessionCache::new(),
         }
     }
 
@@ -180,15 +367,11 @@ impl EncryptionContext {
             .ok_or_else(|| EncryptionError::CekDecryptionFailed("No CEK value available".into()))?;
 
         // Find the appropriate key store provider
-        let provider = self
-            .providers
-            .get(&cek_value.key_store_provider_name)
-            .ok_or_else(|| {
-                EncryptionError::KeyStoreNotFound(cek_value.key_store_provider_name.clone())
-            })?;
+        let provider = self.provider(&cek_value.key_store_provider_name)?;
 
         // Decrypt the CEK
-        let decrypted_cek = provider
+        #[allow(unused_mut)]
+        let mut decrypted_cek = provider
             .decrypt_cek(
                 &cek_value.cmk_path,
                 &cek_value.encryption_algorithm,
@@ -198,10 +381,14 @@ impl EncryptionContext {
 
         // Create encryptor and cache it
         if self.cache_enabled {
-            self.cek_cache.insert(cache_key, decrypted_cek)
+            self.cek_cache
+                .insert_for_cmk(cache_key, &cek_value.cmk_path, decrypted_cek)
         } else {
             // Create encryptor without caching
-            Ok(Arc::new(AeadEncryptor::new(&decrypted_cek)?))
+            let encryptor = AeadEncryptor::new(&decrypted_cek);
+            #[cfg(feature = "zeroize")]
+            zeroize::Zeroize::zeroize(&mut decrypted_cek);
+            Ok(Arc::new(encryptor?))
         }
     }
 
@@ -250,9 +437,263 @@ impl EncryptionContext {
         self.cek_cache.clear();
     }
 
-    /// Check if a provider is registered.
+    /// Remove cached CEKs protected by the Column Master Key at `cmk_path`.
+    ///
+    /// Call this after rotating or revoking a CMK so its CEKs are decrypted
+    /// again on next use. Returns the number of entries removed.
+    pub fn invalidate_cmk(&self, cmk_path: &str) -> usize {
+        self.cek_cache.invalidate_cmk(cmk_path)
+    }
+
+    /// CEK cache hit, miss and eviction counters.
+    pub fn cek_cache_stats(&self) -> CekCacheStats {
+        self.cek_cache.stats()
+    }
+
+    /// The enclave attestation protocol and URL, if enclave computations
+    /// are enabled.
+    pub fn enclave_attestation(&self) -> Option<(AttestationProtocol, &str)> {
+        self.enclave_attestation
+            .as_ref()
+            .map(|(protocol, url)| (*protocol, url.as_str()))
+    }
+
+    /// Forget all attested enclave sessions.
+    ///
+    /// The next query that needs the enclave attests it again.
+    pub fn clear_enclave_sessions(&self) {
+        self.enclave_sessions.clear();
+    }
+
+    /// Get the cached enclave session for `server`, if any.
+    pub(crate) fn enclave_session(&self, server: &str) -> Option<Arc<EnclaveSession>> {
+        self.enclave_sessions.get(server)
+    }
+
+    /// Start attesting the enclave of a server.
+    ///
+    /// Returns `None` when enclave computations are not enabled.
+    pub(crate) fn attestation_request(
+        &self,
+    ) -> Result<Option<AttestationRequest>, EncryptionError> {
+        self.enclave_attestation
+            .as_ref()
+            .map(|(protocol, url)| AttestationRequest::new(*protocol, url.clone()))
+            .transpose()
+    }
+
+    /// Verify the attestation information returned by the server and cache
+    /// the resulting enclave session for `server`.
+    pub(crate) async fn establish_enclave_session(
+        &self,
+        server: &str,
+        request: AttestationRequest,
+        attestation_info: &[u8],
+    ) -> Result<Arc<EnclaveSession>, EncryptionError> {
+        let session = request
+            .establish_session(attestation_info, self.attestation_verifier.as_deref())
+            .await?;
+        Ok(self.enclave_sessions.insert(server, session))
+    }
+
+    /// Build the enclave package carrying the keys the enclave requested
+    /// for `sql`.
+    ///
+    /// Each key's CMK must be signed for enclave computations; keys are
+    /// decrypted with their key store provider and never cached in
+    /// plaintext. Returns `None` when the enclave requested no keys.
+    pub(crate) async fn enclave_package(
+        &self,
+        session: &EnclaveSession,
+        info: &ParameterEncryptionInfo,
+        sql: &str,
+    ) -> Result<Option<Vec<u8>>, EncryptionError> {
+        if info.enclave_ceks.is_empty() {
+            return Ok(None);
+        }
+
+        let mut keys = Vec::with_capacity(info.enclave_ceks.len());
+        for &ordinal in &info.enclave_ceks {
+            let cek_entry = info.cek_table.get(ordinal).ok_or_else(|| {
+                EncryptionError::MetadataNotAvailable(format!("no CEK at ordinal {ordinal}"))
+            })?;
+            let cek_value = cek_entry.primary_value().ok_or_else(|| {
+                EncryptionError::CekDecryptionFailed("No CEK value available".into())
+            })?;
+            let provider = self.provider(&cek_value.key_store_provider_name)?;
+
+            let signature = info
+                .cmk_signatures
+                .get(&cek_value.cmk_path)
+                .ok_or_else(|| {
+                    EncryptionError::AttestationFailed(format!(
+                        "column master key {} is not enabled for enclave computations",
+                        cek_value.cmk_path
+                    ))
+                })?;
+            let digest = cmk_metadata_digest(
+                &cek_value.key_store_provider_name,
+                &cek_value.cmk_path,
+                true,
+            );
+            if !provider
+                .verify_signature(&cek_value.cmk_path, &digest, signature)
+                .await?
+            {
+                return Err(EncryptionError::AttestationFailed(format!(
+                    "invalid enclave signature for column master key {}",
+                    cek_value.cmk_path
+                )));
+            }
+
+            let key = provider
+                .decrypt_cek(
+                    &cek_value.cmk_path,
+                    &cek_value.encryption_algorithm,
+                    &cek_value.encrypted_value,
+                )
+                .await?;
+            keys.push(EnclaveKey {
+                database_id: cek_entry.database_id,
+                cek_id: cek_entry.cek_id,
+                cek_md_version: cek_entry.cek_md_version,
+                key,
+            });
+        }
+
+        session.package(sql, &keys).map(Some)
+    }
+
+    /// Find the key store provider for `name`, ignoring case.
+    ///
+    /// Providers registered on the connection are used exclusively when
+    /// there are any; otherwise the provider registry is consulted.
+    fn provider(&self, name: &str) -> Result<Arc<dyn KeyStoreProvider>, EncryptionError> {
+        let provider = if self.providers.is_empty() {
+            self.registry.get(name)
+        } else {
+            self.providers.get(&name.to_ascii_uppercase()).cloned()
+        };
+        provider.ok_or_else(|| EncryptionError::KeyStoreNotFound(name.to_string()))
+    }
+
+    /// Check if a provider is available for `name`.
     pub fn has_provider(&self, name: &str) -> bool {
-        self.providers.contains_key(name)
+        self.provider(name).is_ok()
+    }
+
+    /// Resolve the keys needed to decrypt a result set.
+    ///
+    /// Returns `None` when the result set has no encrypted columns.
+    pub(crate) async fn column_decryptor(
+        &self,
+        meta: &ColMetaData,
+    ) -> Result<Option<ColumnDecryptor>, EncryptionError> {
+        let Some(info) = ResultSetEncryptionInfo::from_metadata(meta) else {
+            return Ok(None);
+        };
+
+        let mut columns = Vec::with_capacity(meta.columns.len());
+        for (ordinal, column) in meta.columns.iter().enumerate() {
+            let decrypt = match info.get_cek_for_column(ordinal) {
+                Some(cek_entry) => Some((
+                    self.get_encryptor(cek_entry).await?,
+                    column.plaintext_column(),
+                )),
+                None if info.is_column_encrypted(ordinal) => {
+                    return Err(EncryptionError::MetadataNotAvailable(format!(
+                        "no CEK for encrypted column {}",
+                        column.name
+                    )));
+                }
+                None => None,
+            };
+            columns.push(decrypt);
+        }
+
+        Ok(Some(ColumnDecryptor { columns }))
+    }
+
+    /// Encrypt the parameters described by `info`.
+    ///
+    /// Parameters not listed in `info` are returned unchanged. Encrypted
+    /// parameters are normalized, encrypted with their column's CEK and
+    /// replaced by `varbinary` ciphertext carrying the original type in
+    /// their cipher info.
+    pub async fn encrypt_parameters(
+        &self,
+        info: &ParameterEncryptionInfo,
+        params: Vec<RpcParam>,
+    ) -> Result<Vec<RpcParam>, EncryptionError> {
+        let mut encrypted = Vec::with_capacity(params.len());
+
+        for param in params {
+            let Some(crypto) = info.get_parameter(&param.name) else {
+                encrypted.push(param);
+                continue;
+            };
+
+            let cek_entry = info.cek_table.get(crypto.cek_ordinal).ok_or_else(|| {
+                EncryptionError::MetadataNotAvailable(format!(
+                    "no CEK at ordinal {} for parameter {}",
+                    crypto.cek_ordinal, param.name
+                ))
+            })?;
+
+            let ciphertext = match normalize_parameter(&param) {
+                Some(plaintext) => Some(Bytes::from(
+                    self.encrypt_value(&plaintext, cek_entry, crypto.encryption_type)
+                        .await?,
+                )),
+                None => None,
+            };
+
+            let cipher_info = ParamCipherInfo {
+                type_info: param.type_info,
+                algorithm_id: crypto.algorithm_id,
+                encryption_type: crypto.encryption_type.to_u8(),
+                database_id: cek_entry.database_id,
+                cek_id: cek_entry.cek_id,
+                cek_version: cek_entry.cek_version,
+                cek_md_version: cek_entry.cek_md_version,
+                normalization_version: crypto.normalization_version,
+            };
+
+            let mut param_out = RpcParam::encrypted(param.name, ciphertext, cipher_info);
+            param_out.flags.by_ref = param.flags.by_ref;
+            encrypted.push(param_out);
+        }
+
+        Ok(encrypted)
+    }
+}
+
+/// Serialize a parameter value the way the server normalizes it before
+/// encryption (normalization rule version 1).
+///
+/// Integer and bit values are widened to eight bytes; every other type is
+/// encrypted in its TDS value encoding. Returns `None` for NULL.
+#[cfg(feature = "always-encrypted")]
+fn normalize_parameter(param: &RpcParam) -> Option<Vec<u8>> {
+    let value = param.value.as_ref()?;
+
+    match param.type_info.type_id {
+        // INTNTYPE / BITNTYPE
+        0x26 | 0x68 => {
+            let widened = match value.len() {
+                1 => i64::from(value[0]),
+                2 => i64::from(i16::from_le_bytes([value[0], value[1]])),
+                4 => i64::from(i32::from_le_bytes([value[0], value[1], value[2], value[3]])),
+                _ => {
+                    let mut bytes = [0u8; 8];
+                    let len = value.len().min(8);
+                    bytes[..len].copy_from_slice(&value[..len]);
+                    i64::from_le_bytes(bytes)
+                }
+            };
+            Some(widened.to_le_bytes().to_vec())
+        }
+        _ => Some(value.to_vec()),
     }
 }
 
@@ -263,10 +704,158 @@ impl std::fmt::Debug for EncryptionContext {
             .field("providers", &self.providers.keys().collect::<Vec<_>>())
             .field("cache_entries", &self.cek_cache.len())
             .field("cache_enabled", &self.cache_enabled)
+            .field("enclave_attestation", &self.enclave_attestation)
+            .field("enclave_sessions", &self.enclave_sessions.len())
             .finish()
     }
 }
 
+/// Decrypts the Always Encrypted columns of one result set.
+///
+/// Keys are resolved once per result set, so rows decrypt synchronously.
+#[cfg(feature = "always-encrypted")]
+pub(crate) struct ColumnDecryptor {
+    /// Encryptor and plaintext column description of each encrypted column.
+    columns: Vec<Option<(Arc<AeadEncryptor>, ColumnData)>>,
+}
+
+#[cfg(feature = "always-encrypted")]
+impl ColumnDecryptor {
+    /// Replace the ciphertext in a row with the decrypted values.
+    pub(crate) fn decrypt_row(&self, row: Row) -> crate::error::Result<Row> {
+        use mssql_types::SqlValue;
+
+        let values = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(ordinal, decrypt)| {
+                let value = row.get_raw(ordinal).unwrap_or(SqlValue::Null);
+                let Some((encryptor, column)) = decrypt else {
+                    return Ok(value);
+                };
+                match value {
+                    SqlValue::Null => Ok(SqlValue::Null),
+                    SqlValue::Binary(ciphertext) => {
+                        let plaintext = encryptor.decrypt(&ciphertext)?;
+                        decode_plaintext(&plaintext, column)
+                    }
+                    other => Err(EncryptionError::DecryptionFailed(format!(
+                        "expected ciphertext in column {}, got {}",
+                        column.name,
+                        other.type_name()
+                    ))
+                    .into()),
+                }
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+
+        Ok(Row::from_shared_values(Arc::clone(row.metadata()), values))
+    }
+}
+
+/// Without the `always-encrypted` feature no decryptor can be built.
+#[cfg(not(feature = "always-encrypted"))]
+pub(crate) enum ColumnDecryptor {}
+
+#[cfg(not(feature = "always-encrypted"))]
+impl ColumnDecryptor {
+    pub(crate) fn decrypt_row(&self, _row: Row) -> crate::error::Result<Row> {
+        match *self {}
+    }
+}
+
+/// Decode a decrypted value of the given plaintext column.
+///
+/// The plaintext is the normalized value without its length prefix, so it
+/// is framed the way it would appear in a row and parsed as such.
+#[cfg(feature = "always-encrypted")]
+fn decode_plaintext(
+    plaintext: &[u8],
+    column: &ColumnData,
+) -> crate::error::Result<mssql_types::SqlValue> {
+    use tds_protocol::types::TypeId;
+
+    let too_short = || {
+        EncryptionError::DecryptionFailed(format!(
+            "decrypted value of column {} is too short",
+            column.name
+        ))
+    };
+    let prefix_u8 = |value: &[u8]| -> crate::error::Result<Vec<u8>> {
+        let len = u8::try_from(value.len()).map_err(|_| too_short())?;
+        let mut framed = Vec::with_capacity(value.len() + 1);
+        framed.push(len);
+        framed.extend_from_slice(value);
+        Ok(framed)
+    };
+
+    let framed = match column.type_id {
+        // Integers are normalized to eight bytes
+        TypeId::IntN | TypeId::BitN => {
+            let len = match column.type_id {
+                TypeId::BitN => 1,
+                _ => column.type_info.max_length.unwrap_or(8) as usize,
+            };
+            prefix_u8(plaintext.get(..len).ok_or_else(too_short)?)?
+        }
+        TypeId::Int1 | TypeId::Bit => plaintext.get(..1).ok_or_else(too_short)?.to_vec(),
+        TypeId::Int2 => plaintext.get(..2).ok_or_else(too_short)?.to_vec(),
+        TypeId::Int4 => plaintext.get(..4).ok_or_else(too_short)?.to_vec(),
+        TypeId::Int8
+        | TypeId::Float4
+        | TypeId::Float8
+        | TypeId::Money
+        | TypeId::Money4
+        | TypeId::DateTime
+        | TypeId::DateTime4 => plaintext.to_vec(),
+        TypeId::FloatN
+        | TypeId::MoneyN
+        | TypeId::DateTimeN
+        | TypeId::Guid
+        | TypeId::Decimal
+        | TypeId::Numeric
+        | TypeId::DecimalN
+        | TypeId::NumericN
+        | TypeId::Date
+        | TypeId::Time
+        | TypeId::DateTime2
+        | TypeId::DateTimeOffset => prefix_u8(plaintext)?,
+        TypeId::BigVarChar
+        | TypeId::BigChar
+        | TypeId::NVarChar
+        | TypeId::NChar
+        | TypeId::BigVarBinary
+        | TypeId::BigBinary => {
+            if column.type_info.max_length == Some(0xFFFF) {
+                // MAX types are PLP: total length, one chunk, terminator
+                let mut framed = Vec::with_capacity(plaintext.len() + 16);
+                framed.extend_from_slice(&(plaintext.len() as u64).to_le_bytes());
+                framed.extend_from_slice(&(plaintext.len() as u32).to_le_bytes());
+                framed.extend_from_slice(plaintext);
+                framed.extend_from_slice(&0u32.to_le_bytes());
+                framed
+            } else {
+                let len = u16::try_from(plaintext.len()).map_err(|_| too_short())?;
+                let mut framed = Vec::with_capacity(plaintext.len() + 2);
+                framed.extend_from_slice(&len.to_le_bytes());
+                framed.extend_from_slice(plaintext);
+                framed
+            }
+        }
+        other => {
+            return Err(EncryptionError::UnsupportedOperation(format!(
+                "decrypting {other:?} column {}",
+                column.name
+            ))
+            .into());
+        }
+    };
+
+    let mut buf = framed.as_slice();
+    crate::client::Client::<crate::state::Ready>::parse_column_value(&mut buf, column)
+}
+
 /// Column encryption metadata for a result set.
 ///
 /// This combines the CEK table with per-column crypto metadata,
@@ -288,6 +877,25 @@ impl ResultSetEncryptionInfo {
         }
     }
 
+    /// Build encryption info from COLMETADATA.
+    ///
+    /// Returns `None` unless the metadata carries a CEK table and at least
+    /// one encrypted column.
+    pub fn from_metadata(meta: &ColMetaData) -> Option<Self> {
+        let cek_table = meta.cek_table.as_ref()?;
+        if !meta.columns.iter().any(|c| c.is_encrypted()) {
+            return None;
+        }
+
+        let mut info = Self::new(cek_table.clone(), meta.columns.len());
+        for (ordinal, column) in meta.columns.iter().enumerate() {
+            if let Some(encryption) = &column.encryption {
+                info.set_column_crypto(ordinal, encryption.crypto.clone());
+            }
+        }
+        Some(info)
+    }
+
     /// Set crypto metadata for a column.
     pub fn set_column_crypto(&mut self, ordinal: usize, metadata: CryptoMetadata) {
         if ordinal < self.column_crypto.len() {
@@ -328,6 +936,10 @@ pub struct ParameterEncryptionInfo {
     pub cek_table: CekTable,
     /// Mapping from parameter name to crypto metadata.
     pub parameters: HashMap<String, ParameterCryptoInfo>,
+    /// CEKs the enclave needs for the query, as indexes into `cek_table`.
+    pub enclave_ceks: Vec<u16>,
+    /// CMK signatures allowing enclave computations, by CMK path.
+    pub cmk_signatures: HashMap<String, Bytes>,
 }
 
 impl ParameterEncryptionInfo {
@@ -336,6 +948,8 @@ impl ParameterEncryptionInfo {
         Self {
             cek_table: CekTable::new(),
             parameters: HashMap::new(),
+            enclave_ceks: Vec::new(),
+            cmk_signatures: HashMap::new(),
         }
     }
 
@@ -353,6 +967,101 @@ impl ParameterEncryptionInfo {
     pub fn needs_encryption(&self, name: &str) -> bool {
         self.parameters.contains_key(name)
     }
+
+    /// Build encryption info from the two result sets returned by
+    /// `sp_describe_parameter_encryption`.
+    ///
+    /// The first result set lists the CEKs (one row per CEK value, so a key
+    /// encrypted by several CMKs appears more than once); the second maps
+    /// each parameter to a CEK ordinal. Plaintext parameters are skipped.
+    ///
+    /// With enclave support negotiated, CEK rows also say whether the
+    /// enclave needs the key and carry the CMK's enclave signature.
+    pub fn from_describe_results(
+        cek_rows: &[Row],
+        param_rows: &[Row],
+    ) -> crate::error::Result<Self> {
+        let mut info = Self::new();
+        // Server CEK ordinal -> index into our CEK table.
+        let mut cek_index: HashMap<i32, u16> = HashMap::new();
+
+        for row in cek_rows {
+            let ordinal: i32 = row.get(0)?;
+            let md_version: Vec<u8> = row.get(4)?;
+            let md_version: [u8; 8] = md_version.as_slice().try_into().map_err(|_| {
+                EncryptionError::MetadataNotAvailable(format!(
+                    "CEK metadata version must be 8 bytes, got {}",
+                    md_version.len()
+                ))
+            })?;
+            let value = CekValue {
+                encrypted_value: Bytes::from(row.get::<Vec<u8>>(5)?),
+                key_store_provider_name: row.get(6)?,
+                cmk_path: row.get(7)?,
+                encryption_algorithm: row.get(8)?,
+            };
+            let requested_by_enclave = row.try_get::<bool>(9).unwrap_or(false);
+            if let Some(signature) = row.try_get::<Vec<u8>>(10) {
+                info.cmk_signatures
+                    .insert(value.cmk_path.clone(), Bytes::from(signature));
+            }
+
+            if let Some(&index) = cek_index.get(&ordinal) {
+                info.cek_table.entries[usize::from(index)]
+                    .values
+                    .push(value);
+                continue;
+            }
+
+            let index = u16::try_from(info.cek_table.entries.len()).map_err(|_| {
+                EncryptionError::MetadataNotAvailable("too many column encryption keys".into())
+            })?;
+            info.cek_table.entries.push(CekTableEntry {
+                database_id: row.get::<i32>(1)? as u32,
+                cek_id: row.get::<i32>(2)? as u32,
+                cek_version: row.get::<i32>(3)? as u32,
+                cek_md_version: u64::from_le_bytes(md_version),
+                values: vec![value],
+            });
+            cek_index.insert(ordinal, index);
+            if requested_by_enclave {
+                info.enclave_ceks.push(index);
+            }
+        }
+
+        for row in param_rows {
+            let name: String = row.get(1)?;
+            let algorithm_id: u8 = row.get(2)?;
+            let encryption_type: u8 = row.get(3)?;
+            if encryption_type == 0 {
+                continue;
+            }
+            let encryption_type =
+                EncryptionTypeWire::from_u8(encryption_type).ok_or_else(|| {
+                    EncryptionError::MetadataNotAvailable(format!(
+                        "unknown encryption type {encryption_type} for parameter {name}"
+                    ))
+                })?;
+            let cek_ordinal: i32 = row.get(4)?;
+            let cek_ordinal = *cek_index.get(&cek_ordinal).ok_or_else(|| {
+                EncryptionError::MetadataNotAvailable(format!(
+                    "parameter {name} references unknown CEK ordinal {cek_ordinal}"
+                ))
+            })?;
+
+            let mut crypto = ParameterCryptoInfo::new(
+                cek_ordinal,
+                encryption_type,
+                algorithm_id,
+                row.get::<i32>(0)? as u16,
+                info.cek_table.entries[usize::from(cek_ordinal)].database_id,
+            );
+            crypto.normalization_version = row.get(5)?;
+            info.add_parameter(name, crypto);
+        }
+
+        Ok(info)
+    }
 }
 
 impl Default for ParameterEncryptionInfo {
@@ -374,6 +1083,8 @@ pub struct ParameterCryptoInfo {
     pub column_ordinal: u16,
     /// Target column database ID.
     pub database_id: u32,
+    /// Normalization rule version applied before encryption.
+    pub normalization_version: u8,
 }
 
 impl ParameterCryptoInfo {
@@ -391,6 +1102,7 @@ impl ParameterCryptoInfo {
             algorithm_id,
             column_ordinal,
             database_id,
+            normalization_version: NORMALIZATION_RULE_VERSION,
         }
     }
 }
@@ -450,4 +1162,325 @@ mod tests {
         let param = info.get_parameter("@p1").unwrap();
         assert_eq!(param.encryption_type, EncryptionTypeWire::Randomized);
     }
+
+    fn row(values: Vec<mssql_types::SqlValue>) -> Row {
+        let columns = (0..values.len())
+            .map(|i| crate::row::Column::new(format!("c{i}"), i, "Unknown"))
+            .collect();
+        Row::from_values(columns, values)
+    }
+
+    fn describe_results() -> (Vec<Row>, Vec<Row>) {
+        use mssql_types::SqlValue;
+
+        let cek = |provider: &str| {
+            row(vec![
+                SqlValue::Int(1),
+                SqlValue::Int(5),
+                SqlValue::Int(7),
+                SqlValue::Int(1),
+                SqlValue::Binary(Bytes::from_static(&[9, 0, 0, 0, 0, 0, 0, 0])),
+                SqlValue::Binary(Bytes::from_static(&[0xAA; 4])),
+                SqlValue::String(provider.into()),
+                SqlValue::String("CurrentUser/My/ABC".into()),
+                SqlValue::String("RSA_OAEP".into()),
+            ])
+        };
+        let param = |ordinal: i32, name: &str, encryption_type: u8| {
+            row(vec![
+                SqlValue::Int(ordinal),
+                SqlValue::String(name.into()),
+                SqlValue::TinyInt(2),
+                SqlValue::TinyInt(encryption_type),
+                SqlValue::Int(1),
+                SqlValue::TinyInt(1),
+            ])
+        };
+
+        (
+            vec![cek("MSSQL_CERTIFICATE_STORE"), cek("AZURE_KEY_VAULT")],
+            vec![param(1, "@p1", 1), param(2, "@p2", 0)],
+        )
+    }
+
+    #[test]
+    fn test_parameter_encryption_from_describe_results() {
+        let (cek_rows, param_rows) = describe_results();
+        let info = ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+
+        // Both rows describe the same CEK, encrypted by two CMKs.
+        assert_eq!(info.cek_table.len(), 1);
+        let cek = info.cek_table.get(0).unwrap();
+        assert_eq!(cek.database_id, 5);
+        assert_eq!(cek.cek_id, 7);
+        assert_eq!(cek.cek_md_version, 9);
+        assert_eq!(cek.values.len(), 2);
+        assert_eq!(cek.values[1].key_store_provider_name, "AZURE_KEY_VAULT");
+
+        // @p2 is plaintext and needs no encryption.
+        assert!(info.needs_encryption("@p1"));
+        assert!(!info.needs_encryption("@p2"));
+        let p1 = info.get_parameter("@p1").unwrap();
+        assert_eq!(p1.cek_ordinal, 0);
+        assert_eq!(p1.encryption_type, EncryptionTypeWire::Deterministic);
+        assert_eq!(p1.normalization_version, 1);
+    }
+
+    #[test]
+    fn test_parameter_encryption_unknown_cek() {
+        let (_, param_rows) = describe_results();
+        let result = ParameterEncryptionInfo::from_describe_results(&[], &param_rows);
+        assert!(matches!(result, Err(crate::Error::Encryption(_))));
+    }
+
+    #[test]
+    fn test_parameter_encryption_enclave_keys() {
+        use mssql_types::SqlValue;
+
+        let (mut cek_rows, param_rows) = describe_results();
+        let mut values: Vec<SqlValue> = (0..9).map(|i| cek_rows[0].get_raw(i).unwrap()).collect();
+        values.push(SqlValue::Bool(true));
+        values.push(SqlValue::Binary(Bytes::from_static(&[0x5A; 4])));
+        cek_rows[0] = row(values);
+
+        let info = ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+        assert_eq!(info.enclave_ceks, vec![0]);
+        assert_eq!(
+            info.cmk_signatures
+                .get("CurrentUser/My/ABC")
+                .unwrap()
+                .as_ref(),
+            &[0x5A; 4]
+        );
+
+        // Without the enclave columns no keys are requested
+        let (cek_rows, param_rows) = describe_results();
+        let info = ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+        assert!(info.enclave_ceks.is_empty());
+        assert!(info.cmk_signatures.is_empty());
+    }
+
+    /// Key store that unwraps every CEK to a fixed key and accepts the
+    /// enclave signature `b"signed"`.
+    #[cfg(feature = "always-encrypted")]
+    struct SigningKeyStore;
+
+    #[cfg(feature = "always-encrypted")]
+    #[async_trait::async_trait]
+    impl KeyStoreProvider for SigningKeyStore {
+        fn provider_name(&self) -> &str {
+            "MSSQL_CERTIFICATE_STORE"
+        }
+
+        async fn decrypt_cek(
+            &self,
+            _cmk_path: &str,
+            _algorithm: &str,
+            _encrypted_cek: &[u8],
+        ) -> Result<Vec<u8>, EncryptionError> {
+            Ok(vec![3u8; 32])
+        }
+
+        async fn verify_signature(
+            &self,
+            cmk_path: &str,
+            data: &[u8],
+            signature: &[u8],
+        ) -> Result<bool, EncryptionError> {
+            let digest = cmk_metadata_digest(self.provider_name(), cmk_path, true);
+            Ok(data == digest && signature == b"signed")
+        }
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[tokio::test]
+    async fn test_enclave_package_verifies_cmk_signature() {
+        let (cek_rows, param_rows) = describe_results();
+        let mut info =
+            ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+        let context =
+            EncryptionContext::new(EncryptionConfig::new().with_provider(SigningKeyStore));
+        let session = EnclaveSession::new(42, &[1u8; 32]).unwrap();
+
+        // Nothing requested by the enclave
+        assert!(
+            context
+                .enclave_package(&session, &info, "SELECT 1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Requested, but the CMK is not signed for enclave computations
+        info.enclave_ceks.push(0);
+        let result = context.enclave_package(&session, &info, "SELECT 1").await;
+        assert!(matches!(result, Err(EncryptionError::AttestationFailed(_))));
+
+        info.cmk_signatures
+            .insert("CurrentUser/My/ABC".into(), Bytes::from_static(b"forged"));
+        let result = context.enclave_package(&session, &info, "SELECT 1").await;
+        assert!(matches!(result, Err(EncryptionError::AttestationFailed(_))));
+
+        info.cmk_signatures
+            .insert("CurrentUser/My/ABC".into(), Bytes::from_static(b"signed"));
+        let package = context
+            .enclave_package(&session, &info, "SELECT 1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&package[..8], &42i64.to_le_bytes());
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[test]
+    fn test_normalize_parameter() {
+        use tds_protocol::rpc::TypeInfo;
+
+        let int = RpcParam::new(
+            "@p1",
+            TypeInfo::int(),
+            Bytes::from_static(&[0xFE, 0xFF, 0xFF, 0xFF]),
+        );
+        assert_eq!(normalize_parameter(&int).unwrap(), (-2i64).to_le_bytes());
+
+        let bit = RpcParam::new("@p2", TypeInfo::bit(), Bytes::from_static(&[1]));
+        assert_eq!(normalize_parameter(&bit).unwrap(), 1i64.to_le_bytes());
+
+        let text = RpcParam::nvarchar("@p3", "hi");
+        assert_eq!(normalize_parameter(&text).unwrap(), vec![b'h', 0, b'i', 0]);
+
+        let null = RpcParam::null("@p4", TypeInfo::int());
+        assert!(normalize_parameter(&null).is_none());
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[tokio::test]
+    async fn test_encrypt_parameters_requires_provider() {
+        let (cek_rows, param_rows) = describe_results();
+        let info = ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+        let context = EncryptionContext::new(EncryptionConfig::new());
+
+        let params = vec![
+            RpcParam::nvarchar("@p1", "secret"),
+            RpcParam::nvarchar("@p2", "x"),
+        ];
+        let result = context.encrypt_parameters(&info, params).await;
+        assert!(matches!(result, Err(EncryptionError::KeyStoreNotFound(_))));
+
+        // Nothing to encrypt leaves parameters untouched.
+        let params = vec![RpcParam::nvarchar("@p2", "x")];
+        let params = context.encrypt_parameters(&info, params).await.unwrap();
+        assert!(params[0].cipher_info.is_none());
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[test]
+    fn test_provider_precedence() {
+        let registry = Arc::new(KeyStoreProviderRegistry::new());
+        registry.register_factory(|name| {
+            (name == "HSM_STORE").then(|| Arc::new(SigningKeyStore) as Arc<dyn KeyStoreProvider>)
+        });
+
+        // Without connection providers the registry is consulted, ignoring case
+        let config = EncryptionConfig::new().with_provider_registry(Arc::clone(&registry));
+        assert!(config.is_ready());
+        let context = EncryptionContext::new(config);
+        assert!(context.has_provider("HSM_STORE"));
+        assert!(!context.has_provider("mssql_certificate_store"));
+
+        // Connection providers are used exclusively
+        let context = EncryptionContext::new(
+            EncryptionConfig::new()
+                .with_provider_registry(registry)
+                .with_provider(SigningKeyStore),
+        );
+        assert!(context.has_provider("mssql_certificate_store"));
+        assert!(!context.has_provider("HSM_STORE"));
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[tokio::test]
+    async fn test_cek_cache_configuration() {
+        let (cek_rows, param_rows) = describe_results();
+        let info = ParameterEncryptionInfo::from_describe_results(&cek_rows, &param_rows).unwrap();
+        let cek_entry = &info.cek_table.entries[0];
+        let context = EncryptionContext::new(
+            EncryptionConfig::new()
+                .with_provider(SigningKeyStore)
+                .with_cek_cache_ttl(Duration::from_secs(60))
+                .with_cek_cache_max_entries(1),
+        );
+
+        context.get_encryptor(cek_entry).await.unwrap();
+        context.get_encryptor(cek_entry).await.unwrap();
+        let stats = context.cek_cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        // Rotating the CMK drops the CEKs it protects
+        assert_eq!(context.invalidate_cmk("CurrentUser/My/ABC"), 1);
+        assert_eq!(context.cek_cache_stats().entries, 0);
+        context.get_encryptor(cek_entry).await.unwrap();
+        assert_eq!(context.cek_cache_stats().misses, 2);
+    }
+
+    #[cfg(feature = "always-encrypted")]
+    #[test]
+    fn test_column_decryptor_decrypts_row() {
+        use mssql_types::SqlValue;
+        use tds_protocol::token::TypeInfo;
+        use tds_protocol::types::TypeId;
+
+        let encryptor = Arc::new(AeadEncryptor::new(&[7u8; 32]).unwrap());
+        let encrypt = |plaintext: &[u8]| {
+            let ciphertext = encryptor
+                .encrypt(plaintext, mssql_auth::EncryptionType::Deterministic)
+                .unwrap();
+            SqlValue::Binary(Bytes::from(ciphertext))
+        };
+        let column = |name: &str, type_id: TypeId, col_type: u8, max_length: u32| ColumnData {
+            name: name.into(),
+            type_id,
+            col_type,
+            flags: 0x01,
+            user_type: 0,
+            type_info: TypeInfo {
+                max_length: Some(max_length),
+                ..Default::default()
+            },
+            encryption: None,
+        };
+
+        let decryptor = ColumnDecryptor {
+            columns: vec![
+                Some((encryptor.clone(), column("id", TypeId::IntN, 0x26, 4))),
+                Some((
+                    encryptor.clone(),
+                    column("name", TypeId::NVarChar, 0xE7, 100),
+                )),
+                Some((encryptor.clone(), column("note", TypeId::IntN, 0x26, 4))),
+                None,
+            ],
+        };
+
+        let columns = ["id", "name", "note", "plain"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| crate::row::Column::new(*name, i, "BigVarBinary"))
+            .collect();
+        let row = Row::from_values(
+            columns,
+            vec![
+                encrypt(&42i64.to_le_bytes()),
+                encrypt(&[b'h', 0, b'i', 0]),
+                SqlValue::Null,
+                SqlValue::Int(5),
+            ],
+        );
+
+        let row = decryptor.decrypt_row(row).unwrap();
+        assert_eq!(row.get::<i32>(0).unwrap(), 42);
+        assert_eq!(row.get::<String>(1).unwrap(), "hi");
+        assert!(row.is_null(2));
+        assert_eq!(row.get::<i32>(3).unwrap(), 5);
+    }
 }
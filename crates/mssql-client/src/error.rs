@@ -63,6 +63,15 @@ pub enum Error {
     #[error("transaction error: {0}")]
     Transaction(String),
 
+    /// A server error rolled back the transaction in progress.
+    ///
+    /// The server has already ended the transaction (for example a deadlock
+    /// victim, or any error under `SET XACT_ABORT ON`), so none of its work
+    /// was kept. Roll back to release the client and retry the whole
+    /// transaction if the error is transient.
+    #[error("transaction rolled back by server error {}: {}", .0.number, .0.message)]
+    TransactionDoomed(DatabaseError),
+
     /// Configuration error.
     #[error("configuration error: {0}")]
     Config(String),
@@ -118,6 +127,204 @@ pub enum Error {
     /// Query was cancelled by user request.
     #[error("query cancelled")]
     Cancelled,
+
+    /// Schema migration error.
+    #[error("migration error: {0}")]
+    Migration(String),
+
+    /// CSV or Parquet ingestion error.
+    #[error("ingest error: {0}")]
+    Ingest(String),
+
+    /// Always Encrypted key or cryptography error.
+    #[error("column encryption error: {0}")]
+    Encryption(#[from] mssql_auth::EncryptionError),
+}
+
+/// How a server error affects the request and the connection.
+///
+/// Derived from the error class (severity) SQL Server reports:
+/// - 0-10: informational messages, sent as INFO rather than ERROR tokens
+/// - 11-16: errors the user can correct; the statement is aborted
+/// - 17-19: resource or software errors; the statement is aborted
+/// - 20-25: fatal errors; the server closes the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ErrorSeverity {
+    /// Informational message (class 0-10).
+    Informational,
+    /// User error such as a syntax error or constraint violation (class 11-16).
+    User,
+    /// Resource or software error such as running out of memory (class 17-19).
+    Resource,
+    /// Fatal error that terminates the connection (class 20-25).
+    Fatal,
+}
+
+impl ErrorSeverity {
+    /// Classify an error class (severity) reported by the server.
+    #[must_use]
+    pub fn from_class(class: u8) -> Self {
+        match class {
+            0..=10 => Self::Informational,
+            11..=16 => Self::User,
+            17..=19 => Self::Resource,
+            _ => Self::Fatal,
+        }
+    }
+
+    /// Check if the server aborts the current statement.
+    #[must_use]
+    pub fn aborts_statement(self) -> bool {
+        self != Self::Informational
+    }
+
+    /// Check if the server closes the connection.
+    #[must_use]
+    pub fn terminates_connection(self) -> bool {
+        self == Self::Fatal
+    }
+}
+
+/// Structured details of an error reported by SQL Server in an ERROR token.
+///
+/// Obtain one from any [`Error`] with [`Error::database_error()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseError {
+    /// Error number.
+    pub number: i32,
+    /// Error state.
+    pub state: u8,
+    /// Error class/severity (0-25).
+    pub class: u8,
+    /// Error message.
+    pub message: String,
+    /// Server name where error occurred.
+    pub server: Option<String>,
+    /// Stored procedure name (if applicable).
+    pub procedure: Option<String>,
+    /// Line number in the SQL batch or procedure.
+    pub line: u32,
+}
+
+impl DatabaseError {
+    /// Classify the error by its class.
+    #[must_use]
+    pub fn severity(&self) -> ErrorSeverity {
+        ErrorSeverity::from_class(self.class)
+    }
+
+    /// Check if the server closed the connection after this error (class 20 and above).
+    #[must_use]
+    pub fn is_fatal(&self) -> bool {
+        self.severity().terminates_connection()
+    }
+
+    /// Check if this error is a deadlock (error 1205).
+    #[must_use]
+    pub fn is_deadlock(&self) -> bool {
+        self.number == 1205
+    }
+
+    /// Check if this error is a constraint violation.
+    ///
+    /// Covers the following server error codes:
+    /// - 515: Cannot insert NULL into a non-nullable column
+    /// - 547: CHECK or FOREIGN KEY constraint conflict
+    /// - 2601: Duplicate key in a unique index
+    /// - 2627: PRIMARY KEY or UNIQUE constraint violation
+    #[must_use]
+    pub fn is_constraint_violation(&self) -> bool {
+        is_constraint_number(self.number)
+    }
+
+    /// Get the name of the violated constraint or unique index.
+    ///
+    /// The name is parsed from the server message, so it is only available
+    /// for constraint violations whose message quotes the constraint name
+    /// (errors 547, 2601, and 2627).
+    #[must_use]
+    pub fn constraint_name(&self) -> Option<&str> {
+        constraint_name_in(self.number, &self.message)
+    }
+}
+
+impl std::fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "server error {} (state {}, class {}",
+            self.number, self.state, self.class
+        )?;
+        if let Some(procedure) = &self.procedure {
+            write!(f, ", procedure {procedure}")?;
+        }
+        write!(f, ", line {}): {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DatabaseError {}
+
+impl From<&tds_protocol::token::ServerError> for DatabaseError {
+    fn from(err: &tds_protocol::token::ServerError) -> Self {
+        Self {
+            number: err.number,
+            state: err.state,
+            class: err.class,
+            message: err.message.clone(),
+            server: if err.server.is_empty() {
+                None
+            } else {
+                Some(err.server.clone())
+            },
+            procedure: if err.procedure.is_empty() {
+                None
+            } else {
+                Some(err.procedure.clone())
+            },
+            line: err.line as u32,
+        }
+    }
+}
+
+impl From<DatabaseError> for Error {
+    fn from(e: DatabaseError) -> Self {
+        Error::Server {
+            number: e.number,
+            class: e.class,
+            state: e.state,
+            message: e.message,
+            server: e.server,
+            procedure: e.procedure,
+            line: e.line,
+        }
+    }
+}
+
+/// Check if a server error number is a constraint violation.
+fn is_constraint_number(number: i32) -> bool {
+    matches!(number, 515 | 547 | 2601 | 2627)
+}
+
+/// Get the constraint or unique index named in the message of a constraint
+/// violation.
+fn constraint_name_in(number: i32, message: &str) -> Option<&str> {
+    match number {
+        547 | 2627 => quoted_after(message, "constraint "),
+        2601 => quoted_after(message, "unique index "),
+        _ => None,
+    }
+}
+
+/// Extract the text between the quotes following `marker` in `message`.
+///
+/// SQL Server quotes object names with either `'` or `"` depending on the
+/// message, so both are accepted.
+fn quoted_after<'a>(message: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = &message[message.find(marker)? + marker.len()..];
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &rest[1..];
+    rest.find(quote).map(|end| &rest[..end])
 }
 
 impl From<mssql_tls::TlsError> for Error {
@@ -166,6 +373,7 @@ impl Error {
             | Self::PoolExhausted
             | Self::Io(_) => true,
             Self::Server { number, .. } => Self::is_transient_server_error(*number),
+            Self::TransactionDoomed(e) => Self::is_transient_server_error(e.number),
             _ => false,
         }
     }
@@ -201,14 +409,14 @@ impl Error {
     /// - 102: Syntax error
     /// - 207: Invalid column
     /// - 208: Invalid object
-    /// - 547: Constraint violation
-    /// - 2627: Unique constraint violation
-    /// - 2601: Duplicate key
+    /// - the constraint violations of [`is_constraint_violation`](Self::is_constraint_violation)
+    ///   (515, 547, 2601, 2627)
     #[must_use]
     pub fn is_terminal(&self) -> bool {
         match self {
             Self::Config(_) | Self::InvalidIdentifier(_) => true,
             Self::Server { number, .. } => Self::is_terminal_server_error(*number),
+            Self::TransactionDoomed(e) => Self::is_terminal_server_error(e.number),
             _ => false,
         }
     }
@@ -222,11 +430,8 @@ impl Error {
             number,
             102 |       // Syntax error
             207 |       // Invalid column
-            208 |       // Invalid object
-            547 |       // Constraint violation
-            2627 |      // Unique constraint violation
-            2601 // Duplicate key
-        )
+            208 // Invalid object
+        ) || is_constraint_number(number)
     }
 
     /// Check if this error indicates a protocol/driver bug.
@@ -241,7 +446,78 @@ impl Error {
     /// Check if this is a server error with a specific number.
     #[must_use]
     pub fn is_server_error(&self, number: i32) -> bool {
-        matches!(self, Self::Server { number: n, .. } if *n == number)
+        self.sql_error_number() == Some(number)
+    }
+
+    /// Get the structured server error details, if this is a server error.
+    #[must_use]
+    pub fn database_error(&self) -> Option<DatabaseError> {
+        match self {
+            Self::Server {
+                number,
+                class,
+                state,
+                message,
+                server,
+                procedure,
+                line,
+            } => Some(DatabaseError {
+                number: *number,
+                state: *state,
+                class: *class,
+                message: message.clone(),
+                server: server.clone(),
+                procedure: procedure.clone(),
+                line: *line,
+            }),
+            Self::TransactionDoomed(e) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// Get the number, class and message of a server error.
+    fn server_details(&self) -> Option<(i32, u8, &str)> {
+        match self {
+            Self::Server {
+                number,
+                class,
+                message,
+                ..
+            } => Some((*number, *class, message)),
+            Self::TransactionDoomed(e) => Some((e.number, e.class, &e.message)),
+            _ => None,
+        }
+    }
+
+    /// Get the SQL Server error number, if this is a server error.
+    #[must_use]
+    pub fn sql_error_number(&self) -> Option<i32> {
+        self.server_details().map(|(number, _, _)| number)
+    }
+
+    /// Check if this error is a deadlock (server error 1205).
+    ///
+    /// Deadlock victims are transient; the whole transaction should be retried.
+    #[must_use]
+    pub fn is_deadlock(&self) -> bool {
+        self.is_server_error(1205)
+    }
+
+    /// Check if this error is a constraint violation.
+    ///
+    /// See [`DatabaseError::is_constraint_violation()`] for the error codes covered.
+    #[must_use]
+    pub fn is_constraint_violation(&self) -> bool {
+        self.sql_error_number().is_some_and(is_constraint_number)
+    }
+
+    /// Get the name of the violated constraint, if this is a constraint violation.
+    ///
+    /// See [`DatabaseError::constraint_name()`].
+    #[must_use]
+    pub fn constraint_name(&self) -> Option<&str> {
+        let (number, _, message) = self.server_details()?;
+        constraint_name_in(number, message)
     }
 
     /// Get the error class/severity if this is a server error.
@@ -253,10 +529,7 @@ impl Error {
     /// - 20-25: System errors (connection terminating)
     #[must_use]
     pub fn class(&self) -> Option<u8> {
-        match self {
-            Self::Server { class, .. } => Some(*class),
-            _ => None,
-        }
+        self.server_details().map(|(_, class, _)| class)
     }
 
     /// Alias for `class()` - returns error severity.
@@ -264,6 +537,33 @@ impl Error {
     pub fn severity(&self) -> Option<u8> {
         self.class()
     }
+
+    /// Classify the server error by its class, if this is a server error.
+    #[must_use]
+    pub fn error_severity(&self) -> Option<ErrorSeverity> {
+        self.class().map(ErrorSeverity::from_class)
+    }
+
+    /// Check if the connection can no longer be used after this error.
+    ///
+    /// True for fatal server errors (class 20 and above), after which the
+    /// server closes the connection, and for I/O errors and closed
+    /// connections. Pools should discard the connection.
+    #[must_use]
+    pub fn terminates_connection(&self) -> bool {
+        match self {
+            Self::ConnectionClosed | Self::Io(_) => true,
+            _ => self
+                .error_severity()
+                .is_some_and(ErrorSeverity::terminates_connection),
+        }
+    }
+
+    /// Check if a server error rolled back the transaction in progress.
+    #[must_use]
+    pub fn is_transaction_doomed(&self) -> bool {
+        matches!(self, Self::TransactionDoomed(_))
+    }
 }
 
 /// Result type for client operations.
@@ -357,6 +657,7 @@ mod tests {
         assert!(make_server_error(208).is_terminal()); // Invalid object
         assert!(make_server_error(547).is_terminal()); // Constraint violation
         assert!(make_server_error(2627).is_terminal()); // Unique constraint violation
+        assert!(make_server_error(515).is_terminal()); // NULL into a NOT NULL column
         assert!(make_server_error(2601).is_terminal()); // Duplicate key
     }
 
@@ -407,4 +708,157 @@ mod tests {
 
         assert!(!Error::ConnectionTimeout.is_server_error(102));
     }
+
+    fn make_server_error_with_message(number: i32, message: &str) -> Error {
+        Error::Server {
+            number,
+            class: 14,
+            state: 1,
+            message: message.to_string(),
+            server: None,
+            procedure: Some("usp_insert_user".to_string()),
+            line: 7,
+        }
+    }
+
+    #[test]
+    fn test_sql_error_number() {
+        assert_eq!(make_server_error(2627).sql_error_number(), Some(2627));
+        assert_eq!(Error::ConnectionClosed.sql_error_number(), None);
+    }
+
+    #[test]
+    fn test_is_deadlock() {
+        assert!(make_server_error(1205).is_deadlock());
+        assert!(!make_server_error(1222).is_deadlock());
+        assert!(!Error::CommandTimeout.is_deadlock());
+    }
+
+    #[test]
+    fn test_is_constraint_violation() {
+        for number in [515, 547, 2601, 2627] {
+            assert!(make_server_error(number).is_constraint_violation());
+        }
+        assert!(!make_server_error(102).is_constraint_violation());
+        assert!(!Error::Config("x".into()).is_constraint_violation());
+    }
+
+    #[test]
+    fn test_constraint_name_primary_key() {
+        let err = make_server_error_with_message(
+            2627,
+            "Violation of PRIMARY KEY constraint 'PK_Users'. Cannot insert duplicate key in object 'dbo.Users'.",
+        );
+        assert_eq!(err.constraint_name(), Some("PK_Users"));
+    }
+
+    #[test]
+    fn test_constraint_name_foreign_key() {
+        let err = make_server_error_with_message(
+            547,
+            "The INSERT statement conflicted with the FOREIGN KEY constraint \"FK_Orders_Users\". The conflict occurred in database \"app\".",
+        );
+        assert_eq!(err.constraint_name(), Some("FK_Orders_Users"));
+    }
+
+    #[test]
+    fn test_constraint_name_unique_index() {
+        let err = make_server_error_with_message(
+            2601,
+            "Cannot insert duplicate key row in object 'dbo.Users' with unique index 'IX_Users_Email'.",
+        );
+        assert_eq!(err.constraint_name(), Some("IX_Users_Email"));
+        assert_eq!(make_server_error(515).constraint_name(), None);
+    }
+
+    #[test]
+    fn test_database_error_round_trip() {
+        let err =
+            make_server_error_with_message(2627, "Violation of UNIQUE KEY constraint 'UQ_Email'.");
+        let db = err.database_error().unwrap();
+        assert_eq!(db.number, 2627);
+        assert_eq!(db.class, 14);
+        assert_eq!(db.line, 7);
+        assert_eq!(db.procedure.as_deref(), Some("usp_insert_user"));
+        assert!(db.is_constraint_violation());
+        assert_eq!(db.constraint_name(), Some("UQ_Email"));
+
+        let back: Error = db.clone().into();
+        assert_eq!(back.database_error(), Some(db));
+        assert!(Error::ConnectionClosed.database_error().is_none());
+    }
+
+    #[test]
+    fn test_error_severity_from_class() {
+        assert_eq!(ErrorSeverity::from_class(0), ErrorSeverity::Informational);
+        assert_eq!(ErrorSeverity::from_class(10), ErrorSeverity::Informational);
+        assert_eq!(ErrorSeverity::from_class(11), ErrorSeverity::User);
+        assert_eq!(ErrorSeverity::from_class(16), ErrorSeverity::User);
+        assert_eq!(ErrorSeverity::from_class(17), ErrorSeverity::Resource);
+        assert_eq!(ErrorSeverity::from_class(19), ErrorSeverity::Resource);
+        assert_eq!(ErrorSeverity::from_class(20), ErrorSeverity::Fatal);
+        assert_eq!(ErrorSeverity::from_class(25), ErrorSeverity::Fatal);
+
+        assert!(!ErrorSeverity::Informational.aborts_statement());
+        assert!(ErrorSeverity::User.aborts_statement());
+        assert!(!ErrorSeverity::Resource.terminates_connection());
+        assert!(ErrorSeverity::Fatal.terminates_connection());
+    }
+
+    #[test]
+    fn test_terminates_connection() {
+        let mut db = make_server_error(4014).database_error().unwrap();
+        assert!(!db.is_fatal());
+        assert!(!Error::from(db.clone()).terminates_connection());
+
+        db.class = 20;
+        assert!(db.is_fatal());
+        let err = Error::from(db);
+        assert_eq!(err.error_severity(), Some(ErrorSeverity::Fatal));
+        assert!(err.terminates_connection());
+
+        assert!(Error::ConnectionClosed.terminates_connection());
+        assert!(!Error::Config("x".into()).terminates_connection());
+        assert_eq!(Error::Config("x".into()).error_severity(), None);
+    }
+
+    #[test]
+    fn test_transaction_doomed() {
+        let deadlock = make_server_error(1205).database_error().unwrap();
+        let err = Error::TransactionDoomed(deadlock.clone());
+
+        assert!(err.is_transaction_doomed());
+        assert!(!make_server_error(1205).is_transaction_doomed());
+
+        // Server error helpers see through the doomed transaction
+        assert!(err.is_deadlock());
+        assert!(err.is_transient());
+        assert!(!err.is_terminal());
+        assert_eq!(err.sql_error_number(), Some(1205));
+        assert_eq!(err.class(), Some(16));
+        assert_eq!(err.database_error(), Some(deadlock));
+        assert_eq!(
+            err.to_string(),
+            "transaction rolled back by server error 1205: Test error"
+        );
+
+        let violation = make_server_error_with_message(2627, "Violation of constraint 'PK_T'.")
+            .database_error()
+            .unwrap();
+        let err = Error::TransactionDoomed(violation);
+        assert!(err.is_terminal());
+        assert!(err.is_constraint_violation());
+        assert_eq!(err.constraint_name(), Some("PK_T"));
+    }
+
+    #[test]
+    fn test_database_error_display() {
+        let db = make_server_error_with_message(547, "conflict")
+            .database_error()
+            .unwrap();
+        assert_eq!(
+            db.to_string(),
+            "server error 547 (state 1, class 14, procedure usp_insert_user, line 7): conflict"
+        );
+    }
 }
@@ -26,6 +26,24 @@
 //! - `#[mssql(skip)]` - Skip field, use Default value
 //! - `#[mssql(default)]` - Use Default if column not found
 //! - `#[mssql(flatten)]` - Flatten nested FromRow structs
+//! - `#[mssql(flatten, prefix = "billing_")]` - Flatten a nested struct from
+//!   the columns starting with a prefix; an `Option` field is `None` when all
+//!   of those columns are NULL
+//!
+//! ## Tuples
+//!
+//! Tuples of up to 16 `FromSql` types implement `FromRow` by column
+//! position, for queries that don't warrant a struct:
+//!
+//! ```rust,ignore
+//! let pairs: Vec<(i32, Option<String>)> = client
+//!     .query("SELECT id, email FROM users", &[])
+//!     .await?
+//!     .map_rows()
+//!     .collect::<Result<_, _>>()?;
+//! ```
+
+use mssql_types::FromSql;
 
 use crate::error::Error;
 use crate::row::Row;
@@ -66,6 +84,45 @@ pub trait FromRow: Sized {
     fn from_row(row: &Row) -> Result<Self, Error>;
 }
 
+macro_rules! impl_from_row_for_tuple {
+    ($($index:tt $ty:ident),+) => {
+        impl<$($ty: FromSql),+> FromRow for ($($ty,)+) {
+            fn from_row(row: &Row) -> Result<Self, Error> {
+                Ok(($(row.get::<$ty>($index)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_row_for_tuple!(0 T0);
+impl_from_row_for_tuple!(0 T0, 1 T1);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9);
+impl_from_row_for_tuple!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10);
+impl_from_row_for_tuple!(
+    0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11
+);
+impl_from_row_for_tuple!(
+    0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11, 12 T12
+);
+impl_from_row_for_tuple!(
+    0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11, 12 T12, 13 T13
+);
+impl_from_row_for_tuple!(
+    0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11, 12 T12, 13 T13,
+    14 T14
+);
+impl_from_row_for_tuple!(
+    0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11, 12 T12, 13 T13,
+    14 T14, 15 T15
+);
+
 /// Extension trait for iterating over query results as typed structs.
 ///
 /// This trait is automatically implemented for any iterator of `Result<Row, Error>`.
@@ -189,4 +246,31 @@ mod tests {
         assert_eq!(users[1].id, 2);
         assert_eq!(users[1].name, "Bob");
     }
+
+    #[test]
+    fn test_from_row_tuple() {
+        let columns = vec![
+            Column::new("id", 0, "INT".to_string()),
+            Column::new("name", 1, "NVARCHAR".to_string()),
+            Column::new("email", 2, "NVARCHAR".to_string()),
+        ];
+        let row = Row::from_values(
+            columns,
+            vec![
+                SqlValue::Int(7),
+                SqlValue::String("Alice".to_string()),
+                SqlValue::Null,
+            ],
+        );
+
+        let (id, name, email) = <(i32, String, Option<String>)>::from_row(&row).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(name, "Alice");
+        assert_eq!(email, None);
+
+        // Extra columns are ignored, missing ones are an error.
+        let (id,) = <(i64,)>::from_row(&row).unwrap();
+        assert_eq!(id, 7);
+        assert!(<(i32, String, String, i32)>::from_row(&row).is_err());
+    }
 }
@@ -0,0 +1,43 @@
+//! Entry points for the cargo-fuzz targets in `fuzz/`.
+//!
+//! Only compiled under `--cfg fuzzing`, which cargo-fuzz sets. Not part of
+//! the public API.
+
+use bytes::Bytes;
+use tds_protocol::token::{ColMetaData, Token, TokenParser};
+
+use crate::client::Client;
+use crate::row::Column;
+use crate::state::Ready;
+
+/// Parse a server token stream and decode every ROW and NBCROW against the
+/// preceding COLMETADATA, as the client does when reading a result set.
+pub fn decode_token_stream(data: &[u8]) {
+    let mut parser = TokenParser::new(Bytes::copy_from_slice(data));
+    let mut metadata: Option<ColMetaData> = None;
+    let mut columns: Vec<Column> = Vec::new();
+    let mut row_metadata = None;
+
+    while let Ok(Some(token)) = parser.next_token_with_metadata(metadata.as_ref()) {
+        match token {
+            Token::ColMetaData(meta) => {
+                columns = Client::<Ready>::build_columns(&meta, Default::default());
+                row_metadata = None;
+                metadata = Some(meta);
+            }
+            Token::Row(raw) => {
+                if let Some(meta) = &metadata {
+                    let shared = Client::<Ready>::shared_row_metadata(&mut row_metadata, &columns);
+                    let _ = Client::<Ready>::convert_raw_row(&raw, meta, shared);
+                }
+            }
+            Token::NbcRow(nbc) => {
+                if let Some(meta) = &metadata {
+                    let shared = Client::<Ready>::shared_row_metadata(&mut row_metadata, &columns);
+                    let _ = Client::<Ready>::convert_nbc_row(&nbc, meta, shared);
+                }
+            }
+            _ => {}
+        }
+    }
+}
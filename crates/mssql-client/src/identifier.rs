@@ -0,0 +1,141 @@
+//! Bracket quoting of SQL Server identifiers.
+//!
+//! Values can be sent as parameters, but table, column and database names
+//! have to be part of the SQL text. These helpers bracket-quote arbitrary
+//! names, doubling any `]`, so a name can never end the quoted identifier
+//! early and inject SQL:
+//!
+//! ```rust
+//! use mssql_client::{quote_identifier, quote_schema_qualified};
+//!
+//! assert_eq!(quote_identifier("Order Details"), "[Order Details]");
+//! assert_eq!(quote_identifier("x]; DROP TABLE t;--"), "[x]]; DROP TABLE t;--]");
+//! assert_eq!(quote_schema_qualified("sales.Orders"), "[sales].[Orders]");
+//! ```
+//!
+//! Unlike the validation used by generated DDL, any name is accepted,
+//! including spaces and non-ASCII characters. Whether the object exists,
+//! or the name is too long, is left for the server to report.
+
+/// Bracket-quote a single identifier, doubling any `]`.
+///
+/// The name is taken literally: dots and brackets in it are part of the
+/// name. Use [`quote_schema_qualified`] for multi-part names.
+#[must_use]
+pub fn quote_identifier(name: &str) -> String {
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('[');
+    for c in name.chars() {
+        if c == ']' {
+            quoted.push(']');
+        }
+        quoted.push(c);
+    }
+    quoted.push(']');
+    quoted
+}
+
+/// Bracket-quote a possibly qualified object name such as `dbo.Orders` or
+/// `Sales.dbo.Orders`.
+///
+/// The name is split on dots, and each part is quoted with
+/// [`quote_identifier`]. Parts that are already bracket-quoted, like
+/// `[Order.Archive]` or `[Odd]]Name]`, are kept as written, so dots inside
+/// them do not split the name.
+#[must_use]
+pub fn quote_schema_qualified(name: &str) -> String {
+    split_parts(name)
+        .iter()
+        .map(|part| quote_identifier(part))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Split a multi-part name into its unquoted parts.
+fn split_parts(name: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = name;
+    loop {
+        let (part, tail) = quoted_part(rest).unwrap_or_else(|| {
+            let end = rest.find('.').unwrap_or(rest.len());
+            (rest[..end].to_string(), &rest[end..])
+        });
+        parts.push(part);
+        match tail.strip_prefix('.') {
+            Some(tail) => rest = tail,
+            None => return parts,
+        }
+    }
+}
+
+/// Parse a bracket-quoted part at the start of `name`, returning the
+/// unescaped part and the text after it.
+///
+/// Returns `None` unless the closing bracket ends the part.
+fn quoted_part(name: &str) -> Option<(String, &str)> {
+    let inner = name.strip_prefix('[')?;
+    let mut part = String::new();
+    let mut chars = inner.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == ']' {
+            if inner[i + 1..].starts_with(']') {
+                chars.next();
+            } else {
+                let tail = &inner[i + 1..];
+                return (tail.is_empty() || tail.starts_with('.')).then_some((part, tail));
+            }
+        }
+        part.push(c);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("Orders"), "[Orders]");
+        assert_eq!(quote_identifier("Order Details"), "[Order Details]");
+        assert_eq!(quote_identifier("Bestellungen_Ü"), "[Bestellungen_Ü]");
+        assert_eq!(quote_identifier("Odd]Name"), "[Odd]]Name]");
+        assert_eq!(quote_identifier("a.b"), "[a.b]");
+        assert_eq!(
+            quote_identifier("t]; DROP TABLE users; --"),
+            "[t]]; DROP TABLE users; --]"
+        );
+    }
+
+    #[test]
+    fn test_quote_schema_qualified() {
+        assert_eq!(quote_schema_qualified("Orders"), "[Orders]");
+        assert_eq!(quote_schema_qualified("dbo.Orders"), "[dbo].[Orders]");
+        assert_eq!(
+            quote_schema_qualified("Sales.dbo.Orders"),
+            "[Sales].[dbo].[Orders]"
+        );
+        assert_eq!(
+            quote_schema_qualified("my schema.Order Details"),
+            "[my schema].[Order Details]"
+        );
+        assert_eq!(quote_schema_qualified("Odd]Name"), "[Odd]]Name]");
+    }
+
+    #[test]
+    fn test_quote_schema_qualified_keeps_quoted_parts() {
+        assert_eq!(quote_schema_qualified("[dbo].[Orders]"), "[dbo].[Orders]");
+        assert_eq!(
+            quote_schema_qualified("dbo.[Order.Archive]"),
+            "[dbo].[Order.Archive]"
+        );
+        assert_eq!(quote_schema_qualified("[Odd]]Name]"), "[Odd]]Name]");
+        // An unterminated or trailing-text bracket is part of the name
+        assert_eq!(quote_schema_qualified("[abc"), "[[abc]");
+        assert_eq!(quote_schema_qualified("[a]b.c"), "[[a]]b].[c]");
+        assert_eq!(
+            quote_schema_qualified("[x]]; DROP TABLE t; --"),
+            "[[x]]]]; DROP TABLE t; --]"
+        );
+    }
+}
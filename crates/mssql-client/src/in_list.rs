@@ -0,0 +1,590 @@
+//! Expanding value lists into `IN (...)` parameters.
+//!
+//! SQL Server has no array parameters, so `WHERE Id IN (@ids)` needs one
+//! parameter per value. [`expand_in`] rewrites the placeholder into numbered
+//! parameters instead of formatting the values into the SQL text, and splits
+//! lists that do not fit into one statement into several statements of at
+//! most [`MAX_PARAMS`] parameters each:
+//!
+//! ```rust,ignore
+//! use mssql_client::in_list::expand_in;
+//!
+//! let ids = [3, 5, 8];
+//! let sql = "SELECT * FROM dbo.Orders WHERE CustomerId = @p1 AND Id IN (@ids)";
+//! for chunk in expand_in(sql, "@ids", &ids, &[&42])? {
+//!     let rows = client.query(chunk.sql(), &chunk.params()).await?;
+//!     // ...
+//! }
+//! ```
+//!
+//! [`Client::query_in`](crate::Client::query_in) and
+//! [`Client::execute_in`](crate::Client::execute_in) run every statement and
+//! combine the results. `execute_in` runs them in a nested transaction with
+//! a savepoint: if one fails, the others are rolled back too, and an outer
+//! transaction stays open unless the error dooms it.
+//!
+//! Splitting a list is only correct when the result for the whole list is
+//! the union of the results for its parts, as for `SELECT ... WHERE Id IN`,
+//! `UPDATE` and `DELETE`. Statements with `NOT IN`, aggregates, `TOP`,
+//! `DISTINCT`, `GROUP BY` or `ORDER BY` would see one part of the list at a
+//! time, so they are rejected instead of split.
+//!
+//! ## Long lists as table-valued parameters
+//!
+//! With [`Config::in_list_tvp`](crate::Config::in_list_tvp) enabled,
+//! `query_in` and `execute_in` avoid splitting: a list with more values than
+//! fit next to the statement's parameters in [`MAX_PARAMS`] is sent as a
+//! single table-valued parameter, and `IN (@ids)` becomes
+//! `IN (SELECT [Value] FROM @pN)`. The statement runs once, so every kind of
+//! statement sees the whole list. Only the parameter limit triggers the
+//! rewrite; the length of the SQL text is not checked. This needs:
+//!
+//! - values that are all `TINYINT`, `SMALLINT`, `INT`, `BIGINT`,
+//!   `UNIQUEIDENTIFIER`, or strings of at most 4000 characters,
+//! - the placeholder to be the only thing inside the `IN` parentheses, and
+//! - a one-column table type such as `dbo.MssqlClientIntList`, which is
+//!   created in the current database on first use. It is a permanent
+//!   schema object, and creating it needs `CREATE TYPE` permission.
+//!
+//! When the values do not qualify, or the login may not create the table
+//! type, the query fails unless
+//! [`Config::in_list_split_fallback`](crate::Config::in_list_split_fallback)
+//! allows splitting the list as above. While `in_list_tvp` is disabled, the
+//! default, long lists are always split.
+
+use mssql_types::{SqlValue, ToSql};
+
+use crate::client::validate_identifier;
+use crate::error::{Error, Result};
+use crate::returning::{skip_quoted, sql_words};
+use crate::tvp::{TvpColumn, TvpRow, TvpValue, create_type_sql_for};
+
+/// Maximum number of parameters of one statement.
+///
+/// An RPC request carries at most 2100 parameters, and `sp_executesql` uses
+/// two of them for the statement text and the parameter declarations.
+pub const MAX_PARAMS: usize = 2098;
+
+/// What an empty list expands to.
+const EMPTY_LIST: &str = "SELECT NULL WHERE 1 = 0";
+
+/// A statement produced by [`expand_in`].
+#[derive(Debug, Clone)]
+pub struct ExpandedQuery {
+    sql: String,
+    params: Vec<SqlValue>,
+}
+
+impl ExpandedQuery {
+    /// Get the SQL text, which refers to the parameters as `@p1`, `@p2`, ...
+    #[must_use]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Get the parameters to run the statement with.
+    #[must_use]
+    pub fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params
+            .iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+/// Expand the placeholder `list` in `sql` into one parameter per value.
+///
+/// `params` are the statement's own parameters, `@p1` to `@pN`; the values
+/// of the list follow them as `@pN+1`, `@pN+2`, ... Every occurrence of the
+/// placeholder outside string literals, quoted identifiers and comments is
+/// replaced. When the values do not fit next to `params` in
+/// [`MAX_PARAMS`], one statement is returned per part of the list; see the
+/// [module documentation](self) for when that is correct.
+///
+/// An empty list expands to a subquery returning no rows, so `IN (@list)`
+/// matches no rows and `NOT IN (@list)` matches every row.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if the placeholder does not occur in `sql`,
+/// `params` leave no room for the list, or the list needs splitting and
+/// `sql` cannot be split, and [`Error::InvalidIdentifier`] if
+/// `list` is not a valid parameter name.
+///
+/// # Example
+///
+/// ```rust
+/// use mssql_client::in_list::expand_in;
+///
+/// let queries = expand_in(
+///     "DELETE FROM dbo.Sessions WHERE UserId = @p1 AND Id IN (@ids)",
+///     "@ids",
+///     &[10, 11, 12],
+///     &[&7],
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     queries[0].sql(),
+///     "DELETE FROM dbo.Sessions WHERE UserId = @p1 AND Id IN (@p2, @p3, @p4)"
+/// );
+/// assert_eq!(queries[0].params().len(), 4);
+/// ```
+pub fn expand_in<T: ToSql>(
+    sql: &str,
+    list: &str,
+    values: &[T],
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Vec<ExpandedQuery>> {
+    let (name, positions) = find_placeholder(sql, list)?;
+    let capacity = MAX_PARAMS.saturating_sub(params.len());
+    if capacity == 0 {
+        return Err(Error::Config(format!(
+            "statement has {} parameters, leaving no room for @{name}",
+            params.len()
+        )));
+    }
+
+    let fixed = params
+        .iter()
+        .map(|p| p.to_sql())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let values = values
+        .iter()
+        .map(ToSql::to_sql)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let placeholder_len = name.len() + 1;
+
+    if values.is_empty() {
+        // Not `NULL`: `x NOT IN (NULL)` is unknown for every row
+        return Ok(vec![ExpandedQuery {
+            sql: splice(sql, &positions, placeholder_len, EMPTY_LIST),
+            params: fixed,
+        }]);
+    }
+
+    if values.len() > capacity {
+        if let Some(reason) = split_hazard(sql, &positions) {
+            return Err(Error::Config(format!(
+                "@{name} holds {} values, more than fit into one statement, and \
+                 a statement with {reason} cannot be split",
+                values.len()
+            )));
+        }
+    }
+
+    Ok(values
+        .chunks(capacity)
+        .map(|chunk| {
+            let first = fixed.len() + 1;
+            let names = (first..first + chunk.len())
+                .map(|i| format!("@p{i}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut params = fixed.clone();
+            params.extend_from_slice(chunk);
+            ExpandedQuery {
+                sql: splice(sql, &positions, placeholder_len, &names),
+                params,
+            }
+        })
+        .collect())
+}
+
+/// Functions whose result over the whole list differs from the results
+/// over its parts.
+const AGGREGATES: &[&str] = &[
+    "AVG",
+    "CHECKSUM_AGG",
+    "COUNT",
+    "COUNT_BIG",
+    "GROUPING",
+    "MAX",
+    "MIN",
+    "STDEV",
+    "STDEVP",
+    "STRING_AGG",
+    "SUM",
+    "VAR",
+    "VARP",
+];
+
+/// Describe what makes `sql` wrong to run once per part of the list at
+/// `positions`, if anything.
+///
+/// Only the query that compares with the list and the queries around it
+/// count; subqueries and derived tables beside the list see every row.
+fn split_hazard(sql: &str, positions: &[usize]) -> Option<&'static str> {
+    let words = sql_words(sql);
+    let is = |word: &str, keyword: &str| word.eq_ignore_ascii_case(keyword);
+
+    let mut depth = 0;
+    for &position in positions {
+        let before = words.partition_point(|(offset, _, _)| *offset < position);
+        if let [.., (_, _, not), (_, _, in_)] = &words[..before] {
+            if is(in_, "IN") && is(not, "NOT") {
+                return Some("NOT IN");
+            }
+        }
+        // The placeholder sits inside the parentheses of its `IN`
+        if let Some((_, list_depth, _)) = words.get(before) {
+            depth = depth.max(list_depth.saturating_sub(1));
+        }
+    }
+
+    let words: Vec<_> = words.into_iter().filter(|(_, d, _)| *d <= depth).collect();
+    for (i, (offset, _, word)) in words.iter().enumerate() {
+        let next = words.get(i + 1).map(|(_, _, w)| *w).unwrap_or_default();
+        if is(word, "TOP") {
+            return Some("TOP");
+        }
+        if is(word, "DISTINCT") {
+            return Some("DISTINCT");
+        }
+        if is(word, "GROUP") && is(next, "BY") {
+            return Some("GROUP BY");
+        }
+        if is(word, "ORDER") && is(next, "BY") {
+            return Some("ORDER BY");
+        }
+        let called = sql[offset + word.len()..].trim_start().starts_with('(');
+        if called && AGGREGATES.iter().any(|f| is(word, f)) {
+            return Some("an aggregate");
+        }
+    }
+    None
+}
+
+/// A long list rewritten into a join with a table-valued parameter.
+#[derive(Debug)]
+pub(crate) struct TvpList {
+    /// Batch creating the table type if it does not exist yet.
+    pub(crate) create_type: String,
+    /// The statement, with the table-valued parameter last.
+    pub(crate) query: ExpandedQuery,
+}
+
+/// Rewrite a list that does not fit into one statement into a join with a
+/// table-valued parameter.
+///
+/// Returns `None` when the list fits next to `params`, when its values
+/// have no common table type, or when a placeholder is not the whole list
+/// of an `IN`; see the [module documentation](self).
+pub(crate) fn expand_in_tvp<T: ToSql>(
+    sql: &str,
+    list: &str,
+    values: &[T],
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Option<TvpList>> {
+    if params.len() + values.len() <= MAX_PARAMS {
+        return Ok(None);
+    }
+    let (name, positions) = find_placeholder(sql, list)?;
+    if !positions
+        .iter()
+        .all(|&position| is_whole_in_list(sql, position, name.len() + 1))
+    {
+        return Ok(None);
+    }
+    let values = values
+        .iter()
+        .map(ToSql::to_sql)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let Some((type_name, sql_type)) = list_type(&values) else {
+        return Ok(None);
+    };
+
+    let columns = vec![TvpColumn::new("Value", sql_type, 0)];
+    let create_type = create_type_sql_for(type_name, &columns)?;
+    let tvp = TvpValue {
+        type_name: type_name.to_string(),
+        columns,
+        rows: values.into_iter().map(|v| TvpRow::new(vec![v])).collect(),
+    };
+
+    let mut params = params
+        .iter()
+        .map(|p| p.to_sql())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    params.push(tvp.to_sql()?);
+    let select = format!("SELECT [Value] FROM @p{}", params.len());
+    Ok(Some(TvpList {
+        create_type,
+        query: ExpandedQuery {
+            sql: splice(sql, &positions, name.len() + 1, &select),
+            params,
+        },
+    }))
+}
+
+/// Check that the placeholder at `position` is all there is inside the
+/// parentheses of an `IN`, so it can be replaced by a subquery.
+fn is_whole_in_list(sql: &str, position: usize, len: usize) -> bool {
+    let Some(before) = sql[..position].trim_end().strip_suffix('(') else {
+        return false;
+    };
+    let before = before.trim_end();
+    let Some(keyword) = before
+        .len()
+        .checked_sub(2)
+        .and_then(|start| before.get(start..))
+    else {
+        return false;
+    };
+    let separated = before[..before.len() - 2]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')));
+    keyword.eq_ignore_ascii_case("IN")
+        && separated
+        && sql[position + len..].trim_start().starts_with(')')
+}
+
+/// Pick the table type for a list: the values must all have one of the
+/// supported types, ignoring NULLs.
+fn list_type(values: &[SqlValue]) -> Option<(&'static str, &'static str)> {
+    let mut found = None;
+    for value in values {
+        let this = match value {
+            SqlValue::Null => continue,
+            SqlValue::TinyInt(_) => ("dbo.MssqlClientTinyIntList", "TINYINT"),
+            SqlValue::SmallInt(_) => ("dbo.MssqlClientSmallIntList", "SMALLINT"),
+            SqlValue::Int(_) => ("dbo.MssqlClientIntList", "INT"),
+            SqlValue::BigInt(_) => ("dbo.MssqlClientBigIntList", "BIGINT"),
+            SqlValue::String(s) if s.encode_utf16().count() <= 4000 => {
+                ("dbo.MssqlClientStringList", "NVARCHAR(4000)")
+            }
+            #[cfg(feature = "uuid")]
+            SqlValue::Uuid(_) => ("dbo.MssqlClientGuidList", "UNIQUEIDENTIFIER"),
+            _ => return None,
+        };
+        match found {
+            None => found = Some(this),
+            Some(kind) if kind == this => {}
+            Some(_) => return None,
+        }
+    }
+    found
+}
+
+/// Validate the placeholder name and find where it occurs in `sql`.
+fn find_placeholder<'a>(sql: &str, list: &'a str) -> Result<(&'a str, Vec<usize>)> {
+    let name = list.trim_start_matches('@');
+    validate_identifier(name)?;
+    let positions = placeholder_positions(sql, name);
+    if positions.is_empty() {
+        return Err(Error::Config(format!(
+            "placeholder @{name} does not occur in the statement"
+        )));
+    }
+    Ok((name, positions))
+}
+
+/// Find the byte offsets of `@name` outside literals and comments.
+fn placeholder_positions(sql: &str, name: &str) -> Vec<usize> {
+    let bytes = sql.as_bytes();
+    let mut positions = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => i = skip_quoted(bytes, i, b'\''),
+            b'"' => i = skip_quoted(bytes, i, b'"'),
+            b'[' => i = skip_quoted(bytes, i, b']'),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            b'@' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || matches!(bytes[i], b'_' | b'@' | b'#' | b'$'))
+                {
+                    i += 1;
+                }
+                if sql[start + 1..i].eq_ignore_ascii_case(name) {
+                    positions.push(start);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    positions
+}
+
+/// Replace the `len` bytes at each of `positions` with `replacement`.
+fn splice(sql: &str, positions: &[usize], len: usize, replacement: &str) -> String {
+    let mut out = String::with_capacity(sql.len() + positions.len() * replacement.len());
+    let mut last = 0;
+    for &position in positions {
+        out.push_str(&sql[last..position]);
+        out.push_str(replacement);
+        last = position + len;
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_in_skips_literals_and_comments() {
+        let queries = expand_in(
+            "SELECT '@ids' AS [@ids] FROM t WHERE a IN (@IDS) -- @ids\n OR b IN (@ids) OR c = @idsx",
+            "ids",
+            &["x", "y"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(
+            queries[0].sql(),
+            "SELECT '@ids' AS [@ids] FROM t WHERE a IN (@p1, @p2) -- @ids\n \
+             OR b IN (@p1, @p2) OR c = @idsx"
+        );
+    }
+
+    #[test]
+    fn test_expand_in_chunks_at_limit() {
+        let values: Vec<i32> = (0..5000).collect();
+        let queries = expand_in(
+            "SELECT * FROM t WHERE id IN (@ids)",
+            "@ids",
+            &values,
+            &[&1, &2],
+        )
+        .unwrap();
+        assert_eq!(queries.len(), 3);
+        assert_eq!(queries[0].params().len(), MAX_PARAMS);
+        assert_eq!(queries[2].params().len(), 2 + 5000 - 2 * (MAX_PARAMS - 2));
+        assert!(
+            queries[1]
+                .sql()
+                .starts_with("SELECT * FROM t WHERE id IN (@p3, @p4,")
+        );
+    }
+
+    #[test]
+    fn test_expand_in_rejects_unsplittable() {
+        let values: Vec<i32> = (0..3000).collect();
+        for sql in [
+            "SELECT * FROM t WHERE id NOT IN (@ids)",
+            "DELETE FROM t WHERE id not  in  ( @ids )",
+            "SELECT TOP (10) * FROM t WHERE id IN (@ids)",
+            "SELECT DISTINCT a FROM t WHERE id IN (@ids)",
+            "SELECT a FROM t WHERE id IN (@ids) ORDER BY a",
+            "SELECT a, COUNT(*) FROM t WHERE id IN (@ids) GROUP BY a",
+            "SELECT SUM (a) FROM t WHERE id IN (@ids)",
+        ] {
+            assert!(
+                matches!(expand_in(sql, "@ids", &values, &[]), Err(Error::Config(_))),
+                "split: {sql}"
+            );
+            // One statement sees the whole list
+            assert_eq!(expand_in(sql, "@ids", &values[..10], &[]).unwrap().len(), 1);
+        }
+
+        // Only words outside literals and comments count
+        let sql = "SELECT [Count], 'TOP' FROM t -- ORDER BY\n WHERE id IN (@ids)";
+        assert_eq!(expand_in(sql, "@ids", &values, &[]).unwrap().len(), 2);
+
+        // Subqueries beside the list see every row
+        let sql = "SELECT n FROM (SELECT TOP (10) ROW_NUMBER() OVER (ORDER BY a) AS n FROM t) x \
+                   WHERE n > (SELECT MAX(b) FROM u) AND n IN (@ids)";
+        assert_eq!(expand_in(sql, "@ids", &values, &[]).unwrap().len(), 2);
+        let sql = "SELECT COUNT(*) FROM (SELECT a FROM t WHERE id IN (@ids)) x";
+        assert!(expand_in(sql, "@ids", &values, &[]).is_err());
+    }
+
+    #[test]
+    fn test_expand_in_tvp() {
+        let sql = "SELECT * FROM t WHERE tenant = @p1 AND id IN (@ids)";
+        let values: Vec<i64> = (0..3000).collect();
+        let list = expand_in_tvp(sql, "@ids", &values, &[&7]).unwrap().unwrap();
+        assert_eq!(
+            list.query.sql(),
+            "SELECT * FROM t WHERE tenant = @p1 AND id IN (SELECT [Value] FROM @p2)"
+        );
+        assert!(matches!(list.query.params[1], SqlValue::Tvp(ref tvp) if tvp.rows.len() == 3000));
+        assert!(
+            list.create_type
+                .contains("[dbo].[MssqlClientBigIntList] AS TABLE ([Value] BIGINT NULL)")
+        );
+
+        // Short lists stay parameters
+        assert!(
+            expand_in_tvp(sql, "@ids", &[1, 2], &[&7])
+                .unwrap()
+                .is_none()
+        );
+
+        // Mixed value types have no table type
+        let mixed: Vec<SqlValue> = (0..3000)
+            .map(|i| {
+                if i % 2 == 0 {
+                    SqlValue::Int(i)
+                } else {
+                    SqlValue::BigInt(i.into())
+                }
+            })
+            .collect();
+        assert!(expand_in_tvp(sql, "@ids", &mixed, &[&7]).unwrap().is_none());
+
+        // Only a placeholder that is the whole IN list becomes a subquery
+        for sql in [
+            "SELECT * FROM t WHERE id IN (@ids, 5)",
+            "SELECT * FROM t WHERE id IN (5, @ids)",
+            "SELECT * FROM t WHERE id = @ids",
+            "SELECT * FROM t WHERE id IN (@ids) OR id = @ids",
+            "SELECT * FROM t WHERE id JOIN (@ids)",
+        ] {
+            assert!(
+                expand_in_tvp(sql, "@ids", &values, &[]).unwrap().is_none(),
+                "{sql}"
+            );
+        }
+        assert!(
+            expand_in_tvp("DELETE t WHERE id not in(\n@ids )", "@ids", &values, &[])
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_expand_in_empty_and_errors() {
+        let queries =
+            expand_in::<i32>("SELECT * FROM t WHERE id IN (@ids)", "@ids", &[], &[]).unwrap();
+        assert_eq!(
+            queries[0].sql(),
+            "SELECT * FROM t WHERE id IN (SELECT NULL WHERE 1 = 0)"
+        );
+        let queries =
+            expand_in::<i32>("DELETE FROM t WHERE id NOT IN (@ids)", "@ids", &[], &[]).unwrap();
+        assert_eq!(
+            queries[0].sql(),
+            "DELETE FROM t WHERE id NOT IN (SELECT NULL WHERE 1 = 0)"
+        );
+
+        assert!(matches!(
+            expand_in("SELECT 1", "@ids", &[1], &[]),
+            Err(Error::Config(_))
+        ));
+        assert!(matches!(
+            expand_in("SELECT 1", "@ids); --", &[1], &[]),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+}
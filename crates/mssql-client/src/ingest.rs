@@ -0,0 +1,1359 @@
+//! CSV and Parquet/Arrow ingestion into bulk copy.
+//!
+//! [`Ingest`] reads rows from a CSV reader, an Arrow record batch reader,
+//! or a Parquet file, converts each value to the type of its target
+//! [`BulkColumn`], and writes the rows into a [`BulkInsert`]. Rows that
+//! cannot be converted are skipped and collected in an [`IngestReport`]
+//! until more than [`Ingest::max_errors`] rows have been rejected.
+//!
+//! Loading is incremental: [`IngestLoader::fill`] writes rows until the
+//! bulk insert reaches its batch size, so the packets of one batch can be
+//! sent before the next is read.
+//!
+//! ## Column Mapping
+//!
+//! Target columns take the source column of the same name (matched
+//! case-insensitively) unless mapped with [`Ingest::map_column`] or
+//! [`Ingest::map_column_index`]. CSV files without a header row must map
+//! every column by index.
+//!
+//! ## Type Coercion
+//!
+//! Values are converted to the target column type:
+//!
+//! - Text is parsed: integers, floats, decimals (rounded to the column
+//!   scale), booleans (see [`CoercionRules`]), GUIDs, hex binary (with or
+//!   without `0x`), ISO 8601 dates and times plus any extra formats
+//! - Numbers convert between integer, float and decimal types when the
+//!   value fits the target; fractional values are not truncated to integers
+//! - Arrow timestamps without a time zone load as `DATETIME2`; timestamps
+//!   with a time zone are UTC instants and load as `DATETIMEOFFSET` at
+//!   offset `+00:00`
+//! - Any value converts to text; strings longer than the column are
+//!   rejected rather than truncated
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use mssql_client::{BulkColumn, BulkInsert, Ingest};
+//!
+//! let columns = vec![
+//!     BulkColumn::new("id", "INT", 0),
+//!     BulkColumn::new("customer", "NVARCHAR(100)", 1),
+//!     BulkColumn::new("total", "DECIMAL(18,2)", 2),
+//! ];
+//!
+//! let mut loader = Ingest::new(&columns)
+//!     .map_column("customer", "Customer Name")
+//!     .max_errors(100)
+//!     .csv(csv::Reader::from_path("orders.csv")?)?;
+//!
+//! let mut bulk = BulkInsert::new(columns, 5000);
+//! while loader.fill(&mut bulk)? {
+//!     send(bulk.take_packets()).await?;
+//! }
+//! send(bulk.finish_packets()).await?;
+//!
+//! for error in &loader.report().errors {
+//!     eprintln!("{error}");
+//! }
+//! ```
+
+use std::fmt;
+use std::io::Read;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Date32Type, Date64Type, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+    Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, DataType, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use mssql_types::{FromSql, SqlValue};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::reader::ChunkReader;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+use crate::bulk::{BulkColumn, BulkInsert};
+use crate::error::{Error, Result};
+
+/// Rules for converting text values.
+#[derive(Debug, Clone)]
+pub struct CoercionRules {
+    /// CSV fields that load as NULL.
+    ///
+    /// Default: the empty string.
+    pub null_values: Vec<String>,
+
+    /// Trim leading and trailing whitespace from CSV fields.
+    ///
+    /// Default: true
+    pub trim: bool,
+
+    /// Text accepted as `true` for BIT columns, compared case-insensitively.
+    ///
+    /// Default: `1`, `true`, `t`, `yes`, `y`
+    pub true_values: Vec<String>,
+
+    /// Text accepted as `false` for BIT columns, compared case-insensitively.
+    ///
+    /// Default: `0`, `false`, `f`, `no`, `n`
+    pub false_values: Vec<String>,
+
+    /// Extra `chrono` format strings tried after ISO 8601 when parsing
+    /// dates, times and timestamps (e.g. `"%d/%m/%Y"`).
+    ///
+    /// Default: none
+    pub datetime_formats: Vec<String>,
+}
+
+impl Default for CoercionRules {
+    fn default() -> Self {
+        Self {
+            null_values: vec![String::new()],
+            trim: true,
+            true_values: ["1", "true", "t", "yes", "y"].map(String::from).to_vec(),
+            false_values: ["0", "false", "f", "no", "n"].map(String::from).to_vec(),
+            datetime_formats: Vec::new(),
+        }
+    }
+}
+
+/// A source row that was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// 1-based number of the data row in the source (a CSV header row is
+    /// not counted).
+    pub row: u64,
+    /// Target column the error refers to, if any.
+    pub column: Option<String>,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.column {
+            Some(column) => write!(f, "row {}, column '{}': {}", self.row, column, self.message),
+            None => write!(f, "row {}: {}", self.row, self.message),
+        }
+    }
+}
+
+/// Outcome of an ingestion.
+#[derive(Debug, Clone, Default)]
+pub struct IngestReport {
+    /// Number of data rows read from the source.
+    pub rows_read: u64,
+    /// Number of rows written to the bulk insert.
+    pub rows_loaded: u64,
+    /// Rows that were skipped, in source order.
+    pub errors: Vec<RowError>,
+}
+
+impl IngestReport {
+    /// Number of rows that were skipped.
+    #[must_use]
+    pub fn rows_rejected(&self) -> usize {
+        self.errors.len()
+    }
+}
+
+/// Source of the values of a target column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SourceColumn {
+    Name(String),
+    Index(usize),
+}
+
+/// Builder for loading CSV or Arrow data into a bulk insert.
+///
+/// See the [module documentation](self) for column mapping and coercion.
+#[derive(Debug, Clone)]
+pub struct Ingest {
+    columns: Vec<BulkColumn>,
+    mappings: Vec<(String, SourceColumn)>,
+    rules: CoercionRules,
+    max_errors: usize,
+}
+
+impl Ingest {
+    /// Create an ingestion into the given bulk insert columns.
+    ///
+    /// The columns must be the ones the [`BulkInsert`] was created with.
+    pub fn new(columns: &[BulkColumn]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            mappings: Vec::new(),
+            rules: CoercionRules::default(),
+            max_errors: 0,
+        }
+    }
+
+    /// Load the target column from the source column with this name.
+    #[must_use]
+    pub fn map_column(mut self, target: impl Into<String>, source: impl Into<String>) -> Self {
+        self.mappings
+            .push((target.into(), SourceColumn::Name(source.into())));
+        self
+    }
+
+    /// Load the target column from the source column at this 0-based index.
+    #[must_use]
+    pub fn map_column_index(mut self, target: impl Into<String>, index: usize) -> Self {
+        self.mappings
+            .push((target.into(), SourceColumn::Index(index)));
+        self
+    }
+
+    /// Set the text coercion rules.
+    #[must_use]
+    pub fn coercion(mut self, rules: CoercionRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Set how many rows may be rejected before the ingestion fails.
+    ///
+    /// Default: 0 (fail on the first rejected row).
+    #[must_use]
+    pub fn max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Read rows from a CSV reader.
+    ///
+    /// Columns are matched by the header row when the reader has one.
+    pub fn csv<R: Read + Send + 'static>(
+        &self,
+        mut reader: csv::Reader<R>,
+    ) -> Result<IngestLoader> {
+        let names = if reader.has_headers() {
+            let headers = reader
+                .headers()
+                .map_err(|e| Error::Ingest(format!("failed to read CSV header: {e}")))?;
+            Some(headers.iter().map(str::to_string).collect::<Vec<_>>())
+        } else {
+            None
+        };
+        let indexes = self.resolve(names.as_deref(), "CSV")?;
+        Ok(self.loader(Box::new(CsvSource {
+            records: reader.into_records(),
+            indexes,
+            rules: self.rules.clone(),
+        })))
+    }
+
+    /// Read rows from an Arrow record batch reader.
+    pub fn arrow<I>(&self, reader: I) -> Result<IngestLoader>
+    where
+        I: RecordBatchReader + Send + 'static,
+    {
+        let schema = reader.schema();
+        let names: Vec<String> = schema.fields().iter().map(|f| f.name().clone()).collect();
+        let indexes = self.resolve(Some(&names), "Arrow")?;
+        for (column, &index) in self.columns.iter().zip(&indexes) {
+            let data_type = schema.field(index).data_type();
+            if !is_supported(data_type) {
+                return Err(Error::Ingest(format!(
+                    "unsupported Arrow type {data_type} for column '{}'",
+                    column.name
+                )));
+            }
+        }
+        Ok(self.loader(Box::new(ArrowSource {
+            reader: Box::new(reader),
+            batch: None,
+            position: 0,
+            indexes,
+        })))
+    }
+
+    /// Read rows from a Parquet file.
+    ///
+    /// `reader` is typically a [`std::fs::File`] or [`Bytes`].
+    pub fn parquet<R: ChunkReader + 'static>(&self, reader: R) -> Result<IngestLoader> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(reader)
+            .and_then(|builder| builder.build())
+            .map_err(|e| Error::Ingest(format!("failed to open Parquet file: {e}")))?;
+        self.arrow(reader)
+    }
+
+    fn loader(&self, source: Box<dyn RowSource>) -> IngestLoader {
+        IngestLoader {
+            source,
+            targets: self.columns.iter().map(Target::of).collect(),
+            columns: self.columns.clone(),
+            rules: self.rules.clone(),
+            max_errors: self.max_errors,
+            report: IngestReport::default(),
+        }
+    }
+
+    /// Find the source column index of every target column.
+    fn resolve(&self, names: Option<&[String]>, format: &str) -> Result<Vec<usize>> {
+        for (target, _) in &self.mappings {
+            if !self
+                .columns
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(target))
+            {
+                return Err(Error::Ingest(format!(
+                    "mapped column '{target}' is not a bulk insert column"
+                )));
+            }
+        }
+
+        self.columns
+            .iter()
+            .map(|column| {
+                let source = self
+                    .mappings
+                    .iter()
+                    .rev()
+                    .find(|(target, _)| target.eq_ignore_ascii_case(&column.name))
+                    .map(|(_, source)| source.clone())
+                    .unwrap_or_else(|| SourceColumn::Name(column.name.clone()));
+
+                match source {
+                    SourceColumn::Index(index) => match names {
+                        Some(names) if index >= names.len() => Err(Error::Ingest(format!(
+                            "column '{}' is mapped to index {index} but the {format} source has {} columns",
+                            column.name,
+                            names.len()
+                        ))),
+                        _ => Ok(index),
+                    },
+                    SourceColumn::Name(name) => {
+                        let names = names.ok_or_else(|| {
+                            Error::Ingest(format!(
+                                "{format} source has no header row; map column '{}' by index",
+                                column.name
+                            ))
+                        })?;
+                        names
+                            .iter()
+                            .position(|n| *n == name)
+                            .or_else(|| names.iter().position(|n| n.eq_ignore_ascii_case(&name)))
+                            .ok_or_else(|| {
+                                Error::Ingest(format!(
+                                    "{format} source has no column '{name}' for column '{}' (available: {})",
+                                    column.name,
+                                    names.join(", ")
+                                ))
+                            })
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// An ingestion in progress.
+///
+/// Created by [`Ingest::csv`], [`Ingest::arrow`] or [`Ingest::parquet`].
+pub struct IngestLoader {
+    source: Box<dyn RowSource>,
+    columns: Vec<BulkColumn>,
+    targets: Vec<Target>,
+    rules: CoercionRules,
+    max_errors: usize,
+    report: IngestReport,
+}
+
+impl IngestLoader {
+    /// Write source rows into the bulk insert until it needs a flush.
+    ///
+    /// Returns `true` when the bulk insert reached its batch size and more
+    /// rows may follow; take its packets before calling `fill` again.
+    /// Returns `false` once the source is exhausted. With a batch size of
+    /// 0 the whole source is loaded in one call.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the source cannot be read, or when more than
+    /// `max_errors` rows have been rejected.
+    pub fn fill(&mut self, bulk: &mut BulkInsert) -> Result<bool> {
+        loop {
+            if bulk.should_flush() {
+                return Ok(true);
+            }
+            let Some(row) = self.source.next_row()? else {
+                return Ok(false);
+            };
+            self.report.rows_read += 1;
+            let number = self.report.rows_read;
+
+            let loaded = match row {
+                SourceRow::Malformed { column, message } => Err(RowError {
+                    row: number,
+                    column: column.map(|i| self.columns[i].name.clone()),
+                    message,
+                }),
+                SourceRow::Values(values) => self.coerce_row(number, values).and_then(|values| {
+                    bulk.send_row_values(&values).map_err(|e| RowError {
+                        row: number,
+                        column: None,
+                        message: e.to_string(),
+                    })
+                }),
+            };
+
+            match loaded {
+                Ok(()) => self.report.rows_loaded += 1,
+                Err(error) => self.reject(error)?,
+            }
+        }
+    }
+
+    /// The progress of the ingestion so far.
+    #[must_use]
+    pub fn report(&self) -> &IngestReport {
+        &self.report
+    }
+
+    /// Finish the ingestion, returning its report.
+    #[must_use]
+    pub fn into_report(self) -> IngestReport {
+        self.report
+    }
+
+    fn coerce_row(
+        &self,
+        number: u64,
+        values: Vec<SqlValue>,
+    ) -> std::result::Result<Vec<SqlValue>, RowError> {
+        values
+            .into_iter()
+            .zip(self.columns.iter().zip(&self.targets))
+            .map(|(value, (column, target))| {
+                coerce(value, column, *target, &self.rules).map_err(|message| RowError {
+                    row: number,
+                    column: Some(column.name.clone()),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    fn reject(&mut self, error: RowError) -> Result<()> {
+        tracing::debug!(%error, "ingest row rejected");
+        self.report.errors.push(error);
+        if self.report.errors.len() > self.max_errors {
+            let last = self.report.errors.last().map(ToString::to_string);
+            return Err(Error::Ingest(format!(
+                "{} rows rejected, more than max_errors ({}); last: {}",
+                self.report.errors.len(),
+                self.max_errors,
+                last.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for IngestLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IngestLoader")
+            .field("columns", &self.columns)
+            .field("max_errors", &self.max_errors)
+            .field("report", &self.report)
+            .finish_non_exhaustive()
+    }
+}
+
+// =============================================================================
+// Sources
+// =============================================================================
+
+/// A row read from a source, projected onto the target columns.
+enum SourceRow {
+    Values(Vec<SqlValue>),
+    /// A row that could not be read; `column` is a target column index.
+    Malformed {
+        column: Option<usize>,
+        message: String,
+    },
+}
+
+trait RowSource: Send {
+    /// Read the next row, failing only when the source itself is unusable.
+    fn next_row(&mut self) -> Result<Option<SourceRow>>;
+}
+
+struct CsvSource<R> {
+    records: csv::StringRecordsIntoIter<R>,
+    indexes: Vec<usize>,
+    rules: CoercionRules,
+}
+
+impl<R: Read + Send> RowSource for CsvSource<R> {
+    fn next_row(&mut self) -> Result<Option<SourceRow>> {
+        let record = match self.records.next() {
+            None => return Ok(None),
+            Some(Ok(record)) => record,
+            Some(Err(e)) if e.is_io_error() => {
+                return Err(Error::Ingest(format!("failed to read CSV: {e}")));
+            }
+            Some(Err(e)) => {
+                return Ok(Some(SourceRow::Malformed {
+                    column: None,
+                    message: e.to_string(),
+                }));
+            }
+        };
+
+        let mut values = Vec::with_capacity(self.indexes.len());
+        for (column, &index) in self.indexes.iter().enumerate() {
+            let Some(field) = record.get(index) else {
+                return Ok(Some(SourceRow::Malformed {
+                    column: Some(column),
+                    message: format!("row has {} fields, no field {index}", record.len()),
+                }));
+            };
+            let field = if self.rules.trim { field.trim() } else { field };
+            values.push(if self.rules.null_values.iter().any(|n| n == field) {
+                SqlValue::Null
+            } else {
+                SqlValue::String(field.to_string())
+            });
+        }
+        Ok(Some(SourceRow::Values(values)))
+    }
+}
+
+type BatchReader = Box<dyn Iterator<Item = std::result::Result<RecordBatch, ArrowError>> + Send>;
+
+struct ArrowSource {
+    reader: BatchReader,
+    batch: Option<RecordBatch>,
+    position: usize,
+    indexes: Vec<usize>,
+}
+
+impl RowSource for ArrowSource {
+    fn next_row(&mut self) -> Result<Option<SourceRow>> {
+        loop {
+            if let Some(batch) = &self.batch {
+                if self.position < batch.num_rows() {
+                    break;
+                }
+            }
+            match self.reader.next() {
+                None => return Ok(None),
+                Some(Ok(batch)) => {
+                    self.batch = Some(batch);
+                    self.position = 0;
+                }
+                Some(Err(e)) => {
+                    return Err(Error::Ingest(format!("failed to read record batch: {e}")));
+                }
+            }
+        }
+
+        let Some(batch) = &self.batch else {
+            return Ok(None);
+        };
+        let row = self.position;
+        self.position += 1;
+
+        let mut values = Vec::with_capacity(self.indexes.len());
+        for (column, &index) in self.indexes.iter().enumerate() {
+            match arrow_value(batch.column(index), row) {
+                Ok(value) => values.push(value),
+                Err(message) => {
+                    return Ok(Some(SourceRow::Malformed {
+                        column: Some(column),
+                        message,
+                    }));
+                }
+            }
+        }
+        Ok(Some(SourceRow::Values(values)))
+    }
+}
+
+/// Check whether values of an Arrow type can be read.
+fn is_supported(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Null
+            | DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Decimal128(_, _)
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Utf8View
+            | DataType::Binary
+            | DataType::LargeBinary
+            | DataType::BinaryView
+            | DataType::FixedSizeBinary(_)
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Time32(TimeUnit::Second | TimeUnit::Millisecond)
+            | DataType::Time64(TimeUnit::Microsecond | TimeUnit::Nanosecond)
+            | DataType::Timestamp(_, _)
+    )
+}
+
+/// Read one value of an Arrow array.
+fn arrow_value(array: &ArrayRef, row: usize) -> std::result::Result<SqlValue, String> {
+    if array.is_null(row) {
+        return Ok(SqlValue::Null);
+    }
+
+    let value = match array.data_type() {
+        DataType::Boolean => SqlValue::Bool(array.as_boolean().value(row)),
+        DataType::Int8 => SqlValue::SmallInt(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => SqlValue::SmallInt(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => SqlValue::Int(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => SqlValue::BigInt(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => SqlValue::TinyInt(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => SqlValue::Int(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => SqlValue::BigInt(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => {
+            let v = array.as_primitive::<UInt64Type>().value(row);
+            i64::try_from(v)
+                .map(SqlValue::BigInt)
+                .unwrap_or_else(|_| SqlValue::Decimal(Decimal::from(v)))
+        }
+        DataType::Float32 => SqlValue::Float(array.as_primitive::<Float32Type>().value(row)),
+        DataType::Float64 => SqlValue::Double(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Decimal128(_, scale) => {
+            let v = array
+                .as_primitive::<arrow_array::types::Decimal128Type>()
+                .value(row);
+            let scale = u32::try_from(*scale).map_err(|_| "negative decimal scale".to_string())?;
+            Decimal::try_from_i128_with_scale(v, scale)
+                .map(SqlValue::Decimal)
+                .map_err(|e| format!("decimal out of range: {e}"))?
+        }
+        DataType::Utf8 => SqlValue::String(array.as_string::<i32>().value(row).to_string()),
+        DataType::LargeUtf8 => SqlValue::String(array.as_string::<i64>().value(row).to_string()),
+        DataType::Utf8View => SqlValue::String(array.as_string_view().value(row).to_string()),
+        DataType::Binary => {
+            SqlValue::Binary(Bytes::copy_from_slice(array.as_binary::<i32>().value(row)))
+        }
+        DataType::LargeBinary => {
+            SqlValue::Binary(Bytes::copy_from_slice(array.as_binary::<i64>().value(row)))
+        }
+        DataType::BinaryView => {
+            SqlValue::Binary(Bytes::copy_from_slice(array.as_binary_view().value(row)))
+        }
+        DataType::FixedSizeBinary(_) => SqlValue::Binary(Bytes::copy_from_slice(
+            array.as_fixed_size_binary().value(row),
+        )),
+        DataType::Date32 => SqlValue::Date(temporal(
+            array.as_primitive::<Date32Type>().value_as_date(row),
+        )?),
+        DataType::Date64 => SqlValue::Date(temporal(
+            array.as_primitive::<Date64Type>().value_as_date(row),
+        )?),
+        DataType::Time32(TimeUnit::Second) => SqlValue::Time(temporal(
+            array.as_primitive::<Time32SecondType>().value_as_time(row),
+        )?),
+        DataType::Time32(TimeUnit::Millisecond) => SqlValue::Time(temporal(
+            array
+                .as_primitive::<Time32MillisecondType>()
+                .value_as_time(row),
+        )?),
+        DataType::Time64(TimeUnit::Microsecond) => SqlValue::Time(temporal(
+            array
+                .as_primitive::<Time64MicrosecondType>()
+                .value_as_time(row),
+        )?),
+        DataType::Time64(TimeUnit::Nanosecond) => SqlValue::Time(temporal(
+            array
+                .as_primitive::<Time64NanosecondType>()
+                .value_as_time(row),
+        )?),
+        DataType::Timestamp(unit, tz) => {
+            let datetime = temporal(match unit {
+                TimeUnit::Second => array
+                    .as_primitive::<TimestampSecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Millisecond => array
+                    .as_primitive::<TimestampMillisecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Microsecond => array
+                    .as_primitive::<TimestampMicrosecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Nanosecond => array
+                    .as_primitive::<TimestampNanosecondType>()
+                    .value_as_datetime(row),
+            })?;
+            if tz.is_some() {
+                SqlValue::DateTimeOffset(
+                    DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc).fixed_offset(),
+                )
+            } else {
+                SqlValue::DateTime(datetime)
+            }
+        }
+        other => return Err(format!("unsupported Arrow type {other}")),
+    };
+    Ok(value)
+}
+
+fn temporal<T>(value: Option<T>) -> std::result::Result<T, String> {
+    value.ok_or_else(|| "date or time out of range".to_string())
+}
+
+// =============================================================================
+// Coercion
+// =============================================================================
+
+/// Value kind a bulk column expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Bit,
+    TinyInt,
+    SmallInt,
+    Int,
+    BigInt,
+    Real,
+    Float,
+    Decimal {
+        precision: u8,
+        scale: u8,
+    },
+    Guid,
+    Date,
+    Time,
+    DateTime,
+    DateTimeOffset,
+    /// Text with a maximum length in characters (`None` for MAX).
+    Text {
+        max_chars: Option<u32>,
+    },
+    /// Binary with a maximum length in bytes (`None` for MAX).
+    Binary {
+        max_bytes: Option<u32>,
+    },
+}
+
+impl Target {
+    fn of(column: &BulkColumn) -> Self {
+        let limit = |divisor: u32| {
+            column
+                .max_length()
+                .filter(|&len| len != 0xFFFF && len < 0x7FFF_FFFF)
+                .map(|len| len / divisor)
+        };
+        match column.type_id() {
+            0x32 => Self::Bit,
+            0x30 => Self::TinyInt,
+            0x34 => Self::SmallInt,
+            0x38 => Self::Int,
+            0x7F => Self::BigInt,
+            0x3B => Self::Real,
+            0x3E => Self::Float,
+            0x6C | 0x6A => {
+                let (precision, scale) = column.precision_scale();
+                Self::Decimal {
+                    precision: precision.unwrap_or(18),
+                    scale: scale.unwrap_or(0),
+                }
+            }
+            0x3C => Self::Decimal {
+                precision: 19,
+                scale: 4,
+            },
+            0x7A => Self::Decimal {
+                precision: 10,
+                scale: 4,
+            },
+            0x24 => Self::Guid,
+            0x28 => Self::Date,
+            0x29 => Self::Time,
+            0x3D | 0x3F | 0x2A => Self::DateTime,
+            0x2B => Self::DateTimeOffset,
+            0xA5 | 0x22 => Self::Binary {
+                max_bytes: limit(1),
+            },
+            0xE7 => Self::Text {
+                max_chars: limit(2),
+            },
+            _ => Self::Text {
+                max_chars: limit(1),
+            },
+        }
+    }
+}
+
+/// Convert a source value to the type of a bulk column.
+fn coerce(
+    value: SqlValue,
+    column: &BulkColumn,
+    target: Target,
+    rules: &CoercionRules,
+) -> std::result::Result<SqlValue, String> {
+    if value.is_null() {
+        return if column.nullable {
+            Ok(SqlValue::Null)
+        } else {
+            Err("NULL in a non-nullable column".to_string())
+        };
+    }
+
+    match target {
+        Target::Bit => match &value {
+            SqlValue::Bool(v) => Ok(SqlValue::Bool(*v)),
+            SqlValue::String(s) => {
+                let s = s.trim();
+                if rules.true_values.iter().any(|t| t.eq_ignore_ascii_case(s)) {
+                    Ok(SqlValue::Bool(true))
+                } else if rules.false_values.iter().any(|f| f.eq_ignore_ascii_case(s)) {
+                    Ok(SqlValue::Bool(false))
+                } else {
+                    Err(format!("invalid boolean '{s}'"))
+                }
+            }
+            _ => match integer(&value)? {
+                0 => Ok(SqlValue::Bool(false)),
+                1 => Ok(SqlValue::Bool(true)),
+                v => Err(format!("{v} is not a valid BIT value")),
+            },
+        },
+        Target::TinyInt => narrow(integer(&value)?, "TINYINT").map(SqlValue::TinyInt),
+        Target::SmallInt => narrow(integer(&value)?, "SMALLINT").map(SqlValue::SmallInt),
+        Target::Int => narrow(integer(&value)?, "INT").map(SqlValue::Int),
+        Target::BigInt => narrow(integer(&value)?, "BIGINT").map(SqlValue::BigInt),
+        Target::Real => Ok(SqlValue::Float(float(&value)? as f32)),
+        Target::Float => Ok(SqlValue::Double(float(&value)?)),
+        Target::Decimal { precision, scale } => {
+            let mut d = match &value {
+                SqlValue::Decimal(d) => *d,
+                SqlValue::Float(_) | SqlValue::Double(_) => {
+                    let f = float(&value)?;
+                    Decimal::from_f64(f).ok_or_else(|| format!("{f} is out of DECIMAL range"))?
+                }
+                SqlValue::String(s) => {
+                    let s = s.trim();
+                    s.parse::<Decimal>()
+                        .or_else(|_| Decimal::from_scientific(s))
+                        .map_err(|_| format!("invalid decimal '{s}'"))?
+                }
+                _ => Decimal::from_i128_with_scale(integer(&value)?, 0),
+            };
+            d.rescale(u32::from(scale));
+            let digits = d
+                .mantissa()
+                .unsigned_abs()
+                .checked_ilog10()
+                .map_or(1, |n| n + 1);
+            if digits > u32::from(precision) {
+                return Err(format!("{d} does not fit DECIMAL({precision},{scale})"));
+            }
+            Ok(SqlValue::Decimal(d))
+        }
+        Target::Guid => match value {
+            SqlValue::String(s) => uuid_value(&SqlValue::String(s.trim().to_string())),
+            other => uuid_value(&other),
+        },
+        Target::Date => match &value {
+            SqlValue::Date(d) => Ok(SqlValue::Date(*d)),
+            SqlValue::DateTime(dt) => Ok(SqlValue::Date(dt.date())),
+            SqlValue::String(s) => parse_date(s.trim(), rules).map(SqlValue::Date),
+            _ => Err(mismatch(&value, "DATE")),
+        },
+        Target::Time => match &value {
+            SqlValue::Time(t) => Ok(SqlValue::Time(*t)),
+            SqlValue::DateTime(dt) => Ok(SqlValue::Time(dt.time())),
+            SqlValue::String(s) => parse_time(s.trim(), rules).map(SqlValue::Time),
+            _ => Err(mismatch(&value, "TIME")),
+        },
+        Target::DateTime => match &value {
+            SqlValue::DateTime(dt) => Ok(SqlValue::DateTime(*dt)),
+            SqlValue::Date(d) => Ok(SqlValue::DateTime(d.and_time(NaiveTime::MIN))),
+            SqlValue::DateTimeOffset(dto) => Ok(SqlValue::DateTime(dto.naive_utc())),
+            SqlValue::String(s) => parse_datetime(s.trim(), rules).map(SqlValue::DateTime),
+            _ => Err(mismatch(&value, "DATETIME2")),
+        },
+        Target::DateTimeOffset => match &value {
+            SqlValue::DateTimeOffset(dto) => Ok(SqlValue::DateTimeOffset(*dto)),
+            SqlValue::String(s) => {
+                parse_datetimeoffset(s.trim(), rules).map(SqlValue::DateTimeOffset)
+            }
+            _ => Err(mismatch(&value, "DATETIMEOFFSET")),
+        },
+        Target::Text { max_chars } => {
+            let text = text(value)?;
+            let chars = if matches!(column.type_id(), 0xE7 | 0x63) {
+                text.encode_utf16().count()
+            } else {
+                text.chars().count()
+            };
+            match max_chars {
+                Some(max) if chars > max as usize => Err(format!(
+                    "text of length {chars} exceeds the column length {max}"
+                )),
+                _ => Ok(SqlValue::String(text)),
+            }
+        }
+        Target::Binary { max_bytes } => {
+            let bytes = match value {
+                SqlValue::Binary(b) => b,
+                SqlValue::String(s) => Bytes::from(parse_hex(s.trim())?),
+                other => return Err(mismatch(&other, "VARBINARY")),
+            };
+            match max_bytes {
+                Some(max) if bytes.len() > max as usize => Err(format!(
+                    "binary of length {} exceeds the column length {max}",
+                    bytes.len()
+                )),
+                _ => Ok(SqlValue::Binary(bytes)),
+            }
+        }
+    }
+}
+
+fn mismatch(value: &SqlValue, target: &str) -> String {
+    format!("cannot convert {} to {target}", value.type_name())
+}
+
+/// Read an integral value; fractional numbers are rejected.
+fn integer(value: &SqlValue) -> std::result::Result<i128, String> {
+    let whole = |f: f64| {
+        if f.is_finite() && f.fract() == 0.0 && f.abs() < 1e38 {
+            Ok(f as i128)
+        } else {
+            Err(format!("{f} is not an integer"))
+        }
+    };
+    match value {
+        SqlValue::Bool(v) => Ok(i128::from(*v)),
+        SqlValue::TinyInt(v) => Ok(i128::from(*v)),
+        SqlValue::SmallInt(v) => Ok(i128::from(*v)),
+        SqlValue::Int(v) => Ok(i128::from(*v)),
+        SqlValue::BigInt(v) => Ok(i128::from(*v)),
+        SqlValue::Float(v) => whole(f64::from(*v)),
+        SqlValue::Double(v) => whole(*v),
+        SqlValue::Decimal(d) if d.fract().is_zero() => {
+            d.to_i128().ok_or_else(|| format!("{d} is out of range"))
+        }
+        SqlValue::Decimal(d) => Err(format!("{d} is not an integer")),
+        SqlValue::String(s) => {
+            let s = s.trim();
+            s.parse::<i128>()
+                .map_err(|_| format!("invalid integer '{s}'"))
+        }
+        other => Err(mismatch(other, "an integer")),
+    }
+}
+
+fn narrow<T: TryFrom<i128>>(value: i128, target: &str) -> std::result::Result<T, String> {
+    T::try_from(value).map_err(|_| format!("{value} is out of {target} range"))
+}
+
+fn float(value: &SqlValue) -> std::result::Result<f64, String> {
+    match value {
+        SqlValue::Float(v) => Ok(f64::from(*v)),
+        SqlValue::Double(v) => Ok(*v),
+        SqlValue::Decimal(d) => d.to_f64().ok_or_else(|| format!("{d} is out of range")),
+        SqlValue::String(s) => {
+            let s = s.trim();
+            s.parse::<f64>()
+                .map_err(|_| format!("invalid number '{s}'"))
+        }
+        other => integer(other).map(|v| v as f64),
+    }
+}
+
+fn uuid_value(value: &SqlValue) -> std::result::Result<SqlValue, String> {
+    FromSql::from_sql(value)
+        .map(SqlValue::Uuid)
+        .map_err(|e| e.to_string())
+}
+
+/// Render any value as text.
+fn text(value: SqlValue) -> std::result::Result<String, String> {
+    Ok(match value {
+        SqlValue::String(s) | SqlValue::Xml(s) => s,
+        SqlValue::Bool(v) => if v { "1" } else { "0" }.to_string(),
+        SqlValue::TinyInt(v) => v.to_string(),
+        SqlValue::SmallInt(v) => v.to_string(),
+        SqlValue::Int(v) => v.to_string(),
+        SqlValue::BigInt(v) => v.to_string(),
+        SqlValue::Float(v) => v.to_string(),
+        SqlValue::Double(v) => v.to_string(),
+        SqlValue::Decimal(v) => v.to_string(),
+        SqlValue::Uuid(v) => v.to_string(),
+        SqlValue::Date(v) => v.format("%Y-%m-%d").to_string(),
+        SqlValue::Time(v) => v.format("%H:%M:%S%.f").to_string(),
+        SqlValue::DateTime(v) => v.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+        SqlValue::DateTimeOffset(v) => v.format("%Y-%m-%d %H:%M:%S%.f %:z").to_string(),
+        SqlValue::Binary(b) => {
+            let mut hex = String::with_capacity(2 + b.len() * 2);
+            hex.push_str("0x");
+            for byte in b.iter() {
+                hex.push_str(&format!("{byte:02X}"));
+            }
+            hex
+        }
+        other => return Err(mismatch(&other, "text")),
+    })
+}
+
+fn parse_hex(s: &str) -> std::result::Result<Vec<u8>, String> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    if digits.len() % 2 != 0 || !digits.is_ascii() {
+        return Err(format!("invalid hex binary '{s}'"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex binary '{s}'"))
+        })
+        .collect()
+}
+
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+fn parse_date(s: &str, rules: &CoercionRules) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .or_else(|| {
+            rules
+                .datetime_formats
+                .iter()
+                .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+        })
+        .ok_or_else(|| format!("invalid date '{s}'"))
+}
+
+fn parse_time(s: &str, rules: &CoercionRules) -> std::result::Result<NaiveTime, String> {
+    ["%H:%M:%S%.f", "%H:%M"]
+        .iter()
+        .copied()
+        .chain(rules.datetime_formats.iter().map(String::as_str))
+        .find_map(|f| NaiveTime::parse_from_str(s, f).ok())
+        .ok_or_else(|| format!("invalid time '{s}'"))
+}
+
+fn parse_datetime(s: &str, rules: &CoercionRules) -> std::result::Result<NaiveDateTime, String> {
+    DATETIME_FORMATS
+        .iter()
+        .copied()
+        .chain(rules.datetime_formats.iter().map(String::as_str))
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| {
+            parse_date(s, rules)
+                .ok()
+                .map(|d| d.and_time(NaiveTime::MIN))
+        })
+        .ok_or_else(|| format!("invalid datetime '{s}'"))
+}
+
+fn parse_datetimeoffset(
+    s: &str,
+    rules: &CoercionRules,
+) -> std::result::Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f %:z"]
+                .iter()
+                .copied()
+                .chain(rules.datetime_formats.iter().map(String::as_str))
+                .find_map(|f| DateTime::parse_from_str(s, f).ok())
+        })
+        .ok_or_else(|| format!("invalid datetimeoffset '{s}'"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Int64Array, RecordBatchIterator, StringArray, TimestampMillisecondArray};
+    use arrow_schema::{Field, Schema};
+
+    use super::*;
+
+    fn columns() -> Vec<BulkColumn> {
+        vec![
+            BulkColumn::new("id", "INT", 0).with_nullable(false),
+            BulkColumn::new("name", "NVARCHAR(5)", 1),
+            BulkColumn::new("total", "DECIMAL(6,2)", 2),
+        ]
+    }
+
+    fn load(loader: &mut IngestLoader, columns: Vec<BulkColumn>) -> Result<BulkInsert> {
+        let mut bulk = BulkInsert::new(columns, 2);
+        while loader.fill(&mut bulk)? {
+            bulk.take_packets();
+        }
+        Ok(bulk)
+    }
+
+    #[test]
+    fn test_csv_ingest_collects_row_errors() {
+        let data = "ID,Name,Total\n1,ann,10.5\n2,bob,x\n,cy,1\n4,toolong,2\n5, ed ,3.456\n6,,\n";
+        let mut loader = Ingest::new(&columns())
+            .max_errors(3)
+            .csv(csv::Reader::from_reader(data.as_bytes()))
+            .unwrap();
+        let bulk = load(&mut loader, columns()).unwrap();
+
+        let report = loader.into_report();
+        assert_eq!(report.rows_read, 6);
+        assert_eq!(report.rows_loaded, 3);
+        assert_eq!(bulk.total_rows(), 3);
+        let rejected: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.row, e.column.as_deref()))
+            .collect();
+        assert_eq!(
+            rejected,
+            [(2, Some("total")), (3, Some("id")), (4, Some("name"))]
+        );
+        assert_eq!(
+            report.errors[0].to_string(),
+            "row 2, column 'total': invalid decimal 'x'"
+        );
+    }
+
+    #[test]
+    fn test_csv_ingest_max_errors_exceeded() {
+        let data = "id,name,total\nx,a,1\ny,b,2\n";
+        let mut loader = Ingest::new(&columns())
+            .max_errors(1)
+            .csv(csv::Reader::from_reader(data.as_bytes()))
+            .unwrap();
+        assert!(matches!(
+            load(&mut loader, columns()),
+            Err(Error::Ingest(_))
+        ));
+        assert_eq!(loader.report().rows_rejected(), 2);
+    }
+
+    #[test]
+    fn test_csv_mapping() {
+        let data = "7,amount,label\n1,2.5,a\n";
+        let reader = || {
+            csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(data.as_bytes())
+        };
+
+        let err = Ingest::new(&columns()).csv(reader()).unwrap_err();
+        assert!(err.to_string().contains("map column 'id' by index"));
+
+        let mut loader = Ingest::new(&columns())
+            .map_column_index("id", 0)
+            .map_column_index("NAME", 2)
+            .map_column_index("total", 1)
+            .max_errors(1)
+            .csv(reader())
+            .unwrap();
+        load(&mut loader, columns()).unwrap();
+        let report = loader.into_report();
+        assert_eq!((report.rows_read, report.rows_loaded), (2, 1));
+
+        let with_headers = "Key,Customer,Amount\n1,a,2\n";
+        let err = Ingest::new(&columns())
+            .map_column("id", "Key")
+            .csv(csv::Reader::from_reader(with_headers.as_bytes()))
+            .unwrap_err();
+        assert!(err.to_string().contains("available: Key, Customer, Amount"));
+
+        let err = Ingest::new(&columns())
+            .map_column("missing", "Key")
+            .csv(csv::Reader::from_reader(with_headers.as_bytes()))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'missing' is not a bulk insert column")
+        );
+    }
+
+    #[test]
+    fn test_arrow_ingest() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("at", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 5_000_000_000])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(TimestampMillisecondArray::from(vec![Some(0), None])),
+            ],
+        )
+        .unwrap();
+        let columns = vec![
+            BulkColumn::new("id", "INT", 0),
+            BulkColumn::new("name", "NVARCHAR(10)", 1),
+            BulkColumn::new("at", "DATETIME2", 2),
+        ];
+
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let mut loader = Ingest::new(&columns).max_errors(1).arrow(reader).unwrap();
+        let mut bulk = BulkInsert::new(columns, 0);
+        assert!(!loader.fill(&mut bulk).unwrap());
+
+        let report = loader.into_report();
+        assert_eq!(report.rows_loaded, 1);
+        assert_eq!(report.errors[0].row, 2);
+        assert_eq!(report.errors[0].message, "5000000000 is out of INT range");
+    }
+
+    #[test]
+    fn test_parquet_ingest() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ID", DataType::Int64, false),
+            Field::new("Name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+            ],
+        )
+        .unwrap();
+        let mut file = Vec::new();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(&mut file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let columns = vec![
+            BulkColumn::new("id", "BIGINT", 0),
+            BulkColumn::new("name", "NVARCHAR(10)", 1),
+        ];
+        let mut loader = Ingest::new(&columns).parquet(Bytes::from(file)).unwrap();
+        let bulk = load(&mut loader, columns).unwrap();
+        assert_eq!(bulk.total_rows(), 3);
+        assert_eq!(bulk.result().batches_committed, 1);
+        assert_eq!(bulk.rows_in_batch(), 1);
+        assert_eq!(loader.report().rows_loaded, 3);
+    }
+
+    #[test]
+    fn test_arrow_unsupported_type() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "id",
+            DataType::Float16,
+            false,
+        )]));
+        let reader = RecordBatchIterator::new(Vec::new(), schema);
+        let err = Ingest::new(&[BulkColumn::new("id", "INT", 0)])
+            .arrow(reader)
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported Arrow type"));
+    }
+
+    #[test]
+    fn test_coerce_text() {
+        let rules = CoercionRules {
+            datetime_formats: vec!["%d/%m/%Y".to_string()],
+            ..CoercionRules::default()
+        };
+        let check = |sql_type: &str, input: &str| {
+            let column = BulkColumn::new("c", sql_type, 0);
+            coerce(
+                SqlValue::String(input.to_string()),
+                &column,
+                Target::of(&column),
+                &rules,
+            )
+        };
+
+        assert_eq!(check("BIT", "Yes"), Ok(SqlValue::Bool(true)));
+        assert!(check("BIT", "maybe").is_err());
+        assert_eq!(check("TINYINT", "255"), Ok(SqlValue::TinyInt(255)));
+        assert!(check("TINYINT", "256").is_err());
+        assert_eq!(check("BIGINT", " -7 "), Ok(SqlValue::BigInt(-7)));
+        assert_eq!(check("FLOAT", "1e3"), Ok(SqlValue::Double(1000.0)));
+        assert_eq!(
+            check("DECIMAL(5,1)", "12.35"),
+            Ok(SqlValue::Decimal(Decimal::new(124, 1)))
+        );
+        assert!(check("DECIMAL(3,1)", "123").is_err());
+        assert_eq!(
+            check("DATE", "31/12/2024"),
+            Ok(SqlValue::Date(
+                NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+            ))
+        );
+        assert_eq!(
+            check("DATETIME2", "2024-01-02T03:04:05.5"),
+            Ok(SqlValue::DateTime(
+                NaiveDate::from_ymd_opt(2024, 1, 2)
+                    .unwrap()
+                    .and_hms_milli_opt(3, 4, 5, 500)
+                    .unwrap()
+            ))
+        );
+        assert!(matches!(
+            check("DATETIMEOFFSET", "2024-01-02T03:04:05+02:00"),
+            Ok(SqlValue::DateTimeOffset(_))
+        ));
+        assert!(matches!(
+            check("UNIQUEIDENTIFIER", "67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            Ok(SqlValue::Uuid(_))
+        ));
+        assert_eq!(
+            check("VARBINARY(2)", "0xCAFE"),
+            Ok(SqlValue::Binary(Bytes::from_static(&[0xCA, 0xFE])))
+        );
+        assert!(check("VARBINARY(1)", "CAFE").is_err());
+        assert_eq!(
+            check("NVARCHAR(MAX)", "any length"),
+            Ok(SqlValue::String("any length".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_coerce_numbers() {
+        let check = |sql_type: &str, value: SqlValue| {
+            let column = BulkColumn::new("c", sql_type, 0);
+            coerce(
+                value,
+                &column,
+                Target::of(&column),
+                &CoercionRules::default(),
+            )
+        };
+
+        assert_eq!(check("INT", SqlValue::Double(3.0)), Ok(SqlValue::Int(3)));
+        assert!(check("INT", SqlValue::Double(3.5)).is_err());
+        assert_eq!(
+            check("DECIMAL(10,2)", SqlValue::BigInt(5)),
+            Ok(SqlValue::Decimal(Decimal::new(500, 2)))
+        );
+        assert_eq!(
+            check("NVARCHAR(10)", SqlValue::Bool(true)),
+            Ok(SqlValue::String("1".to_string()))
+        );
+        assert_eq!(check("REAL", SqlValue::Int(2)), Ok(SqlValue::Float(2.0)));
+    }
+}
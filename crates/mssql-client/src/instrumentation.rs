@@ -8,7 +8,8 @@
 //!
 //! When the `otel` feature is enabled, the following instrumentation is available:
 //!
-//! - **Connection spans**: Track connection establishment time and success/failure
+//! - **Connection spans**: Track connection establishment time and success/failure,
+//!   with per-phase durations from [`ConnectTimings`]
 //! - **Query spans**: Track SQL execution with sanitized statement attributes
 //! - **Transaction spans**: Track transaction boundaries (begin, commit, rollback)
 //! - **Error events**: Record errors with appropriate attributes
@@ -87,6 +88,8 @@ pub mod attributes {
     pub const DB_CONNECTION_ID: &str = "db.connection_id";
     /// Error type.
     pub const ERROR_TYPE: &str = "error.type";
+    /// Connection open phase (`dns`, `tcp`, `tls`, `prelogin`, `login`, `total`).
+    pub const DB_CONNECT_PHASE: &str = "db.mssql.connect.phase";
 }
 
 /// Configuration for SQL statement sanitization.
@@ -353,6 +356,22 @@ impl InstrumentationContext {
             span.set_attribute(KeyValue::new(attributes::DB_ROWS_AFFECTED, rows as i64));
         }
     }
+
+    /// Record connection phase durations (in seconds) on a connection span.
+    ///
+    /// Each phase is set as `db.mssql.connect.<phase>.duration`.
+    pub fn record_connect_timings(span: &mut impl Span, timings: &ConnectTimings) {
+        for (phase, duration) in timings.phases() {
+            span.set_attribute(KeyValue::new(
+                format!("db.mssql.connect.{phase}.duration"),
+                duration.as_secs_f64(),
+            ));
+        }
+        span.set_attribute(KeyValue::new(
+            "db.mssql.connect.redirects",
+            i64::from(timings.redirects),
+        ));
+    }
 }
 
 /// No-op instrumentation context when otel feature is disabled.
@@ -381,6 +400,72 @@ impl InstrumentationContext {
     }
 }
 
+// =============================================================================
+// Trace Context Propagation
+// =============================================================================
+
+/// Session context key that [`Client::set_trace_context`] stores the W3C
+/// `traceparent` under.
+///
+/// [`Client::set_trace_context`]: crate::Client::set_trace_context
+pub const TRACEPARENT_SESSION_KEY: &str = "traceparent";
+
+/// Build the PreLogin trace ID for a W3C trace ID and span ID.
+///
+/// The trace ID becomes the activity ID, byte-swapped so that SQL Server
+/// displays the GUID with the same hex digits as the trace ID. The low 32
+/// bits of the span ID become the activity sequence.
+#[must_use]
+pub fn prelogin_trace_id(trace_id: [u8; 16], span_id: [u8; 8]) -> tds_protocol::prelogin::TraceId {
+    let mut activity_id = trace_id;
+    // GUIDs are sent with their first three fields little-endian.
+    activity_id[..4].reverse();
+    activity_id[4..6].reverse();
+    activity_id[6..8].reverse();
+
+    let sequence = u32::from_be_bytes([span_id[4], span_id[5], span_id[6], span_id[7]]);
+    tds_protocol::prelogin::TraceId::new(activity_id, sequence)
+}
+
+/// Format a W3C `traceparent` value.
+#[must_use]
+pub fn format_traceparent(trace_id: [u8; 16], span_id: [u8; 8], sampled: bool) -> String {
+    let mut value = String::with_capacity(55);
+    value.push_str("00-");
+    for byte in trace_id {
+        value.push_str(&format!("{byte:02x}"));
+    }
+    value.push('-');
+    for byte in span_id {
+        value.push_str(&format!("{byte:02x}"));
+    }
+    value.push_str(if sampled { "-01" } else { "-00" });
+    value
+}
+
+/// Get the OpenTelemetry span context of the current `tracing` span.
+///
+/// Falls back to the active OpenTelemetry context, and returns `None` if
+/// there is no valid span.
+#[cfg(feature = "otel")]
+#[must_use]
+pub fn current_span_context() -> Option<opentelemetry::trace::SpanContext> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = tracing::Span::current().context();
+    let span_context = context.span().span_context().clone();
+    if span_context.is_valid() {
+        return Some(span_context);
+    }
+
+    let span_context = opentelemetry::Context::current()
+        .span()
+        .span_context()
+        .clone();
+    span_context.is_valid().then_some(span_context)
+}
+
 // =============================================================================
 // OpenTelemetry Metrics Support
 // =============================================================================
@@ -405,6 +490,8 @@ pub mod metric_names {
     pub const DB_CLIENT_ERRORS_TOTAL: &str = "db.client.errors.total";
     /// Histogram: Time spent waiting for a connection from the pool.
     pub const DB_CLIENT_CONNECTIONS_WAIT_TIME: &str = "db.client.connections.wait_time";
+    /// Histogram: Time to open a connection, per phase.
+    pub const DB_CLIENT_CONNECTIONS_CREATE_TIME: &str = "db.client.connections.create_time";
 }
 
 /// Database metrics collector using OpenTelemetry.
@@ -428,6 +515,8 @@ pub struct DatabaseMetrics {
     errors_total: opentelemetry::metrics::Counter<u64>,
     /// Connection wait time histogram.
     connections_wait_time: opentelemetry::metrics::Histogram<f64>,
+    /// Connection open time histogram, per phase.
+    connections_create_time: opentelemetry::metrics::Histogram<f64>,
     /// Base attributes for all metrics.
     base_attributes: Vec<opentelemetry::KeyValue>,
 }
@@ -500,6 +589,12 @@ impl DatabaseMetrics {
             .with_unit("s")
             .build();
 
+        let connections_create_time = meter
+            .f64_histogram(metric_names::DB_CLIENT_CONNECTIONS_CREATE_TIME)
+            .with_description("Time to open a connection, per phase")
+            .with_unit("s")
+            .build();
+
         let mut base_attributes = vec![
             KeyValue::new(attributes::DB_SYSTEM, DB_SYSTEM),
             KeyValue::new(attributes::SERVER_ADDRESS, server_address.to_string()),
@@ -520,6 +615,7 @@ impl DatabaseMetrics {
             operations_total,
             errors_total,
             connections_wait_time,
+            connections_create_time,
             base_attributes,
         }
     }
@@ -565,6 +661,19 @@ impl DatabaseMetrics {
         self.connections_wait_time
             .record(duration_seconds, &self.base_attributes);
     }
+
+    /// Record the phase durations of a connection open, plus the total.
+    pub fn record_connect_timings(&self, timings: &ConnectTimings) {
+        use opentelemetry::KeyValue;
+
+        let total = ("total", timings.total);
+        for (phase, duration) in timings.phases().into_iter().chain([total]) {
+            let mut attrs = self.base_attributes.clone();
+            attrs.push(KeyValue::new(attributes::DB_CONNECT_PHASE, phase));
+            self.connections_create_time
+                .record(duration.as_secs_f64(), &attrs);
+        }
+    }
 }
 
 /// No-op metrics collector when otel feature is disabled.
@@ -594,6 +703,52 @@ impl DatabaseMetrics {
 
     /// Record connection wait time (no-op).
     pub fn record_connection_wait(&self, _duration_seconds: f64) {}
+
+    /// Record connection phase durations (no-op).
+    pub fn record_connect_timings(&self, _timings: &ConnectTimings) {}
+}
+
+/// Durations of the phases of opening a connection.
+///
+/// Available from [`Client::connect_timings`](crate::Client::connect_timings).
+/// When the server redirects the client (Azure SQL gateway routing), the
+/// phases describe the final connection and [`total`](Self::total) covers
+/// every hop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectTimings {
+    /// Resolving the server host name.
+    pub dns: std::time::Duration,
+    /// Establishing the TCP connection.
+    pub tcp: std::time::Duration,
+    /// The TLS handshake, if the connection is encrypted.
+    pub tls: Option<std::time::Duration>,
+    /// The PreLogin exchange.
+    pub prelogin: std::time::Duration,
+    /// Sending LOGIN7 until the server acknowledged the login, including
+    /// authentication.
+    pub login: std::time::Duration,
+    /// Wall time of the whole connect, including redirects.
+    pub total: std::time::Duration,
+    /// Number of routing redirects followed.
+    pub redirects: u32,
+}
+
+impl ConnectTimings {
+    /// Get the phases in connection order as `(name, duration)` pairs.
+    ///
+    /// Names are `dns`, `tcp`, `tls` (only for encrypted connections),
+    /// `prelogin` and `login`.
+    #[must_use]
+    pub fn phases(&self) -> Vec<(&'static str, std::time::Duration)> {
+        let mut phases = vec![("dns", self.dns), ("tcp", self.tcp)];
+        if let Some(tls) = self.tls {
+            phases.push(("tls", tls));
+        }
+        phases.push(("prelogin", self.prelogin));
+        phases.push(("login", self.login));
+        phases
+    }
 }
 
 /// Helper for timing operations.
@@ -641,6 +796,24 @@ impl OperationTimer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_connect_timings_phases() {
+        use std::time::Duration;
+
+        let mut timings = ConnectTimings {
+            dns: Duration::from_millis(1),
+            tcp: Duration::from_millis(2),
+            prelogin: Duration::from_millis(3),
+            login: Duration::from_millis(4),
+            ..Default::default()
+        };
+        let names = |t: &ConnectTimings| t.phases().iter().map(|p| p.0).collect::<Vec<_>>();
+        assert_eq!(names(&timings), ["dns", "tcp", "prelogin", "login"]);
+
+        timings.tls = Some(Duration::from_millis(5));
+        assert_eq!(names(&timings), ["dns", "tcp", "tls", "prelogin", "login"]);
+    }
+
     #[test]
     fn test_extract_operation() {
         assert_eq!(extract_operation("SELECT * FROM users"), "SELECT");
@@ -714,4 +887,30 @@ mod tests {
         let sql = "SELECT * FROM users WHERE name = 'Alice'";
         assert_eq!(config.sanitize(sql), sql);
     }
+
+    #[test]
+    fn test_prelogin_trace_id() {
+        let trace_id = [
+            0x4b, 0xf9, 0x2f, 0x35, 0x77, 0xb3, 0x4d, 0xa6, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e,
+            0x47, 0x36,
+        ];
+        let span_id = [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7];
+
+        let prelogin = prelogin_trace_id(trace_id, span_id);
+        assert_eq!(
+            prelogin.activity_id,
+            [
+                0x35, 0x2f, 0xf9, 0x4b, 0xb3, 0x77, 0xa6, 0x4d, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e,
+                0x47, 0x36,
+            ]
+        );
+        assert_eq!(prelogin.activity_sequence, 0x0ba9_02b7);
+        assert_eq!(prelogin.connection_id, [0; 16]);
+
+        assert_eq!(
+            format_traceparent(trace_id, span_id, true),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert!(format_traceparent(trace_id, span_id, false).ends_with("-00"));
+    }
 }
@@ -70,50 +70,120 @@
 #![warn(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod activity;
+pub mod admin;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod availability;
+pub mod backup;
+pub mod batch;
 pub mod blob;
+pub mod blocking;
+pub mod browse;
 pub mod bulk;
 pub mod cancel;
+pub mod cdc;
 pub mod change_tracking;
 pub mod client;
 pub mod config;
+pub mod cursor;
+pub mod diagnostics;
+pub mod dns_cache;
 pub mod encryption;
 pub mod error;
 pub mod from_row;
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzzing;
+pub mod identifier;
+pub mod in_list;
+#[cfg(feature = "ingest")]
+pub mod ingest;
 pub mod instrumentation;
+pub mod localdb;
+pub mod merge;
+pub mod message;
+#[cfg(feature = "migrations")]
+pub mod migrations;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+mod proxy;
 pub mod query;
+pub mod resilient;
+pub mod returning;
 pub mod row;
+pub mod schema;
+pub mod script;
+pub mod service_broker;
+mod socket;
 pub mod state;
 pub mod statement_cache;
+pub mod statistics;
 pub mod stream;
+pub mod temp_table;
 pub mod to_params;
 pub mod transaction;
+pub mod transport;
 pub mod tvp;
 
 // Re-export commonly used types
+pub use activity::{ActiveSession, TransactionState};
+pub use admin::Admin;
+pub use availability::{
+    AvailabilityGroups, DatabaseReplicaState, ReplicaRole, ReplicaState, SynchronizationHealth,
+    SynchronizationState,
+};
+pub use backup::{BackupOperation, BackupOptions, BackupProgress, BackupSummary, RestoreOptions};
+pub use batch::{Batch, BatchMode, BatchResult};
+pub use blocking::{BlockingNode, BlockingSession, DeadlockReport};
+pub use browse::RowEdit;
 pub use bulk::{BulkColumn, BulkInsert, BulkInsertBuilder, BulkInsertResult, BulkOptions};
 pub use cancel::CancelHandle;
 pub use client::Client;
-pub use config::{Config, RedirectConfig, RetryPolicy, TimeoutConfig};
-pub use error::Error;
+pub use config::{
+    Config, DeadlockPriority, ProxyConfig, ProxyKind, RedirectConfig, RetryPolicy, SessionOptions,
+    SocketConfig, TimeoutConfig,
+};
+pub use cursor::{Cursor, CursorConcurrency, CursorOptions, CursorType, FetchDirection};
+pub use diagnostics::{Delta, Diagnostics, IndexUsage, QueryStat, Snapshot, WaitStat};
+pub use dns_cache::DnsCache;
+pub use error::{DatabaseError, Error, ErrorSeverity};
 
 // Re-export TDS version for configuration
 pub use from_row::{FromRow, MapRows, RowIteratorExt};
-pub use mssql_auth::Credentials;
+pub use identifier::{quote_identifier, quote_schema_qualified};
+pub use in_list::ExpandedQuery;
+pub use mssql_auth::{Credentials, SecretString};
+pub use mssql_codec::PacketCapture;
+pub use mssql_tls::TlsBackend;
 pub use tds_protocol::version::TdsVersion;
 
+pub use merge::{MergeBuilder, MergeResult};
+pub use message::{MessageHandler, ServerMessage};
 // Secure credential types (with zeroize feature)
 #[cfg(feature = "zeroize")]
-pub use mssql_auth::{SecretString, SecureCredentials};
-pub use mssql_types::{FromSql, SqlValue, ToSql};
-pub use query::Query;
+pub use mssql_auth::SecureCredentials;
+pub use mssql_types::{
+    FromSql, OutParam, Param, SqlType, SqlValue, TimeZonePolicy, ToSql, Varchar,
+};
+pub use query::{Query, QueryOptions, TableHint};
+pub use resilient::{Idempotency, ResilientClient};
 pub use row::{Column, Row};
+pub use schema::{ColumnInfo, IndexColumn, IndexInfo, SchemaInspector, TableInfo, TableKind};
+pub use script::{Script, ScriptBatch, ScriptBatchResult, ScriptResult};
+pub use service_broker::{BrokerMessage, ConversationHandle, DialogOptions, ServiceBroker};
 pub use state::{
     Connected, ConnectionState, Disconnected, InTransaction, ProtocolState, Ready, Streaming,
 };
 pub use statement_cache::{PreparedStatement, StatementCache, StatementCacheConfig};
-pub use stream::{ExecuteResult, MultiResultStream, OutputParam, QueryStream, ResultSet};
+pub use statistics::{QueryStatistics, TableIoStatistics, TimeStatistics};
+pub use stream::{
+    ExecuteResult, MultiResultStream, OutputParam, QueryStream, ResultSet, RowCount, RowStream,
+};
+pub use temp_table::TempTable;
 pub use to_params::{NamedParam, ParamList, ToParams};
 pub use transaction::{IsolationLevel, SavePoint, Transaction};
+pub use transport::{BoxedTransport, TransportFactory};
 pub use tvp::{Tvp, TvpColumn, TvpRow, TvpValue};
 
 // Always Encrypted types
@@ -125,10 +195,32 @@ pub use encryption::{
 
 // OpenTelemetry instrumentation (available whether or not otel feature is enabled)
 pub use instrumentation::{
-    DatabaseMetrics, OperationTimer, SanitizationConfig, attributes, metric_names, span_names,
+    ConnectTimings, DatabaseMetrics, OperationTimer, SanitizationConfig, attributes, metric_names,
+    span_names,
 };
 
+// Prometheus metrics export
+#[cfg(feature = "prometheus")]
+pub use prometheus::PrometheusMetrics;
+
+// Arrow export
+#[cfg(feature = "arrow")]
+pub use arrow::ArrowBatches;
+
+// CSV and Parquet ingestion
+#[cfg(feature = "ingest")]
+pub use ingest::{CoercionRules, Ingest, IngestLoader, IngestReport, RowError};
+
+// Schema migrations
+#[cfg(feature = "migrations")]
+pub use migrations::{AppliedMigration, Migration, MigrationReport, Migrator};
+
 // Change Tracking support
 pub use change_tracking::{
-    ChangeMetadata, ChangeOperation, ChangeTracking, ChangeTrackingQuery, SyncVersionStatus,
+    ChangeMetadata, ChangeOperation, ChangeTracking, ChangeTrackingClient, ChangeTrackingQuery,
+    ChangedRow, MemoryVersionStore, SyncData, SyncOutcome, SyncSession, SyncVersionStatus,
+    SyncVersionStore,
 };
+
+// Change Data Capture support
+pub use cdc::{CdcChange, CdcOperation, CdcQuery, CdcReader, ChangeDataCapture, Lsn};
@@ -0,0 +1,83 @@
+//! SQL Server Express LocalDB instance discovery.
+//!
+//! LocalDB instances listen only on a named pipe whose name changes every
+//! time the instance starts. A server given as `(localdb)\MSSQLLocalDB` is
+//! resolved at connect time by starting the instance with the `sqllocaldb`
+//! utility that ships with LocalDB and reading its pipe name from
+//! `sqllocaldb info`. Requires the `named-pipes` feature on Windows.
+//!
+//! The utility is used instead of the LocalDB instance API because that API
+//! is only available through a native DLL, and this crate has no unsafe code.
+
+/// Extract the instance pipe name from `sqllocaldb info` output.
+///
+/// The labels are localized, so the pipe is found by its `np:` prefix
+/// rather than by the "Instance pipe name" label. Returns `None` when the
+/// instance is stopped and has no pipe.
+pub fn parse_pipe_name(info: &str) -> Option<String> {
+    info.lines()
+        .find_map(|line| line.find(r"np:\\").map(|at| &line[at + 3..]))
+        .map(|pipe| pipe.trim().to_string())
+        .filter(|pipe| !pipe.is_empty())
+}
+
+/// Start a LocalDB instance if needed and get its pipe name.
+#[cfg(all(windows, feature = "named-pipes"))]
+pub(crate) async fn resolve_pipe_name(instance: &str) -> crate::error::Result<String> {
+    use crate::error::Error;
+
+    async fn sqllocaldb(args: &[&str]) -> crate::error::Result<String> {
+        let output = tokio::process::Command::new("sqllocaldb")
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| Error::Config(format!("failed to run sqllocaldb: {e}")))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        if output.status.success() {
+            Ok(stdout)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(Error::Config(format!(
+                "sqllocaldb {} failed: {}",
+                args.join(" "),
+                if stderr.trim().is_empty() {
+                    stdout.trim()
+                } else {
+                    stderr.trim()
+                }
+            )))
+        }
+    }
+
+    // Starting a running instance is a no-op; the automatic instance
+    // (MSSQLLocalDB) is created on first start
+    sqllocaldb(&["start", instance]).await?;
+    let info = sqllocaldb(&["info", instance]).await?;
+    parse_pipe_name(&info).ok_or_else(|| {
+        Error::Config(format!(
+            "LocalDB instance {instance} did not report a pipe name"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pipe_name() {
+        let info = "Name:               MSSQLLocalDB\r\n\
+                    Version:            15.0.4153.1\r\n\
+                    State:              Running\r\n\
+                    Instance pipe name: np:\\\\.\\pipe\\LOCALDB#D8B2BFB6\\tsql\\query\r\n";
+        assert_eq!(
+            parse_pipe_name(info).as_deref(),
+            Some(r"\\.\pipe\LOCALDB#D8B2BFB6\tsql\query")
+        );
+
+        let stopped = "Name:               MSSQLLocalDB\r\n\
+                       State:              Stopped\r\n\
+                       Instance pipe name: \r\n";
+        assert_eq!(parse_pipe_name(stopped), None);
+    }
+}
@@ -0,0 +1,411 @@
+//! Bulk upsert with `MERGE` and a table-valued parameter.
+//!
+//! [`Client::merge_into`](crate::Client::merge_into) synchronizes a slice of
+//! rows into a table in one round trip: the rows are sent as a TVP and a
+//! generated `MERGE` statement matches them to the target on key columns.
+//!
+//! The row type implements [`Tvp`], usually via `#[derive(Tvp)]`, and its
+//! `type_name` must name a table type that exists in the database:
+//!
+//! ```sql
+//! CREATE TYPE dbo.ProductRows AS TABLE (
+//!     Sku NVARCHAR(50) NOT NULL,
+//!     Name NVARCHAR(200) NOT NULL,
+//!     Price DECIMAL(10, 2) NOT NULL
+//! );
+//! ```
+//!
+//! ```rust,ignore
+//! #[derive(Tvp)]
+//! #[mssql(type_name = "dbo.ProductRows", rename_all = "PascalCase")]
+//! struct Product {
+//!     sku: String,
+//!     name: String,
+//!     price: Decimal,
+//! }
+//!
+//! let result = client
+//!     .merge_into("dbo.Products")
+//!     .on(&["Sku"])
+//!     .execute(&products)
+//!     .await?;
+//! println!("{} inserted, {} updated", result.inserted, result.updated);
+//! ```
+//!
+//! The statement takes `HOLDLOCK` on the target by default, so concurrent
+//! merges of the same keys cannot both insert.
+
+use std::fmt;
+
+use crate::client::{Client, quote_object_name, validated_identifier};
+use crate::error::{Error, Result};
+use crate::state::ConnectionState;
+use crate::tvp::{Tvp, TvpValue};
+
+/// Row counts of an executed `MERGE`, by action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeResult {
+    /// Rows inserted into the target.
+    pub inserted: u64,
+    /// Target rows updated from the source.
+    pub updated: u64,
+    /// Target rows deleted because no source row matched them.
+    pub deleted: u64,
+}
+
+impl MergeResult {
+    /// Total number of affected rows.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.inserted + self.updated + self.deleted
+    }
+}
+
+/// The clauses of a generated `MERGE` statement.
+#[derive(Debug, Clone)]
+struct MergeOptions {
+    table: String,
+    keys: Vec<String>,
+    update_columns: Option<Vec<String>>,
+    update: bool,
+    insert: bool,
+    delete_unmatched: bool,
+    skip_unchanged: bool,
+    holdlock: bool,
+}
+
+/// Builder for a `MERGE` of TVP rows into a table.
+///
+/// Created by [`Client::merge_into`](crate::Client::merge_into). By default
+/// matched rows are updated and unmatched source rows inserted; target rows
+/// missing from the source are kept unless
+/// [`delete_unmatched`](Self::delete_unmatched) is set.
+pub struct MergeBuilder<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+    options: MergeOptions,
+}
+
+impl<'a, S: ConnectionState> MergeBuilder<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            options: MergeOptions {
+                table: table.into(),
+                keys: Vec::new(),
+                update_columns: None,
+                update: true,
+                insert: true,
+                delete_unmatched: false,
+                skip_unchanged: false,
+                holdlock: true,
+            },
+        }
+    }
+
+    /// Set the key columns that match source rows to target rows.
+    #[must_use]
+    pub fn on(mut self, keys: &[&str]) -> Self {
+        self.options.keys = keys.iter().map(|k| (*k).to_string()).collect();
+        self
+    }
+
+    /// Only update these columns of matched rows.
+    ///
+    /// By default every non-key column of the row type is updated.
+    #[must_use]
+    pub fn update_columns(mut self, columns: &[&str]) -> Self {
+        self.options.update_columns = Some(columns.iter().map(|c| (*c).to_string()).collect());
+        self
+    }
+
+    /// Leave matched rows unchanged, only inserting new ones.
+    #[must_use]
+    pub fn insert_only(mut self) -> Self {
+        self.options.update = false;
+        self.options.insert = true;
+        self
+    }
+
+    /// Only update matched rows, never inserting.
+    #[must_use]
+    pub fn update_only(mut self) -> Self {
+        self.options.update = true;
+        self.options.insert = false;
+        self
+    }
+
+    /// Delete target rows that no source row matches.
+    ///
+    /// This makes the table mirror the slice: merging an empty slice deletes
+    /// every row.
+    #[must_use]
+    pub fn delete_unmatched(mut self) -> Self {
+        self.options.delete_unmatched = true;
+        self
+    }
+
+    /// Skip updates of matched rows whose values are already equal.
+    ///
+    /// Compares with `EXCEPT`, so NULLs compare equal. Skipped rows are not
+    /// counted in [`MergeResult::updated`] and don't fire update triggers.
+    #[must_use]
+    pub fn skip_unchanged(mut self) -> Self {
+        self.options.skip_unchanged = true;
+        self
+    }
+
+    /// Take `HOLDLOCK` on the target table (default: on).
+    #[must_use]
+    pub fn holdlock(mut self, enabled: bool) -> Self {
+        self.options.holdlock = enabled;
+        self
+    }
+
+    /// Get the SQL that [`execute`](Self::execute) sends for rows of type `T`.
+    ///
+    /// The rows are bound as `@p1`.
+    pub fn sql<T: Tvp>(&self) -> Result<String> {
+        let columns: Vec<String> = T::columns().into_iter().map(|c| c.name).collect();
+        merge_sql(&self.options, &columns)
+    }
+
+    /// Merge the rows into the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if no key columns are set or a key or update
+    /// column is not a column of `T`, [`Error::InvalidIdentifier`] for names
+    /// that are not plain identifiers, and the server's error if the
+    /// statement fails.
+    pub async fn execute<T: Tvp>(self, rows: &[T]) -> Result<MergeResult> {
+        let sql = self.sql::<T>()?;
+        let tvp = TvpValue::new(rows)?;
+
+        tracing::debug!(
+            table = %self.options.table,
+            rows = rows.len(),
+            "merging rows"
+        );
+        let result = self.client.fetch_rows(&sql, &[&tvp]).await?;
+        let Some(row) = result.first() else {
+            return Err(Error::Protocol("MERGE returned no action counts".into()));
+        };
+
+        let count = |index| -> Result<u64> {
+            let n: i64 = row.get(index)?;
+            Ok(u64::try_from(n).unwrap_or(0))
+        };
+        Ok(MergeResult {
+            inserted: count(0)?,
+            updated: count(1)?,
+            deleted: count(2)?,
+        })
+    }
+}
+
+impl<S: ConnectionState> fmt::Debug for MergeBuilder<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MergeBuilder")
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Find a column of the row type, returning its declared name.
+fn find_column<'c>(columns: &'c [String], name: &str) -> Result<&'c str> {
+    columns
+        .iter()
+        .find(|c| c.eq_ignore_ascii_case(name))
+        .map(String::as_str)
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "MERGE column '{name}' is not a column of the row type (columns: {})",
+                columns.join(", ")
+            ))
+        })
+}
+
+fn merge_sql(options: &MergeOptions, columns: &[String]) -> Result<String> {
+    if options.keys.is_empty() {
+        return Err(Error::Config(
+            "MERGE requires at least one key column; call on()".into(),
+        ));
+    }
+
+    let table = quote_object_name(&options.table)?;
+    let keys = options
+        .keys
+        .iter()
+        .map(|k| find_column(columns, k))
+        .collect::<Result<Vec<_>>>()?;
+    let update_columns: Vec<&str> = match &options.update_columns {
+        Some(list) => list
+            .iter()
+            .map(|c| find_column(columns, c))
+            .collect::<Result<_>>()?,
+        None => columns
+            .iter()
+            .map(String::as_str)
+            .filter(|c| !keys.iter().any(|k| k.eq_ignore_ascii_case(c)))
+            .collect(),
+    };
+
+    let on = keys
+        .iter()
+        .map(|k| validated_identifier(k).map(|k| format!("T.{k} = S.{k}")))
+        .collect::<Result<Vec<_>>>()?
+        .join(" AND ");
+
+    let hint = if options.holdlock {
+        " WITH (HOLDLOCK)"
+    } else {
+        ""
+    };
+    let mut sql = format!(
+        "DECLARE @merge_actions TABLE ([action] NVARCHAR(10)); \
+         MERGE INTO {table}{hint} AS T USING @p1 AS S ON {on}"
+    );
+
+    if options.update && !update_columns.is_empty() {
+        let quoted = update_columns
+            .iter()
+            .map(|c| validated_identifier(c))
+            .collect::<Result<Vec<_>>>()?;
+        sql.push_str(" WHEN MATCHED");
+        if options.skip_unchanged {
+            let source = quoted.iter().map(|c| format!("S.{c}")).collect::<Vec<_>>();
+            let target = quoted.iter().map(|c| format!("T.{c}")).collect::<Vec<_>>();
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT {} EXCEPT SELECT {})",
+                source.join(", "),
+                target.join(", ")
+            ));
+        }
+        let set = quoted
+            .iter()
+            .map(|c| format!("T.{c} = S.{c}"))
+            .collect::<Vec<_>>();
+        sql.push_str(&format!(" THEN UPDATE SET {}", set.join(", ")));
+    }
+
+    if options.insert {
+        let quoted = columns
+            .iter()
+            .map(|c| validated_identifier(c))
+            .collect::<Result<Vec<_>>>()?;
+        let values = quoted.iter().map(|c| format!("S.{c}")).collect::<Vec<_>>();
+        sql.push_str(&format!(
+            " WHEN NOT MATCHED BY TARGET THEN INSERT ({}) VALUES ({})",
+            quoted.join(", "),
+            values.join(", ")
+        ));
+    }
+
+    if options.delete_unmatched {
+        sql.push_str(" WHEN NOT MATCHED BY SOURCE THEN DELETE");
+    }
+
+    sql.push_str(
+        " OUTPUT $action INTO @merge_actions; \
+         SELECT COUNT_BIG(CASE WHEN [action] = N'INSERT' THEN 1 END), \
+         COUNT_BIG(CASE WHEN [action] = N'UPDATE' THEN 1 END), \
+         COUNT_BIG(CASE WHEN [action] = N'DELETE' THEN 1 END) \
+         FROM @merge_actions;",
+    );
+    Ok(sql)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn options(keys: &[&str]) -> MergeOptions {
+        MergeOptions {
+            table: "dbo.Products".into(),
+            keys: keys.iter().map(|k| (*k).to_string()).collect(),
+            update_columns: None,
+            update: true,
+            insert: true,
+            delete_unmatched: false,
+            skip_unchanged: false,
+            holdlock: true,
+        }
+    }
+
+    fn columns() -> Vec<String> {
+        vec!["Sku".into(), "Name".into(), "Price".into()]
+    }
+
+    #[test]
+    fn test_merge_sql_upsert() {
+        let sql = merge_sql(&options(&["sku"]), &columns()).unwrap();
+        assert_eq!(
+            sql,
+            "DECLARE @merge_actions TABLE ([action] NVARCHAR(10)); \
+             MERGE INTO [dbo].[Products] WITH (HOLDLOCK) AS T USING @p1 AS S \
+             ON T.[Sku] = S.[Sku] \
+             WHEN MATCHED THEN UPDATE SET T.[Name] = S.[Name], T.[Price] = S.[Price] \
+             WHEN NOT MATCHED BY TARGET THEN INSERT ([Sku], [Name], [Price]) \
+             VALUES (S.[Sku], S.[Name], S.[Price]) \
+             OUTPUT $action INTO @merge_actions; \
+             SELECT COUNT_BIG(CASE WHEN [action] = N'INSERT' THEN 1 END), \
+             COUNT_BIG(CASE WHEN [action] = N'UPDATE' THEN 1 END), \
+             COUNT_BIG(CASE WHEN [action] = N'DELETE' THEN 1 END) \
+             FROM @merge_actions;"
+        );
+    }
+
+    #[test]
+    fn test_merge_sql_options() {
+        let mut opts = options(&["Sku"]);
+        opts.update_columns = Some(vec!["Price".into()]);
+        opts.skip_unchanged = true;
+        opts.delete_unmatched = true;
+        opts.holdlock = false;
+        let sql = merge_sql(&opts, &columns()).unwrap();
+        assert!(sql.contains("MERGE INTO [dbo].[Products] AS T"));
+        assert!(sql.contains(
+            "WHEN MATCHED AND EXISTS (SELECT S.[Price] EXCEPT SELECT T.[Price]) \
+             THEN UPDATE SET T.[Price] = S.[Price]"
+        ));
+        assert!(sql.contains("WHEN NOT MATCHED BY SOURCE THEN DELETE"));
+
+        opts.update = false;
+        let sql = merge_sql(&opts, &columns()).unwrap();
+        assert!(!sql.contains("WHEN MATCHED"));
+        assert!(sql.contains("WHEN NOT MATCHED BY TARGET"));
+    }
+
+    #[test]
+    fn test_merge_sql_all_key_columns_skips_update() {
+        let sql = merge_sql(&options(&["Sku", "Name", "Price"]), &columns()).unwrap();
+        assert!(sql.contains("ON T.[Sku] = S.[Sku] AND T.[Name] = S.[Name]"));
+        assert!(!sql.contains("WHEN MATCHED"));
+    }
+
+    #[test]
+    fn test_merge_sql_errors() {
+        assert!(matches!(
+            merge_sql(&options(&[]), &columns()),
+            Err(Error::Config(_))
+        ));
+        assert!(matches!(
+            merge_sql(&options(&["Id"]), &columns()),
+            Err(Error::Config(msg)) if msg.contains("columns: Sku, Name, Price")
+        ));
+
+        let mut opts = options(&["Sku"]);
+        opts.table = "#Products".into();
+        assert!(
+            merge_sql(&opts, &columns())
+                .unwrap()
+                .contains("MERGE INTO [#Products]")
+        );
+        opts.table = "dbo.Products; DROP TABLE x".into();
+        assert!(matches!(
+            merge_sql(&opts, &columns()),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+}
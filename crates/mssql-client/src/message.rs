@@ -0,0 +1,87 @@
+//! Informational messages from the server.
+//!
+//! SQL Server sends INFO tokens for messages with severity 10 or lower,
+//! including `PRINT` output and `RAISERROR ... WITH NOWAIT` progress text.
+//! These are not errors, so the request still succeeds; the messages are
+//! buffered with the result and optionally passed to a callback registered
+//! with [`Client::on_message`](crate::Client::on_message).
+
+use std::sync::Arc;
+
+/// An informational message sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerMessage {
+    /// Message number (0 for `PRINT`, 50000 for ad-hoc `RAISERROR`).
+    pub number: i32,
+    /// Message state.
+    pub state: u8,
+    /// Message class/severity (0-10).
+    pub class: u8,
+    /// Message text.
+    pub message: String,
+    /// Server name that produced the message.
+    pub server: Option<String>,
+    /// Stored procedure name (if applicable).
+    pub procedure: Option<String>,
+    /// Line number in the SQL batch or procedure.
+    pub line: u32,
+}
+
+impl From<&tds_protocol::token::ServerInfo> for ServerMessage {
+    fn from(info: &tds_protocol::token::ServerInfo) -> Self {
+        Self {
+            number: info.number,
+            state: info.state,
+            class: info.class,
+            message: info.message.clone(),
+            server: if info.server.is_empty() {
+                None
+            } else {
+                Some(info.server.clone())
+            },
+            procedure: if info.procedure.is_empty() {
+                None
+            } else {
+                Some(info.procedure.clone())
+            },
+            line: info.line as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Callback invoked for every informational message the server sends.
+pub type MessageHandler = Arc<dyn Fn(&ServerMessage) + Send + Sync>;
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use tds_protocol::token::ServerInfo;
+
+    #[test]
+    fn test_from_server_info() {
+        let info = ServerInfo {
+            number: 0,
+            state: 1,
+            class: 0,
+            message: "50% complete".to_string(),
+            server: "sql01".to_string(),
+            procedure: String::new(),
+            line: 3,
+        };
+
+        let msg = ServerMessage::from(&info);
+        assert_eq!(msg.number, 0);
+        assert_eq!(msg.message, "50% complete");
+        assert_eq!(msg.server.as_deref(), Some("sql01"));
+        assert_eq!(msg.procedure, None);
+        assert_eq!(msg.line, 3);
+        assert_eq!(msg.to_string(), "50% complete");
+    }
+}
@@ -0,0 +1,646 @@
+//! Schema migrations.
+//!
+//! A [`Migrator`] applies versioned SQL scripts to a database and records
+//! each applied version, along with a SHA-256 checksum of its script, in a
+//! history table (`__mssql_migrations` by default). Scripts may contain
+//! `GO` separators; they are split with [`Script`].
+//!
+//! Each migration runs inside a transaction, together with the insert into
+//! the history table, so a failed migration leaves no partial changes.
+//! Some statements cannot run inside a transaction (e.g. `ALTER DATABASE`,
+//! full-text index DDL); add the line `-- mssql:no-transaction` to such a
+//! script to run it without one.
+//!
+//! Concurrent migrators are serialized with a session-level application
+//! lock (`sp_getapplock`), so several application instances can start at
+//! the same time.
+//!
+//! ## Embedding migration files
+//!
+//! The `embed_migrations!` macro from `mssql-derive` embeds a directory of
+//! migration files at compile time. Files are named
+//! `<version>_<name>.sql` (or `.up.sql`), with an optional
+//! `<version>_<name>.down.sql` holding the down migration:
+//!
+//! ```text
+//! migrations/
+//!     1_create_users.sql
+//!     2_add_email.up.sql
+//!     2_add_email.down.sql
+//! ```
+//!
+//! ```rust,ignore
+//! use mssql_derive::embed_migrations;
+//!
+//! let migrator = embed_migrations!("migrations");
+//!
+//! // Show what would run without changing anything
+//! let plan = migrator.clone().dry_run(true).run(&mut client).await?;
+//! println!("pending: {:?}", plan.applied);
+//!
+//! migrator.run(&mut client).await?;
+//!
+//! // Roll back to version 1
+//! migrator.undo(&mut client, 1).await?;
+//! ```
+
+use std::borrow::Cow;
+use std::time::Instant;
+
+use sha2::{Digest, Sha256};
+
+use crate::client::{Client, validate_identifier};
+use crate::error::{Error, Result};
+use crate::script::Script;
+use crate::state::Ready;
+
+/// Default name of the migration history table.
+pub const DEFAULT_MIGRATIONS_TABLE: &str = "__mssql_migrations";
+
+/// Directive that disables the wrapping transaction for a migration script.
+pub const NO_TRANSACTION_DIRECTIVE: &str = "-- mssql:no-transaction";
+
+/// How long to wait for another migrator to release the migration lock.
+const LOCK_TIMEOUT_MS: i32 = 60_000;
+
+/// A single versioned migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    version: i64,
+    name: Cow<'static, str>,
+    up: Cow<'static, str>,
+    down: Option<Cow<'static, str>>,
+}
+
+impl Migration {
+    /// Create a migration from its version, name, and up script.
+    pub fn new(
+        version: i64,
+        name: impl Into<Cow<'static, str>>,
+        up: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: up.into(),
+            down: None,
+        }
+    }
+
+    /// Set the script that reverts this migration.
+    #[must_use]
+    pub fn with_down(mut self, down: impl Into<Cow<'static, str>>) -> Self {
+        self.down = Some(down.into());
+        self
+    }
+
+    /// Get the version.
+    #[must_use]
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Get the name.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the up script.
+    #[must_use]
+    pub fn up_sql(&self) -> &str {
+        &self.up
+    }
+
+    /// Get the down script, if any.
+    #[must_use]
+    pub fn down_sql(&self) -> Option<&str> {
+        self.down.as_deref()
+    }
+
+    /// Check if the up script runs inside a transaction.
+    #[must_use]
+    pub fn is_transactional(&self) -> bool {
+        is_transactional(&self.up)
+    }
+
+    /// Get the SHA-256 checksum of the up script.
+    ///
+    /// Carriage returns are ignored, so the checksum does not change when a
+    /// file is checked out with different line endings.
+    #[must_use]
+    pub fn checksum(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        for chunk in self.up.split('\r') {
+            hasher.update(chunk.as_bytes());
+        }
+        hasher.finalize().to_vec()
+    }
+}
+
+/// A migration recorded in the history table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// Migration version.
+    pub version: i64,
+    /// Migration name.
+    pub name: String,
+    /// Checksum of the up script when it was applied.
+    pub checksum: Vec<u8>,
+}
+
+/// Outcome of [`Migrator::run`] or [`Migrator::undo`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Versions applied, in order. For a dry run, the versions that would be applied.
+    pub applied: Vec<i64>,
+    /// Versions reverted, in order. For a dry run, the versions that would be reverted.
+    pub reverted: Vec<i64>,
+    /// Whether this was a dry run that made no changes.
+    pub dry_run: bool,
+}
+
+/// Applies and reverts a set of [`Migration`]s.
+#[derive(Debug, Clone)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    table: String,
+    dry_run: bool,
+}
+
+impl Migrator {
+    /// Create a migrator for the given migrations, in any order.
+    #[must_use]
+    pub fn new(mut migrations: Vec<Migration>) -> Self {
+        migrations.sort_by_key(Migration::version);
+        Self {
+            migrations,
+            table: DEFAULT_MIGRATIONS_TABLE.to_string(),
+            dry_run: false,
+        }
+    }
+
+    /// Set the name of the history table.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIdentifier`] if the name is not a valid identifier.
+    pub fn table_name(mut self, table: impl Into<String>) -> Result<Self> {
+        let table = table.into();
+        validate_identifier(&table)?;
+        self.table = table;
+        Ok(self)
+    }
+
+    /// Report what would be applied or reverted without changing the database.
+    #[must_use]
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Get the migrations, sorted by version.
+    #[must_use]
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Get the migrations recorded in the history table.
+    ///
+    /// Returns an empty list if the history table does not exist yet.
+    pub async fn applied(&self, client: &mut Client<Ready>) -> Result<Vec<AppliedMigration>> {
+        let sql = format!(
+            "IF OBJECT_ID(N'{table}', N'U') IS NOT NULL \
+             SELECT version, name, checksum FROM [{table}] ORDER BY version",
+            table = self.table
+        );
+
+        let mut applied = Vec::new();
+        for row in client.query(&sql, &[]).await? {
+            let row = row?;
+            applied.push(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                checksum: row.get(2)?,
+            });
+        }
+        Ok(applied)
+    }
+
+    /// Get the migrations that have not been applied yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Migration`] if an applied migration was modified
+    /// or is missing from this migrator.
+    pub async fn pending(&self, client: &mut Client<Ready>) -> Result<Vec<&Migration>> {
+        let applied = self.applied(client).await?;
+        self.resolve_pending(&applied)
+    }
+
+    /// Apply all pending migrations in version order.
+    ///
+    /// Stops at the first migration that fails; migrations applied before
+    /// it remain applied.
+    pub async fn run(&self, client: &mut Client<Ready>) -> Result<MigrationReport> {
+        self.validate()?;
+        let mut report = MigrationReport {
+            dry_run: self.dry_run,
+            ..MigrationReport::default()
+        };
+
+        if self.dry_run {
+            let applied = self.applied(client).await?;
+            report.applied = self
+                .resolve_pending(&applied)?
+                .iter()
+                .map(|m| m.version)
+                .collect();
+            return Ok(report);
+        }
+
+        self.ensure_table(client).await?;
+        self.lock(client).await?;
+        let result = self.apply_pending(client, &mut report).await;
+        self.unlock(client).await;
+        result.map(|()| report)
+    }
+
+    /// Revert applied migrations with a version greater than `target`, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Migration`] if a migration to revert has no down
+    /// script or is missing from this migrator.
+    pub async fn undo(&self, client: &mut Client<Ready>, target: i64) -> Result<MigrationReport> {
+        self.validate()?;
+        let mut report = MigrationReport {
+            dry_run: self.dry_run,
+            ..MigrationReport::default()
+        };
+
+        if self.dry_run {
+            let applied = self.applied(client).await?;
+            report.reverted = self
+                .resolve_revert(&applied, target)?
+                .iter()
+                .map(|m| m.version)
+                .collect();
+            return Ok(report);
+        }
+
+        self.ensure_table(client).await?;
+        self.lock(client).await?;
+        let result = self.revert_to(client, target, &mut report).await;
+        self.unlock(client).await;
+        result.map(|()| report)
+    }
+
+    async fn apply_pending(
+        &self,
+        client: &mut Client<Ready>,
+        report: &mut MigrationReport,
+    ) -> Result<()> {
+        let applied = self.applied(client).await?;
+        let insert = format!(
+            "INSERT INTO [{}] (version, name, checksum, execution_ms) VALUES (@p1, @p2, @p3, @p4)",
+            self.table
+        );
+
+        for migration in self.resolve_pending(&applied)? {
+            tracing::info!(
+                version = migration.version,
+                name = %migration.name,
+                "applying migration"
+            );
+            let started = Instant::now();
+            let checksum = migration.checksum();
+            let name = migration.name.as_ref();
+
+            self.run_in_transaction(client, migration, &migration.up, async |client| {
+                let elapsed = i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
+                client
+                    .execute(&insert, &[&migration.version, &name, &checksum, &elapsed])
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+            report.applied.push(migration.version);
+        }
+
+        Ok(())
+    }
+
+    async fn revert_to(
+        &self,
+        client: &mut Client<Ready>,
+        target: i64,
+        report: &mut MigrationReport,
+    ) -> Result<()> {
+        let applied = self.applied(client).await?;
+        let delete = format!("DELETE FROM [{}] WHERE version = @p1", self.table);
+
+        for migration in self.resolve_revert(&applied, target)? {
+            tracing::info!(
+                version = migration.version,
+                name = %migration.name,
+                "reverting migration"
+            );
+            let down = migration.down.as_deref().unwrap_or_default();
+
+            self.run_in_transaction(client, migration, down, async |client| {
+                client
+                    .execute(&delete, &[&migration.version])
+                    .await
+                    .map(|_| ())
+            })
+            .await?;
+            report.reverted.push(migration.version);
+        }
+
+        Ok(())
+    }
+
+    /// Run a migration script and its history update, in a transaction
+    /// unless the script opts out.
+    async fn run_in_transaction<F>(
+        &self,
+        client: &mut Client<Ready>,
+        migration: &Migration,
+        sql: &str,
+        record: F,
+    ) -> Result<()>
+    where
+        F: AsyncFnOnce(&mut Client<Ready>) -> Result<()>,
+    {
+        let script = Script::parse(sql)?;
+        let transactional = is_transactional(sql);
+
+        if transactional {
+            client.execute("BEGIN TRANSACTION", &[]).await?;
+        }
+
+        let result = async {
+            let outcome = client.execute_script(&script).await?;
+            if let Some((batch, error)) = outcome.errors().next() {
+                return Err(Error::Migration(format!(
+                    "migration {} ({}) failed in batch starting at line {}: {error}",
+                    migration.version, migration.name, batch.line
+                )));
+            }
+            record(&mut *client).await
+        }
+        .await;
+
+        if transactional {
+            let end = if result.is_ok() {
+                "COMMIT TRANSACTION"
+            } else {
+                "IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION"
+            };
+            let ended = client.execute(end, &[]).await;
+            if let (Ok(()), Err(e)) = (&result, ended) {
+                return Err(e);
+            }
+        }
+
+        result
+    }
+
+    async fn ensure_table(&self, client: &mut Client<Ready>) -> Result<()> {
+        let sql = format!(
+            "IF OBJECT_ID(N'{table}', N'U') IS NULL \
+             CREATE TABLE [{table}] (\
+                 version BIGINT NOT NULL PRIMARY KEY, \
+                 name NVARCHAR(255) NOT NULL, \
+                 checksum VARBINARY(32) NOT NULL, \
+                 applied_at DATETIME2 NOT NULL DEFAULT SYSUTCDATETIME(), \
+                 execution_ms BIGINT NOT NULL\
+             )",
+            table = self.table
+        );
+        client.execute(&sql, &[]).await?;
+        Ok(())
+    }
+
+    async fn lock(&self, client: &mut Client<Ready>) -> Result<()> {
+        let sql = format!(
+            "DECLARE @result INT; \
+             EXEC @result = sp_getapplock @Resource = N'{table}', @LockMode = 'Exclusive', \
+                 @LockOwner = 'Session', @LockTimeout = {LOCK_TIMEOUT_MS}; \
+             SELECT @result",
+            table = self.table
+        );
+        let status = match client.query(&sql, &[]).await?.next() {
+            Some(row) => row?.get::<i32>(0)?,
+            None => -999,
+        };
+        if status < 0 {
+            return Err(Error::Migration(format!(
+                "could not acquire migration lock (sp_getapplock returned {status})"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn unlock(&self, client: &mut Client<Ready>) {
+        let sql = format!(
+            "EXEC sp_releaseapplock @Resource = N'{}', @LockOwner = 'Session'",
+            self.table
+        );
+        if let Err(e) = client.execute(&sql, &[]).await {
+            tracing::warn!(error = %e, "failed to release migration lock");
+        }
+    }
+
+    /// Check that versions are unique.
+    fn validate(&self) -> Result<()> {
+        for pair in self.migrations.windows(2) {
+            if pair[0].version == pair[1].version {
+                return Err(Error::Migration(format!(
+                    "duplicate migration version {}",
+                    pair[0].version
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare the history table with the known migrations.
+    fn resolve_pending(&self, applied: &[AppliedMigration]) -> Result<Vec<&Migration>> {
+        for record in applied {
+            let migration = self.find(record.version)?;
+            if migration.checksum() != record.checksum {
+                return Err(Error::Migration(format!(
+                    "migration {} ({}) was modified after it was applied",
+                    record.version, record.name
+                )));
+            }
+        }
+
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|m| !applied.iter().any(|a| a.version == m.version))
+            .collect())
+    }
+
+    fn resolve_revert(&self, applied: &[AppliedMigration], target: i64) -> Result<Vec<&Migration>> {
+        applied
+            .iter()
+            .rev()
+            .filter(|a| a.version > target)
+            .map(|a| {
+                let migration = self.find(a.version)?;
+                if migration.down.is_none() {
+                    return Err(Error::Migration(format!(
+                        "migration {} ({}) has no down script",
+                        migration.version, migration.name
+                    )));
+                }
+                Ok(migration)
+            })
+            .collect()
+    }
+
+    fn find(&self, version: i64) -> Result<&Migration> {
+        self.migrations
+            .iter()
+            .find(|m| m.version == version)
+            .ok_or_else(|| {
+                Error::Migration(format!(
+                    "migration {version} was applied but is missing from the migrator"
+                ))
+            })
+    }
+}
+
+fn is_transactional(sql: &str) -> bool {
+    !sql.lines()
+        .any(|line| line.trim().eq_ignore_ascii_case(NO_TRANSACTION_DIRECTIVE))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn applied(migration: &Migration) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version(),
+            name: migration.name().to_string(),
+            checksum: migration.checksum(),
+        }
+    }
+
+    fn migrator() -> Migrator {
+        Migrator::new(vec![
+            Migration::new(3, "add_index", "CREATE INDEX ix ON users (name)")
+                .with_down("DROP INDEX ix ON users"),
+            Migration::new(1, "create_users", "CREATE TABLE users (id INT)")
+                .with_down("DROP TABLE users"),
+            Migration::new(2, "seed", "INSERT INTO users VALUES (1)"),
+        ])
+    }
+
+    #[test]
+    fn test_migrations_sorted() {
+        let versions: Vec<_> = migrator()
+            .migrations()
+            .iter()
+            .map(|m| m.version())
+            .collect();
+        assert_eq!(versions, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_checksum_ignores_line_endings() {
+        let unix = Migration::new(1, "a", "SELECT 1\nGO\n");
+        let windows = Migration::new(1, "a", "SELECT 1\r\nGO\r\n");
+        let changed = Migration::new(1, "a", "SELECT 2\nGO\n");
+
+        assert_eq!(unix.checksum().len(), 32);
+        assert_eq!(unix.checksum(), windows.checksum());
+        assert_ne!(unix.checksum(), changed.checksum());
+    }
+
+    #[test]
+    fn test_no_transaction_directive() {
+        assert!(Migration::new(1, "a", "CREATE TABLE t (id INT)").is_transactional());
+        assert!(
+            !Migration::new(
+                1,
+                "a",
+                "-- mssql:no-transaction\nALTER DATABASE CURRENT SET RECOVERY SIMPLE"
+            )
+            .is_transactional()
+        );
+    }
+
+    #[test]
+    fn test_resolve_pending() {
+        let migrator = migrator();
+        let history = [applied(&migrator.migrations()[0])];
+
+        let pending: Vec<_> = migrator
+            .resolve_pending(&history)
+            .unwrap()
+            .iter()
+            .map(|m| m.version())
+            .collect();
+        assert_eq!(pending, [2, 3]);
+    }
+
+    #[test]
+    fn test_resolve_pending_detects_changes() {
+        let migrator = migrator();
+        let mut modified = applied(&migrator.migrations()[0]);
+        modified.checksum = vec![0; 32];
+        assert!(matches!(
+            migrator.resolve_pending(&[modified]),
+            Err(Error::Migration(_))
+        ));
+
+        let missing = AppliedMigration {
+            version: 99,
+            name: "gone".into(),
+            checksum: vec![0; 32],
+        };
+        assert!(migrator.resolve_pending(&[missing]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_revert() {
+        let migrator = migrator();
+        let m = migrator.migrations();
+
+        let history = [applied(&m[0]), applied(&m[2])];
+        let reverted: Vec<_> = migrator
+            .resolve_revert(&history, 0)
+            .unwrap()
+            .iter()
+            .map(|m| m.version())
+            .collect();
+        assert_eq!(reverted, [3, 1]);
+
+        // Version 2 has no down script
+        let history = [applied(&m[0]), applied(&m[1])];
+        assert!(migrator.resolve_revert(&history, 1).is_err());
+        assert!(migrator.resolve_revert(&history, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_versions() {
+        let migrator = Migrator::new(vec![
+            Migration::new(1, "a", "SELECT 1"),
+            Migration::new(1, "b", "SELECT 2"),
+        ]);
+        assert!(migrator.validate().is_err());
+    }
+
+    #[test]
+    fn test_table_name_validated() {
+        assert!(migrator().table_name("schema_history").is_ok());
+        assert!(migrator().table_name("x]; DROP TABLE users--").is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Prometheus metrics export.
+//!
+//! [`PrometheusMetrics`] records the same pool gauges, operation histograms,
+//! and error counters as the OpenTelemetry [`DatabaseMetrics`], and renders
+//! them in the Prometheus text exposition format for a `/metrics` endpoint,
+//! without an OTLP collector.
+//!
+//! Metric names are the [`metric_names`] constants with dots replaced by
+//! underscores; histograms in seconds get a `_seconds` suffix, e.g.
+//! `db.client.operation.duration` becomes `db_client_operation_duration_seconds`.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use mssql_client::instrumentation::OperationTimer;
+//! use mssql_client::prometheus::PrometheusMetrics;
+//!
+//! let metrics = PrometheusMetrics::new(Some("orders"), "sql01", 1433);
+//!
+//! let timer = OperationTimer::start("SELECT");
+//! let result = client.query("SELECT * FROM Orders", &[]).await;
+//! metrics.record_operation(timer.operation(), timer.elapsed_seconds(), result.is_ok());
+//!
+//! // In the /metrics handler:
+//! let body = metrics.render();
+//! ```
+//!
+//! [`DatabaseMetrics`]: crate::instrumentation::DatabaseMetrics
+//! [`metric_names`]: crate::instrumentation::metric_names
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::instrumentation::{ConnectTimings, DB_SYSTEM, attributes, metric_names};
+
+/// Upper bounds of the duration histogram buckets, in seconds.
+pub const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Per-bucket (non-cumulative) counts.
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(index) = DURATION_BUCKETS.iter().position(|&bound| value <= bound) {
+            self.buckets[index] += 1;
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct OperationStats {
+    duration: Histogram,
+}
+
+/// Database metrics collector with Prometheus text output.
+///
+/// All recording methods take `&self`, so one collector can be shared
+/// behind an `Arc` by every task using a pool.
+#[derive(Debug)]
+pub struct PrometheusMetrics {
+    /// Rendered base labels shared by every series.
+    labels: String,
+    connections_usage: AtomicU64,
+    connections_idle: AtomicU64,
+    connections_max: AtomicU64,
+    connections_created: AtomicU64,
+    connections_closed: AtomicU64,
+    /// Operation histograms keyed by operation name and success.
+    operations: Mutex<BTreeMap<(String, bool), OperationStats>>,
+    connections_wait: Mutex<Histogram>,
+    /// Connection open histograms keyed by phase.
+    connect_phases: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+impl PrometheusMetrics {
+    /// Create a new metrics collector.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool_name` - Optional name to identify this pool in metrics
+    /// * `server_address` - Server hostname
+    /// * `server_port` - Server port
+    #[must_use]
+    pub fn new(pool_name: Option<&str>, server_address: &str, server_port: u16) -> Self {
+        let mut labels = vec![
+            (attributes::DB_SYSTEM, DB_SYSTEM.to_string()),
+            (attributes::SERVER_ADDRESS, server_address.to_string()),
+            (attributes::SERVER_PORT, server_port.to_string()),
+        ];
+        if let Some(name) = pool_name {
+            labels.push(("db.client.pool.name", name.to_string()));
+        }
+
+        Self {
+            labels: render_labels(&labels),
+            connections_usage: AtomicU64::new(0),
+            connections_idle: AtomicU64::new(0),
+            connections_max: AtomicU64::new(0),
+            connections_created: AtomicU64::new(0),
+            connections_closed: AtomicU64::new(0),
+            operations: Mutex::new(BTreeMap::new()),
+            connections_wait: Mutex::new(Histogram::default()),
+            connect_phases: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record pool connection status.
+    pub fn record_pool_status(&self, in_use: u64, idle: u64, max: u64) {
+        self.connections_usage.store(in_use, Ordering::Relaxed);
+        self.connections_idle.store(idle, Ordering::Relaxed);
+        self.connections_max.store(max, Ordering::Relaxed);
+    }
+
+    /// Record a connection being created.
+    pub fn record_connection_created(&self) {
+        self.connections_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a connection being closed.
+    pub fn record_connection_closed(&self) {
+        self.connections_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the connection totals from a source that counts them itself,
+    /// such as a pool's own metrics.
+    pub fn set_connection_totals(&self, created: u64, closed: u64) {
+        self.connections_created.store(created, Ordering::Relaxed);
+        self.connections_closed.store(closed, Ordering::Relaxed);
+    }
+
+    /// Record an operation duration.
+    pub fn record_operation(&self, operation: &str, duration_seconds: f64, success: bool) {
+        let mut operations = self
+            .operations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        operations
+            .entry((operation.to_string(), success))
+            .or_default()
+            .duration
+            .observe(duration_seconds);
+    }
+
+    /// Record time spent waiting for a connection from the pool.
+    pub fn record_connection_wait(&self, duration_seconds: f64) {
+        self.connections_wait
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .observe(duration_seconds);
+    }
+
+    /// Record the phase durations of a connection open, plus the total.
+    pub fn record_connect_timings(&self, timings: &ConnectTimings) {
+        let mut phases = self
+            .connect_phases
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let total = ("total", timings.total);
+        for (phase, duration) in timings.phases().into_iter().chain([total]) {
+            phases
+                .entry(phase)
+                .or_default()
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format (0.0.4).
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let labels = &self.labels;
+
+        for (name, help, value) in [
+            (
+                metric_names::DB_CLIENT_CONNECTIONS_USAGE,
+                "Number of connections currently in use",
+                &self.connections_usage,
+            ),
+            (
+                metric_names::DB_CLIENT_CONNECTIONS_IDLE,
+                "Number of idle connections available",
+                &self.connections_idle,
+            ),
+            (
+                metric_names::DB_CLIENT_CONNECTIONS_MAX,
+                "Maximum number of connections allowed",
+                &self.connections_max,
+            ),
+        ] {
+            let name = prometheus_name(name, "");
+            write_header(&mut out, &name, help, "gauge");
+            let _ = writeln!(out, "{name}{{{labels}}} {}", value.load(Ordering::Relaxed));
+        }
+
+        for (name, help, value) in [
+            (
+                metric_names::DB_CLIENT_CONNECTIONS_CREATE_TOTAL,
+                "Total number of connections created",
+                &self.connections_created,
+            ),
+            (
+                metric_names::DB_CLIENT_CONNECTIONS_CLOSE_TOTAL,
+                "Total number of connections closed",
+                &self.connections_closed,
+            ),
+        ] {
+            let name = prometheus_name(name, "");
+            write_header(&mut out, &name, help, "counter");
+            let _ = writeln!(out, "{name}{{{labels}}} {}", value.load(Ordering::Relaxed));
+        }
+
+        let operations = self
+            .operations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let name = prometheus_name(metric_names::DB_CLIENT_OPERATIONS_TOTAL, "");
+        write_header(
+            &mut out,
+            &name,
+            "Total number of database operations",
+            "counter",
+        );
+        for ((operation, success), stats) in operations.iter() {
+            let series = operation_labels(labels, operation, *success);
+            let _ = writeln!(out, "{name}{{{series}}} {}", stats.duration.count);
+        }
+
+        let name = prometheus_name(metric_names::DB_CLIENT_ERRORS_TOTAL, "");
+        write_header(
+            &mut out,
+            &name,
+            "Total number of operation errors",
+            "counter",
+        );
+        for ((operation, _), stats) in operations.iter().filter(|((_, success), _)| !success) {
+            let series = operation_labels(labels, operation, false);
+            let _ = writeln!(out, "{name}{{{series}}} {}", stats.duration.count);
+        }
+
+        let name = prometheus_name(metric_names::DB_CLIENT_OPERATION_DURATION, "_seconds");
+        write_header(
+            &mut out,
+            &name,
+            "Duration of database operations",
+            "histogram",
+        );
+        for ((operation, success), stats) in operations.iter() {
+            let series = operation_labels(labels, operation, *success);
+            stats.duration.render(&mut out, &name, &series);
+        }
+        drop(operations);
+
+        let name = prometheus_name(metric_names::DB_CLIENT_CONNECTIONS_WAIT_TIME, "_seconds");
+        write_header(
+            &mut out,
+            &name,
+            "Time spent waiting for a connection",
+            "histogram",
+        );
+        self.connections_wait
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .render(&mut out, &name, labels);
+
+        let name = prometheus_name(metric_names::DB_CLIENT_CONNECTIONS_CREATE_TIME, "_seconds");
+        write_header(
+            &mut out,
+            &name,
+            "Time to open a connection, per phase",
+            "histogram",
+        );
+        let phases = self
+            .connect_phases
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for (phase, histogram) in phases.iter() {
+            let series = format!(
+                "{labels},{}=\"{phase}\"",
+                attributes::DB_CONNECT_PHASE.replace('.', "_")
+            );
+            histogram.render(&mut out, &name, &series);
+        }
+
+        out
+    }
+}
+
+/// Convert an OpenTelemetry metric name to a Prometheus one.
+fn prometheus_name(name: &str, unit_suffix: &str) -> String {
+    let mut name = name.replace('.', "_");
+    if !name.ends_with(unit_suffix) {
+        name.push_str(unit_suffix);
+    }
+    name
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn render_labels(labels: &[(&str, String)]) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key.replace('.', "_"), escape_label(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn operation_labels(base: &str, operation: &str, success: bool) -> String {
+    format!(
+        "{base},{}=\"{}\",db_operation_success=\"{success}\"",
+        attributes::DB_OPERATION.replace('.', "_"),
+        escape_label(operation)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_name() {
+        assert_eq!(
+            prometheus_name(metric_names::DB_CLIENT_CONNECTIONS_CREATE_TOTAL, ""),
+            "db_client_connections_create_total"
+        );
+        assert_eq!(
+            prometheus_name(metric_names::DB_CLIENT_OPERATION_DURATION, "_seconds"),
+            "db_client_operation_duration_seconds"
+        );
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = PrometheusMetrics::new(Some("main"), "sql01", 1433);
+        metrics.record_pool_status(3, 2, 10);
+        metrics.record_connection_created();
+        metrics.record_operation("SELECT", 0.02, true);
+        metrics.record_operation("SELECT", 3.0, true);
+        metrics.record_operation("INSERT", 0.5, false);
+        metrics.record_connection_wait(0.001);
+        metrics.record_connect_timings(&ConnectTimings {
+            total: std::time::Duration::from_millis(40),
+            ..Default::default()
+        });
+
+        let text = metrics.render();
+        let base = "db_system=\"mssql\",server_address=\"sql01\",server_port=\"1433\",\
+                    db_client_pool_name=\"main\"";
+
+        assert!(text.contains("# TYPE db_client_connections_usage gauge\n"));
+        assert!(text.contains(&format!("db_client_connections_usage{{{base}}} 3\n")));
+        assert!(text.contains(&format!("db_client_connections_max{{{base}}} 10\n")));
+        assert!(text.contains(&format!("db_client_connections_create_total{{{base}}} 1\n")));
+
+        let select = format!("{base},db_operation=\"SELECT\",db_operation_success=\"true\"");
+        assert!(text.contains(&format!("db_client_operations_total{{{select}}} 2\n")));
+        assert!(text.contains(&format!(
+            "db_client_operation_duration_seconds_bucket{{{select},le=\"0.025\"}} 1\n"
+        )));
+        assert!(text.contains(&format!(
+            "db_client_operation_duration_seconds_bucket{{{select},le=\"5\"}} 2\n"
+        )));
+        assert!(text.contains(&format!(
+            "db_client_operation_duration_seconds_count{{{select}}} 2\n"
+        )));
+
+        let insert = format!("{base},db_operation=\"INSERT\",db_operation_success=\"false\"");
+        assert!(text.contains(&format!("db_client_errors_total{{{insert}}} 1\n")));
+        assert!(!text.contains(&format!("db_client_errors_total{{{select}}}")));
+
+        assert!(text.contains(&format!(
+            "db_client_connections_wait_time_seconds_count{{{base}}} 1\n"
+        )));
+        assert!(text.contains(&format!(
+            "db_client_connections_create_time_seconds_bucket{{{base},db_mssql_connect_phase=\"total\",le=\"0.05\"}} 1\n"
+        )));
+        assert!(!text.contains("db_mssql_connect_phase=\"tls\""));
+    }
+}
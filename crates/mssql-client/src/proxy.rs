@@ -0,0 +1,291 @@
+//! SOCKS5 and HTTP `CONNECT` tunnel handshakes for [`ProxyConfig`].
+
+use std::io;
+use std::net::IpAddr;
+
+use base64::Engine;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::{ProxyConfig, ProxyKind};
+
+/// Largest HTTP response header accepted from a proxy.
+const MAX_HTTP_RESPONSE: usize = 8192;
+
+/// Ask the proxy on `stream` to open a tunnel to `host`:`port`.
+///
+/// On success the stream carries the tunneled connection.
+pub(crate) async fn handshake<S>(
+    stream: &mut S,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match proxy.kind {
+        ProxyKind::Socks5 => socks5(stream, proxy, host, port).await,
+        ProxyKind::HttpConnect => http_connect(stream, proxy, host, port).await,
+    }
+}
+
+fn proxy_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, message.into())
+}
+
+async fn socks5<S>(stream: &mut S, proxy: &ProxyConfig, host: &str, port: u16) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    const VERSION: u8 = 0x05;
+    const NO_AUTH: u8 = 0x00;
+    const USER_PASS: u8 = 0x02;
+
+    // Method negotiation
+    let credentials = proxy.username.as_deref().zip(proxy.password.as_deref());
+    if credentials.is_some() {
+        stream.write_all(&[VERSION, 2, NO_AUTH, USER_PASS]).await?;
+    } else {
+        stream.write_all(&[VERSION, 1, NO_AUTH]).await?;
+    }
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(proxy_error("proxy is not a SOCKS5 server"));
+    }
+    match (reply[1], credentials) {
+        (NO_AUTH, _) => {}
+        (USER_PASS, Some((username, password))) => {
+            // RFC 1929 username/password sub-negotiation
+            let mut auth = vec![0x01];
+            for field in [username, password] {
+                let len = u8::try_from(field.len())
+                    .map_err(|_| proxy_error("SOCKS5 credentials exceed 255 bytes"))?;
+                auth.push(len);
+                auth.extend_from_slice(field.as_bytes());
+            }
+            stream.write_all(&auth).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0x00 {
+                return Err(proxy_error("SOCKS5 proxy rejected the credentials"));
+            }
+        }
+        _ => {
+            return Err(proxy_error(
+                "SOCKS5 proxy requires an unsupported authentication method",
+            ));
+        }
+    }
+
+    // CONNECT request; host names are resolved by the proxy
+    let mut request = vec![VERSION, 0x01, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let len =
+                u8::try_from(host.len()).map_err(|_| proxy_error("host name exceeds 255 bytes"))?;
+            request.push(0x03);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[1] != 0x00 {
+        let reason = match header[1] {
+            0x01 => "general failure",
+            0x02 => "connection not allowed by ruleset",
+            0x03 => "network unreachable",
+            0x04 => "host unreachable",
+            0x05 => "connection refused",
+            0x06 => "TTL expired",
+            0x07 => "command not supported",
+            0x08 => "address type not supported",
+            _ => "unknown error",
+        };
+        return Err(proxy_error(format!(
+            "SOCKS5 proxy could not connect to {host}:{port}: {reason}"
+        )));
+    }
+
+    // Skip the bound address and port
+    let addr_len = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => usize::from(stream.read_u8().await?),
+        _ => return Err(proxy_error("SOCKS5 proxy sent an invalid address type")),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+async fn http_connect<S>(
+    stream: &mut S,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let authority = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{ip}]:{port}"),
+        _ => format!("{host}:{port}"),
+    };
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some(username) = &proxy.username {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!(
+            "{username}:{}",
+            proxy.password.as_deref().unwrap_or_default()
+        ));
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read byte by byte so nothing past the header is consumed
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_HTTP_RESPONSE {
+            return Err(proxy_error("HTTP proxy response header is too large"));
+        }
+        response.push(stream.read_u8().await?);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let version = parts.next().unwrap_or_default();
+    let status = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/1.") {
+        return Err(proxy_error("proxy did not answer with HTTP"));
+    }
+    if !status.starts_with('2') {
+        return Err(proxy_error(format!(
+            "HTTP proxy refused CONNECT to {authority}: {}",
+            status_line.trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    async fn read_n(stream: &mut tokio::io::DuplexStream, n: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; n];
+        stream.read_exact(&mut buf).await.unwrap();
+        buf
+    }
+
+    #[tokio::test]
+    async fn test_socks5_with_credentials() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = ProxyConfig::socks5("proxy", 1080).credentials("user", "pw");
+
+        let server = tokio::spawn(async move {
+            assert_eq!(read_n(&mut server, 4).await, [5, 2, 0, 2]);
+            server.write_all(&[5, 2]).await.unwrap();
+            assert_eq!(read_n(&mut server, 9).await, b"\x01\x04user\x02pw");
+            server.write_all(&[1, 0]).await.unwrap();
+
+            let mut expected = vec![5, 1, 0, 3, 8];
+            expected.extend_from_slice(b"db.local");
+            expected.extend_from_slice(&1433u16.to_be_bytes());
+            assert_eq!(read_n(&mut server, expected.len()).await, expected);
+            server
+                .write_all(&[5, 0, 0, 1, 10, 0, 0, 1, 0x05, 0x99, b'T'])
+                .await
+                .unwrap();
+        });
+
+        handshake(&mut client, &proxy, "db.local", 1433)
+            .await
+            .unwrap();
+        server.await.unwrap();
+        // Data after the reply belongs to the tunnel
+        assert_eq!(client.read_u8().await.unwrap(), b'T');
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_failure() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = ProxyConfig::socks5("proxy", 1080);
+
+        let server = tokio::spawn(async move {
+            assert_eq!(read_n(&mut server, 3).await, [5, 1, 0]);
+            server.write_all(&[5, 0]).await.unwrap();
+            read_n(&mut server, 10).await;
+            server
+                .write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+
+        let err = handshake(&mut client, &proxy, "10.0.0.1", 1433)
+            .await
+            .unwrap_err();
+        server.await.unwrap();
+        assert!(err.to_string().contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = ProxyConfig::http_connect("proxy", 3128).credentials("user", "pw");
+
+        let server = tokio::spawn(async move {
+            let expected = "CONNECT db.local:1433 HTTP/1.1\r\nHost: db.local:1433\r\n\
+                            Proxy-Authorization: Basic dXNlcjpwdw==\r\n\r\n";
+            assert_eq!(
+                read_n(&mut server, expected.len()).await,
+                expected.as_bytes()
+            );
+            server
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nT")
+                .await
+                .unwrap();
+        });
+
+        handshake(&mut client, &proxy, "db.local", 1433)
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(client.read_u8().await.unwrap(), b'T');
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_refused() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = ProxyConfig::http_connect("proxy", 3128);
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 256];
+            let _ = server.read(&mut buf).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+            // Keep the stream open until the client is done
+            let _ = server.read(&mut buf).await;
+        });
+
+        let err = handshake(&mut client, &proxy, "db.local", 1433)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("407"));
+    }
+}
@@ -1,6 +1,12 @@
 //! Query builder and prepared statement support.
 
-use mssql_types::ToSql;
+use std::time::Duration;
+
+use mssql_types::{SqlValue, ToSql};
+
+use crate::client::{quote_object_name, validate_identifier};
+use crate::error::{Error, Result};
+use crate::returning::{skip_quoted, top_level_words};
 
 /// A prepared query builder.
 ///
@@ -65,7 +71,356 @@ impl<'a> BoundQuery<'a> {
     }
 }
 
+/// A table hint applied through [`QueryOptions::table_hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TableHint {
+    /// `NOLOCK`: read uncommitted data without taking shared locks.
+    NoLock,
+    /// `READPAST`: skip rows locked by other transactions.
+    ReadPast,
+    /// `UPDLOCK`: take update locks instead of shared locks.
+    UpdLock,
+    /// `ROWLOCK`: take row locks instead of page or table locks.
+    RowLock,
+}
+
+impl TableHint {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::NoLock => "NOLOCK",
+            Self::ReadPast => "READPAST",
+            Self::UpdLock => "UPDLOCK",
+            Self::RowLock => "ROWLOCK",
+        }
+    }
+}
+
+/// Per-statement options for
+/// [`Client::query_with_options`](crate::Client::query_with_options) and
+/// [`Client::execute_with_options`](crate::Client::execute_with_options).
+///
+/// Query and table hints are appended to the statement as an `OPTION (...)`
+/// clause on a new line, so the statement must be a single `SELECT`,
+/// `INSERT`, `UPDATE`, `DELETE` or `MERGE` without an `OPTION` clause of its
+/// own; batches and other statements are rejected. Table hints
+/// use `OPTION (TABLE HINT (...))` and name the table the way the statement
+/// exposes it, which is its alias when it has one.
+///
+/// A lock timeout or LOB size limit is set with `SET LOCK_TIMEOUT` or
+/// `SET TEXTSIZE` in front of the statement, which then always runs through
+/// `sp_executesql` so the setting ends with it and does not leak into the
+/// session.
+///
+/// The prefetch limits only apply to
+/// [`Client::query_stream_with_options`](crate::Client::query_stream_with_options);
+/// other queries read the whole response.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use mssql_client::{QueryOptions, TableHint};
+///
+/// let options = QueryOptions::new()
+///     .lock_timeout(Duration::from_secs(2))
+///     .maxdop(1)
+///     .table_hint("o", TableHint::ReadPast);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    lock_timeout: Option<Duration>,
+    max_lob_size: Option<u64>,
+    prefetch_rows: Option<usize>,
+    max_buffered_bytes: Option<usize>,
+    maxdop: Option<u16>,
+    recompile: bool,
+    optimize_for_unknown: bool,
+    optimize_for: Vec<(String, Option<SqlValue>)>,
+    table_hints: Vec<(String, TableHint)>,
+}
+
+impl QueryOptions {
+    /// Create options that leave the statement unchanged.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail with error 1222 when a lock cannot be acquired within `timeout`
+    /// instead of waiting indefinitely.
+    #[must_use]
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Accept large values up to `bytes` long for this statement, instead of
+    /// [`Config::max_lob_size`](crate::Config#structfield.max_lob_size).
+    #[must_use]
+    pub fn max_lob_size(mut self, bytes: u64) -> Self {
+        self.max_lob_size = Some(bytes);
+        self
+    }
+
+    /// Decode at most `rows` rows ahead of the consumer of a
+    /// [`RowStream`](crate::RowStream) (default: 1024).
+    ///
+    /// Values of 0 are treated as 1.
+    #[must_use]
+    pub fn prefetch_rows(mut self, rows: usize) -> Self {
+        self.prefetch_rows = Some(rows);
+        self
+    }
+
+    /// Stop decoding ahead of the consumer of a [`RowStream`](crate::RowStream)
+    /// once the waiting rows hold `bytes` bytes of row data (default: 4 MiB).
+    ///
+    /// One row is always decoded, so a single row larger than the budget is
+    /// still returned; limit those with
+    /// [`max_lob_size`](Self::max_lob_size).
+    #[must_use]
+    pub fn max_buffered_bytes(mut self, bytes: usize) -> Self {
+        self.max_buffered_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit the degree of parallelism (`MAXDOP`); 0 lets the server decide.
+    #[must_use]
+    pub fn maxdop(mut self, degree: u16) -> Self {
+        self.maxdop = Some(degree);
+        self
+    }
+
+    /// Compile a fresh plan for this execution and do not cache it
+    /// (`RECOMPILE`).
+    #[must_use]
+    pub fn recompile(mut self) -> Self {
+        self.recompile = true;
+        self
+    }
+
+    /// Optimize for average parameter values instead of the sniffed ones
+    /// (`OPTIMIZE FOR UNKNOWN`).
+    #[must_use]
+    pub fn optimize_for_unknown(mut self) -> Self {
+        self.optimize_for_unknown = true;
+        self
+    }
+
+    /// Optimize the plan as if `param` had `value`
+    /// (`OPTIMIZE FOR (@param = value)`).
+    ///
+    /// The value is rendered as a literal; only NULL, bit, integer, float
+    /// and string values are supported.
+    #[must_use]
+    pub fn optimize_for(mut self, param: impl Into<String>, value: impl Into<SqlValue>) -> Self {
+        self.optimize_for.push((param.into(), Some(value.into())));
+        self
+    }
+
+    /// Optimize the plan for an average value of `param`
+    /// (`OPTIMIZE FOR (@param UNKNOWN)`).
+    #[must_use]
+    pub fn optimize_for_param_unknown(mut self, param: impl Into<String>) -> Self {
+        self.optimize_for.push((param.into(), None));
+        self
+    }
+
+    /// Apply a table hint to `table`, the table name or alias used in the
+    /// statement.
+    #[must_use]
+    pub fn table_hint(mut self, table: impl Into<String>, hint: TableHint) -> Self {
+        self.table_hints.push((table.into(), hint));
+        self
+    }
+
+    /// Check whether the options change the statement at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock_timeout.is_none() && self.max_lob_size.is_none() && self.query_hints_empty()
+    }
+
+    fn query_hints_empty(&self) -> bool {
+        self.maxdop.is_none()
+            && !self.recompile
+            && !self.optimize_for_unknown
+            && self.optimize_for.is_empty()
+            && self.table_hints.is_empty()
+    }
+
+    /// Check whether the statement must run in its own scope so that a
+    /// `SET` does not outlive it.
+    pub(crate) fn needs_scope(&self) -> bool {
+        self.lock_timeout.is_some() || self.max_lob_size.is_some()
+    }
+
+    /// Get the LOB size limit for the statement's response, if overridden.
+    pub(crate) fn lob_limit(&self) -> Option<u64> {
+        self.max_lob_size
+    }
+
+    /// Get the number of rows a stream may decode ahead of its consumer.
+    pub(crate) fn prefetch_limit(&self) -> usize {
+        self.prefetch_rows.unwrap_or(DEFAULT_PREFETCH_ROWS).max(1)
+    }
+
+    /// Get the bytes of row data a stream may buffer ahead of its consumer.
+    pub(crate) fn buffered_bytes_limit(&self) -> usize {
+        self.max_buffered_bytes
+            .unwrap_or(DEFAULT_MAX_BUFFERED_BYTES)
+    }
+
+    /// Rewrite `sql` with the hints and settings of these options.
+    pub(crate) fn apply(&self, sql: &str) -> Result<String> {
+        let mut out = String::new();
+        if let Some(timeout) = self.lock_timeout {
+            let millis = timeout.as_millis().min(i32::MAX as u128);
+            out.push_str(&format!("SET LOCK_TIMEOUT {millis}; "));
+        }
+        if let Some(bytes) = self.max_lob_size {
+            out.push_str(&crate::config::text_size_sql(bytes));
+            out.push_str("; ");
+        }
+        if self.query_hints_empty() {
+            out.push_str(sql);
+            return Ok(out);
+        }
+
+        let mut hints = Vec::new();
+        if let Some(degree) = self.maxdop {
+            hints.push(format!("MAXDOP {degree}"));
+        }
+        if self.recompile {
+            hints.push("RECOMPILE".to_string());
+        }
+        if self.optimize_for_unknown {
+            hints.push("OPTIMIZE FOR UNKNOWN".to_string());
+        }
+        if !self.optimize_for.is_empty() {
+            let params = self
+                .optimize_for
+                .iter()
+                .map(|(param, value)| {
+                    let name = param.trim_start_matches('@');
+                    validate_identifier(name)?;
+                    Ok(match value {
+                        Some(value) => format!("@{name} = {}", hint_literal(value)?),
+                        None => format!("@{name} UNKNOWN"),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            hints.push(format!("OPTIMIZE FOR ({})", params.join(", ")));
+        }
+        for (table, hint) in &self.table_hints {
+            hints.push(format!(
+                "TABLE HINT ({}, {})",
+                quote_object_name(table)?,
+                hint.as_sql()
+            ));
+        }
+
+        // On a line of its own, so a trailing line comment cannot swallow it
+        out.push_str(hinted_statement(sql)?);
+        out.push_str(&format!("\nOPTION ({})", hints.join(", ")));
+        Ok(out)
+    }
+}
+
+/// Rows a [`RowStream`](crate::RowStream) decodes ahead by default.
+const DEFAULT_PREFETCH_ROWS: usize = 1024;
+
+/// Bytes of row data a [`RowStream`](crate::RowStream) buffers by default.
+const DEFAULT_MAX_BUFFERED_BYTES: usize = 4 * 1024 * 1024;
+
+/// Statements an `OPTION` clause can be appended to.
+const HINTABLE_STATEMENTS: &[&str] = &["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "WITH"];
+
+/// Check that `sql` is a single statement that takes an `OPTION` clause,
+/// and return it without its terminating semicolon and trailing comments.
+fn hinted_statement(sql: &str) -> Result<&str> {
+    let bytes = sql.as_bytes();
+    let mut end = 0;
+    let mut terminated = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            b';' => {
+                terminated = true;
+                i += 1;
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            b => {
+                if terminated {
+                    return Err(Error::Query(
+                        "query hints need a single statement, found several".into(),
+                    ));
+                }
+                i = match b {
+                    b'\'' | b'"' => skip_quoted(bytes, i, b),
+                    b'[' => skip_quoted(bytes, i, b']'),
+                    _ => i + 1,
+                };
+                end = i.min(bytes.len());
+            }
+        }
+    }
+
+    let statement = &sql[..end];
+    let words = top_level_words(statement);
+    let first = words.first().map(|(_, word)| *word).unwrap_or_default();
+    if !HINTABLE_STATEMENTS
+        .iter()
+        .any(|kw| first.eq_ignore_ascii_case(kw))
+    {
+        return Err(Error::Query(format!(
+            "query hints need a SELECT, INSERT, UPDATE, DELETE or MERGE statement, found `{first}`"
+        )));
+    }
+    if words
+        .iter()
+        .any(|(_, word)| word.eq_ignore_ascii_case("OPTION"))
+    {
+        return Err(Error::Query(
+            "query hints cannot be added to a statement with an OPTION clause".into(),
+        ));
+    }
+    Ok(statement)
+}
+
+/// Render a value as a T-SQL literal for `OPTIMIZE FOR`.
+fn hint_literal(value: &SqlValue) -> Result<String> {
+    Ok(match value {
+        SqlValue::Null => "NULL".to_string(),
+        SqlValue::Bool(b) => u8::from(*b).to_string(),
+        SqlValue::TinyInt(n) => n.to_string(),
+        SqlValue::SmallInt(n) => n.to_string(),
+        SqlValue::Int(n) => n.to_string(),
+        SqlValue::BigInt(n) => n.to_string(),
+        SqlValue::Float(f) if f.is_finite() => f.to_string(),
+        SqlValue::Double(f) if f.is_finite() => f.to_string(),
+        SqlValue::String(s) => format!("N'{}'", s.replace('\'', "''")),
+        other => {
+            return Err(Error::Query(format!(
+                "unsupported OPTIMIZE FOR value: {other:?}"
+            )));
+        }
+    })
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -132,4 +487,104 @@ mod tests {
             .bind(&c);
         assert_eq!(bound.params().len(), 3);
     }
+
+    #[test]
+    fn test_query_options_empty_leaves_sql() {
+        let options = QueryOptions::new();
+        assert!(options.is_empty());
+        assert_eq!(options.apply("SELECT 1;").unwrap(), "SELECT 1;");
+    }
+
+    #[test]
+    fn test_query_options_hints() {
+        let options = QueryOptions::new()
+            .lock_timeout(Duration::from_millis(2500))
+            .maxdop(1)
+            .recompile()
+            .optimize_for("@p1", 42)
+            .optimize_for("name", "O'Brien")
+            .optimize_for_param_unknown("p3")
+            .table_hint("o", TableHint::NoLock)
+            .table_hint("dbo.Queue", TableHint::ReadPast);
+        assert!(options.needs_scope());
+        assert_eq!(
+            options
+                .apply("SELECT * FROM Orders o JOIN dbo.Queue ON 1 = 1 ;\n")
+                .unwrap(),
+            "SET LOCK_TIMEOUT 2500; SELECT * FROM Orders o JOIN dbo.Queue ON 1 = 1\n\
+             OPTION (MAXDOP 1, RECOMPILE, \
+             OPTIMIZE FOR (@p1 = 42, @name = N'O''Brien', @p3 UNKNOWN), \
+             TABLE HINT ([o], NOLOCK), TABLE HINT ([dbo].[Queue], READPAST))"
+        );
+    }
+
+    #[test]
+    fn test_query_options_hint_after_line_comment() {
+        let options = QueryOptions::new().maxdop(1);
+        assert_eq!(
+            options
+                .apply("SELECT * FROM Orders -- open orders only")
+                .unwrap(),
+            "SELECT * FROM Orders\nOPTION (MAXDOP 1)"
+        );
+        assert_eq!(
+            options
+                .apply("SELECT ';' AS s -- note\nFROM t; -- done\n")
+                .unwrap(),
+            "SELECT ';' AS s -- note\nFROM t\nOPTION (MAXDOP 1)"
+        );
+    }
+
+    #[test]
+    fn test_query_options_hints_reject_batches() {
+        let options = QueryOptions::new().recompile();
+        assert!(options.apply("SELECT 1; SELECT 2").is_err());
+        assert!(options.apply("UPDATE t SET a = 1;\nDELETE FROM u").is_err());
+        assert!(options.apply("DECLARE @n int = 1").is_err());
+        assert!(options.apply("EXEC dbo.Report").is_err());
+        assert!(options.apply("SELECT * FROM t OPTION (MAXDOP 2)").is_err());
+        assert!(
+            options
+                .apply("WITH c AS (SELECT 1 AS n) SELECT n FROM c")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_query_options_max_lob_size() {
+        let options = QueryOptions::new().max_lob_size(100);
+        assert!(!options.is_empty());
+        assert!(options.needs_scope());
+        assert_eq!(options.lob_limit(), Some(100));
+        assert_eq!(
+            options.apply("SELECT doc FROM Docs").unwrap(),
+            "SET TEXTSIZE 101; SELECT doc FROM Docs"
+        );
+    }
+
+    #[test]
+    fn test_query_options_prefetch_limits() {
+        let options = QueryOptions::new();
+        assert_eq!(options.prefetch_limit(), DEFAULT_PREFETCH_ROWS);
+        assert_eq!(options.buffered_bytes_limit(), DEFAULT_MAX_BUFFERED_BYTES);
+
+        let options = options.prefetch_rows(0).max_buffered_bytes(64 * 1024);
+        assert_eq!(options.prefetch_limit(), 1);
+        assert_eq!(options.buffered_bytes_limit(), 64 * 1024);
+        // The statement itself is unchanged
+        assert!(options.is_empty());
+        assert!(!options.needs_scope());
+    }
+
+    #[test]
+    fn test_query_options_reject_unsafe_names() {
+        let options = QueryOptions::new().table_hint("o]; DROP TABLE x--", TableHint::NoLock);
+        assert!(options.apply("SELECT 1").is_err());
+
+        let options = QueryOptions::new().optimize_for("p1 = 1) --", 1);
+        assert!(options.apply("SELECT 1").is_err());
+
+        let options = QueryOptions::new().optimize_for("p1", f64::NAN);
+        assert!(options.apply("SELECT 1").is_err());
+    }
 }
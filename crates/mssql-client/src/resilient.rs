@@ -0,0 +1,302 @@
+//! Connection wrapper that reconnects after the connection breaks.
+//!
+//! [`ResilientClient`] owns a [`Client<Ready>`] and the [`Config`] it was
+//! made from. When an operation fails because the connection is gone (an
+//! I/O error, a closed connection, or a fatal server error of class 20 or
+//! above), it reconnects following the config's [`RetryPolicy`] and
+//! restores the session state the server reported through ENVCHANGE: the
+//! current database (after `USE`) and language (after `SET LANGUAGE`).
+//!
+//! Whether the failed operation is then run again is up to the caller.
+//! The server may have applied a statement before the connection dropped,
+//! so only operations marked [`Idempotency::Idempotent`] are retried; a
+//! [`Idempotency::NotIdempotent`] operation returns its error after the
+//! reconnect, leaving the client usable for the next call.
+//!
+//! ```rust,ignore
+//! use mssql_client::{Idempotency, ResilientClient};
+//!
+//! let mut client = ResilientClient::connect(config).await?;
+//! let rows = client
+//!     .query("SELECT * FROM dbo.Products", &[], Idempotency::Idempotent)
+//!     .await?;
+//! client
+//!     .execute("INSERT INTO dbo.Log (Msg) VALUES (@p1)", &[&"hi"], Idempotency::NotIdempotent)
+//!     .await?;
+//! ```
+//!
+//! Transactions are not replayed: the wrapper only holds a connection in
+//! the [`Ready`] state.
+//!
+//! [`RetryPolicy`]: crate::RetryPolicy
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::identifier::quote_identifier;
+use crate::state::Ready;
+use crate::stream::QueryStream;
+
+/// Whether an operation may safely run more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotency {
+    /// Running the operation again has no further effect (reads, upserts
+    /// keyed on the same values). Retried after a reconnect.
+    Idempotent,
+    /// The operation must not run twice. Not retried.
+    NotIdempotent,
+}
+
+/// Session state restored after a reconnect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SessionState {
+    database: Option<String>,
+    language: Option<String>,
+}
+
+impl SessionState {
+    fn capture(client: &Client<Ready>) -> Self {
+        Self {
+            database: client.database().map(str::to_string),
+            language: client.language().map(str::to_string),
+        }
+    }
+
+    /// Build the batch that moves a fresh session to this state.
+    fn restore_sql(&self, fresh: &Self) -> Option<String> {
+        let mut sql = Vec::new();
+        if let Some(database) = &self.database {
+            if fresh.database.as_ref() != Some(database) {
+                sql.push(format!("USE {};", quote_identifier(database)));
+            }
+        }
+        if let Some(language) = &self.language {
+            if fresh.language.as_ref() != Some(language) {
+                sql.push(format!("SET LANGUAGE {};", quote_identifier(language)));
+            }
+        }
+        (!sql.is_empty()).then(|| sql.join(" "))
+    }
+}
+
+/// Check if an error means the connection must be replaced.
+fn is_connection_broken(error: &Error) -> bool {
+    error.terminates_connection() || matches!(error, Error::Codec(_))
+}
+
+/// Client that reconnects and restores its session when the connection breaks.
+///
+/// See the [module documentation](self).
+pub struct ResilientClient {
+    config: Config,
+    client: Option<Client<Ready>>,
+    session: SessionState,
+    reconnects: u64,
+}
+
+impl ResilientClient {
+    /// Connect to SQL Server, retrying transient failures per the config's
+    /// retry policy.
+    pub async fn connect(config: Config) -> Result<Self> {
+        let client = Self::connect_with_retry(&config).await?;
+        Ok(Self {
+            session: SessionState::capture(&client),
+            config,
+            client: Some(client),
+            reconnects: 0,
+        })
+    }
+
+    /// Get the configuration used to reconnect.
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the number of times the connection was replaced.
+    #[must_use]
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// Get the underlying client, reconnecting first if the connection was
+    /// lost.
+    ///
+    /// Operations run directly on the client are not retried.
+    pub async fn client(&mut self) -> Result<&mut Client<Ready>> {
+        if self.client.as_ref().is_none_or(Client::is_closed) {
+            self.reconnect().await?;
+        }
+        self.client.as_mut().ok_or(Error::ConnectionClosed)
+    }
+
+    /// Replace the connection and restore the session state.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        if let Some(client) = &self.client {
+            // Keep state changed since the last successful operation
+            if !client.is_closed() {
+                self.session = SessionState::capture(client);
+            }
+        }
+        self.client = None;
+
+        tracing::info!(
+            host = %self.config.host,
+            database = ?self.session.database,
+            "reconnecting after broken connection"
+        );
+        let mut client = Self::connect_with_retry(&self.config).await?;
+        if let Some(sql) = self.session.restore_sql(&SessionState::capture(&client)) {
+            client.simple_query(&sql).await?;
+        }
+        self.client = Some(client);
+        self.reconnects += 1;
+        Ok(())
+    }
+
+    /// Unwrap the current client, if connected.
+    #[must_use]
+    pub fn into_inner(self) -> Option<Client<Ready>> {
+        self.client
+    }
+
+    /// Execute a query and return its rows, reconnecting if the connection
+    /// breaks.
+    pub async fn query(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        idempotency: Idempotency,
+    ) -> Result<QueryStream<'_>> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client()
+                .await?
+                .query(sql, params)
+                .await
+                .map(QueryStream::into_owned);
+            match self.after(result, idempotency, attempt).await? {
+                Some(stream) => return Ok(stream),
+                None => attempt += 1,
+            }
+        }
+    }
+
+    /// Execute a statement and return the number of affected rows,
+    /// reconnecting if the connection breaks.
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        params: &[&(dyn crate::ToSql + Sync)],
+        idempotency: Idempotency,
+    ) -> Result<u64> {
+        let mut attempt = 0;
+        loop {
+            let result = self.client().await?.execute(sql, params).await;
+            match self.after(result, idempotency, attempt).await? {
+                Some(rows) => return Ok(rows),
+                None => attempt += 1,
+            }
+        }
+    }
+
+    /// Handle the outcome of an attempt.
+    ///
+    /// Returns the value on success, `None` if the operation should be
+    /// retried, and the error otherwise.
+    async fn after<T>(
+        &mut self,
+        result: Result<T>,
+        idempotency: Idempotency,
+        attempt: u32,
+    ) -> Result<Option<T>> {
+        let error = match result {
+            Ok(value) => {
+                if let Some(client) = &self.client {
+                    self.session = SessionState::capture(client);
+                }
+                return Ok(Some(value));
+            }
+            Err(e) => e,
+        };
+
+        let closed = self.client.as_ref().is_none_or(Client::is_closed);
+        if !closed && !is_connection_broken(&error) {
+            return Err(error);
+        }
+        tracing::warn!(error = %error, attempt, "connection broken");
+
+        // The dead client cannot report its session; use the last capture
+        self.client = None;
+        self.reconnect().await?;
+
+        if idempotency == Idempotency::Idempotent && self.config.retry.should_retry(attempt) {
+            Ok(None)
+        } else {
+            Err(error)
+        }
+    }
+
+    async fn connect_with_retry(config: &Config) -> Result<Client<Ready>> {
+        let mut attempt = 0;
+        loop {
+            match Client::connect(config.clone()).await {
+                Ok(client) => return Ok(client),
+                Err(e) if e.is_transient() && config.retry.should_retry(attempt) => {
+                    let backoff = config.retry.backoff_for_attempt(attempt);
+                    tracing::debug!(error = %e, attempt, ?backoff, "connect failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ResilientClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResilientClient")
+            .field("host", &self.config.host)
+            .field("connected", &self.client.is_some())
+            .field("session", &self.session)
+            .field("reconnects", &self.reconnects)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_sql() {
+        let before = SessionState {
+            database: Some("Sales]Db".into()),
+            language: Some("Deutsch".into()),
+        };
+        let fresh = SessionState {
+            database: Some("master".into()),
+            language: Some("us_english".into()),
+        };
+        assert_eq!(
+            before.restore_sql(&fresh).as_deref(),
+            Some("USE [Sales]]Db]; SET LANGUAGE [Deutsch];")
+        );
+        assert_eq!(before.restore_sql(&before), None);
+        assert_eq!(SessionState::default().restore_sql(&fresh), None);
+    }
+
+    #[test]
+    fn test_is_connection_broken() {
+        let io = Error::Io(std::sync::Arc::new(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset,
+        )));
+        assert!(is_connection_broken(&io));
+        assert!(is_connection_broken(&Error::ConnectionClosed));
+        assert!(!is_connection_broken(&Error::CommandTimeout));
+        assert!(!is_connection_broken(&Error::Query("bad".into())));
+    }
+}
@@ -0,0 +1,263 @@
+//! Helpers for returning generated values from INSERT statements.
+//!
+//! Two approaches are supported:
+//!
+//! - [`Client::insert_returning_identity`](crate::Client::insert_returning_identity)
+//!   reads `SCOPE_IDENTITY()` in the same scope as the insert, which is
+//!   unaffected by identities generated inside triggers.
+//! - [`Client::insert_returning`](crate::Client::insert_returning) adds an
+//!   `OUTPUT INSERTED.<col>, ...` clause to the statement, returning any
+//!   server-generated columns (identity, defaults, computed) for every
+//!   inserted row.
+//!
+//! Note that SQL Server rejects `OUTPUT` without `INTO` when the target table
+//! has enabled triggers (error 334); use `insert_returning_identity` for such
+//! tables.
+
+use crate::client::validate_identifier;
+use crate::error::{Error, Result};
+
+/// Build an `OUTPUT INSERTED.<col>, ...` clause for the given columns.
+///
+/// Pass `["*"]` to return every column. Column names are validated and
+/// bracket-quoted.
+///
+/// # Example
+///
+/// ```rust
+/// use mssql_client::returning::output_inserted_clause;
+///
+/// let clause = output_inserted_clause(&["Id", "CreatedAt"]).unwrap();
+/// assert_eq!(clause, "OUTPUT INSERTED.[Id], INSERTED.[CreatedAt]");
+/// ```
+pub fn output_inserted_clause(columns: &[&str]) -> Result<String> {
+    if columns.is_empty() {
+        return Err(Error::Config(
+            "OUTPUT clause requires at least one column".into(),
+        ));
+    }
+    if columns == ["*"] {
+        return Ok("OUTPUT INSERTED.*".to_string());
+    }
+
+    let mut clause = String::from("OUTPUT ");
+    for (i, column) in columns.iter().enumerate() {
+        validate_identifier(column)?;
+        if i > 0 {
+            clause.push_str(", ");
+        }
+        clause.push_str("INSERTED.[");
+        clause.push_str(column);
+        clause.push(']');
+    }
+    Ok(clause)
+}
+
+/// Add an `OUTPUT INSERTED` clause to an INSERT statement.
+///
+/// The clause is placed after the target table and column list, before the
+/// `VALUES`, `SELECT`, `DEFAULT VALUES`, or `EXEC` source. String literals,
+/// quoted identifiers, and comments are skipped while locating it.
+///
+/// # Errors
+///
+/// Returns [`Error::Config`] if `sql` is not a single INSERT statement
+/// or already contains an `OUTPUT` clause.
+///
+/// # Example
+///
+/// ```rust
+/// use mssql_client::returning::with_output_inserted;
+///
+/// let sql = with_output_inserted(
+///     "INSERT INTO dbo.Users (Name) VALUES (@p1)",
+///     &["Id"],
+/// ).unwrap();
+/// assert_eq!(sql, "INSERT INTO dbo.Users (Name) OUTPUT INSERTED.[Id] VALUES (@p1)");
+/// ```
+pub fn with_output_inserted(sql: &str, columns: &[&str]) -> Result<String> {
+    let clause = output_inserted_clause(columns)?;
+    let words = top_level_words(sql);
+
+    match words.first() {
+        Some((_, word)) if word.eq_ignore_ascii_case("INSERT") => {}
+        _ => {
+            return Err(Error::Config(
+                "OUTPUT INSERTED requires an INSERT statement".into(),
+            ));
+        }
+    }
+
+    let mut position = None;
+    for (offset, word) in words.iter().skip(1) {
+        if word.eq_ignore_ascii_case("OUTPUT") {
+            return Err(Error::Config(
+                "INSERT statement already has an OUTPUT clause".into(),
+            ));
+        }
+        if ["VALUES", "SELECT", "DEFAULT", "EXEC", "EXECUTE"]
+            .iter()
+            .any(|kw| word.eq_ignore_ascii_case(kw))
+        {
+            position = Some(*offset);
+            break;
+        }
+    }
+
+    let position = position.ok_or_else(|| {
+        Error::Config("could not locate the source of the INSERT statement".into())
+    })?;
+
+    let mut out = String::with_capacity(sql.len() + clause.len() + 1);
+    out.push_str(&sql[..position]);
+    out.push_str(&clause);
+    out.push(' ');
+    out.push_str(&sql[position..]);
+    Ok(out)
+}
+
+/// Collect the bare words outside parentheses, literals, and comments,
+/// along with their byte offsets.
+pub(crate) fn top_level_words(sql: &str) -> Vec<(usize, &str)> {
+    sql_words(sql)
+        .into_iter()
+        .filter(|(_, depth, _)| *depth == 0)
+        .map(|(offset, _, word)| (offset, word))
+        .collect()
+}
+
+/// Collect the bare words outside literals and comments, along with their
+/// byte offsets and parenthesis depths.
+pub(crate) fn sql_words(sql: &str) -> Vec<(usize, usize, &str)> {
+    let bytes = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => i = skip_quoted(bytes, i, b'\''),
+            b'"' => i = skip_quoted(bytes, i, b'"'),
+            b'[' => i = skip_quoted(bytes, i, b']'),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || matches!(bytes[i], b'_' | b'@' | b'#' | b'$'))
+                {
+                    i += 1;
+                }
+                words.push((start, depth, &sql[start..i]));
+            }
+            _ => i += 1,
+        }
+    }
+
+    words
+}
+
+/// Skip a quoted section starting at `start`, honoring doubled closing quotes.
+pub(crate) fn skip_quoted(bytes: &[u8], start: usize, close: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == close {
+            if bytes.get(i + 1) == Some(&close) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_clause_columns() {
+        assert_eq!(
+            output_inserted_clause(&["Id"]).unwrap(),
+            "OUTPUT INSERTED.[Id]"
+        );
+        assert_eq!(output_inserted_clause(&["*"]).unwrap(), "OUTPUT INSERTED.*");
+    }
+
+    #[test]
+    fn test_output_clause_rejects_invalid() {
+        assert!(output_inserted_clause(&[]).is_err());
+        assert!(output_inserted_clause(&["Id]; DROP TABLE x--"]).is_err());
+    }
+
+    #[test]
+    fn test_with_output_values() {
+        let sql = with_output_inserted(
+            "INSERT INTO [dbo].[Users] ([Name], [Email]) VALUES (@p1, @p2)",
+            &["Id", "CreatedAt"],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO [dbo].[Users] ([Name], [Email]) \
+             OUTPUT INSERTED.[Id], INSERTED.[CreatedAt] VALUES (@p1, @p2)"
+        );
+    }
+
+    #[test]
+    fn test_with_output_select_and_default_values() {
+        assert_eq!(
+            with_output_inserted("insert t (a) select a from s", &["*"]).unwrap(),
+            "insert t (a) OUTPUT INSERTED.* select a from s"
+        );
+        assert_eq!(
+            with_output_inserted("INSERT INTO t DEFAULT VALUES", &["Id"]).unwrap(),
+            "INSERT INTO t OUTPUT INSERTED.[Id] DEFAULT VALUES"
+        );
+    }
+
+    #[test]
+    fn test_with_output_skips_literals_and_comments() {
+        let sql = with_output_inserted(
+            "-- seed values\nINSERT INTO [values] ([select]) /* VALUES */ VALUES ('SELECT')",
+            &["Id"],
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "-- seed values\nINSERT INTO [values] ([select]) /* VALUES */ \
+             OUTPUT INSERTED.[Id] VALUES ('SELECT')"
+        );
+    }
+
+    #[test]
+    fn test_with_output_rejects_non_insert() {
+        assert!(with_output_inserted("UPDATE t SET a = 1", &["Id"]).is_err());
+        assert!(
+            with_output_inserted("INSERT INTO t (a) OUTPUT INSERTED.a VALUES (1)", &["Id"])
+                .is_err()
+        );
+    }
+}
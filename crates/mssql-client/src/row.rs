@@ -12,12 +12,13 @@
 //! - `get<T>()` - Type-converting accessor with allocation only if needed
 
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use bytes::Bytes;
+use indexmap::IndexMap;
 
 use mssql_types::decode::{TypeInfo, decode_value};
-use mssql_types::{FromSql, SqlValue, TypeError};
+use mssql_types::{FromSql, SqlValue, TimeZonePolicy, TypeError};
 
 use crate::blob::BlobReader;
 
@@ -83,6 +84,36 @@ pub struct Column {
     /// When present, enables collation-aware decoding that correctly
     /// handles locale-specific ANSI encodings (e.g., Shift_JIS, GB18030).
     pub collation: Option<tds_protocol::Collation>,
+    /// Whether string comparisons on the column are case-sensitive.
+    pub case_sensitive: bool,
+    /// Whether the column is an identity column.
+    pub identity: bool,
+    /// Whether the column is computed.
+    pub computed: bool,
+    /// Whether the column can be updated, or `None` if the server does not
+    /// know.
+    pub updatable: Option<bool>,
+    /// Base table of the column, with each part bracket-quoted, such as
+    /// `[dbo].[Orders]`.
+    ///
+    /// Only known for browse-mode result sets; `None` for expressions.
+    pub base_table: Option<String>,
+    /// Name of the column in its base table.
+    ///
+    /// Only known for browse-mode result sets; `None` for expressions.
+    pub base_column: Option<String>,
+    /// Whether the column is part of its base table's key.
+    ///
+    /// Only known for browse-mode result sets.
+    pub key: bool,
+    /// Whether the server added the column to identify rows; it was not
+    /// selected by the query.
+    ///
+    /// Only known for browse-mode result sets.
+    pub hidden: bool,
+    /// Time zone policy for converting naive date/time values, taken from
+    /// the connection's [`Config`](crate::Config).
+    pub(crate) time_zone: TimeZonePolicy,
 }
 
 impl Column {
@@ -97,6 +128,15 @@ impl Column {
             precision: None,
             scale: None,
             collation: None,
+            case_sensitive: false,
+            identity: false,
+            computed: false,
+            updatable: None,
+            base_table: None,
+            base_column: None,
+            key: false,
+            hidden: false,
+            time_zone: TimeZonePolicy::default(),
         }
     }
 
@@ -238,6 +278,8 @@ fn type_name_to_id(name: &str) -> u8 {
 pub struct ColMetaData {
     /// Column definitions.
     pub columns: Arc<[Column]>,
+    /// Disambiguated column names, built on first use.
+    unique_names: OnceLock<Vec<String>>,
 }
 
 impl ColMetaData {
@@ -245,6 +287,7 @@ impl ColMetaData {
     pub fn new(columns: Vec<Column>) -> Self {
         Self {
             columns: columns.into(),
+            unique_names: OnceLock::new(),
         }
     }
 
@@ -273,6 +316,67 @@ impl ColMetaData {
             .iter()
             .position(|c| c.name.eq_ignore_ascii_case(name))
     }
+
+    /// Get a distinct name for every column, in column order.
+    ///
+    /// Names are compared case-insensitively:
+    ///
+    /// - The first column with a given name keeps it
+    /// - Each later duplicate gets the first free suffix `_2`, `_3`, ...
+    ///   that is neither a column name nor already taken (`id`, `id`
+    ///   become `id`, `id_2`)
+    /// - Unnamed columns (such as `SELECT 1`) are named `column_{index}`,
+    ///   with the same suffix rule if that name is taken
+    ///
+    /// The names are built once and shared by all rows of the result set.
+    #[must_use]
+    pub fn unique_names(&self) -> &[String] {
+        self.unique_names.get_or_init(|| self.build_unique_names())
+    }
+
+    fn build_unique_names(&self) -> Vec<String> {
+        let lower = |name: &str| name.to_ascii_lowercase();
+        let original: std::collections::HashSet<String> =
+            self.columns.iter().map(|c| lower(&c.name)).collect();
+        let mut taken = std::collections::HashSet::new();
+
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let base = if column.name.is_empty() {
+                    format!("column_{index}")
+                } else {
+                    column.name.clone()
+                };
+                let mut name = base.clone();
+                let mut suffix = 1;
+                // A column's own name is only unavailable once taken; a
+                // generated name must not shadow any column name either
+                while taken.contains(&lower(&name))
+                    || (suffix > 1 || column.name.is_empty()) && original.contains(&lower(&name))
+                {
+                    suffix += 1;
+                    name = format!("{base}_{suffix}");
+                }
+                taken.insert(lower(&name));
+                name
+            })
+            .collect()
+    }
+
+    /// Find a column index by name, resolving the disambiguated names of
+    /// [`unique_names`](Self::unique_names) (case-insensitive).
+    ///
+    /// A plain column name resolves to the first column with that name.
+    #[must_use]
+    pub fn find_by_unique_name(&self, name: &str) -> Option<usize> {
+        self.find_by_name(name).or_else(|| {
+            self.unique_names()
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(name))
+        })
+    }
 }
 
 /// A row from a query result.
@@ -329,9 +433,13 @@ impl Row {
     ///
     /// This constructor supports existing code that works with `SqlValue` directly.
     /// It's less efficient than the buffer-based approach but maintains compatibility.
-    #[allow(dead_code)]
     pub(crate) fn from_values(columns: Vec<Column>, values: Vec<SqlValue>) -> Self {
-        let metadata = Arc::new(ColMetaData::new(columns));
+        Self::from_shared_values(Arc::new(ColMetaData::new(columns)), values)
+    }
+
+    /// Create a row from pre-parsed values, sharing the metadata of its
+    /// result set.
+    pub(crate) fn from_shared_values(metadata: Arc<ColMetaData>, values: Vec<SqlValue>) -> Self {
         let slices: Arc<[ColumnSlice]> = values
             .iter()
             .enumerate()
@@ -514,7 +622,7 @@ impl Row {
     /// ```
     #[must_use]
     pub fn get_stream_by_name(&self, name: &str) -> Option<BlobReader> {
-        let index = self.metadata.find_by_name(name)?;
+        let index = self.column_index(name)?;
         self.get_stream(index)
     }
 
@@ -530,40 +638,32 @@ impl Row {
         if let Some(ref values) = self.values {
             return values
                 .get(index)
-                .ok_or_else(|| TypeError::TypeMismatch {
-                    expected: "valid column index",
-                    actual: format!("index {index} out of bounds"),
-                })
-                .and_then(T::from_sql);
+                .ok_or_else(|| self.index_out_of_bounds(index))
+                .and_then(|v| T::from_sql_with_time_zone(v, self.time_zone(index)));
         }
 
         // Otherwise, parse on demand from the buffer
         let slice = self
             .slices
             .get(index)
-            .ok_or_else(|| TypeError::TypeMismatch {
-                expected: "valid column index",
-                actual: format!("index {index} out of bounds"),
-            })?;
-
-        if slice.is_null {
-            return Err(TypeError::UnexpectedNull);
-        }
+            .ok_or_else(|| self.index_out_of_bounds(index))?;
 
         // Parse via SqlValue then convert to target type
         // Note: parse_value uses zero-copy buffer slicing (Arc<Bytes>::slice)
         let value = self.parse_value(index, slice)?;
-        T::from_sql(&value)
+        T::from_sql_with_time_zone(&value, self.time_zone(index))
     }
 
     /// Get a value by column name with type conversion.
     pub fn get_by_name<T: FromSql>(&self, name: &str) -> Result<T, TypeError> {
         let index = self
-            .metadata
-            .find_by_name(name)
+            .column_index(name)
             .ok_or_else(|| TypeError::TypeMismatch {
                 expected: "valid column name",
-                actual: format!("column '{name}' not found"),
+                actual: format!(
+                    "column '{name}' not found (available: {})",
+                    self.column_list()
+                ),
             })?;
 
         self.get(index)
@@ -575,7 +675,8 @@ impl Row {
         if let Some(ref values) = self.values {
             return values
                 .get(index)
-                .and_then(|v| T::from_sql_nullable(v).ok().flatten());
+                .filter(|v| !v.is_null())
+                .and_then(|v| T::from_sql_with_time_zone(v, self.time_zone(index)).ok());
         }
 
         // Otherwise check the slice
@@ -589,7 +690,7 @@ impl Row {
 
     /// Try to get a value by column name, returning None if NULL or not found.
     pub fn try_get_by_name<T: FromSql>(&self, name: &str) -> Option<T> {
-        let index = self.metadata.find_by_name(name)?;
+        let index = self.column_index(name)?;
         self.try_get(index)
     }
 
@@ -613,10 +714,36 @@ impl Row {
     /// Get the raw SQL value by column name.
     #[must_use]
     pub fn get_raw_by_name(&self, name: &str) -> Option<SqlValue> {
-        let index = self.metadata.find_by_name(name)?;
+        let index = self.column_index(name)?;
         self.get_raw(index)
     }
 
+    /// Find a column index by name (case-insensitive).
+    ///
+    /// A name resolves to the first column with that name; later columns
+    /// with the same name are addressed by the disambiguated names of
+    /// [`ColMetaData::unique_names`] (`id_2`, `column_0`, ...). All
+    /// by-name accessors resolve names this way.
+    #[must_use]
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.metadata.find_by_unique_name(name)
+    }
+
+    /// Convert the row into a map from column name to value, in column order.
+    ///
+    /// Keys are the disambiguated names of [`ColMetaData::unique_names`], so
+    /// duplicate and unnamed columns are kept. Values that cannot be decoded
+    /// are `NULL`.
+    #[must_use]
+    pub fn into_map(self) -> IndexMap<String, SqlValue> {
+        self.metadata
+            .unique_names()
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), self.get_raw(index).unwrap_or(SqlValue::Null)))
+            .collect()
+    }
+
     // ========================================================================
     // Metadata Access
     // ========================================================================
@@ -654,16 +781,84 @@ impl Row {
     /// Check if a column value is NULL by name.
     #[must_use]
     pub fn is_null_by_name(&self, name: &str) -> bool {
-        self.metadata
-            .find_by_name(name)
+        self.column_index(name)
             .map(|i| self.is_null(i))
             .unwrap_or(true)
     }
 
+    /// Get the columns whose names start with `prefix` as a row of their own.
+    ///
+    /// The prefix is matched case-insensitively and removed from the column
+    /// names, so with prefix `billing_` the column `billing_city` becomes
+    /// `city`. `#[mssql(flatten, prefix = "...")]` uses this to map repeated
+    /// column groups onto the same struct.
+    pub fn strip_column_prefix(&self, prefix: &str) -> Result<Row, TypeError> {
+        let mut columns = Vec::new();
+        let mut values = Vec::new();
+        for (index, column) in self.columns().iter().enumerate() {
+            let Some(name) = column
+                .name
+                .get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &column.name[prefix.len()..])
+            else {
+                continue;
+            };
+            let value = match self.values {
+                Some(ref cached) => cached.get(index).cloned().unwrap_or(SqlValue::Null),
+                None => match self.slices.get(index) {
+                    Some(slice) => self.parse_value(index, slice)?,
+                    None => SqlValue::Null,
+                },
+            };
+
+            let mut column = column.clone();
+            column.name = name.to_string();
+            column.index = columns.len();
+            columns.push(column);
+            values.push(value);
+        }
+        Ok(Row::from_values(columns, values))
+    }
+
     // ========================================================================
     // Internal Helpers
     // ========================================================================
 
+    /// Describe the columns for lookup errors, e.g. `0: id, 1: (unnamed)`.
+    fn column_list(&self) -> String {
+        self.columns()
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if c.name.is_empty() {
+                    format!("{i}: (unnamed)")
+                } else {
+                    format!("{i}: {}", c.name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn time_zone(&self, index: usize) -> TimeZonePolicy {
+        self.metadata
+            .columns
+            .get(index)
+            .map(|c| c.time_zone)
+            .unwrap_or_default()
+    }
+
+    fn index_out_of_bounds(&self, index: usize) -> TypeError {
+        TypeError::TypeMismatch {
+            expected: "valid column index",
+            actual: format!(
+                "index {index} out of bounds (available: {})",
+                self.column_list()
+            ),
+        }
+    }
+
     /// Parse a value from the buffer at the given slice.
     ///
     /// Uses the mssql-types decode module for efficient binary parsing.
@@ -720,6 +915,34 @@ impl std::fmt::Debug for Row {
     }
 }
 
+/// Serializes the row as a map from column name to value, in column order.
+///
+/// Keys are the disambiguated names of [`ColMetaData::unique_names`].
+///
+/// Values use the representations of `SqlValue`'s `Serialize` impl. A
+/// value that cannot be decoded fails serialization rather than being
+/// written as `null`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Row {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error as _, SerializeMap};
+
+        let names = self.metadata.unique_names();
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (index, (column, name)) in self.metadata.columns.iter().zip(names).enumerate() {
+            let value = match (&self.values, self.slices.get(index)) {
+                (Some(values), _) => values.get(index).cloned().unwrap_or(SqlValue::Null),
+                (None, Some(slice)) => self
+                    .parse_value(index, slice)
+                    .map_err(|e| S::Error::custom(format!("column '{}': {e}", column.name)))?,
+                (None, None) => SqlValue::Null,
+            };
+            map.serialize_entry(name, &value)?;
+        }
+        map.end()
+    }
+}
+
 /// Iterator over row values as SqlValue.
 pub struct RowIter<'a> {
     row: &'a Row,
@@ -781,6 +1004,59 @@ mod tests {
         assert_eq!(col.precision, Some(10));
     }
 
+    #[test]
+    fn test_col_metadata_unique_names() {
+        let meta = ColMetaData::new(vec![
+            Column::new("id", 0, "INT"),
+            Column::new("ID", 1, "INT"),
+            Column::new("", 2, "INT"),
+            Column::new("id_2", 3, "INT"),
+            Column::new("column_2", 4, "INT"),
+        ]);
+
+        assert_eq!(
+            meta.unique_names(),
+            vec!["id", "ID_3", "column_2_2", "id_2", "column_2"]
+        );
+        assert_eq!(meta.find_by_unique_name("Id"), Some(0));
+        assert_eq!(meta.find_by_unique_name("id_3"), Some(1));
+        assert_eq!(meta.find_by_unique_name("column_2_2"), Some(2));
+        assert_eq!(meta.find_by_unique_name("id_2"), Some(3));
+        assert_eq!(meta.find_by_unique_name("column_2"), Some(4));
+        assert_eq!(meta.find_by_unique_name("id_4"), None);
+        // Built once and reused by later lookups
+        assert!(std::ptr::eq(meta.unique_names(), meta.unique_names()));
+    }
+
+    #[test]
+    fn test_row_into_map_and_column_index() {
+        let columns = vec![
+            Column::new("name", 0, "NVARCHAR"),
+            Column::new("name", 1, "NVARCHAR"),
+            Column::new("", 2, "INT"),
+        ];
+        let row = Row::from_values(
+            columns,
+            vec![
+                SqlValue::String("a".into()),
+                SqlValue::String("b".into()),
+                SqlValue::Int(1),
+            ],
+        );
+
+        assert_eq!(row.column_index("NAME"), Some(0));
+        assert_eq!(row.column_index("name_2"), Some(1));
+        assert_eq!(row.column_index("column_2"), Some(2));
+        assert_eq!(row.get_by_name::<String>("name_2").unwrap(), "b");
+
+        let map = row.into_map();
+        assert_eq!(
+            map.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["name", "name_2", "column_2"]
+        );
+        assert!(matches!(map.get("column_2"), Some(SqlValue::Int(1))));
+    }
+
     #[test]
     fn test_col_metadata_find_by_name() {
         let meta = ColMetaData::new(vec![
@@ -824,6 +1100,62 @@ mod tests {
         assert!(row.is_null(99)); // Out of bounds returns true
     }
 
+    #[test]
+    fn test_row_get_option_of_null_buffer_column() {
+        let buffer = Arc::new(Bytes::new());
+        let slices: Arc<[ColumnSlice]> = vec![ColumnSlice::null()].into();
+        let meta = Arc::new(ColMetaData::new(vec![Column::new("val", 0, "INT")]));
+
+        let row = Row::new(buffer, slices, meta);
+
+        assert_eq!(row.get::<Option<i32>>(0).unwrap(), None);
+        assert!(matches!(row.get::<i32>(0), Err(TypeError::UnexpectedNull)));
+    }
+
+    #[test]
+    fn test_row_lookup_errors_list_columns() {
+        let columns = vec![Column::new("id", 0, "INT"), Column::new("", 1, "INT")];
+        let row = Row::from_values(columns, vec![SqlValue::Int(1), SqlValue::Int(2)]);
+
+        let err = row.get_by_name::<i32>("name").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type mismatch: expected valid column name, got column 'name' not found \
+             (available: 0: id, 1: (unnamed))"
+        );
+        let err = row.get::<i32>(5).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("index 5 out of bounds (available: 0: id")
+        );
+    }
+
+    #[test]
+    fn test_row_strip_column_prefix() {
+        let columns = vec![
+            Column::new("id", 0, "INT"),
+            Column::new("Billing_City", 1, "NVARCHAR"),
+            Column::new("billing_zip", 2, "NVARCHAR"),
+            Column::new("shipping_city", 3, "NVARCHAR"),
+        ];
+        let values = vec![
+            SqlValue::Int(1),
+            SqlValue::String("Oslo".to_string()),
+            SqlValue::Null,
+            SqlValue::String("Bergen".to_string()),
+        ];
+        let row = Row::from_values(columns, values);
+
+        let billing = row.strip_column_prefix("billing_").unwrap();
+        assert_eq!(billing.len(), 2);
+        assert_eq!(billing.columns()[0].name, "City");
+        assert_eq!(billing.columns()[1].index, 1);
+        assert_eq!(billing.get_by_name::<String>("city").unwrap(), "Oslo");
+        assert!(billing.is_null_by_name("zip"));
+
+        assert!(row.strip_column_prefix("other_").unwrap().is_empty());
+    }
+
     #[test]
     fn test_row_get_bytes_with_buffer() {
         let buffer = Arc::new(Bytes::from_static(b"Hello World"));
@@ -867,6 +1199,29 @@ mod tests {
         assert_eq!(row.metadata().len(), 1);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_row_serialize() {
+        let columns = vec![
+            Column::new("id", 0, "INT"),
+            Column::new("name", 1, "NVARCHAR"),
+            Column::new("photo", 2, "VARBINARY"),
+        ];
+        let row = Row::from_values(
+            columns,
+            vec![
+                SqlValue::Int(7),
+                SqlValue::Null,
+                SqlValue::Binary(Bytes::from_static(b"hi")),
+            ],
+        );
+
+        assert_eq!(
+            serde_json::to_string(&row).unwrap(),
+            r#"{"id":7,"name":null,"photo":"aGk="}"#
+        );
+    }
+
     #[test]
     fn test_row_get_stream() {
         let buffer = Arc::new(Bytes::from_static(b"Hello, World!"));
@@ -923,4 +1278,23 @@ mod tests {
         // Unknown column returns None
         assert!(row.get_stream_by_name("unknown").is_none());
     }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_row_get_uses_column_time_zone() {
+        let naive = chrono::NaiveDate::from_ymd_opt(2024, 6, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let mut column = Column::new("created", 0, "DateTime2");
+        let row = Row::from_values(vec![column.clone()], vec![SqlValue::DateTime(naive)]);
+        let utc: chrono::DateTime<chrono::Utc> = row.get(0).unwrap();
+        assert_eq!(utc.naive_utc(), naive);
+
+        column.time_zone = TimeZonePolicy::RejectNaive;
+        let row = Row::from_values(vec![column], vec![SqlValue::DateTime(naive)]);
+        assert!(row.get::<chrono::DateTime<chrono::Utc>>(0).is_err());
+        assert!(row.try_get::<chrono::DateTime<chrono::Utc>>(0).is_none());
+        assert_eq!(row.get::<chrono::NaiveDateTime>(0).unwrap(), naive);
+    }
 }
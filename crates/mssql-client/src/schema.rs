@@ -0,0 +1,310 @@
+//! Database schema introspection.
+//!
+//! [`Client::schema`](crate::Client::schema) returns a [`SchemaInspector`]
+//! that reads the catalog views (`sys.objects`, `sys.columns`,
+//! `sys.indexes`, ...) into typed structs, for code generation, migration
+//! diffing, and admin tooling.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! for table in client.schema().tables().await? {
+//!     println!("{}", table.qualified_name());
+//!     for column in client.schema().columns(&table.schema, &table.name).await? {
+//!         println!(
+//!             "  {} {}{}",
+//!             column.name,
+//!             column.data_type,
+//!             if column.is_nullable { "" } else { " NOT NULL" }
+//!         );
+//!     }
+//! }
+//! ```
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::identifier::quote_identifier;
+use crate::row::Row;
+use crate::state::ConnectionState;
+
+const TABLES_SQL: &str = "\
+SELECT s.name, o.name, o.type_desc \
+FROM sys.objects o \
+JOIN sys.schemas s ON s.schema_id = o.schema_id \
+WHERE o.type IN ('U', 'V') AND o.is_ms_shipped = 0";
+
+const COLUMNS_SQL: &str = "\
+SELECT c.name, c.column_id, ty.name, c.max_length, c.precision, c.scale, \
+    c.is_nullable, c.is_identity, c.is_computed, dc.definition, c.collation_name, \
+    CAST(CASE WHEN EXISTS ( \
+        SELECT 1 FROM sys.index_columns ic \
+        JOIN sys.indexes i ON i.object_id = ic.object_id AND i.index_id = ic.index_id \
+        WHERE i.is_primary_key = 1 AND ic.object_id = c.object_id AND ic.column_id = c.column_id \
+    ) THEN 1 ELSE 0 END AS BIT) \
+FROM sys.columns c \
+JOIN sys.types ty ON ty.user_type_id = c.user_type_id \
+LEFT JOIN sys.default_constraints dc ON dc.object_id = c.default_object_id \
+WHERE c.object_id = OBJECT_ID(QUOTENAME(@p1) + N'.' + QUOTENAME(@p2)) \
+ORDER BY c.column_id";
+
+const INDEXES_SQL: &str = "\
+SELECT i.name, i.type_desc, i.is_unique, i.is_primary_key, \
+    c.name, ic.is_descending_key, ic.is_included_column \
+FROM sys.indexes i \
+JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id \
+JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id \
+WHERE i.object_id = OBJECT_ID(QUOTENAME(@p1) + N'.' + QUOTENAME(@p2)) AND i.type > 0 \
+ORDER BY i.index_id, ic.is_included_column, ic.key_ordinal, ic.index_column_id";
+
+/// The kind of a table-like object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TableKind {
+    /// A user table.
+    Table,
+    /// A view.
+    View,
+}
+
+/// A table or view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// Schema name.
+    pub schema: String,
+    /// Table name.
+    pub name: String,
+    /// Whether this is a table or a view.
+    pub kind: TableKind,
+}
+
+impl TableInfo {
+    /// Get the bracket-quoted `[schema].[name]`.
+    #[must_use]
+    pub fn qualified_name(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&self.schema),
+            quote_identifier(&self.name)
+        )
+    }
+}
+
+/// A column of a table or view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    /// Column name.
+    pub name: String,
+    /// 1-based column position.
+    pub ordinal: i32,
+    /// Full SQL type as it would appear in DDL, e.g. `nvarchar(100)` or `decimal(18,2)`.
+    pub data_type: String,
+    /// Base type name, e.g. `nvarchar`.
+    pub type_name: String,
+    /// Maximum length in bytes (-1 for `MAX` types).
+    pub max_length: i16,
+    /// Numeric precision (0 for non-numeric types).
+    pub precision: u8,
+    /// Numeric or fractional-seconds scale.
+    pub scale: u8,
+    /// Whether the column accepts NULL.
+    pub is_nullable: bool,
+    /// Whether the column is an identity column.
+    pub is_identity: bool,
+    /// Whether the column is computed.
+    pub is_computed: bool,
+    /// Whether the column is part of the primary key.
+    pub is_primary_key: bool,
+    /// Default constraint definition, e.g. `(getdate())`.
+    pub default: Option<String>,
+    /// Collation of character columns.
+    pub collation: Option<String>,
+}
+
+/// A column of an index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexColumn {
+    /// Column name.
+    pub name: String,
+    /// Whether the key column is sorted descending.
+    pub descending: bool,
+}
+
+/// An index on a table or view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    /// Index name.
+    pub name: String,
+    /// Index type, e.g. `CLUSTERED` or `NONCLUSTERED`.
+    pub kind: String,
+    /// Whether the index enforces uniqueness.
+    pub is_unique: bool,
+    /// Whether the index backs the primary key.
+    pub is_primary_key: bool,
+    /// Key columns, in key order.
+    pub columns: Vec<IndexColumn>,
+    /// Non-key columns added with `INCLUDE`.
+    pub included_columns: Vec<String>,
+}
+
+/// Reads schema metadata through a client.
+///
+/// Created with [`Client::schema`](crate::Client::schema).
+pub struct SchemaInspector<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+}
+
+impl<'a, S: ConnectionState> SchemaInspector<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self { client }
+    }
+
+    /// List user tables and views, ordered by schema and name.
+    pub async fn tables(&mut self) -> Result<Vec<TableInfo>> {
+        let sql = format!("{TABLES_SQL} ORDER BY s.name, o.name");
+        let rows = self.client.fetch_rows(&sql, &[]).await?;
+        rows.iter().map(table_from_row).collect()
+    }
+
+    /// Look up a single table or view.
+    pub async fn table(&mut self, schema: &str, name: &str) -> Result<Option<TableInfo>> {
+        let sql = format!("{TABLES_SQL} AND s.name = @p1 AND o.name = @p2");
+        let rows = self.client.fetch_rows(&sql, &[&schema, &name]).await?;
+        rows.first().map(table_from_row).transpose()
+    }
+
+    /// List the columns of a table or view, in column order.
+    ///
+    /// Returns an empty list if the table does not exist.
+    pub async fn columns(&mut self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        let rows = self
+            .client
+            .fetch_rows(COLUMNS_SQL, &[&schema, &table])
+            .await?;
+        rows.iter().map(column_from_row).collect()
+    }
+
+    /// List the indexes of a table or view, excluding heaps.
+    pub async fn indexes(&mut self, schema: &str, table: &str) -> Result<Vec<IndexInfo>> {
+        let rows = self
+            .client
+            .fetch_rows(INDEXES_SQL, &[&schema, &table])
+            .await?;
+
+        let mut indexes: Vec<IndexInfo> = Vec::new();
+        for row in &rows {
+            let name: String = row.get(0)?;
+            if indexes.last().is_none_or(|index| index.name != name) {
+                indexes.push(IndexInfo {
+                    name,
+                    kind: row.get(1)?,
+                    is_unique: row.get(2)?,
+                    is_primary_key: row.get(3)?,
+                    columns: Vec::new(),
+                    included_columns: Vec::new(),
+                });
+            }
+            let Some(index) = indexes.last_mut() else {
+                continue;
+            };
+
+            let column: String = row.get(4)?;
+            if row.get::<bool>(6)? {
+                index.included_columns.push(column);
+            } else {
+                index.columns.push(IndexColumn {
+                    name: column,
+                    descending: row.get(5)?,
+                });
+            }
+        }
+
+        Ok(indexes)
+    }
+}
+
+impl<S: ConnectionState> std::fmt::Debug for SchemaInspector<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaInspector").finish_non_exhaustive()
+    }
+}
+
+fn table_from_row(row: &Row) -> Result<TableInfo> {
+    let type_desc: String = row.get(2)?;
+    Ok(TableInfo {
+        schema: row.get(0)?,
+        name: row.get(1)?,
+        kind: if type_desc == "VIEW" {
+            TableKind::View
+        } else {
+            TableKind::Table
+        },
+    })
+}
+
+fn column_from_row(row: &Row) -> Result<ColumnInfo> {
+    let type_name: String = row.get(2)?;
+    let max_length: i16 = row.get(3)?;
+    let precision: u8 = row.get(4)?;
+    let scale: u8 = row.get(5)?;
+
+    Ok(ColumnInfo {
+        name: row.get(0)?,
+        ordinal: row.get(1)?,
+        data_type: format_sql_type(&type_name, max_length, precision, scale),
+        type_name,
+        max_length,
+        precision,
+        scale,
+        is_nullable: row.get(6)?,
+        is_identity: row.get(7)?,
+        is_computed: row.get(8)?,
+        default: row.get(9)?,
+        collation: row.get(10)?,
+        is_primary_key: row.get(11)?,
+    })
+}
+
+/// Format a type as it appears in DDL from its `sys.columns` attributes.
+fn format_sql_type(type_name: &str, max_length: i16, precision: u8, scale: u8) -> String {
+    let length = |bytes_per_char: i16| {
+        if max_length == -1 {
+            "max".to_string()
+        } else {
+            (max_length / bytes_per_char).to_string()
+        }
+    };
+
+    match type_name {
+        "nvarchar" | "nchar" => format!("{type_name}({})", length(2)),
+        "varchar" | "char" | "varbinary" | "binary" => format!("{type_name}({})", length(1)),
+        "decimal" | "numeric" => format!("{type_name}({precision},{scale})"),
+        "datetime2" | "datetimeoffset" | "time" => format!("{type_name}({scale})"),
+        _ => type_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_sql_type() {
+        assert_eq!(format_sql_type("nvarchar", 200, 0, 0), "nvarchar(100)");
+        assert_eq!(format_sql_type("nvarchar", -1, 0, 0), "nvarchar(max)");
+        assert_eq!(format_sql_type("varbinary", 16, 0, 0), "varbinary(16)");
+        assert_eq!(format_sql_type("decimal", 9, 18, 2), "decimal(18,2)");
+        assert_eq!(format_sql_type("datetime2", 8, 27, 7), "datetime2(7)");
+        assert_eq!(format_sql_type("int", 4, 10, 0), "int");
+    }
+
+    #[test]
+    fn test_qualified_name() {
+        let table = TableInfo {
+            schema: "dbo".into(),
+            name: "Order]Lines".into(),
+            kind: TableKind::Table,
+        };
+        assert_eq!(table.qualified_name(), "[dbo].[Order]]Lines]");
+    }
+}
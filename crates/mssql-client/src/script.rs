@@ -0,0 +1,396 @@
+//! sqlcmd-style scripts with `GO` batch separators.
+//!
+//! `GO` is not T-SQL: it is a client-side separator understood by SSMS and
+//! sqlcmd that splits a script into batches sent to the server one at a
+//! time. [`Script`] performs the same splitting so that migration scripts
+//! authored for those tools can be run unmodified.
+//!
+//! A separator is a line containing only `GO` (case-insensitive), optionally
+//! followed by a repeat count and a `--` comment, e.g. `GO 5 -- seed rows`.
+//! Lines inside string literals, quoted identifiers, and block comments are
+//! never treated as separators. sqlcmd commands such as `:setvar` and `:r`
+//! are not supported.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use mssql_client::Script;
+//!
+//! let script = Script::parse(include_str!("../migrations/001_init.sql"))?;
+//! let result = client.execute_script(&script).await?;
+//!
+//! for (batch, error) in result.errors() {
+//!     eprintln!("batch starting at line {} failed: {error}", batch.line);
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+
+/// A single batch of a [`Script`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptBatch {
+    sql: String,
+    line: usize,
+    repeat: u32,
+}
+
+impl ScriptBatch {
+    /// Get the SQL text of the batch.
+    #[must_use]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Get the 1-based line number in the script where the batch starts.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Get the number of times the batch is executed (the `GO n` count).
+    #[must_use]
+    pub fn repeat(&self) -> u32 {
+        self.repeat
+    }
+}
+
+/// A T-SQL script split into batches on `GO` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    batches: Vec<ScriptBatch>,
+    continue_on_error: bool,
+}
+
+impl Script {
+    /// Split a script into batches.
+    ///
+    /// Batches containing only whitespace are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if a `GO` separator has a repeat count of
+    /// zero or one that does not fit in a `u32`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mssql_client::Script;
+    ///
+    /// let script = Script::parse(
+    ///     "CREATE TABLE t (id INT)\nGO\nINSERT INTO t VALUES (1)\nGO 3\n",
+    /// ).unwrap();
+    ///
+    /// assert_eq!(script.len(), 2);
+    /// assert_eq!(script.batches()[1].sql(), "INSERT INTO t VALUES (1)\n");
+    /// assert_eq!(script.batches()[1].repeat(), 3);
+    /// ```
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut batches = Vec::new();
+        let mut current = String::new();
+        let mut start_line = 1;
+        let mut state = ScanState::Normal;
+
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let line_number = index + 1;
+
+            if state == ScanState::Normal {
+                if let Some(repeat) = parse_separator(line, line_number)? {
+                    push_batch(&mut batches, &mut current, start_line, repeat);
+                    start_line = line_number + 1;
+                    continue;
+                }
+            }
+
+            // Leading blank lines are dropped so that line numbers in server
+            // errors count from `ScriptBatch::line`
+            if current.trim().is_empty() {
+                current.clear();
+                start_line = line_number;
+            }
+            current.push_str(line);
+            state = scan_line(line, state);
+        }
+
+        push_batch(&mut batches, &mut current, start_line, 1);
+
+        Ok(Self {
+            batches,
+            continue_on_error: false,
+        })
+    }
+
+    /// Keep executing the remaining batches after one fails.
+    ///
+    /// By default execution stops at the first failed batch, as sqlcmd does
+    /// with `-b`.
+    #[must_use]
+    pub fn continue_on_error(mut self, enabled: bool) -> Self {
+        self.continue_on_error = enabled;
+        self
+    }
+
+    /// Check if execution continues after a failed batch.
+    #[must_use]
+    pub fn continues_on_error(&self) -> bool {
+        self.continue_on_error
+    }
+
+    /// Get the batches of the script.
+    #[must_use]
+    pub fn batches(&self) -> &[ScriptBatch] {
+        &self.batches
+    }
+
+    /// Get the number of batches.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Check if the script has no batches.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+}
+
+/// Outcome of one batch of an executed [`Script`].
+#[derive(Debug)]
+pub struct ScriptBatchResult {
+    /// Index of the batch within the script.
+    pub index: usize,
+    /// Line in the script where the batch starts.
+    pub line: usize,
+    /// Total rows affected by all repetitions, or the error that stopped the batch.
+    pub result: Result<u64>,
+}
+
+/// Per-batch results of an executed [`Script`].
+///
+/// Batches that were not run because an earlier batch failed are not
+/// included.
+#[derive(Debug, Default)]
+pub struct ScriptResult {
+    batches: Vec<ScriptBatchResult>,
+}
+
+impl ScriptResult {
+    pub(crate) fn push(&mut self, result: ScriptBatchResult) {
+        self.batches.push(result);
+    }
+
+    /// Get the results of the executed batches, in script order.
+    #[must_use]
+    pub fn batches(&self) -> &[ScriptBatchResult] {
+        &self.batches
+    }
+
+    /// Check if every executed batch succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.batches.iter().all(|b| b.result.is_ok())
+    }
+
+    /// Iterate over the failed batches.
+    pub fn errors(&self) -> impl Iterator<Item = (&ScriptBatchResult, &Error)> {
+        self.batches
+            .iter()
+            .filter_map(|b| b.result.as_ref().err().map(|e| (b, e)))
+    }
+
+    /// Get the total number of rows affected by the successful batches.
+    #[must_use]
+    pub fn total_rows_affected(&self) -> u64 {
+        self.batches
+            .iter()
+            .filter_map(|b| b.result.as_ref().ok())
+            .sum()
+    }
+}
+
+/// Lexical state carried from one line to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Normal,
+    /// Inside a string literal or quoted identifier closed by the given byte.
+    Quoted(u8),
+    /// Inside a block comment with the given nesting depth.
+    BlockComment(usize),
+}
+
+/// Advance the lexical state over a single line.
+fn scan_line(line: &str, mut state: ScanState) -> ScanState {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match state {
+            ScanState::Normal => match bytes[i] {
+                b'-' if bytes.get(i + 1) == Some(&b'-') => break,
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    state = ScanState::BlockComment(1);
+                    i += 1;
+                }
+                b'\'' => state = ScanState::Quoted(b'\''),
+                b'"' => state = ScanState::Quoted(b'"'),
+                b'[' => state = ScanState::Quoted(b']'),
+                _ => {}
+            },
+            // A doubled closing quote closes and immediately reopens the
+            // literal, so it needs no special handling
+            ScanState::Quoted(close) => {
+                if bytes[i] == close {
+                    state = ScanState::Normal;
+                }
+            }
+            ScanState::BlockComment(depth) => {
+                if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+                    state = ScanState::BlockComment(depth + 1);
+                    i += 1;
+                } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+                    state = if depth == 1 {
+                        ScanState::Normal
+                    } else {
+                        ScanState::BlockComment(depth - 1)
+                    };
+                    i += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    state
+}
+
+/// Check if a line is a `GO [count] [-- comment]` separator, returning the count.
+fn parse_separator(line: &str, line_number: usize) -> Result<Option<u32>> {
+    let line = line.trim();
+    let line = match line.find("--") {
+        Some(pos) => line[..pos].trim_end(),
+        None => line,
+    };
+
+    let mut parts = line.split_whitespace();
+    match parts.next() {
+        Some(word) if word.eq_ignore_ascii_case("GO") => {}
+        _ => return Ok(None),
+    }
+
+    let repeat = match (parts.next(), parts.next()) {
+        (None, _) => 1,
+        (Some(count), None) if count.bytes().all(|b| b.is_ascii_digit()) => {
+            match count.parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    return Err(Error::Config(format!(
+                        "invalid GO repeat count '{count}' on line {line_number}"
+                    )));
+                }
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(repeat))
+}
+
+fn push_batch(batches: &mut Vec<ScriptBatch>, current: &mut String, line: usize, repeat: u32) {
+    let sql = std::mem::take(current);
+    if !sql.trim().is_empty() {
+        batches.push(ScriptBatch { sql, line, repeat });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_on_go() {
+        let script = Script::parse(
+            "CREATE TABLE a (id INT);\ngo\n\nCREATE TABLE b (id INT);\n  GO  \nSELECT 1",
+        )
+        .unwrap();
+
+        let batches = script.batches();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].sql(), "CREATE TABLE a (id INT);\n");
+        assert_eq!(batches[0].line(), 1);
+        assert_eq!(batches[1].sql(), "CREATE TABLE b (id INT);\n");
+        assert_eq!(batches[1].line(), 4);
+        assert_eq!(batches[2].sql(), "SELECT 1");
+        assert_eq!(batches[2].line(), 6);
+    }
+
+    #[test]
+    fn test_go_repeat_count_and_comment() {
+        let script =
+            Script::parse("INSERT INTO t DEFAULT VALUES\nGO 10 -- seed rows\r\nSELECT 1\r\n")
+                .unwrap();
+
+        assert_eq!(script.len(), 2);
+        assert_eq!(script.batches()[0].repeat(), 10);
+        assert_eq!(script.batches()[1].repeat(), 1);
+        assert_eq!(script.batches()[1].sql(), "SELECT 1\r\n");
+    }
+
+    #[test]
+    fn test_go_inside_string_and_comments() {
+        let sql = "INSERT INTO t VALUES ('line one\nGO\nline three')\n\
+                   /* block\nGO\n/* nested */\nGO\n*/\n\
+                   SELECT [col\nGO\n]\n\
+                   GO\n\
+                   SELECT 2 -- GO\n";
+        let script = Script::parse(sql).unwrap();
+
+        assert_eq!(script.len(), 2);
+        assert!(script.batches()[0].sql().contains("line three"));
+        assert!(script.batches()[0].sql().contains("nested */\nGO\n*/"));
+        assert!(script.batches()[0].sql().contains("SELECT [col\nGO\n]"));
+        assert_eq!(script.batches()[1].sql(), "SELECT 2 -- GO\n");
+    }
+
+    #[test]
+    fn test_not_a_separator() {
+        let script = Script::parse("GOTO done\nGO fish\ndone:\nSELECT 1\n").unwrap();
+        assert_eq!(script.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_batches_dropped() {
+        let script = Script::parse("GO\n\n  \nGO\nSELECT 1\nGO\n").unwrap();
+        assert_eq!(script.len(), 1);
+        assert_eq!(script.batches()[0].line(), 5);
+
+        assert!(Script::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_repeat_count() {
+        assert!(Script::parse("SELECT 1\nGO 0\n").is_err());
+        assert!(Script::parse("SELECT 1\nGO 99999999999\n").is_err());
+    }
+
+    #[test]
+    fn test_script_result() {
+        let mut result = ScriptResult::default();
+        result.push(ScriptBatchResult {
+            index: 0,
+            line: 1,
+            result: Ok(3),
+        });
+        result.push(ScriptBatchResult {
+            index: 1,
+            line: 4,
+            result: Err(Error::Query("invalid object name".into())),
+        });
+
+        assert!(!result.is_success());
+        assert_eq!(result.total_rows_affected(), 3);
+        let errors: Vec<_> = result.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0.line, 4);
+    }
+}
@@ -0,0 +1,474 @@
+//! Service Broker messaging.
+//!
+//! [`Client::service_broker`](crate::Client::service_broker) returns a
+//! [`ServiceBroker`] that opens dialogs, sends messages on them, and waits
+//! for messages on a queue, so Rust services can take part in Service
+//! Broker (SSB) conversations.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use mssql_client::service_broker::DialogOptions;
+//!
+//! // Initiator
+//! let options = DialogOptions::new("//App/Initiator", "//App/Target", "//App/Contract");
+//! let handle = client.service_broker().begin_dialog(&options).await?;
+//! client
+//!     .service_broker()
+//!     .send_on_conversation(&handle, "//App/Request", b"<order id=\"42\"/>")
+//!     .await?;
+//!
+//! // Target
+//! let messages = client
+//!     .service_broker()
+//!     .receive("dbo.TargetQueue", Duration::from_secs(30))
+//!     .await?;
+//! for message in messages {
+//!     if message.is_end_dialog() || message.is_error() {
+//!         client.service_broker().end_conversation(&message.conversation_handle).await?;
+//!     } else {
+//!         handle_request(message.body.as_deref());
+//!     }
+//! }
+//! ```
+//!
+//! ## Waiting and cancellation
+//!
+//! [`ServiceBroker::receive`] sends `WAITFOR (RECEIVE ...), TIMEOUT`, so the
+//! server ends the wait and returns an empty result when no message arrives
+//! in time; the connection stays usable. The wait can be interrupted early
+//! from another task with [`Client::cancel_handle`](crate::Client::cancel_handle).
+//! As a safeguard against an unresponsive server, the client also stops
+//! waiting [`RECEIVE_GRACE_PERIOD`] after the server timeout should have
+//! fired; the connection is then closed because the response was only
+//! partially read, and [`Error::CommandTimeout`] is returned.
+//!
+//! `RECEIVE` removes messages from the queue. Run it inside a transaction
+//! ([`Client::begin_transaction`](crate::Client::begin_transaction)) to
+//! put the messages back if processing fails.
+
+use std::time::Duration;
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::identifier::quote_identifier;
+use crate::row::Row;
+use crate::state::ConnectionState;
+
+/// Message type sent by Service Broker when the remote side ends a dialog.
+pub const END_DIALOG_MESSAGE_TYPE: &str =
+    "http://schemas.microsoft.com/SQL/ServiceBroker/EndDialog";
+
+/// Message type sent by Service Broker when a dialog ends with an error.
+pub const ERROR_MESSAGE_TYPE: &str = "http://schemas.microsoft.com/SQL/ServiceBroker/Error";
+
+/// Message type sent when a dialog timer set with `BEGIN CONVERSATION TIMER` expires.
+pub const DIALOG_TIMER_MESSAGE_TYPE: &str =
+    "http://schemas.microsoft.com/SQL/ServiceBroker/DialogTimer";
+
+/// How long the client waits past the server-side `RECEIVE` timeout.
+pub const RECEIVE_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Identifies one side of a Service Broker conversation.
+///
+/// Holds the `conversation_handle` GUID in its canonical string form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConversationHandle(String);
+
+impl ConversationHandle {
+    /// Wrap a conversation handle GUID, e.g. one stored in a table.
+    pub fn new(handle: impl Into<String>) -> Self {
+        Self(handle.into())
+    }
+
+    /// Get the handle as a GUID string.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ConversationHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Options for [`ServiceBroker::begin_dialog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogOptions {
+    /// Initiating service in the current database.
+    pub from_service: String,
+    /// Target service name (case-sensitive).
+    pub to_service: String,
+    /// Broker instance of the target service, or `None` for the current database.
+    pub broker_instance: Option<String>,
+    /// Contract the conversation follows.
+    pub contract: String,
+    /// Maximum conversation lifetime in seconds, or `None` for the server default.
+    pub lifetime: Option<u32>,
+    /// Whether messages must be encrypted when leaving the instance.
+    pub encryption: bool,
+}
+
+impl DialogOptions {
+    /// Create options for a dialog between two services on a contract.
+    ///
+    /// Encryption is off, which suits dialogs within one instance.
+    pub fn new(
+        from_service: impl Into<String>,
+        to_service: impl Into<String>,
+        contract: impl Into<String>,
+    ) -> Self {
+        Self {
+            from_service: from_service.into(),
+            to_service: to_service.into(),
+            broker_instance: None,
+            contract: contract.into(),
+            lifetime: None,
+            encryption: false,
+        }
+    }
+
+    /// Target a service in another database by its broker instance GUID.
+    #[must_use]
+    pub fn broker_instance(mut self, broker_instance: impl Into<String>) -> Self {
+        self.broker_instance = Some(broker_instance.into());
+        self
+    }
+
+    /// Set the maximum conversation lifetime in seconds.
+    #[must_use]
+    pub fn lifetime(mut self, seconds: u32) -> Self {
+        self.lifetime = Some(seconds);
+        self
+    }
+
+    /// Require message encryption.
+    #[must_use]
+    pub fn encryption(mut self, encryption: bool) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Build the `BEGIN DIALOG` batch; `@p1` is the target service and
+    /// `@p2` the broker instance.
+    fn to_sql(&self) -> String {
+        let target = if self.broker_instance.is_some() {
+            "@p1, @p2"
+        } else {
+            "@p1"
+        };
+        let mut options = format!("ENCRYPTION = {}", on_off(self.encryption));
+        if let Some(lifetime) = self.lifetime {
+            options = format!("LIFETIME = {lifetime}, {options}");
+        }
+
+        format!(
+            "DECLARE @h UNIQUEIDENTIFIER; \
+             BEGIN DIALOG CONVERSATION @h FROM SERVICE {} TO SERVICE {target} \
+             ON CONTRACT {} WITH {options}; \
+             SELECT CAST(@h AS NVARCHAR(36))",
+            quote_identifier(&self.from_service),
+            quote_identifier(&self.contract),
+        )
+    }
+}
+
+/// A message received from a queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokerMessage {
+    /// Conversation the message belongs to.
+    pub conversation_handle: ConversationHandle,
+    /// Conversation group of the conversation.
+    pub conversation_group_id: String,
+    /// Sequence number of the message within the conversation.
+    pub sequence_number: i64,
+    /// Service the message was sent to.
+    pub service_name: String,
+    /// Contract the conversation follows.
+    pub contract_name: String,
+    /// Message type name.
+    pub message_type: String,
+    /// Message body, `None` for messages without one.
+    pub body: Option<Vec<u8>>,
+}
+
+impl BrokerMessage {
+    /// Check if the remote side ended the dialog.
+    #[must_use]
+    pub fn is_end_dialog(&self) -> bool {
+        self.message_type == END_DIALOG_MESSAGE_TYPE
+    }
+
+    /// Check if the dialog ended with an error.
+    ///
+    /// The body holds the error description as UTF-16 XML.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.message_type == ERROR_MESSAGE_TYPE
+    }
+
+    /// Check if this is a conversation timer message.
+    #[must_use]
+    pub fn is_dialog_timer(&self) -> bool {
+        self.message_type == DIALOG_TIMER_MESSAGE_TYPE
+    }
+
+    /// Check if this is one of the messages Service Broker sends itself.
+    #[must_use]
+    pub fn is_system_message(&self) -> bool {
+        self.is_end_dialog() || self.is_error() || self.is_dialog_timer()
+    }
+
+    /// Get the body as UTF-8 text.
+    #[must_use]
+    pub fn body_str(&self) -> Option<&str> {
+        self.body
+            .as_deref()
+            .and_then(|body| std::str::from_utf8(body).ok())
+    }
+}
+
+/// Sends and receives Service Broker messages through a client.
+///
+/// Created with [`Client::service_broker`](crate::Client::service_broker).
+pub struct ServiceBroker<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+}
+
+impl<'a, S: ConnectionState> ServiceBroker<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>) -> Self {
+        Self { client }
+    }
+
+    /// Begin a dialog and return its conversation handle.
+    pub async fn begin_dialog(&mut self, options: &DialogOptions) -> Result<ConversationHandle> {
+        let sql = options.to_sql();
+        let rows = match &options.broker_instance {
+            Some(instance) => {
+                self.client
+                    .fetch_rows(&sql, &[&options.to_service.as_str(), &instance.as_str()])
+                    .await?
+            }
+            None => {
+                self.client
+                    .fetch_rows(&sql, &[&options.to_service.as_str()])
+                    .await?
+            }
+        };
+
+        let handle: String = rows
+            .first()
+            .ok_or_else(|| Error::Protocol("BEGIN DIALOG returned no handle".into()))?
+            .get(0)?;
+        Ok(ConversationHandle(handle))
+    }
+
+    /// Send a message on a conversation.
+    pub async fn send_on_conversation(
+        &mut self,
+        handle: &ConversationHandle,
+        message_type: &str,
+        body: &[u8],
+    ) -> Result<()> {
+        let sql = send_sql(message_type);
+        self.client
+            .fetch_rows(&sql, &[&handle.as_str(), &body])
+            .await?;
+        Ok(())
+    }
+
+    /// End a conversation.
+    pub async fn end_conversation(&mut self, handle: &ConversationHandle) -> Result<()> {
+        self.client
+            .fetch_rows(END_CONVERSATION_SQL, &[&handle.as_str()])
+            .await?;
+        Ok(())
+    }
+
+    /// End a conversation with an error that is sent to the remote side.
+    pub async fn end_conversation_with_error(
+        &mut self,
+        handle: &ConversationHandle,
+        code: i32,
+        description: &str,
+    ) -> Result<()> {
+        self.client
+            .fetch_rows(
+                END_CONVERSATION_WITH_ERROR_SQL,
+                &[&handle.as_str(), &code, &description],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for messages on a queue and receive them.
+    ///
+    /// Returns every message of the first conversation group that has
+    /// messages, or an empty list if none arrive in time. The queue name may
+    /// be schema-qualified, e.g. `dbo.TargetQueue`.
+    pub async fn receive(&mut self, queue: &str, timeout: Duration) -> Result<Vec<BrokerMessage>> {
+        self.receive_top(queue, None, timeout).await
+    }
+
+    /// Wait up to `timeout` for one message on a queue and receive it.
+    pub async fn receive_one(
+        &mut self,
+        queue: &str,
+        timeout: Duration,
+    ) -> Result<Option<BrokerMessage>> {
+        let messages = self.receive_top(queue, Some(1), timeout).await?;
+        Ok(messages.into_iter().next())
+    }
+
+    async fn receive_top(
+        &mut self,
+        queue: &str,
+        top: Option<u32>,
+        timeout: Duration,
+    ) -> Result<Vec<BrokerMessage>> {
+        let sql = receive_sql(queue, top, timeout);
+        let rows = match tokio::time::timeout(
+            timeout + RECEIVE_GRACE_PERIOD,
+            self.client.fetch_rows(&sql, &[]),
+        )
+        .await
+        {
+            Ok(rows) => rows?,
+            Err(_) => {
+                self.client
+                    .abandon_connection("Service Broker RECEIVE did not return in time");
+                return Err(Error::CommandTimeout);
+            }
+        };
+
+        rows.iter().map(message_from_row).collect()
+    }
+}
+
+impl<S: ConnectionState> std::fmt::Debug for ServiceBroker<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceBroker").finish_non_exhaustive()
+    }
+}
+
+const END_CONVERSATION_SQL: &str = "\
+DECLARE @h UNIQUEIDENTIFIER = @p1; \
+END CONVERSATION @h";
+
+const END_CONVERSATION_WITH_ERROR_SQL: &str = "\
+DECLARE @h UNIQUEIDENTIFIER = @p1; \
+END CONVERSATION @h WITH ERROR = @p2 DESCRIPTION = @p3";
+
+fn send_sql(message_type: &str) -> String {
+    format!(
+        "DECLARE @h UNIQUEIDENTIFIER = @p1; \
+         SEND ON CONVERSATION @h MESSAGE TYPE {} (@p2)",
+        quote_identifier(message_type)
+    )
+}
+
+fn receive_sql(queue: &str, top: Option<u32>, timeout: Duration) -> String {
+    let top = top.map(|n| format!("TOP ({n}) ")).unwrap_or_default();
+    // TIMEOUT takes an int; clamp rather than wrap for very long waits.
+    let timeout_ms = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+    let queue = queue
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".");
+
+    format!(
+        "WAITFOR (RECEIVE {top}CAST(conversation_handle AS NVARCHAR(36)), \
+         CAST(conversation_group_id AS NVARCHAR(36)), message_sequence_number, \
+         service_name, service_contract_name, message_type_name, message_body \
+         FROM {queue}), TIMEOUT {timeout_ms}"
+    )
+}
+
+fn message_from_row(row: &Row) -> Result<BrokerMessage> {
+    Ok(BrokerMessage {
+        conversation_handle: ConversationHandle(row.get(0)?),
+        conversation_group_id: row.get(1)?,
+        sequence_number: row.get(2)?,
+        service_name: row.get(3)?,
+        contract_name: row.get(4)?,
+        message_type: row.get(5)?,
+        body: row.get(6)?,
+    })
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: &str) -> BrokerMessage {
+        BrokerMessage {
+            conversation_handle: ConversationHandle::new("6F9619FF-8B86-D011-B42D-00C04FC964FF"),
+            conversation_group_id: "7A9619FF-8B86-D011-B42D-00C04FC964FF".into(),
+            sequence_number: 0,
+            service_name: "//App/Target".into(),
+            contract_name: "//App/Contract".into(),
+            message_type: message_type.into(),
+            body: Some(b"<order/>".to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_begin_dialog_sql() {
+        let options = DialogOptions::new("//App/Initiator", "//App/Target", "//App/Contract");
+        assert_eq!(
+            options.to_sql(),
+            "DECLARE @h UNIQUEIDENTIFIER; \
+             BEGIN DIALOG CONVERSATION @h FROM SERVICE [//App/Initiator] TO SERVICE @p1 \
+             ON CONTRACT [//App/Contract] WITH ENCRYPTION = OFF; \
+             SELECT CAST(@h AS NVARCHAR(36))"
+        );
+
+        let options = options
+            .broker_instance("A1B2C3D4-0000-0000-0000-000000000000")
+            .lifetime(600)
+            .encryption(true);
+        let sql = options.to_sql();
+        assert!(sql.contains("TO SERVICE @p1, @p2 "));
+        assert!(sql.contains("WITH LIFETIME = 600, ENCRYPTION = ON;"));
+    }
+
+    #[test]
+    fn test_send_sql_quotes_message_type() {
+        assert_eq!(
+            send_sql("//App/Req]uest"),
+            "DECLARE @h UNIQUEIDENTIFIER = @p1; \
+             SEND ON CONVERSATION @h MESSAGE TYPE [//App/Req]]uest] (@p2)"
+        );
+    }
+
+    #[test]
+    fn test_receive_sql() {
+        let sql = receive_sql("dbo.TargetQueue", Some(1), Duration::from_secs(30));
+        assert!(sql.starts_with("WAITFOR (RECEIVE TOP (1) CAST(conversation_handle"));
+        assert!(sql.ends_with("FROM [dbo].[TargetQueue]), TIMEOUT 30000"));
+
+        let sql = receive_sql("TargetQueue", None, Duration::from_secs(u64::MAX / 1000));
+        assert!(sql.starts_with("WAITFOR (RECEIVE CAST("));
+        assert!(sql.ends_with("FROM [TargetQueue]), TIMEOUT 2147483647"));
+    }
+
+    #[test]
+    fn test_message_kinds() {
+        assert!(message(END_DIALOG_MESSAGE_TYPE).is_end_dialog());
+        assert!(message(ERROR_MESSAGE_TYPE).is_error());
+        assert!(message(DIALOG_TIMER_MESSAGE_TYPE).is_system_message());
+
+        let request = message("//App/Request");
+        assert!(!request.is_system_message());
+        assert_eq!(request.body_str(), Some("<order/>"));
+    }
+}
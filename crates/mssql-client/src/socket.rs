@@ -0,0 +1,214 @@
+//! TCP socket creation with the options from [`SocketConfig`].
+
+use std::io;
+use std::net::SocketAddr;
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{TcpSocket, TcpStream};
+
+use crate::config::SocketConfig;
+
+/// Connect to the first reachable address, returning the last error if none is.
+pub(crate) async fn connect_any(
+    addrs: &[SocketAddr],
+    options: &SocketConfig,
+) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in addrs {
+        // A socket bound to an IPv4 address cannot reach an IPv6 server
+        if options
+            .local_address
+            .is_some_and(|local| local.is_ipv4() != addr.is_ipv4())
+        {
+            continue;
+        }
+        match connect(*addr, options).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no server address matches the configured local address",
+        )
+    }))
+}
+
+/// Create a socket for `addr`, apply the configured options and connect.
+pub(crate) async fn connect(addr: SocketAddr, options: &SocketConfig) -> io::Result<TcpStream> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+
+    // Buffer sizes must be set before connecting to affect the TCP window
+    if let Some(size) = options.recv_buffer_size {
+        socket.set_recv_buffer_size(size)?;
+    }
+    if let Some(size) = options.send_buffer_size {
+        socket.set_send_buffer_size(size)?;
+    }
+    if let Some(keepalive) = options.keepalive_time {
+        SockRef::from(&socket).set_tcp_keepalive(&keepalive_params(keepalive, options))?;
+    }
+    if let Some(local) = options.local_address {
+        socket.bind(SocketAddr::new(local, 0))?;
+    }
+
+    let stream = socket.connect(addr).await?;
+    stream.set_nodelay(options.nodelay)?;
+    Ok(stream)
+}
+
+/// A duplicate of a connection's TCP socket, for checking whether the
+/// server closed it without reading from the connection.
+#[derive(Debug)]
+pub(crate) struct SocketProbe(socket2::Socket);
+
+impl SocketProbe {
+    pub(crate) fn new(stream: &TcpStream) -> io::Result<Self> {
+        let socket = SockRef::from(stream).try_clone()?;
+        socket.set_nonblocking(true)?;
+        Ok(Self(socket))
+    }
+
+    /// Check whether the peer closed or reset the connection.
+    ///
+    /// Peeks at the socket, so buffered data is left for the connection to
+    /// read. Data waiting to be read says nothing about a later close; the
+    /// socket is only reported closed once the data is consumed.
+    pub(crate) fn is_closed(&self) -> bool {
+        let mut buf = [std::mem::MaybeUninit::<u8>::uninit(); 1];
+        match self.0.peek(&mut buf) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(e) => !matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+            ),
+        }
+    }
+}
+
+fn keepalive_params(time: std::time::Duration, options: &SocketConfig) -> TcpKeepalive {
+    let params = TcpKeepalive::new().with_time(time);
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        windows
+    ))]
+    let params = match options.keepalive_interval {
+        Some(interval) => params.with_interval(interval),
+        None => params,
+    };
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd"
+    ))]
+    let params = match options.keepalive_retries {
+        Some(retries) => params.with_retries(retries),
+        None => params,
+    };
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd"
+    )))]
+    let _ = options.keepalive_retries;
+    params
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_connect_applies_options() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let options = SocketConfig::new()
+            .nodelay(false)
+            .keepalive_time(Duration::from_secs(45))
+            .keepalive_interval(Duration::from_secs(5))
+            .keepalive_retries(4)
+            .recv_buffer_size(64 * 1024)
+            .local_address("127.0.0.1".parse().unwrap());
+        let stream = connect(addr, &options).await.unwrap();
+
+        assert!(!stream.nodelay().unwrap());
+        assert_eq!(
+            stream.local_addr().unwrap().ip(),
+            options.local_address.unwrap()
+        );
+        let sock = SockRef::from(&stream);
+        assert!(sock.keepalive().unwrap());
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(sock.tcp_keepalive_time().unwrap(), Duration::from_secs(45));
+            assert_eq!(
+                sock.tcp_keepalive_interval().unwrap(),
+                Duration::from_secs(5)
+            );
+            assert_eq!(sock.tcp_keepalive_retries().unwrap(), 4);
+        }
+
+        let stream = connect(addr, &SocketConfig::new().no_keepalive())
+            .await
+            .unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_socket_probe() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut stream = connect(addr, &SocketConfig::new()).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        let probe = SocketProbe::new(&stream).unwrap();
+        assert!(!probe.is_closed());
+
+        // Pending data is peeked, not consumed
+        server.write_all(b"x").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!probe.is_closed());
+        let mut buf = [0u8; 1];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"x");
+
+        drop(server);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(probe.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_skips_other_family() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let v6: SocketAddr = format!("[::1]:{}", addr.port()).parse().unwrap();
+
+        let options = SocketConfig::new().local_address("127.0.0.1".parse().unwrap());
+        let stream = connect_any(&[v6, addr], &options).await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+
+        assert!(connect_any(&[v6], &options).await.is_err());
+    }
+}
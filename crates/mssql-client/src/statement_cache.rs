@@ -6,8 +6,9 @@
 //!
 //! ## Lifecycle
 //!
-//! 1. First execution of a parameterized query calls `sp_prepare`, returning a handle
-//! 2. The handle is cached by SQL hash; subsequent executions use `sp_execute`
+//! 1. [`Client::prepare`](crate::Client::prepare) calls `sp_prepare`, returning a handle
+//! 2. The handle is cached by the hash of the SQL text and parameter types;
+//!    executions of the same statement use `sp_execute`
 //! 3. When the cache is full, LRU eviction calls `sp_unprepare` for evicted handles
 //! 4. Pool reset (`sp_reset_connection`) invalidates all handles, clearing the cache
 //! 5. Connection close implicitly releases all server-side handles
@@ -0,0 +1,251 @@
+//! Per-query execution statistics.
+//!
+//! [`Client::query_with_stats`](crate::Client::query_with_stats) runs a query
+//! with `SET STATISTICS IO, TIME ON` and parses the informational messages
+//! the server sends back into a [`QueryStatistics`].
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! let (rows, stats) = client
+//!     .query_with_stats("SELECT * FROM Orders WHERE CustomerId = @p1", &[&42])
+//!     .await?;
+//! println!(
+//!     "{} logical reads, {} ms CPU, {} ms elapsed",
+//!     stats.logical_reads(),
+//!     stats.execution.cpu_ms,
+//!     stats.execution.elapsed_ms
+//! );
+//! for table in &stats.tables {
+//!     println!("  {}: {} scans, {} reads", table.table, table.scan_count, table.logical_reads);
+//! }
+//! ```
+
+use crate::message::ServerMessage;
+
+/// Message number of `SET STATISTICS TIME` execution times.
+const EXECUTION_TIME_MESSAGE: i32 = 3612;
+/// Message number of `SET STATISTICS TIME` parse and compile times.
+const COMPILE_TIME_MESSAGE: i32 = 3613;
+/// Message number of `SET STATISTICS IO` table I/O.
+const TABLE_IO_MESSAGE: i32 = 3615;
+
+/// I/O performed on one table, from `SET STATISTICS IO`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableIoStatistics {
+    /// Table name, e.g. `Orders` or `Worktable`.
+    pub table: String,
+    /// Number of seeks or scans.
+    pub scan_count: u64,
+    /// Pages read from the buffer cache.
+    pub logical_reads: u64,
+    /// Pages read from disk.
+    pub physical_reads: u64,
+    /// Pages read ahead into the cache.
+    pub read_ahead_reads: u64,
+    /// LOB pages read from the buffer cache.
+    pub lob_logical_reads: u64,
+    /// LOB pages read from disk.
+    pub lob_physical_reads: u64,
+    /// LOB pages read ahead into the cache.
+    pub lob_read_ahead_reads: u64,
+}
+
+/// CPU and elapsed time, from `SET STATISTICS TIME`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeStatistics {
+    /// CPU time in milliseconds.
+    pub cpu_ms: u64,
+    /// Elapsed time in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+/// Execution statistics of one request.
+///
+/// Times are summed over every statement of the request. A table touched
+/// by several statements appears once per statement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryStatistics {
+    /// Per-table I/O.
+    pub tables: Vec<TableIoStatistics>,
+    /// Parse and compile time.
+    pub compile: TimeStatistics,
+    /// Execution time.
+    pub execution: TimeStatistics,
+}
+
+impl QueryStatistics {
+    /// Parse statistics from the informational messages of a request.
+    ///
+    /// Messages other than `SET STATISTICS IO` and `TIME` output are ignored.
+    #[must_use]
+    pub fn from_messages(messages: &[ServerMessage]) -> Self {
+        let mut stats = Self::default();
+        for message in messages {
+            match message.number {
+                TABLE_IO_MESSAGE => {
+                    if let Some(table) = parse_table_io(&message.message) {
+                        stats.tables.push(table);
+                    }
+                }
+                EXECUTION_TIME_MESSAGE => add_times(&mut stats.execution, &message.message),
+                COMPILE_TIME_MESSAGE => add_times(&mut stats.compile, &message.message),
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Total logical reads across all tables.
+    #[must_use]
+    pub fn logical_reads(&self) -> u64 {
+        self.tables.iter().map(|t| t.logical_reads).sum()
+    }
+
+    /// Total physical reads across all tables.
+    #[must_use]
+    pub fn physical_reads(&self) -> u64 {
+        self.tables.iter().map(|t| t.physical_reads).sum()
+    }
+
+    /// Total scan count across all tables.
+    #[must_use]
+    pub fn scan_count(&self) -> u64 {
+        self.tables.iter().map(|t| t.scan_count).sum()
+    }
+}
+
+/// Parse `Table 'Orders'. Scan count 1, logical reads 12, ...`.
+fn parse_table_io(text: &str) -> Option<TableIoStatistics> {
+    let rest = text.trim().strip_prefix("Table '")?;
+    let (table, counters) = rest.split_once("'.")?;
+
+    let mut stats = TableIoStatistics {
+        table: table.to_string(),
+        ..TableIoStatistics::default()
+    };
+    for counter in counters.split(',') {
+        let counter = counter.trim().trim_end_matches('.');
+        let Some((name, value)) = counter.rsplit_once(' ') else {
+            continue;
+        };
+        let Ok(value) = value.parse() else {
+            continue;
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "scan count" => stats.scan_count = value,
+            "logical reads" => stats.logical_reads = value,
+            "physical reads" => stats.physical_reads = value,
+            "read-ahead reads" => stats.read_ahead_reads = value,
+            "lob logical reads" => stats.lob_logical_reads = value,
+            "lob physical reads" => stats.lob_physical_reads = value,
+            "lob read-ahead reads" => stats.lob_read_ahead_reads = value,
+            _ => {}
+        }
+    }
+    Some(stats)
+}
+
+/// Add the `CPU time = N ms, elapsed time = M ms` figures of a message.
+fn add_times(times: &mut TimeStatistics, text: &str) {
+    if let Some(cpu) = time_after(text, "CPU time =") {
+        times.cpu_ms += cpu;
+    }
+    if let Some(elapsed) = time_after(text, "elapsed time =") {
+        times.elapsed_ms += elapsed;
+    }
+}
+
+fn time_after(text: &str, label: &str) -> Option<u64> {
+    let start = text.find(label)? + label.len();
+    text[start..]
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.trim_end_matches(',').parse().ok())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn message(number: i32, text: &str) -> ServerMessage {
+        ServerMessage {
+            number,
+            state: 1,
+            class: 0,
+            message: text.to_string(),
+            server: None,
+            procedure: None,
+            line: 1,
+        }
+    }
+
+    #[test]
+    fn test_parse_table_io() {
+        let table = parse_table_io(
+            "Table 'Orders'. Scan count 1, logical reads 12, physical reads 2, \
+             page server reads 0, read-ahead reads 8, page server read-ahead reads 0, \
+             lob logical reads 3, lob physical reads 0, lob page server reads 0, \
+             lob read-ahead reads 1, lob page server read-ahead reads 0.",
+        )
+        .unwrap();
+
+        assert_eq!(table.table, "Orders");
+        assert_eq!(table.scan_count, 1);
+        assert_eq!(table.logical_reads, 12);
+        assert_eq!(table.physical_reads, 2);
+        assert_eq!(table.read_ahead_reads, 8);
+        assert_eq!(table.lob_logical_reads, 3);
+        assert_eq!(table.lob_read_ahead_reads, 1);
+
+        assert!(parse_table_io("not statistics").is_none());
+    }
+
+    #[test]
+    fn test_from_messages() {
+        let messages = [
+            message(
+                COMPILE_TIME_MESSAGE,
+                "SQL Server parse and compile time: \n   CPU time = 2 ms, elapsed time = 5 ms.",
+            ),
+            message(
+                TABLE_IO_MESSAGE,
+                "Table 'Orders'. Scan count 1, logical reads 12, physical reads 0.",
+            ),
+            message(
+                TABLE_IO_MESSAGE,
+                "Table 'Worktable'. Scan count 0, logical reads 4, physical reads 1.",
+            ),
+            message(
+                EXECUTION_TIME_MESSAGE,
+                " SQL Server Execution Times:\n   CPU time = 15 ms,  elapsed time = 20 ms.",
+            ),
+            message(
+                EXECUTION_TIME_MESSAGE,
+                " SQL Server Execution Times:\n   CPU time = 1 ms,  elapsed time = 2 ms.",
+            ),
+            message(0, "PRINT output"),
+        ];
+
+        let stats = QueryStatistics::from_messages(&messages);
+        assert_eq!(stats.tables.len(), 2);
+        assert_eq!(stats.logical_reads(), 16);
+        assert_eq!(stats.physical_reads(), 1);
+        assert_eq!(stats.scan_count(), 1);
+        assert_eq!(
+            stats.compile,
+            TimeStatistics {
+                cpu_ms: 2,
+                elapsed_ms: 5
+            }
+        );
+        assert_eq!(
+            stats.execution,
+            TimeStatistics {
+                cpu_ms: 16,
+                elapsed_ms: 22
+            }
+        );
+    }
+}
@@ -12,16 +12,28 @@
 //! 2. Memory is shared via `Arc<Bytes>` pattern per ADR-004
 //! 3. No complex lifetime/borrow issues with the connection
 //!
-//! For truly large result sets, consider using OFFSET/FETCH pagination.
+//! For large result sets, [`Client::query_stream`](crate::Client::query_stream)
+//! returns a [`RowStream`] instead, which reads the response packet by
+//! packet and decodes only a bounded number of rows ahead of the consumer.
+//! A slow consumer then holds back the server through TCP flow control
+//! rather than having the whole response buffered.
 
 use std::collections::VecDeque;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use bytes::BytesMut;
 use futures_core::Stream;
+use tds_protocol::token::{ColMetaData, TabName};
 
-use crate::error::Error;
+use crate::client::Client;
+use crate::encryption::ColumnDecryptor;
+use crate::error::{DatabaseError, Error};
+use crate::message::ServerMessage;
+use crate::query::QueryOptions;
 use crate::row::{Column, Row};
+use crate::state::Ready;
 
 /// A streaming result set from a query.
 ///
@@ -47,6 +59,8 @@ pub struct QueryStream<'a> {
     rows: VecDeque<Row>,
     /// Whether the stream has completed.
     finished: bool,
+    /// Informational messages received with the result.
+    messages: Vec<ServerMessage>,
     /// Lifetime tied to the connection.
     _marker: std::marker::PhantomData<&'a ()>,
 }
@@ -58,6 +72,7 @@ impl QueryStream<'_> {
             columns,
             rows: rows.into(),
             finished: false,
+            messages: Vec::new(),
             _marker: std::marker::PhantomData,
         }
     }
@@ -69,10 +84,34 @@ impl QueryStream<'_> {
             columns: Vec::new(),
             rows: VecDeque::new(),
             finished: true,
+            messages: Vec::new(),
             _marker: std::marker::PhantomData,
         }
     }
 
+    /// Detach the buffered rows from the borrow of the connection.
+    pub(crate) fn into_owned(self) -> QueryStream<'static> {
+        QueryStream {
+            columns: self.columns,
+            rows: self.rows,
+            finished: self.finished,
+            messages: self.messages,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Attach the informational messages received with this result.
+    pub(crate) fn with_messages(mut self, messages: Vec<ServerMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Get the informational messages (e.g. `PRINT` output) received with this result.
+    #[must_use]
+    pub fn messages(&self) -> &[ServerMessage] {
+        &self.messages
+    }
+
     /// Get the column metadata for this result set.
     #[must_use]
     pub fn columns(&self) -> &[Column] {
@@ -166,17 +205,266 @@ impl Iterator for QueryStream<'_> {
     }
 }
 
+/// Rows of a query read from the connection as they are consumed.
+///
+/// Created by [`Client::query_stream`](crate::Client::query_stream) and
+/// [`Client::query_stream_with_options`](crate::Client::query_stream_with_options).
+/// Rows are decoded at most
+/// [`prefetch_rows`](crate::QueryOptions::prefetch_rows) or
+/// [`max_buffered_bytes`](crate::QueryOptions::max_buffered_bytes) ahead of
+/// the consumer; the rest of the response stays in the socket until it is
+/// asked for.
+///
+/// The stream borrows the connection until it is dropped. Dropping it
+/// before the last row cancels the query, and the rest of the response is
+/// drained before the next request on the connection.
+///
+/// Rows of later result sets in a batch follow those of the first; each
+/// row carries its own columns.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = QueryOptions::new().prefetch_rows(256);
+/// let mut rows = client
+///     .query_stream_with_options("SELECT * FROM large_table", &[], &options)
+///     .await?;
+/// while let Some(row) = rows.next_row().await? {
+///     process_row(&row).await;
+/// }
+/// ```
+pub struct RowStream<'a> {
+    client: &'a mut Client<Ready>,
+    state: RowStreamState,
+}
+
+/// Decoding state of a [`RowStream`].
+pub(crate) struct RowStreamState {
+    /// Token bytes read but not yet decoded.
+    pub(crate) buffer: BytesMut,
+    /// Length `buffer` must reach before an incomplete token is parsed
+    /// again, so a large row split over many packets is not re-parsed for
+    /// every packet.
+    pub(crate) retry_len: usize,
+    /// Whether the last packet of the response was read.
+    pub(crate) end_of_message: bool,
+    /// Whether every token of the response was handled.
+    pub(crate) complete: bool,
+    /// Metadata of the current result set.
+    pub(crate) metadata: Option<ColMetaData>,
+    /// Columns of the current result set.
+    pub(crate) columns: Vec<Column>,
+    /// Row metadata shared by the current result set's rows.
+    pub(crate) row_metadata: Option<Arc<crate::row::ColMetaData>>,
+    /// Decryptor for the current result set's encrypted columns.
+    pub(crate) decryptor: Option<ColumnDecryptor>,
+    /// Table names for the current result set's `COLINFO`.
+    pub(crate) tab_name: Option<TabName>,
+    /// Decoded rows with their size in bytes.
+    pub(crate) rows: VecDeque<(Row, usize)>,
+    /// Total size of `rows`.
+    pub(crate) buffered_bytes: usize,
+    /// First server error, returned once the response is complete.
+    pub(crate) error: Option<DatabaseError>,
+    /// Whether a transaction was open when the query was sent.
+    pub(crate) in_transaction: bool,
+    prefetch_rows: usize,
+    max_buffered_bytes: usize,
+}
+
+impl RowStreamState {
+    pub(crate) fn new(options: &QueryOptions, in_transaction: bool) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            retry_len: 0,
+            end_of_message: false,
+            complete: false,
+            metadata: None,
+            columns: Vec::new(),
+            row_metadata: None,
+            decryptor: None,
+            tab_name: None,
+            rows: VecDeque::new(),
+            buffered_bytes: 0,
+            error: None,
+            in_transaction,
+            prefetch_rows: options.prefetch_limit(),
+            max_buffered_bytes: options.buffered_bytes_limit(),
+        }
+    }
+
+    /// Check whether enough rows are waiting that decoding should pause.
+    pub(crate) fn is_full(&self) -> bool {
+        !self.rows.is_empty()
+            && (self.rows.len() >= self.prefetch_rows
+                || self.buffered_bytes >= self.max_buffered_bytes)
+    }
+
+    /// Queue a decoded row of `size` bytes.
+    pub(crate) fn push_row(&mut self, row: Row, size: usize) {
+        self.buffered_bytes += size;
+        self.rows.push_back((row, size));
+    }
+
+    fn pop_row(&mut self) -> Option<Row> {
+        let (row, size) = self.rows.pop_front()?;
+        self.buffered_bytes -= size;
+        Some(row)
+    }
+}
+
+impl<'a> RowStream<'a> {
+    /// Read up to the first result set's columns.
+    pub(crate) async fn start(
+        client: &'a mut Client<Ready>,
+        options: &QueryOptions,
+    ) -> Result<Self, Error> {
+        let in_transaction = client.is_in_transaction();
+        let mut stream = Self {
+            client,
+            state: RowStreamState::new(options, in_transaction),
+        };
+        while stream.state.metadata.is_none() && !stream.state.complete {
+            stream.read_more().await?;
+        }
+        if stream.state.rows.is_empty() {
+            stream.take_error()?;
+        }
+        Ok(stream)
+    }
+
+    /// Return the server error of a completed response, if there was one.
+    fn take_error(&mut self) -> Result<(), Error> {
+        match self.state.error.take() {
+            Some(err) if self.state.complete => {
+                Err(self.client.server_error(err, self.state.in_transaction))
+            }
+            error => {
+                self.state.error = error;
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the columns of the current result set.
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.state.columns
+    }
+
+    /// Get the number of decoded rows waiting to be consumed.
+    #[must_use]
+    pub fn rows_buffered(&self) -> usize {
+        self.state.rows.len()
+    }
+
+    /// Get the size in bytes of the decoded rows waiting to be consumed.
+    #[must_use]
+    pub fn bytes_buffered(&self) -> usize {
+        self.state.buffered_bytes
+    }
+
+    /// Check whether the whole response has been read.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.state.complete
+    }
+
+    /// Get the next row, reading more of the response when no decoded rows
+    /// are left.
+    ///
+    /// Returns `None` after the last row. A server error in the response is
+    /// returned once the rows before it have been consumed.
+    pub async fn next_row(&mut self) -> Result<Option<Row>, Error> {
+        loop {
+            if let Some(row) = self.state.pop_row() {
+                return Ok(Some(row));
+            }
+            if self.state.complete {
+                self.take_error()?;
+                return Ok(None);
+            }
+            self.read_more().await?;
+        }
+    }
+
+    /// Decode more of the response, ending the stream on failure.
+    async fn read_more(&mut self) -> Result<(), Error> {
+        let result = self.client.read_row_stream(&mut self.state).await;
+        if result.is_err() {
+            // The undecoded rest of the response cannot be trusted
+            self.state.complete = true;
+            self.state.rows.clear();
+            self.state.buffered_bytes = 0;
+        }
+        result
+    }
+
+    /// Read the remaining rows into a vector.
+    pub async fn collect_all(mut self) -> Result<Vec<Row>, Error> {
+        let mut rows = Vec::new();
+        while let Some(row) = self.next_row().await? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+impl Drop for RowStream<'_> {
+    fn drop(&mut self) {
+        if !self.state.end_of_message {
+            self.client.cancel_abandoned_stream();
+        }
+    }
+}
+
+impl std::fmt::Debug for RowStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowStream")
+            .field("columns", &self.state.columns.len())
+            .field("rows_buffered", &self.state.rows.len())
+            .field("bytes_buffered", &self.state.buffered_bytes)
+            .field("complete", &self.state.complete)
+            .finish()
+    }
+}
+
 /// Result of a non-query execution.
 ///
-/// Contains the number of affected rows and any output parameters.
+/// Contains the number of affected rows, the per-statement row counts
+/// reported by the server, and any output parameters.
 #[derive(Debug, Clone)]
 pub struct ExecuteResult {
-    /// Number of rows affected by the statement.
+    /// Total number of rows affected across all statements.
     pub rows_affected: u64,
+    /// Row counts from each statement's DONE token, in execution order.
+    ///
+    /// Statements inside procedures report theirs with DONEINPROC. The
+    /// DONEPROC ending a procedure call adds an entry only when it carries
+    /// a row count, so that `rows_affected` is always the sum of the valid
+    /// counts here.
+    pub row_counts: Vec<RowCount>,
+    /// Identity value generated by the last insert, when requested.
+    ///
+    /// Populated by [`Client::execute_with_identity`](crate::Client::execute_with_identity)
+    /// using `SCOPE_IDENTITY()`; `None` if no identity value was generated.
+    pub last_identity: Option<i64>,
     /// Output parameters from stored procedures.
     pub output_params: Vec<OutputParam>,
 }
 
+/// Row count reported by a single statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowCount {
+    /// Number of rows affected by the statement.
+    pub rows_affected: u64,
+    /// Whether the count is valid (the DONE_COUNT flag was set).
+    ///
+    /// Counts are not valid for statements that don't affect rows and for
+    /// all statements when `SET NOCOUNT ON` is active.
+    pub count_valid: bool,
+}
+
 /// An output parameter from a stored procedure call.
 #[derive(Debug, Clone)]
 pub struct OutputParam {
@@ -191,6 +479,8 @@ impl ExecuteResult {
     pub fn new(rows_affected: u64) -> Self {
         Self {
             rows_affected,
+            row_counts: Vec::new(),
+            last_identity: None,
             output_params: Vec::new(),
         }
     }
@@ -199,10 +489,35 @@ impl ExecuteResult {
     pub fn with_outputs(rows_affected: u64, output_params: Vec<OutputParam>) -> Self {
         Self {
             rows_affected,
+            row_counts: Vec::new(),
+            last_identity: None,
             output_params,
         }
     }
 
+    /// Record the row count from a DONE or DONEINPROC token.
+    pub(crate) fn record_done(&mut self, row_count: u64, count_valid: bool) {
+        if count_valid {
+            self.rows_affected += row_count;
+        }
+        self.row_counts.push(RowCount {
+            rows_affected: row_count,
+            count_valid,
+        });
+    }
+
+    /// Get the total number of rows affected across all statements.
+    #[must_use]
+    pub fn total_rows_affected(&self) -> u64 {
+        self.rows_affected
+    }
+
+    /// Get the number of statements that reported completion.
+    #[must_use]
+    pub fn statement_count(&self) -> usize {
+        self.row_counts.len()
+    }
+
     /// Get an output parameter by name.
     #[must_use]
     pub fn get_output(&self, name: &str) -> Option<&OutputParam> {
@@ -287,6 +602,8 @@ pub struct MultiResultStream<'a> {
     result_sets: Vec<ResultSet>,
     /// Current result set index (0-based).
     current_result: usize,
+    /// Informational messages received with the batch.
+    messages: Vec<ServerMessage>,
     /// Lifetime tied to the connection.
     _marker: std::marker::PhantomData<&'a ()>,
 }
@@ -297,6 +614,7 @@ impl<'a> MultiResultStream<'a> {
         Self {
             result_sets,
             current_result: 0,
+            messages: Vec::new(),
             _marker: std::marker::PhantomData,
         }
     }
@@ -307,10 +625,23 @@ impl<'a> MultiResultStream<'a> {
         Self {
             result_sets: Vec::new(),
             current_result: 0,
+            messages: Vec::new(),
             _marker: std::marker::PhantomData,
         }
     }
 
+    /// Attach the informational messages received with this batch.
+    pub(crate) fn with_messages(mut self, messages: Vec<ServerMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Get the informational messages (e.g. `PRINT` output) received with this batch.
+    #[must_use]
+    pub fn messages(&self) -> &[ServerMessage] {
+        &self.messages
+    }
+
     /// Get the current result set index (0-based).
     #[must_use]
     pub fn current_result_index(&self) -> usize {
@@ -339,6 +670,21 @@ impl<'a> MultiResultStream<'a> {
             .map(|rs| rs.columns())
     }
 
+    /// Get a result set by index (0-based).
+    ///
+    /// Each result set keeps the columns of the statement that produced it,
+    /// so later statements in a batch may have different columns.
+    #[must_use]
+    pub fn result_set(&self, index: usize) -> Option<&ResultSet> {
+        self.result_sets.get(index)
+    }
+
+    /// Get all result sets, in the order the server returned them.
+    #[must_use]
+    pub fn result_sets(&self) -> &[ResultSet] {
+        &self.result_sets
+    }
+
     /// Move to the next result set.
     ///
     /// Returns `true` if there is another result set, `false` if no more.
@@ -398,6 +744,19 @@ mod tests {
         assert!(result.output_params.is_empty());
     }
 
+    #[test]
+    fn test_execute_result_row_counts() {
+        let mut result = ExecuteResult::new(0);
+        result.record_done(1, true);
+        result.record_done(0, false);
+        result.record_done(2, true);
+
+        assert_eq!(result.total_rows_affected(), 3);
+        assert_eq!(result.statement_count(), 3);
+        assert!(!result.row_counts[1].count_valid);
+        assert_eq!(result.last_identity, None);
+    }
+
     #[test]
     fn test_execute_result_with_outputs() {
         let outputs = vec![OutputParam {
@@ -423,6 +782,15 @@ mod tests {
             precision: Some(0),
             scale: Some(0),
             collation: None,
+            case_sensitive: false,
+            identity: false,
+            computed: false,
+            updatable: None,
+            base_table: None,
+            base_column: None,
+            key: false,
+            hidden: false,
+            time_zone: Default::default(),
         }];
 
         let stream = QueryStream::new(columns, Vec::new());
@@ -431,6 +799,25 @@ mod tests {
         assert!(!stream.is_finished());
     }
 
+    #[test]
+    fn test_query_stream_messages() {
+        let message = ServerMessage {
+            number: 0,
+            state: 1,
+            class: 0,
+            message: "step 1 done".to_string(),
+            server: None,
+            procedure: None,
+            line: 1,
+        };
+
+        let stream = QueryStream::new(Vec::new(), Vec::new());
+        assert!(stream.messages().is_empty());
+
+        let stream = stream.with_messages(vec![message.clone()]);
+        assert_eq!(stream.messages(), &[message]);
+    }
+
     #[test]
     fn test_query_stream_with_rows() {
         use mssql_types::SqlValue;
@@ -445,6 +832,15 @@ mod tests {
                 precision: None,
                 scale: None,
                 collation: None,
+                case_sensitive: false,
+                identity: false,
+                computed: false,
+                updatable: None,
+                base_table: None,
+                base_column: None,
+                key: false,
+                hidden: false,
+                time_zone: Default::default(),
             },
             Column {
                 name: "name".to_string(),
@@ -455,6 +851,15 @@ mod tests {
                 precision: None,
                 scale: None,
                 collation: None,
+                case_sensitive: false,
+                identity: false,
+                computed: false,
+                updatable: None,
+                base_table: None,
+                base_column: None,
+                key: false,
+                hidden: false,
+                time_zone: Default::default(),
             },
         ];
 
@@ -502,6 +907,15 @@ mod tests {
             precision: None,
             scale: None,
             collation: None,
+            case_sensitive: false,
+            identity: false,
+            computed: false,
+            updatable: None,
+            base_table: None,
+            base_column: None,
+            key: false,
+            hidden: false,
+            time_zone: Default::default(),
         }];
 
         let rows = vec![
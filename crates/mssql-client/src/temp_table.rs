@@ -0,0 +1,170 @@
+//! Temporary tables scoped to a guard.
+//!
+//! [`Client::temp_table`](crate::Client::temp_table) creates a `#temp` table
+//! with the columns of a [`Tvp`] type, inserts rows into it, and returns a
+//! [`TempTable`] guard. This is the usual way to work with key sets too
+//! large for parameters: load the keys once, then join against them.
+//!
+//! ```rust,ignore
+//! use mssql_derive::Tvp;
+//!
+//! #[derive(Tvp)]
+//! #[mssql(type_name = "dbo.OrderKeys")]
+//! struct OrderKey {
+//!     order_id: i64,
+//! }
+//!
+//! let mut keys = client.temp_table("#keys", &order_keys).await?;
+//! let rows = keys
+//!     .client()
+//!     .query("SELECT o.* FROM dbo.Orders o JOIN #keys k ON k.order_id = o.Id", &[])
+//!     .await?
+//!     .collect_all()
+//!     .await?;
+//! keys.drop_table().await?;
+//! ```
+//!
+//! A temporary table only exists on the connection that created it. The
+//! guard therefore borrows the client, and statements using the table go
+//! through [`TempTable::client`]; they cannot end up on another pooled
+//! connection by accident.
+//!
+//! Rows are inserted from a table-valued parameter, as in
+//! [`Client::insert_many`](crate::Client::insert_many), so the table type
+//! named by the `Tvp` is created if it does not exist yet. A guard dropped
+//! without [`TempTable::drop_table`] drops its table before the next request
+//! on the connection.
+
+use crate::client::{Client, quote_object_name};
+use crate::error::{Error, Result};
+use crate::state::ConnectionState;
+use crate::tvp::{Tvp, column_definitions};
+
+/// A temporary table that is dropped with the guard.
+///
+/// Created by [`Client::temp_table`](crate::Client::temp_table); see the
+/// [module documentation](self).
+pub struct TempTable<'a, S: ConnectionState> {
+    client: &'a mut Client<S>,
+    name: String,
+    drop_sql: String,
+    dropped: bool,
+}
+
+impl<'a, S: ConnectionState> TempTable<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>, name: &str) -> Result<Self> {
+        Ok(Self {
+            client,
+            name: name.to_string(),
+            drop_sql: drop_sql(name)?,
+            dropped: false,
+        })
+    }
+
+    /// Get the table name, e.g. `#keys`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the client of the connection the table lives on.
+    pub fn client(&mut self) -> &mut Client<S> {
+        self.client
+    }
+
+    /// Insert more rows into the table, returning the number inserted.
+    pub async fn insert<T: Tvp>(&mut self, rows: &[T]) -> Result<u64> {
+        self.client.insert_tvp_rows(&self.name, rows).await
+    }
+
+    /// Drop the table now.
+    pub async fn drop_table(mut self) -> Result<()> {
+        self.dropped = true;
+        self.client.execute_rows(&self.drop_sql, &[]).await?;
+        tracing::debug!(table = %self.name, "dropped temporary table");
+        Ok(())
+    }
+}
+
+impl<S: ConnectionState> Drop for TempTable<'_, S> {
+    fn drop(&mut self) {
+        if !self.dropped {
+            self.client
+                .defer_cleanup(std::mem::take(&mut self.drop_sql));
+        }
+    }
+}
+
+impl<S: ConnectionState> std::fmt::Debug for TempTable<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TempTable")
+            .field("name", &self.name)
+            .field("dropped", &self.dropped)
+            .finish()
+    }
+}
+
+/// Build a `CREATE TABLE` for the temporary table `name` with the columns
+/// of `T`.
+pub(crate) fn create_sql<T: Tvp>(name: &str) -> Result<String> {
+    Ok(format!(
+        "CREATE TABLE {} ({})",
+        quote_temp_name(name)?,
+        column_definitions(&T::columns())?
+    ))
+}
+
+/// Build a statement dropping the temporary table `name` if it exists.
+pub(crate) fn drop_sql(name: &str) -> Result<String> {
+    Ok(format!(
+        "IF OBJECT_ID(N'tempdb..{name}') IS NOT NULL DROP TABLE {}",
+        quote_temp_name(name)?
+    ))
+}
+
+fn quote_temp_name(name: &str) -> Result<String> {
+    if !name.starts_with('#') || name.contains('.') {
+        return Err(Error::InvalidIdentifier(format!(
+            "invalid temporary table name '{name}': must start with '#'"
+        )));
+    }
+    quote_object_name(name)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::tvp::{TvpColumn, TvpRow};
+    use mssql_types::TypeError;
+
+    struct OrderKey;
+
+    impl Tvp for OrderKey {
+        fn type_name() -> &'static str {
+            "dbo.OrderKeys"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![TvpColumn::new("OrderId", "BIGINT", 0)]
+        }
+
+        fn to_row(&self) -> std::result::Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(Vec::new()))
+        }
+    }
+
+    #[test]
+    fn test_temp_table_sql() {
+        assert_eq!(
+            create_sql::<OrderKey>("#keys").unwrap(),
+            "CREATE TABLE [#keys] ([OrderId] BIGINT NULL)"
+        );
+        assert_eq!(
+            drop_sql("#keys").unwrap(),
+            "IF OBJECT_ID(N'tempdb..#keys') IS NOT NULL DROP TABLE [#keys]"
+        );
+        assert!(create_sql::<OrderKey>("dbo.keys").is_err());
+        assert!(drop_sql("#keys'; DROP TABLE x; --").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Byte streams the client connects over.
+//!
+//! TDS runs over TCP by default. With the `named-pipes` feature on Windows,
+//! a server given as `np:\\server\pipe\sql\query` (or just `np:server`) in
+//! the connection string is reached through its named pipe instead; see
+//! [`Config::named_pipe`](crate::Config::named_pipe).
+//!
+//! Any other stream, such as a Unix socket to a proxy, an SSH tunnel or a
+//! TLS-terminating sidecar, can be supplied with a [`TransportFactory`] set
+//! through [`Config::with_transport`](crate::Config::with_transport). The
+//! client then skips its own TCP connect and runs PreLogin, TLS (unless
+//! disabled) and login over the returned stream:
+//!
+//! ```rust,ignore
+//! use mssql_client::transport::BoxedTransport;
+//!
+//! let config = Config::new().with_transport(|_host: String, _port: u16| async move {
+//!     let stream = tokio::net::UnixStream::connect("/run/sql-proxy.sock").await?;
+//!     Ok(Box::new(stream) as BoxedTransport)
+//! });
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use mssql_codec::AsyncTransport;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+#[cfg(all(windows, feature = "named-pipes"))]
+use tokio::net::windows::named_pipe::NamedPipeClient;
+
+use crate::socket::SocketProbe;
+
+/// A caller-supplied stream.
+pub type BoxedTransport = Box<dyn AsyncTransport + Sync>;
+
+/// Opens caller-supplied streams to the server.
+///
+/// Implemented for closures taking the host and port and returning a future
+/// of a [`BoxedTransport`].
+#[async_trait::async_trait]
+pub trait TransportFactory: Send + Sync + 'static {
+    /// Open a connected stream to `host`:`port`.
+    ///
+    /// The host and port are those of the configuration, or of the server an
+    /// Azure SQL gateway redirected to.
+    async fn connect(&self, host: &str, port: u16) -> io::Result<BoxedTransport>;
+}
+
+#[async_trait::async_trait]
+impl<F, Fut> TransportFactory for F
+where
+    F: Fn(String, u16) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = io::Result<BoxedTransport>> + Send,
+{
+    async fn connect(&self, host: &str, port: u16) -> io::Result<BoxedTransport> {
+        self(host.to_string(), port).await
+    }
+}
+
+impl std::fmt::Debug for dyn TransportFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TransportFactory")
+    }
+}
+
+/// The stream a connection runs over.
+pub(crate) enum Transport {
+    /// TCP socket.
+    Tcp(TcpStream),
+    /// Windows named pipe client.
+    #[cfg(all(windows, feature = "named-pipes"))]
+    NamedPipe(NamedPipeClient),
+    /// Stream opened by a [`TransportFactory`].
+    Custom(BoxedTransport),
+}
+
+impl Transport {
+    /// Get the remote address, if the transport has one.
+    pub(crate) fn peer_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(stream) => stream.peer_addr().ok(),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(_) => None,
+            Self::Custom(_) => None,
+        }
+    }
+
+    /// Get a probe for detecting a closed connection, if the transport is
+    /// a TCP socket.
+    pub(crate) fn probe(&self) -> Option<SocketProbe> {
+        match self {
+            Self::Tcp(stream) => SocketProbe::new(stream)
+                .inspect_err(|e| tracing::debug!(error = %e, "no socket probe"))
+                .ok(),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(_) => None,
+            Self::Custom(_) => None,
+        }
+    }
+}
+
+impl From<TcpStream> for Transport {
+    fn from(stream: TcpStream) -> Self {
+        Self::Tcp(stream)
+    }
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(pipe) => Pin::new(pipe).poll_read(cx, buf),
+            Self::Custom(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(pipe) => Pin::new(pipe).poll_write(cx, buf),
+            Self::Custom(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(pipe) => Pin::new(pipe).poll_flush(cx),
+            Self::Custom(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(all(windows, feature = "named-pipes"))]
+            Self::NamedPipe(pipe) => Pin::new(pipe).poll_shutdown(cx),
+            Self::Custom(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Build the pipe path and server name for an `np:` server value.
+///
+/// Accepts a full pipe path (`\\server\pipe\sql\query`) or a server with an
+/// optional instance (`server`, `server\instance`), which is mapped to the
+/// default pipe of that instance. `.` and `(local)` name the local machine.
+pub(crate) fn named_pipe_path(value: &str) -> (String, String) {
+    let local = |server: &str| {
+        if server == "." || server.eq_ignore_ascii_case("(local)") {
+            "localhost".to_string()
+        } else {
+            server.to_string()
+        }
+    };
+
+    if let Some(rest) = value.strip_prefix(r"\\") {
+        let server = rest.split('\\').next().unwrap_or_default();
+        return (local(server), value.to_string());
+    }
+
+    match value.split_once('\\') {
+        Some((server, instance)) => (
+            local(server),
+            format!(r"\\{server}\pipe\MSSQL${instance}\sql\query"),
+        ),
+        None => (local(value), format!(r"\\{value}\pipe\sql\query")),
+    }
+}
+
+/// Open a named pipe, waiting while all pipe instances are busy.
+#[cfg(all(windows, feature = "named-pipes"))]
+pub(crate) async fn open_named_pipe(path: &str) -> io::Result<NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    loop {
+        match ClientOptions::new().open(path) {
+            Ok(pipe) => return Ok(pipe),
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {}
+            Err(e) => return Err(e),
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_pipe_path() {
+        assert_eq!(
+            named_pipe_path(r"\\db01\pipe\sql\query"),
+            ("db01".into(), r"\\db01\pipe\sql\query".into())
+        );
+        assert_eq!(
+            named_pipe_path("db01"),
+            ("db01".into(), r"\\db01\pipe\sql\query".into())
+        );
+        assert_eq!(
+            named_pipe_path(r"db01\SQLEXPRESS"),
+            (
+                "db01".into(),
+                r"\\db01\pipe\MSSQL$SQLEXPRESS\sql\query".into()
+            )
+        );
+        assert_eq!(
+            named_pipe_path(r"\\.\pipe\sql\query"),
+            ("localhost".into(), r"\\.\pipe\sql\query".into())
+        );
+    }
+}
@@ -45,9 +45,18 @@
 //!
 //! - `#[mssql(type_name = "schema.TypeName")]` - SQL Server TVP type name (required)
 //! - `#[mssql(rename = "column_name")]` - Map field to different column name
+//!
+//! ## Inserting Rows
+//!
+//! [`Client::insert_many`](crate::Client::insert_many) inserts a slice of
+//! `Tvp` rows with one `INSERT ... SELECT` from the TVP, creating the table
+//! type from the row's columns if it doesn't exist yet.
 
 use mssql_types::{SqlValue, ToSql, TvpColumnDef, TvpColumnType, TvpData, TypeError};
 
+use crate::client::{quote_object_name, validated_identifier};
+use crate::error::Error;
+
 /// Metadata for a TVP column.
 #[derive(Debug, Clone)]
 pub struct TvpColumn {
@@ -236,6 +245,7 @@ impl ToSql for TvpValue {
             type_name,
             columns,
             rows,
+            order_hints: Vec::new(),
         };
 
         Ok(SqlValue::Tvp(Box::new(tvp_data)))
@@ -246,6 +256,69 @@ impl ToSql for TvpValue {
     }
 }
 
+/// Build a batch creating the table type for `T` unless it already exists.
+///
+/// A type created concurrently by another connection (error 219) is not
+/// an error.
+pub(crate) fn create_type_sql<T: Tvp>() -> crate::error::Result<String> {
+    create_type_sql_for(T::type_name(), &T::columns())
+}
+
+/// Build a batch creating the table type `type_name` with `columns` unless
+/// it already exists.
+pub(crate) fn create_type_sql_for(
+    type_name: &str,
+    columns: &[TvpColumn],
+) -> crate::error::Result<String> {
+    let quoted = quote_object_name(type_name)?;
+    Ok(format!(
+        "IF TYPE_ID(N'{type_name}') IS NULL \
+         BEGIN TRY CREATE TYPE {quoted} AS TABLE ({}) END TRY \
+         BEGIN CATCH IF ERROR_NUMBER() <> 219 THROW; END CATCH",
+        column_definitions(columns)?
+    ))
+}
+
+/// Build the column list of a `CREATE TABLE` or `CREATE TYPE ... AS TABLE`
+/// for `columns`, all nullable.
+pub(crate) fn column_definitions(columns: &[TvpColumn]) -> crate::error::Result<String> {
+    let columns = columns
+        .iter()
+        .map(|column| {
+            let valid_type = !column.sql_type.is_empty()
+                && column
+                    .sql_type
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '(' | ')' | ',' | ' ' | '_'));
+            if !valid_type {
+                return Err(Error::Config(format!(
+                    "invalid SQL type '{}' for TVP column '{}'",
+                    column.sql_type, column.name
+                )));
+            }
+            Ok(format!(
+                "{} {} NULL",
+                validated_identifier(&column.name)?,
+                column.sql_type
+            ))
+        })
+        .collect::<crate::error::Result<Vec<_>>>()?;
+    Ok(columns.join(", "))
+}
+
+/// Build an `INSERT ... SELECT` of the columns of `T` from the TVP `@p1`.
+pub(crate) fn insert_select_sql<T: Tvp>(table: &str) -> crate::error::Result<String> {
+    let table = quote_object_name(table)?;
+    let columns = T::columns()
+        .iter()
+        .map(|column| validated_identifier(&column.name))
+        .collect::<crate::error::Result<Vec<_>>>()?
+        .join(", ");
+    Ok(format!(
+        "INSERT INTO {table} ({columns}) SELECT {columns} FROM @p1"
+    ))
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -311,6 +384,65 @@ mod tests {
         assert!(tvp.is_empty());
     }
 
+    struct TestOrder;
+
+    impl Tvp for TestOrder {
+        fn type_name() -> &'static str {
+            "dbo.OrderRows"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![
+                TvpColumn::new("Id", "INT", 0),
+                TvpColumn::new("Total", "DECIMAL(38,10)", 1),
+            ]
+        }
+
+        fn to_row(&self) -> Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(Vec::new()))
+        }
+    }
+
+    struct BadType;
+
+    impl Tvp for BadType {
+        fn type_name() -> &'static str {
+            "dbo.BadRows"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![TvpColumn::new("Id", "INT); DROP TABLE x; --", 0)]
+        }
+
+        fn to_row(&self) -> Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(Vec::new()))
+        }
+    }
+
+    #[test]
+    fn test_create_type_sql() {
+        assert_eq!(
+            create_type_sql::<TestOrder>().unwrap(),
+            "IF TYPE_ID(N'dbo.OrderRows') IS NULL \
+             BEGIN TRY CREATE TYPE [dbo].[OrderRows] AS TABLE \
+             ([Id] INT NULL, [Total] DECIMAL(38,10) NULL) END TRY \
+             BEGIN CATCH IF ERROR_NUMBER() <> 219 THROW; END CATCH"
+        );
+        assert!(matches!(
+            create_type_sql::<BadType>(),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_insert_select_sql() {
+        assert_eq!(
+            insert_select_sql::<TestOrder>("dbo.Orders").unwrap(),
+            "INSERT INTO [dbo].[Orders] ([Id], [Total]) SELECT [Id], [Total] FROM @p1"
+        );
+        assert!(insert_select_sql::<TestOrder>("Orders; --").is_err());
+    }
+
     #[test]
     fn test_tvp_column() {
         let col = TvpColumn::new("TestCol", "NVARCHAR(100)", 0);
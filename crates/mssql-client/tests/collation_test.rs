@@ -5,7 +5,7 @@
 
 #![allow(clippy::expect_used)]
 
-use mssql_client::{Client, Config, Error};
+use mssql_client::{Client, Config, Error, Ready, Varchar};
 
 /// Helper to get test configuration from environment
 fn get_test_config() -> Option<Config> {
@@ -213,3 +213,85 @@ async fn test_nvarchar_unicode() -> Result<(), Error> {
     client.close().await?;
     Ok(())
 }
+
+/// Test VARCHAR parameters are encoded in the connection's code page
+#[cfg(feature = "encoding")]
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_varchar_param_roundtrip() -> Result<(), Error> {
+    let config = get_test_config().expect("Could not create config");
+    let mut client = Client::connect(config).await?;
+
+    let rows = client
+        .query(
+            "SELECT @p1 AS value, SQL_VARIANT_PROPERTY(@p1, 'BaseType') AS base_type",
+            &[&Varchar("Café")],
+        )
+        .await?;
+
+    for result in rows {
+        let row = result?;
+        assert_eq!(row.get::<String>(0)?, "Café");
+        assert_eq!(row.get::<String>(1)?, "varchar");
+    }
+
+    client.close().await?;
+    Ok(())
+}
+
+/// Look up the cached plan of the statement containing `marker`.
+async fn cached_plan(client: &mut Client<Ready>, marker: &str) -> Result<String, Error> {
+    let sql = format!(
+        "SELECT TOP (1) CAST(qp.query_plan AS NVARCHAR(MAX))
+         FROM sys.dm_exec_cached_plans cp
+         CROSS APPLY sys.dm_exec_sql_text(cp.plan_handle) st
+         CROSS APPLY sys.dm_exec_query_plan(cp.plan_handle) qp
+         WHERE st.text LIKE '%{marker}%' AND st.text NOT LIKE '%dm_exec_cached_plans%'"
+    );
+    let row = client
+        .query(&sql, &[])
+        .await?
+        .next()
+        .expect("no cached plan for marker")?;
+    Ok(row.get::<String>(0)?)
+}
+
+/// Test a VARCHAR parameter seeks a VARCHAR index without CONVERT_IMPLICIT,
+/// while the default NVARCHAR parameter converts the column
+#[tokio::test]
+#[ignore = "Requires SQL Server with VIEW SERVER STATE"]
+async fn test_varchar_param_plan_parity() -> Result<(), Error> {
+    let config = get_test_config().expect("Could not create config");
+    let mut client = Client::connect(config).await?;
+
+    client
+        .execute(
+            "CREATE TABLE #plan_test (
+                code VARCHAR(20) COLLATE SQL_Latin1_General_CP1_CI_AS PRIMARY KEY
+            )",
+            &[],
+        )
+        .await?;
+    client
+        .execute("INSERT INTO #plan_test VALUES ('A-1'), ('A-2')", &[])
+        .await?;
+
+    let varchar_marker = "varchar_plan_parity_1";
+    let nvarchar_marker = "nvarchar_plan_parity_1";
+    let sql = |marker: &str| format!("SELECT code FROM #plan_test WHERE code = @p1 /* {marker} */");
+
+    let rows = client
+        .query(&sql(varchar_marker), &[&Varchar("A-1")])
+        .await?;
+    assert_eq!(rows.count(), 1);
+    let rows = client.query(&sql(nvarchar_marker), &[&"A-1"]).await?;
+    assert_eq!(rows.count(), 1);
+
+    let plan = cached_plan(&mut client, varchar_marker).await?;
+    assert!(!plan.contains("CONVERT_IMPLICIT"), "{plan}");
+    let plan = cached_plan(&mut client, nvarchar_marker).await?;
+    assert!(plan.contains("CONVERT_IMPLICIT"), "{plan}");
+
+    client.close().await?;
+    Ok(())
+}
@@ -0,0 +1,2 @@
+CREATE TABLE users (id INT PRIMARY KEY, name NVARCHAR(100) NOT NULL)
+GO
@@ -0,0 +1 @@
+ALTER TABLE users ADD email NVARCHAR(255) NULL
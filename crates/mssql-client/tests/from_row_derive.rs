@@ -0,0 +1,177 @@
+//! `#[derive(FromRow)]` mapping tests.
+//!
+//! These tests decode real result sets, so they require a running SQL Server
+//! instance and are ignored by default. Run them with:
+//!
+//! ```bash
+//! cargo test -p mssql-client --test from_row_derive -- --ignored
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::{Client, Config, FromRow, RowIteratorExt};
+use mssql_derive::FromRow;
+
+/// Helper to get test configuration from environment variables.
+fn get_test_config() -> Option<Config> {
+    let host = std::env::var("MSSQL_HOST").ok()?;
+    let port = std::env::var("MSSQL_PORT")
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(1433);
+    let user = std::env::var("MSSQL_USER").unwrap_or_else(|_| "sa".into());
+    let password = std::env::var("MSSQL_PASSWORD").unwrap_or_else(|_| "MyStrongPassw0rd".into());
+    let database = std::env::var("MSSQL_DATABASE").unwrap_or_else(|_| "master".into());
+    let encrypt = std::env::var("MSSQL_ENCRYPT").unwrap_or_else(|_| "false".into());
+
+    let conn_str = format!(
+        "Server={},{};Database={};User Id={};Password={};TrustServerCertificate=true;Encrypt={}",
+        host, port, database, user, password, encrypt
+    );
+
+    Config::from_connection_string(&conn_str).ok()
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct UserId(u32);
+
+impl TryFrom<i32> for UserId {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        u32::try_from(value).map(UserId)
+    }
+}
+
+#[derive(Debug, FromRow)]
+#[mssql(rename_all = "PascalCase")]
+struct User {
+    #[mssql(try_from = "i32")]
+    id: UserId,
+    #[mssql(try_from = "i32")]
+    manager_id: Option<UserId>,
+    /// Legacy BIT column read into an integer field.
+    #[mssql(try_from = "bool")]
+    is_admin: i32,
+    #[mssql(default)]
+    login_count: i64,
+    #[mssql(default)]
+    nickname: String,
+}
+
+async fn query_one<T: FromRow>(client: &mut Client<mssql_client::Ready>, sql: &str) -> T {
+    client
+        .query(sql, &[])
+        .await
+        .expect("query failed")
+        .map_rows::<T>()
+        .next()
+        .expect("no rows")
+        .expect("mapping failed")
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_try_from_and_default() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let user: User = query_one(
+        &mut client,
+        "SELECT 7 AS Id, NULL AS ManagerId, CAST(1 AS BIT) AS IsAdmin, NULL AS LoginCount",
+    )
+    .await;
+    assert_eq!(user.id, UserId(7));
+    assert_eq!(user.manager_id, None);
+    assert_eq!(user.is_admin, 1);
+    // NULL and missing columns both fall back to Default.
+    assert_eq!(user.login_count, 0);
+    assert_eq!(user.nickname, "");
+
+    let user: User = query_one(
+        &mut client,
+        "SELECT 7 AS Id, 3 AS ManagerId, CAST(0 AS BIT) AS IsAdmin, \
+         CAST(12 AS BIGINT) AS LoginCount, N'sam' AS Nickname",
+    )
+    .await;
+    assert_eq!(user.manager_id, Some(UserId(3)));
+    assert_eq!(user.login_count, 12);
+    assert_eq!(user.nickname, "sam");
+
+    client.close().await.expect("Failed to close");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_try_from_and_default_errors() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    // A failed TryFrom names the target type.
+    let err = client
+        .query(
+            "SELECT -1 AS Id, NULL AS ManagerId, CAST(0 AS BIT) AS IsAdmin",
+            &[],
+        )
+        .await
+        .expect("query failed")
+        .map_rows::<User>()
+        .next()
+        .expect("no rows")
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("cannot convert to UserId"),
+        "{err}"
+    );
+
+    // A present but unconvertible value is an error, not the default.
+    let result = client
+        .query(
+            "SELECT 1 AS Id, NULL AS ManagerId, CAST(0 AS BIT) AS IsAdmin, N'x' AS LoginCount",
+            &[],
+        )
+        .await
+        .expect("query failed")
+        .map_rows::<User>()
+        .next()
+        .expect("no rows");
+    assert!(result.is_err());
+
+    client.close().await.expect("Failed to close");
+}
+
+#[derive(Debug, FromRow)]
+struct Pair {
+    #[mssql(index = 0)]
+    first: i32,
+    #[mssql(index = 1)]
+    second: i32,
+    #[mssql(index = 2)]
+    label: Option<String>,
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_index_mapping() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    // Unnamed and duplicate column names bind by ordinal.
+    let pair: Pair = query_one(&mut client, "SELECT 1, 2 AS x, NULL AS x").await;
+    assert_eq!(pair.first, 1);
+    assert_eq!(pair.second, 2);
+    assert_eq!(pair.label, None);
+
+    // Lookup failures list the columns the row does have.
+    let err = client
+        .query("SELECT 1 AS Id", &[])
+        .await
+        .expect("query failed")
+        .map_rows::<User>()
+        .next()
+        .expect("no rows")
+        .unwrap_err();
+    assert!(err.to_string().contains("(available: 0: Id)"), "{err}");
+
+    client.close().await.expect("Failed to close");
+}
@@ -2140,6 +2140,76 @@ async fn test_tvp_empty_table() {
     client.close().await.expect("Failed to close");
 }
 
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_tvp_default_column_and_order_hint() {
+    use mssql_types::{SqlValue, TvpColumnDef, TvpColumnType, TvpData, TvpOrderHint};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let create_type = r#"
+        IF TYPE_ID('dbo.DefaultColumnList') IS NULL
+        BEGIN
+            CREATE TYPE dbo.DefaultColumnList AS TABLE (
+                Id INT NOT NULL PRIMARY KEY,
+                Status NVARCHAR(20) NOT NULL DEFAULT N'new'
+            );
+        END
+    "#;
+    client
+        .execute(create_type, &[])
+        .await
+        .expect("Failed to create TVP type");
+
+    // Status takes the type's default; the placeholder values are not sent.
+    let mut tvp = TvpData::new("dbo", "DefaultColumnList")
+        .with_column(TvpColumnDef::new(TvpColumnType::Int))
+        .with_column(TvpColumnDef::new(TvpColumnType::NVarChar { max_length: 20 }).with_default())
+        .with_order_hint(TvpOrderHint::ascending(0).unique());
+    for id in 1..=3 {
+        tvp.try_add_row(vec![SqlValue::Int(id), SqlValue::Null])
+            .expect("row matches columns");
+    }
+    let tvp = SqlValue::Tvp(Box::new(tvp));
+
+    let rows: Vec<(i32, String)> = client
+        .query("SELECT Id, Status FROM @p1 ORDER BY Id", &[&tvp])
+        .await
+        .expect("Query with TVP failed")
+        .filter_map(|r| r.ok())
+        .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            (1, "new".to_string()),
+            (2, "new".to_string()),
+            (3, "new".to_string())
+        ]
+    );
+
+    // An unsorted TVP contradicting the hint is rejected by the server.
+    let unsorted = SqlValue::Tvp(Box::new(
+        TvpData::new("dbo", "DefaultColumnList")
+            .with_column(TvpColumnDef::new(TvpColumnType::Int))
+            .with_column(
+                TvpColumnDef::new(TvpColumnType::NVarChar { max_length: 20 }).with_default(),
+            )
+            .with_order_hint(TvpOrderHint::ascending(0).unique())
+            .with_row(vec![SqlValue::Int(2), SqlValue::Null])
+            .with_row(vec![SqlValue::Int(1), SqlValue::Null]),
+    ));
+    let result = client.query("SELECT COUNT(*) FROM @p1", &[&unsorted]).await;
+    let failed = match result {
+        Err(_) => true,
+        Ok(mut stream) => stream.any(|r| r.is_err()),
+    };
+    assert!(failed, "server should reject rows out of hinted order");
+
+    client.close().await.expect("Failed to close");
+}
+
 #[tokio::test]
 #[ignore = "Requires SQL Server"]
 async fn test_tvp_multi_column() {
@@ -2332,6 +2402,168 @@ async fn test_tvp_bulk_insert() {
     client.close().await.expect("Failed to close");
 }
 
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_merge_into_tvp() {
+    use mssql_client::{MergeResult, Tvp, TvpColumn, TvpRow};
+    use mssql_types::{ToSql, TypeError};
+
+    struct Product {
+        sku: &'static str,
+        price: i32,
+    }
+
+    impl Tvp for Product {
+        fn type_name() -> &'static str {
+            "dbo.MergeTestProducts"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![
+                TvpColumn::new("Sku", "NVARCHAR(50)", 0),
+                TvpColumn::new("Price", "INT", 1),
+            ]
+        }
+
+        fn to_row(&self) -> Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(vec![self.sku.to_sql()?, self.price.to_sql()?]))
+        }
+    }
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    client
+        .execute(
+            "IF TYPE_ID('dbo.MergeTestProducts') IS NULL \
+             CREATE TYPE dbo.MergeTestProducts AS TABLE (Sku NVARCHAR(50) NOT NULL, Price INT NOT NULL)",
+            &[],
+        )
+        .await
+        .expect("Failed to create TVP type");
+    client
+        .execute(
+            "CREATE TABLE #MergeTarget (Sku NVARCHAR(50) PRIMARY KEY, Price INT NOT NULL); \
+             INSERT INTO #MergeTarget VALUES (N'A', 1), (N'B', 2), (N'C', 3)",
+            &[],
+        )
+        .await
+        .expect("Failed to create target table");
+
+    let products = [
+        Product { sku: "A", price: 1 },
+        Product {
+            sku: "B",
+            price: 20,
+        },
+        Product { sku: "D", price: 4 },
+    ];
+    let result = client
+        .merge_into("#MergeTarget")
+        .on(&["Sku"])
+        .skip_unchanged()
+        .delete_unmatched()
+        .execute(&products)
+        .await
+        .expect("MERGE failed");
+    assert_eq!(
+        result,
+        MergeResult {
+            inserted: 1,
+            updated: 1,
+            deleted: 1,
+        }
+    );
+
+    let rows: Vec<(String, i32)> = client
+        .query("SELECT Sku, Price FROM #MergeTarget ORDER BY Sku", &[])
+        .await
+        .expect("Query failed")
+        .filter_map(|r| r.ok())
+        .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+        .collect();
+    assert_eq!(rows, [("A".into(), 1), ("B".into(), 20), ("D".into(), 4)]);
+
+    client.close().await.expect("Failed to close");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_insert_many_tvp() {
+    use mssql_client::{Tvp, TvpColumn, TvpRow};
+    use mssql_types::{ToSql, TypeError};
+
+    struct Order {
+        id: i32,
+        note: Option<String>,
+    }
+
+    impl Tvp for Order {
+        fn type_name() -> &'static str {
+            "dbo.InsertManyTestOrders"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![
+                TvpColumn::new("Id", "INT", 0),
+                TvpColumn::new("Note", "NVARCHAR(100)", 1),
+            ]
+        }
+
+        fn to_row(&self) -> Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(vec![self.id.to_sql()?, self.note.to_sql()?]))
+        }
+    }
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    client
+        .execute(
+            "CREATE TABLE #InsertManyTarget (Id INT PRIMARY KEY, Note NVARCHAR(100) NULL)",
+            &[],
+        )
+        .await
+        .expect("Failed to create target table");
+
+    let orders: Vec<Order> = (1..=500)
+        .map(|id| Order {
+            id,
+            note: (id % 2 == 0).then(|| format!("order {id}")),
+        })
+        .collect();
+
+    // The second call reuses the table type created by the first.
+    let inserted = client
+        .insert_many("#InsertManyTarget", &orders[..250])
+        .await
+        .expect("insert_many failed");
+    assert_eq!(inserted, 250);
+    let inserted = client
+        .insert_many("#InsertManyTarget", &orders[250..])
+        .await
+        .expect("insert_many failed");
+    assert_eq!(inserted, 250);
+    assert_eq!(
+        client
+            .insert_many::<Order>("#InsertManyTarget", &[])
+            .await
+            .unwrap(),
+        0
+    );
+
+    let rows: Vec<(i32, i32)> = client
+        .query("SELECT COUNT(*), COUNT(Note) FROM #InsertManyTarget", &[])
+        .await
+        .expect("Query failed")
+        .filter_map(|r| r.ok())
+        .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+        .collect();
+    assert_eq!(rows, [(500, 250)]);
+
+    client.close().await.expect("Failed to close");
+}
+
 // =============================================================================
 // Data Type Regression Tests (v0.2.3+ fixes)
 // =============================================================================
@@ -2777,3 +3009,734 @@ async fn test_data_type_sql_variant() {
 
     client.close().await.expect("Failed to close");
 }
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_query_arrow() {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal128Type, Int32Type};
+    use arrow_schema::DataType;
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let batches = client
+        .query_arrow(
+            "SELECT TOP (5) CAST(n AS INT) AS id, CAST(n * 1.5 AS DECIMAL(10,2)) AS amount, \
+             CASE WHEN n % 2 = 0 THEN NULL ELSE N'odd' END AS label \
+             FROM (SELECT ROW_NUMBER() OVER (ORDER BY object_id) AS n FROM sys.objects) AS t \
+             ORDER BY n",
+            &[],
+        )
+        .await
+        .expect("Query failed")
+        .with_batch_size(2);
+
+    let schema = batches.schema();
+    assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+    assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(10, 2));
+    assert_eq!(schema.field(2).data_type(), &DataType::Utf8);
+
+    let batches: Vec<_> = batches
+        .collect::<Result<_, _>>()
+        .expect("Arrow conversion failed");
+    assert_eq!(
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+        vec![2, 2, 1]
+    );
+    assert_eq!(
+        batches[0].column(0).as_primitive::<Int32Type>().values(),
+        &[1, 2]
+    );
+    assert_eq!(
+        batches[0]
+            .column(1)
+            .as_primitive::<Decimal128Type>()
+            .value(1),
+        300
+    );
+    assert!(batches[0].column(2).is_null(1));
+
+    client.close().await.expect("Failed to close");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_connect_timings() {
+    let config = get_test_config().expect("SQL Server config required");
+    let client = Client::connect(config).await.expect("Failed to connect");
+
+    let timings = client.connect_timings();
+    assert!(timings.login > std::time::Duration::ZERO);
+    let phases: std::time::Duration = timings.phases().iter().map(|(_, d)| *d).sum();
+    assert!(phases <= timings.total);
+
+    client.close().await.expect("Failed to close");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_resilient_client_reconnects_after_kill() {
+    use mssql_client::{Idempotency, ResilientClient};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = ResilientClient::connect(config.clone())
+        .await
+        .expect("Failed to connect");
+    client
+        .execute("USE tempdb", &[], Idempotency::Idempotent)
+        .await
+        .expect("USE failed");
+    let rows = client
+        .query("SELECT @@SPID", &[], Idempotency::Idempotent)
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Failed to collect");
+    let spid: i16 = rows[0].get(0).expect("Failed to get SPID");
+
+    // Kill the session from another connection
+    let mut admin = Client::connect(config).await.expect("Failed to connect");
+    admin
+        .execute(&format!("KILL {spid}"), &[])
+        .await
+        .expect("KILL failed");
+
+    let rows = client
+        .query("SELECT DB_NAME()", &[], Idempotency::Idempotent)
+        .await
+        .expect("Query was not retried")
+        .collect_all()
+        .await
+        .expect("Failed to collect");
+    let database: String = rows[0].get(0).expect("Failed to get database");
+    assert_eq!(database, "tempdb");
+    assert_eq!(client.reconnects(), 1);
+
+    admin.close().await.expect("Failed to close");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_current_blocking_tree() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut holder = Client::connect(config.clone())
+        .await
+        .expect("Failed to connect");
+    let mut waiter = Client::connect(config.clone())
+        .await
+        .expect("Failed to connect");
+    let mut observer = Client::connect(config).await.expect("Failed to connect");
+
+    holder
+        .execute(
+            "IF OBJECT_ID('tempdb..##blocking_probe') IS NULL \
+             CREATE TABLE ##blocking_probe (id INT PRIMARY KEY); \
+             BEGIN TRANSACTION; INSERT INTO ##blocking_probe VALUES (1);",
+            &[],
+        )
+        .await
+        .expect("Failed to take lock");
+
+    let blocked = tokio::spawn(async move {
+        waiter
+            .execute("SELECT COUNT(*) FROM ##blocking_probe", &[])
+            .await
+            .map(|_| waiter)
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let trees = observer
+        .current_blocking_tree()
+        .await
+        .expect("Failed to read blocking tree");
+    let chain = trees
+        .iter()
+        .find(|node| !node.blocked.is_empty())
+        .expect("Expected a blocking chain");
+    assert_eq!(chain.session.blocking_session_id, 0);
+    assert_eq!(chain.blocked_count(), 1);
+    assert!(
+        chain.blocked[0]
+            .session
+            .wait_type
+            .as_deref()
+            .is_some_and(|w| w.starts_with("LCK_M_"))
+    );
+
+    holder
+        .execute("ROLLBACK; DROP TABLE ##blocking_probe", &[])
+        .await
+        .expect("Failed to release lock");
+    blocked
+        .await
+        .expect("Task panicked")
+        .expect("Blocked query failed");
+
+    // system_health may have no deadlocks, but the query must run
+    observer
+        .recent_deadlocks(5)
+        .await
+        .expect("Failed to read deadlocks");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_diagnostics_snapshots() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let before = client
+        .diagnostics()
+        .query_stats(50)
+        .await
+        .expect("Failed to read query stats");
+    for _ in 0..3 {
+        client
+            .execute("SELECT COUNT(*) FROM sys.objects WHERE type = 'U'", &[])
+            .await
+            .expect("Query failed");
+    }
+    let after = client
+        .diagnostics()
+        .query_stats(50)
+        .await
+        .expect("Failed to read query stats");
+    assert!(after.delta(&before).elapsed > std::time::Duration::ZERO);
+
+    let waits = client
+        .diagnostics()
+        .wait_stats()
+        .await
+        .expect("Failed to read wait stats");
+    assert!(waits.rows.iter().all(|w| w.waiting_tasks > 0));
+
+    client
+        .diagnostics()
+        .index_usage()
+        .await
+        .expect("Failed to read index usage");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_backup_database_reports_progress() {
+    use mssql_client::BackupOptions;
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    // Backups to the null device are discarded by the server
+    let options = BackupOptions::new().copy_only(true).stats(25);
+    let mut backup = client
+        .backup_database("master", "NUL", &options)
+        .await
+        .expect("Failed to start backup");
+
+    let mut percents = Vec::new();
+    while let Some(progress) = backup.next_progress().await.expect("Backup failed") {
+        percents.push(progress.percent);
+    }
+    let summary = backup.wait().await.expect("Backup failed");
+
+    assert_eq!(percents.last(), Some(&100));
+    assert!(percents.windows(2).all(|w| w[0] < w[1]));
+    assert!(summary.pages.is_some_and(|pages| pages > 0));
+
+    // The connection is usable afterwards
+    client
+        .execute("SELECT 1", &[])
+        .await
+        .expect("Query after backup failed");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_admin_ensure_is_idempotent() {
+    use mssql_client::admin::{Securable, UserSource};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+    let mut admin = client.admin();
+
+    admin
+        .drop_user_if_exists("mssql_admin_test")
+        .await
+        .expect("Failed to drop user");
+    assert!(
+        admin
+            .ensure_user("mssql_admin_test", &UserSource::WithoutLogin)
+            .await
+            .expect("Failed to create user")
+    );
+    assert!(
+        !admin
+            .ensure_user("mssql_admin_test", &UserSource::WithoutLogin)
+            .await
+            .expect("Failed to check user")
+    );
+    assert!(
+        admin
+            .ensure_role_member("db_datareader", "mssql_admin_test")
+            .await
+            .expect("Failed to add role member")
+    );
+    assert!(
+        !admin
+            .ensure_role_member("db_datareader", "mssql_admin_test")
+            .await
+            .expect("Failed to check role member")
+    );
+    admin
+        .grant("VIEW DEFINITION", &Securable::Database, "mssql_admin_test")
+        .await
+        .expect("Failed to grant");
+    assert!(
+        admin
+            .drop_user_if_exists("mssql_admin_test")
+            .await
+            .expect("Failed to drop user")
+    );
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_availability_group_queries() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+    let mut ag = client.availability_groups();
+
+    // Without Always On the views are empty and every replica is caught up
+    let replicas = ag.replicas().await.expect("Failed to read replicas");
+    let databases = ag.databases().await.expect("Failed to read databases");
+    if replicas.is_empty() {
+        assert!(databases.is_empty());
+        assert!(
+            ag.wait_for_secondary_catchup(std::time::Duration::from_secs(1))
+                .await
+                .expect("Failed to check catch-up")
+        );
+    }
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_activity_snapshot_shows_idle_transaction() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut idle = Client::connect(config.clone())
+        .await
+        .expect("Failed to connect");
+    let mut observer = Client::connect(config).await.expect("Failed to connect");
+
+    let spid: i16 = idle
+        .query("SELECT CAST(@@SPID AS SMALLINT)", &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed")[0]
+        .get(0)
+        .expect("Missing SPID");
+    idle.execute(
+        "CREATE TABLE #activity_probe (id INT); BEGIN TRANSACTION; \
+         INSERT INTO #activity_probe VALUES (1);",
+        &[],
+    )
+    .await
+    .expect("Failed to open transaction");
+
+    let sessions = observer
+        .activity_snapshot()
+        .await
+        .expect("Failed to read activity");
+    let session = sessions
+        .iter()
+        .find(|s| s.session_id == spid)
+        .expect("Idle transaction not listed");
+    assert!(session.is_idle_in_transaction());
+    assert_eq!(session.open_transactions, 1);
+
+    idle.execute("ROLLBACK", &[])
+        .await
+        .expect("Rollback failed");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_session_options_survive_reset() {
+    use mssql_client::SessionOptions;
+
+    let config = get_test_config()
+        .expect("SQL Server config required")
+        .session_options(
+            SessionOptions::new()
+                .arithabort(true)
+                .lock_timeout(std::time::Duration::from_millis(1500)),
+        );
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    for reset in [false, true] {
+        if reset {
+            client
+                .execute("SET ARITHABORT OFF", &[])
+                .await
+                .expect("SET failed");
+            client.mark_needs_reset();
+        }
+        let rows = client
+            .query(
+                "SELECT CAST(SESSIONPROPERTY('ARITHABORT') AS INT), @@LOCK_TIMEOUT",
+                &[],
+            )
+            .await
+            .expect("Query failed")
+            .collect_all()
+            .await
+            .expect("Query failed");
+        assert_eq!(rows[0].get::<i32>(0).expect("ARITHABORT"), 1);
+        assert_eq!(rows[0].get::<i32>(1).expect("LOCK_TIMEOUT"), 1500);
+    }
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_time_zone_policy() {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use mssql_client::TimeZonePolicy;
+
+    let sql = "SELECT CAST('2024-06-01T12:00:00' AS DATETIME2), \
+               CAST('2024-06-01T12:00:00+02:00' AS DATETIMEOFFSET)";
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+    let rows = client
+        .query(sql, &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed");
+    let utc: DateTime<Utc> = rows[0].get(0).expect("DATETIME2 as UTC");
+    assert_eq!(utc.to_rfc3339(), "2024-06-01T12:00:00+00:00");
+
+    let config = get_test_config()
+        .expect("SQL Server config required")
+        .time_zone_policy(TimeZonePolicy::RejectNaive);
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+    let rows = client
+        .query(sql, &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed");
+    assert!(rows[0].get::<DateTime<Utc>>(0).is_err());
+    assert!(rows[0].get::<NaiveDateTime>(0).is_ok());
+    let offset: DateTime<Utc> = rows[0].get(1).expect("DATETIMEOFFSET as UTC");
+    assert_eq!(offset.to_rfc3339(), "2024-06-01T10:00:00+00:00");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_query_in_expands_long_lists() {
+    // More values than fit into one statement, sent as a TVP and split
+    let ids: Vec<i64> = (1..=5000).collect();
+    for tvp in [true, false] {
+        let config = get_test_config()
+            .expect("SQL Server config required")
+            .in_list_tvp(tvp);
+        let mut client = Client::connect(config).await.expect("Failed to connect");
+        let rows = client
+            .query_in(
+                "SELECT n FROM (SELECT TOP (10000) ROW_NUMBER() OVER (ORDER BY (SELECT NULL)) AS n \
+                 FROM sys.all_columns a CROSS JOIN sys.all_columns b) t \
+                 WHERE n % @p1 = 0 AND n IN (@ids)",
+                "@ids",
+                &ids,
+                &[&2i64],
+            )
+            .await
+            .expect("Query failed")
+            .collect_all()
+            .await
+            .expect("Query failed");
+        assert_eq!(rows.len(), 2500);
+    }
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let empty: Vec<i32> = Vec::new();
+    let rows = client
+        .query_in("SELECT 1 WHERE 1 IN (@ids)", "@ids", &empty, &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed");
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_temp_table_guard() {
+    use mssql_client::{Tvp, TvpColumn, TvpRow};
+    use mssql_types::{ToSql, TypeError};
+
+    struct Key {
+        id: i64,
+    }
+
+    impl Tvp for Key {
+        fn type_name() -> &'static str {
+            "dbo.TempTableTestKeys"
+        }
+
+        fn columns() -> Vec<TvpColumn> {
+            vec![TvpColumn::new("Id", "BIGINT", 0)]
+        }
+
+        fn to_row(&self) -> Result<TvpRow, TypeError> {
+            Ok(TvpRow::new(vec![self.id.to_sql()?]))
+        }
+    }
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+    let keys: Vec<Key> = (1..=1000).map(|id| Key { id }).collect();
+
+    {
+        let mut table = client
+            .temp_table("#keys", &keys[..600])
+            .await
+            .expect("temp_table failed");
+        assert_eq!(
+            table.insert(&keys[600..]).await.expect("insert failed"),
+            400
+        );
+        let rows = table
+            .client()
+            .query("SELECT COUNT(*) FROM #keys", &[])
+            .await
+            .expect("Query failed")
+            .collect_all()
+            .await
+            .expect("Query failed");
+        assert_eq!(rows[0].get::<i32>(0).unwrap(), 1000);
+    }
+
+    // The dropped guard's table is gone before the next request runs
+    let rows = client
+        .query("SELECT OBJECT_ID(N'tempdb..#keys')", &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed");
+    assert_eq!(rows[0].get::<Option<i32>>(0).unwrap(), None);
+
+    let table = client
+        .temp_table("#keys", &keys)
+        .await
+        .expect("temp_table failed");
+    table.drop_table().await.expect("drop failed");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_query_with_options() {
+    use mssql_client::{QueryOptions, TableHint};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let options = QueryOptions::new()
+        .lock_timeout(std::time::Duration::from_millis(750))
+        .maxdop(1)
+        .optimize_for("p1", 5)
+        .table_hint("o", TableHint::NoLock);
+    let rows = client
+        .query_with_options(
+            "SELECT COUNT(*), @@LOCK_TIMEOUT FROM sys.objects o WHERE o.object_id > @p1;",
+            &[&0i32],
+            &options,
+        )
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed");
+    assert!(rows[0].get::<i32>(0).expect("count") > 0);
+    assert_eq!(rows[0].get::<i32>(1).expect("lock timeout"), 750);
+
+    // The lock timeout is scoped to the statement.
+    let timeout: i32 = client
+        .query("SELECT @@LOCK_TIMEOUT", &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed")[0]
+        .get(0)
+        .expect("lock timeout");
+    assert_eq!(timeout, -1);
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_output_parameters() {
+    use mssql_client::{OutParam, SqlType};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    client
+        .execute(
+            "CREATE PROCEDURE #double_it @x INT, @doubled INT OUTPUT, @label NVARCHAR(50) OUTPUT \
+             AS SET @doubled = @x * 2; SET @label = @label + N'!'",
+            &[],
+        )
+        .await
+        .expect("CREATE PROCEDURE failed");
+
+    let doubled = OutParam::<i32>::new(SqlType::Int);
+    let label = OutParam::<String>::input_output(SqlType::NVarChar(50), "done");
+    client
+        .execute(
+            "EXEC #double_it @p1, @p2 OUTPUT, @p3 OUTPUT",
+            &[&21i32, &doubled, &label],
+        )
+        .await
+        .expect("EXEC failed");
+
+    assert_eq!(doubled.value().expect("doubled"), 42);
+    assert_eq!(label.value().expect("label"), "done!");
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_query_json_joins_fragments() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let json = client
+        .query_json(
+            "SELECT TOP (200) object_id, name FROM sys.all_objects ORDER BY object_id FOR JSON PATH",
+            &[],
+        )
+        .await
+        .expect("FOR JSON query failed");
+    assert!(json.len() > 4000, "expected output spanning several rows");
+    let value: serde_json::Value = serde_json::from_str(&json).expect("invalid JSON");
+    assert_eq!(value.as_array().map(Vec::len), Some(200));
+
+    let empty = client
+        .query_json(
+            "SELECT name FROM sys.all_objects WHERE 1 = 0 FOR JSON PATH",
+            &[],
+        )
+        .await
+        .expect("FOR JSON query failed");
+    assert!(empty.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_column_metadata_flags_and_base_names() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    client
+        .execute(
+            "CREATE TABLE #meta (id INT IDENTITY PRIMARY KEY, price DECIMAL(9, 2), \
+             doubled AS price * 2)",
+            &[],
+        )
+        .await
+        .expect("CREATE TABLE failed");
+    client
+        .execute("SET NO_BROWSETABLE ON", &[])
+        .await
+        .expect("SET failed");
+
+    let stream = client
+        .query(
+            "SELECT id, price AS amount, doubled, 1 AS one FROM #meta",
+            &[],
+        )
+        .await
+        .expect("Query failed");
+    let columns = stream.columns();
+    assert!(columns[0].identity);
+    assert_eq!((columns[1].precision, columns[1].scale), (Some(9), Some(2)));
+    assert_eq!(columns[1].base_column.as_deref(), Some("price"));
+    assert!(columns[1].base_table.is_some());
+    assert!(columns[2].computed);
+    assert_eq!(columns[3].base_table, None);
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_browse_mode_row_edits() {
+    use mssql_client::browse::{delete_row, update_row};
+
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.expect("Failed to connect");
+
+    let _ = client
+        .execute("DROP TABLE IF EXISTS dbo.browse_products", &[])
+        .await;
+    client
+        .execute(
+            "CREATE TABLE dbo.browse_products (id INT PRIMARY KEY, name NVARCHAR(50), price INT); \
+             INSERT INTO dbo.browse_products VALUES (1, N'pen', 2), (2, N'ink', 5)",
+            &[],
+        )
+        .await
+        .expect("setup failed");
+
+    let rows = client
+        .query_browse(
+            "SELECT name, price FROM dbo.browse_products ORDER BY id",
+            &[],
+        )
+        .await
+        .expect("browse query failed")
+        .collect_all()
+        .await
+        .expect("browse query failed");
+    let columns = rows[0].columns();
+    assert!(columns.iter().any(|c| c.key && c.hidden));
+
+    let edit = update_row(&rows[0], &[("price", &3i32)]).expect("update_row failed");
+    let updated = client
+        .execute(edit.sql(), &edit.params())
+        .await
+        .expect("UPDATE failed");
+    assert_eq!(updated, 1);
+
+    let edit = delete_row(&rows[1]).expect("delete_row failed");
+    let deleted = client
+        .execute(edit.sql(), &edit.params())
+        .await
+        .expect("DELETE failed");
+    assert_eq!(deleted, 1);
+
+    let price: i32 = client
+        .query("SELECT SUM(price) FROM dbo.browse_products", &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Query failed")[0]
+        .get(0)
+        .expect("price");
+    assert_eq!(price, 3);
+
+    client
+        .execute("DROP TABLE dbo.browse_products", &[])
+        .await
+        .expect("cleanup failed");
+}
@@ -0,0 +1,95 @@
+//! Schema migration embedding tests.
+//!
+//! Checks that `embed_migrations!` picks up versioned up/down scripts. Tests
+//! that apply migrations against a live SQL Server are ignored by default.
+
+#![cfg(feature = "migrations")]
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::{Client, Config, Migrator};
+use mssql_derive::embed_migrations;
+
+/// Helper to get test configuration from environment variables.
+fn get_test_config() -> Option<Config> {
+    let host = std::env::var("MSSQL_HOST").ok()?;
+    let port = std::env::var("MSSQL_PORT")
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(1433);
+    let user = std::env::var("MSSQL_USER").unwrap_or_else(|_| "sa".into());
+    let password = std::env::var("MSSQL_PASSWORD").unwrap_or_else(|_| "MyStrongPassw0rd".into());
+    let database = std::env::var("MSSQL_DATABASE").unwrap_or_else(|_| "master".into());
+    let encrypt = std::env::var("MSSQL_ENCRYPT").unwrap_or_else(|_| "false".into());
+
+    let conn_str = format!(
+        "Server={},{};Database={};User Id={};Password={};TrustServerCertificate=true;Encrypt={}",
+        host, port, database, user, password, encrypt
+    );
+
+    Config::from_connection_string(&conn_str).ok()
+}
+
+fn migrator() -> Migrator {
+    embed_migrations!("tests/fixtures/migrations")
+}
+
+#[test]
+fn test_embedded_migrations() {
+    let migrator = migrator();
+    let migrations = migrator.migrations();
+
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(migrations[0].version(), 1);
+    assert_eq!(migrations[0].name(), "create_users");
+    assert!(migrations[0].up_sql().contains("CREATE TABLE users"));
+    assert!(migrations[0].down_sql().is_none());
+
+    assert_eq!(migrations[1].version(), 2);
+    assert_eq!(migrations[1].name(), "add_email");
+    assert!(
+        migrations[1]
+            .down_sql()
+            .unwrap()
+            .contains("DROP COLUMN email")
+    );
+}
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_run_and_undo_migrations() {
+    let config = get_test_config().expect("SQL Server config required");
+    let mut client = Client::connect(config).await.unwrap();
+
+    client
+        .execute(
+            "IF OBJECT_ID(N'users', N'U') IS NOT NULL DROP TABLE users; \
+             IF OBJECT_ID(N'__mssql_migrations', N'U') IS NOT NULL DROP TABLE __mssql_migrations",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let migrator = migrator();
+
+    let plan = migrator
+        .clone()
+        .dry_run(true)
+        .run(&mut client)
+        .await
+        .unwrap();
+    assert_eq!(plan.applied, [1, 2]);
+    assert!(migrator.applied(&mut client).await.unwrap().is_empty());
+
+    let report = migrator.run(&mut client).await.unwrap();
+    assert_eq!(report.applied, [1, 2]);
+    assert!(migrator.pending(&mut client).await.unwrap().is_empty());
+
+    // Running again is a no-op
+    assert!(migrator.run(&mut client).await.unwrap().applied.is_empty());
+
+    let report = migrator.undo(&mut client, 1).await.unwrap();
+    assert_eq!(report.reverted, [2]);
+    assert_eq!(migrator.applied(&mut client).await.unwrap().len(), 1);
+
+    client.close().await.unwrap();
+}
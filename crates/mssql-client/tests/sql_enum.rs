@@ -0,0 +1,119 @@
+//! `#[derive(SqlEnum)]` conversion tests.
+//!
+//! Checks that derived enums round-trip through `SqlValue` as strings or
+//! integers, and that unknown values produce errors listing the valid ones.
+
+#![allow(clippy::unwrap_used)]
+
+use mssql_client::{FromSql, SqlValue, ToSql};
+use mssql_derive::SqlEnum;
+use mssql_types::TypeError;
+
+#[derive(Debug, PartialEq, SqlEnum)]
+#[mssql(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Status {
+    Active,
+    #[mssql(rename = "ON_HOLD")]
+    Paused,
+    InReview,
+}
+
+#[derive(Debug, PartialEq, SqlEnum)]
+#[mssql(repr = "int")]
+#[repr(u8)]
+enum Priority {
+    Low = 1,
+    Medium,
+    High = 10,
+}
+
+#[derive(Debug, PartialEq, SqlEnum)]
+#[mssql(repr = "int")]
+enum Level {
+    Debug = -1,
+    Info,
+}
+
+#[test]
+fn test_string_enum_round_trip() {
+    for (status, stored) in [
+        (Status::Active, "ACTIVE"),
+        (Status::Paused, "ON_HOLD"),
+        (Status::InReview, "IN_REVIEW"),
+    ] {
+        let value = status.to_sql().unwrap();
+        assert_eq!(value, SqlValue::String(stored.into()));
+        assert_eq!(Status::from_sql(&value).unwrap(), status);
+    }
+    assert_eq!(Status::Active.sql_type(), "NVARCHAR");
+}
+
+#[test]
+fn test_string_enum_ignores_char_padding() {
+    let value = SqlValue::String("ACTIVE    ".into());
+    assert_eq!(Status::from_sql(&value).unwrap(), Status::Active);
+}
+
+#[test]
+fn test_string_enum_unknown_value() {
+    let err = Status::from_sql(&SqlValue::String("Paused".into())).unwrap_err();
+    assert!(matches!(
+        err,
+        TypeError::UnknownVariant {
+            type_name: "Status",
+            ..
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "invalid value Paused for Status, expected one of: ACTIVE, ON_HOLD, IN_REVIEW"
+    );
+}
+
+#[test]
+fn test_int_enum_round_trip() {
+    for (priority, stored) in [
+        (Priority::Low, 1),
+        (Priority::Medium, 2),
+        (Priority::High, 10),
+    ] {
+        let value = priority.to_sql().unwrap();
+        assert_eq!(value, SqlValue::TinyInt(stored));
+        assert_eq!(Priority::from_sql(&value).unwrap(), priority);
+    }
+    assert_eq!(Priority::Low.sql_type(), "TINYINT");
+
+    // Wider integer columns are accepted too.
+    assert_eq!(
+        Priority::from_sql(&SqlValue::BigInt(10)).unwrap(),
+        Priority::High
+    );
+
+    assert_eq!(Level::Debug.to_sql().unwrap(), SqlValue::Int(-1));
+    assert_eq!(Level::from_sql(&SqlValue::Int(0)).unwrap(), Level::Info);
+}
+
+#[test]
+fn test_int_enum_unknown_value() {
+    let err = Priority::from_sql(&SqlValue::Int(3)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value 3 for Priority, expected one of: 1 (Low), 2 (Medium), 10 (High)"
+    );
+}
+
+#[test]
+fn test_enum_null_and_mismatch() {
+    assert!(matches!(
+        Status::from_sql(&SqlValue::Null),
+        Err(TypeError::UnexpectedNull)
+    ));
+    assert_eq!(Status::from_sql_nullable(&SqlValue::Null).unwrap(), None);
+    assert!(matches!(
+        Priority::from_sql(&SqlValue::String("Low".into())),
+        Err(TypeError::TypeMismatch {
+            expected: "TINYINT",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,90 @@
+//! Benchmarks for sending multi-packet messages.
+//!
+//! Compares one flushed write per packet with `send_packets`, which hands
+//! the whole message to the socket in a single vectored write. Runs over a
+//! loopback TCP connection so that the syscall cost is included.
+
+#![allow(missing_docs, clippy::unwrap_used)]
+
+use bytes::{Bytes, BytesMut};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use futures_util::SinkExt;
+use mssql_codec::{Packet, PacketWriter};
+use tds_protocol::packet::{PACKET_HEADER_SIZE, PacketHeader, PacketStatus, PacketType};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+
+const PACKET_SIZE: usize = 4096;
+
+/// Split a message payload into packets the way `Connection` does.
+fn packets(payload: &Bytes) -> Vec<Packet> {
+    let chunks: Vec<_> = payload.chunks(PACKET_SIZE - PACKET_HEADER_SIZE).collect();
+    let last = chunks.len() - 1;
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let status = if i == last {
+                PacketStatus::END_OF_MESSAGE
+            } else {
+                PacketStatus::NORMAL
+            };
+            let header = PacketHeader::new(PacketType::BulkLoad, status, 0);
+            Packet::new(header, BytesMut::from(chunk))
+        })
+        .collect()
+}
+
+/// Connect to a loopback listener that discards everything it receives.
+fn connect(rt: &Runtime) -> PacketWriter<TcpStream> {
+    rt.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 256 * 1024];
+            while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        stream.set_nodelay(true).unwrap();
+        PacketWriter::new(stream)
+    })
+}
+
+fn bench_send_message(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("send_message");
+
+    for (name, size) in [("param_64kb", 64 * 1024), ("bulk_insert_1mb", 1024 * 1024)] {
+        let payload = Bytes::from(vec![0x5Au8; size]);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        let mut writer = connect(&rt);
+        group.bench_with_input(
+            BenchmarkId::new("per_packet", name),
+            &payload,
+            |b, payload| {
+                b.iter(|| {
+                    rt.block_on(async {
+                        for packet in packets(payload) {
+                            writer.send(packet).await.unwrap();
+                        }
+                    })
+                })
+            },
+        );
+
+        let mut writer = connect(&rt);
+        group.bench_with_input(
+            BenchmarkId::new("send_packets", name),
+            &payload,
+            |b, payload| b.iter(|| rt.block_on(writer.send_packets(packets(payload))).unwrap()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_send_message);
+criterion_main!(benches);
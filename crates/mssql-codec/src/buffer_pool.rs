@@ -0,0 +1,166 @@
+//! Reusable payload buffers.
+//!
+//! Decoding a packet copies its payload out of the read buffer, and sending a
+//! message copies each chunk into a packet. Without reuse, every packet costs
+//! an allocation. [`BufferPool`] keeps a small free list of payload buffers
+//! per codec so that steady-state row streaming allocates little or nothing.
+
+use bytes::BytesMut;
+use tds_protocol::packet::MAX_PACKET_SIZE;
+
+/// Default number of buffers kept by a codec's pool.
+pub const DEFAULT_POOL_BUFFERS: usize = 8;
+
+/// A bounded free list of payload buffers.
+///
+/// Buffers are handed out with [`get`](Self::get) and given back with
+/// [`put`](Self::put). Returned buffers are dropped instead of kept once the
+/// pool holds `max_buffers`, or if they have grown past `max_buffer_size`, so
+/// a pool never retains more than `max_buffers * max_buffer_size` bytes.
+///
+/// Cloning a pool copies its limits but not its buffers.
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Vec<BytesMut>,
+    max_buffers: usize,
+    max_buffer_size: usize,
+}
+
+impl BufferPool {
+    /// Create a pool holding at most `max_buffers` buffers of up to
+    /// `max_buffer_size` bytes each.
+    #[must_use]
+    pub fn new(max_buffers: usize, max_buffer_size: usize) -> Self {
+        Self {
+            buffers: Vec::new(),
+            max_buffers,
+            max_buffer_size,
+        }
+    }
+
+    /// Create a pool that never keeps buffers.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self::new(0, 0)
+    }
+
+    /// Get the maximum number of buffers kept.
+    #[must_use]
+    pub fn max_buffers(&self) -> usize {
+        self.max_buffers
+    }
+
+    /// Get the maximum capacity of a kept buffer.
+    #[must_use]
+    pub fn max_buffer_size(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    /// Get the number of buffers currently available.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Check if no buffers are available.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Take an empty buffer with room for at least `capacity` bytes.
+    pub fn get(&mut self, capacity: usize) -> BytesMut {
+        match self.buffers.pop() {
+            Some(mut buf) => {
+                buf.reserve(capacity);
+                buf
+            }
+            None => BytesMut::with_capacity(capacity),
+        }
+    }
+
+    /// Give a buffer back to the pool.
+    ///
+    /// The buffer is cleared; buffers without any capacity are not kept.
+    pub fn put(&mut self, mut buf: BytesMut) {
+        if self.buffers.len() >= self.max_buffers
+            || buf.capacity() == 0
+            || buf.capacity() > self.max_buffer_size
+        {
+            return;
+        }
+
+        buf.clear();
+        self.buffers.push(buf);
+    }
+
+    /// Drop all available buffers.
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_BUFFERS, MAX_PACKET_SIZE)
+    }
+}
+
+impl Clone for BufferPool {
+    fn clone(&self) -> Self {
+        Self::new(self.max_buffers, self.max_buffer_size)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let mut pool = BufferPool::new(2, 1024);
+
+        let mut buf = pool.get(512);
+        buf.extend_from_slice(b"payload");
+        let ptr = buf.as_ptr();
+        pool.put(buf);
+        assert_eq!(pool.len(), 1);
+
+        let buf = pool.get(512);
+        assert!(buf.is_empty());
+        assert_eq!(buf.as_ptr(), ptr);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut pool = BufferPool::new(1, 1024);
+
+        pool.put(BytesMut::with_capacity(4096));
+        assert!(pool.is_empty());
+
+        pool.put(BytesMut::with_capacity(64));
+        pool.put(BytesMut::with_capacity(64));
+        assert_eq!(pool.len(), 1);
+
+        pool.clear();
+        pool.put(BytesMut::new());
+        assert!(pool.is_empty());
+
+        let mut disabled = BufferPool::disabled();
+        disabled.put(BytesMut::with_capacity(64));
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_clone_keeps_limits_only() {
+        let mut pool = BufferPool::new(3, 2048);
+        pool.put(BytesMut::with_capacity(64));
+
+        let cloned = pool.clone();
+        assert!(cloned.is_empty());
+        assert_eq!(cloned.max_buffers(), 3);
+        assert_eq!(cloned.max_buffer_size(), 2048);
+    }
+}
@@ -125,7 +125,9 @@ where
 
             match self.reader.next().await {
                 Some(Ok(packet)) => {
-                    if let Some(message) = self.assembler.push(packet) {
+                    let message = self.assembler.push_ref(&packet);
+                    self.reader.codec_mut().recycle(packet.payload);
+                    if let Some(message) = message {
                         return Ok(Some(message));
                     }
                     // Continue reading packets until message complete
@@ -160,6 +162,9 @@ where
 
     /// Send a complete message, splitting into multiple packets if needed.
     ///
+    /// The packets are written together rather than one write per packet;
+    /// see [`PacketWriter::send_packets`].
+    ///
     /// If `reset_connection` is true, the RESETCONNECTION flag is set on the
     /// first packet. This causes SQL Server to reset connection state (temp
     /// tables, SET options, isolation level, etc.) before executing the command.
@@ -186,36 +191,46 @@ where
         payload: Bytes,
         max_packet_size: usize,
         reset_connection: bool,
+    ) -> Result<(), CodecError> {
+        let first_packet_status = if reset_connection {
+            PacketStatus::RESET_CONNECTION
+        } else {
+            PacketStatus::NORMAL
+        };
+        self.send_message_with_status(packet_type, payload, max_packet_size, first_packet_status)
+            .await
+    }
+
+    /// Send a complete message with extra status flags on its first packet.
+    ///
+    /// Use [`PacketStatus::RESET_CONNECTION`] to reset the session before the
+    /// request runs, as `sp_reset_connection` does for pooled connections, or
+    /// [`PacketStatus::RESET_CONNECTION_KEEP_TRANSACTION`] (RESETCONNECTIONSKIPTRAN)
+    /// to reset it without ending an open transaction. END_OF_MESSAGE is set
+    /// on the last packet as usual.
+    pub async fn send_message_with_status(
+        &mut self,
+        packet_type: PacketType,
+        payload: Bytes,
+        max_packet_size: usize,
+        first_packet_status: PacketStatus,
     ) -> Result<(), CodecError> {
         let max_payload = max_packet_size - PACKET_HEADER_SIZE;
         let chunks: Vec<_> = payload.chunks(max_payload).collect();
         let total_chunks = chunks.len();
 
         let mut writer = self.writer.lock().await;
+        let mut packets = Vec::with_capacity(total_chunks);
 
         for (i, chunk) in chunks.into_iter().enumerate() {
-            let is_first = i == 0;
-            let is_last = i == total_chunks - 1;
-
-            // Build status flags
-            let mut status = if is_last {
-                PacketStatus::END_OF_MESSAGE
-            } else {
-                PacketStatus::NORMAL
-            };
-
-            // Per TDS spec, RESETCONNECTION must be on the first packet only
-            if is_first && reset_connection {
-                status |= PacketStatus::RESET_CONNECTION;
-            }
-
+            let status = packet_status(i, total_chunks, first_packet_status);
             let header = PacketHeader::new(packet_type, status, 0);
-            let packet = Packet::new(header, BytesMut::from(chunk));
-
-            writer.send(packet).await?;
+            let mut buf = writer.codec_mut().payload_buffer(chunk.len());
+            buf.extend_from_slice(chunk);
+            packets.push(Packet::new(header, buf));
         }
 
-        Ok(())
+        writer.send_packets(packets).await
     }
 
     /// Flush the write buffer.
@@ -250,6 +265,7 @@ where
                             return Ok(None);
                         }
                     }
+                    self.reader.codec_mut().recycle(packet.payload);
                     // Continue draining
                 }
                 Some(Err(e)) => {
@@ -295,6 +311,36 @@ where
     pub fn read_codec_mut(&mut self) -> &mut TdsCodec {
         self.reader.codec_mut()
     }
+
+    /// Get the negotiated packet size.
+    #[must_use]
+    pub fn packet_size(&self) -> usize {
+        self.reader.codec().max_packet_size()
+    }
+
+    /// Apply a packet size negotiated with the server to both the read and
+    /// write codecs.
+    pub async fn set_packet_size(&mut self, size: usize) {
+        self.reader.codec_mut().set_max_packet_size(size);
+        self.writer
+            .lock()
+            .await
+            .codec_mut()
+            .set_max_packet_size(size);
+        tracing::debug!(packet_size = self.packet_size(), "packet size changed");
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T> Connection<crate::transport::Compat<T>>
+where
+    T: futures_util::io::AsyncRead + futures_util::io::AsyncWrite,
+{
+    /// Create a new connection over a `futures-io` transport, e.g. an
+    /// async-std or smol `TcpStream`.
+    pub fn from_futures_io(transport: T) -> Self {
+        Self::new(crate::transport::compat(transport))
+    }
 }
 
 impl<T> std::fmt::Debug for Connection<T>
@@ -334,7 +380,11 @@ where
         // Mark cancellation in progress
         self.cancelling
             .store(true, std::sync::atomic::Ordering::Release);
+        self.send_attention().await
+    }
 
+    /// Write and flush an Attention packet.
+    async fn send_attention(&self) -> Result<(), CodecError> {
         tracing::debug!("sending Attention packet for query cancellation");
 
         // Send the Attention packet
@@ -371,6 +421,39 @@ where
     }
 }
 
+impl<T> CancelHandle<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    /// Cancel the current query, sending the Attention packet from a
+    /// spawned task.
+    ///
+    /// For use where awaiting is not possible, such as in `Drop`. The
+    /// connection is marked as cancelling before this returns, so the next
+    /// [`Connection::read_message`] drains the rest of the response. Does
+    /// nothing if a cancellation is already in progress. Returns `false`,
+    /// without cancelling, when called outside a Tokio runtime.
+    pub fn cancel_in_background(&self) -> bool {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        if self
+            .cancelling
+            .swap(true, std::sync::atomic::Ordering::AcqRel)
+        {
+            return true;
+        }
+
+        let handle = self.clone();
+        runtime.spawn(async move {
+            if let Err(e) = handle.send_attention().await {
+                tracing::debug!(error = %e, "failed to send Attention packet");
+            }
+        });
+        true
+    }
+}
+
 impl<T> Clone for CancelHandle<T>
 where
     T: AsyncRead + AsyncWrite,
@@ -395,11 +478,46 @@ where
     }
 }
 
+/// Status flags for packet `index` of a message split into `total` packets.
+///
+/// Per the TDS spec, reset flags are only valid on the first packet.
+fn packet_status(index: usize, total: usize, first_packet_status: PacketStatus) -> PacketStatus {
+    let mut status = if index + 1 == total {
+        PacketStatus::END_OF_MESSAGE
+    } else {
+        PacketStatus::NORMAL
+    };
+    if index == 0 {
+        status |= first_packet_status;
+    }
+    status
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_packet_status_flags_on_first_packet() {
+        let reset = PacketStatus::RESET_CONNECTION;
+        assert_eq!(
+            packet_status(0, 1, reset),
+            PacketStatus::END_OF_MESSAGE | PacketStatus::RESET_CONNECTION
+        );
+
+        assert_eq!(packet_status(0, 3, reset), PacketStatus::RESET_CONNECTION);
+        assert_eq!(packet_status(1, 3, reset), PacketStatus::NORMAL);
+        assert_eq!(packet_status(2, 3, reset), PacketStatus::END_OF_MESSAGE);
+
+        let keep = PacketStatus::RESET_CONNECTION_KEEP_TRANSACTION;
+        assert_eq!(packet_status(0, 2, keep), keep);
+        assert_eq!(
+            packet_status(0, 1, PacketStatus::NORMAL),
+            PacketStatus::END_OF_MESSAGE
+        );
+    }
+
     #[test]
     fn test_attention_packet_header() {
         // Verify attention packet header construction
@@ -414,6 +532,38 @@ mod tests {
         assert_eq!(header.length, PACKET_HEADER_SIZE as u16);
     }
 
+    #[tokio::test]
+    async fn test_cancel_in_background() {
+        use tokio::io::AsyncReadExt;
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        let conn = Connection::new(client);
+        let handle = conn.cancel_handle();
+
+        assert!(handle.cancel_in_background());
+        assert!(conn.is_cancelling());
+        // Already cancelling: no second Attention packet
+        assert!(handle.cancel_in_background());
+
+        let mut buf = [0u8; PACKET_HEADER_SIZE];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf[0], PacketType::Attention as u8);
+
+        drop(conn);
+        drop(handle);
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_in_background_outside_runtime() {
+        let (client, _server) = tokio::io::duplex(1024);
+        let conn = Connection::new(client);
+        assert!(!conn.cancel_handle().cancel_in_background());
+        assert!(!conn.is_cancelling());
+    }
+
     #[test]
     fn test_check_attention_done() {
         // Test DONE token with ATTN flag detection
@@ -6,20 +6,36 @@
 //! - `PacketWriter<T>` - Write-only sink for sending packets
 //!
 //! The split types are used by `Connection` for cancellation safety (ADR-005).
+//! A `PacketStream` can be turned into them with [`PacketStream::split`] and
+//! rebuilt with [`PacketReader::reunite`].
+//!
+//! Multi-packet messages should be sent with `send_packets`, which writes all
+//! packets with one vectored write where the transport supports it, instead
+//! of one write per packet.
 
+use std::collections::VecDeque;
+use std::io::IoSlice;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use bytes::BytesMut;
 use futures_core::Stream;
-use futures_util::Sink;
+use futures_util::{Sink, SinkExt};
 use pin_project_lite::pin_project;
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_util::codec::{Framed, FramedRead, FramedWrite};
+use tds_protocol::packet::PACKET_HEADER_SIZE;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio_util::codec::{Decoder, Framed, FramedParts, FramedRead, FramedWrite};
 
+use crate::capture::Direction;
 use crate::error::CodecError;
 use crate::packet_codec::{Packet, TdsCodec};
 
+/// Encoded bytes buffered before a sink flushes on its own.
+///
+/// Sized so that a multi-packet message fed without vectored I/O goes out in
+/// a few large writes rather than one per packet.
+const WRITE_COALESCE_LIMIT: usize = 64 * 1024;
+
 pin_project! {
     /// A framed packet stream over an async I/O transport.
     ///
@@ -37,16 +53,14 @@ where
 {
     /// Create a new packet stream over the given transport.
     pub fn new(transport: T) -> Self {
-        Self {
-            inner: Framed::new(transport, TdsCodec::new()),
-        }
+        Self::with_codec(transport, TdsCodec::new())
     }
 
     /// Create a new packet stream with a custom codec.
     pub fn with_codec(transport: T, codec: TdsCodec) -> Self {
-        Self {
-            inner: Framed::new(transport, codec),
-        }
+        let mut inner = Framed::new(transport, codec);
+        inner.set_backpressure_boundary(WRITE_COALESCE_LIMIT);
+        Self { inner }
     }
 
     /// Get a reference to the underlying transport.
@@ -83,6 +97,74 @@ where
     pub fn read_buffer_mut(&mut self) -> &mut BytesMut {
         self.inner.read_buffer_mut()
     }
+
+    /// Split the stream into independent read and write halves.
+    ///
+    /// Buffered data and codec state carry over to the halves, so a response
+    /// can be drained from the reader while the writer sends an Attention
+    /// packet. Rejoin the halves with [`PacketReader::reunite`].
+    pub fn split(self) -> (PacketReader<ReadHalf<T>>, PacketWriter<WriteHalf<T>>) {
+        let mut parts = self.inner.into_parts();
+        let (read_half, write_half) = tokio::io::split(parts.io);
+
+        // `FramedRead` only decodes after reading from the transport, so
+        // complete packets already buffered are decoded here instead
+        let mut reader = PacketReader::with_codec(read_half, parts.codec.clone());
+        while let Ok(Some(packet)) = reader.inner.decoder_mut().decode(&mut parts.read_buf) {
+            reader.buffered.push_back(packet);
+        }
+        reader.read_buffer_mut().extend_from_slice(&parts.read_buf);
+
+        let mut writer = PacketWriter::with_codec(write_half, parts.codec);
+        writer
+            .inner
+            .write_buffer_mut()
+            .extend_from_slice(&parts.write_buf);
+
+        (reader, writer)
+    }
+}
+
+impl<T> PacketStream<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Send several packets, e.g. the packets of one message, and flush them.
+    ///
+    /// If the transport supports vectored I/O, all headers and payloads go
+    /// out in a single `write_vectored` call (repeated only on partial
+    /// writes). Otherwise the packets are encoded into the write buffer and
+    /// flushed together.
+    pub async fn send_packets(&mut self, packets: Vec<Packet>) -> Result<(), CodecError> {
+        // Anything already buffered must go out first to keep packet order
+        SinkExt::flush(self).await?;
+
+        if !self.get_ref().is_write_vectored() {
+            for packet in packets {
+                self.feed(packet).await?;
+            }
+            return SinkExt::flush(self).await;
+        }
+
+        let headers = encode_headers(self.codec_mut(), &packets)?;
+        write_all_vectored(self.get_mut(), &headers, &packets).await?;
+        for packet in packets {
+            self.codec_mut().recycle(packet.payload);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T> PacketStream<crate::transport::Compat<T>>
+where
+    T: futures_util::io::AsyncRead + futures_util::io::AsyncWrite,
+{
+    /// Create a new packet stream over a `futures-io` transport, e.g. an
+    /// async-std or smol `TcpStream`.
+    pub fn from_futures_io(transport: T) -> Self {
+        Self::new(crate::transport::compat(transport))
+    }
 }
 
 impl<T> Stream for PacketStream<T>
@@ -142,6 +224,7 @@ pin_project! {
     pub struct PacketReader<T> {
         #[pin]
         inner: FramedRead<T, TdsCodec>,
+        buffered: VecDeque<Packet>,
     }
 }
 
@@ -153,6 +236,7 @@ where
     pub fn new(transport: T) -> Self {
         Self {
             inner: FramedRead::new(transport, TdsCodec::new()),
+            buffered: VecDeque::new(),
         }
     }
 
@@ -160,6 +244,7 @@ where
     pub fn with_codec(transport: T, codec: TdsCodec) -> Self {
         Self {
             inner: FramedRead::new(transport, codec),
+            buffered: VecDeque::new(),
         }
     }
 
@@ -194,6 +279,51 @@ where
     }
 }
 
+impl<T> PacketReader<ReadHalf<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Rejoin the halves produced by [`PacketStream::split`].
+    ///
+    /// Unread buffered data, unflushed writes, and the writer's packet
+    /// sequence carry over to the rebuilt stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ReuniteError`], holding both halves, if they did not come
+    /// from the same stream.
+    // Mirrors `tokio::io::ReadHalf::unsplit`; the halves are handed back by value
+    #[allow(clippy::result_large_err)]
+    pub fn reunite(
+        mut self,
+        mut writer: PacketWriter<WriteHalf<T>>,
+    ) -> Result<PacketStream<T>, ReuniteError<T>> {
+        if !self.get_ref().is_pair_of(writer.get_ref()) {
+            return Err(ReuniteError(self, writer));
+        }
+
+        // Packets decoded at split time but not yet consumed go back in front
+        let mut read_buf = BytesMut::new();
+        for packet in self.buffered.drain(..) {
+            packet.header.encode(&mut read_buf);
+            read_buf.extend_from_slice(&packet.payload);
+        }
+        read_buf.extend_from_slice(self.read_buffer());
+        let write_buf = std::mem::take(writer.inner.write_buffer_mut());
+        let mut codec = writer.codec().clone();
+        codec.set_max_packet_size(self.codec().max_packet_size());
+
+        let io = self.inner.into_inner().unsplit(writer.inner.into_inner());
+        let mut parts = FramedParts::<T, TdsCodec>::new::<Packet>(io, codec);
+        parts.read_buf = read_buf;
+        parts.write_buf = write_buf;
+
+        let mut inner = Framed::from_parts(parts);
+        inner.set_backpressure_boundary(WRITE_COALESCE_LIMIT);
+        Ok(PacketStream { inner })
+    }
+}
+
 impl<T> Stream for PacketReader<T>
 where
     T: AsyncRead + Unpin,
@@ -201,7 +331,11 @@ where
     type Item = Result<Packet, CodecError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.project().inner.poll_next(cx)
+        let this = self.project();
+        if let Some(packet) = this.buffered.pop_front() {
+            return Poll::Ready(Some(Ok(packet)));
+        }
+        this.inner.poll_next(cx)
     }
 }
 
@@ -233,16 +367,14 @@ where
 {
     /// Create a new packet writer over the given transport.
     pub fn new(transport: T) -> Self {
-        Self {
-            inner: FramedWrite::new(transport, TdsCodec::new()),
-        }
+        Self::with_codec(transport, TdsCodec::new())
     }
 
     /// Create a new packet writer with a custom codec.
     pub fn with_codec(transport: T, codec: TdsCodec) -> Self {
-        Self {
-            inner: FramedWrite::new(transport, codec),
-        }
+        let mut inner = FramedWrite::new(transport, codec);
+        inner.set_backpressure_boundary(WRITE_COALESCE_LIMIT);
+        Self { inner }
     }
 
     /// Get a reference to the underlying transport.
@@ -266,6 +398,33 @@ where
     }
 }
 
+impl<T> PacketWriter<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Send several packets, e.g. the packets of one message, and flush them.
+    ///
+    /// See [`PacketStream::send_packets`].
+    pub async fn send_packets(&mut self, packets: Vec<Packet>) -> Result<(), CodecError> {
+        // Anything already buffered must go out first to keep packet order
+        SinkExt::flush(self).await?;
+
+        if !self.get_ref().is_write_vectored() {
+            for packet in packets {
+                self.feed(packet).await?;
+            }
+            return SinkExt::flush(self).await;
+        }
+
+        let headers = encode_headers(self.codec_mut(), &packets)?;
+        write_all_vectored(self.get_mut(), &headers, &packets).await?;
+        for packet in packets {
+            self.codec_mut().recycle(packet.payload);
+        }
+        Ok(())
+    }
+}
+
 impl<T> Sink<Packet> for PacketWriter<T>
 where
     T: AsyncWrite + Unpin,
@@ -299,3 +458,289 @@ where
             .finish()
     }
 }
+
+/// Build the wire headers for `packets`, assigning packet IDs in order.
+fn encode_headers(
+    codec: &mut TdsCodec,
+    packets: &[Packet],
+) -> Result<Vec<[u8; PACKET_HEADER_SIZE]>, CodecError> {
+    packets
+        .iter()
+        .map(|packet| {
+            let header = codec.prepare_header(packet)?;
+            let mut buf = [0u8; PACKET_HEADER_SIZE];
+            header.encode(&mut &mut buf[..]);
+            codec.inspect(Direction::Sent, &header, &[&buf, &packet.payload]);
+            Ok(buf)
+        })
+        .collect()
+}
+
+/// Write interleaved headers and payloads with vectored writes, then flush.
+async fn write_all_vectored<W>(
+    io: &mut W,
+    headers: &[[u8; PACKET_HEADER_SIZE]],
+    packets: &[Packet],
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut slices: Vec<IoSlice<'_>> = headers
+        .iter()
+        .zip(packets)
+        .flat_map(|(header, packet)| [IoSlice::new(header), IoSlice::new(&packet.payload)])
+        .filter(|slice| !slice.is_empty())
+        .collect();
+    let mut remaining = &mut slices[..];
+
+    while !remaining.is_empty() {
+        let written = io.write_vectored(remaining).await?;
+        if written == 0 {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        IoSlice::advance_slices(&mut remaining, written);
+    }
+
+    io.flush().await
+}
+
+/// Error returned by [`PacketReader::reunite`] when the halves came from
+/// different streams.
+///
+/// Both halves are handed back unchanged.
+pub struct ReuniteError<T>(
+    pub PacketReader<ReadHalf<T>>,
+    pub PacketWriter<WriteHalf<T>>,
+);
+
+impl<T> std::fmt::Debug for ReuniteError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ReuniteError").finish_non_exhaustive()
+    }
+}
+
+impl<T> std::fmt::Display for ReuniteError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same packet stream")
+    }
+}
+
+impl<T> std::error::Error for ReuniteError<T> {}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tds_protocol::packet::{PacketHeader, PacketStatus, PacketType};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn packet(packet_type: PacketType, payload: &[u8]) -> Packet {
+        let header = PacketHeader::new(packet_type, PacketStatus::END_OF_MESSAGE, 0);
+        Packet::new(header, BytesMut::from(payload))
+    }
+
+    #[tokio::test]
+    async fn test_split_send_while_reading() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let stream = PacketStream::new(client);
+        let (mut reader, mut writer) = stream.split();
+
+        // Start reading before the server has replied
+        let read = tokio::spawn(async move {
+            let packet = reader.next().await.unwrap().unwrap();
+            (reader, packet)
+        });
+
+        writer
+            .send(packet(PacketType::Attention, &[]))
+            .await
+            .unwrap();
+
+        let mut header = [0u8; 8];
+        server.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], PacketType::Attention as u8);
+
+        // TabularResult: DONE with ATTN
+        let mut reply = BytesMut::new();
+        let done = [0xFD, 0x20, 0x00, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0];
+        PacketHeader::new(
+            PacketType::TabularResult,
+            PacketStatus::END_OF_MESSAGE,
+            (8 + done.len()) as u16,
+        )
+        .encode(&mut reply);
+        reply.extend_from_slice(&done);
+        server.write_all(&reply).await.unwrap();
+
+        let (reader, response) = read.await.unwrap();
+        assert_eq!(response.header.packet_type, PacketType::TabularResult);
+        assert_eq!(&response.payload[..], &done);
+
+        let mut stream = reader.reunite(writer).unwrap();
+        stream
+            .send(packet(PacketType::SqlBatch, b"test"))
+            .await
+            .unwrap();
+        server.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], PacketType::SqlBatch as u8);
+    }
+
+    #[tokio::test]
+    async fn test_split_keeps_buffered_data() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        // Packets arrive together; reading the first buffers the rest
+        let mut data = BytesMut::new();
+        for payload in [b"one", b"two", b"six"] {
+            PacketHeader::new(PacketType::TabularResult, PacketStatus::END_OF_MESSAGE, 11)
+                .encode(&mut data);
+            data.extend_from_slice(payload);
+        }
+        server.write_all(&data).await.unwrap();
+
+        let mut stream = PacketStream::new(client);
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(&first.payload[..], b"one");
+
+        let (mut reader, writer) = stream.split();
+        let second = reader.next().await.unwrap().unwrap();
+        assert_eq!(&second.payload[..], b"two");
+
+        let mut stream = reader.reunite(writer).unwrap();
+        let third = stream.next().await.unwrap().unwrap();
+        assert_eq!(&third.payload[..], b"six");
+    }
+
+    /// Transport that records each write call, accepting at most `limit`
+    /// bytes per call.
+    struct RecordingWriter {
+        data: Vec<u8>,
+        writes: usize,
+        limit: usize,
+        vectored: bool,
+    }
+
+    impl RecordingWriter {
+        fn new(limit: usize, vectored: bool) -> Self {
+            Self {
+                data: Vec::new(),
+                writes: 0,
+                limit,
+                vectored,
+            }
+        }
+    }
+
+    impl AsyncWrite for RecordingWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let n = buf.len().min(self.limit);
+            self.data.extend_from_slice(&buf[..n]);
+            self.writes += 1;
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<std::io::Result<usize>> {
+            let mut n = 0;
+            for buf in bufs {
+                let take = buf.len().min(self.limit - n);
+                self.data.extend_from_slice(&buf[..take]);
+                n += take;
+                if n == self.limit {
+                    break;
+                }
+            }
+            self.writes += 1;
+            Poll::Ready(Ok(n))
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.vectored
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncRead for RecordingWriter {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn message_packets() -> Vec<Packet> {
+        (0..3u8)
+            .map(|i| {
+                let status = if i == 2 {
+                    PacketStatus::END_OF_MESSAGE
+                } else {
+                    PacketStatus::NORMAL
+                };
+                let header = PacketHeader::new(PacketType::SqlBatch, status, 0);
+                Packet::new(header, BytesMut::from(&[i; 4000][..]))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_send_packets_vectored() {
+        let mut expected = PacketWriter::new(RecordingWriter::new(usize::MAX, false));
+        for packet in message_packets() {
+            expected.send(packet).await.unwrap();
+        }
+        assert_eq!(expected.get_ref().writes, 3);
+
+        // One call covers the whole message
+        let mut writer = PacketWriter::new(RecordingWriter::new(usize::MAX, true));
+        writer.send_packets(message_packets()).await.unwrap();
+        assert_eq!(writer.get_ref().writes, 1);
+        assert_eq!(writer.get_ref().data, expected.get_ref().data);
+        assert_eq!(writer.codec().buffer_pool().len(), 3);
+
+        // Partial writes resume where they stopped
+        let mut writer = PacketWriter::new(RecordingWriter::new(5000, true));
+        writer.send_packets(message_packets()).await.unwrap();
+        assert_eq!(writer.get_ref().writes, 3);
+        assert_eq!(writer.get_ref().data, expected.get_ref().data);
+    }
+
+    #[tokio::test]
+    async fn test_send_packets_coalesced() {
+        let mut stream = PacketStream::new(RecordingWriter::new(usize::MAX, false));
+        stream.send_packets(message_packets()).await.unwrap();
+        assert_eq!(stream.get_ref().writes, 1);
+        assert_eq!(stream.get_ref().data.len(), 3 * 4008);
+        assert_eq!(stream.get_ref().data[8 + 4000 + 6], 2);
+    }
+
+    #[tokio::test]
+    async fn test_reunite_mismatched_halves() {
+        let (a, _a_peer) = tokio::io::duplex(64);
+        let (b, _b_peer) = tokio::io::duplex(64);
+        let (reader_a, writer_a) = PacketStream::new(a).split();
+        let (reader_b, writer_b) = PacketStream::new(b).split();
+
+        let err = reader_a.reunite(writer_b).unwrap_err();
+        let ReuniteError(reader_a, writer_b) = err;
+
+        assert!(reader_a.reunite(writer_a).is_ok());
+        assert!(reader_b.reunite(writer_b).is_ok());
+    }
+}
@@ -11,7 +11,11 @@
 //! - Packet reassembly across TCP segments
 //! - Message reassembly from multiple packets
 //! - IO splitting for cancellation safety (ADR-005)
+//! - Payload buffer reuse via a bounded per-codec [`BufferPool`]
+//! - Opt-in packet header tracing and pcap capture for protocol debugging
 //! - Integration with tokio-util's codec framework
+//! - No dependency on the tokio runtime; `futures-io` transports (async-std,
+//!   smol) are supported with the `futures-io` feature
 //!
 //! ## Architecture
 //!
@@ -42,14 +46,20 @@
 #![warn(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod buffer_pool;
+pub mod capture;
 pub mod connection;
 pub mod error;
 pub mod framed;
 pub mod message;
 pub mod packet_codec;
+pub mod transport;
 
+pub use buffer_pool::{BufferPool, DEFAULT_POOL_BUFFERS};
+pub use capture::{Direction, PacketCapture};
 pub use connection::{CancelHandle, Connection};
 pub use error::CodecError;
-pub use framed::{PacketReader, PacketStream, PacketWriter};
+pub use framed::{PacketReader, PacketStream, PacketWriter, ReuniteError};
 pub use message::{Message, MessageAssembler};
 pub use packet_codec::{Packet, TdsCodec};
+pub use transport::AsyncTransport;
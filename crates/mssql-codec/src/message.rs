@@ -83,6 +83,14 @@ impl MessageAssembler {
     /// Returns `Some(Message)` if this packet completes a message,
     /// `None` if more packets are needed.
     pub fn push(&mut self, packet: Packet) -> Option<Message> {
+        self.push_ref(&packet)
+    }
+
+    /// Push a borrowed packet into the assembler.
+    ///
+    /// The payload is copied, so the caller can hand the packet's buffer back
+    /// to the codec with [`TdsCodec::recycle`](crate::TdsCodec::recycle).
+    pub fn push_ref(&mut self, packet: &Packet) -> Option<Message> {
         // Record the packet type from the first packet
         if self.packet_type.is_none() {
             self.packet_type = Some(packet.header.packet_type);
@@ -1,9 +1,13 @@
 //! TDS packet codec implementation.
 
 use bytes::{BufMut, BytesMut};
-use tds_protocol::packet::{MAX_PACKET_SIZE, PACKET_HEADER_SIZE, PacketHeader};
+use tds_protocol::packet::{
+    MAX_PACKET_SIZE, MIN_PACKET_SIZE, PACKET_HEADER_SIZE, PacketHeader, PacketStatus,
+};
 use tokio_util::codec::{Decoder, Encoder};
 
+use crate::buffer_pool::BufferPool;
+use crate::capture::{Direction, PacketCapture};
 use crate::error::CodecError;
 
 /// A TDS packet with header and payload.
@@ -33,17 +37,70 @@ impl Packet {
     pub fn is_end_of_message(&self) -> bool {
         self.header.is_end_of_message()
     }
+
+    /// Get the packet's status flags.
+    #[must_use]
+    pub fn status(&self) -> PacketStatus {
+        self.header.status
+    }
+
+    /// Check if the receiver should discard the message (IGNORE).
+    ///
+    /// A sender sets this with END_OF_MESSAGE to abandon a message it has
+    /// only partly sent.
+    #[must_use]
+    pub fn is_ignored(&self) -> bool {
+        self.header.status.contains(PacketStatus::IGNORE_EVENT)
+    }
+
+    /// Check if the server resets the session before running the request (RESETCONNECTION).
+    #[must_use]
+    pub fn resets_connection(&self) -> bool {
+        self.header.status.contains(PacketStatus::RESET_CONNECTION)
+    }
+
+    /// Check if the server resets the session but keeps its transaction (RESETCONNECTIONSKIPTRAN).
+    #[must_use]
+    pub fn resets_connection_keep_transaction(&self) -> bool {
+        self.header
+            .status
+            .contains(PacketStatus::RESET_CONNECTION_KEEP_TRANSACTION)
+    }
+
+    /// Add status flags to the packet.
+    ///
+    /// Reset flags must only be set on the first packet of a request.
+    #[must_use]
+    pub fn with_status(mut self, status: PacketStatus) -> Self {
+        self.header.status |= status;
+        self
+    }
 }
 
 /// TDS packet codec for tokio-util framing.
 ///
 /// This codec handles the low-level encoding and decoding of TDS packets
 /// over a byte stream.
+///
+/// Decoded payloads are taken from the codec's [`BufferPool`], and the
+/// payloads of encoded packets are returned to it. Hand payloads that are no
+/// longer needed back with [`recycle`](Self::recycle) to reuse them.
+///
+/// For protocol debugging, [`with_packet_tracing`](Self::with_packet_tracing)
+/// logs every packet header and [`with_capture`](Self::with_capture) records
+/// every packet to a pcap file.
+#[derive(Debug, Clone)]
 pub struct TdsCodec {
     /// Maximum packet size to accept.
     max_packet_size: usize,
     /// Current packet sequence number for encoding.
     packet_id: u8,
+    /// Free list of payload buffers.
+    pool: BufferPool,
+    /// Whether to log the full header of every packet.
+    trace_packets: bool,
+    /// Capture file that packets are recorded to.
+    capture: Option<PacketCapture>,
 }
 
 impl TdsCodec {
@@ -53,16 +110,94 @@ impl TdsCodec {
         Self {
             max_packet_size: MAX_PACKET_SIZE,
             packet_id: 1,
+            pool: BufferPool::default(),
+            trace_packets: false,
+            capture: None,
         }
     }
 
+    /// Create a new TDS codec with a custom payload buffer pool.
+    ///
+    /// Use [`BufferPool::disabled`] to allocate a fresh buffer per packet.
+    #[must_use]
+    pub fn with_buffer_pool(mut self, pool: BufferPool) -> Self {
+        self.pool = pool;
+        self
+    }
+
     /// Create a new TDS codec with a custom maximum packet size.
     #[must_use]
     pub fn with_max_packet_size(mut self, size: usize) -> Self {
-        self.max_packet_size = size.min(MAX_PACKET_SIZE);
+        self.set_max_packet_size(size);
         self
     }
 
+    /// Log the header of every packet sent or received (type, status,
+    /// length, SPID, packet ID) at `trace` level.
+    #[must_use]
+    pub fn with_packet_tracing(mut self, enabled: bool) -> Self {
+        self.trace_packets = enabled;
+        self
+    }
+
+    /// Record every packet sent or received to a pcap capture.
+    #[must_use]
+    pub fn with_capture(mut self, capture: PacketCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Check if packet header tracing is enabled.
+    #[must_use]
+    pub fn is_packet_tracing(&self) -> bool {
+        self.trace_packets
+    }
+
+    /// Get the packet capture, if any.
+    #[must_use]
+    pub fn capture(&self) -> Option<&PacketCapture> {
+        self.capture.as_ref()
+    }
+
+    /// Trace and capture a packet, if enabled.
+    ///
+    /// `frame` is the complete packet, possibly split across several slices.
+    pub(crate) fn inspect(&mut self, direction: Direction, header: &PacketHeader, frame: &[&[u8]]) {
+        if self.trace_packets {
+            tracing::trace!(
+                direction = direction.as_str(),
+                packet_type = ?header.packet_type,
+                status = header.status.bits(),
+                length = header.length,
+                spid = header.spid,
+                packet_id = header.packet_id,
+                "TDS packet"
+            );
+        }
+
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.record_parts(direction, frame) {
+                // Stop capturing rather than warn on every packet
+                tracing::warn!(error = %e, "packet capture failed, disabling it");
+                self.capture = None;
+            }
+        }
+    }
+
+    /// Get the maximum packet size.
+    #[must_use]
+    pub fn max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+
+    /// Change the maximum packet size, e.g. after the server renegotiates it
+    /// with a PacketSize ENVCHANGE.
+    ///
+    /// The size is clamped to the range TDS allows.
+    pub fn set_max_packet_size(&mut self, size: usize) {
+        self.max_packet_size = size.clamp(MIN_PACKET_SIZE, MAX_PACKET_SIZE);
+    }
+
     /// Get the next packet ID and increment the counter.
     fn next_packet_id(&mut self) -> u8 {
         let id = self.packet_id;
@@ -77,6 +212,40 @@ impl TdsCodec {
     pub fn reset_packet_id(&mut self) {
         self.packet_id = 1;
     }
+
+    /// Validate a packet for sending and build its wire header, assigning the
+    /// next packet ID.
+    pub(crate) fn prepare_header(&mut self, item: &Packet) -> Result<PacketHeader, CodecError> {
+        let total_length = item.total_size();
+
+        if total_length > self.max_packet_size {
+            return Err(CodecError::PacketTooLarge {
+                size: total_length,
+                max: self.max_packet_size,
+            });
+        }
+
+        let mut header = item.header;
+        header.length = total_length as u16;
+        header.packet_id = self.next_packet_id();
+        Ok(header)
+    }
+
+    /// Get a reference to the payload buffer pool.
+    #[must_use]
+    pub fn buffer_pool(&self) -> &BufferPool {
+        &self.pool
+    }
+
+    /// Take an empty payload buffer with room for at least `capacity` bytes.
+    pub fn payload_buffer(&mut self, capacity: usize) -> BytesMut {
+        self.pool.get(capacity)
+    }
+
+    /// Return a payload buffer to the pool for reuse.
+    pub fn recycle(&mut self, payload: BytesMut) {
+        self.pool.put(payload);
+    }
 }
 
 impl Default for TdsCodec {
@@ -122,9 +291,12 @@ impl Decoder for TdsCodec {
 
         // Parse the header
         let header = PacketHeader::decode(&mut cursor)?;
+        self.inspect(Direction::Received, &header, &[&packet_bytes]);
 
-        // Extract payload
-        let payload = BytesMut::from(&packet_bytes[PACKET_HEADER_SIZE..]);
+        // Copy the payload into a pooled buffer so the read buffer's
+        // allocation can be reused for the next read
+        let mut payload = self.pool.get(length - PACKET_HEADER_SIZE);
+        payload.extend_from_slice(&packet_bytes[PACKET_HEADER_SIZE..]);
 
         tracing::trace!(
             packet_type = ?header.packet_type,
@@ -141,28 +313,21 @@ impl Encoder<Packet> for TdsCodec {
     type Error = CodecError;
 
     fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let total_length = PACKET_HEADER_SIZE + item.payload.len();
-
-        if total_length > self.max_packet_size {
-            return Err(CodecError::PacketTooLarge {
-                size: total_length,
-                max: self.max_packet_size,
-            });
-        }
+        // Create header with correct length and packet ID
+        let header = self.prepare_header(&item)?;
+        let total_length = usize::from(header.length);
 
         // Reserve space
         dst.reserve(total_length);
 
-        // Create header with correct length and packet ID
-        let mut header = item.header;
-        header.length = total_length as u16;
-        header.packet_id = self.next_packet_id();
-
         // Encode header
+        let start = dst.len();
         header.encode(dst);
 
         // Encode payload
         dst.put_slice(&item.payload);
+        self.inspect(Direction::Sent, &header, &[&dst[start..]]);
+        self.pool.put(item.payload);
 
         tracing::trace!(
             packet_type = ?header.packet_type,
@@ -179,7 +344,35 @@ impl Encoder<Packet> for TdsCodec {
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
-    use tds_protocol::packet::{PacketStatus, PacketType};
+    use tds_protocol::packet::PacketType;
+
+    #[test]
+    fn test_packet_status_flags() {
+        let header = PacketHeader::new(PacketType::SqlBatch, PacketStatus::NORMAL, 0);
+        let packet = Packet::new(header, BytesMut::new());
+        assert!(!packet.is_end_of_message());
+        assert!(!packet.resets_connection());
+
+        let packet =
+            packet.with_status(PacketStatus::END_OF_MESSAGE | PacketStatus::RESET_CONNECTION);
+        assert!(packet.is_end_of_message());
+        assert!(packet.resets_connection());
+        assert!(!packet.resets_connection_keep_transaction());
+        assert!(!packet.is_ignored());
+
+        // Flags survive encoding
+        let mut codec = TdsCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(packet, &mut buf).unwrap();
+        let decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            decoded.status(),
+            PacketStatus::END_OF_MESSAGE | PacketStatus::RESET_CONNECTION
+        );
+
+        let ignored = decoded.with_status(PacketStatus::IGNORE_EVENT);
+        assert!(ignored.is_ignored());
+    }
 
     #[test]
     fn test_decode_packet() {
@@ -233,4 +426,82 @@ mod tests {
         let result = codec.decode(&mut data).unwrap();
         assert!(result.is_none()); // Should return None for incomplete
     }
+
+    #[test]
+    fn test_set_max_packet_size() {
+        let mut codec = TdsCodec::new().with_max_packet_size(1024);
+        assert_eq!(codec.max_packet_size(), 1024);
+
+        // A 1032-byte packet exceeds the limit
+        let mut data = BytesMut::new();
+        data.put_u8(PacketType::TabularResult as u8);
+        data.put_u8(PacketStatus::END_OF_MESSAGE.bits());
+        data.put_u16(1032);
+        data.put_u16(0);
+        data.put_u8(1);
+        data.put_u8(0);
+        data.put_bytes(0, 1024);
+        assert!(codec.decode(&mut data.clone()).is_err());
+
+        codec.set_max_packet_size(8000);
+        assert!(codec.decode(&mut data).unwrap().is_some());
+
+        codec.set_max_packet_size(1);
+        assert_eq!(codec.max_packet_size(), MIN_PACKET_SIZE);
+        codec.set_max_packet_size(100_000);
+        assert_eq!(codec.max_packet_size(), MAX_PACKET_SIZE);
+    }
+
+    #[test]
+    fn test_capture_both_directions() {
+        let path = std::env::temp_dir().join(format!("tds-capture-{}.pcap", std::process::id()));
+        let capture = PacketCapture::create(&path).unwrap();
+        let mut codec = TdsCodec::new()
+            .with_packet_tracing(true)
+            .with_capture(capture.clone());
+        assert!(codec.is_packet_tracing());
+
+        let header = PacketHeader::new(PacketType::SqlBatch, PacketStatus::END_OF_MESSAGE, 0);
+        let mut dst = BytesMut::new();
+        codec
+            .encode(Packet::new(header, BytesMut::from(&b"test"[..])), &mut dst)
+            .unwrap();
+        codec.decode(&mut dst).unwrap().unwrap();
+        capture.flush().unwrap();
+
+        // pcap header, then two records of 16 + 40 + 12 bytes
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(data.len(), 24 + 2 * (16 + 40 + 12));
+        assert_eq!(data[24 + 16 + 40], PacketType::SqlBatch as u8);
+    }
+
+    #[test]
+    fn test_payload_buffers_recycled() {
+        let mut codec = TdsCodec::new();
+
+        // Encoding returns the payload to the pool
+        let header = PacketHeader::new(PacketType::SqlBatch, PacketStatus::END_OF_MESSAGE, 0);
+        let mut payload = codec.payload_buffer(64);
+        payload.put_slice(b"test");
+        let ptr = payload.as_ptr();
+        let mut dst = BytesMut::new();
+        codec
+            .encode(Packet::new(header, payload), &mut dst)
+            .unwrap();
+        assert_eq!(codec.buffer_pool().len(), 1);
+
+        // ...and decoding takes it back out
+        let packet = codec.decode(&mut dst).unwrap().unwrap();
+        assert_eq!(&packet.payload[..], b"test");
+        assert_eq!(packet.payload.as_ptr(), ptr);
+        assert!(codec.buffer_pool().is_empty());
+
+        codec.recycle(packet.payload);
+        assert_eq!(codec.buffer_pool().len(), 1);
+
+        let mut codec = TdsCodec::new().with_buffer_pool(BufferPool::disabled());
+        codec.recycle(BytesMut::with_capacity(64));
+        assert!(codec.buffer_pool().is_empty());
+    }
 }
@@ -0,0 +1,74 @@
+//! Transport abstraction.
+//!
+//! The codec reads and writes through tokio's `AsyncRead`/`AsyncWrite`
+//! traits but uses nothing that needs the tokio runtime: there is no reactor,
+//! timer, or task spawning, and the synchronization in [`Connection`] works
+//! on any executor. [`AsyncTransport`] names the bound a transport must meet.
+//!
+//! Transports implementing the `futures-io` traits instead, such as async-std
+//! and smol sockets, are adapted with `compat` when the `futures-io` feature
+//! is enabled:
+//!
+//! ```rust,ignore
+//! use mssql_codec::Connection;
+//!
+//! let stream = smol::net::TcpStream::connect("db:1433").await?;
+//! let conn = Connection::from_futures_io(stream);
+//! ```
+//!
+//! [`Connection`]: crate::Connection
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[cfg(feature = "futures-io")]
+pub use tokio_util::compat::Compat;
+
+/// A byte stream the codec can run over.
+///
+/// Implemented for every tokio `AsyncRead + AsyncWrite` type that can be
+/// moved between tasks.
+pub trait AsyncTransport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> AsyncTransport for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Adapt a `futures-io` transport to the tokio I/O traits.
+#[cfg(feature = "futures-io")]
+pub fn compat<T>(transport: T) -> Compat<T>
+where
+    T: futures_util::io::AsyncRead + futures_util::io::AsyncWrite,
+{
+    use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+    transport.compat()
+}
+
+#[cfg(all(test, feature = "futures-io"))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use bytes::BytesMut;
+    use futures_util::io::Cursor;
+    use futures_util::{SinkExt, StreamExt};
+    use tds_protocol::packet::{PacketHeader, PacketStatus, PacketType};
+
+    use crate::{Packet, PacketStream};
+
+    #[test]
+    fn test_packet_stream_over_futures_io() {
+        // Driven by a non-tokio executor
+        futures_executor::block_on(async {
+            let mut stream = PacketStream::from_futures_io(Cursor::new(Vec::new()));
+            let header = PacketHeader::new(PacketType::SqlBatch, PacketStatus::END_OF_MESSAGE, 0);
+            stream
+                .send(Packet::new(header, BytesMut::from(&b"test"[..])))
+                .await
+                .unwrap();
+            let written = stream.into_inner().into_inner().into_inner();
+            assert_eq!(written.len(), 12);
+
+            let mut stream = PacketStream::from_futures_io(Cursor::new(written));
+            let packet = stream.next().await.unwrap().unwrap();
+            assert_eq!(packet.header.packet_type, PacketType::SqlBatch);
+            assert_eq!(&packet.payload[..], b"test");
+        });
+    }
+}
@@ -13,6 +13,9 @@
 //! - `#[derive(FromRow)]` - Convert database rows to structs
 //! - `#[derive(ToParams)]` - Convert structs to query parameters
 //! - `#[derive(Tvp)]` - Table-valued parameter support
+//! - `#[derive(SqlEnum)]` - Store fieldless enums as strings or integers
+//! - `embed_migrations!` - Embed a directory of SQL migrations (requires the
+//!   `migrations` feature of `mssql-client`)
 //!
 //! ## Example
 //!
@@ -41,7 +44,9 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, Type, parse_macro_input};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, Type, parse_macro_input,
+};
 
 /// Field configuration extracted from attributes.
 #[derive(Default)]
@@ -54,6 +59,12 @@ struct FieldConfig {
     default: bool,
     /// Flatten nested struct.
     flatten: bool,
+    /// Column prefix of a flattened struct.
+    prefix: Option<String>,
+    /// SQL-side type to decode before converting with `TryFrom`.
+    try_from: Option<LitStr>,
+    /// Bind to the column at this ordinal instead of by name.
+    index: Option<usize>,
 }
 
 /// Struct-level configuration extracted from attributes.
@@ -63,6 +74,8 @@ struct StructConfig {
     type_name: Option<String>,
     /// Rename all fields using a casing convention.
     rename_all: Option<String>,
+    /// Enum representation (`"string"` or `"int"`).
+    repr: Option<String>,
 }
 
 /// Parse mssql attributes from a list of attributes.
@@ -90,6 +103,30 @@ fn parse_field_config(attrs: &[Attribute]) -> FieldConfig {
                 config.default = true;
             } else if meta.path.is_ident("flatten") {
                 config.flatten = true;
+            } else if meta.path.is_ident("index") {
+                let value: Expr = meta.value()?.parse()?;
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Int(lit), ..
+                }) = value
+                {
+                    config.index = Some(lit.base10_parse()?);
+                }
+            } else if meta.path.is_ident("try_from") {
+                let value: Expr = meta.value()?.parse()?;
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) = value
+                {
+                    config.try_from = Some(lit);
+                }
+            } else if meta.path.is_ident("prefix") {
+                let value: Expr = meta.value()?.parse()?;
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) = value
+                {
+                    config.prefix = Some(lit.value());
+                }
             }
             Ok(())
         });
@@ -98,8 +135,16 @@ fn parse_field_config(attrs: &[Attribute]) -> FieldConfig {
     config
 }
 
+/// Casing conventions accepted by `rename_all`.
+const RENAME_ALL_RULES: &[&str] = &[
+    "snake_case",
+    "camelCase",
+    "PascalCase",
+    "SCREAMING_SNAKE_CASE",
+];
+
 /// Parse struct-level mssql attributes.
-fn parse_struct_config(attrs: &[Attribute]) -> StructConfig {
+fn parse_struct_config(attrs: &[Attribute]) -> syn::Result<StructConfig> {
     let mut config = StructConfig::default();
 
     for attr in attrs {
@@ -107,6 +152,7 @@ fn parse_struct_config(attrs: &[Attribute]) -> StructConfig {
             continue;
         }
 
+        let mut rename_all_error = None;
         let _ = attr.parse_nested_meta(|meta| {
             if meta.path.is_ident("type_name") {
                 let value: Expr = meta.value()?.parse()?;
@@ -122,18 +168,42 @@ fn parse_struct_config(attrs: &[Attribute]) -> StructConfig {
                     lit: Lit::Str(lit), ..
                 }) = value
                 {
+                    if !RENAME_ALL_RULES.contains(&lit.value().as_str()) {
+                        rename_all_error = Some(syn::Error::new_spanned(
+                            &lit,
+                            format!(
+                                "unknown rename_all rule `{}`, expected one of: {}",
+                                lit.value(),
+                                RENAME_ALL_RULES.join(", ")
+                            ),
+                        ));
+                    }
                     config.rename_all = Some(lit.value());
                 }
+            } else if meta.path.is_ident("repr") {
+                let value: Expr = meta.value()?.parse()?;
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) = value
+                {
+                    config.repr = Some(lit.value());
+                }
             }
             Ok(())
         });
+        if let Some(err) = rename_all_error {
+            return Err(err);
+        }
     }
 
-    config
+    Ok(config)
 }
 
 /// Convert a field name to a column name based on rename_all setting.
+///
+/// Raw identifiers lose their `r#` prefix, so `r#type` maps to `type`.
 fn apply_rename_all(name: &str, rename_all: Option<&str>) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
     match rename_all {
         Some("snake_case") => to_snake_case(name),
         Some("camelCase") => to_camel_case(name),
@@ -143,11 +213,20 @@ fn apply_rename_all(name: &str, rename_all: Option<&str>) -> String {
     }
 }
 
+/// Convert to snake_case, keeping acronyms together (`HTTPServer` -> `http_server`).
 fn to_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
     let mut result = String::new();
-    for (i, c) in s.chars().enumerate() {
+    for (i, &c) in chars.iter().enumerate() {
         if c.is_uppercase() {
-            if i > 0 {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let word_start = prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+            if word_start {
                 result.push('_');
             }
             result.extend(c.to_lowercase());
@@ -205,13 +284,22 @@ fn to_screaming_snake_case(s: &str) -> String {
 /// ### Field Attributes
 ///
 /// - `#[mssql(rename = "column_name")]` - Map field to a different column name
+/// - `#[mssql(index = 0)]` - Bind to the column at this ordinal, for unnamed or
+///   duplicate column names
 /// - `#[mssql(skip)]` - Skip this field (must have a Default implementation)
 /// - `#[mssql(default)]` - Use Default if column is NULL or missing
+/// - `#[mssql(try_from = "i32")]` - Decode the column as the given type, then
+///   convert it with `TryFrom`. The conversion error must implement `Display`.
+///   Works for `Option` fields too.
 /// - `#[mssql(flatten)]` - Flatten a nested struct implementing FromRow
+/// - `#[mssql(flatten, prefix = "billing_")]` - Flatten a nested struct from the
+///   columns starting with the prefix, with the prefix removed. An
+///   `Option<T>` field is `None` when all of those columns are NULL.
 ///
 /// ### Struct Attributes
 ///
-/// - `#[mssql(rename_all = "snake_case")]` - Apply naming convention to all fields
+/// - `#[mssql(rename_all = "snake_case")]` - Apply naming convention to all
+///   fields (`snake_case`, `camelCase`, `PascalCase`, `SCREAMING_SNAKE_CASE`)
 ///
 /// ## Example
 ///
@@ -219,7 +307,8 @@ fn to_screaming_snake_case(s: &str) -> String {
 /// #[derive(FromRow)]
 /// #[mssql(rename_all = "PascalCase")]
 /// struct User {
-///     id: i32,
+///     #[mssql(try_from = "i32")]
+///     id: UserId,
 ///     #[mssql(rename = "UserName")]
 ///     name: String,
 ///     #[mssql(default)]
@@ -242,7 +331,7 @@ fn impl_from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let struct_config = parse_struct_config(&input.attrs);
+    let struct_config = parse_struct_config(&input.attrs)?;
 
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
@@ -277,40 +366,133 @@ fn impl_from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
             continue;
         }
 
+        if config.index.is_some() && (config.flatten || config.rename.is_some()) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[mssql(index)] cannot be combined with rename or flatten",
+            ));
+        }
+
         if config.flatten {
-            // Recursively call FromRow for nested structs
-            field_extractions.push(quote! {
-                #field_name: <#field_type as mssql_client::FromRow>::from_row(row)?
-            });
+            let Some(prefix) = config.prefix else {
+                if is_option_type(field_type) {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "flattened Option fields need #[mssql(prefix = \"...\")]",
+                    ));
+                }
+                // Recursively call FromRow for nested structs
+                field_extractions.push(quote! {
+                    #field_name: <#field_type as mssql_client::FromRow>::from_row(row)?
+                });
+                continue;
+            };
+
+            let nested = quote! {
+                row.strip_column_prefix(#prefix).map_err(mssql_client::Error::from)?
+            };
+            match option_inner_type(field_type) {
+                Some(inner) => field_extractions.push(quote! {
+                    #field_name: {
+                        let nested = #nested;
+                        if (0..nested.len()).all(|i| nested.is_null(i)) {
+                            ::std::option::Option::None
+                        } else {
+                            ::std::option::Option::Some(
+                                <#inner as mssql_client::FromRow>::from_row(&nested)?
+                            )
+                        }
+                    }
+                }),
+                None => field_extractions.push(quote! {
+                    #field_name: <#field_type as mssql_client::FromRow>::from_row(&#nested)?
+                }),
+            }
             continue;
         }
 
-        // Determine the column name
-        let column_name = config.rename.unwrap_or_else(|| {
-            apply_rename_all(&field_name.to_string(), struct_config.rename_all.as_deref())
-        });
+        // Look the column up by ordinal or by name
+        let (get, try_get, is_null, column) = match config.index {
+            Some(index) => (
+                quote!(get),
+                quote!(try_get),
+                quote!(is_null),
+                quote!(#index),
+            ),
+            None => {
+                let column_name = config.rename.unwrap_or_else(|| {
+                    apply_rename_all(&field_name.to_string(), struct_config.rename_all.as_deref())
+                });
+                (
+                    quote!(get_by_name),
+                    quote!(try_get_by_name),
+                    quote!(is_null_by_name),
+                    quote!(#column_name),
+                )
+            }
+        };
+
+        if let Some(source) = &config.try_from {
+            let source: Type = source.parse()?;
+            let target = option_inner_type(field_type).unwrap_or(field_type);
+            let target_name = quote!(#target).to_string();
+            let convert = quote! {
+                |raw: #source| <#target as ::std::convert::TryFrom<#source>>::try_from(raw)
+                    .map_err(|e| mssql_client::Error::from(mssql_types::TypeError::Conversion {
+                        target_type: #target_name,
+                        message: e.to_string(),
+                    }))
+            };
+
+            if is_option_type(field_type) {
+                field_extractions.push(quote! {
+                    #field_name: row.#try_get::<#source>(#column)
+                        .map(#convert)
+                        .transpose()?
+                });
+            } else if config.default {
+                field_extractions.push(quote! {
+                    #field_name: if row.#is_null(#column) {
+                        ::std::default::Default::default()
+                    } else {
+                        (#convert)(row.#get::<#source>(#column)
+                            .map_err(mssql_client::Error::from)?)?
+                    }
+                });
+            } else {
+                field_extractions.push(quote! {
+                    #field_name: (#convert)(row.#get::<#source>(#column)
+                        .map_err(mssql_client::Error::from)?)?
+                });
+            }
+            continue;
+        }
 
         if config.default {
             // Use try_get_by_name which returns Option, fallback to Default
             if is_option_type(field_type) {
                 field_extractions.push(quote! {
-                    #field_name: row.try_get_by_name(#column_name)
+                    #field_name: row.#try_get(#column)
                 });
             } else {
+                // Only a missing or NULL column falls back; conversion errors surface
                 field_extractions.push(quote! {
-                    #field_name: row.try_get_by_name(#column_name)
-                        .unwrap_or_else(::std::default::Default::default)
+                    #field_name: if row.#is_null(#column) {
+                        ::std::default::Default::default()
+                    } else {
+                        row.#get(#column).map_err(mssql_client::Error::from)?
+                    }
                 });
             }
         } else if is_option_type(field_type) {
             // Option types use try_get which handles NULL gracefully
             field_extractions.push(quote! {
-                #field_name: row.try_get_by_name(#column_name)
+                #field_name: row.#try_get(#column)
             });
         } else {
             // Required fields use get_by_name which returns Result
             field_extractions.push(quote! {
-                #field_name: row.get_by_name(#column_name)
+                #field_name: row.#get(#column)
                     .map_err(mssql_client::Error::from)?
             });
         }
@@ -337,6 +519,24 @@ fn is_option_type(ty: &Type) -> bool {
     false
 }
 
+/// Get `T` from an `Option<T>` type.
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Derive macro for implementing `ToParams` trait.
 ///
 /// This macro generates code to convert a struct into query parameters.
@@ -345,6 +545,8 @@ fn is_option_type(ty: &Type) -> bool {
 ///
 /// - `#[mssql(rename = "param_name")]` - Use a different parameter name
 /// - `#[mssql(skip)]` - Don't include this field as a parameter
+/// - `#[mssql(rename_all = "PascalCase")]` - Apply naming convention to all
+///   fields (`snake_case`, `camelCase`, `PascalCase`, `SCREAMING_SNAKE_CASE`)
 ///
 /// ## Example
 ///
@@ -383,7 +585,7 @@ fn impl_to_params(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let struct_config = parse_struct_config(&input.attrs);
+    let struct_config = parse_struct_config(&input.attrs)?;
 
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
@@ -496,7 +698,7 @@ fn impl_tvp(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let struct_config = parse_struct_config(&input.attrs);
+    let struct_config = parse_struct_config(&input.attrs)?;
 
     let type_name = struct_config.type_name.ok_or_else(|| {
         syn::Error::new_spanned(
@@ -609,6 +811,394 @@ fn infer_sql_type(ty: &Type) -> &'static str {
     "NVARCHAR(MAX)"
 }
 
+/// Derive macro for implementing `ToSql` and `FromSql` on fieldless enums.
+///
+/// By default each variant is stored as its name. With
+/// `#[mssql(repr = "int")]` it is stored as its discriminant instead, using
+/// the SQL type matching the enum's `#[repr]` (`INT` if there is none).
+///
+/// Reading a value that matches no variant fails with
+/// `TypeError::UnknownVariant`, which lists the accepted values. Trailing
+/// spaces are ignored when reading strings, so `CHAR` columns work.
+///
+/// ## Attributes
+///
+/// ### Enum Attributes
+///
+/// - `#[mssql(repr = "int")]` - Store the discriminant instead of the name
+/// - `#[mssql(rename_all = "snake_case")]` - Apply naming convention to all variants
+///
+/// ### Variant Attributes
+///
+/// - `#[mssql(rename = "value")]` - Store the variant as a different string
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// #[derive(SqlEnum)]
+/// #[mssql(rename_all = "SCREAMING_SNAKE_CASE")]
+/// enum Status {
+///     Active,
+///     #[mssql(rename = "ON_HOLD")]
+///     Paused,
+///     Closed,
+/// }
+///
+/// #[derive(SqlEnum)]
+/// #[mssql(repr = "int")]
+/// #[repr(u8)]
+/// enum Priority {
+///     Low = 1,
+///     High = 10,
+/// }
+/// ```
+#[proc_macro_derive(SqlEnum, attributes(mssql))]
+pub fn derive_sql_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match impl_sql_enum(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn impl_sql_enum(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let enum_config = parse_struct_config(&input.attrs)?;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "SqlEnum can only be derived for enums",
+            ));
+        }
+    };
+    if variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "SqlEnum cannot be derived for enums without variants",
+        ));
+    }
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "SqlEnum variants cannot have fields",
+            ));
+        }
+    }
+
+    let idents: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+
+    match enum_config.repr.as_deref() {
+        None | Some("string") => {
+            let mut values = Vec::with_capacity(variants.len());
+            for variant in variants {
+                let config = parse_field_config(&variant.attrs);
+                let value = config.rename.unwrap_or_else(|| {
+                    apply_rename_all(
+                        &variant.ident.to_string(),
+                        enum_config.rename_all.as_deref(),
+                    )
+                });
+                if values.contains(&value) {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        format!("SqlEnum value `{value}` is used by more than one variant"),
+                    ));
+                }
+                values.push(value);
+            }
+
+            Ok(quote! {
+                impl #impl_generics mssql_types::ToSql for #name #ty_generics #where_clause {
+                    fn to_sql(&self) -> ::std::result::Result<mssql_types::SqlValue, mssql_types::TypeError> {
+                        Ok(match self {
+                            #(Self::#idents => mssql_types::SqlValue::String(#values.to_owned()),)*
+                        })
+                    }
+
+                    fn sql_type(&self) -> &'static str {
+                        "NVARCHAR"
+                    }
+                }
+
+                impl #impl_generics mssql_types::FromSql for #name #ty_generics #where_clause {
+                    fn from_sql(value: &mssql_types::SqlValue) -> ::std::result::Result<Self, mssql_types::TypeError> {
+                        if value.is_null() {
+                            return Err(mssql_types::TypeError::UnexpectedNull);
+                        }
+                        let s = value.as_str().ok_or_else(|| mssql_types::TypeError::TypeMismatch {
+                            expected: "String",
+                            actual: value.type_name().to_string(),
+                        })?;
+                        match s.trim_end_matches(' ') {
+                            #(#values => Ok(Self::#idents),)*
+                            other => Err(mssql_types::TypeError::UnknownVariant {
+                                type_name: #type_name,
+                                value: other.to_string(),
+                                expected: &[#(#values),*],
+                            }),
+                        }
+                    }
+                }
+            })
+        }
+        Some("int") => {
+            let (int_type, sql_value, sql_type) = enum_int_repr(input)?;
+            let expected = int_variant_labels(variants);
+
+            Ok(quote! {
+                impl #impl_generics mssql_types::ToSql for #name #ty_generics #where_clause {
+                    fn to_sql(&self) -> ::std::result::Result<mssql_types::SqlValue, mssql_types::TypeError> {
+                        Ok(match self {
+                            #(Self::#idents => mssql_types::SqlValue::#sql_value(Self::#idents as #int_type),)*
+                        })
+                    }
+
+                    fn sql_type(&self) -> &'static str {
+                        #sql_type
+                    }
+                }
+
+                impl #impl_generics mssql_types::FromSql for #name #ty_generics #where_clause {
+                    fn from_sql(value: &mssql_types::SqlValue) -> ::std::result::Result<Self, mssql_types::TypeError> {
+                        if value.is_null() {
+                            return Err(mssql_types::TypeError::UnexpectedNull);
+                        }
+                        let v = value.as_i64().ok_or_else(|| mssql_types::TypeError::TypeMismatch {
+                            expected: #sql_type,
+                            actual: value.type_name().to_string(),
+                        })?;
+                        #(
+                            if v == Self::#idents as i64 {
+                                return Ok(Self::#idents);
+                            }
+                        )*
+                        Err(mssql_types::TypeError::UnknownVariant {
+                            type_name: #type_name,
+                            value: v.to_string(),
+                            expected: &[#(#expected),*],
+                        })
+                    }
+                }
+            })
+        }
+        Some(other) => Err(syn::Error::new_spanned(
+            input,
+            format!("unknown SqlEnum repr `{other}`, expected \"string\" or \"int\""),
+        )),
+    }
+}
+
+/// Pick the integer type, `SqlValue` variant and SQL type for an enum's `#[repr]`.
+fn enum_int_repr(input: &DeriveInput) -> syn::Result<(syn::Ident, syn::Ident, &'static str)> {
+    let mut repr = None;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+        let _ = attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                repr = Some(ident.clone());
+            }
+            Ok(())
+        });
+    }
+
+    let (int_type, sql_value, sql_type) = match repr.as_ref().map(|r| r.to_string()).as_deref() {
+        Some("u8") => ("u8", "TinyInt", "TINYINT"),
+        Some("i8" | "i16") => ("i16", "SmallInt", "SMALLINT"),
+        None | Some("u16" | "i32") => ("i32", "Int", "INT"),
+        Some("u32" | "i64") => ("i64", "BigInt", "BIGINT"),
+        Some(other) => {
+            return Err(syn::Error::new_spanned(
+                repr,
+                format!("SqlEnum does not support #[repr({other})]"),
+            ));
+        }
+    };
+    Ok((
+        syn::Ident::new(int_type, proc_macro2::Span::call_site()),
+        syn::Ident::new(sql_value, proc_macro2::Span::call_site()),
+        sql_type,
+    ))
+}
+
+/// Label each variant with its discriminant for error messages, e.g. `1 (Low)`.
+///
+/// Falls back to the bare variant name once a discriminant is not an integer
+/// literal, since its value is only known to the compiler.
+fn int_variant_labels(
+    variants: &syn::punctuated::Punctuated<syn::Variant, syn::Token![,]>,
+) -> Vec<String> {
+    let mut next = Some(0i128);
+    variants
+        .iter()
+        .map(|variant| {
+            if let Some((_, expr)) = &variant.discriminant {
+                next = int_literal(expr);
+            }
+            let label = match next {
+                Some(value) => format!("{value} ({})", variant.ident),
+                None => variant.ident.to_string(),
+            };
+            next = next.map(|value| value + 1);
+            label
+        })
+        .collect()
+}
+
+/// Evaluate an integer literal, optionally negated.
+fn int_literal(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse().ok(),
+        Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => int_literal(expr).map(|value| -value),
+        _ => None,
+    }
+}
+
+/// Embed a directory of SQL migration files.
+///
+/// The path is relative to the crate's `Cargo.toml`. Files are named
+/// `<version>_<name>.sql` or `<version>_<name>.up.sql`, with an optional
+/// `<version>_<name>.down.sql` holding the down migration. Other files are
+/// ignored. The contents are embedded with `include_str!`, so changes to
+/// existing files trigger a rebuild.
+///
+/// Expands to an `mssql_client::migrations::Migrator`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use mssql_derive::embed_migrations;
+///
+/// let migrator = embed_migrations!("migrations");
+/// migrator.run(&mut client).await?;
+/// ```
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    match impl_embed_migrations(&path) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// A migration file name split into its parts.
+#[derive(Debug, PartialEq, Eq)]
+struct MigrationFileName {
+    version: i64,
+    name: String,
+    down: bool,
+}
+
+/// Parse `<version>_<name>[.up|.down].sql`, returning `None` for non-SQL files.
+fn parse_migration_file_name(file_name: &str) -> Option<Result<MigrationFileName, String>> {
+    let stem = file_name.strip_suffix(".sql")?;
+    let (stem, down) = match stem.strip_suffix(".down") {
+        Some(stem) => (stem, true),
+        None => (stem.strip_suffix(".up").unwrap_or(stem), false),
+    };
+
+    let parsed = stem
+        .split_once('_')
+        .filter(|(_, name)| !name.is_empty())
+        .and_then(|(version, name)| {
+            let version = version.parse::<i64>().ok()?;
+            Some(MigrationFileName {
+                version,
+                name: name.to_string(),
+                down,
+            })
+        })
+        .ok_or_else(|| {
+            format!("migration file `{file_name}` must be named `<version>_<name>.sql`")
+        });
+    Some(parsed)
+}
+
+fn impl_embed_migrations(path: &LitStr) -> syn::Result<TokenStream2> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(path.span(), "CARGO_MANIFEST_DIR is not set"))?;
+    let dir = std::path::Path::new(&manifest_dir).join(path.value());
+
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("cannot read migrations directory {}: {e}", dir.display()),
+        )
+    })?;
+
+    // version -> (name, up path, down path)
+    let mut migrations: std::collections::BTreeMap<i64, (String, Option<String>, Option<String>)> =
+        std::collections::BTreeMap::new();
+
+    for entry in entries {
+        let entry = entry.map_err(|e| syn::Error::new(path.span(), e.to_string()))?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(parsed) = parse_migration_file_name(&file_name) else {
+            continue;
+        };
+        let parsed = parsed.map_err(|msg| syn::Error::new(path.span(), msg))?;
+        let file_path = entry.path().to_string_lossy().into_owned();
+
+        let slot = migrations
+            .entry(parsed.version)
+            .or_insert_with(|| (parsed.name.clone(), None, None));
+        if slot.0 != parsed.name {
+            return Err(syn::Error::new(
+                path.span(),
+                format!(
+                    "migration version {} is used by both `{}` and `{}`",
+                    parsed.version, slot.0, parsed.name
+                ),
+            ));
+        }
+        let target = if parsed.down {
+            &mut slot.2
+        } else {
+            &mut slot.1
+        };
+        if target.replace(file_path).is_some() {
+            return Err(syn::Error::new(
+                path.span(),
+                format!("duplicate migration file for version {}", parsed.version),
+            ));
+        }
+    }
+
+    let mut items = Vec::with_capacity(migrations.len());
+    for (version, (name, up, down)) in migrations {
+        let up = up.ok_or_else(|| {
+            syn::Error::new(
+                path.span(),
+                format!("migration {version} ({name}) has a down script but no up script"),
+            )
+        })?;
+        let with_down = down.map(|down| quote! { .with_down(include_str!(#down)) });
+        items.push(quote! {
+            ::mssql_client::migrations::Migration::new(#version, #name, include_str!(#up))
+                #with_down
+        });
+    }
+
+    Ok(quote! {
+        ::mssql_client::migrations::Migrator::new(::std::vec![#(#items),*])
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -620,6 +1210,48 @@ mod tests {
         assert_eq!(to_snake_case("user_name"), "user_name");
     }
 
+    #[test]
+    fn test_to_snake_case_acronyms() {
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+        assert_eq!(to_snake_case("UserID"), "user_id");
+        assert_eq!(to_snake_case("Address2Line"), "address2_line");
+    }
+
+    #[test]
+    fn test_apply_rename_all() {
+        assert_eq!(
+            apply_rename_all("created_at", Some("PascalCase")),
+            "CreatedAt"
+        );
+        assert_eq!(
+            apply_rename_all("created_at", Some("camelCase")),
+            "createdAt"
+        );
+        assert_eq!(apply_rename_all("r#type", Some("PascalCase")), "Type");
+        assert_eq!(apply_rename_all("r#type", None), "type");
+        assert_eq!(apply_rename_all("created_at", None), "created_at");
+    }
+
+    #[test]
+    fn test_parse_struct_config_rejects_unknown_rename_all() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[mssql(rename_all = "kebab-case")]
+            struct User { id: i32 }
+        };
+        let err = parse_struct_config(&input.attrs).err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("unknown rename_all rule `kebab-case`")
+        );
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[mssql(rename_all = "PascalCase")]
+            struct User { id: i32 }
+        };
+        let config = parse_struct_config(&input.attrs).unwrap();
+        assert_eq!(config.rename_all.as_deref(), Some("PascalCase"));
+    }
+
     #[test]
     fn test_to_camel_case() {
         assert_eq!(to_camel_case("user_name"), "userName");
@@ -637,4 +1269,68 @@ mod tests {
         assert_eq!(to_screaming_snake_case("userName"), "USER_NAME");
         assert_eq!(to_screaming_snake_case("user_name"), "USER_NAME");
     }
+
+    #[test]
+    fn test_option_inner_type() {
+        let ty: Type = syn::parse_quote!(Option<Address>);
+        let inner: Type = syn::parse_quote!(Address);
+        assert_eq!(option_inner_type(&ty), Some(&inner));
+
+        let ty: Type = syn::parse_quote!(Address);
+        assert_eq!(option_inner_type(&ty), None);
+    }
+
+    #[test]
+    fn test_int_variant_labels() {
+        let input: DeriveInput = syn::parse_quote! {
+            enum Priority { Low, Medium, High = 10, Urgent, Custom = BASE }
+        };
+        let Data::Enum(data) = &input.data else {
+            unreachable!()
+        };
+        assert_eq!(
+            int_variant_labels(&data.variants),
+            [
+                "0 (Low)",
+                "1 (Medium)",
+                "10 (High)",
+                "11 (Urgent)",
+                "Custom"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_migration_file_name() {
+        assert_eq!(
+            parse_migration_file_name("1_create_users.sql"),
+            Some(Ok(MigrationFileName {
+                version: 1,
+                name: "create_users".into(),
+                down: false,
+            }))
+        );
+        assert_eq!(
+            parse_migration_file_name("20240101_add_email.up.sql"),
+            Some(Ok(MigrationFileName {
+                version: 20240101,
+                name: "add_email".into(),
+                down: false,
+            }))
+        );
+        assert_eq!(
+            parse_migration_file_name("2_add_email.down.sql"),
+            Some(Ok(MigrationFileName {
+                version: 2,
+                name: "add_email".into(),
+                down: true,
+            }))
+        );
+        assert_eq!(parse_migration_file_name("README.md"), None);
+        assert!(matches!(
+            parse_migration_file_name("create_users.sql"),
+            Some(Err(_))
+        ));
+        assert!(matches!(parse_migration_file_name("3_.sql"), Some(Err(_))));
+    }
 }
@@ -29,12 +29,35 @@ pub struct PoolConfig {
     /// Maximum lifetime of a connection.
     pub max_lifetime: Duration,
 
+    /// Upper bound of the random amount by which each connection's
+    /// `max_lifetime` and `idle_timeout` are shortened.
+    ///
+    /// Connections opened together, e.g. during warm-up, would otherwise all
+    /// expire and be replaced at the same moment. Defaults to zero.
+    pub expiry_jitter: Duration,
+
     /// Whether to test connections on checkout.
     pub test_on_checkout: bool,
 
     /// Whether to test connections on checkin.
     pub test_on_checkin: bool,
 
+    /// Whether to pre-ping connections on checkout.
+    ///
+    /// When enabled, a lightweight ping replaces the full health check query
+    /// that `test_on_checkout` would otherwise run.
+    pub test_before_acquire: bool,
+
+    /// Whether to create `min_connections` when the pool is built.
+    pub warm_up: bool,
+
+    /// Whether warm-up failures are tolerated.
+    ///
+    /// When `false`, pool creation fails if any warm-up connection cannot be
+    /// established. When `true`, failures are logged and connections are
+    /// created on demand instead.
+    pub lazy: bool,
+
     /// Interval between health checks for idle connections.
     pub health_check_interval: Duration,
 
@@ -63,6 +86,18 @@ pub struct PoolConfig {
     /// - `SELECT GETDATE()` - Check server can execute functions
     /// - `SELECT 1 FROM sys.databases WHERE name = 'mydb'` - Check database exists
     pub health_check_query: Arc<str>,
+
+    /// Consecutive connection failures that open the circuit breaker
+    /// (0 disables it).
+    ///
+    /// While the breaker is open, requests for a new connection fail at once
+    /// with [`PoolError::CircuitOpen`](crate::PoolError::CircuitOpen)
+    /// instead of waiting for another connect timeout.
+    pub circuit_breaker_threshold: u32,
+
+    /// How long the circuit breaker stays open before a single connection
+    /// attempt is let through to probe the server.
+    pub circuit_breaker_cooldown: Duration,
 }
 
 impl Default for PoolConfig {
@@ -74,12 +109,18 @@ impl Default for PoolConfig {
             connection_timeout: Duration::from_secs(30),
             idle_timeout: Duration::from_secs(600),
             max_lifetime: Duration::from_secs(1800),
+            expiry_jitter: Duration::ZERO,
             test_on_checkout: true,
             test_on_checkin: false,
+            test_before_acquire: false,
+            warm_up: true,
+            lazy: false,
             health_check_interval: Duration::from_secs(30),
             sp_reset_connection: true,
             reset_on_return: true,
             health_check_query: Arc::from(DEFAULT_HEALTH_CHECK_QUERY),
+            circuit_breaker_threshold: 0,
+            circuit_breaker_cooldown: Duration::from_secs(30),
         }
     }
 }
@@ -126,6 +167,16 @@ impl PoolConfig {
         self
     }
 
+    /// Shorten each connection's `max_lifetime` and `idle_timeout` by a
+    /// random amount of up to `jitter`, spreading reconnections over time.
+    ///
+    /// A jitter of a few percent of `max_lifetime` is usually enough.
+    #[must_use]
+    pub fn expiry_jitter(mut self, jitter: Duration) -> Self {
+        self.expiry_jitter = jitter;
+        self
+    }
+
     /// Enable or disable testing connections on checkout.
     #[must_use]
     pub fn test_on_checkout(mut self, enabled: bool) -> Self {
@@ -140,6 +191,27 @@ impl PoolConfig {
         self
     }
 
+    /// Enable or disable the lightweight pre-ping on checkout.
+    #[must_use]
+    pub fn test_before_acquire(mut self, enabled: bool) -> Self {
+        self.test_before_acquire = enabled;
+        self
+    }
+
+    /// Enable or disable creating `min_connections` at build time.
+    #[must_use]
+    pub fn warm_up(mut self, enabled: bool) -> Self {
+        self.warm_up = enabled;
+        self
+    }
+
+    /// Tolerate warm-up failures instead of failing pool creation.
+    #[must_use]
+    pub fn lazy(mut self, enabled: bool) -> Self {
+        self.lazy = enabled;
+        self
+    }
+
     /// Set the health check interval.
     #[must_use]
     pub fn health_check_interval(mut self, interval: Duration) -> Self {
@@ -197,6 +269,17 @@ impl PoolConfig {
         self
     }
 
+    /// Fail connection requests fast after `threshold` consecutive
+    /// connection failures, for `cooldown` before trying the server again.
+    ///
+    /// A `threshold` of 0 disables the circuit breaker, which is the default.
+    #[must_use]
+    pub fn circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker_threshold = threshold;
+        self.circuit_breaker_cooldown = cooldown;
+        self
+    }
+
     /// Validate the configuration.
     pub fn validate(&self) -> Result<(), crate::error::PoolError> {
         if self.max_connections == 0 {
@@ -209,6 +292,11 @@ impl PoolConfig {
                 "min_connections cannot be greater than max_connections".into(),
             ));
         }
+        if self.expiry_jitter >= self.max_lifetime || self.expiry_jitter >= self.idle_timeout {
+            return Err(crate::error::PoolError::Configuration(
+                "expiry_jitter must be less than max_lifetime and idle_timeout".into(),
+            ));
+        }
         Ok(())
     }
 }
@@ -226,7 +314,11 @@ mod tests {
         assert!(config.sp_reset_connection);
         assert!(config.test_on_checkout);
         assert!(!config.test_on_checkin);
+        assert!(!config.test_before_acquire);
+        assert!(config.warm_up);
+        assert!(!config.lazy);
         assert_eq!(&*config.health_check_query, DEFAULT_HEALTH_CHECK_QUERY);
+        assert_eq!(config.circuit_breaker_threshold, 0);
     }
 
     #[test]
@@ -299,6 +391,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_validation_expiry_jitter() {
+        let config = PoolConfig::new().expiry_jitter(Duration::from_secs(60));
+        assert!(config.validate().is_ok());
+
+        let config = config.idle_timeout(Duration::from_secs(60));
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_config_equal_min_max() {
         let config = PoolConfig::new().min_connections(5).max_connections(5);
@@ -1,17 +1,39 @@
 //! Pool error types.
+//!
+//! Errors that come from the server keep the underlying
+//! [`mssql_client::Error`] as their [`source`](std::error::Error::source), so
+//! a caller can tell a pool that is merely busy ([`PoolError::Timeout`]) from
+//! a server that cannot be reached ([`PoolError::CreateFailed`],
+//! [`PoolError::CircuitOpen`]):
+//!
+//! ```rust,ignore
+//! match pool.get().await {
+//!     Ok(conn) => { /* use connection */ }
+//!     Err(e) if e.is_capacity_exhausted() => {
+//!         // All connections busy - shed load or scale the pool
+//!     }
+//!     Err(e) if e.is_server_unavailable() => {
+//!         // SQL Server is down or unreachable
+//!     }
+//!     Err(e) => return Err(e.into()),
+//! }
+//! ```
+
+use std::time::Duration;
 
 use thiserror::Error;
 
 /// Errors that can occur during pool operations.
 #[derive(Debug, Error)]
 pub enum PoolError {
-    /// Failed to acquire a connection within the timeout.
-    #[error("connection acquisition timeout after {0:?}")]
-    AcquisitionTimeout(std::time::Duration),
-
-    /// Timeout waiting for a connection.
-    #[error("timeout waiting for connection")]
-    Timeout,
+    /// No connection became available within the acquisition timeout.
+    #[error("timed out after {waited:?} waiting for a connection ({queue_depth} waiting)")]
+    Timeout {
+        /// How long the caller waited.
+        waited: Duration,
+        /// Number of callers waiting for a connection, including this one.
+        queue_depth: u32,
+    },
 
     /// Pool is closed.
     #[error("pool is closed")]
@@ -19,15 +41,34 @@ pub enum PoolError {
 
     /// Connection error.
     #[error("connection error: {0}")]
-    Connection(String),
+    Connection(#[from] mssql_client::Error),
+
+    /// Opening a new connection failed.
+    #[error("failed to create connection (attempt {attempt}): {source}")]
+    CreateFailed {
+        /// Number of consecutive failed attempts, including this one.
+        attempt: u32,
+        /// The error from the server or the network.
+        source: mssql_client::Error,
+    },
 
-    /// Connection creation failed.
-    #[error("failed to create connection: {0}")]
-    ConnectionCreation(String),
+    /// Connection creation is suspended after repeated failures.
+    ///
+    /// Returned while the circuit breaker configured with
+    /// [`PoolConfig::circuit_breaker`](crate::PoolConfig::circuit_breaker)
+    /// is open.
+    #[error("connection creation suspended after repeated failures, retry after {retry_after:?}")]
+    CircuitOpen {
+        /// Time until the next connection attempt is allowed.
+        retry_after: Duration,
+    },
 
-    /// Connection is unhealthy.
-    #[error("connection health check failed: {0}")]
-    UnhealthyConnection(String),
+    /// Connection health check failed.
+    #[error("connection health check failed: {source}")]
+    HealthCheckFailed {
+        /// The error returned by the health check query.
+        source: mssql_client::Error,
+    },
 
     /// Connection reset failed.
     #[error("connection reset failed: {0}")]
@@ -48,3 +89,67 @@ pub enum PoolError {
     #[error("connection validation failed: {0}")]
     ValidationFailed(String),
 }
+
+impl PoolError {
+    /// Check whether the pool ran out of connections while the server was
+    /// reachable.
+    #[must_use]
+    pub fn is_capacity_exhausted(&self) -> bool {
+        matches!(
+            self,
+            Self::Timeout { .. } | Self::MaxConnectionsReached { .. }
+        )
+    }
+
+    /// Check whether the pool could not open a connection to the server.
+    #[must_use]
+    pub fn is_server_unavailable(&self) -> bool {
+        matches!(self, Self::CreateFailed { .. } | Self::CircuitOpen { .. })
+    }
+
+    /// Get the underlying client error, if there is one.
+    #[must_use]
+    pub fn client_error(&self) -> Option<&mssql_client::Error> {
+        match self {
+            Self::Connection(e)
+            | Self::CreateFailed { source: e, .. }
+            | Self::HealthCheckFailed { source: e } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_error_classification() {
+        let timeout = PoolError::Timeout {
+            waited: Duration::from_secs(5),
+            queue_depth: 3,
+        };
+        assert!(timeout.is_capacity_exhausted());
+        assert!(!timeout.is_server_unavailable());
+        assert!(timeout.client_error().is_none());
+
+        let create = PoolError::CreateFailed {
+            attempt: 2,
+            source: mssql_client::Error::ConnectTimeout,
+        };
+        assert!(create.is_server_unavailable());
+        assert!(!create.is_capacity_exhausted());
+        assert!(matches!(
+            create.client_error(),
+            Some(mssql_client::Error::ConnectTimeout)
+        ));
+        assert!(create.source().is_some());
+        assert!(create.to_string().contains("attempt 2"));
+
+        let open = PoolError::CircuitOpen {
+            retry_after: Duration::from_secs(1),
+        };
+        assert!(open.is_server_unavailable());
+    }
+}
@@ -0,0 +1,299 @@
+//! Connection factories.
+//!
+//! The pool does not talk to SQL Server directly. Every physical connection
+//! is created, validated, reset, and closed through a [`ConnectionFactory`].
+//! [`MssqlConnectionFactory`] is the default implementation backed by
+//! `mssql-client`; [`MockConnectionFactory`] provides in-memory connections
+//! so pool behavior can be exercised without a live server.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use mssql_client::{Client, Config as ClientConfig, Ready};
+
+use crate::config::DEFAULT_HEALTH_CHECK_QUERY;
+use crate::error::PoolError;
+
+/// Creates and manages the physical connections held by a [`Pool`](crate::Pool).
+///
+/// Implementations are shared between the pool, its reaper task, and every
+/// checked-out connection, so they must be cheap to call concurrently.
+///
+/// Uses `#[async_trait]` so the factory can be stored behind an `Arc` and
+/// shared with spawned tasks.
+#[async_trait::async_trait]
+pub trait ConnectionFactory: Send + Sync + 'static {
+    /// The connection type produced by this factory.
+    type Connection: Send + 'static;
+
+    /// Open a new connection.
+    ///
+    /// Report connection failures as [`PoolError::Connection`]; the pool
+    /// passes them on as [`PoolError::CreateFailed`].
+    async fn create(&self) -> Result<Self::Connection, PoolError>;
+
+    /// Check that an idle connection is still usable.
+    ///
+    /// `query` is the pool's configured health check query.
+    async fn validate(&self, conn: &mut Self::Connection, query: &str) -> Result<(), PoolError>;
+
+    /// Lightweight liveness check used when `test_before_acquire` is enabled.
+    ///
+    /// Defaults to validating with [`DEFAULT_HEALTH_CHECK_QUERY`], regardless
+    /// of the pool's configured health check query.
+    async fn ping(&self, conn: &mut Self::Connection) -> Result<(), PoolError> {
+        self.validate(conn, DEFAULT_HEALTH_CHECK_QUERY).await
+    }
+
+    /// Clean up session state before the connection is handed to a new caller.
+    ///
+    /// Only invoked when `sp_reset_connection` is enabled in the pool config.
+    async fn reset(&self, conn: &mut Self::Connection) -> Result<(), PoolError>;
+
+    /// Close a connection that the pool is retiring.
+    async fn close(&self, conn: Self::Connection);
+
+    /// Check whether a returned connection may go back into the idle queue.
+    ///
+    /// This runs synchronously when a [`PooledConnection`](crate::PooledConnection)
+    /// is dropped. Returning `false` discards the connection.
+    fn is_reusable(&self, _conn: &Self::Connection) -> bool {
+        true
+    }
+
+    /// Check, without a round trip, whether a connection is known to be dead.
+    ///
+    /// Runs synchronously at checkout, before any reset or health check
+    /// query, and when a connection is returned; `true` discards the
+    /// connection. Must be cheap and must not block.
+    fn is_broken(&self, _conn: &Self::Connection) -> bool {
+        false
+    }
+}
+
+/// The default factory, creating connections with [`Client::connect`].
+#[derive(Debug, Clone)]
+pub struct MssqlConnectionFactory {
+    config: ClientConfig,
+}
+
+impl MssqlConnectionFactory {
+    /// Create a factory that connects using the given client configuration.
+    #[must_use]
+    pub fn new(config: ClientConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the client configuration used for new connections.
+    #[must_use]
+    pub fn client_config(&self) -> &ClientConfig {
+        &self.config
+    }
+}
+
+#[async_trait::async_trait]
+impl ConnectionFactory for MssqlConnectionFactory {
+    type Connection = Client<Ready>;
+
+    async fn create(&self) -> Result<Self::Connection, PoolError> {
+        Ok(Client::connect(self.config.clone()).await?)
+    }
+
+    async fn validate(&self, conn: &mut Self::Connection, query: &str) -> Result<(), PoolError> {
+        let rows = conn
+            .query(query, &[])
+            .await
+            .map_err(|source| PoolError::HealthCheckFailed { source })?;
+        // Consume the result set
+        for _ in rows {}
+        Ok(())
+    }
+
+    async fn reset(&self, conn: &mut Self::Connection) -> Result<(), PoolError> {
+        // A request dropped by the previous user leaves its response on the
+        // connection; read it now rather than during the next user's query.
+        conn.drain()
+            .await
+            .map_err(|e| PoolError::ResetFailed(e.to_string()))?;
+        // Sets the RESETCONNECTION flag on the first TDS packet of the next
+        // request, causing SQL Server to reset connection state (temp tables,
+        // SET options, isolation level, etc.) before executing.
+        conn.mark_needs_reset();
+        Ok(())
+    }
+
+    async fn close(&self, conn: Self::Connection) {
+        if let Err(e) = conn.close().await {
+            tracing::debug!(error = %e, "error while closing pooled connection");
+        }
+    }
+
+    fn is_reusable(&self, conn: &Self::Connection) -> bool {
+        // A transaction started via raw SQL cannot be rolled back from a
+        // synchronous drop, and the next user would get a connection
+        // mid-transaction.
+        !conn.is_in_transaction()
+    }
+
+    fn is_broken(&self, conn: &Self::Connection) -> bool {
+        conn.is_broken()
+    }
+}
+
+/// An in-memory connection produced by [`MockConnectionFactory`].
+#[derive(Debug)]
+pub struct MockConnection {
+    id: u64,
+    resets: u64,
+    broken: Arc<AtomicBool>,
+    closed: Arc<AtomicBool>,
+    in_transaction: bool,
+}
+
+impl MockConnection {
+    /// Get the factory-assigned identifier of this connection.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get the number of times this connection has been reset.
+    #[must_use]
+    pub fn reset_count(&self) -> u64 {
+        self.resets
+    }
+
+    /// Mark the connection as broken so that validation fails.
+    pub fn break_connection(&self) {
+        self.broken.store(true, Ordering::Relaxed);
+    }
+
+    /// Simulate the server closing the connection, so that the factory's
+    /// `is_broken` reports it without a health check.
+    pub fn close_socket(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+
+    /// Simulate an open transaction, making the connection non-reusable.
+    pub fn set_in_transaction(&mut self, in_transaction: bool) {
+        self.in_transaction = in_transaction;
+    }
+}
+
+/// A factory producing [`MockConnection`]s, for tests and examples.
+///
+/// Clones share the same counters, so a test can keep a handle to the factory
+/// after passing it to [`Pool::with_factory`](crate::Pool::with_factory).
+#[derive(Debug, Clone, Default)]
+pub struct MockConnectionFactory {
+    state: Arc<MockFactoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MockFactoryState {
+    next_id: AtomicU64,
+    created: AtomicU64,
+    closed: AtomicU64,
+    pings: AtomicU64,
+    fail_create: AtomicBool,
+    /// One more than the number of creates allowed to succeed; zero for no
+    /// limit.
+    create_limit: AtomicU64,
+    fail_validate: AtomicBool,
+}
+
+impl MockConnectionFactory {
+    /// Create a new mock factory.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make subsequent `create` calls fail.
+    pub fn set_fail_create(&self, fail: bool) {
+        self.state.fail_create.store(fail, Ordering::Relaxed);
+    }
+
+    /// Make `create` calls fail once `count` connections have been created.
+    pub fn fail_create_after(&self, count: u64) {
+        self.state.create_limit.store(count + 1, Ordering::Relaxed);
+    }
+
+    /// Make subsequent `validate` calls fail.
+    pub fn set_fail_validate(&self, fail: bool) {
+        self.state.fail_validate.store(fail, Ordering::Relaxed);
+    }
+
+    /// Get the number of connections created.
+    #[must_use]
+    pub fn created(&self) -> u64 {
+        self.state.created.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of connections closed.
+    #[must_use]
+    pub fn closed(&self) -> u64 {
+        self.state.closed.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of pre-ping checks performed.
+    #[must_use]
+    pub fn pings(&self) -> u64 {
+        self.state.pings.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait::async_trait]
+impl ConnectionFactory for MockConnectionFactory {
+    type Connection = MockConnection;
+
+    async fn create(&self) -> Result<Self::Connection, PoolError> {
+        let limit = self.state.create_limit.load(Ordering::Relaxed);
+        if self.state.fail_create.load(Ordering::Relaxed)
+            || (limit > 0 && self.created() + 1 >= limit)
+        {
+            return Err(PoolError::Connection(mssql_client::Error::Connection(
+                "mock create failure".to_string(),
+            )));
+        }
+        self.state.created.fetch_add(1, Ordering::Relaxed);
+        Ok(MockConnection {
+            id: self.state.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            resets: 0,
+            broken: Arc::new(AtomicBool::new(false)),
+            closed: Arc::new(AtomicBool::new(false)),
+            in_transaction: false,
+        })
+    }
+
+    async fn validate(&self, conn: &mut Self::Connection, _query: &str) -> Result<(), PoolError> {
+        if self.state.fail_validate.load(Ordering::Relaxed) || conn.broken.load(Ordering::Relaxed) {
+            return Err(PoolError::HealthCheckFailed {
+                source: mssql_client::Error::Connection("mock validation failure".to_string()),
+            });
+        }
+        Ok(())
+    }
+
+    async fn ping(&self, conn: &mut Self::Connection) -> Result<(), PoolError> {
+        self.state.pings.fetch_add(1, Ordering::Relaxed);
+        self.validate(conn, DEFAULT_HEALTH_CHECK_QUERY).await
+    }
+
+    async fn reset(&self, conn: &mut Self::Connection) -> Result<(), PoolError> {
+        conn.resets += 1;
+        Ok(())
+    }
+
+    async fn close(&self, _conn: Self::Connection) {
+        self.state.closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_reusable(&self, conn: &Self::Connection) -> bool {
+        !conn.in_transaction && !conn.broken.load(Ordering::Relaxed)
+    }
+
+    fn is_broken(&self, conn: &Self::Connection) -> bool {
+        conn.closed.load(Ordering::Relaxed)
+    }
+}
@@ -8,12 +8,15 @@
 //! ## Features
 //!
 //! - `sp_reset_connection` execution on connection return
-//! - Configurable health checks (default: `SELECT 1`)
+//! - Configurable health checks (default: `SELECT 1`) and optional pre-ping
+//! - Eager warm-up of `min_connections` that fails fast unless `lazy` is set
 //! - Configurable min/max pool sizes
 //! - Connection timeout, idle timeout, and max lifetime
 //! - Background reaper task for expired connection cleanup
 //! - Comprehensive metrics (wait queue depth, acquisition time, etc.)
 //! - Per-connection prepared statement cache management
+//! - Pluggable [`ConnectionFactory`] for mock or alternative transports
+//! - [`ConnectionLifecycle`] hooks around creation, checkout, return, and close
 //!
 //! ## Example
 //!
@@ -59,6 +62,7 @@
 
 pub mod config;
 pub mod error;
+pub mod factory;
 pub mod lifecycle;
 pub mod pool;
 
@@ -71,6 +75,11 @@ pub use error::PoolError;
 // Pool types
 pub use pool::{Pool, PoolBuilder, PoolMetrics, PoolStatus, PooledConnection};
 
+// Connection factories
+pub use factory::{
+    ConnectionFactory, MockConnection, MockConnectionFactory, MssqlConnectionFactory,
+};
+
 // Lifecycle management
 pub use lifecycle::{
     ConnectionLifecycle, ConnectionMetadata, ConnectionState, DynConnectionLifecycle,
@@ -1,59 +1,204 @@
 //! Connection lifecycle management.
 //!
-//! This module defines traits and types for managing connection lifecycle
-//! in the pool, including health checks and connection reset operations.
+//! This module defines the hooks the pool runs at fixed points in a
+//! connection's life, and the types the pool uses to track connections.
+//!
+//! ## Hook order
+//!
+//! For every connection, the pool runs the hooks of a [`ConnectionLifecycle`]
+//! in this order, and never runs two hooks for the same connection at once:
+//!
+//! 1. [`before_create`](ConnectionLifecycle::before_create), before each
+//!    attempt to open a connection
+//! 2. [`after_create`](ConnectionLifecycle::after_create), once the
+//!    connection is open and before the pool uses it
+//! 3. [`before_checkout`](ConnectionLifecycle::before_checkout) and
+//!    [`after_return`](ConnectionLifecycle::after_return), once for every
+//!    checkout
+//! 4. [`before_destroy`](ConnectionLifecycle::before_destroy), at most once,
+//!    before the pool closes the connection
+//!
+//! `before_checkout` runs after any reset and health check, right before the
+//! connection is handed out. `after_return` runs only for connections that go
+//! back to the idle queue; broken and non-reusable connections are destroyed
+//! instead. `before_destroy` runs for every connection whose `after_create`
+//! succeeded, unless it is detached or dropped without a runtime.
+//!
+//! With several lifecycles registered, each point runs them in registration
+//! order and stops at the first error.
+//!
+//! ## Errors
+//!
+//! - `before_create`: no connection is opened, and the error is returned to
+//!   the caller.
+//! - `after_create`: the connection is closed (without `before_destroy`), and
+//!   the error is returned to the caller.
+//! - `before_checkout`: the connection is destroyed, and the error is
+//!   returned from [`Pool::get`](crate::Pool::get).
+//! - `after_return`: the connection is destroyed instead of going back to the
+//!   idle queue. The error is logged, as the caller has already moved on.
+//!
+//! ## `try_get`
+//!
+//! [`Pool::try_get`](crate::Pool::try_get) never waits, so it polls the
+//! `before_checkout` hooks only once. A hook that completes without waiting
+//! behaves as with `get`. A hook that has to wait is dropped at its first
+//! await point: the connection stays in the idle queue and `try_get` returns
+//! `None`. Hooks that await I/O therefore make `try_get` hand out nothing,
+//! and must tolerate being dropped part-way.
+
+use std::future::Future;
 
 use crate::error::PoolError;
 
-/// Trait for connection lifecycle management.
+/// Hooks run by the pool at fixed points in a connection's life.
+///
+/// Register hooks with [`PoolBuilder::lifecycle`](crate::PoolBuilder::lifecycle),
+/// or with [`Pool::with_lifecycles`](crate::Pool::with_lifecycles) for a
+/// custom factory. Every hook does nothing by default. See the
+/// [module documentation](self) for the order hooks run in and what an error
+/// does at each point.
 ///
-/// This trait defines the operations needed to manage connection health
-/// and state within the pool. Implementations handle SQL Server-specific
-/// lifecycle requirements like `sp_reset_connection`.
+/// ```rust,ignore
+/// use mssql_client::{Client, Ready};
+/// use mssql_driver_pool::{ConnectionLifecycle, ConnectionMetadata, PoolError};
+///
+/// struct SessionContext;
+///
+/// impl ConnectionLifecycle<Client<Ready>> for SessionContext {
+///     async fn after_create(
+///         &self,
+///         conn: &mut Client<Ready>,
+///         _metadata: &ConnectionMetadata,
+///     ) -> Result<(), PoolError> {
+///         conn.execute("SET ARITHABORT ON", &[]).await?;
+///         Ok(())
+///     }
+/// }
+/// ```
 ///
 /// # Native Async Traits
 ///
 /// Per ARCHITECTURE.md §4.1, this uses native async traits (Rust 2024 Edition)
-/// for zero overhead. For trait object usage, see `DynConnectionLifecycle`.
-#[allow(async_fn_in_trait)]
-pub trait ConnectionLifecycle: Send + Sync {
-    /// Check if the connection is healthy.
-    ///
-    /// Typically executes `SELECT 1` to verify the connection is alive
-    /// and responsive.
-    async fn health_check(&self) -> Result<(), PoolError>;
+/// for zero overhead. Every implementation is also a
+/// [`DynConnectionLifecycle`], which is how the pool stores it.
+pub trait ConnectionLifecycle<C>: Send + Sync + 'static {
+    /// Run before each attempt to open a connection.
+    fn before_create(&self) -> impl Future<Output = Result<(), PoolError>> + Send {
+        async { Ok(()) }
+    }
 
-    /// Reset connection state for pool return.
-    ///
-    /// Executes `sp_reset_connection` to clean up server-side state:
-    /// - Closes open cursors
-    /// - Releases temp tables
-    /// - Resets SET options to defaults
-    /// - Clears transaction state
-    /// - Invalidates prepared statement handles
-    async fn reset(&mut self) -> Result<(), PoolError>;
-
-    /// Check if the connection is still valid for use.
-    ///
-    /// This is a lighter-weight check than `health_check`, typically
-    /// just checking if the underlying TCP connection is still open.
-    fn is_valid(&self) -> bool;
+    /// Run once a new connection is open, before the pool uses it.
+    fn after_create(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> impl Future<Output = Result<(), PoolError>> + Send {
+        let _ = (conn, metadata);
+        async { Ok(()) }
+    }
+
+    /// Run right before a connection is handed to a caller.
+    fn before_checkout(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> impl Future<Output = Result<(), PoolError>> + Send {
+        let _ = (conn, metadata);
+        async { Ok(()) }
+    }
+
+    /// Run when a caller returns a connection, before it goes back to the
+    /// idle queue.
+    fn after_return(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> impl Future<Output = Result<(), PoolError>> + Send {
+        let _ = (conn, metadata);
+        async { Ok(()) }
+    }
+
+    /// Run before the pool closes a connection.
+    fn before_destroy(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> impl Future<Output = ()> + Send {
+        let _ = (conn, metadata);
+        async {}
+    }
 }
 
 /// Async trait for connection lifecycle with trait object compatibility.
 ///
-/// Use this when you need `dyn ConnectionLifecycle` (e.g., for dynamic dispatch).
-/// Per ARCHITECTURE.md, `#[async_trait]` is required for object safety.
+/// The pool stores its lifecycles as `Box<dyn DynConnectionLifecycle<C>>`.
+/// Implement [`ConnectionLifecycle`] instead; every implementation is also a
+/// `DynConnectionLifecycle`. Per ARCHITECTURE.md, `#[async_trait]` is
+/// required for object safety.
+#[async_trait::async_trait]
+pub trait DynConnectionLifecycle<C: Send>: Send + Sync {
+    /// Run before each attempt to open a connection.
+    async fn before_create(&self) -> Result<(), PoolError>;
+
+    /// Run once a new connection is open, before the pool uses it.
+    async fn after_create(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError>;
+
+    /// Run right before a connection is handed to a caller.
+    async fn before_checkout(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError>;
+
+    /// Run when a caller returns a connection.
+    async fn after_return(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError>;
+
+    /// Run before the pool closes a connection.
+    async fn before_destroy(&self, conn: &mut C, metadata: &ConnectionMetadata);
+}
+
 #[async_trait::async_trait]
-pub trait DynConnectionLifecycle: Send + Sync {
-    /// Check if the connection is healthy.
-    async fn health_check(&self) -> Result<(), PoolError>;
+impl<C: Send, T: ConnectionLifecycle<C>> DynConnectionLifecycle<C> for T {
+    async fn before_create(&self) -> Result<(), PoolError> {
+        ConnectionLifecycle::before_create(self).await
+    }
 
-    /// Reset connection state for pool return.
-    async fn reset(&mut self) -> Result<(), PoolError>;
+    async fn after_create(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError> {
+        ConnectionLifecycle::after_create(self, conn, metadata).await
+    }
+
+    async fn before_checkout(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError> {
+        ConnectionLifecycle::before_checkout(self, conn, metadata).await
+    }
+
+    async fn after_return(
+        &self,
+        conn: &mut C,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError> {
+        ConnectionLifecycle::after_return(self, conn, metadata).await
+    }
 
-    /// Check if the connection is still valid for use.
-    fn is_valid(&self) -> bool;
+    async fn before_destroy(&self, conn: &mut C, metadata: &ConnectionMetadata) {
+        ConnectionLifecycle::before_destroy(self, conn, metadata).await
+    }
 }
 
 /// Health check result with timing information.
@@ -141,6 +286,15 @@ pub struct ConnectionMetadata {
     pub checkout_count: u64,
     /// Current state of the connection.
     pub state: ConnectionState,
+    /// How much earlier than the pool's `max_lifetime` and `idle_timeout`
+    /// this connection expires.
+    ///
+    /// Drawn at random per connection so connections created together do
+    /// not all expire together.
+    pub expiry_jitter: std::time::Duration,
+    /// Tag of the last [`Pool::get_tagged`](crate::Pool::get_tagged)
+    /// checkout, if any.
+    pub tag: Option<std::sync::Arc<str>>,
 }
 
 impl ConnectionMetadata {
@@ -154,19 +308,31 @@ impl ConnectionMetadata {
             last_checked_at: None,
             checkout_count: 0,
             state: ConnectionState::Idle,
+            expiry_jitter: std::time::Duration::ZERO,
+            tag: None,
         }
     }
 
-    /// Check if the connection has exceeded its maximum lifetime.
+    /// Set how much earlier than the configured limits the connection
+    /// expires.
+    #[must_use]
+    pub fn with_expiry_jitter(mut self, jitter: std::time::Duration) -> Self {
+        self.expiry_jitter = jitter;
+        self
+    }
+
+    /// Check if the connection has exceeded its maximum lifetime, less its
+    /// expiry jitter.
     #[must_use]
     pub fn is_expired(&self, max_lifetime: std::time::Duration) -> bool {
-        self.created_at.elapsed() > max_lifetime
+        self.created_at.elapsed() > max_lifetime.saturating_sub(self.expiry_jitter)
     }
 
-    /// Check if the connection has been idle too long.
+    /// Check if the connection has been idle too long, less its expiry
+    /// jitter.
     #[must_use]
     pub fn is_idle_expired(&self, idle_timeout: std::time::Duration) -> bool {
-        self.last_used_at.elapsed() > idle_timeout
+        self.last_used_at.elapsed() > idle_timeout.saturating_sub(self.expiry_jitter)
     }
 
     /// Check if a health check is due.
@@ -252,6 +418,18 @@ mod tests {
         assert_eq!(meta.state, ConnectionState::Idle);
     }
 
+    #[test]
+    fn test_connection_metadata_expiry_jitter() {
+        let meta = ConnectionMetadata::new(1);
+        assert!(!meta.is_expired(Duration::from_secs(60)));
+        assert!(!meta.is_idle_expired(Duration::from_secs(60)));
+
+        let meta = meta.with_expiry_jitter(Duration::from_secs(60));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(meta.is_expired(Duration::from_secs(60)));
+        assert!(meta.is_idle_expired(Duration::from_secs(60)));
+    }
+
     #[test]
     fn test_health_check_result_healthy() {
         let result = HealthCheckResult::healthy(Duration::from_millis(5));
@@ -3,9 +3,11 @@
 //! This module provides a purpose-built connection pool for SQL Server
 //! with SQL Server-specific lifecycle management including `sp_reset_connection`.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::hash::BuildHasher;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
 use mssql_client::{Client, Config as ClientConfig, Ready};
@@ -15,13 +17,18 @@ use tokio::time::timeout;
 
 use crate::config::PoolConfig;
 use crate::error::PoolError;
-use crate::lifecycle::ConnectionMetadata;
+use crate::factory::{ConnectionFactory, MssqlConnectionFactory};
+use crate::lifecycle::{ConnectionLifecycle, ConnectionMetadata, DynConnectionLifecycle};
 
 /// A connection pool for SQL Server.
 ///
 /// The pool manages a set of database connections, providing automatic
 /// connection reuse, health checking, and lifecycle management.
 ///
+/// Connections are created through a [`ConnectionFactory`]. The default,
+/// [`MssqlConnectionFactory`], connects with `mssql-client`; use
+/// [`Pool::with_factory`] to supply a different one.
+///
 /// # Features
 ///
 /// - `sp_reset_connection` execution on connection return
@@ -49,24 +56,30 @@ use crate::lifecycle::ConnectionMetadata;
 /// let conn = pool.get().await?;
 /// // Use connection...
 /// ```
-pub struct Pool {
+pub struct Pool<F: ConnectionFactory = MssqlConnectionFactory> {
     config: PoolConfig,
-    client_config: ClientConfig,
-    inner: Arc<PoolInner>,
+    inner: Arc<PoolInner<F>>,
 }
 
 /// A pooled connection entry.
-struct PooledEntry {
-    /// The actual client connection.
-    client: Client<Ready>,
+struct PooledEntry<C> {
+    /// The actual connection.
+    conn: C,
     /// Connection metadata.
     metadata: ConnectionMetadata,
+    /// Whether the connection must be reset before its next checkout.
+    needs_reset: bool,
+    /// Eviction epoch of the connection's tag when it was last checked out.
+    tag_epoch: u64,
 }
 
-struct PoolInner {
+struct PoolInner<F: ConnectionFactory> {
     /// Pool configuration.
     config: PoolConfig,
 
+    /// Factory used to create, validate, reset, and close connections.
+    factory: F,
+
     /// Whether the pool is closed.
     closed: AtomicBool,
 
@@ -80,7 +93,7 @@ struct PoolInner {
     metrics: Mutex<PoolMetricsInner>,
 
     /// Idle connections ready for use.
-    idle_connections: Mutex<VecDeque<PooledEntry>>,
+    idle_connections: Mutex<VecDeque<PooledEntry<F::Connection>>>,
 
     /// Semaphore to limit total connections (wrapped in Arc for owned permits).
     semaphore: Arc<Semaphore>,
@@ -93,6 +106,149 @@ struct PoolInner {
 
     /// Number of tasks waiting for a connection.
     wait_queue_depth: AtomicU64,
+
+    /// Consecutive connection failures, for the circuit breaker.
+    create_failures: Mutex<CreateFailures>,
+
+    /// User hooks, run in registration order.
+    lifecycles: Vec<Box<dyn DynConnectionLifecycle<F::Connection>>>,
+
+    /// How often each tag was evicted; connections tagged in an older epoch
+    /// are discarded.
+    tag_epochs: Mutex<HashMap<Arc<str>, u64>>,
+}
+
+/// Circuit breaker state for connection creation.
+#[derive(Debug, Default)]
+struct CreateFailures {
+    /// Connection attempts that failed since the last success.
+    consecutive: u32,
+    /// While set and in the future, connection attempts fail fast.
+    open_until: Option<Instant>,
+}
+
+impl<F: ConnectionFactory> PoolInner<F> {
+    /// Close a connection, running the `before_destroy` hooks first.
+    async fn destroy(&self, mut conn: F::Connection, metadata: &ConnectionMetadata) {
+        for lifecycle in &self.lifecycles {
+            lifecycle.before_destroy(&mut conn, metadata).await;
+        }
+        self.factory.close(conn).await;
+    }
+
+    /// Close a connection from a synchronous context.
+    ///
+    /// [`destroy`](Self::destroy) is spawned onto the current runtime when
+    /// one is available; otherwise the connection is simply dropped.
+    fn retire(self: &Arc<Self>, conn: F::Connection, metadata: ConnectionMetadata) {
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let inner = Arc::clone(self);
+            handle.spawn(async move {
+                inner.destroy(conn, &metadata).await;
+            });
+        }
+    }
+
+    /// Get the current eviction epoch of `tag`.
+    fn tag_epoch(&self, tag: &str) -> u64 {
+        self.tag_epochs.lock().get(tag).copied().unwrap_or(0)
+    }
+
+    /// Check whether the tag of a connection was evicted since it was
+    /// checked out with that tag.
+    fn is_evicted(&self, metadata: &ConnectionMetadata, tag_epoch: u64) -> bool {
+        metadata
+            .tag
+            .as_deref()
+            .is_some_and(|tag| self.tag_epoch(tag) != tag_epoch)
+    }
+
+    /// Take the idle connection best suited to `tag`: one with the same tag,
+    /// else an untagged one, else any.
+    ///
+    /// A connection keeps its session state only when taken for its own tag;
+    /// otherwise it is marked for a reset and its tag replaced. Connections
+    /// of an evicted tag found on the way are retired.
+    fn take_idle(self: &Arc<Self>, tag: Option<&Arc<str>>) -> Option<PooledEntry<F::Connection>> {
+        let (entry, evicted) = {
+            let mut idle = self.idle_connections.lock();
+            let mut evicted = Vec::new();
+            if !self.tag_epochs.lock().is_empty() {
+                let mut live = VecDeque::with_capacity(idle.len());
+                for entry in idle.drain(..) {
+                    if self.is_evicted(&entry.metadata, entry.tag_epoch) {
+                        evicted.push(entry);
+                    } else {
+                        live.push_back(entry);
+                    }
+                }
+                *idle = live;
+            }
+            let position = tag
+                .and_then(|tag| {
+                    idle.iter()
+                        .position(|e| e.metadata.tag.as_ref() == Some(tag))
+                })
+                .or_else(|| idle.iter().position(|e| e.metadata.tag.is_none()));
+            let entry = match position {
+                Some(i) => idle.remove(i),
+                None => idle.pop_front(),
+            };
+            (entry, evicted)
+        };
+
+        if !evicted.is_empty() {
+            let mut metrics = self.metrics.lock();
+            metrics.connections_closed += evicted.len() as u64;
+            metrics.connections_evicted += evicted.len() as u64;
+        }
+        for entry in evicted {
+            self.retire(entry.conn, entry.metadata);
+        }
+
+        let mut entry = entry?;
+        if entry.metadata.tag.as_ref() != tag {
+            // Session state set up for another tag must not leak
+            entry.needs_reset |= entry.metadata.tag.is_some();
+            entry.metadata.tag = tag.cloned();
+        } else if tag.is_some() {
+            entry.needs_reset = false;
+        }
+        Some(entry)
+    }
+
+    /// Run the `before_checkout` hooks, stopping at the first error.
+    async fn before_checkout(
+        &self,
+        conn: &mut F::Connection,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError> {
+        for lifecycle in &self.lifecycles {
+            lifecycle.before_checkout(conn, metadata).await?;
+        }
+        Ok(())
+    }
+
+    /// Run the `after_return` hooks, then put the connection back in the
+    /// idle queue, or destroy it if a hook failed.
+    async fn check_in(&self, mut entry: PooledEntry<F::Connection>) {
+        for lifecycle in &self.lifecycles {
+            if let Err(e) = lifecycle
+                .after_return(&mut entry.conn, &entry.metadata)
+                .await
+            {
+                tracing::debug!(
+                    connection_id = entry.metadata.id,
+                    error = %e,
+                    "after_return hook failed, discarding connection"
+                );
+                self.metrics.lock().connections_closed += 1;
+                self.destroy(entry.conn, &entry.metadata).await;
+                return;
+            }
+        }
+        self.idle_connections.lock().push_back(entry);
+    }
 }
 
 /// Internal metrics tracking.
@@ -118,6 +274,10 @@ struct PoolMetricsInner {
     connections_idle_expired: u64,
     /// Connections closed due to max lifetime.
     connections_lifetime_expired: u64,
+    /// Connections discarded because the factory found them broken.
+    connections_broken: u64,
+    /// Connections closed by [`Pool::evict_tag`].
+    connections_evicted: u64,
     /// Reaper task runs.
     reaper_runs: u64,
     /// Peak wait queue depth observed.
@@ -141,10 +301,31 @@ impl Pool {
     ///
     /// For more control over pool creation, use [`Pool::builder()`].
     pub async fn new(config: PoolConfig, client_config: ClientConfig) -> Result<Self, PoolError> {
+        Self::with_factory(config, MssqlConnectionFactory::new(client_config)).await
+    }
+}
+
+impl<F: ConnectionFactory> Pool<F> {
+    /// Create a new pool that obtains its connections from `factory`.
+    pub async fn with_factory(config: PoolConfig, factory: F) -> Result<Self, PoolError> {
+        Self::with_lifecycles(config, factory, Vec::new()).await
+    }
+
+    /// Create a new pool that obtains its connections from `factory` and runs
+    /// `lifecycles` at each point of a connection's life.
+    ///
+    /// Lifecycles run in the order given; see the
+    /// [`lifecycle`](crate::lifecycle) module for when each hook runs.
+    pub async fn with_lifecycles(
+        config: PoolConfig,
+        factory: F,
+        lifecycles: Vec<Box<dyn DynConnectionLifecycle<F::Connection>>>,
+    ) -> Result<Self, PoolError> {
         config.validate()?;
 
         let inner = Arc::new(PoolInner {
             config: config.clone(),
+            factory,
             closed: AtomicBool::new(false),
             next_connection_id: AtomicU64::new(1),
             created_at: Instant::now(),
@@ -154,6 +335,9 @@ impl Pool {
             in_use_count: AtomicU64::new(0),
             total_connections: AtomicU64::new(0),
             wait_queue_depth: AtomicU64::new(0),
+            create_failures: Mutex::new(CreateFailures::default()),
+            lifecycles,
+            tag_epochs: Mutex::new(HashMap::new()),
         });
 
         // Start the reaper task for connection cleanup
@@ -165,14 +349,22 @@ impl Pool {
 
         let pool = Self {
             config: config.clone(),
-            client_config: client_config.clone(),
             inner,
         };
 
         // Warm up the pool by creating min_connections initial connections
-        if config.min_connections > 0 {
+        if config.warm_up && config.min_connections > 0 {
             tracing::info!(count = config.min_connections, "warming up connection pool");
-            pool.warm_up(config.min_connections).await;
+            if let Err(e) = pool.warm_up(config.min_connections).await {
+                // Stop the reaper task; the pool is never handed out
+                pool.inner.closed.store(true, Ordering::Release);
+                // Close the connections opened before the failure
+                let opened: Vec<_> = pool.inner.idle_connections.lock().drain(..).collect();
+                for entry in opened {
+                    pool.inner.destroy(entry.conn, &entry.metadata).await;
+                }
+                return Err(e);
+            }
         }
 
         tracing::info!(
@@ -189,8 +381,9 @@ impl Pool {
     /// Warm up the pool by creating initial connections.
     ///
     /// This creates up to `count` connections and adds them to the idle pool.
-    /// Connection failures during warm-up are logged but don't prevent pool creation.
-    async fn warm_up(&self, count: u32) {
+    /// Connection failures are returned immediately unless `lazy` is set, in
+    /// which case they are logged and don't prevent pool creation.
+    async fn warm_up(&self, count: u32) -> Result<(), PoolError> {
         let mut created = 0u32;
         for _ in 0..count {
             // Acquire a permit first
@@ -203,10 +396,14 @@ impl Pool {
             };
 
             let id = self.next_connection_id();
-            match Client::connect(self.client_config.clone()).await {
-                Ok(client) => {
-                    let metadata = ConnectionMetadata::new(id);
-                    let entry = PooledEntry { client, metadata };
+            match self.open_connection(id).await {
+                Ok((conn, metadata)) => {
+                    let entry = PooledEntry {
+                        conn,
+                        metadata,
+                        needs_reset: false,
+                        tag_epoch: 0,
+                    };
                     self.inner.idle_connections.lock().push_back(entry);
                     self.inner.total_connections.fetch_add(1, Ordering::Relaxed);
                     self.inner.metrics.lock().connections_created += 1;
@@ -218,6 +415,10 @@ impl Pool {
                 Err(e) => {
                     // Release permit on failure
                     drop(permit);
+                    if !self.config.lazy {
+                        tracing::error!(error = %e, "warm-up: failed to create connection");
+                        return Err(e);
+                    }
                     tracing::warn!(
                         error = %e,
                         "warm-up: failed to create connection, continuing"
@@ -230,6 +431,7 @@ impl Pool {
             created = created,
             "connection pool warm-up complete"
         );
+        Ok(())
     }
 
     /// Background reaper task that cleans up expired connections.
@@ -237,8 +439,13 @@ impl Pool {
     /// This task runs periodically and:
     /// - Removes connections that exceed `max_lifetime`
     /// - Removes connections that exceed `idle_timeout` (keeping at least `min_connections`)
-    async fn reaper_task(inner: Arc<PoolInner>, interval: Duration) {
-        let mut ticker = tokio::time::interval(interval);
+    ///
+    /// The first run is delayed by a random part of `interval`, so pools
+    /// started together (e.g. by replicas of one service) do not reap in
+    /// lockstep.
+    async fn reaper_task(inner: Arc<PoolInner<F>>, interval: Duration) {
+        let start = tokio::time::Instant::now() + random_duration(interval);
+        let mut ticker = tokio::time::interval_at(start, interval);
         ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         loop {
@@ -253,6 +460,7 @@ impl Pool {
             // Collect expired connections
             let mut expired_lifetime = 0u64;
             let mut expired_idle = 0u64;
+            let mut expired = Vec::new();
 
             {
                 let mut idle = inner.idle_connections.lock();
@@ -260,7 +468,8 @@ impl Pool {
                 let min_connections = inner.config.min_connections as usize;
 
                 // Remove connections that exceed max_lifetime first
-                idle.retain(|entry| {
+                let mut live = VecDeque::with_capacity(idle.len());
+                for entry in idle.drain(..) {
                     if entry.metadata.is_expired(inner.config.max_lifetime) {
                         expired_lifetime += 1;
                         tracing::debug!(
@@ -268,11 +477,12 @@ impl Pool {
                             age_secs = entry.metadata.created_at.elapsed().as_secs(),
                             "closing connection: max lifetime exceeded"
                         );
-                        false
+                        expired.push(entry);
                     } else {
-                        true
+                        live.push_back(entry);
                     }
-                });
+                }
+                *idle = live;
 
                 // Remove connections that exceed idle_timeout, but keep min_connections
                 if idle.len() > min_connections {
@@ -287,6 +497,7 @@ impl Pool {
                                 idle_secs = entry.metadata.last_used_at.elapsed().as_secs(),
                                 "closing connection: idle timeout exceeded"
                             );
+                            expired.push(entry);
                         } else {
                             new_idle.push_back(entry);
                         }
@@ -304,6 +515,10 @@ impl Pool {
                 }
             }
 
+            for entry in expired {
+                inner.destroy(entry.conn, &entry.metadata).await;
+            }
+
             // Update metrics
             if expired_lifetime > 0 || expired_idle > 0 {
                 let mut metrics = inner.metrics.lock();
@@ -328,7 +543,88 @@ impl Pool {
     /// if the pool is not at capacity. If all connections are in use and the
     /// pool is at capacity, this will wait until a connection becomes available
     /// or the timeout is reached.
-    pub async fn get(&self) -> Result<PooledConnection, PoolError> {
+    ///
+    /// Untagged idle connections are preferred; a connection last used with
+    /// [`get_tagged`](Self::get_tagged) is reset before it is handed out.
+    pub async fn get(&self) -> Result<PooledConnection<F>, PoolError> {
+        self.acquire(None, self.config.connection_timeout).await
+    }
+
+    /// Get a connection, waiting at most `wait` instead of the pool's
+    /// `connection_timeout`.
+    ///
+    /// Latency-sensitive paths can pass a short timeout to fail fast with
+    /// [`PoolError::Timeout`] while background jobs wait longer. Use
+    /// [`try_get`](Self::try_get) to not wait at all.
+    pub async fn get_timeout(&self, wait: Duration) -> Result<PooledConnection<F>, PoolError> {
+        self.acquire(None, wait).await
+    }
+
+    /// Get a connection for `tag`, e.g. a tenant.
+    ///
+    /// An idle connection last checked out with the same tag is preferred
+    /// and handed out without a reset, so the database selected with `USE`
+    /// and the session context it was given carry over. Otherwise an
+    /// untagged or new connection is tagged; a connection of another tag is
+    /// reset first. Use [`evict_tag`](Self::evict_tag) to close a tag's
+    /// connections.
+    ///
+    /// ```rust,ignore
+    /// let mut conn = pool.get_tagged("tenant-42").await?;
+    /// conn.execute("USE [tenant_42]", &[]).await?;
+    /// ```
+    ///
+    /// Because same-tag checkouts skip the reset, temporary tables and `SET`
+    /// options also carry over between them.
+    pub async fn get_tagged(&self, tag: &str) -> Result<PooledConnection<F>, PoolError> {
+        self.acquire(Some(Arc::from(tag)), self.config.connection_timeout)
+            .await
+    }
+
+    /// Close the connections tagged `tag`.
+    ///
+    /// Idle connections with the tag are closed now, and checked-out ones
+    /// when they are returned. Returns the number of idle connections
+    /// closed. Other connections are not affected.
+    pub async fn evict_tag(&self, tag: &str) -> usize {
+        *self
+            .inner
+            .tag_epochs
+            .lock()
+            .entry(Arc::from(tag))
+            .or_insert(0) += 1;
+
+        let evicted = {
+            let mut idle = self.inner.idle_connections.lock();
+            let (evicted, live): (Vec<_>, Vec<_>) = idle
+                .drain(..)
+                .partition(|e| e.metadata.tag.as_deref() == Some(tag));
+            idle.extend(live);
+            evicted
+        };
+        tracing::debug!(
+            tag = tag,
+            closed = evicted.len(),
+            "evicting tagged connections"
+        );
+
+        {
+            let mut metrics = self.inner.metrics.lock();
+            metrics.connections_closed += evicted.len() as u64;
+            metrics.connections_evicted += evicted.len() as u64;
+        }
+        let count = evicted.len();
+        for entry in evicted {
+            self.inner.destroy(entry.conn, &entry.metadata).await;
+        }
+        count
+    }
+
+    async fn acquire(
+        &self,
+        tag: Option<Arc<str>>,
+        wait: Duration,
+    ) -> Result<PooledConnection<F>, PoolError> {
         let acquisition_start = Instant::now();
 
         if self.inner.closed.load(Ordering::Acquire) {
@@ -347,12 +643,7 @@ impl Pool {
         }
 
         // Try to acquire semaphore permit with timeout
-        let permit = match timeout(
-            self.config.connection_timeout,
-            Arc::clone(&self.inner.semaphore).acquire_owned(),
-        )
-        .await
-        {
+        let permit = match timeout(wait, Arc::clone(&self.inner.semaphore).acquire_owned()).await {
             Ok(Ok(permit)) => {
                 self.inner.wait_queue_depth.fetch_sub(1, Ordering::Relaxed);
                 permit
@@ -367,102 +658,130 @@ impl Pool {
                 // Timeout waiting for semaphore
                 self.inner.wait_queue_depth.fetch_sub(1, Ordering::Relaxed);
                 self.inner.metrics.lock().checkouts_failed += 1;
-                return Err(PoolError::Timeout);
+                return Err(PoolError::Timeout {
+                    waited: wait,
+                    queue_depth: u32::try_from(current_depth).unwrap_or(u32::MAX),
+                });
             }
         };
 
         // Try to get an idle connection first, skipping expired ones
         let entry = loop {
-            let candidate = {
-                let mut idle = self.inner.idle_connections.lock();
-                idle.pop_front()
-            };
+            let candidate = self.inner.take_idle(tag.as_ref());
 
             match candidate {
-                Some(entry) => {
+                Some(mut entry) => {
                     // Check if connection exceeds max_lifetime
                     if entry.metadata.is_expired(self.config.max_lifetime) {
                         tracing::debug!(
                             connection_id = entry.metadata.id,
                             "discarding expired connection on checkout"
                         );
-                        let mut metrics = self.inner.metrics.lock();
-                        metrics.connections_closed += 1;
-                        metrics.connections_lifetime_expired += 1;
+                        {
+                            let mut metrics = self.inner.metrics.lock();
+                            metrics.connections_closed += 1;
+                            metrics.connections_lifetime_expired += 1;
+                        }
+                        self.inner.destroy(entry.conn, &entry.metadata).await;
                         // Don't return permit - we'll try to get another connection
                         continue;
                     }
+
+                    // Cheap check for a dead socket before any reset or
+                    // health check query
+                    if self.inner.factory.is_broken(&entry.conn) {
+                        tracing::debug!(
+                            connection_id = entry.metadata.id,
+                            "discarding broken connection on checkout"
+                        );
+                        {
+                            let mut metrics = self.inner.metrics.lock();
+                            metrics.connections_closed += 1;
+                            metrics.connections_broken += 1;
+                        }
+                        self.inner.destroy(entry.conn, &entry.metadata).await;
+                        continue;
+                    }
+
+                    // Reset session state left behind by the previous user
+                    if entry.needs_reset {
+                        let result = self.inner.factory.reset(&mut entry.conn).await;
+                        if !self.record_reset(entry.metadata.id, result) {
+                            self.inner.metrics.lock().connections_closed += 1;
+                            self.inner.destroy(entry.conn, &entry.metadata).await;
+                            continue;
+                        }
+                        entry.needs_reset = false;
+                    }
                     break Some(entry);
                 }
                 None => break None,
             }
         };
 
-        let (client, mut metadata) = match entry {
+        let (mut conn, mut metadata) = match entry {
             Some(mut entry) => {
                 tracing::trace!(connection_id = entry.metadata.id, "reusing idle connection");
 
-                // Perform health check if configured
-                if self.config.test_on_checkout {
-                    if !self
-                        .health_check(&mut entry.client, entry.metadata.id)
-                        .await
-                    {
-                        tracing::debug!(
-                            connection_id = entry.metadata.id,
-                            "discarding unhealthy connection, will create new"
-                        );
-                        self.inner.metrics.lock().connections_closed += 1;
-
-                        // Connection is unhealthy, create a new one instead
-                        let id = self.next_connection_id();
-                        tracing::debug!(
-                            connection_id = id,
-                            "creating new connection after health check failure"
-                        );
-
-                        match Client::connect(self.client_config.clone()).await {
-                            Ok(client) => {
-                                self.inner.total_connections.fetch_add(1, Ordering::Relaxed);
-                                self.inner.metrics.lock().connections_created += 1;
-                                (client, ConnectionMetadata::new(id))
-                            }
-                            Err(e) => {
-                                drop(permit);
-                                self.inner.metrics.lock().checkouts_failed += 1;
-                                return Err(PoolError::Connection(e.to_string()));
-                            }
+                // Perform pre-ping or health check if configured
+                if (self.config.test_before_acquire || self.config.test_on_checkout)
+                    && !self.health_check(&mut entry.conn, entry.metadata.id).await
+                {
+                    tracing::debug!(
+                        connection_id = entry.metadata.id,
+                        "discarding unhealthy connection, will create new"
+                    );
+                    self.inner.metrics.lock().connections_closed += 1;
+                    self.inner.destroy(entry.conn, &entry.metadata).await;
+
+                    // Connection is unhealthy, create a new one instead
+                    match self.create_connection().await {
+                        Ok(created) => created,
+                        Err(e) => {
+                            drop(permit);
+                            self.inner.metrics.lock().checkouts_failed += 1;
+                            return Err(e);
                         }
-                    } else {
-                        (entry.client, entry.metadata)
                     }
                 } else {
-                    (entry.client, entry.metadata)
+                    (entry.conn, entry.metadata)
                 }
             }
             None => {
                 // No idle connection, create a new one
-                let id = self.next_connection_id();
-                tracing::debug!(connection_id = id, "creating new connection");
-
-                match Client::connect(self.client_config.clone()).await {
-                    Ok(client) => {
-                        self.inner.total_connections.fetch_add(1, Ordering::Relaxed);
-                        self.inner.metrics.lock().connections_created += 1;
-                        (client, ConnectionMetadata::new(id))
-                    }
+                match self.create_connection().await {
+                    Ok(created) => created,
                     Err(e) => {
                         // Return the permit since we failed to create connection
                         drop(permit);
                         self.inner.metrics.lock().checkouts_failed += 1;
-                        return Err(PoolError::Connection(e.to_string()));
+                        return Err(e);
                     }
                 }
             }
         };
 
         // Mark as in use and record acquisition time
+        metadata.tag = tag;
+        let tag_epoch = metadata
+            .tag
+            .as_deref()
+            .map_or(0, |tag| self.inner.tag_epoch(tag));
         metadata.mark_checkout();
+        if let Err(e) = self.inner.before_checkout(&mut conn, &metadata).await {
+            tracing::debug!(
+                connection_id = metadata.id,
+                error = %e,
+                "before_checkout hook failed, discarding connection"
+            );
+            {
+                let mut metrics = self.inner.metrics.lock();
+                metrics.connections_closed += 1;
+                metrics.checkouts_failed += 1;
+            }
+            self.inner.destroy(conn, &metadata).await;
+            return Err(e);
+        }
         self.inner.in_use_count.fetch_add(1, Ordering::Relaxed);
 
         let acquisition_time_us = acquisition_start.elapsed().as_micros() as u64;
@@ -474,19 +793,27 @@ impl Pool {
         }
 
         Ok(PooledConnection {
-            client: Some(client),
+            conn: Some(conn),
             metadata,
+            tag_epoch,
             pool: self.inner.clone(),
-            client_config: self.client_config.clone(),
-            _permit: permit,
+            permit: Some(permit),
         })
     }
 
     /// Try to get a connection without waiting.
     ///
-    /// Returns `None` if no connections are immediately available.
+    /// Returns an idle connection if there is one and `None` otherwise.
     /// This is non-blocking and will not create new connections.
-    pub fn try_get(&self) -> Result<Option<PooledConnection>, PoolError> {
+    ///
+    /// If the idle connection needs a reset, the factory's `reset` is polled
+    /// once; a connection whose reset cannot complete immediately is discarded.
+    ///
+    /// The [`before_checkout`](crate::ConnectionLifecycle::before_checkout)
+    /// hooks are polled once as well. If they cannot complete without
+    /// waiting, the connection stays in the idle queue and `None` is
+    /// returned; use [`get`](Self::get) with such hooks.
+    pub fn try_get(&self) -> Result<Option<PooledConnection<F>>, PoolError> {
         if self.inner.closed.load(Ordering::Acquire) {
             return Err(PoolError::PoolClosed);
         }
@@ -501,15 +828,75 @@ impl Pool {
         };
 
         // Try to get an idle connection (non-blocking)
-        let entry = {
-            let mut idle = self.inner.idle_connections.lock();
-            idle.pop_front()
-        };
+        let entry = self.inner.take_idle(None);
 
         match entry {
-            Some(entry) => {
-                let mut metadata = entry.metadata;
+            Some(mut entry) => {
+                if self.inner.factory.is_broken(&entry.conn) {
+                    tracing::debug!(
+                        connection_id = entry.metadata.id,
+                        "try_get: discarding broken connection"
+                    );
+                    {
+                        let mut metrics = self.inner.metrics.lock();
+                        metrics.connections_closed += 1;
+                        metrics.connections_broken += 1;
+                    }
+                    self.inner.retire(entry.conn, entry.metadata);
+                    return Ok(None);
+                }
+
+                if entry.needs_reset {
+                    let mut cx = Context::from_waker(Waker::noop());
+                    let polled = self
+                        .inner
+                        .factory
+                        .reset(&mut entry.conn)
+                        .as_mut()
+                        .poll(&mut cx);
+                    let result = match polled {
+                        Poll::Ready(result) => result,
+                        Poll::Pending => Err(PoolError::ResetFailed(
+                            "reset did not complete without waiting".to_string(),
+                        )),
+                    };
+                    if !self.record_reset(entry.metadata.id, result) {
+                        self.inner.metrics.lock().connections_closed += 1;
+                        self.inner.retire(entry.conn, entry.metadata);
+                        return Ok(None);
+                    }
+                }
+
+                let mut metadata = entry.metadata.clone();
                 metadata.mark_checkout();
+
+                let mut cx = Context::from_waker(Waker::noop());
+                let polled = std::pin::pin!(self.inner.before_checkout(&mut entry.conn, &metadata))
+                    .poll(&mut cx);
+                match polled {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(e)) => {
+                        tracing::debug!(
+                            connection_id = metadata.id,
+                            error = %e,
+                            "try_get: before_checkout hook failed, discarding connection"
+                        );
+                        self.inner.metrics.lock().connections_closed += 1;
+                        self.inner.retire(entry.conn, metadata);
+                        return Err(e);
+                    }
+                    Poll::Pending => {
+                        // The hook has to wait; the connection is still
+                        // healthy, so leave it for `get()`
+                        tracing::trace!(
+                            connection_id = metadata.id,
+                            "try_get: before_checkout hook did not complete, leaving connection idle"
+                        );
+                        entry.needs_reset = false;
+                        self.inner.idle_connections.lock().push_front(entry);
+                        return Ok(None);
+                    }
+                }
                 self.inner.in_use_count.fetch_add(1, Ordering::Relaxed);
                 self.inner.metrics.lock().checkouts_successful += 1;
 
@@ -519,11 +906,11 @@ impl Pool {
                 );
 
                 Ok(Some(PooledConnection {
-                    client: Some(entry.client),
+                    conn: Some(entry.conn),
                     metadata,
+                    tag_epoch: 0,
                     pool: self.inner.clone(),
-                    client_config: self.client_config.clone(),
-                    _permit: permit,
+                    permit: Some(permit),
                 }))
             }
             None => {
@@ -576,6 +963,8 @@ impl Pool {
             resets_failed: inner.resets_failed,
             connections_idle_expired: inner.connections_idle_expired,
             connections_lifetime_expired: inner.connections_lifetime_expired,
+            connections_broken: inner.connections_broken,
+            connections_evicted: inner.connections_evicted,
             reaper_runs: inner.reaper_runs,
             peak_wait_queue_depth: inner.peak_wait_queue_depth,
             avg_acquisition_time_us,
@@ -583,6 +972,23 @@ impl Pool {
         }
     }
 
+    /// Copy the pool status and connection totals into a Prometheus collector.
+    ///
+    /// Call this from the `/metrics` handler before
+    /// [`PrometheusMetrics::render`](mssql_client::PrometheusMetrics::render).
+    #[cfg(feature = "prometheus")]
+    pub fn export_prometheus(&self, metrics: &mssql_client::PrometheusMetrics) {
+        let status = self.status();
+        metrics.record_pool_status(
+            u64::from(status.in_use),
+            u64::from(status.available),
+            u64::from(status.max),
+        );
+
+        let totals = self.metrics();
+        metrics.set_connection_totals(totals.connections_created, totals.connections_closed);
+    }
+
     /// Close the pool, dropping all connections.
     pub async fn close(&self) {
         self.inner.closed.store(true, Ordering::Release);
@@ -601,6 +1007,12 @@ impl Pool {
         &self.config
     }
 
+    /// Get the connection factory used by this pool.
+    #[must_use]
+    pub fn factory(&self) -> &F {
+        &self.inner.factory
+    }
+
     /// Generate a new unique connection ID.
     fn next_connection_id(&self) -> u64 {
         self.inner
@@ -608,21 +1020,148 @@ impl Pool {
             .fetch_add(1, Ordering::Relaxed)
     }
 
+    /// Create a new connection through the factory.
+    async fn create_connection(&self) -> Result<(F::Connection, ConnectionMetadata), PoolError> {
+        let id = self.next_connection_id();
+        tracing::debug!(connection_id = id, "creating new connection");
+
+        let created = self.open_connection(id).await?;
+        self.inner.total_connections.fetch_add(1, Ordering::Relaxed);
+        self.inner.metrics.lock().connections_created += 1;
+        Ok(created)
+    }
+
+    /// Open a connection and run the `after_create` hooks on it.
+    ///
+    /// A connection whose hook fails is closed without `before_destroy`.
+    async fn open_connection(
+        &self,
+        id: u64,
+    ) -> Result<(F::Connection, ConnectionMetadata), PoolError> {
+        let mut conn = self.connect().await?;
+        let metadata = ConnectionMetadata::new(id)
+            .with_expiry_jitter(random_duration(self.config.expiry_jitter));
+        for lifecycle in &self.inner.lifecycles {
+            if let Err(e) = lifecycle.after_create(&mut conn, &metadata).await {
+                tracing::debug!(
+                    connection_id = id,
+                    error = %e,
+                    "after_create hook failed, closing connection"
+                );
+                self.inner.factory.close(conn).await;
+                return Err(e);
+            }
+        }
+        Ok((conn, metadata))
+    }
+
+    /// Open a connection through the factory, subject to the circuit breaker.
+    ///
+    /// The `before_create` hooks run right before the factory is called.
+    ///
+    /// Connection errors from the factory are reported as
+    /// [`PoolError::CreateFailed`] with the number of consecutive failures.
+    /// Once the breaker is open, attempts fail with
+    /// [`PoolError::CircuitOpen`] until the cooldown has passed; then one
+    /// attempt is let through, and the breaker closes if it succeeds.
+    async fn connect(&self) -> Result<F::Connection, PoolError> {
+        let threshold = self.config.circuit_breaker_threshold;
+        let cooldown = self.config.circuit_breaker_cooldown;
+        if threshold > 0 {
+            let mut failures = self.inner.create_failures.lock();
+            if let Some(until) = failures.open_until {
+                let now = Instant::now();
+                if now < until {
+                    return Err(PoolError::CircuitOpen {
+                        retry_after: until - now,
+                    });
+                }
+                // Half-open: hold the breaker shut for everyone else while
+                // this attempt probes the server
+                failures.open_until = Some(now + cooldown);
+            }
+        }
+
+        for lifecycle in &self.inner.lifecycles {
+            lifecycle.before_create().await?;
+        }
+
+        match self.inner.factory.create().await {
+            Ok(conn) => {
+                let mut failures = self.inner.create_failures.lock();
+                if failures.open_until.is_some() {
+                    tracing::info!("connection succeeded, closing circuit breaker");
+                }
+                *failures = CreateFailures::default();
+                Ok(conn)
+            }
+            Err(e) => {
+                let mut failures = self.inner.create_failures.lock();
+                failures.consecutive = failures.consecutive.saturating_add(1);
+                let attempt = failures.consecutive;
+                if threshold > 0 && attempt >= threshold {
+                    if failures.open_until.is_none() {
+                        tracing::warn!(
+                            failures = attempt,
+                            cooldown_ms = cooldown.as_millis() as u64,
+                            "opening circuit breaker after repeated connection failures"
+                        );
+                    }
+                    failures.open_until = Some(Instant::now() + cooldown);
+                }
+                Err(match e {
+                    PoolError::Connection(source) => PoolError::CreateFailed { attempt, source },
+                    e => e,
+                })
+            }
+        }
+    }
+
+    /// Record the outcome of a connection reset.
+    ///
+    /// Returns `true` if the reset succeeded.
+    fn record_reset(&self, connection_id: u64, result: Result<(), PoolError>) -> bool {
+        let mut metrics = self.inner.metrics.lock();
+        metrics.resets_performed += 1;
+        match result {
+            Ok(()) => {
+                tracing::trace!(connection_id = connection_id, "connection reset");
+                true
+            }
+            Err(e) => {
+                tracing::debug!(
+                    connection_id = connection_id,
+                    error = %e,
+                    "connection reset failed, discarding"
+                );
+                metrics.resets_failed += 1;
+                false
+            }
+        }
+    }
+
     /// Perform a health check on a connection.
     ///
+    /// Uses the factory's lightweight ping when `test_before_acquire` is
+    /// enabled, and the configured health check query otherwise.
+    ///
     /// Returns `true` if the connection is healthy, `false` otherwise.
-    async fn health_check(&self, client: &mut Client<Ready>, connection_id: u64) -> bool {
-        let health_query = &*self.config.health_check_query;
-        tracing::trace!(
-            connection_id = connection_id,
-            query = %health_query,
-            "performing health check"
-        );
+    async fn health_check(&self, conn: &mut F::Connection, connection_id: u64) -> bool {
+        let result = if self.config.test_before_acquire {
+            tracing::trace!(connection_id = connection_id, "performing pre-ping");
+            self.inner.factory.ping(conn).await
+        } else {
+            let health_query = &*self.config.health_check_query;
+            tracing::trace!(
+                connection_id = connection_id,
+                query = %health_query,
+                "performing health check"
+            );
+            self.inner.factory.validate(conn, health_query).await
+        };
 
-        match client.query(health_query, &[]).await {
-            Ok(rows) => {
-                // Consume the result set
-                for _ in rows {}
+        match result {
+            Ok(()) => {
                 tracing::trace!(connection_id = connection_id, "health check passed");
                 self.inner.metrics.lock().health_checks_performed += 1;
                 true
@@ -642,6 +1181,17 @@ impl Pool {
     }
 }
 
+/// Pick a random duration between zero and `max`, inclusive.
+fn random_duration(max: Duration) -> Duration {
+    let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+    if max_nanos == 0 {
+        return Duration::ZERO;
+    }
+    // Every RandomState is seeded differently, which is all jitter needs
+    let random = std::hash::RandomState::new().hash_one(());
+    Duration::from_nanos(random % max_nanos.saturating_add(1))
+}
+
 /// Builder for creating a connection pool.
 ///
 /// # Example
@@ -656,6 +1206,7 @@ impl Pool {
 pub struct PoolBuilder {
     pool_config: PoolConfig,
     client_config: Option<ClientConfig>,
+    lifecycles: Vec<Box<dyn DynConnectionLifecycle<Client<Ready>>>>,
 }
 
 impl PoolBuilder {
@@ -664,6 +1215,7 @@ impl PoolBuilder {
         Self {
             pool_config: PoolConfig::default(),
             client_config: None,
+            lifecycles: Vec::new(),
         }
     }
 
@@ -709,6 +1261,15 @@ impl PoolBuilder {
         self
     }
 
+    /// Spread connection expiry over time.
+    ///
+    /// See [`PoolConfig::expiry_jitter`].
+    #[must_use]
+    pub fn expiry_jitter(mut self, jitter: std::time::Duration) -> Self {
+        self.pool_config.expiry_jitter = jitter;
+        self
+    }
+
     /// Enable or disable `sp_reset_connection` on return.
     #[must_use]
     pub fn sp_reset_connection(mut self, enabled: bool) -> Self {
@@ -716,6 +1277,77 @@ impl PoolBuilder {
         self
     }
 
+    /// Enable or disable creating `min_connections` at build time.
+    ///
+    /// When enabled, `build()` fails if a connection cannot be established,
+    /// unless [`lazy`](Self::lazy) is also set.
+    #[must_use]
+    pub fn warm_up(mut self, enabled: bool) -> Self {
+        self.pool_config.warm_up = enabled;
+        self
+    }
+
+    /// Tolerate warm-up failures, creating connections on demand instead.
+    #[must_use]
+    pub fn lazy(mut self, enabled: bool) -> Self {
+        self.pool_config.lazy = enabled;
+        self
+    }
+
+    /// Enable or disable a lightweight pre-ping on checkout.
+    ///
+    /// Replaces the full health check query run by `test_on_checkout`.
+    #[must_use]
+    pub fn test_before_acquire(mut self, enabled: bool) -> Self {
+        self.pool_config.test_before_acquire = enabled;
+        self
+    }
+
+    /// Fail fast after `threshold` consecutive connection failures.
+    ///
+    /// See [`PoolConfig::circuit_breaker`].
+    #[must_use]
+    pub fn circuit_breaker(mut self, threshold: u32, cooldown: std::time::Duration) -> Self {
+        self.pool_config = self.pool_config.circuit_breaker(threshold, cooldown);
+        self
+    }
+
+    /// Add hooks run at each point of a connection's life.
+    ///
+    /// Lifecycles run in the order they are added; see the
+    /// [`lifecycle`](crate::lifecycle) module for when each hook runs.
+    #[must_use]
+    pub fn lifecycle(mut self, lifecycle: impl ConnectionLifecycle<Client<Ready>>) -> Self {
+        self.lifecycles.push(Box::new(lifecycle));
+        self
+    }
+
+    /// Prepare `statements` on every new connection, before first use.
+    ///
+    /// Each statement is prepared with [`Client::prepare`], so the first
+    /// request for it on a new connection runs with `sp_execute` instead of
+    /// compiling it. The statements take no parameters; for parameterized
+    /// statements, call [`Client::prepare`] with sample values from an
+    /// `after_create` [`lifecycle`](Self::lifecycle) hook.
+    ///
+    /// A statement that fails to prepare is logged and skipped. A connection
+    /// reset discards prepared statements, so the benefit lasts until the
+    /// connection's first reset.
+    ///
+    /// ```rust,ignore
+    /// let pool = Pool::builder()
+    ///     .client_config(config)
+    ///     .prepare_on_create(&["SELECT Id, Name FROM dbo.Regions"])
+    ///     .build()
+    ///     .await?;
+    /// ```
+    #[must_use]
+    pub fn prepare_on_create(self, statements: &[&str]) -> Self {
+        self.lifecycle(PrepareStatements {
+            statements: statements.iter().map(|s| s.to_string()).collect(),
+        })
+    }
+
     /// Build the pool.
     ///
     /// # Errors
@@ -725,7 +1357,49 @@ impl PoolBuilder {
         let client_config = self
             .client_config
             .ok_or_else(|| PoolError::Configuration("client_config is required".to_string()))?;
-        Pool::new(self.pool_config, client_config).await
+        Pool::with_lifecycles(
+            self.pool_config,
+            MssqlConnectionFactory::new(client_config),
+            self.lifecycles,
+        )
+        .await
+    }
+
+    /// Build the pool using a custom connection factory.
+    ///
+    /// Any `client_config` or `lifecycle` set on the builder is ignored; pass
+    /// lifecycles for a custom factory to [`Pool::with_lifecycles`].
+    pub async fn build_with_factory<F: ConnectionFactory>(
+        self,
+        factory: F,
+    ) -> Result<Pool<F>, PoolError> {
+        Pool::with_factory(self.pool_config, factory).await
+    }
+}
+
+/// Lifecycle preparing statements on new connections, for
+/// [`PoolBuilder::prepare_on_create`].
+struct PrepareStatements {
+    statements: Vec<String>,
+}
+
+impl ConnectionLifecycle<Client<Ready>> for PrepareStatements {
+    async fn after_create(
+        &self,
+        conn: &mut Client<Ready>,
+        metadata: &ConnectionMetadata,
+    ) -> Result<(), PoolError> {
+        for sql in &self.statements {
+            if let Err(e) = conn.prepare(sql, &[]).await {
+                tracing::warn!(
+                    connection_id = metadata.id,
+                    sql = %sql,
+                    error = %e,
+                    "failed to prepare statement on new connection"
+                );
+            }
+        }
+        Ok(())
     }
 }
 
@@ -804,6 +1478,12 @@ pub struct PoolMetrics {
     pub connections_idle_expired: u64,
     /// Connections closed due to max lifetime expiration.
     pub connections_lifetime_expired: u64,
+    /// Connections discarded without a health check because
+    /// [`ConnectionFactory::is_broken`](crate::ConnectionFactory::is_broken)
+    /// reported them dead.
+    pub connections_broken: u64,
+    /// Connections closed by [`Pool::evict_tag`].
+    pub connections_evicted: u64,
     /// Number of reaper task runs.
     pub reaper_runs: u64,
     /// Peak wait queue depth observed.
@@ -846,45 +1526,65 @@ impl PoolMetrics {
 ///
 /// When dropped, the connection is automatically returned to the pool.
 /// Use [`detach()`](PooledConnection::detach) to prevent automatic return.
-pub struct PooledConnection {
-    /// The actual client connection (Option to allow taking on drop).
-    client: Option<Client<Ready>>,
+pub struct PooledConnection<F: ConnectionFactory = MssqlConnectionFactory> {
+    /// The actual connection (Option to allow taking on drop).
+    conn: Option<F::Connection>,
     /// Connection metadata.
     metadata: ConnectionMetadata,
+    /// Eviction epoch of the connection's tag at checkout.
+    tag_epoch: u64,
     /// Reference to the pool for returning the connection.
-    pool: Arc<PoolInner>,
-    /// Client config for reconnection if needed.
-    #[allow(dead_code)] // Will be used for reconnection logic
-    client_config: ClientConfig,
+    pool: Arc<PoolInner<F>>,
     /// Semaphore permit (released when connection returns to pool).
-    _permit: OwnedSemaphorePermit,
+    permit: Option<OwnedSemaphorePermit>,
 }
 
-impl PooledConnection {
+impl<F: ConnectionFactory> PooledConnection<F> {
     /// Get the connection metadata.
     #[must_use]
     pub fn metadata(&self) -> &ConnectionMetadata {
         &self.metadata
     }
 
-    /// Get a reference to the underlying client.
+    /// Get the tag the connection was checked out with by
+    /// [`Pool::get_tagged`].
     #[must_use]
-    pub fn client(&self) -> Option<&Client<Ready>> {
-        self.client.as_ref()
+    pub fn tag(&self) -> Option<&str> {
+        self.metadata.tag.as_deref()
     }
 
-    /// Get a mutable reference to the underlying client.
+    /// Get a reference to the underlying connection.
     #[must_use]
-    pub fn client_mut(&mut self) -> Option<&mut Client<Ready>> {
-        self.client.as_mut()
+    pub fn connection(&self) -> Option<&F::Connection> {
+        self.conn.as_ref()
+    }
+
+    /// Get a mutable reference to the underlying connection.
+    #[must_use]
+    pub fn connection_mut(&mut self) -> Option<&mut F::Connection> {
+        self.conn.as_mut()
     }
 
     /// Detach the connection from the pool.
     ///
-    /// Returns the underlying client. The connection will not be returned
+    /// Returns the underlying connection. The connection will not be returned
     /// to the pool when this `PooledConnection` is dropped.
-    pub fn detach(mut self) -> Option<Client<Ready>> {
-        self.client.take()
+    pub fn detach(mut self) -> Option<F::Connection> {
+        self.conn.take()
+    }
+}
+
+impl PooledConnection {
+    /// Get a reference to the underlying client.
+    #[must_use]
+    pub fn client(&self) -> Option<&Client<Ready>> {
+        self.conn.as_ref()
+    }
+
+    /// Get a mutable reference to the underlying client.
+    #[must_use]
+    pub fn client_mut(&mut self) -> Option<&mut Client<Ready>> {
+        self.conn.as_mut()
     }
 
     /// Execute a query on this pooled connection.
@@ -893,13 +1593,11 @@ impl PooledConnection {
         sql: &str,
         params: &[&(dyn mssql_client::ToSql + Sync)],
     ) -> Result<mssql_client::QueryStream<'a>, PoolError> {
-        let client = self.client.as_mut().ok_or(PoolError::Connection(
-            "connection detached or invalid".to_string(),
-        ))?;
-        client
-            .query(sql, params)
-            .await
-            .map_err(|e| PoolError::Connection(e.to_string()))
+        let client = self
+            .conn
+            .as_mut()
+            .ok_or(PoolError::Connection(mssql_client::Error::ConnectionClosed))?;
+        Ok(client.query(sql, params).await?)
     }
 
     /// Execute a statement on this pooled connection.
@@ -908,34 +1606,59 @@ impl PooledConnection {
         sql: &str,
         params: &[&(dyn mssql_client::ToSql + Sync)],
     ) -> Result<u64, PoolError> {
-        let client = self.client.as_mut().ok_or(PoolError::Connection(
-            "connection detached or invalid".to_string(),
-        ))?;
-        client
-            .execute(sql, params)
-            .await
-            .map_err(|e| PoolError::Connection(e.to_string()))
+        let client = self
+            .conn
+            .as_mut()
+            .ok_or(PoolError::Connection(mssql_client::Error::ConnectionClosed))?;
+        Ok(client.execute(sql, params).await?)
     }
 }
 
-impl Drop for PooledConnection {
+impl<F: ConnectionFactory> Drop for PooledConnection<F> {
     fn drop(&mut self) {
         // Always decrement in_use_count since it was incremented during checkout.
         // This handles both normal returns and detached connections.
         self.pool.in_use_count.fetch_sub(1, Ordering::Relaxed);
 
-        if let Some(mut client) = self.client.take() {
-            // Check if connection is in a transaction started via raw SQL.
-            // If so, we cannot safely return it to the pool because:
-            // 1. Drop is sync, so we can't execute ROLLBACK
-            // 2. The next user would get a connection mid-transaction
-            // Instead, we discard the connection entirely.
-            if client.is_in_transaction() {
+        if let Some(conn) = self.conn.take() {
+            if self.pool.factory.is_broken(&conn) {
+                tracing::debug!(
+                    connection_id = self.metadata.id,
+                    "connection returned to pool broken - discarding"
+                );
+                {
+                    let mut metrics = self.pool.metrics.lock();
+                    metrics.connections_closed += 1;
+                    metrics.connections_broken += 1;
+                }
+                self.pool.retire(conn, self.metadata.clone());
+                return;
+            }
+
+            // The factory decides whether the connection left usable state
+            // behind (e.g. a transaction started via raw SQL). Drop is sync,
+            // so such connections cannot be repaired here and are discarded.
+            if !self.pool.factory.is_reusable(&conn) {
                 tracing::warn!(
                     connection_id = self.metadata.id,
-                    "connection returned to pool with active transaction - discarding"
+                    "connection returned to pool in a non-reusable state - discarding"
+                );
+                self.pool.metrics.lock().connections_closed += 1;
+                self.pool.retire(conn, self.metadata.clone());
+                return;
+            }
+
+            if self.pool.is_evicted(&self.metadata, self.tag_epoch) {
+                tracing::debug!(
+                    connection_id = self.metadata.id,
+                    "connection tag evicted while checked out - discarding"
                 );
-                // Connection is dropped here, not returned to pool
+                {
+                    let mut metrics = self.pool.metrics.lock();
+                    metrics.connections_closed += 1;
+                    metrics.connections_evicted += 1;
+                }
+                self.pool.retire(conn, self.metadata.clone());
                 return;
             }
 
@@ -944,36 +1667,43 @@ impl Drop for PooledConnection {
                 "returning connection to pool"
             );
 
-            // Mark connection for reset on next use if sp_reset_connection is enabled.
-            // This sets the RESETCONNECTION flag on the first TDS packet of the next
-            // request, causing SQL Server to reset connection state (temp tables,
-            // SET options, isolation level, etc.) before executing.
-            if self.pool.config.sp_reset_connection {
-                client.mark_needs_reset();
-                self.pool.metrics.lock().resets_performed += 1;
-                tracing::trace!(
-                    connection_id = self.metadata.id,
-                    "marked connection for reset on next use"
-                );
-            }
-
             // Update metadata for checkin
             self.metadata.mark_checkin();
 
-            // Return connection to idle queue
+            // Return connection to idle queue. If sp_reset_connection is
+            // enabled, the factory resets it before the next checkout.
             let entry = PooledEntry {
-                client,
+                conn,
                 metadata: self.metadata.clone(),
+                needs_reset: self.pool.config.sp_reset_connection,
+                tag_epoch: self.tag_epoch,
             };
 
-            self.pool.idle_connections.lock().push_back(entry);
+            if self.pool.lifecycles.is_empty() {
+                self.pool.idle_connections.lock().push_back(entry);
+            } else if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                // The permit travels with the connection so it is not handed
+                // out again before the after_return hooks finish
+                let pool = Arc::clone(&self.pool);
+                let permit = self.permit.take();
+                handle.spawn(async move {
+                    pool.check_in(entry).await;
+                    drop(permit);
+                });
+            } else {
+                tracing::debug!(
+                    connection_id = self.metadata.id,
+                    "no runtime to run after_return hooks - discarding connection"
+                );
+                self.pool.metrics.lock().connections_closed += 1;
+            }
         } else {
             tracing::trace!(
                 connection_id = self.metadata.id,
                 "connection detached, not returning to pool"
             );
         }
-        // Note: the semaphore permit is automatically released when _permit is dropped
+        // Note: the semaphore permit is automatically released when `permit` is dropped
     }
 }
 
@@ -1049,6 +1779,8 @@ mod tests {
             resets_failed: 2,
             connections_idle_expired: 1,
             connections_lifetime_expired: 1,
+            connections_broken: 0,
+            connections_evicted: 0,
             reaper_runs: 5,
             peak_wait_queue_depth: 3,
             avg_acquisition_time_us: 500,
@@ -1066,6 +1798,12 @@ mod tests {
         assert_eq!(builder.pool_config.max_connections, 10);
     }
 
+    #[test]
+    fn test_builder_prepare_on_create() {
+        let builder = PoolBuilder::new().prepare_on_create(&["SELECT 1", "SELECT 2"]);
+        assert_eq!(builder.lifecycles.len(), 1);
+    }
+
     #[test]
     fn test_builder_fluent() {
         let builder = Pool::builder()
@@ -1077,4 +1815,542 @@ mod tests {
         assert_eq!(builder.pool_config.max_connections, 50);
         assert!(!builder.pool_config.sp_reset_connection);
     }
+
+    fn mock_config() -> PoolConfig {
+        PoolConfig::new().min_connections(0).max_connections(2)
+    }
+
+    #[tokio::test]
+    async fn test_mock_factory_reuses_connection() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::with_factory(mock_config(), factory.clone())
+            .await
+            .unwrap();
+
+        let first_id = {
+            let conn = pool.get().await.unwrap();
+            conn.connection().unwrap().id()
+        };
+        let conn = pool.get().await.unwrap();
+
+        assert_eq!(conn.connection().unwrap().id(), first_id);
+        assert_eq!(factory.created(), 1);
+        // sp_reset_connection is enabled by default
+        assert_eq!(conn.connection().unwrap().reset_count(), 1);
+        assert_eq!(pool.metrics().resets_performed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_factory_discards_non_reusable() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::with_factory(mock_config(), factory.clone())
+            .await
+            .unwrap();
+
+        {
+            let mut conn = pool.get().await.unwrap();
+            conn.connection_mut().unwrap().set_in_transaction(true);
+        }
+
+        assert_eq!(pool.status().available, 0);
+        tokio::task::yield_now().await;
+        assert_eq!(factory.closed(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_factory_discards_broken_without_health_check() {
+        let factory = crate::MockConnectionFactory::new();
+        let config = mock_config().test_on_checkout(true);
+        let pool = Pool::with_factory(config, factory.clone()).await.unwrap();
+
+        {
+            let conn = pool.get().await.unwrap();
+            conn.connection().unwrap().close_socket();
+        }
+        tokio::task::yield_now().await;
+        assert_eq!(factory.closed(), 1);
+
+        let conn = pool.get().await.unwrap();
+        assert_eq!(conn.connection().unwrap().id(), 2);
+        let metrics = pool.metrics();
+        assert_eq!(metrics.connections_broken, 1);
+        assert_eq!(metrics.health_checks_performed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_factory_replaces_unhealthy() {
+        let factory = crate::MockConnectionFactory::new();
+        let config = mock_config().test_on_checkout(true);
+        let pool = Pool::with_factory(config, factory.clone()).await.unwrap();
+
+        drop(pool.get().await.unwrap());
+        factory.set_fail_validate(true);
+        let conn = pool.get().await.unwrap();
+
+        assert_eq!(conn.connection().unwrap().id(), 2);
+        assert_eq!(factory.closed(), 1);
+        assert_eq!(pool.metrics().health_checks_failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_factory_create_failure() {
+        let factory = crate::MockConnectionFactory::new();
+        factory.set_fail_create(true);
+        let pool = Pool::with_factory(mock_config(), factory).await.unwrap();
+
+        assert!(matches!(
+            pool.get().await,
+            Err(PoolError::CreateFailed { attempt: 1, .. })
+        ));
+        assert!(matches!(
+            pool.get().await,
+            Err(PoolError::CreateFailed { attempt: 2, .. })
+        ));
+        assert_eq!(pool.metrics().checkouts_failed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let factory = crate::MockConnectionFactory::new();
+        factory.set_fail_create(true);
+        let config = mock_config().circuit_breaker(2, Duration::from_millis(50));
+        let pool = Pool::with_factory(config, factory.clone()).await.unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                pool.get().await,
+                Err(PoolError::CreateFailed { .. })
+            ));
+        }
+        let err = pool.get().await.err().unwrap();
+        assert!(
+            matches!(err, PoolError::CircuitOpen { retry_after } if retry_after <= Duration::from_millis(50))
+        );
+        assert!(err.is_server_unavailable());
+
+        // After the cooldown one attempt probes the server and closes the
+        // breaker again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        factory.set_fail_create(false);
+        drop(pool.get().await.unwrap());
+        assert_eq!(factory.created(), 1);
+    }
+
+    #[test]
+    fn test_random_duration() {
+        assert_eq!(random_duration(Duration::ZERO), Duration::ZERO);
+        let max = Duration::from_secs(10);
+        let samples: Vec<_> = (0..32).map(|_| random_duration(max)).collect();
+        assert!(samples.iter().all(|d| *d <= max));
+        assert!(samples.iter().any(|d| *d != samples[0]));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_jitter_per_connection() {
+        let factory = crate::MockConnectionFactory::new();
+        let config = mock_config()
+            .max_connections(4)
+            .expiry_jitter(Duration::from_secs(60));
+        let pool = Pool::with_factory(config, factory).await.unwrap();
+
+        let mut conns = Vec::new();
+        for _ in 0..4 {
+            conns.push(pool.get().await.unwrap());
+        }
+        let jitters: Vec<_> = conns.iter().map(|c| c.metadata().expiry_jitter).collect();
+        assert!(jitters.iter().all(|j| *j <= Duration::from_secs(60)));
+        assert!(jitters.iter().any(|j| *j != jitters[0]));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_reports_wait() {
+        let factory = crate::MockConnectionFactory::new();
+        let config = mock_config()
+            .max_connections(1)
+            .connection_timeout(Duration::from_millis(20));
+        let pool = Pool::with_factory(config, factory).await.unwrap();
+
+        let _held = pool.get().await.unwrap();
+        let err = pool.get().await.err().unwrap();
+        assert!(matches!(
+            err,
+            PoolError::Timeout { waited, queue_depth: 1 } if waited == Duration::from_millis(20)
+        ));
+        assert!(err.is_capacity_exhausted());
+    }
+
+    #[tokio::test]
+    async fn test_get_timeout_overrides_default() {
+        let factory = crate::MockConnectionFactory::new();
+        let config = mock_config()
+            .max_connections(1)
+            .connection_timeout(Duration::from_secs(30));
+        let pool = Pool::with_factory(config, factory).await.unwrap();
+
+        let held = pool.get_timeout(Duration::from_millis(5)).await.unwrap();
+        let err = pool
+            .get_timeout(Duration::from_millis(5))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            PoolError::Timeout { waited, .. } if waited == Duration::from_millis(5)
+        ));
+
+        drop(held);
+        assert!(pool.get_timeout(Duration::from_millis(5)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failed_warm_up_closes_opened_connections() {
+        let factory = crate::MockConnectionFactory::new();
+        factory.fail_create_after(2);
+        let config = PoolConfig::new().min_connections(3).max_connections(4);
+
+        let result = Pool::with_factory(config, factory.clone()).await;
+
+        assert!(matches!(result, Err(PoolError::CreateFailed { .. })));
+        assert_eq!(factory.created(), 2);
+        assert_eq!(factory.closed(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_try_get_resets_idle_connection() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::with_factory(mock_config(), factory).await.unwrap();
+
+        assert!(pool.try_get().unwrap().is_none());
+        drop(pool.get().await.unwrap());
+        let conn = pool.try_get().unwrap().unwrap();
+
+        assert_eq!(conn.connection().unwrap().reset_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_tagged_reuses_session() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::with_factory(mock_config(), factory.clone())
+            .await
+            .unwrap();
+
+        let first = pool.get_tagged("tenant-42").await.unwrap();
+        assert_eq!(first.tag(), Some("tenant-42"));
+        let first_id = first.connection().unwrap().id();
+        drop(pool.get_tagged("tenant-7").await.unwrap());
+        drop(first);
+
+        // Same tag: the tenant's session state carries over
+        let conn = pool.get_tagged("tenant-42").await.unwrap();
+        assert_eq!(conn.connection().unwrap().id(), first_id);
+        assert_eq!(conn.connection().unwrap().reset_count(), 0);
+        assert_eq!(factory.created(), 2);
+        drop(conn);
+
+        // Another tag's connection is reset before it is repurposed
+        let conn = pool.get().await.unwrap();
+        assert_eq!(conn.tag(), None);
+        assert_eq!(conn.connection().unwrap().reset_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evict_tag() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::with_factory(mock_config(), factory.clone())
+            .await
+            .unwrap();
+
+        drop(pool.get_tagged("tenant-42").await.unwrap());
+        let in_use = pool.get_tagged("tenant-42").await.unwrap();
+        let other = pool.get_tagged("tenant-7").await.unwrap();
+        drop(other);
+        assert_eq!(factory.created(), 2);
+
+        // Only the idle tenant-7 connection stays; tenant-42 is in use
+        assert_eq!(pool.evict_tag("tenant-42").await, 0);
+        assert_eq!(pool.evict_tag("tenant-7").await, 1);
+        assert_eq!(factory.closed(), 1);
+
+        drop(in_use);
+        tokio::task::yield_now().await;
+        assert_eq!(factory.closed(), 2);
+        assert_eq!(pool.status().available, 0);
+        assert_eq!(pool.metrics().connections_evicted, 2);
+
+        // The tag can be used again with fresh connections
+        let conn = pool.get_tagged("tenant-42").await.unwrap();
+        assert_eq!(factory.created(), 3);
+        drop(conn);
+        assert_eq!(pool.status().available, 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_fails_fast() {
+        let factory = crate::MockConnectionFactory::new();
+        factory.set_fail_create(true);
+        let result = Pool::builder()
+            .min_connections(2)
+            .build_with_factory(factory)
+            .await;
+
+        assert!(matches!(result, Err(PoolError::CreateFailed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_lazy_tolerates_failure() {
+        let factory = crate::MockConnectionFactory::new();
+        factory.set_fail_create(true);
+        let pool = Pool::builder()
+            .min_connections(2)
+            .lazy(true)
+            .build_with_factory(factory)
+            .await
+            .unwrap();
+
+        assert_eq!(pool.status().available, 0);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_disabled() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::builder()
+            .min_connections(2)
+            .warm_up(false)
+            .build_with_factory(factory.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(factory.created(), 0);
+        assert_eq!(pool.status().available, 0);
+    }
+
+    #[tokio::test]
+    async fn test_before_acquire_uses_ping() {
+        let factory = crate::MockConnectionFactory::new();
+        let pool = Pool::builder()
+            .min_connections(1)
+            .test_before_acquire(true)
+            .build_with_factory(factory.clone())
+            .await
+            .unwrap();
+
+        let _conn = pool.get().await.unwrap();
+
+        assert_eq!(factory.pings(), 1);
+        assert_eq!(pool.metrics().health_checks_performed, 1);
+    }
+
+    /// Records every hook it runs, failing the hook named by `fail_at`.
+    struct Recorder {
+        name: &'static str,
+        fail_at: Option<&'static str>,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn record(&self, hook: &'static str, id: Option<u64>) -> Result<(), PoolError> {
+            let event = match id {
+                Some(id) => format!("{}:{hook}:{id}", self.name),
+                None => format!("{}:{hook}", self.name),
+            };
+            self.events.lock().push(event);
+            if self.fail_at == Some(hook) {
+                return Err(PoolError::ValidationFailed(hook.to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    impl ConnectionLifecycle<crate::MockConnection> for Recorder {
+        async fn before_create(&self) -> Result<(), PoolError> {
+            self.record("before_create", None)
+        }
+
+        async fn after_create(
+            &self,
+            _conn: &mut crate::MockConnection,
+            metadata: &ConnectionMetadata,
+        ) -> Result<(), PoolError> {
+            self.record("after_create", Some(metadata.id))
+        }
+
+        async fn before_checkout(
+            &self,
+            _conn: &mut crate::MockConnection,
+            metadata: &ConnectionMetadata,
+        ) -> Result<(), PoolError> {
+            self.record("before_checkout", Some(metadata.id))
+        }
+
+        async fn after_return(
+            &self,
+            _conn: &mut crate::MockConnection,
+            metadata: &ConnectionMetadata,
+        ) -> Result<(), PoolError> {
+            self.record("after_return", Some(metadata.id))
+        }
+
+        async fn before_destroy(
+            &self,
+            _conn: &mut crate::MockConnection,
+            metadata: &ConnectionMetadata,
+        ) {
+            let _ = self.record("before_destroy", Some(metadata.id));
+        }
+    }
+
+    async fn recorded_pool(
+        fail_at: Option<&'static str>,
+    ) -> (
+        Pool<crate::MockConnectionFactory>,
+        crate::MockConnectionFactory,
+        Arc<Mutex<Vec<String>>>,
+    ) {
+        let factory = crate::MockConnectionFactory::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let lifecycles: Vec<Box<dyn DynConnectionLifecycle<crate::MockConnection>>> = vec![
+            Box::new(Recorder {
+                name: "a",
+                fail_at,
+                events: Arc::clone(&events),
+            }),
+            Box::new(Recorder {
+                name: "b",
+                fail_at: None,
+                events: Arc::clone(&events),
+            }),
+        ];
+        let pool = Pool::with_lifecycles(mock_config(), factory.clone(), lifecycles)
+            .await
+            .unwrap();
+        (pool, factory, events)
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_hook_order() {
+        let (pool, _factory, events) = recorded_pool(None).await;
+
+        drop(pool.get().await.unwrap());
+        tokio::task::yield_now().await;
+        let conn = pool.get().await.unwrap();
+        conn.connection().unwrap().close_socket();
+        drop(conn);
+        tokio::task::yield_now().await;
+
+        assert_eq!(
+            *events.lock(),
+            [
+                "a:before_create",
+                "b:before_create",
+                "a:after_create:1",
+                "b:after_create:1",
+                "a:before_checkout:1",
+                "b:before_checkout:1",
+                "a:after_return:1",
+                "b:after_return:1",
+                "a:before_checkout:1",
+                "b:before_checkout:1",
+                "a:before_destroy:1",
+                "b:before_destroy:1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_before_create_error() {
+        let (pool, factory, events) = recorded_pool(Some("before_create")).await;
+
+        let err = pool.get().await.err().unwrap();
+        assert!(matches!(err, PoolError::ValidationFailed(_)));
+        assert_eq!(factory.created(), 0);
+        assert_eq!(*events.lock(), ["a:before_create"]);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_after_create_error() {
+        let (pool, factory, events) = recorded_pool(Some("after_create")).await;
+
+        assert!(pool.get().await.is_err());
+        assert_eq!(factory.closed(), 1);
+        assert_eq!(
+            *events.lock(),
+            ["a:before_create", "b:before_create", "a:after_create:1"]
+        );
+        assert_eq!(pool.metrics().connections_created, 0);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_before_checkout_error() {
+        let (pool, factory, events) = recorded_pool(Some("before_checkout")).await;
+
+        assert!(pool.get().await.is_err());
+        assert_eq!(factory.closed(), 1);
+        assert_eq!(
+            events.lock()[4..],
+            [
+                "a:before_checkout:1",
+                "a:before_destroy:1",
+                "b:before_destroy:1"
+            ]
+        );
+        assert_eq!(pool.metrics().checkouts_failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_after_return_error() {
+        let (pool, factory, events) = recorded_pool(Some("after_return")).await;
+
+        drop(pool.get().await.unwrap());
+        tokio::task::yield_now().await;
+
+        assert_eq!(factory.closed(), 1);
+        assert_eq!(pool.status().available, 0);
+        assert_eq!(
+            events.lock()[6..],
+            [
+                "a:after_return:1",
+                "a:before_destroy:1",
+                "b:before_destroy:1"
+            ]
+        );
+        let conn = pool.get().await.unwrap();
+        assert_eq!(conn.connection().unwrap().id(), 2);
+    }
+
+    /// A `before_checkout` hook that yields once before it completes.
+    struct YieldingCheckout;
+
+    impl ConnectionLifecycle<crate::MockConnection> for YieldingCheckout {
+        async fn before_checkout(
+            &self,
+            _conn: &mut crate::MockConnection,
+            _metadata: &ConnectionMetadata,
+        ) -> Result<(), PoolError> {
+            tokio::task::yield_now().await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_try_get_keeps_connection_when_before_checkout_waits() {
+        let factory = crate::MockConnectionFactory::new();
+        let lifecycles: Vec<Box<dyn DynConnectionLifecycle<crate::MockConnection>>> =
+            vec![Box::new(YieldingCheckout)];
+        let pool = Pool::with_lifecycles(mock_config(), factory.clone(), lifecycles)
+            .await
+            .unwrap();
+        drop(pool.get().await.unwrap());
+        tokio::task::yield_now().await;
+        assert_eq!(pool.status().available, 1);
+
+        assert!(pool.try_get().unwrap().is_none());
+        tokio::task::yield_now().await;
+
+        assert_eq!(pool.status().available, 1);
+        assert_eq!(pool.status().in_use, 0);
+        assert_eq!(factory.closed(), 0);
+        assert_eq!(pool.metrics().connections_closed, 0);
+        let conn = pool.get().await.unwrap();
+        assert_eq!(conn.connection().unwrap().id(), 1);
+    }
 }
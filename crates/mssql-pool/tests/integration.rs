@@ -446,7 +446,7 @@ async fn test_pool_connection_timeout() {
     // Try to get another connection - should timeout
     let result = pool.get().await;
     assert!(
-        matches!(result, Err(PoolError::Timeout)),
+        matches!(result, Err(PoolError::Timeout { .. })),
         "Should timeout waiting for connection"
     );
 
@@ -825,7 +825,7 @@ async fn test_pool_no_deadlock_nested_acquisition() {
     // Trying to get another should timeout, not deadlock
     let result = pool.get().await;
     assert!(
-        matches!(result, Err(PoolError::Timeout)),
+        matches!(result, Err(PoolError::Timeout { .. })),
         "Should timeout, not deadlock"
     );
 
@@ -1014,3 +1014,33 @@ async fn test_pool_mixed_operations_no_deadlock() {
 
     pool.close().await;
 }
+
+#[tokio::test]
+#[ignore = "Requires SQL Server"]
+async fn test_pool_prepare_on_create() {
+    let client_config = get_test_config().expect("SQL Server config required");
+
+    let pool = Pool::builder()
+        .client_config(client_config)
+        .max_connections(1)
+        .prepare_on_create(&["SELECT 1 AS n", "SELECT * FROM no_such_table"])
+        .build()
+        .await
+        .expect("Failed to create pool");
+
+    let mut conn = pool.get().await.expect("Failed to get connection");
+    // The statement that fails to prepare is skipped
+    assert_eq!(conn.client().unwrap().statement_cache().len(), 1);
+
+    let rows = conn
+        .query("SELECT 1 AS n", &[])
+        .await
+        .expect("Query failed")
+        .collect_all()
+        .await
+        .expect("Failed to read rows");
+    assert_eq!(rows[0].get::<i32>(0).unwrap(), 1);
+    assert_eq!(conn.client().unwrap().statement_cache().hits(), 1);
+
+    pool.close().await;
+}
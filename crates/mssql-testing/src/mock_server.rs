@@ -6,7 +6,9 @@
 //! ## Features
 //!
 //! - Simulates TDS protocol handshake (prelogin, login)
-//! - Configurable responses for SQL queries
+//! - Configurable responses for SQL batches and `sp_executesql` RPCs
+//! - Fault injection: slow replies, dropped connections, truncated packets,
+//!   and failed logins
 //! - Support for multiple concurrent connections
 //! - Recorded packet replay for regression testing
 //!
@@ -27,12 +29,34 @@
 //!     // Connect your client to addr...
 //! }
 //! ```
+//!
+//! ## Fault Injection
+//!
+//! ```rust,ignore
+//! use mssql_testing::mock_server::{Fault, MockResponse, MockTdsServer};
+//! use std::time::Duration;
+//!
+//! let server = MockTdsServer::builder()
+//!     // Reply to this query after two seconds
+//!     .with_response(
+//!         "SELECT 1",
+//!         MockResponse::scalar_int(1).with_fault(Fault::Delay(Duration::from_secs(2))),
+//!     )
+//!     // Drop the connection when this query arrives
+//!     .with_response("SELECT 2", MockResponse::disconnect())
+//!     // Reject every login
+//!     .with_login_error(18456, "Login failed for user 'sa'.")
+//!     .build()
+//!     .await?;
+//! ```
 
 use bytes::{BufMut, Bytes, BytesMut};
 use std::collections::HashMap;
 use std::fmt;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tds_protocol::prelogin::{EncryptionLevel, PreLogin};
 use tds_protocol::types::TypeId;
 use tds_protocol::{
     DoneStatus, EnvChangeType, PACKET_HEADER_SIZE, PacketHeader, PacketStatus, PacketType,
@@ -92,8 +116,34 @@ pub enum MockResponse {
     /// Return raw pre-encoded TDS tokens.
     Raw(Bytes),
 
+    /// Return the results of several statements, as for a multi-statement
+    /// batch. Every statement but the last is sent with `DONE_MORE` set.
+    Batch(Vec<MockResponse>),
+
     /// Execute a custom handler.
     Custom(Arc<dyn Fn(&str) -> MockResponse + Send + Sync>),
+
+    /// Apply a fault when sending a response.
+    WithFault {
+        /// The fault to inject.
+        fault: Fault,
+        /// The response the fault applies to.
+        response: Box<MockResponse>,
+    },
+}
+
+/// A fault injected into a server reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Fault {
+    /// Wait before sending the reply.
+    Delay(Duration),
+    /// Close the connection instead of replying.
+    Disconnect,
+    /// Never reply, leaving the client waiting.
+    NoResponse,
+    /// Send only the first `n` bytes of the reply packet, then close the connection.
+    Truncate(usize),
 }
 
 impl fmt::Debug for MockResponse {
@@ -117,7 +167,13 @@ impl fmt::Debug for MockResponse {
                 .finish(),
             Self::RowsAffected(n) => f.debug_tuple("RowsAffected").field(n).finish(),
             Self::Raw(data) => f.debug_tuple("Raw").field(&data.len()).finish(),
+            Self::Batch(responses) => f.debug_tuple("Batch").field(responses).finish(),
             Self::Custom(_) => f.debug_tuple("Custom").field(&"<fn>").finish(),
+            Self::WithFault { fault, response } => f
+                .debug_struct("WithFault")
+                .field("fault", fault)
+                .field("response", response)
+                .finish(),
         }
     }
 }
@@ -156,6 +212,24 @@ impl MockResponse {
     pub fn rows(columns: Vec<MockColumn>, rows: Vec<Vec<ScalarValue>>) -> Self {
         Self::Rows { columns, rows }
     }
+
+    /// Create a multi-statement batch response.
+    pub fn batch(responses: Vec<MockResponse>) -> Self {
+        Self::Batch(responses)
+    }
+
+    /// Create a response that closes the connection.
+    pub fn disconnect() -> Self {
+        Self::empty().with_fault(Fault::Disconnect)
+    }
+
+    /// Apply a fault when sending this response.
+    pub fn with_fault(self, fault: Fault) -> Self {
+        Self::WithFault {
+            fault,
+            response: Box::new(self),
+        }
+    }
 }
 
 /// Scalar value for mock responses.
@@ -319,6 +393,12 @@ pub struct MockServerConfig {
     tds_version: u32,
     /// Default database name.
     database: String,
+    /// Fault applied to the PRELOGIN reply.
+    prelogin_fault: Option<Fault>,
+    /// Fault applied to the LOGIN7 reply.
+    login_fault: Option<Fault>,
+    /// Error number and message sent in place of a LoginAck.
+    login_error: Option<(i32, String)>,
 }
 
 /// Builder for `MockTdsServer`.
@@ -336,6 +416,9 @@ impl MockServerBuilder {
                 server_name: "MockSQLServer".to_string(),
                 tds_version: 0x74000004, // TDS 7.4
                 database: "master".to_string(),
+                prelogin_fault: None,
+                login_fault: None,
+                login_error: None,
             },
         }
     }
@@ -364,6 +447,24 @@ impl MockServerBuilder {
         self
     }
 
+    /// Apply a fault to the PRELOGIN reply.
+    pub fn with_prelogin_fault(mut self, fault: Fault) -> Self {
+        self.config.prelogin_fault = Some(fault);
+        self
+    }
+
+    /// Apply a fault to the LOGIN7 reply.
+    pub fn with_login_fault(mut self, fault: Fault) -> Self {
+        self.config.login_fault = Some(fault);
+        self
+    }
+
+    /// Reject logins with the given error instead of a LoginAck.
+    pub fn with_login_error(mut self, number: i32, message: impl Into<String>) -> Self {
+        self.config.login_error = Some((number, message.into()));
+        self
+    }
+
     /// Build and start the mock server.
     pub async fn build(self) -> Result<MockTdsServer> {
         MockTdsServer::start(self.config).await
@@ -391,6 +492,8 @@ pub struct MockTdsServer {
     config: Arc<MockServerConfig>,
     /// Connection count.
     connection_count: Arc<Mutex<usize>>,
+    /// Statement text of every SQL batch and RPC received, in order.
+    received_sql: Arc<Mutex<Vec<String>>>,
 }
 
 impl MockTdsServer {
@@ -406,12 +509,14 @@ impl MockTdsServer {
         let (shutdown_tx, _) = broadcast::channel(1);
         let config = Arc::new(config);
         let connection_count = Arc::new(Mutex::new(0usize));
+        let received_sql = Arc::new(Mutex::new(Vec::new()));
 
         let server = Self {
             addr,
             shutdown_tx: shutdown_tx.clone(),
             config: config.clone(),
             connection_count: connection_count.clone(),
+            received_sql: received_sql.clone(),
         };
 
         // Spawn the accept loop
@@ -424,12 +529,13 @@ impl MockTdsServer {
                             Ok((stream, _peer_addr)) => {
                                 let config = config.clone();
                                 let count = connection_count.clone();
+                                let received = received_sql.clone();
                                 tokio::spawn(async move {
                                     {
                                         let mut c = count.lock().await;
                                         *c += 1;
                                     }
-                                    if let Err(e) = handle_connection(stream, config).await {
+                                    if let Err(e) = handle_connection(stream, config, received).await {
                                         tracing::debug!("Connection error: {}", e);
                                     }
                                     {
@@ -474,6 +580,12 @@ impl MockTdsServer {
         *self.connection_count.lock().await
     }
 
+    /// Get the statement text of every SQL batch and RPC received so far,
+    /// in order. RPC parameters are not included.
+    pub async fn received_sql(&self) -> Vec<String> {
+        self.received_sql.lock().await.clone()
+    }
+
     /// Stop the server.
     pub fn stop(&self) {
         let _ = self.shutdown_tx.send(());
@@ -487,7 +599,11 @@ impl Drop for MockTdsServer {
 }
 
 /// Handle a single client connection.
-async fn handle_connection(mut stream: TcpStream, config: Arc<MockServerConfig>) -> Result<()> {
+async fn handle_connection(
+    mut stream: TcpStream,
+    config: Arc<MockServerConfig>,
+    received_sql: Arc<Mutex<Vec<String>>>,
+) -> Result<()> {
     // Step 1: Handle PRELOGIN
     let prelogin_request = read_packet(&mut stream).await?;
     if prelogin_request.packet_type != PacketType::PreLogin {
@@ -496,7 +612,24 @@ async fn handle_connection(mut stream: TcpStream, config: Arc<MockServerConfig>)
             prelogin_request.packet_type
         )));
     }
-    send_prelogin_response(&mut stream).await?;
+    // Clients that do not support encryption (`Encrypt=no_tls`) stay on
+    // plain TCP; everyone else is told encryption is off
+    let encryption = match PreLogin::decode(&prelogin_request.payload[..]) {
+        Ok(prelogin) if prelogin.encryption == EncryptionLevel::NotSupported => {
+            EncryptionLevel::NotSupported
+        }
+        _ => EncryptionLevel::Off,
+    };
+    let delivery = send_reply(
+        &mut stream,
+        PacketType::PreLogin,
+        &encode_prelogin_response(encryption),
+        config.prelogin_fault.as_ref(),
+    )
+    .await?;
+    if delivery == Delivery::Close {
+        return Ok(());
+    }
 
     // Step 2: Handle LOGIN7
     let login_request = read_packet(&mut stream).await?;
@@ -506,7 +639,16 @@ async fn handle_connection(mut stream: TcpStream, config: Arc<MockServerConfig>)
             login_request.packet_type
         )));
     }
-    send_login_response(&mut stream, &config).await?;
+    let delivery = send_reply(
+        &mut stream,
+        PacketType::TabularResult,
+        &encode_login_response(&config),
+        config.login_fault.as_ref(),
+    )
+    .await?;
+    if delivery == Delivery::Close || config.login_error.is_some() {
+        return Ok(());
+    }
 
     // Step 3: Handle SQL batches and RPC requests
     loop {
@@ -519,28 +661,34 @@ async fn handle_connection(mut stream: TcpStream, config: Arc<MockServerConfig>)
             Err(e) => return Err(e),
         };
 
-        match packet.packet_type {
+        let delivery = match packet.packet_type {
             PacketType::SqlBatch => {
                 let sql = decode_sql_batch(&packet.payload)?;
+                received_sql.lock().await.push(sql.clone());
                 let response = find_response(&sql, &config);
-                send_query_response(&mut stream, response).await?;
+                send_query_response(&mut stream, &sql, &response).await?
             }
             PacketType::Rpc => {
-                // For RPC requests (sp_executesql, sp_prepare, etc.)
-                // Extract the SQL from the RPC payload and handle similarly
-                let response = config
-                    .default_response
-                    .clone()
-                    .unwrap_or(MockResponse::empty());
-                send_query_response(&mut stream, response).await?;
+                // sp_executesql is matched on its statement text; other
+                // procedures (sp_prepare, cursors, ...) get the default response
+                let sql = decode_rpc_sql(&packet.payload).unwrap_or_default();
+                received_sql.lock().await.push(sql.clone());
+                let response = find_response(&sql, &config);
+                send_query_response(&mut stream, &sql, &response).await?
             }
             PacketType::Attention => {
                 // Client sent attention/cancel signal
                 send_attention_ack(&mut stream).await?;
+                Delivery::Continue
             }
             _ => {
                 tracing::debug!("Unexpected packet type: {:?}", packet.packet_type);
+                Delivery::Continue
             }
+        };
+
+        if delivery == Delivery::Close {
+            break;
         }
     }
 
@@ -600,33 +748,108 @@ async fn read_packet(stream: &mut TcpStream) -> Result<Packet> {
     })
 }
 
+/// Whether the connection stays open after a reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delivery {
+    Continue,
+    Close,
+}
+
+/// Write a TDS packet to the stream, applying a fault if one is configured.
+async fn send_reply(
+    stream: &mut TcpStream,
+    packet_type: PacketType,
+    payload: &[u8],
+    fault: Option<&Fault>,
+) -> Result<Delivery> {
+    match fault {
+        None => {}
+        Some(Fault::Delay(delay)) => tokio::time::sleep(*delay).await,
+        Some(Fault::Disconnect) => return Ok(Delivery::Close),
+        Some(Fault::NoResponse) => return Ok(Delivery::Continue),
+        Some(Fault::Truncate(len)) => {
+            let packet = encode_packet(packet_type, payload);
+            stream.write_all(&packet[..packet.len().min(*len)]).await?;
+            stream.flush().await?;
+            return Ok(Delivery::Close);
+        }
+    }
+
+    write_packet(stream, packet_type, payload).await?;
+    Ok(Delivery::Continue)
+}
+
 /// Write a TDS packet to the stream.
 async fn write_packet(
     stream: &mut TcpStream,
     packet_type: PacketType,
     payload: &[u8],
 ) -> Result<()> {
-    let total_len = PACKET_HEADER_SIZE + payload.len();
+    stream
+        .write_all(&encode_message(packet_type, payload))
+        .await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Packet size the mock server announces at login.
+const PACKET_SIZE: usize = 4096;
+
+/// Encode a TDS message, split into packets of the announced size.
+fn encode_message(packet_type: PacketType, payload: &[u8]) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(payload.len() + PACKET_HEADER_SIZE);
+    let mut chunks = payload.chunks(PACKET_SIZE - PACKET_HEADER_SIZE).peekable();
+    let mut packet_id = 1u8;
+    while let Some(chunk) = chunks.next() {
+        let status = if chunks.peek().is_some() {
+            PacketStatus::NORMAL
+        } else {
+            PacketStatus::END_OF_MESSAGE
+        };
+        encode_packet_into(&mut buf, packet_type, status, packet_id, chunk);
+        packet_id = packet_id.wrapping_add(1);
+    }
+    if payload.is_empty() {
+        encode_packet_into(&mut buf, packet_type, PacketStatus::END_OF_MESSAGE, 1, &[]);
+    }
+    buf
+}
+
+/// Encode a single-packet TDS message.
+fn encode_packet(packet_type: PacketType, payload: &[u8]) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(PACKET_HEADER_SIZE + payload.len());
+    encode_packet_into(
+        &mut buf,
+        packet_type,
+        PacketStatus::END_OF_MESSAGE,
+        1,
+        payload,
+    );
+    buf
+}
+
+/// Append one packet to `buf`.
+fn encode_packet_into(
+    buf: &mut BytesMut,
+    packet_type: PacketType,
+    status: PacketStatus,
+    packet_id: u8,
+    payload: &[u8],
+) {
     let header = PacketHeader {
         packet_type,
-        status: PacketStatus::END_OF_MESSAGE,
-        length: total_len as u16,
+        status,
+        length: (PACKET_HEADER_SIZE + payload.len()) as u16,
         spid: 0,
-        packet_id: 1,
+        packet_id,
         window: 0,
     };
-
-    let mut buf = BytesMut::with_capacity(total_len);
-    header.encode(&mut buf);
+    header.encode(buf);
     buf.extend_from_slice(payload);
-
-    stream.write_all(&buf).await?;
-    stream.flush().await?;
-    Ok(())
 }
 
-/// Send PRELOGIN response.
-async fn send_prelogin_response(stream: &mut TcpStream) -> Result<()> {
+/// Encode the PRELOGIN response.
+fn encode_prelogin_response(encryption: EncryptionLevel) -> BytesMut {
     // PRELOGIN response format:
     // Option tokens (5 bytes each: type + offset + length) followed by data
     // VERSION (0x00), ENCRYPTION (0x01)
@@ -664,20 +887,40 @@ async fn send_prelogin_response(stream: &mut TcpStream) -> Result<()> {
     response.put_u16_le(0); // sub-build number
 
     // ENCRYPTION data (at offset 17)
-    response.put_u8(0x00); // ENCRYPT_OFF (no encryption)
+    response.put_u8(encryption as u8);
 
-    write_packet(stream, PacketType::PreLogin, &response).await
+    response
 }
 
-/// Send LOGIN7 response (LoginAck + EnvChange + Done).
-async fn send_login_response(stream: &mut TcpStream, config: &MockServerConfig) -> Result<()> {
+/// Encode the LOGIN7 response (LoginAck + EnvChange + Done), or a login
+/// failure if one is configured.
+fn encode_login_response(config: &MockServerConfig) -> BytesMut {
     let mut response = BytesMut::new();
 
+    if let Some((number, message)) = &config.login_error {
+        encode_error(&mut response, *number, message, 14);
+        response.put_u8(TokenType::Done as u8);
+        let status = DoneStatus {
+            error: true,
+            ..Default::default()
+        };
+        response.put_u16_le(status.to_bits());
+        response.put_u16_le(0);
+        response.put_u64_le(0);
+        return response;
+    }
+
     // EnvChange: Database
     encode_env_change(&mut response, EnvChangeType::Database, &config.database, "");
 
     // EnvChange: PacketSize
-    encode_env_change(&mut response, EnvChangeType::PacketSize, "4096", "4096");
+    let packet_size = PACKET_SIZE.to_string();
+    encode_env_change(
+        &mut response,
+        EnvChangeType::PacketSize,
+        &packet_size,
+        &packet_size,
+    );
 
     // LoginAck
     encode_login_ack(&mut response, &config.server_name, config.tds_version);
@@ -685,7 +928,7 @@ async fn send_login_response(stream: &mut TcpStream, config: &MockServerConfig)
     // Done
     encode_done(&mut response, 0, false);
 
-    write_packet(stream, PacketType::TabularResult, &response).await
+    response
 }
 
 /// Encode an EnvChange token.
@@ -722,7 +965,7 @@ fn encode_login_ack(dst: &mut BytesMut, server_name: &str, tds_version: u32) {
     dst.put_u8(TokenType::LoginAck as u8);
     dst.put_u16_le(data_len as u16);
     dst.put_u8(1); // interface: SQL
-    dst.put_u32_le(tds_version);
+    dst.put_u32(tds_version); // big-endian, unlike LOGIN7
 
     // Program name (B_VARCHAR)
     dst.put_u8(name_utf16.len() as u8);
@@ -754,17 +997,7 @@ fn decode_sql_batch(payload: &Bytes) -> Result<String> {
     // SQL Batch format: ALL_HEADERS (optional) + SQL text in UTF-16LE
     // For simplicity, assume no ALL_HEADERS (check first 4 bytes)
 
-    let mut cursor = payload.as_ref();
-
-    // Check if ALL_HEADERS is present
-    if cursor.len() >= 4 {
-        let total_len = u32::from_le_bytes([cursor[0], cursor[1], cursor[2], cursor[3]]) as usize;
-
-        // If total_len looks like a header length (reasonable size), skip headers
-        if total_len >= 4 && total_len < cursor.len() && total_len < 1000 {
-            cursor = &cursor[total_len..];
-        }
-    }
+    let cursor = skip_all_headers(payload);
 
     // Read UTF-16LE SQL text
     if cursor.len() % 2 != 0 {
@@ -773,15 +1006,91 @@ fn decode_sql_batch(payload: &Bytes) -> Result<String> {
         ));
     }
 
-    let char_count = cursor.len() / 2;
-    let mut chars = Vec::with_capacity(char_count);
-    for i in 0..char_count {
-        let c = u16::from_le_bytes([cursor[i * 2], cursor[i * 2 + 1]]);
-        chars.push(c);
+    decode_utf16(cursor)
+        .ok_or_else(|| MockServerError::Protocol("Invalid UTF-16 SQL text".to_string()))
+}
+
+/// Skip the ALL_HEADERS section of a request payload, if present.
+fn skip_all_headers(payload: &[u8]) -> &[u8] {
+    if payload.len() >= 4 {
+        let total_len =
+            u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]) as usize;
+
+        // If total_len looks like a header length (reasonable size), skip headers
+        if total_len >= 4 && total_len < payload.len() && total_len < 1000 {
+            return &payload[total_len..];
+        }
     }
+    payload
+}
 
-    String::from_utf16(&chars)
-        .map_err(|_| MockServerError::Protocol("Invalid UTF-16 SQL text".to_string()))
+/// Decode UTF-16LE text.
+fn decode_utf16(bytes: &[u8]) -> Option<String> {
+    let chars: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16(&chars).ok()
+}
+
+/// Decode the statement text of an `sp_executesql` RPC request.
+///
+/// Returns `None` for other procedures or payloads that cannot be parsed.
+fn decode_rpc_sql(payload: &[u8]) -> Option<String> {
+    const EXECUTE_SQL_PROC_ID: u16 = 10;
+    const NVARCHAR_TYPE: u8 = 0xE7;
+
+    fn take<'a>(buf: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if buf.len() < n {
+            return None;
+        }
+        let (head, tail) = buf.split_at(n);
+        *buf = tail;
+        Some(head)
+    }
+    fn take_u16(buf: &mut &[u8]) -> Option<u16> {
+        take(buf, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+    fn take_u32(buf: &mut &[u8]) -> Option<u32> {
+        take(buf, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    let mut buf = skip_all_headers(payload);
+
+    // Procedure: 0xFFFF followed by a well-known ID, or a name
+    if take_u16(&mut buf)? != 0xFFFF || take_u16(&mut buf)? != EXECUTE_SQL_PROC_ID {
+        return None;
+    }
+    take_u16(&mut buf)?; // option flags
+
+    // First parameter: name (B_VARCHAR), status, NVARCHAR type info
+    let name_len = take(&mut buf, 1)?[0] as usize;
+    take(&mut buf, name_len * 2 + 1)?;
+    if take(&mut buf, 1)?[0] != NVARCHAR_TYPE {
+        return None;
+    }
+    let max_len = take_u16(&mut buf)?;
+    take(&mut buf, 5)?; // collation
+
+    if max_len == 0xFFFF {
+        // PLP: total length, then chunks until a zero-length terminator
+        take(&mut buf, 8)?;
+        let mut text = Vec::new();
+        loop {
+            let chunk_len = take_u32(&mut buf)? as usize;
+            if chunk_len == 0 {
+                break;
+            }
+            text.extend_from_slice(take(&mut buf, chunk_len)?);
+        }
+        decode_utf16(&text)
+    } else {
+        let len = take_u16(&mut buf)?;
+        if len == 0xFFFF {
+            return None;
+        }
+        decode_utf16(take(&mut buf, len as usize)?)
+    }
 }
 
 /// Find the response for a SQL query.
@@ -809,45 +1118,70 @@ fn find_response(sql: &str, config: &MockServerConfig) -> MockResponse {
 }
 
 /// Send a query response based on the MockResponse.
-async fn send_query_response(stream: &mut TcpStream, response: MockResponse) -> Result<()> {
+async fn send_query_response(
+    stream: &mut TcpStream,
+    sql: &str,
+    response: &MockResponse,
+) -> Result<Delivery> {
     let mut buf = BytesMut::new();
+    let fault = encode_response(&mut buf, sql, response, false);
+    send_reply(stream, PacketType::TabularResult, &buf, fault.as_ref()).await
+}
 
+/// Encode the tokens of a MockResponse, returning the fault to apply.
+///
+/// `more` sets `DONE_MORE` on the final DONE token.
+fn encode_response(
+    buf: &mut BytesMut,
+    sql: &str,
+    response: &MockResponse,
+    more: bool,
+) -> Option<Fault> {
     match response {
         MockResponse::Scalar(value) => {
             // Single column, single row result
-            encode_colmetadata(&mut buf, &[MockColumn::new("", value.type_id())]);
-            encode_row(&mut buf, &[value.clone()]);
-            encode_done(&mut buf, 1, false);
+            encode_colmetadata(buf, &[MockColumn::new("", value.type_id())]);
+            encode_row(buf, std::slice::from_ref(value));
+            encode_done(buf, 1, more);
         }
         MockResponse::Rows { columns, rows } => {
-            encode_colmetadata(&mut buf, &columns);
-            for row in &rows {
-                encode_row(&mut buf, row);
+            encode_colmetadata(buf, columns);
+            for row in rows {
+                encode_row(buf, row);
             }
-            encode_done(&mut buf, rows.len() as u64, false);
+            encode_done(buf, rows.len() as u64, more);
         }
         MockResponse::Error {
             number,
             message,
             severity,
         } => {
-            encode_error(&mut buf, number, &message, severity);
-            encode_done(&mut buf, 0, false);
+            encode_error(buf, *number, message, *severity);
+            encode_done(buf, 0, more);
         }
         MockResponse::RowsAffected(count) => {
-            encode_done(&mut buf, count, false);
+            encode_done(buf, *count, more);
         }
         MockResponse::Raw(data) => {
-            buf.extend_from_slice(&data);
+            buf.extend_from_slice(data);
         }
-        MockResponse::Custom(_handler) => {
-            // For custom handlers, we'd need the SQL here
-            // For now, just send empty result
-            encode_done(&mut buf, 0, false);
+        MockResponse::Batch(responses) => {
+            let mut fault = None;
+            for (i, response) in responses.iter().enumerate() {
+                let last = i + 1 == responses.len();
+                let part = encode_response(buf, sql, response, more || !last);
+                fault = fault.or(part);
+            }
+            return fault;
+        }
+        MockResponse::Custom(handler) => return encode_response(buf, sql, &handler(sql), more),
+        MockResponse::WithFault { fault, response } => {
+            encode_response(buf, sql, response, more);
+            return Some(fault.clone());
         }
     }
 
-    write_packet(stream, PacketType::TabularResult, &buf).await
+    None
 }
 
 /// Encode COLMETADATA token.
@@ -1118,4 +1452,148 @@ mod tests {
         let status = u16::from_le_bytes([buf[1], buf[2]]);
         assert_eq!(status & 0x0010, 0x0010); // DONE_COUNT
     }
+
+    /// Complete the prelogin and login exchange as a client would.
+    async fn handshake(server: &MockTdsServer) -> TcpStream {
+        let mut stream = TcpStream::connect(server.addr()).await.unwrap();
+        stream
+            .write_all(&encode_packet(PacketType::PreLogin, &[0xFF]))
+            .await
+            .unwrap();
+        read_packet(&mut stream).await.unwrap();
+        stream
+            .write_all(&encode_packet(PacketType::Tds7Login, &[0; 8]))
+            .await
+            .unwrap();
+        read_packet(&mut stream).await.unwrap();
+        stream
+    }
+
+    fn sql_batch(sql: &str) -> BytesMut {
+        let payload: Vec<u8> = sql.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        encode_packet(PacketType::SqlBatch, &payload)
+    }
+
+    #[test]
+    fn test_decode_rpc_sql() {
+        let rpc = tds_protocol::RpcRequest::execute_sql("SELECT @p1", vec![]);
+        assert_eq!(decode_rpc_sql(&rpc.encode()).unwrap(), "SELECT @p1");
+
+        let rpc = tds_protocol::RpcRequest::execute_sql("SELECT 1", vec![]);
+        assert_eq!(
+            decode_rpc_sql(&rpc.encode_with_transaction(7)).unwrap(),
+            "SELECT 1"
+        );
+
+        let rpc = tds_protocol::RpcRequest::named("dbo.my_proc");
+        assert!(decode_rpc_sql(&rpc.encode()).is_none());
+    }
+
+    #[test]
+    fn test_custom_response_uses_sql() {
+        let response = MockResponse::Custom(Arc::new(|sql| {
+            MockResponse::scalar_string(sql.to_string()).with_fault(Fault::Disconnect)
+        }));
+
+        let mut buf = BytesMut::new();
+        let fault = encode_response(&mut buf, "SELECT 'x'", &response, false);
+        assert_eq!(fault, Some(Fault::Disconnect));
+        assert_eq!(buf[0], TokenType::ColMetaData as u8);
+    }
+
+    #[tokio::test]
+    async fn test_prelogin_disconnect() {
+        let server = MockTdsServer::builder()
+            .with_prelogin_fault(Fault::Disconnect)
+            .build()
+            .await
+            .unwrap();
+
+        let mut stream = TcpStream::connect(server.addr()).await.unwrap();
+        stream
+            .write_all(&encode_packet(PacketType::PreLogin, &[0xFF]))
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_login_error() {
+        let server = MockTdsServer::builder()
+            .with_login_error(18456, "Login failed for user 'sa'.")
+            .build()
+            .await
+            .unwrap();
+
+        let mut stream = TcpStream::connect(server.addr()).await.unwrap();
+        stream
+            .write_all(&encode_packet(PacketType::PreLogin, &[0xFF]))
+            .await
+            .unwrap();
+        read_packet(&mut stream).await.unwrap();
+        stream
+            .write_all(&encode_packet(PacketType::Tds7Login, &[0; 8]))
+            .await
+            .unwrap();
+
+        let reply = read_packet(&mut stream).await.unwrap();
+        assert_eq!(reply.payload[0], TokenType::Error as u8);
+        assert_eq!(
+            i32::from_le_bytes(reply.payload[3..7].try_into().unwrap()),
+            18456
+        );
+        assert!(read_packet(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response() {
+        let server = MockTdsServer::builder()
+            .with_response(
+                "SELECT 1",
+                MockResponse::scalar_int(1).with_fault(Fault::Truncate(4)),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let mut stream = handshake(&server).await;
+        stream.write_all(&sql_batch("SELECT 1")).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_delay_and_no_response() {
+        let server = MockTdsServer::builder()
+            .with_response(
+                "SELECT 1",
+                MockResponse::scalar_int(1).with_fault(Fault::Delay(Duration::from_millis(50))),
+            )
+            .with_response(
+                "SELECT 2",
+                MockResponse::scalar_int(2).with_fault(Fault::NoResponse),
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let mut stream = handshake(&server).await;
+
+        let start = std::time::Instant::now();
+        stream.write_all(&sql_batch("SELECT 1")).await.unwrap();
+        read_packet(&mut stream).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        stream.write_all(&sql_batch("SELECT 2")).await.unwrap();
+        let reply =
+            tokio::time::timeout(Duration::from_millis(100), read_packet(&mut stream)).await;
+        assert!(reply.is_err());
+
+        // The connection stays usable after a swallowed request
+        stream.write_all(&sql_batch("SELECT 1")).await.unwrap();
+        read_packet(&mut stream).await.unwrap();
+    }
 }
@@ -0,0 +1,50 @@
+//! `Client::admin()` password handling against the mock TDS server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test admin
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::admin::{LoginAuth, UserSource};
+use mssql_client::{Client, Config, Ready};
+use mssql_testing::mock_server::MockTdsServer;
+
+const PASSWORD: &str = "It's-a-S3cret!";
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+#[tokio::test]
+async fn test_passwords_are_not_in_sql_text() {
+    let server = MockTdsServer::builder()
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    let mut admin = client.admin();
+    let auth = LoginAuth::Password(PASSWORD.into());
+    admin.create_login("app", &auth).await.unwrap();
+    admin.ensure_login("app", &auth).await.unwrap();
+    admin.alter_login_password("app", PASSWORD).await.unwrap();
+    let source = UserSource::Password(PASSWORD.into());
+    admin.create_user("app", &source).await.unwrap();
+    admin.ensure_user("app", &source).await.unwrap();
+
+    let received = server.received_sql().await;
+    assert_eq!(received.len(), 5);
+    for sql in &received {
+        assert!(sql.contains("WITH PASSWORD"), "unexpected statement: {sql}");
+        assert!(!sql.contains("S3cret"), "password in SQL text: {sql}");
+    }
+
+    server.stop();
+}
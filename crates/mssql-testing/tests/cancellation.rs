@@ -0,0 +1,212 @@
+//! Recovery from dropped request futures, against the mock TDS server.
+//!
+//! A query future dropped before it completes (a lost `timeout` or
+//! `select!`) sends an Attention packet, and the next request drains the
+//! stale response before sending anything.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test cancellation
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+use mssql_client::{Client, Config, Error, Ready};
+use mssql_testing::mock_server::{Fault, MockResponse, MockTdsServer};
+
+const SLOW_QUERY: &str = "WAITFOR DELAY '00:00:01'; SELECT 1";
+
+async fn start_server() -> MockTdsServer {
+    MockTdsServer::builder()
+        .with_response(
+            SLOW_QUERY,
+            MockResponse::scalar_int(1).with_fault(Fault::Delay(Duration::from_millis(50))),
+        )
+        .with_response("SELECT 2", MockResponse::scalar_int(2))
+        .build()
+        .await
+        .expect("mock server should start")
+}
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+async fn select_two(client: &mut Client<Ready>) -> Result<i32, Error> {
+    let rows = client.query("SELECT 2", &[]).await?.collect_all().await?;
+    Ok(rows[0].get(0)?)
+}
+
+#[tokio::test]
+async fn test_timed_out_query_is_drained_by_next_request() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let result =
+        tokio::time::timeout(Duration::from_millis(5), client.query(SLOW_QUERY, &[])).await;
+    assert!(result.is_err(), "query should time out");
+    assert!(client.needs_drain());
+
+    // The response to the slow query must not be read as this one's
+    assert_eq!(select_two(&mut client).await.unwrap(), 2);
+    assert!(!client.needs_drain());
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_drain_after_timeout() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let result =
+        tokio::time::timeout(Duration::from_millis(5), client.execute(SLOW_QUERY, &[])).await;
+    assert!(result.is_err(), "statement should time out");
+
+    client.drain().await.unwrap();
+    assert!(!client.needs_drain());
+    // Nothing left to drain
+    client.drain().await.unwrap();
+    assert_eq!(select_two(&mut client).await.unwrap(), 2);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_query_dropped_at_every_await_point() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    // Poll the query once more on every round, so it is dropped at each
+    // of its await points in turn, until it completes
+    for polls in 1.. {
+        let completed = {
+            let mut query = pin!(client.query(SLOW_QUERY, &[]));
+            let mut completed = false;
+            for _ in 0..polls {
+                let poll = std::future::poll_fn(|cx| Poll::Ready(query.as_mut().poll(cx))).await;
+                if poll.is_ready() {
+                    completed = true;
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            completed
+        };
+
+        // The request is small enough to be written in one poll, so the
+        // connection is always recoverable
+        let value = select_two(&mut client)
+            .await
+            .unwrap_or_else(|e| panic!("request failed after {polls} polls: {e}"));
+        assert_eq!(value, 2, "stale response after {polls} polls");
+        assert!(!client.needs_drain());
+
+        if completed {
+            break;
+        }
+    }
+
+    server.stop();
+}
+
+/// Number of `EXECUTE AS` statements minus `REVERT` statements received
+/// from the `since`th statement up to the last `SELECT 2`.
+async fn open_impersonations(server: &MockTdsServer, since: usize) -> usize {
+    let received = server.received_sql().await;
+    let last = received.iter().rposition(|sql| sql == "SELECT 2").unwrap();
+    let count = |needle: &str| -> usize {
+        received[since..last]
+            .iter()
+            .map(|sql| sql.matches(needle).count())
+            .sum()
+    };
+    count("EXECUTE AS").abs_diff(count("REVERT"))
+}
+
+#[tokio::test]
+async fn test_impersonation_dropped_inside_closure_is_reverted() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(20),
+        client.execute_as_user("reader", async |_| {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        }),
+    )
+    .await;
+    assert!(result.is_err(), "impersonation should time out");
+
+    assert_eq!(select_two(&mut client).await.unwrap(), 2);
+    assert_eq!(
+        server.received_sql().await,
+        ["EXECUTE AS USER = N'reader'", "REVERT", "SELECT 2"]
+    );
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_impersonation_dropped_at_every_await_point() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+    // Statements received before the current connection was opened
+    let mut since = 0;
+
+    for polls in 1.. {
+        let completed = {
+            let mut impersonation = pin!(client.execute_as_user("reader", async |c| {
+                c.query(SLOW_QUERY, &[]).await?.collect_all().await?;
+                Ok(())
+            }));
+            let mut completed = false;
+            for _ in 0..polls {
+                let poll =
+                    std::future::poll_fn(|cx| Poll::Ready(impersonation.as_mut().poll(cx))).await;
+                if let Poll::Ready(result) = poll {
+                    result.unwrap();
+                    completed = true;
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            completed
+        };
+
+        // Dropped with the EXECUTE AS or REVERT in flight, the session may
+        // or may not be impersonating, so the connection is closed
+        match select_two(&mut client).await {
+            Ok(value) => {
+                assert_eq!(value, 2, "stale response after {polls} polls");
+                assert_eq!(
+                    open_impersonations(&server, since).await,
+                    0,
+                    "impersonation left open after {polls} polls"
+                );
+            }
+            Err(Error::ConnectionClosed) => {
+                client = connect(&server).await;
+                since = server.received_sql().await.len();
+            }
+            Err(e) => panic!("request failed after {polls} polls: {e}"),
+        }
+
+        if completed {
+            break;
+        }
+    }
+
+    server.stop();
+}
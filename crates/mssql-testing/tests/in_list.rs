@@ -0,0 +1,190 @@
+//! `Client::execute_in()` and `query_in()` against the mock TDS server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test in_list
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::in_list::expand_in;
+use mssql_client::{Client, Config, Error, Ready};
+use mssql_testing::mock_server::{MockResponse, MockTdsServer};
+
+const DELETE: &str = "DELETE FROM dbo.Sessions WHERE Id IN (@ids)";
+
+fn config(server: &MockTdsServer) -> Config {
+    Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap()
+}
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    Client::connect(config(server))
+        .await
+        .expect("should connect")
+}
+
+/// The statements `execute_in` splits `values` into.
+fn chunks(values: &[i32]) -> Vec<String> {
+    expand_in(DELETE, "@ids", values, &[])
+        .unwrap()
+        .iter()
+        .map(|q| q.sql().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_execute_in_commits_split_statements_together() {
+    let values: Vec<i32> = (0..3000).collect();
+    let chunks = chunks(&values);
+    assert_eq!(chunks.len(), 2);
+    let server = MockTdsServer::builder()
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    client
+        .execute_in(DELETE, "@ids", &values, &[])
+        .await
+        .unwrap();
+
+    let received = server.received_sql().await;
+    assert_eq!(received.len(), 4);
+    assert!(received[0].starts_with("BEGIN TRANSACTION; SAVE TRANSACTION"));
+    assert_eq!(received[1..3], chunks);
+    assert_eq!(received[3], "COMMIT TRANSACTION");
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_execute_in_rolls_back_when_a_part_fails() {
+    let values: Vec<i32> = (0..3000).collect();
+    let chunks = chunks(&values);
+    let server = MockTdsServer::builder()
+        .with_response(
+            &chunks[1],
+            MockResponse::error(547, "The DELETE statement conflicted with a constraint."),
+        )
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    let err = client
+        .execute_in(DELETE, "@ids", &values, &[])
+        .await
+        .expect_err("second part should fail");
+    assert_eq!(err.sql_error_number(), Some(547));
+
+    let received = server.received_sql().await;
+    assert_eq!(received.len(), 4);
+    assert_eq!(received[1..3], chunks);
+    assert!(received[3].contains("ROLLBACK TRANSACTION mssql_in_list"));
+
+    // The connection is still usable
+    let rows = client
+        .query("SELECT 1", &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<i32>(0).unwrap(), 1);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_execute_in_single_statement_runs_alone() {
+    let server = MockTdsServer::builder()
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    client
+        .execute_in(DELETE, "@ids", &[1, 2, 3], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(server.received_sql().await, chunks(&[1, 2, 3]));
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_query_in_without_table_type_needs_fallback() {
+    // Floating-point values have no IN list table type
+    let values: Vec<f64> = (0..3000).map(f64::from).collect();
+    let sql = "SELECT * FROM dbo.Readings WHERE Value IN (@vals)";
+    let server = MockTdsServer::builder()
+        .build()
+        .await
+        .expect("mock server should start");
+
+    let mut client = Client::connect(config(&server).in_list_tvp(true))
+        .await
+        .unwrap();
+    let err = client
+        .query_in(sql, "@vals", &values, &[])
+        .await
+        .err()
+        .expect("list should not be split");
+    assert!(matches!(err, Error::Config(_)), "unexpected error: {err}");
+    assert!(server.received_sql().await.is_empty());
+
+    let mut client = Client::connect(
+        config(&server)
+            .in_list_tvp(true)
+            .in_list_split_fallback(true),
+    )
+    .await
+    .unwrap();
+    let rows = client
+        .query_in(sql, "@vals", &values, &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+    assert_eq!(server.received_sql().await.len(), 2);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_query_in_fallback_never_splits_not_in() {
+    let values: Vec<f64> = (0..3000).map(f64::from).collect();
+    let server = MockTdsServer::builder()
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = Client::connect(
+        config(&server)
+            .in_list_tvp(true)
+            .in_list_split_fallback(true),
+    )
+    .await
+    .unwrap();
+
+    let err = client
+        .query_in(
+            "SELECT * FROM dbo.Readings WHERE Value NOT IN (@vals)",
+            "@vals",
+            &values,
+            &[],
+        )
+        .await
+        .err()
+        .expect("NOT IN should not be split");
+    assert!(matches!(err, Error::Config(_)), "unexpected error: {err}");
+    assert!(server.received_sql().await.is_empty());
+
+    server.stop();
+}
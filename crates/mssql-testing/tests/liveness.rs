@@ -0,0 +1,70 @@
+//! `Client::is_broken()` against the mock TDS server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test liveness
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::time::Duration;
+
+use mssql_client::{Client, Config, Ready};
+use mssql_testing::mock_server::{Fault, MockResponse, MockTdsServer};
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+#[tokio::test]
+async fn test_is_broken_after_server_closes_connection() {
+    let server = MockTdsServer::builder()
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .with_response("KILL", MockResponse::empty().with_fault(Fault::Disconnect))
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+    assert!(!client.is_broken());
+
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert!(!client.is_broken());
+
+    assert!(client.execute("KILL", &[]).await.is_err());
+    // Give the FIN time to arrive
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(client.is_broken());
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_is_broken_after_failed_read() {
+    let server = MockTdsServer::builder()
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .with_response(
+            "SELECT 2",
+            MockResponse::scalar_int(2).with_fault(Fault::Truncate(4)),
+        )
+        .build()
+        .await
+        .expect("mock server should start");
+
+    // A reply cut off mid-packet fails the read and closes the socket
+    let mut client = connect(&server).await;
+    assert!(client.query("SELECT 2", &[]).await.is_err());
+    assert!(client.is_broken());
+
+    // Other connections are unaffected
+    let mut other = connect(&server).await;
+    assert!(!other.is_broken());
+    other.query("SELECT 1", &[]).await.unwrap();
+    assert!(!other.is_broken());
+
+    server.stop();
+}
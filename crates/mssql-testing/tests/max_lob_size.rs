@@ -0,0 +1,143 @@
+//! `Config::max_lob_size` and its per-query override against the mock TDS
+//! server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test max_lob_size
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use bytes::{BufMut, Bytes, BytesMut};
+use mssql_client::{Client, Config, Error, QueryOptions, Ready};
+use mssql_testing::mock_server::{MockResponse, MockTdsServer};
+
+const SELECT_DOC: &str = "SELECT doc FROM Docs";
+
+/// One NVARCHAR(MAX) column holding `text`, sent as PLP with an unknown
+/// total length.
+fn nvarchar_max_response(text: &str) -> MockResponse {
+    let mut buf = BytesMut::new();
+
+    // COLMETADATA: one nullable NVARCHAR(MAX) column named "doc"
+    buf.put_u8(0x81);
+    buf.put_u16_le(1);
+    buf.put_u32_le(0); // user type
+    buf.put_u16_le(0x01); // nullable
+    buf.put_u8(0xE7); // NVARCHAR
+    buf.put_u16_le(0xFFFF); // MAX
+    buf.put_u32_le(0x0904D000); // collation LCID
+    buf.put_u8(0x34); // collation sort ID
+    buf.put_u8(3);
+    for c in "doc".encode_utf16() {
+        buf.put_u16_le(c);
+    }
+
+    // ROW with the PLP value in one chunk
+    let utf16: Vec<u16> = text.encode_utf16().collect();
+    buf.put_u8(0xD1);
+    buf.put_u64_le(0xFFFF_FFFF_FFFF_FFFE); // unknown length
+    buf.put_u32_le((utf16.len() * 2) as u32);
+    for c in &utf16 {
+        buf.put_u16_le(*c);
+    }
+    buf.put_u32_le(0);
+
+    // DONE with a row count of 1
+    buf.put_u8(0xFD);
+    buf.put_u16_le(0x10);
+    buf.put_u16_le(0xC1);
+    buf.put_u64_le(1);
+
+    MockResponse::Raw(Bytes::from(buf))
+}
+
+async fn start_server() -> MockTdsServer {
+    MockTdsServer::builder()
+        .with_response(SELECT_DOC, nvarchar_max_response("Hello"))
+        .with_response(
+            format!("SET TEXTSIZE 101; {SELECT_DOC}"),
+            nvarchar_max_response("Hello"),
+        )
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .build()
+        .await
+        .expect("mock server should start")
+}
+
+async fn connect(server: &MockTdsServer, max_lob_size: u64) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap()
+    .max_lob_size(max_lob_size);
+    Client::connect(config).await.expect("should connect")
+}
+
+#[tokio::test]
+async fn test_value_within_limit_is_read() {
+    let server = start_server().await;
+    let mut client = connect(&server, 10).await;
+
+    let rows = client
+        .query(SELECT_DOC, &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<String>(0).unwrap(), "Hello");
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_value_over_limit_fails() {
+    let server = start_server().await;
+    let mut client = connect(&server, 8).await;
+
+    // "Hello" is 10 bytes of UTF-16
+    let err = client
+        .query(SELECT_DOC, &[])
+        .await
+        .err()
+        .expect("query should fail");
+    assert!(
+        matches!(&err, Error::Protocol(msg) if msg.contains("LOB value too large")),
+        "unexpected error: {err:?}"
+    );
+
+    // The response was read in full, so the connection is still usable
+    let rows = client
+        .query("SELECT 1", &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<i32>(0).unwrap(), 1);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_query_options_override_limit() {
+    let server = start_server().await;
+    let mut client = connect(&server, 8).await;
+
+    let options = QueryOptions::new().max_lob_size(100);
+    let rows = client
+        .query_with_options(SELECT_DOC, &[], &options)
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<String>(0).unwrap(), "Hello");
+
+    // The override applies to that statement only
+    assert!(client.query(SELECT_DOC, &[]).await.is_err());
+
+    server.stop();
+}
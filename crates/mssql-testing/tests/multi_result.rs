@@ -0,0 +1,142 @@
+//! `Client::query_multiple()` against batches mixing result shapes.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test multi_result
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::{Client, Config, Ready};
+use mssql_testing::mock_server::{MockColumn, MockResponse, MockTdsServer, ScalarValue};
+
+const MIXED_BATCH: &str = "SELECT id FROM a; UPDATE b SET x = 1; SELECT name, score FROM c";
+const FAILING_BATCH: &str = "SELECT id FROM a; INSERT INTO b VALUES (1); SELECT name FROM c";
+
+async fn start_server() -> MockTdsServer {
+    MockTdsServer::builder()
+        .with_response(
+            MIXED_BATCH,
+            MockResponse::batch(vec![
+                MockResponse::rows(
+                    vec![MockColumn::int("id")],
+                    vec![vec![ScalarValue::Int(1)], vec![ScalarValue::Int(2)]],
+                ),
+                MockResponse::affected(3),
+                MockResponse::rows(
+                    vec![
+                        MockColumn::nvarchar("name", 50),
+                        MockColumn::bigint("score"),
+                    ],
+                    vec![vec![
+                        ScalarValue::String("alice".to_string()),
+                        ScalarValue::BigInt(90),
+                    ]],
+                ),
+            ]),
+        )
+        .with_response(
+            FAILING_BATCH,
+            MockResponse::batch(vec![
+                MockResponse::rows(vec![MockColumn::int("id")], vec![vec![ScalarValue::Int(1)]]),
+                MockResponse::error(2627, "Violation of PRIMARY KEY constraint 'PK_b'."),
+                MockResponse::rows(vec![MockColumn::nvarchar("name", 50)], vec![]),
+            ]),
+        )
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .build()
+        .await
+        .expect("mock server should start")
+}
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+fn column_names(columns: &[mssql_client::Column]) -> Vec<&str> {
+    columns.iter().map(|c| c.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_each_result_set_has_its_own_columns() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let mut results = client.query_multiple(MIXED_BATCH, &[]).await.unwrap();
+
+    // The UPDATE only reports a row count, so it adds no result set
+    assert_eq!(results.result_count(), 2);
+    assert_eq!(
+        column_names(results.result_set(0).unwrap().columns()),
+        ["id"]
+    );
+    assert_eq!(
+        column_names(results.result_set(1).unwrap().columns()),
+        ["name", "score"]
+    );
+
+    let first = results.collect_current();
+    assert_eq!(first.len(), 2);
+    assert_eq!(first[1].get::<i32>(0).unwrap(), 2);
+
+    assert!(results.next_result().await.unwrap());
+    assert_eq!(column_names(results.columns().unwrap()), ["name", "score"]);
+    let row = results.next_row().await.unwrap().unwrap();
+    assert_eq!(row.get::<String>(0).unwrap(), "alice");
+    assert_eq!(row.get::<i64>(1).unwrap(), 90);
+    assert!(!results.next_result().await.unwrap());
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_rowcount_only_batch_has_no_result_sets() {
+    let server = MockTdsServer::builder()
+        .with_response(
+            "UPDATE a SET x = 1; DELETE FROM b",
+            MockResponse::batch(vec![MockResponse::affected(2), MockResponse::affected(0)]),
+        )
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    let results = client
+        .query_multiple("UPDATE a SET x = 1; DELETE FROM b", &[])
+        .await
+        .unwrap();
+    assert_eq!(results.result_count(), 0);
+    assert!(results.columns().is_none());
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_error_mid_batch_is_returned() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let err = client
+        .query_multiple(FAILING_BATCH, &[])
+        .await
+        .err()
+        .expect("batch should fail");
+    assert_eq!(err.sql_error_number(), Some(2627));
+
+    // The rest of the batch was read, so the connection is still in sync
+    let rows = client
+        .query("SELECT 1", &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<i32>(0).unwrap(), 1);
+
+    server.stop();
+}
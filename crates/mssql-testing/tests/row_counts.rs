@@ -0,0 +1,80 @@
+//! `Client::execute_detailed()` row counts for procedure calls against the
+//! mock TDS server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test row_counts
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use bytes::{Bytes, BytesMut};
+use mssql_client::{Client, Config, Ready};
+use mssql_testing::mock_server::{MockResponse, MockTdsServer};
+use tds_protocol::token::{DoneInProc, DoneProc, DoneStatus};
+
+const ARCHIVE: &str = "EXEC dbo.ArchiveOrders";
+const PURGE: &str = "EXEC dbo.PurgeOrders";
+
+fn status(count: bool, more: bool) -> DoneStatus {
+    DoneStatus {
+        count,
+        more,
+        ..Default::default()
+    }
+}
+
+/// The tokens of a procedure whose statements affect `counts` rows, ending
+/// with a DONEPROC that reports `proc_count` if set.
+fn procedure(counts: &[u64], proc_count: Option<u64>) -> MockResponse {
+    let mut buf = BytesMut::new();
+    for &row_count in counts {
+        DoneInProc {
+            status: status(true, true),
+            cur_cmd: 0xC4,
+            row_count,
+        }
+        .encode(&mut buf);
+    }
+    DoneProc {
+        status: status(proc_count.is_some(), false),
+        cur_cmd: 0xE0,
+        row_count: proc_count.unwrap_or_default(),
+    }
+    .encode(&mut buf);
+    MockResponse::Raw(Bytes::from(buf))
+}
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+#[tokio::test]
+async fn test_procedure_row_counts_add_up_to_total() {
+    let server = MockTdsServer::builder()
+        .with_response(ARCHIVE, procedure(&[2, 3], None))
+        .with_response(PURGE, procedure(&[2, 3], Some(4)))
+        .build()
+        .await
+        .expect("mock server should start");
+    let mut client = connect(&server).await;
+
+    let result = client.execute_detailed(ARCHIVE, &[]).await.unwrap();
+    let counts: Vec<u64> = result.row_counts.iter().map(|c| c.rows_affected).collect();
+    assert_eq!(counts, [2, 3]);
+    assert_eq!(result.total_rows_affected(), 5);
+
+    // A DONEPROC with a row count is recorded like any other statement
+    let result = client.execute_detailed(PURGE, &[]).await.unwrap();
+    let counts: Vec<u64> = result.row_counts.iter().map(|c| c.rows_affected).collect();
+    assert_eq!(counts, [2, 3, 4]);
+    assert!(result.row_counts.iter().all(|c| c.count_valid));
+    assert_eq!(result.total_rows_affected(), 9);
+
+    server.stop();
+}
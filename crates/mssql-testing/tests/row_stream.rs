@@ -0,0 +1,199 @@
+//! `Client::query_stream()` prefetch limits against the mock TDS server.
+//!
+//! ```bash
+//! cargo test -p mssql-testing --test row_stream
+//! ```
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use mssql_client::{Client, Config, QueryOptions, Ready};
+use mssql_testing::mock_server::{MockColumn, MockResponse, MockTdsServer, ScalarValue};
+
+const SELECT_MANY: &str = "SELECT n FROM numbers";
+const SELECT_FAILING: &str = "SELECT n FROM numbers; INSERT INTO t VALUES (1)";
+const ROWS: i32 = 5000;
+
+fn numbers() -> MockResponse {
+    MockResponse::rows(
+        vec![MockColumn::int("n")],
+        (0..ROWS).map(|n| vec![ScalarValue::Int(n)]).collect(),
+    )
+}
+
+async fn start_server() -> MockTdsServer {
+    MockTdsServer::builder()
+        .with_response(SELECT_MANY, numbers())
+        .with_response(
+            SELECT_FAILING,
+            MockResponse::batch(vec![
+                numbers(),
+                MockResponse::error(2627, "Violation of PRIMARY KEY constraint 'PK_t'."),
+            ]),
+        )
+        .with_response("SELECT 1", MockResponse::scalar_int(1))
+        .build()
+        .await
+        .expect("mock server should start")
+}
+
+async fn connect(server: &MockTdsServer) -> Client<Ready> {
+    let config = Config::from_connection_string(&format!(
+        "Server={},{};User Id=sa;Password=secret;Encrypt=no_tls",
+        server.host(),
+        server.port()
+    ))
+    .unwrap();
+    Client::connect(config).await.expect("should connect")
+}
+
+async fn select_one(client: &mut Client<Ready>) -> i32 {
+    let rows = client
+        .query("SELECT 1", &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    rows[0].get(0).unwrap()
+}
+
+#[tokio::test]
+async fn test_stream_reads_all_rows_in_order() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let mut stream = client.query_stream(SELECT_MANY, &[]).await.unwrap();
+    assert_eq!(stream.columns()[0].name, "n");
+
+    let mut expected = 0;
+    while let Some(row) = stream.next_row().await.unwrap() {
+        assert_eq!(row.get::<i32>(0).unwrap(), expected);
+        expected += 1;
+    }
+    assert_eq!(expected, ROWS);
+    assert!(stream.is_complete());
+    drop(stream);
+
+    assert_eq!(select_one(&mut client).await, 1);
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_prefetch_rows_bounds_decoded_rows() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let options = QueryOptions::new().prefetch_rows(10);
+    let mut stream = client
+        .query_stream_with_options(SELECT_MANY, &[], &options)
+        .await
+        .unwrap();
+
+    let mut count = 0;
+    while let Some(row) = stream.next_row().await.unwrap() {
+        assert!(stream.rows_buffered() < 10);
+        assert_eq!(row.get::<i32>(0).unwrap(), count);
+        count += 1;
+    }
+    assert_eq!(count, ROWS);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_max_buffered_bytes_bounds_decoded_rows() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    // Each INTN value is 5 bytes: a length byte and the value
+    let options = QueryOptions::new().max_buffered_bytes(16);
+    let mut stream = client
+        .query_stream_with_options(SELECT_MANY, &[], &options)
+        .await
+        .unwrap();
+
+    let mut count = 0;
+    while stream.next_row().await.unwrap().is_some() {
+        assert!(stream.bytes_buffered() < 20);
+        assert!(stream.rows_buffered() < 4);
+        count += 1;
+    }
+    assert_eq!(count, ROWS);
+
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_dropped_stream_is_cancelled_and_drained() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let options = QueryOptions::new().prefetch_rows(1);
+    let mut stream = client
+        .query_stream_with_options(SELECT_MANY, &[], &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        stream
+            .next_row()
+            .await
+            .unwrap()
+            .unwrap()
+            .get::<i32>(0)
+            .unwrap(),
+        0
+    );
+    assert!(!stream.is_complete());
+    drop(stream);
+
+    // The rest of the response must not be read as this query's
+    assert_eq!(select_one(&mut client).await, 1);
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_error_after_rows_is_returned_after_them() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let mut stream = client.query_stream(SELECT_FAILING, &[]).await.unwrap();
+    let mut count = 0;
+    let err = loop {
+        match stream.next_row().await {
+            Ok(Some(_)) => count += 1,
+            other => break other.expect_err("stream should fail"),
+        }
+    };
+    assert_eq!(count, ROWS);
+    assert_eq!(err.sql_error_number(), Some(2627));
+    drop(stream);
+
+    assert_eq!(select_one(&mut client).await, 1);
+    server.stop();
+}
+
+#[tokio::test]
+async fn test_rows_share_result_set_metadata() {
+    let server = start_server().await;
+    let mut client = connect(&server).await;
+
+    let rows = client
+        .query(SELECT_MANY, &[])
+        .await
+        .unwrap()
+        .collect_all()
+        .await
+        .unwrap();
+    assert!(std::sync::Arc::ptr_eq(
+        rows[0].metadata(),
+        rows[1].metadata()
+    ));
+
+    let mut stream = client.query_stream(SELECT_MANY, &[]).await.unwrap();
+    let first = stream.next_row().await.unwrap().unwrap();
+    let second = stream.next_row().await.unwrap().unwrap();
+    assert!(std::sync::Arc::ptr_eq(first.metadata(), second.metadata()));
+    drop(stream);
+
+    server.stop();
+}
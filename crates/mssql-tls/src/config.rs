@@ -66,6 +66,9 @@ pub struct TlsConfig {
 
     /// Application-layer protocol negotiation (ALPN) protocols.
     pub alpn_protocols: Vec<Vec<u8>>,
+
+    /// TLS implementation used for the handshake.
+    pub backend: TlsBackend,
 }
 
 impl Default for TlsConfig {
@@ -79,6 +82,7 @@ impl Default for TlsConfig {
             max_protocol_version: TlsVersion::Tls13,
             strict_mode: false,
             alpn_protocols: Vec::new(),
+            backend: TlsBackend::default(),
         }
     }
 }
@@ -159,6 +163,13 @@ impl TlsConfig {
         self
     }
 
+    /// Select the TLS implementation.
+    #[must_use]
+    pub fn backend(mut self, backend: TlsBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
     /// Check if client certificate authentication is configured.
     #[must_use]
     pub fn has_client_auth(&self) -> bool {
@@ -166,6 +177,52 @@ impl TlsConfig {
     }
 }
 
+/// TLS implementation used by a [`TlsConnector`](crate::TlsConnector).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum TlsBackend {
+    /// rustls with the Mozilla root store (pure Rust, the default).
+    #[default]
+    Rustls,
+    /// The platform TLS stack via `native-tls`: SChannel on Windows,
+    /// Secure Transport on macOS and OpenSSL elsewhere.
+    ///
+    /// Certificates are validated against the operating system's trust
+    /// store, and the platform's FIPS policy applies. Requires the
+    /// `native-tls` feature.
+    NativeTls,
+}
+
+impl TlsBackend {
+    /// Check if this backend was compiled in.
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Rustls => true,
+            Self::NativeTls => cfg!(feature = "native-tls"),
+        }
+    }
+}
+
+impl std::str::FromStr for TlsBackend {
+    type Err = crate::TlsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("rustls") {
+            Ok(Self::Rustls)
+        } else if ["native", "native-tls", "schannel"]
+            .iter()
+            .any(|name| s.eq_ignore_ascii_case(name))
+        {
+            Ok(Self::NativeTls)
+        } else {
+            Err(crate::TlsError::Configuration(format!(
+                "unknown TLS backend: {s}"
+            )))
+        }
+    }
+}
+
 /// TLS protocol version.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum TlsVersion {
@@ -7,10 +7,10 @@ use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
 use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_rustls::TlsConnector as TokioTlsConnector;
-use tokio_rustls::client::TlsStream;
 
-use crate::config::{TlsConfig, TlsVersion};
+use crate::config::{TlsBackend, TlsConfig, TlsVersion};
 use crate::error::TlsError;
+use crate::stream::TlsStream;
 
 // =============================================================================
 // Crypto Provider Initialization
@@ -125,17 +125,40 @@ pub fn default_tls_config() -> Result<ClientConfig, TlsError> {
 /// TLS connector for SQL Server connections.
 ///
 /// This handles both TDS 7.x style (TLS after pre-login) and TDS 8.0
-/// strict mode (TLS before any TDS traffic).
+/// strict mode (TLS before any TDS traffic), with the backend selected by
+/// [`TlsConfig::backend`].
 pub struct TlsConnector {
     config: TlsConfig,
-    inner: TokioTlsConnector,
+    inner: Backend,
+}
+
+/// Backend-specific connector.
+enum Backend {
+    Rustls(TokioTlsConnector),
+    #[cfg(feature = "native-tls")]
+    NativeTls(tokio_native_tls::TlsConnector),
 }
 
 impl TlsConnector {
     /// Create a new TLS connector with the given configuration.
+    ///
+    /// Fails with [`TlsError::Configuration`] if the selected backend was
+    /// not compiled in.
     pub fn new(config: TlsConfig) -> Result<Self, TlsError> {
-        let client_config = Self::build_client_config(&config)?;
-        let inner = TokioTlsConnector::from(Arc::new(client_config));
+        let inner = match config.backend {
+            TlsBackend::Rustls => {
+                let client_config = Self::build_client_config(&config)?;
+                Backend::Rustls(TokioTlsConnector::from(Arc::new(client_config)))
+            }
+            #[cfg(feature = "native-tls")]
+            TlsBackend::NativeTls => Backend::NativeTls(crate::native::build_connector(&config)?),
+            #[cfg(not(feature = "native-tls"))]
+            TlsBackend::NativeTls => {
+                return Err(TlsError::Configuration(
+                    "the native-tls backend requires the `native-tls` feature".into(),
+                ));
+            }
+        };
 
         Ok(Self { config, inner })
     }
@@ -261,20 +284,9 @@ impl TlsConnector {
     {
         let server_name = self.config.server_name.as_deref().unwrap_or(server_name);
 
-        let dns_name = ServerName::try_from(server_name.to_string()).map_err(|_| {
-            TlsError::HostnameVerification {
-                expected: server_name.to_string(),
-                actual: "invalid DNS name".to_string(),
-            }
-        })?;
+        tracing::debug!(server_name = %server_name, backend = ?self.config.backend, "performing TLS handshake");
 
-        tracing::debug!(server_name = %server_name, "performing TLS handshake");
-
-        let tls_stream = self
-            .inner
-            .connect(dns_name, stream)
-            .await
-            .map_err(|e| TlsError::HandshakeFailed(e.to_string()))?;
+        let tls_stream = self.handshake(stream, server_name).await?;
 
         tracing::debug!("TLS handshake completed successfully");
 
@@ -305,33 +317,54 @@ impl TlsConnector {
     {
         let server_name = self.config.server_name.as_deref().unwrap_or(server_name);
 
-        let dns_name = ServerName::try_from(server_name.to_string()).map_err(|_| {
-            TlsError::HostnameVerification {
-                expected: server_name.to_string(),
-                actual: "invalid DNS name".to_string(),
-            }
-        })?;
-
-        tracing::debug!(server_name = %server_name, "performing TLS handshake (PreLogin wrapped)");
+        tracing::debug!(server_name = %server_name, backend = ?self.config.backend, "performing TLS handshake (PreLogin wrapped)");
 
         // Wrap the stream in a PreLogin wrapper
         let wrapper = crate::TlsPreloginWrapper::new(stream);
 
-        let mut tls_stream = self
-            .inner
-            .connect(dns_name, wrapper)
-            .await
-            .map_err(|e| TlsError::HandshakeFailed(e.to_string()))?;
+        let mut tls_stream = self.handshake(wrapper, server_name).await?;
 
         // Mark the handshake as complete so the wrapper becomes pass-through
-        // get_mut() returns (&mut IO, &mut ClientConnection), so access .0 for the wrapper
-        tls_stream.get_mut().0.handshake_complete();
+        if let Some(wrapper) = tls_stream.get_mut() {
+            wrapper.handshake_complete();
+        }
 
         tracing::debug!("TLS handshake completed successfully (PreLogin wrapped)");
 
         Ok(tls_stream)
     }
 
+    /// Run the handshake with the configured backend.
+    async fn handshake<S>(&self, stream: S, server_name: &str) -> Result<TlsStream<S>, TlsError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        match &self.inner {
+            Backend::Rustls(connector) => {
+                let dns_name = ServerName::try_from(server_name.to_string()).map_err(|_| {
+                    TlsError::HostnameVerification {
+                        expected: server_name.to_string(),
+                        actual: "invalid DNS name".to_string(),
+                    }
+                })?;
+
+                let tls_stream = connector
+                    .connect(dns_name, stream)
+                    .await
+                    .map_err(|e| TlsError::HandshakeFailed(e.to_string()))?;
+                Ok(TlsStream::Rustls(tls_stream))
+            }
+            #[cfg(feature = "native-tls")]
+            Backend::NativeTls(connector) => {
+                let tls_stream = connector
+                    .connect(server_name, crate::stream::Detachable::new(stream))
+                    .await
+                    .map_err(|e| TlsError::HandshakeFailed(e.to_string()))?;
+                Ok(TlsStream::NativeTls(tls_stream))
+            }
+        }
+    }
+
     /// Check if this connector is configured for TDS 8.0 strict mode.
     #[must_use]
     pub fn is_strict_mode(&self) -> bool {
@@ -379,6 +412,20 @@ mod tests {
         assert!(!connector.is_strict_mode());
     }
 
+    #[test]
+    fn test_backend_selection() {
+        setup_crypto_provider();
+        let connector = TlsConnector::new(TlsConfig::new()).unwrap();
+        assert!(matches!(connector.inner, Backend::Rustls(_)));
+
+        let result = TlsConnector::new(TlsConfig::new().backend(TlsBackend::NativeTls));
+        if TlsBackend::NativeTls.is_available() {
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(result, Err(TlsError::Configuration(_))));
+        }
+    }
+
     #[test]
     fn test_strict_mode() {
         setup_crypto_provider();
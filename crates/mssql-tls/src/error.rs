@@ -42,6 +42,11 @@ pub enum TlsError {
     #[error("rustls error: {0}")]
     Rustls(#[from] rustls::Error),
 
+    /// native-tls error.
+    #[cfg(feature = "native-tls")]
+    #[error("native-tls error: {0}")]
+    NativeTls(#[from] native_tls::Error),
+
     /// Server requires encryption but client disabled it.
     #[error("server requires encryption")]
     EncryptionRequired,
@@ -20,11 +20,22 @@
 //! ## Features
 //!
 //! - TLS 1.2 and TLS 1.3 support via rustls
+//! - Optional platform TLS (SChannel, Secure Transport, OpenSSL) via the
+//!   `native-tls` feature
 //! - Server certificate validation
 //! - Hostname verification
 //! - Custom certificate authority support
 //! - Client certificate authentication (TDS 8.0)
 //!
+//! ## Backends
+//!
+//! [`TlsConfig::backend`] selects the TLS implementation. [`TlsBackend::Rustls`]
+//! is the default and always available. [`TlsBackend::NativeTls`], enabled by
+//! the `native-tls` feature, uses the operating system's TLS stack and trust
+//! store, which is what Windows environments with certificate-store or FIPS
+//! requirements need. Both backends support TDS 7.x PreLogin wrapping and
+//! TDS 8.0 strict mode, and produce the same [`TlsStream`] type.
+//!
 //! ## Security
 //!
 //! By default, this crate validates server certificates using the Mozilla
@@ -49,15 +60,16 @@
 pub mod config;
 pub mod connector;
 pub mod error;
+#[cfg(feature = "native-tls")]
+mod native;
 pub mod prelogin_wrapper;
+pub mod stream;
 
-pub use config::{ClientAuth, TlsConfig, TlsVersion};
+pub use config::{ClientAuth, TlsBackend, TlsConfig, TlsVersion};
 pub use connector::{TlsConnector, default_tls_config};
 pub use error::TlsError;
 pub use prelogin_wrapper::TlsPreloginWrapper;
-
-// Re-export tokio-rustls stream type for convenience
-pub use tokio_rustls::client::TlsStream;
+pub use stream::TlsStream;
 
 /// TDS TLS negotiation mode.
 ///
@@ -114,6 +126,7 @@ impl TlsNegotiationMode {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -142,4 +155,19 @@ mod tests {
             TlsNegotiationMode::PostPreLogin
         );
     }
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("rustls".parse::<TlsBackend>().unwrap(), TlsBackend::Rustls);
+        assert_eq!(
+            "Native".parse::<TlsBackend>().unwrap(),
+            TlsBackend::NativeTls
+        );
+        assert_eq!(
+            "SChannel".parse::<TlsBackend>().unwrap(),
+            TlsBackend::NativeTls
+        );
+        assert!("boringssl".parse::<TlsBackend>().is_err());
+        assert!(TlsBackend::Rustls.is_available());
+    }
 }
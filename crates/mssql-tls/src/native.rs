@@ -0,0 +1,106 @@
+//! native-tls backend (SChannel, Secure Transport, OpenSSL).
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use native_tls::{Certificate, Identity, Protocol};
+use rustls::pki_types::PrivateKeyDer;
+
+use crate::config::{TlsConfig, TlsVersion};
+use crate::error::TlsError;
+
+/// Build a native-tls connector from the configuration.
+///
+/// With no custom roots configured, the operating system trust store is
+/// used. native-tls cannot pin TLS 1.3 as a minimum, so `Tls13` minimums
+/// are rejected rather than silently weakened.
+pub(crate) fn build_connector(
+    config: &TlsConfig,
+) -> Result<tokio_native_tls::TlsConnector, TlsError> {
+    let mut builder = native_tls::TlsConnector::builder();
+
+    if config.min_protocol_version > TlsVersion::Tls12 {
+        return Err(TlsError::Configuration(
+            "the native-tls backend cannot require TLS 1.3".into(),
+        ));
+    }
+    builder.min_protocol_version(Some(Protocol::Tlsv12));
+    if config.max_protocol_version == TlsVersion::Tls12 {
+        builder.max_protocol_version(Some(Protocol::Tlsv12));
+    }
+
+    if config.trust_server_certificate {
+        tracing::warn!(
+            "TrustServerCertificate is enabled - certificate validation is DISABLED. \
+             This is insecure and should only be used for development/testing. \
+             Connections are vulnerable to man-in-the-middle attacks."
+        );
+        builder
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true);
+    } else if !config.root_certificates.is_empty() {
+        builder.disable_built_in_roots(true);
+        for cert in &config.root_certificates {
+            let cert = Certificate::from_der(cert)
+                .map_err(|e| TlsError::InvalidCertificate(e.to_string()))?;
+            builder.add_root_certificate(cert);
+        }
+    }
+
+    if let Some(client_auth) = &config.client_auth {
+        let PrivateKeyDer::Pkcs8(key) = client_auth.key.as_ref() else {
+            return Err(TlsError::InvalidPrivateKey(
+                "the native-tls backend requires a PKCS#8 key".into(),
+            ));
+        };
+
+        let chain: String = client_auth
+            .certificates
+            .iter()
+            .map(|cert| pem("CERTIFICATE", cert))
+            .collect();
+        let key = pem("PRIVATE KEY", key.secret_pkcs8_der());
+        let identity = Identity::from_pkcs8(chain.as_bytes(), key.as_bytes())
+            .map_err(|e| TlsError::InvalidPrivateKey(e.to_string()))?;
+        builder.identity(identity);
+    }
+
+    let connector = builder.build()?;
+    Ok(tokio_native_tls::TlsConnector::from(connector))
+}
+
+/// Encode DER bytes as a PEM block.
+fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut out = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        out.push_str(&String::from_utf8_lossy(line));
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {label}-----\n"));
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pem_wraps_lines() {
+        let block = pem("CERTIFICATE", &[0xAB; 60]);
+        let lines: Vec<_> = block.lines().collect();
+        assert_eq!(lines[0], "-----BEGIN CERTIFICATE-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[2].len(), 16);
+        assert_eq!(lines[3], "-----END CERTIFICATE-----");
+    }
+
+    #[test]
+    fn test_build_connector() {
+        assert!(build_connector(&TlsConfig::new()).is_ok());
+        assert!(build_connector(&TlsConfig::new().trust_server_certificate(true)).is_ok());
+
+        let err = build_connector(&TlsConfig::new().min_protocol_version(TlsVersion::Tls13));
+        assert!(matches!(err, Err(TlsError::Configuration(_))));
+    }
+}
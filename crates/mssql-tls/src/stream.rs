@@ -0,0 +1,187 @@
+//! Backend-independent TLS stream.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::config::TlsBackend;
+
+/// An established TLS session over a stream `S`.
+///
+/// Wraps the stream type of whichever [`TlsBackend`] performed the
+/// handshake, so connections have the same type regardless of backend.
+// The rustls session is kept inline: it is the default backend, and boxing it
+// would add an indirection to every read and write.
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum TlsStream<S> {
+    /// A rustls session.
+    Rustls(tokio_rustls::client::TlsStream<S>),
+    /// A native-tls session.
+    #[cfg(feature = "native-tls")]
+    NativeTls(tokio_native_tls::TlsStream<Detachable<S>>),
+}
+
+impl<S> TlsStream<S> {
+    /// Get the backend that negotiated this session.
+    #[must_use]
+    pub fn backend(&self) -> TlsBackend {
+        match self {
+            Self::Rustls(_) => TlsBackend::Rustls,
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(_) => TlsBackend::NativeTls,
+        }
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> Option<&mut S> {
+        match self {
+            Self::Rustls(stream) => Some(stream.get_mut().0),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => stream.get_mut().get_mut().get_mut().0.as_mut(),
+        }
+    }
+
+    /// Drop the TLS session and return the underlying stream.
+    ///
+    /// No close_notify alert is sent. This is used for TDS login-only
+    /// encryption, where the server continues in cleartext after Login7.
+    ///
+    /// Returns `None` only if the underlying stream was already taken.
+    pub fn into_inner(self) -> Option<S> {
+        match self {
+            Self::Rustls(stream) => Some(stream.into_inner().0),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(mut stream) => stream.get_mut().get_mut().get_mut().0.take(),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for TlsStream<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsStream")
+            .field("backend", &self.backend())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for TlsStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Rustls(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for TlsStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Rustls(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Rustls(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Self::Rustls(stream) => stream.is_write_vectored(),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => stream.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Rustls(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Rustls(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// A stream that can be taken out from under a native-tls session.
+///
+/// native-tls has no way to recover the transport once a session is
+/// established, which login-only encryption needs.
+#[cfg(feature = "native-tls")]
+#[derive(Debug)]
+pub struct Detachable<S>(Option<S>);
+
+#[cfg(feature = "native-tls")]
+impl<S> Detachable<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        Self(Some(stream))
+    }
+
+    fn stream(&mut self) -> io::Result<Pin<&mut S>>
+    where
+        S: Unpin,
+    {
+        self.0
+            .as_mut()
+            .map(Pin::new)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))
+    }
+}
+
+#[cfg(feature = "native-tls")]
+impl<S: AsyncRead + Unpin> AsyncRead for Detachable<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.get_mut().stream()?.poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "native-tls")]
+impl<S: AsyncWrite + Unpin> AsyncWrite for Detachable<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().stream()?.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().stream()?.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().stream()?.poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,348 @@
+//! Scale-preserving `DATETIME2` and `DATETIMEOFFSET` values.
+//!
+//! chrono types carry nanosecond precision but not the fractional-second
+//! scale a column was declared with, so a `DATETIME2(3)` value read into a
+//! `NaiveDateTime` comes back as a `DATETIME2(7)` parameter. [`SqlDateTime2`]
+//! and [`SqlDateTimeOffset`] keep the scale next to the value: they are what
+//! `DATETIME2` and `DATETIMEOFFSET` columns decode to, and they declare
+//! their parameters with the same scale.
+//!
+//! ```rust,ignore
+//! let created: SqlDateTime2 = row.get(0)?; // DATETIME2(3)
+//! assert_eq!(created.scale, 3);
+//! client.execute("UPDATE t SET created = @p1", &[&created]).await?; // declared DATETIME2(3)
+//! ```
+
+// Allow expect() for chrono date construction with known-valid constant dates
+#![allow(clippy::expect_used)]
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::error::TypeError;
+
+/// Largest fractional-second scale of `TIME`, `DATETIME2` and `DATETIMEOFFSET`.
+pub const MAX_SCALE: u8 = 7;
+
+const DATETIME2_TYPES: [&str; 8] = [
+    "DATETIME2(0)",
+    "DATETIME2(1)",
+    "DATETIME2(2)",
+    "DATETIME2(3)",
+    "DATETIME2(4)",
+    "DATETIME2(5)",
+    "DATETIME2(6)",
+    "DATETIME2(7)",
+];
+
+const DATETIMEOFFSET_TYPES: [&str; 8] = [
+    "DATETIMEOFFSET(0)",
+    "DATETIMEOFFSET(1)",
+    "DATETIMEOFFSET(2)",
+    "DATETIMEOFFSET(3)",
+    "DATETIMEOFFSET(4)",
+    "DATETIMEOFFSET(5)",
+    "DATETIMEOFFSET(6)",
+    "DATETIMEOFFSET(7)",
+];
+
+/// A `DATETIME2` value with its fractional-second scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SqlDateTime2 {
+    /// The date and time, with no precision beyond `scale`.
+    pub value: NaiveDateTime,
+    /// Fractional-second digits, 0 to 7.
+    pub scale: u8,
+}
+
+impl SqlDateTime2 {
+    /// Create a value with the given scale.
+    ///
+    /// Fractional seconds beyond `scale` are truncated, so the value is
+    /// exactly what the server stores.
+    pub fn new(value: NaiveDateTime, scale: u8) -> Result<Self, TypeError> {
+        check_scale(scale)?;
+        Ok(Self {
+            value: value
+                .with_nanosecond(truncate_nanos(value.nanosecond(), scale))
+                .unwrap_or(value),
+            scale,
+        })
+    }
+
+    /// The SQL type declaration, e.g. `DATETIME2(3)`.
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        DATETIME2_TYPES
+            .get(self.scale as usize)
+            .copied()
+            .unwrap_or("DATETIME2")
+    }
+}
+
+impl From<SqlDateTime2> for NaiveDateTime {
+    fn from(v: SqlDateTime2) -> Self {
+        v.value
+    }
+}
+
+/// A `DATETIMEOFFSET` value with its fractional-second scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SqlDateTimeOffset {
+    /// The date, time and offset, with no precision beyond `scale`.
+    pub value: DateTime<FixedOffset>,
+    /// Fractional-second digits, 0 to 7.
+    pub scale: u8,
+}
+
+impl SqlDateTimeOffset {
+    /// Create a value with the given scale.
+    ///
+    /// Fractional seconds beyond `scale` are truncated, so the value is
+    /// exactly what the server stores.
+    pub fn new(value: DateTime<FixedOffset>, scale: u8) -> Result<Self, TypeError> {
+        check_scale(scale)?;
+        Ok(Self {
+            value: value
+                .with_nanosecond(truncate_nanos(value.nanosecond(), scale))
+                .unwrap_or(value),
+            scale,
+        })
+    }
+
+    /// The SQL type declaration, e.g. `DATETIMEOFFSET(3)`.
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        DATETIMEOFFSET_TYPES
+            .get(self.scale as usize)
+            .copied()
+            .unwrap_or("DATETIMEOFFSET")
+    }
+}
+
+impl From<SqlDateTimeOffset> for DateTime<FixedOffset> {
+    fn from(v: SqlDateTimeOffset) -> Self {
+        v.value
+    }
+}
+
+fn check_scale(scale: u8) -> Result<(), TypeError> {
+    if scale > MAX_SCALE {
+        return Err(TypeError::InvalidDateTime(format!(
+            "scale {scale} exceeds the maximum of {MAX_SCALE}"
+        )));
+    }
+    Ok(())
+}
+
+/// Length of the date part of `DATE`, `DATETIME2` and `DATETIMEOFFSET`.
+const DATE_LEN: usize = 3;
+/// Length of the offset part of `DATETIMEOFFSET`.
+const OFFSET_LEN: usize = 2;
+
+/// Units of one time interval at `scale`, in nanoseconds.
+fn interval_nanos(scale: u8) -> u64 {
+    10u64.pow(9 - u32::from(scale.min(MAX_SCALE)))
+}
+
+fn truncate_nanos(nanos: u32, scale: u8) -> u32 {
+    let unit = interval_nanos(scale) as u32;
+    nanos - nanos % unit
+}
+
+/// Number of bytes of a `TIME` value at `scale`.
+#[must_use]
+pub fn time_len(scale: u8) -> usize {
+    match scale {
+        0..=2 => 3,
+        3..=4 => 4,
+        _ => 5,
+    }
+}
+
+/// Encoded length of a `DATETIME2` value at `scale`.
+#[must_use]
+pub fn datetime2_len(scale: u8) -> usize {
+    time_len(scale) + DATE_LEN
+}
+
+/// Encoded length of a `DATETIMEOFFSET` value at `scale`.
+#[must_use]
+pub fn datetimeoffset_len(scale: u8) -> usize {
+    time_len(scale) + DATE_LEN + OFFSET_LEN
+}
+
+/// Encode a time of day as `scale` intervals since midnight.
+pub fn encode_time(time: NaiveTime, scale: u8, buf: &mut BytesMut) {
+    let nanos =
+        u64::from(time.num_seconds_from_midnight()) * 1_000_000_000 + u64::from(time.nanosecond());
+    let intervals = nanos / interval_nanos(scale);
+    buf.put_slice(&intervals.to_le_bytes()[..time_len(scale)]);
+}
+
+fn encode_date(date: NaiveDate, buf: &mut BytesMut) {
+    let base = NaiveDate::from_ymd_opt(1, 1, 1).expect("valid date");
+    let days = date.signed_duration_since(base).num_days() as u32;
+    buf.put_slice(&days.to_le_bytes()[..DATE_LEN]);
+}
+
+/// Encode a `DATETIME2` value: time at `scale`, then the date.
+pub fn encode_datetime2(value: NaiveDateTime, scale: u8, buf: &mut BytesMut) {
+    encode_time(value.time(), scale, buf);
+    encode_date(value.date(), buf);
+}
+
+/// Encode a `DATETIMEOFFSET` value: UTC time at `scale`, the UTC date,
+/// then the offset in minutes.
+pub fn encode_datetimeoffset(value: DateTime<FixedOffset>, scale: u8, buf: &mut BytesMut) {
+    let utc = value.naive_utc();
+    encode_time(utc.time(), scale, buf);
+    encode_date(utc.date(), buf);
+    buf.put_i16_le((value.offset().local_minus_utc() / 60) as i16);
+}
+
+fn decode_time(buf: &mut Bytes, scale: u8) -> Result<NaiveTime, TypeError> {
+    let mut bytes = [0u8; 8];
+    buf.copy_to_slice(&mut bytes[..time_len(scale)]);
+    let nanos = u64::from_le_bytes(bytes) * interval_nanos(scale);
+    NaiveTime::from_num_seconds_from_midnight_opt(
+        (nanos / 1_000_000_000) as u32,
+        (nanos % 1_000_000_000) as u32,
+    )
+    .ok_or_else(|| TypeError::InvalidDateTime(format!("invalid time: {nanos} ns")))
+}
+
+fn decode_date(buf: &mut Bytes) -> NaiveDate {
+    let mut bytes = [0u8; 4];
+    buf.copy_to_slice(&mut bytes[..DATE_LEN]);
+    let base = NaiveDate::from_ymd_opt(1, 1, 1).expect("valid date");
+    base + chrono::Duration::days(i64::from(u32::from_le_bytes(bytes)))
+}
+
+fn ensure_len(buf: &Bytes, needed: usize) -> Result<(), TypeError> {
+    if buf.remaining() < needed {
+        return Err(TypeError::BufferTooSmall {
+            needed,
+            available: buf.remaining(),
+        });
+    }
+    Ok(())
+}
+
+/// Decode a `DATETIME2` value body (without its length prefix).
+pub fn decode_datetime2(buf: &mut Bytes, scale: u8) -> Result<SqlDateTime2, TypeError> {
+    check_scale(scale)?;
+    ensure_len(buf, datetime2_len(scale))?;
+    let time = decode_time(buf, scale)?;
+    let date = decode_date(buf);
+    Ok(SqlDateTime2 {
+        value: date.and_time(time),
+        scale,
+    })
+}
+
+/// Decode a `DATETIMEOFFSET` value body (without its length prefix).
+pub fn decode_datetimeoffset(buf: &mut Bytes, scale: u8) -> Result<SqlDateTimeOffset, TypeError> {
+    check_scale(scale)?;
+    ensure_len(buf, datetimeoffset_len(scale))?;
+    let time = decode_time(buf, scale)?;
+    let date = decode_date(buf);
+    let offset_minutes = buf.get_i16_le();
+    let offset = FixedOffset::east_opt(i32::from(offset_minutes) * 60)
+        .ok_or_else(|| TypeError::InvalidDateTime(format!("invalid offset: {offset_minutes}")))?;
+    Ok(SqlDateTimeOffset {
+        value: DateTime::from_naive_utc_and_offset(date.and_time(time), offset),
+        scale,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn datetime(nanos: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 2, 29)
+            .unwrap()
+            .and_hms_nano_opt(23, 59, 59, nanos)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_new_truncates_to_scale() {
+        let v = SqlDateTime2::new(datetime(123_456_789), 0).unwrap();
+        assert_eq!(v.value, datetime(0));
+        assert_eq!(v.type_name(), "DATETIME2(0)");
+
+        let v = SqlDateTime2::new(datetime(123_456_789), 7).unwrap();
+        assert_eq!(v.value, datetime(123_456_700));
+        assert_eq!(v.type_name(), "DATETIME2(7)");
+
+        assert!(SqlDateTime2::new(datetime(0), 8).is_err());
+    }
+
+    #[test]
+    fn test_datetime2_round_trip_at_scale_boundaries() {
+        for (scale, len) in [(0, 6), (7, 8)] {
+            let v = SqlDateTime2::new(datetime(999_999_999), scale).unwrap();
+            let mut buf = BytesMut::new();
+            encode_datetime2(v.value, v.scale, &mut buf);
+            assert_eq!(buf.len(), len);
+
+            let decoded = decode_datetime2(&mut buf.freeze(), scale).unwrap();
+            assert_eq!(decoded, v);
+        }
+    }
+
+    #[test]
+    fn test_datetimeoffset_round_trip_at_scale_boundaries() {
+        let offset = FixedOffset::east_opt(-(5 * 3600 + 30 * 60)).unwrap();
+        let value = datetime(999_999_999).and_local_timezone(offset).unwrap();
+
+        for (scale, len) in [(0, 8), (7, 10)] {
+            let v = SqlDateTimeOffset::new(value, scale).unwrap();
+            let mut buf = BytesMut::new();
+            encode_datetimeoffset(v.value, v.scale, &mut buf);
+            assert_eq!(buf.len(), len);
+            // The wire value is UTC: 23:59:59 -05:30 is 05:29:59 the next day.
+            assert_eq!(&buf[len - 2..], &(-330i16).to_le_bytes());
+
+            let decoded = decode_datetimeoffset(&mut buf.freeze(), scale).unwrap();
+            assert_eq!(decoded, v);
+            assert_eq!(decoded.value.offset(), &offset);
+            assert_eq!(decoded.type_name(), DATETIMEOFFSET_TYPES[scale as usize]);
+        }
+    }
+
+    #[test]
+    fn test_to_sql_declares_scale() {
+        use crate::{FromSql, SqlValue, ToSql};
+
+        for scale in [0, 7] {
+            let v = SqlDateTime2::new(datetime(500_000_000), scale).unwrap();
+            assert_eq!(v.sql_type(), DATETIME2_TYPES[scale as usize]);
+            let sql = v.to_sql().unwrap();
+            assert_eq!(sql, SqlValue::ScaledDateTime2(v));
+            assert_eq!(SqlDateTime2::from_sql(&sql).unwrap().scale, scale);
+        }
+
+        let plain = SqlValue::DateTime(datetime(0));
+        assert_eq!(SqlDateTime2::from_sql(&plain).unwrap().scale, MAX_SCALE);
+    }
+
+    #[test]
+    fn test_decode_datetimeoffset_utc() {
+        // 2024-01-01 00:00:00 UTC at scale 0 with a +02:00 offset.
+        let days = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .signed_duration_since(NaiveDate::from_ymd_opt(1, 1, 1).unwrap())
+            .num_days() as u32;
+        let mut buf = BytesMut::new();
+        buf.put_slice(&[0, 0, 0]);
+        buf.put_slice(&days.to_le_bytes()[..3]);
+        buf.put_i16_le(120);
+
+        let v = decode_datetimeoffset(&mut buf.freeze(), 0).unwrap();
+        assert_eq!(v.value.to_rfc3339(), "2024-01-01T02:00:00+02:00");
+    }
+}
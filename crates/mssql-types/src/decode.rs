@@ -718,9 +718,6 @@ fn decode_time(buf: &mut Bytes, _type_info: &TypeInfo) -> Result<SqlValue, TypeE
 
 #[cfg(feature = "chrono")]
 fn decode_datetime2(buf: &mut Bytes, type_info: &TypeInfo) -> Result<SqlValue, TypeError> {
-    let scale = type_info.scale.unwrap_or(7);
-    let time_len = time_bytes_for_scale(scale);
-
     if buf.remaining() < 1 {
         return Err(TypeError::BufferTooSmall {
             needed: 1,
@@ -740,20 +737,9 @@ fn decode_datetime2(buf: &mut Bytes, type_info: &TypeInfo) -> Result<SqlValue, T
         });
     }
 
-    // Decode time
-    let mut time_bytes = [0u8; 8];
-    for byte in time_bytes.iter_mut().take(time_len) {
-        *byte = buf.get_u8();
-    }
-    let intervals = u64::from_le_bytes(time_bytes);
-    let time = intervals_to_time(intervals, scale);
-
-    // Decode date
-    let days = buf.get_u8() as u32 | ((buf.get_u8() as u32) << 8) | ((buf.get_u8() as u32) << 16);
-    let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).expect("valid date");
-    let date = base + chrono::Duration::days(days as i64);
-
-    Ok(SqlValue::DateTime(date.and_time(time)))
+    let mut data = buf.split_to(len);
+    let value = crate::datetime::decode_datetime2(&mut data, type_info.scale.unwrap_or(7))?;
+    Ok(SqlValue::ScaledDateTime2(value))
 }
 
 #[cfg(not(feature = "chrono"))]
@@ -783,11 +769,6 @@ fn decode_datetime2(buf: &mut Bytes, _type_info: &TypeInfo) -> Result<SqlValue,
 
 #[cfg(feature = "chrono")]
 fn decode_datetimeoffset(buf: &mut Bytes, type_info: &TypeInfo) -> Result<SqlValue, TypeError> {
-    use chrono::TimeZone;
-
-    let scale = type_info.scale.unwrap_or(7);
-    let time_len = time_bytes_for_scale(scale);
-
     if buf.remaining() < 1 {
         return Err(TypeError::BufferTooSmall {
             needed: 1,
@@ -807,30 +788,9 @@ fn decode_datetimeoffset(buf: &mut Bytes, type_info: &TypeInfo) -> Result<SqlVal
         });
     }
 
-    // Decode time
-    let mut time_bytes = [0u8; 8];
-    for byte in time_bytes.iter_mut().take(time_len) {
-        *byte = buf.get_u8();
-    }
-    let intervals = u64::from_le_bytes(time_bytes);
-    let time = intervals_to_time(intervals, scale);
-
-    // Decode date
-    let days = buf.get_u8() as u32 | ((buf.get_u8() as u32) << 8) | ((buf.get_u8() as u32) << 16);
-    let base = chrono::NaiveDate::from_ymd_opt(1, 1, 1).expect("valid date");
-    let date = base + chrono::Duration::days(days as i64);
-
-    // Decode timezone offset in minutes
-    let offset_minutes = buf.get_i16_le();
-    let offset = chrono::FixedOffset::east_opt((offset_minutes as i32) * 60)
-        .ok_or_else(|| TypeError::InvalidDateTime(format!("invalid offset: {offset_minutes}")))?;
-
-    let datetime = offset
-        .from_local_datetime(&date.and_time(time))
-        .single()
-        .ok_or_else(|| TypeError::InvalidDateTime("ambiguous datetime".to_string()))?;
-
-    Ok(SqlValue::DateTimeOffset(datetime))
+    let mut data = buf.split_to(len);
+    let value = crate::datetime::decode_datetimeoffset(&mut data, type_info.scale.unwrap_or(7))?;
+    Ok(SqlValue::ScaledDateTimeOffset(value))
 }
 
 #[cfg(not(feature = "chrono"))]
@@ -28,6 +28,8 @@ impl TdsEncode for SqlValue {
                 // For INTNTYPE, length 0 means NULL
                 Ok(())
             }
+            SqlValue::TypedNull(_) => Ok(()),
+            SqlValue::Typed(ty, value) => ty.encode_value(value, buf),
             SqlValue::Bool(v) => {
                 buf.put_u8(if *v { 1 } else { 0 });
                 Ok(())
@@ -103,6 +105,16 @@ impl TdsEncode for SqlValue {
                 encode_datetimeoffset(*dto, buf);
                 Ok(())
             }
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTime2(v) => {
+                crate::datetime::encode_datetime2(v.value, v.scale, buf);
+                Ok(())
+            }
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTimeOffset(v) => {
+                crate::datetime::encode_datetimeoffset(v.value, v.scale, buf);
+                Ok(())
+            }
             #[cfg(feature = "json")]
             SqlValue::Json(j) => {
                 // JSON is sent as NVARCHAR string
@@ -130,7 +142,8 @@ impl TdsEncode for SqlValue {
 
     fn type_id(&self) -> u8 {
         match self {
-            SqlValue::Null => 0x1F,        // NULLTYPE
+            SqlValue::Null => 0x1F, // NULLTYPE
+            SqlValue::TypedNull(ty) | SqlValue::Typed(ty, _) => ty.type_id(),
             SqlValue::Bool(_) => 0x32,     // BITTYPE
             SqlValue::TinyInt(_) => 0x30,  // INT1TYPE
             SqlValue::SmallInt(_) => 0x34, // INT2TYPE
@@ -152,6 +165,10 @@ impl TdsEncode for SqlValue {
             SqlValue::DateTime(_) => 0x2A, // DATETIME2TYPE
             #[cfg(feature = "chrono")]
             SqlValue::DateTimeOffset(_) => 0x2B, // DATETIMEOFFSETTYPE
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTime2(_) => 0x2A, // DATETIME2TYPE
+            #[cfg(feature = "chrono")]
+            SqlValue::ScaledDateTimeOffset(_) => 0x2B, // DATETIMEOFFSETTYPE
             #[cfg(feature = "json")]
             SqlValue::Json(_) => 0xE7, // NVARCHARTYPE (JSON as string)
             SqlValue::Xml(_) => 0xF1,      // XMLTYPE
@@ -271,14 +288,16 @@ pub fn encode_datetime2(datetime: chrono::NaiveDateTime, buf: &mut BytesMut) {
 
 /// Encode a DATETIMEOFFSET value.
 ///
-/// DATETIMEOFFSET is encoded as TIME + DATE + offset (in minutes).
+/// DATETIMEOFFSET is encoded as TIME + DATE + offset (in minutes), with the
+/// time and date in UTC.
 #[cfg(feature = "chrono")]
 pub fn encode_datetimeoffset(datetime: chrono::DateTime<chrono::FixedOffset>, buf: &mut BytesMut) {
     use chrono::Offset;
 
-    // Encode time and date components
-    encode_time(datetime.time(), buf);
-    encode_date(datetime.date_naive(), buf);
+    // Encode UTC time and date components
+    let utc = datetime.naive_utc();
+    encode_time(utc.time(), buf);
+    encode_date(utc.date(), buf);
 
     // Encode timezone offset in minutes (signed 16-bit)
     let offset_seconds = datetime.offset().fix().local_minus_utc();
@@ -58,6 +58,26 @@ pub enum TypeError {
         to: &'static str,
     },
 
+    /// Value does not name any variant of an enum.
+    #[error("invalid value {value} for {type_name}, expected one of: {}", .expected.join(", "))]
+    UnknownVariant {
+        /// Enum type name.
+        type_name: &'static str,
+        /// The value that did not match.
+        value: String,
+        /// The values that map to a variant.
+        expected: &'static [&'static str],
+    },
+
+    /// A user-defined conversion (such as `TryFrom`) rejected the value.
+    #[error("cannot convert to {target_type}: {message}")]
+    Conversion {
+        /// Target type name.
+        target_type: &'static str,
+        /// Why the conversion failed.
+        message: String,
+    },
+
     /// Buffer too small for value.
     #[error("buffer too small: need {needed} bytes, have {available}")]
     BufferTooSmall {
@@ -6,6 +6,22 @@
 use crate::error::TypeError;
 use crate::value::SqlValue;
 
+/// How `DATETIME`, `DATETIME2` and `SMALLDATETIME` values, which carry no
+/// time zone, convert into zone-aware types such as `DateTime<Utc>`.
+///
+/// Naive values keep their wall-clock time when read as `NaiveDateTime`
+/// under every policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeZonePolicy {
+    /// Treat naive values as UTC.
+    #[default]
+    AssumeUtc,
+    /// Treat naive values as local time of the client machine.
+    AssumeLocal,
+    /// Refuse to convert naive values into zone-aware types.
+    RejectNaive,
+}
+
 /// Trait for types that can be converted from SQL values.
 ///
 /// This trait is implemented for common Rust types to enable
@@ -24,6 +40,18 @@ pub trait FromSql: Sized {
             Self::from_sql(value).map(Some)
         }
     }
+
+    /// Convert from a SQL value, interpreting naive date/time values with
+    /// `policy`.
+    ///
+    /// Only zone-aware types use the policy; by default it is ignored.
+    fn from_sql_with_time_zone(
+        value: &SqlValue,
+        policy: TimeZonePolicy,
+    ) -> Result<Self, TypeError> {
+        let _ = policy;
+        Self::from_sql(value)
+    }
 }
 
 impl FromSql for bool {
@@ -168,6 +196,17 @@ impl<T: FromSql> FromSql for Option<T> {
     fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
         T::from_sql_nullable(value)
     }
+
+    fn from_sql_with_time_zone(
+        value: &SqlValue,
+        policy: TimeZonePolicy,
+    ) -> Result<Self, TypeError> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_sql_with_time_zone(value, policy).map(Some)
+        }
+    }
 }
 
 #[cfg(feature = "uuid")]
@@ -218,6 +257,7 @@ impl FromSql for chrono::NaiveDate {
         match value {
             SqlValue::Date(v) => Ok(*v),
             SqlValue::DateTime(v) => Ok(v.date()),
+            SqlValue::ScaledDateTime2(v) => Ok(v.value.date()),
             SqlValue::Null => Err(TypeError::UnexpectedNull),
             _ => Err(TypeError::TypeMismatch {
                 expected: "NaiveDate",
@@ -233,6 +273,7 @@ impl FromSql for chrono::NaiveTime {
         match value {
             SqlValue::Time(v) => Ok(*v),
             SqlValue::DateTime(v) => Ok(v.time()),
+            SqlValue::ScaledDateTime2(v) => Ok(v.value.time()),
             SqlValue::Null => Err(TypeError::UnexpectedNull),
             _ => Err(TypeError::TypeMismatch {
                 expected: "NaiveTime",
@@ -247,7 +288,9 @@ impl FromSql for chrono::NaiveDateTime {
     fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
         match value {
             SqlValue::DateTime(v) => Ok(*v),
+            SqlValue::ScaledDateTime2(v) => Ok(v.value),
             SqlValue::DateTimeOffset(v) => Ok(v.naive_utc()),
+            SqlValue::ScaledDateTimeOffset(v) => Ok(v.value.naive_utc()),
             SqlValue::Null => Err(TypeError::UnexpectedNull),
             _ => Err(TypeError::TypeMismatch {
                 expected: "NaiveDateTime",
@@ -262,6 +305,7 @@ impl FromSql for chrono::DateTime<chrono::FixedOffset> {
     fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
         match value {
             SqlValue::DateTimeOffset(v) => Ok(*v),
+            SqlValue::ScaledDateTimeOffset(v) => Ok(v.value),
             SqlValue::Null => Err(TypeError::UnexpectedNull),
             _ => Err(TypeError::TypeMismatch {
                 expected: "DateTime<FixedOffset>",
@@ -274,11 +318,18 @@ impl FromSql for chrono::DateTime<chrono::FixedOffset> {
 #[cfg(feature = "chrono")]
 impl FromSql for chrono::DateTime<chrono::Utc> {
     fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
+        Self::from_sql_with_time_zone(value, TimeZonePolicy::AssumeUtc)
+    }
+
+    fn from_sql_with_time_zone(
+        value: &SqlValue,
+        policy: TimeZonePolicy,
+    ) -> Result<Self, TypeError> {
         match value {
             SqlValue::DateTimeOffset(v) => Ok(v.to_utc()),
-            SqlValue::DateTime(v) => {
-                Ok(chrono::DateTime::from_naive_utc_and_offset(*v, chrono::Utc))
-            }
+            SqlValue::ScaledDateTimeOffset(v) => Ok(v.value.to_utc()),
+            SqlValue::DateTime(v) => naive_to_utc(*v, policy),
+            SqlValue::ScaledDateTime2(v) => naive_to_utc(v.value, policy),
             SqlValue::Null => Err(TypeError::UnexpectedNull),
             _ => Err(TypeError::TypeMismatch {
                 expected: "DateTime<Utc>",
@@ -288,6 +339,70 @@ impl FromSql for chrono::DateTime<chrono::Utc> {
     }
 }
 
+#[cfg(feature = "chrono")]
+fn naive_to_utc(
+    value: chrono::NaiveDateTime,
+    policy: TimeZonePolicy,
+) -> Result<chrono::DateTime<chrono::Utc>, TypeError> {
+    use chrono::TimeZone;
+
+    match policy {
+        TimeZonePolicy::AssumeUtc => Ok(value.and_utc()),
+        TimeZonePolicy::AssumeLocal => chrono::Local
+            .from_local_datetime(&value)
+            .earliest()
+            .map(|local| local.to_utc())
+            .ok_or_else(|| TypeError::Conversion {
+                target_type: "DateTime<Utc>",
+                message: format!("{value} does not exist in the local time zone"),
+            }),
+        TimeZonePolicy::RejectNaive => Err(TypeError::Conversion {
+            target_type: "DateTime<Utc>",
+            message: format!(
+                "{value} has no time zone; read it as NaiveDateTime or choose a TimeZonePolicy"
+            ),
+        }),
+    }
+}
+
+/// `DATETIME` and `SMALLDATETIME` values, which have no scale, are returned
+/// with scale 7.
+#[cfg(feature = "chrono")]
+impl FromSql for crate::datetime::SqlDateTime2 {
+    fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
+        match value {
+            SqlValue::ScaledDateTime2(v) => Ok(*v),
+            SqlValue::DateTime(v) => Ok(Self {
+                value: *v,
+                scale: crate::datetime::MAX_SCALE,
+            }),
+            SqlValue::Null => Err(TypeError::UnexpectedNull),
+            _ => Err(TypeError::TypeMismatch {
+                expected: "SqlDateTime2",
+                actual: value.type_name().to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromSql for crate::datetime::SqlDateTimeOffset {
+    fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
+        match value {
+            SqlValue::ScaledDateTimeOffset(v) => Ok(*v),
+            SqlValue::DateTimeOffset(v) => Ok(Self {
+                value: *v,
+                scale: crate::datetime::MAX_SCALE,
+            }),
+            SqlValue::Null => Err(TypeError::UnexpectedNull),
+            _ => Err(TypeError::TypeMismatch {
+                expected: "SqlDateTimeOffset",
+                actual: value.type_name().to_string(),
+            }),
+        }
+    }
+}
+
 #[cfg(feature = "json")]
 impl FromSql for serde_json::Value {
     fn from_sql(value: &SqlValue) -> Result<Self, TypeError> {
@@ -337,4 +452,45 @@ mod tests {
         let null = SqlValue::Null;
         assert_eq!(Option::<i32>::from_sql(&null).unwrap(), None);
     }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_datetime_utc_time_zone_policy() {
+        use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+        let naive = NaiveDate::from_ymd_opt(2024, 1, 15)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        let value = SqlValue::DateTime(naive);
+
+        let utc = DateTime::<Utc>::from_sql(&value).unwrap();
+        assert_eq!(utc.naive_utc(), naive);
+
+        let local =
+            DateTime::<Utc>::from_sql_with_time_zone(&value, TimeZonePolicy::AssumeLocal).unwrap();
+        assert_eq!(
+            local,
+            chrono::Local.from_local_datetime(&naive).unwrap().to_utc()
+        );
+
+        assert!(matches!(
+            DateTime::<Utc>::from_sql_with_time_zone(&value, TimeZonePolicy::RejectNaive),
+            Err(TypeError::Conversion { .. })
+        ));
+        assert_eq!(
+            Option::<DateTime<Utc>>::from_sql_with_time_zone(
+                &SqlValue::Null,
+                TimeZonePolicy::RejectNaive
+            )
+            .unwrap(),
+            None
+        );
+        // Naive values keep their wall-clock time
+        assert_eq!(
+            chrono::NaiveDateTime::from_sql_with_time_zone(&value, TimeZonePolicy::AssumeLocal)
+                .unwrap(),
+            naive
+        );
+    }
 }
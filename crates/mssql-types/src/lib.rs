@@ -11,6 +11,7 @@
 //! - `uuid` (default): Enable UUID type support
 //! - `decimal` (default): Enable decimal type support via rust_decimal
 //! - `json`: Enable JSON type support via serde_json
+//! - `serde`: Implement `serde::Serialize` for [`SqlValue`]
 //!
 //! ## Type Mappings
 //!
@@ -28,24 +29,33 @@
 //! | `NCHAR`/`NVARCHAR` | `String` |
 //! | `DATE` | `chrono::NaiveDate` |
 //! | `TIME` | `chrono::NaiveTime` |
-//! | `DATETIME2` | `chrono::NaiveDateTime` |
+//! | `DATETIME2` | `chrono::NaiveDateTime`, [`SqlDateTime2`] |
+//! | `DATETIMEOFFSET` | `chrono::DateTime<FixedOffset>`, [`SqlDateTimeOffset`] |
 //! | `UNIQUEIDENTIFIER` | `uuid::Uuid` |
 
 #![warn(missing_docs)]
 #![deny(unsafe_code)]
 
+#[cfg(feature = "chrono")]
+pub mod datetime;
 pub mod decode;
 pub mod encode;
 pub mod error;
 pub mod from_sql;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod sql_type;
 pub mod to_sql;
 pub mod tvp;
 pub mod value;
 
+#[cfg(feature = "chrono")]
+pub use datetime::{SqlDateTime2, SqlDateTimeOffset};
 pub use decode::{Collation, TdsDecode, TypeInfo, decode_utf16_string, decode_value};
 pub use encode::{TdsEncode, encode_utf16_string};
 pub use error::TypeError;
-pub use from_sql::FromSql;
-pub use to_sql::ToSql;
-pub use tvp::{TvpColumnDef, TvpColumnType, TvpData, TvpError};
+pub use from_sql::{FromSql, TimeZonePolicy};
+pub use sql_type::SqlType;
+pub use to_sql::{OutParam, Param, ToSql, Varchar};
+pub use tvp::{TvpColumnDef, TvpColumnType, TvpData, TvpError, TvpOrderHint, TvpSortOrder};
 pub use value::SqlValue;
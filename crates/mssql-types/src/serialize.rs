@@ -0,0 +1,166 @@
+//! `serde` serialization of SQL values.
+//!
+//! [`SqlValue`] serializes to the closest self-describing representation,
+//! so a row can be turned into JSON without mapping it to a struct first:
+//!
+//! | Value | Serialized as |
+//! |-------|---------------|
+//! | `NULL` | unit (`null`) |
+//! | `BIT` | bool |
+//! | integers and floats | numbers |
+//! | strings and `XML` | strings |
+//! | `DECIMAL` / `NUMERIC` / `MONEY` | string, keeping every digit (`"12.50"`) |
+//! | `UNIQUEIDENTIFIER` | hyphenated string |
+//! | `BINARY` / `VARBINARY` | standard base64 string |
+//! | `DATE` | `"2024-03-01"` |
+//! | `TIME` | `"12:30:00.250"` |
+//! | `DATETIME` / `DATETIME2` | `"2024-03-01T12:30:00.250"` |
+//! | `DATETIMEOFFSET` | RFC 3339 (`"2024-03-01T12:30:00.250+02:00"`) |
+//! | JSON | the JSON value itself |
+//! | TVP | sequence of rows, each a sequence of values |
+//!
+//! Dates and times use ISO 8601 with no, 3, 6 or 9 fractional second
+//! digits, whichever is the shortest that keeps the value exact.
+
+use base64::Engine;
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::value::SqlValue;
+
+impl Serialize for SqlValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null | Self::TypedNull(_) => serializer.serialize_unit(),
+            Self::Typed(_, value) => value.serialize(serializer),
+            Self::Bool(v) => serializer.serialize_bool(*v),
+            Self::TinyInt(v) => serializer.serialize_u8(*v),
+            Self::SmallInt(v) => serializer.serialize_i16(*v),
+            Self::Int(v) => serializer.serialize_i32(*v),
+            Self::BigInt(v) => serializer.serialize_i64(*v),
+            Self::Float(v) => serializer.serialize_f32(*v),
+            Self::Double(v) => serializer.serialize_f64(*v),
+            Self::String(v) | Self::Xml(v) => serializer.serialize_str(v),
+            Self::Binary(v) => {
+                serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(v))
+            }
+            #[cfg(feature = "decimal")]
+            Self::Decimal(v) => serializer.collect_str(v),
+            #[cfg(feature = "uuid")]
+            Self::Uuid(v) => serializer.collect_str(&v.hyphenated()),
+            #[cfg(feature = "chrono")]
+            Self::Date(v) => serializer.collect_str(&v.format("%Y-%m-%d")),
+            #[cfg(feature = "chrono")]
+            Self::Time(v) => serializer.collect_str(&v.format("%H:%M:%S%.f")),
+            #[cfg(feature = "chrono")]
+            Self::DateTime(v) => serializer.collect_str(&v.format("%Y-%m-%dT%H:%M:%S%.f")),
+            #[cfg(feature = "chrono")]
+            Self::ScaledDateTime2(v) => {
+                let v = chrono::NaiveDateTime::from(*v);
+                serializer.collect_str(&v.format("%Y-%m-%dT%H:%M:%S%.f"))
+            }
+            #[cfg(feature = "chrono")]
+            Self::DateTimeOffset(v) => serializer.collect_str(&v.format("%Y-%m-%dT%H:%M:%S%.f%:z")),
+            #[cfg(feature = "chrono")]
+            Self::ScaledDateTimeOffset(v) => {
+                let v = chrono::DateTime::<chrono::FixedOffset>::from(*v);
+                serializer.collect_str(&v.format("%Y-%m-%dT%H:%M:%S%.f%:z"))
+            }
+            #[cfg(feature = "json")]
+            Self::Json(v) => v.serialize(serializer),
+            Self::Tvp(tvp) => {
+                let mut seq = serializer.serialize_seq(Some(tvp.rows.len()))?;
+                for row in &tvp.rows {
+                    seq.serialize_element(row)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn json(value: SqlValue) -> String {
+        serde_json::to_string(&value).unwrap()
+    }
+
+    #[test]
+    fn test_scalar_values() {
+        assert_eq!(json(SqlValue::Null), "null");
+        assert_eq!(json(SqlValue::TypedNull(crate::SqlType::Int)), "null");
+        assert_eq!(json(SqlValue::Bool(true)), "true");
+        assert_eq!(json(SqlValue::TinyInt(255)), "255");
+        assert_eq!(json(SqlValue::BigInt(-5)), "-5");
+        assert_eq!(json(SqlValue::Double(1.5)), "1.5");
+        assert_eq!(json(SqlValue::String("a\"b".into())), r#""a\"b""#);
+        assert_eq!(
+            json(SqlValue::Typed(
+                crate::SqlType::BigInt,
+                Box::new(SqlValue::Int(7))
+            )),
+            "7"
+        );
+    }
+
+    #[test]
+    fn test_binary_is_base64() {
+        assert_eq!(
+            json(SqlValue::Binary(Bytes::from_static(&[
+                0xDE, 0xAD, 0xBE, 0xEF
+            ]))),
+            r#""3q2+7w==""#
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_keeps_digits() {
+        let value = SqlValue::Decimal(rust_decimal::Decimal::new(1250, 2));
+        assert_eq!(json(value), r#""12.50""#);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_is_hyphenated() {
+        let value = SqlValue::Uuid(uuid::Uuid::from_u128(
+            0x0123_4567_89ab_cdef_0123_4567_89ab_cdef,
+        ));
+        assert_eq!(json(value), r#""01234567-89ab-cdef-0123-456789abcdef""#);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_dates_are_iso_8601() {
+        use chrono::{FixedOffset, NaiveDate, NaiveTime};
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let time = NaiveTime::from_hms_milli_opt(12, 30, 0, 250).unwrap();
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+
+        assert_eq!(json(SqlValue::Date(date)), r#""2024-03-01""#);
+        assert_eq!(json(SqlValue::Time(time)), r#""12:30:00.250""#);
+        assert_eq!(
+            json(SqlValue::DateTime(date.and_hms_opt(8, 0, 0).unwrap())),
+            r#""2024-03-01T08:00:00""#
+        );
+        assert_eq!(
+            json(SqlValue::DateTimeOffset(
+                date.and_time(time).and_local_timezone(offset).unwrap()
+            )),
+            r#""2024-03-01T12:30:00.250+02:00""#
+        );
+    }
+
+    #[test]
+    fn test_tvp_rows() {
+        let tvp = crate::TvpData::new("dbo", "Ids")
+            .with_column(crate::TvpColumnDef::new(crate::TvpColumnType::Int))
+            .with_row(vec![SqlValue::Int(1)])
+            .with_row(vec![SqlValue::Null]);
+        assert_eq!(json(SqlValue::Tvp(Box::new(tvp))), "[[1],[null]]");
+    }
+}
@@ -0,0 +1,553 @@
+//! Explicit SQL parameter types.
+//!
+//! Parameters normally take their type from the Rust value: a `&str` is
+//! sent as `NVARCHAR`, a `NaiveDateTime` as `DATETIME2(7)`, and a NULL as
+//! `NVARCHAR(1)`. When that type differs from the column it is compared
+//! with, SQL Server adds an implicit conversion, which can turn an index
+//! seek into a scan. [`SqlType`] names the exact type to declare instead,
+//! either through [`Param::with_type`](crate::Param::with_type) or as a
+//! typed NULL with [`SqlValue::TypedNull`].
+//!
+//! ```rust,ignore
+//! use mssql_client::{Param, SqlType};
+//!
+//! let code = Param::new("GB").with_type(SqlType::NChar(2));
+//! let shipped = Param::new(None::<NaiveDateTime>).with_type(SqlType::DateTime);
+//! client
+//!     .query("SELECT * FROM Orders WHERE Country = @p1 AND ShippedAt = @p2", &[&code, &shipped])
+//!     .await?;
+//! ```
+
+// Allow expect() for chrono date construction with known-valid constant dates
+#![allow(clippy::expect_used)]
+
+use bytes::{BufMut, BytesMut};
+
+use crate::error::TypeError;
+use crate::value::SqlValue;
+
+/// Longest `NVARCHAR(n)` and `NCHAR(n)` length, in characters.
+pub const MAX_NVARCHAR_LEN: u16 = 4000;
+/// Longest `VARCHAR(n)` length, in bytes.
+pub const MAX_VARCHAR_LEN: u16 = 8000;
+/// Longest `VARBINARY(n)` length, in bytes.
+pub const MAX_VARBINARY_LEN: u16 = 8000;
+/// Largest `DECIMAL` precision.
+pub const MAX_DECIMAL_PRECISION: u8 = 38;
+
+/// A SQL Server parameter type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SqlType {
+    /// `BIT`.
+    Bit,
+    /// `TINYINT`.
+    TinyInt,
+    /// `SMALLINT`.
+    SmallInt,
+    /// `INT`.
+    Int,
+    /// `BIGINT`.
+    BigInt,
+    /// `REAL`.
+    Real,
+    /// `FLOAT`.
+    Float,
+    /// `DECIMAL(precision, scale)`.
+    Decimal {
+        /// Total number of digits, 1 to 38.
+        precision: u8,
+        /// Digits after the decimal point, at most `precision`.
+        scale: u8,
+    },
+    /// `NVARCHAR(n)`, n in characters (1 to 4000).
+    NVarChar(u16),
+    /// `NVARCHAR(MAX)`.
+    NVarCharMax,
+    /// `VARCHAR(n)`, n in bytes (1 to 8000), in the database collation.
+    VarChar(u16),
+    /// `VARCHAR(MAX)`, in the database collation.
+    VarCharMax,
+    /// `NCHAR(n)`, n in characters (1 to 4000).
+    NChar(u16),
+    /// `VARBINARY(n)`, n in bytes (1 to 8000).
+    VarBinary(u16),
+    /// `VARBINARY(MAX)`.
+    VarBinaryMax,
+    /// `UNIQUEIDENTIFIER`.
+    UniqueIdentifier,
+    /// `DATE`.
+    Date,
+    /// `TIME(scale)`.
+    Time(u8),
+    /// `DATETIME`.
+    DateTime,
+    /// `SMALLDATETIME`.
+    SmallDateTime,
+    /// `DATETIME2(scale)`.
+    DateTime2(u8),
+    /// `DATETIMEOFFSET(scale)`.
+    DateTimeOffset(u8),
+}
+
+impl SqlType {
+    /// The type name without length, precision or scale, e.g. `NVARCHAR`.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bit => "BIT",
+            Self::TinyInt => "TINYINT",
+            Self::SmallInt => "SMALLINT",
+            Self::Int => "INT",
+            Self::BigInt => "BIGINT",
+            Self::Real => "REAL",
+            Self::Float => "FLOAT",
+            Self::Decimal { .. } => "DECIMAL",
+            Self::NVarChar(_) | Self::NVarCharMax => "NVARCHAR",
+            Self::VarChar(_) | Self::VarCharMax => "VARCHAR",
+            Self::NChar(_) => "NCHAR",
+            Self::VarBinary(_) | Self::VarBinaryMax => "VARBINARY",
+            Self::UniqueIdentifier => "UNIQUEIDENTIFIER",
+            Self::Date => "DATE",
+            Self::Time(_) => "TIME",
+            Self::DateTime => "DATETIME",
+            Self::SmallDateTime => "SMALLDATETIME",
+            Self::DateTime2(_) => "DATETIME2",
+            Self::DateTimeOffset(_) => "DATETIMEOFFSET",
+        }
+    }
+
+    /// The full type declaration, e.g. `NVARCHAR(100)` or `DECIMAL(18, 2)`.
+    #[must_use]
+    pub fn declaration(&self) -> String {
+        match self {
+            Self::Decimal { precision, scale } => format!("DECIMAL({precision}, {scale})"),
+            Self::NVarChar(n) | Self::VarChar(n) | Self::NChar(n) | Self::VarBinary(n) => {
+                format!("{}({n})", self.name())
+            }
+            Self::NVarCharMax | Self::VarCharMax | Self::VarBinaryMax => {
+                format!("{}(MAX)", self.name())
+            }
+            Self::Time(scale) | Self::DateTime2(scale) | Self::DateTimeOffset(scale) => {
+                format!("{}({scale})", self.name())
+            }
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// The TDS type ID used for parameters of this type.
+    #[must_use]
+    pub fn type_id(&self) -> u8 {
+        match self {
+            Self::Bit => 0x68,                                                 // BITNTYPE
+            Self::TinyInt | Self::SmallInt | Self::Int | Self::BigInt => 0x26, // INTNTYPE
+            Self::Real | Self::Float => 0x6D,                                  // FLTNTYPE
+            Self::Decimal { .. } => 0x6C,                                      // DECIMALNTYPE
+            Self::NVarChar(_) | Self::NVarCharMax => 0xE7,                     // NVARCHARTYPE
+            Self::VarChar(_) | Self::VarCharMax => 0xA7,                       // BIGVARCHARTYPE
+            Self::NChar(_) => 0xEF,                                            // NCHARTYPE
+            Self::VarBinary(_) | Self::VarBinaryMax => 0xA5,                   // BIGVARBINTYPE
+            Self::UniqueIdentifier => 0x24,                                    // GUIDTYPE
+            Self::Date => 0x28,                                                // DATETYPE
+            Self::Time(_) => 0x29,                                             // TIMETYPE
+            Self::DateTime | Self::SmallDateTime => 0x6F,                      // DATETIMNTYPE
+            Self::DateTime2(_) => 0x2A,                                        // DATETIME2TYPE
+            Self::DateTimeOffset(_) => 0x2B,                                   // DATETIMEOFFSETTYPE
+        }
+    }
+
+    /// Check that lengths, precision and scale are within SQL Server's limits.
+    pub fn validate(&self) -> Result<(), TypeError> {
+        let valid = match *self {
+            Self::Decimal { precision, scale } => {
+                (1..=MAX_DECIMAL_PRECISION).contains(&precision) && scale <= precision
+            }
+            Self::NVarChar(n) | Self::NChar(n) => (1..=MAX_NVARCHAR_LEN).contains(&n),
+            Self::VarChar(n) => (1..=MAX_VARCHAR_LEN).contains(&n),
+            Self::VarBinary(n) => (1..=MAX_VARBINARY_LEN).contains(&n),
+            Self::Time(scale) | Self::DateTime2(scale) | Self::DateTimeOffset(scale) => scale <= 7,
+            _ => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(TypeError::UnsupportedConversion {
+                from: self.declaration(),
+                to: "a valid SQL Server type",
+            })
+        }
+    }
+
+    /// Encode `value` as a parameter of this type, without a length prefix.
+    ///
+    /// Numeric values are converted between widths when they fit, and
+    /// date/time values between the date/time types. Strings and binary
+    /// values longer than the declared length are rejected rather than
+    /// silently truncated.
+    pub fn encode_value(&self, value: &SqlValue, buf: &mut BytesMut) -> Result<(), TypeError> {
+        self.validate()?;
+        let mismatch = || TypeError::UnsupportedConversion {
+            from: value.type_name().to_string(),
+            to: self.name(),
+        };
+        let int = || value.as_i64().ok_or_else(mismatch);
+        let out_of_range = |_| TypeError::OutOfRange {
+            target_type: self.name(),
+        };
+
+        match *self {
+            Self::Bit => match value {
+                SqlValue::Bool(v) => buf.put_u8(u8::from(*v)),
+                _ => buf.put_u8(u8::from(int()? != 0)),
+            },
+            Self::TinyInt => buf.put_u8(u8::try_from(int()?).map_err(out_of_range)?),
+            Self::SmallInt => buf.put_i16_le(i16::try_from(int()?).map_err(out_of_range)?),
+            Self::Int => buf.put_i32_le(i32::try_from(int()?).map_err(out_of_range)?),
+            Self::BigInt => buf.put_i64_le(int()?),
+            Self::Real | Self::Float => {
+                let v = match value.as_f64() {
+                    Some(v) => v,
+                    None => int()? as f64,
+                };
+                if *self == Self::Real {
+                    buf.put_f32_le(v as f32);
+                } else {
+                    buf.put_f64_le(v);
+                }
+            }
+            #[cfg(feature = "decimal")]
+            Self::Decimal { precision, scale } => {
+                let mut d = match value {
+                    SqlValue::Decimal(d) => *d,
+                    _ => rust_decimal::Decimal::from(int()?),
+                };
+                d.rescale(u32::from(scale));
+                let mantissa = d.mantissa().unsigned_abs();
+                if mantissa >= 10u128.pow(u32::from(precision)) {
+                    return Err(TypeError::OutOfRange {
+                        target_type: "DECIMAL",
+                    });
+                }
+                buf.put_u8(if d.is_sign_negative() { 0 } else { 1 });
+                buf.put_slice(&mantissa.to_le_bytes()[..decimal_len(precision)]);
+            }
+            #[cfg(not(feature = "decimal"))]
+            Self::Decimal { .. } => return Err(mismatch()),
+            Self::NVarChar(_) | Self::NVarCharMax | Self::NChar(_) => {
+                let s = value.as_str().ok_or_else(mismatch)?;
+                let units = s.encode_utf16().count();
+                if let Self::NVarChar(n) | Self::NChar(n) = *self {
+                    if units > usize::from(n) {
+                        return Err(TypeError::Truncation(format!(
+                            "{units} UTF-16 code units do not fit {}",
+                            self.declaration()
+                        )));
+                    }
+                }
+                crate::encode::encode_utf16_string_no_len(s, buf);
+                if let Self::NChar(n) = *self {
+                    // NCHAR is fixed-length: pad with spaces
+                    for _ in units..usize::from(n) {
+                        buf.put_u16_le(u16::from(b' '));
+                    }
+                }
+            }
+            Self::VarChar(_) | Self::VarCharMax => {
+                // The code page comes from the connection's collation, which
+                // only the client knows; ASCII is the same in all of them.
+                let s = value.as_str().ok_or_else(mismatch)?;
+                if !s.is_ascii() {
+                    return Err(TypeError::InvalidEncoding(
+                        "non-ASCII VARCHAR values must be encoded with the connection's collation"
+                            .to_string(),
+                    ));
+                }
+                if let Self::VarChar(n) = *self {
+                    if s.len() > usize::from(n) {
+                        return Err(TypeError::Truncation(format!(
+                            "{} bytes do not fit {}",
+                            s.len(),
+                            self.declaration()
+                        )));
+                    }
+                }
+                buf.put_slice(s.as_bytes());
+            }
+            Self::VarBinary(_) | Self::VarBinaryMax => {
+                let b = value.as_bytes().ok_or_else(mismatch)?;
+                if let Self::VarBinary(n) = *self {
+                    if b.len() > usize::from(n) {
+                        return Err(TypeError::Truncation(format!(
+                            "{} bytes do not fit {}",
+                            b.len(),
+                            self.declaration()
+                        )));
+                    }
+                }
+                buf.put_slice(b);
+            }
+            #[cfg(feature = "uuid")]
+            Self::UniqueIdentifier => match value {
+                SqlValue::Uuid(u) => crate::encode::encode_uuid(*u, buf),
+                _ => return Err(mismatch()),
+            },
+            #[cfg(not(feature = "uuid"))]
+            Self::UniqueIdentifier => return Err(mismatch()),
+            #[cfg(feature = "chrono")]
+            Self::Date => {
+                let dt = naive_datetime(value).ok_or_else(mismatch)?;
+                crate::encode::encode_date(dt.date(), buf);
+            }
+            #[cfg(feature = "chrono")]
+            Self::Time(scale) => {
+                let time = match value {
+                    SqlValue::Time(t) => *t,
+                    _ => naive_datetime(value).ok_or_else(mismatch)?.time(),
+                };
+                crate::datetime::encode_time(time, scale, buf);
+            }
+            #[cfg(feature = "chrono")]
+            Self::DateTime => {
+                let dt = naive_datetime(value).ok_or_else(mismatch)?;
+                encode_datetime(dt, buf)?;
+            }
+            #[cfg(feature = "chrono")]
+            Self::SmallDateTime => {
+                let dt = naive_datetime(value).ok_or_else(mismatch)?;
+                encode_smalldatetime(dt, buf)?;
+            }
+            #[cfg(feature = "chrono")]
+            Self::DateTime2(scale) => {
+                let dt = naive_datetime(value).ok_or_else(mismatch)?;
+                crate::datetime::encode_datetime2(dt, scale, buf);
+            }
+            #[cfg(feature = "chrono")]
+            Self::DateTimeOffset(scale) => {
+                let dto = match value {
+                    SqlValue::DateTimeOffset(v) => *v,
+                    SqlValue::ScaledDateTimeOffset(v) => v.value,
+                    // Local date/times are taken to be UTC, as SQL Server converts them
+                    _ => naive_datetime(value)
+                        .ok_or_else(mismatch)?
+                        .and_utc()
+                        .fixed_offset(),
+                };
+                crate::datetime::encode_datetimeoffset(dto, scale, buf);
+            }
+            #[cfg(not(feature = "chrono"))]
+            Self::Date
+            | Self::Time(_)
+            | Self::DateTime
+            | Self::SmallDateTime
+            | Self::DateTime2(_)
+            | Self::DateTimeOffset(_) => return Err(mismatch()),
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SqlType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.declaration())
+    }
+}
+
+/// Bytes of a `DECIMAL` magnitude at `precision`.
+#[cfg(feature = "decimal")]
+fn decimal_len(precision: u8) -> usize {
+    match precision {
+        0..=9 => 4,
+        10..=19 => 8,
+        20..=28 => 12,
+        _ => 16,
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn naive_datetime(value: &SqlValue) -> Option<chrono::NaiveDateTime> {
+    match value {
+        SqlValue::DateTime(v) => Some(*v),
+        SqlValue::ScaledDateTime2(v) => Some(v.value),
+        SqlValue::Date(d) => Some(d.and_time(chrono::NaiveTime::MIN)),
+        _ => None,
+    }
+}
+
+/// Encode a `DATETIME`: days since 1900-01-01, then 1/300 seconds since
+/// midnight, rounded to the nearest tick.
+#[cfg(feature = "chrono")]
+fn encode_datetime(dt: chrono::NaiveDateTime, buf: &mut BytesMut) -> Result<(), TypeError> {
+    use chrono::Timelike;
+
+    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).expect("valid date");
+    let min = chrono::NaiveDate::from_ymd_opt(1753, 1, 1).expect("valid date");
+    if dt.date() < min {
+        return Err(TypeError::OutOfRange {
+            target_type: "DATETIME",
+        });
+    }
+
+    let nanos = u64::from(dt.num_seconds_from_midnight()) * 1_000_000_000
+        + u64::from(dt.nanosecond().min(999_999_999));
+    let mut ticks = (nanos * 300 + 500_000_000) / 1_000_000_000;
+    let mut days = dt.date().signed_duration_since(base).num_days();
+    if ticks >= 300 * 86_400 {
+        ticks = 0;
+        days += 1;
+    }
+
+    buf.put_i32_le(i32::try_from(days).map_err(|_| TypeError::OutOfRange {
+        target_type: "DATETIME",
+    })?);
+    buf.put_u32_le(ticks as u32);
+    Ok(())
+}
+
+/// Encode a `SMALLDATETIME`: days since 1900-01-01, then minutes since
+/// midnight, rounded to the nearest minute.
+#[cfg(feature = "chrono")]
+fn encode_smalldatetime(dt: chrono::NaiveDateTime, buf: &mut BytesMut) -> Result<(), TypeError> {
+    use chrono::Timelike;
+
+    let base = chrono::NaiveDate::from_ymd_opt(1900, 1, 1).expect("valid date");
+    let mut minutes = (dt.num_seconds_from_midnight() + 30) / 60;
+    let mut days = dt.date().signed_duration_since(base).num_days();
+    if minutes >= 24 * 60 {
+        minutes = 0;
+        days += 1;
+    }
+
+    let out_of_range = |_| TypeError::OutOfRange {
+        target_type: "SMALLDATETIME",
+    };
+    buf.put_u16_le(u16::try_from(days).map_err(out_of_range)?);
+    buf.put_u16_le(minutes as u16);
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn encode(ty: SqlType, value: SqlValue) -> Result<Vec<u8>, TypeError> {
+        let mut buf = BytesMut::new();
+        ty.encode_value(&value, &mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    #[test]
+    fn test_declaration() {
+        assert_eq!(SqlType::NVarChar(100).declaration(), "NVARCHAR(100)");
+        assert_eq!(SqlType::VarBinaryMax.declaration(), "VARBINARY(MAX)");
+        assert_eq!(
+            SqlType::Decimal {
+                precision: 18,
+                scale: 2
+            }
+            .declaration(),
+            "DECIMAL(18, 2)"
+        );
+        assert_eq!(SqlType::DateTime2(3).to_string(), "DATETIME2(3)");
+        assert_eq!(SqlType::DateTime.declaration(), "DATETIME");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SqlType::NVarChar(4000).validate().is_ok());
+        assert!(SqlType::NVarChar(0).validate().is_err());
+        assert!(SqlType::NVarChar(4001).validate().is_err());
+        assert!(SqlType::VarBinary(8001).validate().is_err());
+        assert!(SqlType::DateTime2(8).validate().is_err());
+        assert!(
+            SqlType::Decimal {
+                precision: 5,
+                scale: 6
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_encode_integers() {
+        assert_eq!(
+            encode(SqlType::BigInt, SqlValue::Int(1)).unwrap(),
+            1i64.to_le_bytes()
+        );
+        assert_eq!(encode(SqlType::TinyInt, SqlValue::Int(255)).unwrap(), [255]);
+        assert!(matches!(
+            encode(SqlType::TinyInt, SqlValue::Int(256)),
+            Err(TypeError::OutOfRange { .. })
+        ));
+        assert_eq!(encode(SqlType::Bit, SqlValue::Int(2)).unwrap(), [1]);
+        assert!(encode(SqlType::Int, SqlValue::String("1".into())).is_err());
+    }
+
+    #[test]
+    fn test_encode_strings() {
+        assert_eq!(
+            encode(SqlType::NVarChar(2), SqlValue::from("AB")).unwrap(),
+            [0x41, 0, 0x42, 0]
+        );
+        assert!(matches!(
+            encode(SqlType::NVarChar(1), SqlValue::from("AB")),
+            Err(TypeError::Truncation(_))
+        ));
+        assert_eq!(
+            encode(SqlType::VarChar(2), SqlValue::from("AB")).unwrap(),
+            [0x41, 0x42]
+        );
+        assert!(matches!(
+            encode(SqlType::VarChar(8), SqlValue::from("Café")),
+            Err(TypeError::InvalidEncoding(_))
+        ));
+        assert_eq!(
+            encode(SqlType::NChar(2), SqlValue::from("A")).unwrap(),
+            [0x41, 0, 0x20, 0]
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_encode_decimal() {
+        let ty = SqlType::Decimal {
+            precision: 5,
+            scale: 2,
+        };
+        let d = rust_decimal::Decimal::new(-1234, 1); // -123.4
+        assert_eq!(
+            encode(ty, SqlValue::Decimal(d)).unwrap(),
+            [0, 0x34, 0x30, 0, 0] // -12340 at scale 2
+        );
+        assert!(encode(ty, SqlValue::Int(1000)).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_encode_datetime() {
+        let dt = chrono::NaiveDate::from_ymd_opt(1900, 1, 2)
+            .unwrap()
+            .and_hms_milli_opt(0, 0, 1, 500)
+            .unwrap();
+        assert_eq!(
+            encode(SqlType::DateTime, SqlValue::DateTime(dt)).unwrap(),
+            [1, 0, 0, 0, 194, 1, 0, 0] // day 1, 450 ticks
+        );
+        assert_eq!(
+            encode(SqlType::SmallDateTime, SqlValue::DateTime(dt)).unwrap(),
+            [1, 0, 0, 0]
+        );
+
+        let early = chrono::NaiveDate::from_ymd_opt(1700, 1, 1)
+            .unwrap()
+            .and_time(chrono::NaiveTime::MIN);
+        assert!(encode(SqlType::DateTime, SqlValue::DateTime(early)).is_err());
+        assert_eq!(
+            encode(SqlType::DateTime2(0), SqlValue::DateTime(dt))
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+}
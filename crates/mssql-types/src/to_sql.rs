@@ -3,7 +3,12 @@
 // Allow expect() for chrono date construction with known-valid constant dates
 #![allow(clippy::expect_used)]
 
+use std::marker::PhantomData;
+use std::sync::{Mutex, PoisonError};
+
 use crate::error::TypeError;
+use crate::from_sql::FromSql;
+use crate::sql_type::SqlType;
 use crate::value::SqlValue;
 
 /// Trait for types that can be converted to SQL values.
@@ -16,6 +21,20 @@ pub trait ToSql {
 
     /// Get the SQL type name for this value.
     fn sql_type(&self) -> &'static str;
+
+    /// Check whether the server returns a value for this parameter after
+    /// execution (an `OUTPUT` parameter).
+    fn is_output(&self) -> bool {
+        false
+    }
+
+    /// Receive the value the server returned for an output parameter.
+    ///
+    /// Called after execution for parameters where [`is_output`](Self::is_output)
+    /// is true; ignored by default.
+    fn set_output(&self, value: SqlValue) {
+        let _ = value;
+    }
 }
 
 impl ToSql for bool {
@@ -152,6 +171,14 @@ impl<T: ToSql + ?Sized> ToSql for &T {
     fn sql_type(&self) -> &'static str {
         (*self).sql_type()
     }
+
+    fn is_output(&self) -> bool {
+        (*self).is_output()
+    }
+
+    fn set_output(&self, value: SqlValue) {
+        (*self).set_output(value);
+    }
 }
 
 #[cfg(feature = "uuid")]
@@ -233,6 +260,30 @@ impl ToSql for chrono::DateTime<chrono::Utc> {
     }
 }
 
+/// Declared as `DATETIME2(scale)`.
+#[cfg(feature = "chrono")]
+impl ToSql for crate::datetime::SqlDateTime2 {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        Ok(SqlValue::ScaledDateTime2(*self))
+    }
+
+    fn sql_type(&self) -> &'static str {
+        self.type_name()
+    }
+}
+
+/// Declared as `DATETIMEOFFSET(scale)`.
+#[cfg(feature = "chrono")]
+impl ToSql for crate::datetime::SqlDateTimeOffset {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        Ok(SqlValue::ScaledDateTimeOffset(*self))
+    }
+
+    fn sql_type(&self) -> &'static str {
+        self.type_name()
+    }
+}
+
 #[cfg(feature = "json")]
 impl ToSql for serde_json::Value {
     fn to_sql(&self) -> Result<SqlValue, TypeError> {
@@ -244,6 +295,193 @@ impl ToSql for serde_json::Value {
     }
 }
 
+impl ToSql for SqlValue {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        Ok(self.clone())
+    }
+
+    fn sql_type(&self) -> &'static str {
+        self.type_name()
+    }
+}
+
+/// A parameter with an explicit SQL type.
+///
+/// Without a type, parameters are declared from the Rust value, which is
+/// ambiguous for NULLs and can differ from the column being compared with.
+/// See [`SqlType`] for the conversions applied to the value.
+///
+/// ```rust,ignore
+/// let id = Param::new(None::<i64>).with_type(SqlType::BigInt); // BIGINT NULL
+/// let name = Param::new("Alice").with_type(SqlType::NVarChar(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Param<T> {
+    value: T,
+    sql_type: Option<SqlType>,
+}
+
+impl<T: ToSql> Param<T> {
+    /// Wrap a value, keeping its inferred type until [`with_type`](Self::with_type).
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            sql_type: None,
+        }
+    }
+
+    /// Send the value as `sql_type`.
+    #[must_use]
+    pub fn with_type(mut self, sql_type: SqlType) -> Self {
+        self.sql_type = Some(sql_type);
+        self
+    }
+}
+
+impl<T: ToSql> ToSql for Param<T> {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        let value = self.value.to_sql()?;
+        let Some(sql_type) = self.sql_type else {
+            return Ok(value);
+        };
+        sql_type.validate()?;
+        Ok(if value.is_null() {
+            SqlValue::TypedNull(sql_type)
+        } else {
+            SqlValue::Typed(sql_type, Box::new(value))
+        })
+    }
+
+    fn sql_type(&self) -> &'static str {
+        match self.sql_type {
+            Some(sql_type) => sql_type.name(),
+            None => self.value.sql_type(),
+        }
+    }
+}
+
+/// An `OUTPUT` parameter, filled with the value the server returns.
+///
+/// The parameter is declared as `sql_type`; give string and binary types a
+/// length large enough for the returned value. Pass the handle like any other
+/// parameter, mark it `OUTPUT` in the SQL text, and read it after execution:
+///
+/// ```rust,ignore
+/// let total = OutParam::<i32>::new(SqlType::Int);
+/// let name = OutParam::<String>::input_output(SqlType::NVarChar(100), "draft");
+/// client
+///     .execute("EXEC dbo.CloseOrder @p1, @p2 OUTPUT, @p3 OUTPUT", &[&42, &total, &name])
+///     .await?;
+/// println!("{} {}", total.value()?, name.value()?);
+/// ```
+#[derive(Debug)]
+pub struct OutParam<T> {
+    sql_type: SqlType,
+    input: Option<SqlValue>,
+    output: Mutex<Option<SqlValue>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> OutParam<T> {
+    /// Create an output-only parameter; NULL is sent as its value.
+    pub fn new(sql_type: SqlType) -> Self {
+        Self {
+            sql_type,
+            input: None,
+            output: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a parameter that sends `value` and receives the value the
+    /// server leaves in it.
+    pub fn input_output(sql_type: SqlType, value: impl Into<SqlValue>) -> Self {
+        Self {
+            input: Some(value.into()),
+            ..Self::new(sql_type)
+        }
+    }
+
+    /// Get the returned value, or `None` before execution.
+    pub fn raw_value(&self) -> Option<SqlValue> {
+        self.output
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl<T: FromSql> OutParam<T> {
+    /// Convert the returned value.
+    ///
+    /// Fails with [`TypeError::UnexpectedNull`] before execution; use an
+    /// `Option` type for parameters the server may set to NULL.
+    pub fn value(&self) -> Result<T, TypeError> {
+        let guard = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        let value = guard.as_ref().ok_or(TypeError::UnexpectedNull)?;
+        T::from_sql(value)
+    }
+}
+
+impl<T> ToSql for OutParam<T> {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        self.sql_type.validate()?;
+        Ok(match &self.input {
+            Some(value) if !value.is_null() => {
+                SqlValue::Typed(self.sql_type, Box::new(value.clone()))
+            }
+            _ => SqlValue::TypedNull(self.sql_type),
+        })
+    }
+
+    fn sql_type(&self) -> &'static str {
+        self.sql_type.name()
+    }
+
+    fn is_output(&self) -> bool {
+        true
+    }
+
+    fn set_output(&self, value: SqlValue) {
+        *self.output.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+    }
+}
+
+/// A string sent as `VARCHAR` instead of `NVARCHAR`.
+///
+/// Comparing a `VARCHAR` column with an `NVARCHAR` parameter makes SQL
+/// Server convert the column, which can turn an index seek into a scan.
+/// The text is encoded in the code page of the connection's collation;
+/// characters it cannot represent are an error rather than `?`.
+///
+/// ```rust,ignore
+/// client
+///     .query("SELECT * FROM Customers WHERE AccountCode = @p1", &[&Varchar("AC-1001")])
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Varchar<T>(pub T);
+
+impl<T: AsRef<str>> ToSql for Varchar<T> {
+    fn to_sql(&self) -> Result<SqlValue, TypeError> {
+        let s = self.0.as_ref();
+        // Declare every short value with the same length so they share a plan
+        let sql_type = if s.len() <= usize::from(crate::sql_type::MAX_VARCHAR_LEN) {
+            SqlType::VarChar(crate::sql_type::MAX_VARCHAR_LEN)
+        } else {
+            SqlType::VarCharMax
+        };
+        Ok(SqlValue::Typed(
+            sql_type,
+            Box::new(SqlValue::String(s.to_owned())),
+        ))
+    }
+
+    fn sql_type(&self) -> &'static str {
+        "VARCHAR"
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -274,4 +512,60 @@ mod tests {
         let none: Option<i32> = None;
         assert_eq!(none.to_sql().unwrap(), SqlValue::Null);
     }
+
+    #[test]
+    fn test_varchar() {
+        assert_eq!(
+            Varchar("abc").to_sql().unwrap(),
+            SqlValue::Typed(
+                SqlType::VarChar(8000),
+                Box::new(SqlValue::String("abc".into()))
+            )
+        );
+        let long = Varchar("x".repeat(8001)).to_sql().unwrap();
+        assert!(matches!(long, SqlValue::Typed(SqlType::VarCharMax, _)));
+        assert_eq!(Varchar("abc").sql_type(), "VARCHAR");
+    }
+
+    #[test]
+    fn test_param_with_type() {
+        let param = Param::new(42).with_type(SqlType::BigInt);
+        assert_eq!(
+            param.to_sql().unwrap(),
+            SqlValue::Typed(SqlType::BigInt, Box::new(SqlValue::Int(42)))
+        );
+        assert_eq!(param.sql_type(), "BIGINT");
+
+        let null = Param::new(None::<i32>).with_type(SqlType::Int);
+        assert_eq!(null.to_sql().unwrap(), SqlValue::TypedNull(SqlType::Int));
+        assert!(null.to_sql().unwrap().is_null());
+
+        assert_eq!(Param::new(42).to_sql().unwrap(), SqlValue::Int(42));
+        assert!(
+            Param::new("x")
+                .with_type(SqlType::NVarChar(0))
+                .to_sql()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_out_param() {
+        let param = OutParam::<i32>::new(SqlType::Int);
+        assert!(param.is_output());
+        assert_eq!(param.to_sql().unwrap(), SqlValue::TypedNull(SqlType::Int));
+        assert!(matches!(param.value(), Err(TypeError::UnexpectedNull)));
+
+        let dyn_param: &dyn ToSql = &param;
+        dyn_param.set_output(SqlValue::Int(7));
+        assert_eq!(param.value().unwrap(), 7);
+
+        let param = OutParam::<Option<i64>>::input_output(SqlType::BigInt, 5i64);
+        assert_eq!(
+            param.to_sql().unwrap(),
+            SqlValue::Typed(SqlType::BigInt, Box::new(SqlValue::BigInt(5)))
+        );
+        param.set_output(SqlValue::Null);
+        assert_eq!(param.value().unwrap(), None);
+    }
 }
@@ -240,6 +240,8 @@ pub struct TvpColumnDef {
     pub column_type: TvpColumnType,
     /// Whether the column is nullable.
     pub nullable: bool,
+    /// Whether the column takes its server-side default instead of row data.
+    pub default: bool,
 }
 
 impl TvpColumnDef {
@@ -249,6 +251,7 @@ impl TvpColumnDef {
         Self {
             column_type,
             nullable: false,
+            default: false,
         }
     }
 
@@ -258,9 +261,20 @@ impl TvpColumnDef {
         Self {
             column_type,
             nullable: true,
+            default: false,
         }
     }
 
+    /// Mark the column as a default column.
+    ///
+    /// The server fills the column from the table type's `DEFAULT` (or
+    /// `IDENTITY`), and the values rows hold for it are not sent.
+    #[must_use]
+    pub const fn with_default(mut self) -> Self {
+        self.default = true;
+        self
+    }
+
     /// Create from an SQL type string (e.g., "INT", "NVARCHAR(100)").
     ///
     /// Returns `None` if the SQL type is not recognized.
@@ -270,6 +284,69 @@ impl TvpColumnDef {
     }
 }
 
+/// Direction of a TVP sort order hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TvpSortOrder {
+    /// Rows are sorted in ascending order on the column.
+    Ascending,
+    /// Rows are sorted in descending order on the column.
+    Descending,
+}
+
+/// Sort order and uniqueness hint for one TVP column.
+///
+/// Hints let the server skip sorting the rows when inserting them into an
+/// index with the same key. They are promises: rows must actually be sorted
+/// (and unique) as declared, or the statement fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TvpOrderHint {
+    /// Zero-based index of the column.
+    pub column: usize,
+    /// Sort order of the rows on the column, if sorted.
+    pub order: Option<TvpSortOrder>,
+    /// Whether the column is part of a unique key.
+    pub unique: bool,
+}
+
+impl TvpOrderHint {
+    /// Rows are sorted ascending on the column.
+    #[must_use]
+    pub const fn ascending(column: usize) -> Self {
+        Self {
+            column,
+            order: Some(TvpSortOrder::Ascending),
+            unique: false,
+        }
+    }
+
+    /// Rows are sorted descending on the column.
+    #[must_use]
+    pub const fn descending(column: usize) -> Self {
+        Self {
+            column,
+            order: Some(TvpSortOrder::Descending),
+            unique: false,
+        }
+    }
+
+    /// The column is part of a unique key, without a sort order.
+    #[must_use]
+    pub const fn unique_key(column: usize) -> Self {
+        Self {
+            column,
+            order: None,
+            unique: true,
+        }
+    }
+
+    /// Also mark the column as part of a unique key.
+    #[must_use]
+    pub const fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+}
+
 /// Raw table-valued parameter data for encoding.
 ///
 /// This structure holds all the information needed to encode a TVP
@@ -284,6 +361,8 @@ pub struct TvpData {
     pub columns: Vec<TvpColumnDef>,
     /// Row data - each row is a Vec of SqlValues matching the columns.
     pub rows: Vec<Vec<SqlValue>>,
+    /// Sort order and uniqueness hints, most significant sort column first.
+    pub order_hints: Vec<TvpOrderHint>,
 }
 
 impl TvpData {
@@ -295,6 +374,7 @@ impl TvpData {
             type_name: type_name.into(),
             columns: Vec::new(),
             rows: Vec::new(),
+            order_hints: Vec::new(),
         }
     }
 
@@ -305,8 +385,21 @@ impl TvpData {
         self
     }
 
+    /// Add a sort order or uniqueness hint.
+    ///
+    /// Hints for sort columns are added most significant first, e.g. rows
+    /// sorted by `(customer_id, order_date DESC)` take
+    /// `TvpOrderHint::ascending(0)` then `TvpOrderHint::descending(1)`.
+    #[must_use]
+    pub fn with_order_hint(mut self, hint: TvpOrderHint) -> Self {
+        self.order_hints.push(hint);
+        self
+    }
+
     /// Add a row of values.
     ///
+    /// Values for default columns are ignored but must still be present.
+    ///
     /// # Panics
     ///
     /// Panics if the number of values doesn't match the number of columns.
@@ -422,6 +515,33 @@ mod tests {
         assert_eq!(tvp.len(), 3);
     }
 
+    #[test]
+    fn test_tvp_data_order_hints() {
+        let tvp = TvpData::new("dbo", "OrderRows")
+            .with_column(TvpColumnDef::new(TvpColumnType::Int))
+            .with_column(TvpColumnDef::nullable(TvpColumnType::Date).with_default())
+            .with_order_hint(TvpOrderHint::ascending(0).unique())
+            .with_order_hint(TvpOrderHint::descending(1));
+
+        assert!(!tvp.columns[0].default);
+        assert!(tvp.columns[1].default && tvp.columns[1].nullable);
+        assert_eq!(
+            tvp.order_hints,
+            [
+                TvpOrderHint {
+                    column: 0,
+                    order: Some(TvpSortOrder::Ascending),
+                    unique: true,
+                },
+                TvpOrderHint {
+                    column: 1,
+                    order: Some(TvpSortOrder::Descending),
+                    unique: false,
+                },
+            ]
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Row value count (2) must match column count (1)")]
     fn test_tvp_data_row_mismatch_panics() {
@@ -2,6 +2,7 @@
 
 use bytes::Bytes;
 
+use crate::sql_type::SqlType;
 use crate::tvp::TvpData;
 
 /// A SQL value that can represent any SQL Server data type.
@@ -13,6 +14,11 @@ use crate::tvp::TvpData;
 pub enum SqlValue {
     /// NULL value.
     Null,
+    /// NULL value sent as a parameter of the given type.
+    TypedNull(SqlType),
+    /// Value sent as a parameter of the given type instead of the type
+    /// inferred from the value.
+    Typed(SqlType, Box<SqlValue>),
     /// Boolean value (BIT).
     Bool(bool),
     /// 8-bit unsigned integer (TINYINT).
@@ -43,12 +49,18 @@ pub enum SqlValue {
     /// Time value (TIME).
     #[cfg(feature = "chrono")]
     Time(chrono::NaiveTime),
-    /// DateTime value (DATETIME, DATETIME2, SMALLDATETIME).
+    /// DateTime value (DATETIME, SMALLDATETIME, or DATETIME2 without a scale).
     #[cfg(feature = "chrono")]
     DateTime(chrono::NaiveDateTime),
-    /// DateTimeOffset value (DATETIMEOFFSET).
+    /// DateTimeOffset value (DATETIMEOFFSET without a scale).
     #[cfg(feature = "chrono")]
     DateTimeOffset(chrono::DateTime<chrono::FixedOffset>),
+    /// DATETIME2 value with its declared scale.
+    #[cfg(feature = "chrono")]
+    ScaledDateTime2(crate::datetime::SqlDateTime2),
+    /// DATETIMEOFFSET value with its declared scale.
+    #[cfg(feature = "chrono")]
+    ScaledDateTimeOffset(crate::datetime::SqlDateTimeOffset),
     /// JSON value (JSON type in SQL Server 2016+).
     #[cfg(feature = "json")]
     Json(serde_json::Value),
@@ -65,7 +77,7 @@ impl SqlValue {
     /// Check if the value is NULL.
     #[must_use]
     pub fn is_null(&self) -> bool {
-        matches!(self, Self::Null)
+        matches!(self, Self::Null | Self::TypedNull(_))
     }
 
     /// Get the value as a bool, if it is one.
@@ -134,6 +146,7 @@ impl SqlValue {
     pub fn type_name(&self) -> &'static str {
         match self {
             Self::Null => "NULL",
+            Self::TypedNull(t) | Self::Typed(t, _) => t.name(),
             Self::Bool(_) => "BIT",
             Self::TinyInt(_) => "TINYINT",
             Self::SmallInt(_) => "SMALLINT",
@@ -155,6 +168,10 @@ impl SqlValue {
             Self::DateTime(_) => "DATETIME2",
             #[cfg(feature = "chrono")]
             Self::DateTimeOffset(_) => "DATETIMEOFFSET",
+            #[cfg(feature = "chrono")]
+            Self::ScaledDateTime2(_) => "DATETIME2",
+            #[cfg(feature = "chrono")]
+            Self::ScaledDateTimeOffset(_) => "DATETIMEOFFSET",
             #[cfg(feature = "json")]
             Self::Json(_) => "JSON",
             Self::Xml(_) => "XML",
@@ -260,6 +277,20 @@ impl From<chrono::NaiveDateTime> for SqlValue {
     }
 }
 
+#[cfg(feature = "chrono")]
+impl From<crate::datetime::SqlDateTime2> for SqlValue {
+    fn from(v: crate::datetime::SqlDateTime2) -> Self {
+        Self::ScaledDateTime2(v)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<crate::datetime::SqlDateTimeOffset> for SqlValue {
+    fn from(v: crate::datetime::SqlDateTimeOffset) -> Self {
+        Self::ScaledDateTimeOffset(v)
+    }
+}
+
 #[cfg(feature = "json")]
 impl From<serde_json::Value> for SqlValue {
     fn from(v: serde_json::Value) -> Self {
@@ -16,7 +16,7 @@
 //! ┌─────────────────────────────────────────────────────────────────┐
 //! │ Column Count (2 bytes)                                          │
 //! ├─────────────────────────────────────────────────────────────────┤
-//! │ CEK Table (if encrypted columns present)                        │
+//! │ CEK Table (if column encryption negotiated)                     │
 //! │ ├── CEK Count (2 bytes)                                         │
 //! │ ├── CEK Entry 1                                                 │
 //! │ │   ├── Database ID (4 bytes)                                   │
@@ -40,6 +40,8 @@
 //! │ │   ├── Type Info (variable)                                    │
 //! │ │   ├── CryptoMetadata (if encrypted)                           │
 //! │ │   │   ├── CEK Table Ordinal (2 bytes)                         │
+//! │ │   │   ├── Base User Type (4 bytes)                            │
+//! │ │   │   ├── Base Type Info (variable)                           │
 //! │ │   │   ├── Algorithm ID (1 byte)                               │
 //! │ │   │   ├── Encryption Type (1 byte)                            │
 //! │ │   │   └── Normalization Version (1 byte)                      │
@@ -51,6 +51,16 @@ pub enum ProtocolError {
         max: usize,
     },
 
+    /// A large (PLP) value is longer than the configured maximum.
+    #[error("LOB value too large: {length} bytes (max {max})")]
+    LobTooLarge {
+        /// Length of the value, or of the part read so far if the server
+        /// did not send the total length.
+        length: u64,
+        /// Maximum allowed length.
+        max: u64,
+    },
+
     /// Invalid packet status flags.
     #[error("invalid packet status: {0:#x}")]
     InvalidPacketStatus(u8),
@@ -112,26 +112,36 @@ pub mod version;
 
 pub use error::ProtocolError;
 pub use login7::{
-    FeatureExtension, FeatureId, Login7, OptionFlags1, OptionFlags2, OptionFlags3, TypeFlags,
+    AZURE_SQL_SUPPORT_ENABLED, COLUMN_ENCRYPTION_VERSION_1, COLUMN_ENCRYPTION_VERSION_2,
+    COLUMN_ENCRYPTION_VERSION_3, FeatureExtension, FeatureId, Login7, OptionFlags1, OptionFlags2,
+    OptionFlags3, TypeFlags,
 };
 pub use packet::{
-    DEFAULT_PACKET_SIZE, MAX_PACKET_SIZE, PACKET_HEADER_SIZE, PacketHeader, PacketStatus,
-    PacketType,
+    DEFAULT_PACKET_SIZE, MAX_PACKET_SIZE, MIN_PACKET_SIZE, PACKET_HEADER_SIZE, PacketHeader,
+    PacketStatus, PacketType,
 };
 pub use prelogin::{EncryptionLevel, PreLogin, PreLoginOption};
-pub use rpc::{ParamFlags, ProcId, RpcOptionFlags, RpcParam, RpcRequest, TypeInfo as RpcTypeInfo};
-pub use sql_batch::{SqlBatch, encode_sql_batch, encode_sql_batch_with_transaction};
+pub use rpc::{
+    ParamCipherInfo, ParamDirection, ParamFlags, ProcId, RPC_BATCH_FLAG, RpcOptionFlags, RpcParam,
+    RpcRequest, TypeInfo as RpcTypeInfo, encode_rpc_batch, encode_rpc_batch_with_enclave_package,
+};
+pub use sql_batch::{
+    SqlBatch, encode_sql_batch, encode_sql_batch_with_enclave_package,
+    encode_sql_batch_with_transaction,
+};
 pub use token::{
-    ColMetaData, Collation, ColumnData, Done, DoneInProc, DoneProc, DoneStatus, EnvChange,
-    EnvChangeType, EnvChangeValue, FeatureExtAck, FedAuthInfo, LoginAck, NbcRow, Order, RawRow,
-    ReturnValue, ServerError, ServerInfo, SessionState, SspiToken, Token, TokenParser, TokenType,
-    TypeInfo,
+    ColInfo, ColMetaData, Collation, ColumnData, ColumnEncryptionAck, ColumnProperty, Done,
+    DoneInProc, DoneProc, DoneStatus, EncryptedColumn, EnvChange, EnvChangeType, EnvChangeValue,
+    FeatureExtAck, FedAuthInfo, LoginAck, NbcRow, Order, RawRow, ReturnValue, ServerError,
+    ServerInfo, SessionState, SspiToken, TabName, Token, TokenParser, TokenType, TypeInfo,
 };
 pub use tvp::{
-    TVP_END_TOKEN, TVP_ROW_TOKEN, TVP_TYPE_ID, TvpColumnDef as TvpWireColumnDef, TvpColumnFlags,
-    TvpEncoder, TvpWireType, encode_tvp_bit, encode_tvp_date, encode_tvp_datetime2,
-    encode_tvp_datetimeoffset, encode_tvp_decimal, encode_tvp_float, encode_tvp_guid,
-    encode_tvp_int, encode_tvp_null, encode_tvp_nvarchar, encode_tvp_time, encode_tvp_varbinary,
+    TVP_COLUMN_ORDERING_TOKEN, TVP_END_TOKEN, TVP_ORDER_ASC, TVP_ORDER_DESC,
+    TVP_ORDER_UNIQUE_TOKEN, TVP_ROW_TOKEN, TVP_TYPE_ID, TVP_UNIQUE,
+    TvpColumnDef as TvpWireColumnDef, TvpColumnFlags, TvpEncoder, TvpOrderUnique, TvpWireType,
+    encode_tvp_bit, encode_tvp_date, encode_tvp_datetime2, encode_tvp_datetimeoffset,
+    encode_tvp_decimal, encode_tvp_float, encode_tvp_guid, encode_tvp_int, encode_tvp_null,
+    encode_tvp_nvarchar, encode_tvp_time, encode_tvp_varbinary,
 };
 pub use types::{ColumnFlags, TypeId, Updateable};
 pub use version::{SqlServerVersion, TdsVersion};
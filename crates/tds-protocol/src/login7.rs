@@ -14,8 +14,14 @@
 //!
 //! The password is obfuscated (not encrypted) using a simple XOR + bit rotation.
 //! Always use TLS encryption for the connection.
+//!
+//! [`Login7`]'s `Debug` output never includes the password or SSPI data. They
+//! are held in [`Zeroizing`] wrappers and zeroed when the packet builder is
+//! dropped, as is the scratch buffer holding the obfuscated password during
+//! encoding.
 
 use bytes::{BufMut, Bytes, BytesMut};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::codec::write_utf16_string;
 use crate::prelude::*;
@@ -24,6 +30,18 @@ use crate::version::TdsVersion;
 /// LOGIN7 packet header size (fixed portion).
 pub const LOGIN7_HEADER_SIZE: usize = 94;
 
+/// COLUMNENCRYPTION version 1: Always Encrypted without secure enclaves.
+pub const COLUMN_ENCRYPTION_VERSION_1: u8 = 0x01;
+
+/// COLUMNENCRYPTION version 2: Always Encrypted with secure enclaves.
+pub const COLUMN_ENCRYPTION_VERSION_2: u8 = 0x02;
+
+/// COLUMNENCRYPTION version 3: secure enclaves with enclave session caching.
+pub const COLUMN_ENCRYPTION_VERSION_3: u8 = 0x03;
+
+/// AZURESQLSUPPORT flag: the client supports Azure SQL Database features.
+pub const AZURE_SQL_SUPPORT_ENABLED: u8 = 0x01;
+
 /// LOGIN7 option flags 1.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct OptionFlags1 {
@@ -214,7 +232,7 @@ pub enum FeatureId {
 }
 
 /// LOGIN7 packet builder.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Login7 {
     /// TDS version to request.
     pub tds_version: TdsVersion,
@@ -243,7 +261,7 @@ pub struct Login7 {
     /// Username for SQL authentication.
     pub username: String,
     /// Password for SQL authentication.
-    pub password: String,
+    pub password: Zeroizing<String>,
     /// Application name.
     pub app_name: String,
     /// Server name.
@@ -259,11 +277,11 @@ pub struct Login7 {
     /// Client ID (MAC address, typically zeros).
     pub client_id: [u8; 6],
     /// SSPI data for integrated authentication.
-    pub sspi_data: Vec<u8>,
+    pub sspi_data: Zeroizing<Vec<u8>>,
     /// Attach DB filename (for LocalDB).
     pub attach_db_file: String,
     /// New password (for password change).
-    pub new_password: String,
+    pub new_password: Zeroizing<String>,
     /// Feature extensions.
     pub features: Vec<FeatureExtension>,
 }
@@ -277,6 +295,41 @@ pub struct FeatureExtension {
     pub data: Bytes,
 }
 
+impl FeatureExtension {
+    /// Request column encryption (Always Encrypted) up to `version`.
+    ///
+    /// Use [`COLUMN_ENCRYPTION_VERSION_2`] or later to also request secure
+    /// enclave support.
+    #[must_use]
+    pub fn column_encryption(version: u8) -> Self {
+        Self {
+            feature_id: FeatureId::ColumnEncryption,
+            data: Bytes::copy_from_slice(&[version]),
+        }
+    }
+}
+
+impl FeatureExtension {
+    /// Announce Azure SQL Database support (AZURESQLSUPPORT).
+    #[must_use]
+    pub fn azure_sql_support() -> Self {
+        Self {
+            feature_id: FeatureId::AzureSqlSupport,
+            data: Bytes::copy_from_slice(&[AZURE_SQL_SUPPORT_ENABLED]),
+        }
+    }
+
+    /// Ask whether the server allows clients to cache its address
+    /// (AZURESQLDNSCACHING). The request carries no data.
+    #[must_use]
+    pub fn azure_sql_dns_caching() -> Self {
+        Self {
+            feature_id: FeatureId::AzureSqlDnsCaching,
+            data: Bytes::new(),
+        }
+    }
+}
+
 impl Default for Login7 {
     fn default() -> Self {
         #[cfg(feature = "std")]
@@ -310,7 +363,7 @@ impl Default for Login7 {
             client_lcid: 0x0409, // English (US)
             hostname: String::new(),
             username: String::new(),
-            password: String::new(),
+            password: Zeroizing::default(),
             app_name: String::from("rust-mssql-driver"),
             server_name: String::new(),
             unused: String::new(),
@@ -318,9 +371,9 @@ impl Default for Login7 {
             language: String::new(),
             database: String::new(),
             client_id: [0u8; 6],
-            sspi_data: Vec::new(),
+            sspi_data: Zeroizing::default(),
             attach_db_file: String::new(),
-            new_password: String::new(),
+            new_password: Zeroizing::default(),
             features: Vec::new(),
         }
     }
@@ -348,7 +401,7 @@ impl Login7 {
         password: impl Into<String>,
     ) -> Self {
         self.username = username.into();
-        self.password = password.into();
+        self.password = Zeroizing::new(password.into());
         self.option_flags2.integrated_security = false;
         self
     }
@@ -356,7 +409,7 @@ impl Login7 {
     /// Enable integrated (Windows) authentication.
     #[must_use]
     pub fn with_integrated_auth(mut self, sspi_data: Vec<u8>) -> Self {
-        self.sspi_data = sspi_data;
+        self.sspi_data = Zeroizing::new(sspi_data);
         self.option_flags2.integrated_security = true;
         self
     }
@@ -595,6 +648,7 @@ impl Login7 {
 
         // Append variable data
         buf.put_slice(&var_data);
+        var_data[..].zeroize();
 
         buf.freeze()
     }
@@ -619,6 +673,43 @@ impl Login7 {
     }
 }
 
+impl core::fmt::Debug for Login7 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Never expose credentials in debug output
+        f.debug_struct("Login7")
+            .field("tds_version", &self.tds_version)
+            .field("packet_size", &self.packet_size)
+            .field("client_prog_version", &self.client_prog_version)
+            .field("client_pid", &self.client_pid)
+            .field("connection_id", &self.connection_id)
+            .field("option_flags1", &self.option_flags1)
+            .field("option_flags2", &self.option_flags2)
+            .field("type_flags", &self.type_flags)
+            .field("option_flags3", &self.option_flags3)
+            .field("client_timezone", &self.client_timezone)
+            .field("client_lcid", &self.client_lcid)
+            .field("hostname", &self.hostname)
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .field("app_name", &self.app_name)
+            .field("server_name", &self.server_name)
+            .field("library_name", &self.library_name)
+            .field("language", &self.language)
+            .field("database", &self.database)
+            .field(
+                "sspi_data",
+                &format_args!("[{} bytes]", self.sspi_data.len()),
+            )
+            .field("attach_db_file", &self.attach_db_file)
+            .field(
+                "new_password",
+                &(!self.new_password.is_empty()).then_some("[REDACTED]"),
+            )
+            .field("features", &self.features)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -650,6 +741,21 @@ mod tests {
         assert_eq!(tds_version, TdsVersion::V7_4.raw());
     }
 
+    #[test]
+    fn test_debug_redacts_credentials() {
+        let mut login = Login7::new()
+            .with_sql_auth("testuser", "s3cr3t-Pa55")
+            .with_integrated_auth(vec![0xAB; 4]);
+        login.new_password = Zeroizing::new("n3w-Pa55".into());
+
+        let debug = format!("{login:?}");
+        assert!(debug.contains("testuser"));
+        assert!(!debug.contains("s3cr3t-Pa55"));
+        assert!(!debug.contains("n3w-Pa55"));
+        assert!(debug.contains("[4 bytes]"));
+        assert!(debug.contains("[REDACTED]"));
+    }
+
     #[test]
     fn test_password_obfuscation() {
         // Known test case: "a" should encode to specific bytes
@@ -683,4 +789,34 @@ mod tests {
         };
         assert_eq!(flags3.to_byte(), 0x10);
     }
+
+    #[test]
+    fn test_column_encryption_feature() {
+        let plain = Login7::new().encode();
+        let login = Login7::new().with_feature(FeatureExtension::column_encryption(
+            COLUMN_ENCRYPTION_VERSION_1,
+        ));
+        assert!(login.option_flags3.extension);
+
+        // Feature block: offset (4) + id (1) + length (4) + version (1) + terminator (1)
+        let encoded = login.encode();
+        assert_eq!(encoded.len(), plain.len() + 11);
+        assert_eq!(
+            &encoded[encoded.len() - 7..],
+            &[0x04, 1, 0, 0, 0, 0x01, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_azure_sql_features() {
+        let login = Login7::new()
+            .with_feature(FeatureExtension::azure_sql_support())
+            .with_feature(FeatureExtension::azure_sql_dns_caching());
+
+        let encoded = login.encode();
+        assert_eq!(
+            &encoded[encoded.len() - 12..],
+            &[0x08, 1, 0, 0, 0, 0x01, 0x0B, 0, 0, 0, 0, 0xFF]
+        );
+    }
 }
@@ -14,6 +14,9 @@ pub const MAX_PACKET_SIZE: usize = 65535;
 /// Default TDS packet size.
 pub const DEFAULT_PACKET_SIZE: usize = 4096;
 
+/// Minimum TDS packet size a server will negotiate.
+pub const MIN_PACKET_SIZE: usize = 512;
+
 /// TDS packet type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
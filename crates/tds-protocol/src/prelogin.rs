@@ -138,14 +138,42 @@ pub struct PreLogin {
 }
 
 /// Distributed tracing ID.
-#[derive(Debug, Clone, Copy)]
+///
+/// Sent in the TRACEID option so the server can tie the connection to a
+/// client-side activity; SQL Server exposes it to Extended Events as
+/// `attach_activity_id_xfer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TraceId {
+    /// Client connection ID (GUID).
+    pub connection_id: [u8; 16],
     /// Activity ID (GUID).
     pub activity_id: [u8; 16],
     /// Activity sequence.
     pub activity_sequence: u32,
 }
 
+impl TraceId {
+    /// Encoded length of the TRACEID option.
+    pub const SIZE: usize = 36;
+
+    /// Create a trace ID for an activity, with a zero connection ID.
+    #[must_use]
+    pub const fn new(activity_id: [u8; 16], activity_sequence: u32) -> Self {
+        Self {
+            connection_id: [0; 16],
+            activity_id,
+            activity_sequence,
+        }
+    }
+
+    /// Set the client connection ID.
+    #[must_use]
+    pub const fn with_connection_id(mut self, connection_id: [u8; 16]) -> Self {
+        self.connection_id = connection_id;
+        self
+    }
+}
+
 impl PreLogin {
     /// Create a new pre-login message with default values.
     #[must_use]
@@ -186,6 +214,13 @@ impl PreLogin {
         self
     }
 
+    /// Set the trace ID.
+    #[must_use]
+    pub fn with_trace_id(mut self, trace_id: TraceId) -> Self {
+        self.trace_id = Some(trace_id);
+        self
+    }
+
     /// Set the instance name.
     #[must_use]
     pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
@@ -274,12 +309,12 @@ impl PreLogin {
         if let Some(ref trace_id) = self.trace_id {
             buf.put_u8(PreLoginOption::TraceId as u8);
             buf.put_u16(data_offset);
-            buf.put_u16(36);
+            buf.put_u16(TraceId::SIZE as u16);
+            // Per MS-TDS 2.2.6.5: GUID_CONNID, then ActivityId (GUID + sequence)
+            data_buf.put_slice(&trace_id.connection_id);
             data_buf.put_slice(&trace_id.activity_id);
             data_buf.put_u32_le(trace_id.activity_sequence);
-            // Connection ID (16 bytes, typically zeros for client)
-            data_buf.put_slice(&[0u8; 16]);
-            data_offset += 36;
+            data_offset += TraceId::SIZE as u16;
         }
 
         // FEDAUTHREQUIRED option (if set)
@@ -420,6 +455,19 @@ impl PreLogin {
                     prelogin.thread_id =
                         Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
                 }
+                PreLoginOption::TraceId if length >= TraceId::SIZE => {
+                    let bytes = &data[data_offset..data_offset + TraceId::SIZE];
+                    let mut connection_id = [0u8; 16];
+                    connection_id.copy_from_slice(&bytes[..16]);
+                    let mut activity_id = [0u8; 16];
+                    activity_id.copy_from_slice(&bytes[16..32]);
+                    let activity_sequence =
+                        u32::from_le_bytes([bytes[32], bytes[33], bytes[34], bytes[35]]);
+                    prelogin.trace_id = Some(
+                        TraceId::new(activity_id, activity_sequence)
+                            .with_connection_id(connection_id),
+                    );
+                }
                 PreLoginOption::FedAuthRequired if length >= 1 => {
                     prelogin.fed_auth_required = data[data_offset] != 0;
                 }
@@ -453,6 +501,22 @@ mod tests {
         assert_eq!(encoded[0], PreLoginOption::Version as u8);
     }
 
+    #[test]
+    fn test_prelogin_trace_id() {
+        let trace_id = TraceId::new([0xAA; 16], 7).with_connection_id([0x11; 16]);
+        let encoded = PreLogin::new().with_trace_id(trace_id).encode();
+
+        // VERSION, ENCRYPTION, MARS, TRACEID headers + terminator, then
+        // 6 + 1 + 1 bytes of data before the trace ID.
+        let trace_offset = 4 * 5 + 1 + 8;
+        assert_eq!(&encoded[trace_offset..trace_offset + 16], &[0x11; 16]);
+        assert_eq!(&encoded[trace_offset + 16..trace_offset + 32], &[0xAA; 16]);
+        assert_eq!(&encoded[trace_offset + 32..], &7u32.to_le_bytes());
+
+        let decoded = PreLogin::decode(encoded.as_ref()).unwrap();
+        assert_eq!(decoded.trace_id, Some(trace_id));
+    }
+
     #[test]
     fn test_encryption_level() {
         assert!(EncryptionLevel::Required.is_required());
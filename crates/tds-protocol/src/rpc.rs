@@ -147,6 +147,26 @@ impl ParamFlags {
     }
 }
 
+/// Direction of an RPC parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParamDirection {
+    /// Value sent to the server only.
+    #[default]
+    Input,
+    /// Value returned by the server only; sent as NULL.
+    Output,
+    /// Value sent to the server and returned after execution.
+    InputOutput,
+}
+
+impl ParamDirection {
+    /// Check whether the server returns the parameter's value.
+    #[must_use]
+    pub fn is_output(self) -> bool {
+        !matches!(self, Self::Input)
+    }
+}
+
 /// TDS type information for RPC parameters.
 #[derive(Debug, Clone)]
 pub struct TypeInfo {
@@ -274,6 +294,44 @@ impl TypeInfo {
         }
     }
 
+    /// Create type info for VARCHAR with max length in bytes.
+    ///
+    /// `collation` is the 5-byte LCID and sort ID the value is encoded in.
+    pub fn varchar(max_len: u16, collation: [u8; 5]) -> Self {
+        Self {
+            type_id: 0xA7, // BIGVARCHARTYPE
+            max_length: Some(max_len),
+            precision: None,
+            scale: None,
+            collation: Some(collation),
+            tvp_type_name: None,
+        }
+    }
+
+    /// Create type info for VARCHAR(MAX).
+    pub fn varchar_max(collation: [u8; 5]) -> Self {
+        Self {
+            type_id: 0xA7,            // BIGVARCHARTYPE
+            max_length: Some(0xFFFF), // MAX indicator
+            precision: None,
+            scale: None,
+            collation: Some(collation),
+            tvp_type_name: None,
+        }
+    }
+
+    /// Create type info for NCHAR with length.
+    pub fn nchar(len: u16) -> Self {
+        Self {
+            type_id: 0xEF,             // NCHARTYPE
+            max_length: Some(len * 2), // UTF-16, so double the char count
+            precision: None,
+            scale: None,
+            collation: Some([0x09, 0x04, 0xD0, 0x00, 0x34]),
+            tvp_type_name: None,
+        }
+    }
+
     /// Create type info for VARBINARY with max length.
     pub fn varbinary(max_len: u16) -> Self {
         Self {
@@ -310,6 +368,42 @@ impl TypeInfo {
         }
     }
 
+    /// Create type info for TIME.
+    pub fn time(scale: u8) -> Self {
+        Self {
+            type_id: 0x29, // TIMETYPE
+            max_length: None,
+            precision: None,
+            scale: Some(scale),
+            collation: None,
+            tvp_type_name: None,
+        }
+    }
+
+    /// Create type info for DATETIME.
+    pub fn datetime() -> Self {
+        Self {
+            type_id: 0x6F, // DATETIMNTYPE
+            max_length: Some(8),
+            precision: None,
+            scale: None,
+            collation: None,
+            tvp_type_name: None,
+        }
+    }
+
+    /// Create type info for SMALLDATETIME.
+    pub fn smalldatetime() -> Self {
+        Self {
+            type_id: 0x6F, // DATETIMNTYPE
+            max_length: Some(4),
+            precision: None,
+            scale: None,
+            collation: None,
+            tvp_type_name: None,
+        }
+    }
+
     /// Create type info for DATETIME2.
     pub fn datetime2(scale: u8) -> Self {
         Self {
@@ -322,6 +416,18 @@ impl TypeInfo {
         }
     }
 
+    /// Create type info for DATETIMEOFFSET.
+    pub fn datetimeoffset(scale: u8) -> Self {
+        Self {
+            type_id: 0x2B, // DATETIMEOFFSETTYPE
+            max_length: None,
+            precision: None,
+            scale: Some(scale),
+            collation: None,
+            tvp_type_name: None,
+        }
+    }
+
     /// Create type info for DECIMAL.
     pub fn decimal(precision: u8, scale: u8) -> Self {
         Self {
@@ -359,14 +465,14 @@ impl TypeInfo {
 
         // Variable-length types need max length
         match self.type_id {
-            0x26 | 0x68 | 0x6D => {
-                // INTNTYPE, BITNTYPE, FLTNTYPE
+            0x26 | 0x68 | 0x6D | 0x6F => {
+                // INTNTYPE, BITNTYPE, FLTNTYPE, DATETIMNTYPE
                 if let Some(len) = self.max_length {
                     buf.put_u8(len as u8);
                 }
             }
-            0xE7 | 0xA5 | 0xEF => {
-                // NVARCHARTYPE, BIGVARBINTYPE, NCHARTYPE
+            0xE7 | 0xA5 | 0xA7 | 0xEF => {
+                // NVARCHARTYPE, BIGVARBINTYPE, BIGVARCHARTYPE, NCHARTYPE
                 if let Some(len) = self.max_length {
                     buf.put_u16_le(len);
                 }
@@ -404,6 +510,53 @@ impl TypeInfo {
     }
 }
 
+/// Always Encrypted metadata sent after an encrypted parameter value.
+///
+/// ```text
+/// ParamCipherInfo:
+///   type_info: TYPE_INFO (plaintext type)
+///   algorithm_id: BYTE
+///   encryption_type: BYTE
+///   database_id: ULONG
+///   cek_id: ULONG
+///   cek_version: ULONG
+///   cek_md_version: ULONGLONG
+///   normalization_version: BYTE
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParamCipherInfo {
+    /// Type of the parameter before encryption.
+    pub type_info: TypeInfo,
+    /// Encryption algorithm ID.
+    pub algorithm_id: u8,
+    /// Encryption type (1 = deterministic, 2 = randomized).
+    pub encryption_type: u8,
+    /// Database ID where the CEK is defined.
+    pub database_id: u32,
+    /// CEK ID within the database.
+    pub cek_id: u32,
+    /// CEK version.
+    pub cek_version: u32,
+    /// CEK metadata version.
+    pub cek_md_version: u64,
+    /// Normalization rule version.
+    pub normalization_version: u8,
+}
+
+impl ParamCipherInfo {
+    /// Encode to buffer.
+    pub fn encode(&self, buf: &mut BytesMut) {
+        self.type_info.encode(buf);
+        buf.put_u8(self.algorithm_id);
+        buf.put_u8(self.encryption_type);
+        buf.put_u32_le(self.database_id);
+        buf.put_u32_le(self.cek_id);
+        buf.put_u32_le(self.cek_version);
+        buf.put_u64_le(self.cek_md_version);
+        buf.put_u8(self.normalization_version);
+    }
+}
+
 /// An RPC parameter.
 #[derive(Debug, Clone)]
 pub struct RpcParam {
@@ -415,6 +568,8 @@ pub struct RpcParam {
     pub type_info: TypeInfo,
     /// Parameter value (raw bytes).
     pub value: Option<Bytes>,
+    /// Encryption metadata for Always Encrypted parameters.
+    pub cipher_info: Option<ParamCipherInfo>,
 }
 
 impl RpcParam {
@@ -425,6 +580,7 @@ impl RpcParam {
             flags: ParamFlags::default(),
             type_info,
             value: Some(value),
+            cipher_info: None,
         }
     }
 
@@ -435,6 +591,32 @@ impl RpcParam {
             flags: ParamFlags::default(),
             type_info,
             value: None,
+            cipher_info: None,
+        }
+    }
+
+    /// Create an Always Encrypted parameter.
+    ///
+    /// The ciphertext is sent as `varbinary`; the plaintext type travels in
+    /// `cipher_info` and is used for the `sp_executesql` declaration.
+    pub fn encrypted(
+        name: impl Into<String>,
+        ciphertext: Option<Bytes>,
+        cipher_info: ParamCipherInfo,
+    ) -> Self {
+        let type_info = match &ciphertext {
+            Some(value) if value.len() > 8000 => TypeInfo::varbinary(0xFFFF),
+            _ => TypeInfo::varbinary(8000),
+        };
+        Self {
+            name: name.into(),
+            flags: ParamFlags {
+                encrypted: true,
+                ..ParamFlags::default()
+            },
+            type_info,
+            value: ciphertext,
+            cipher_info: Some(cipher_info),
         }
     }
 
@@ -475,6 +657,29 @@ impl RpcParam {
         self
     }
 
+    /// Set the parameter direction.
+    ///
+    /// Output parameters are passed by reference; a pure
+    /// [`Output`](ParamDirection::Output) parameter sends NULL.
+    #[must_use]
+    pub fn with_direction(mut self, direction: ParamDirection) -> Self {
+        self.flags.by_ref = direction.is_output();
+        if direction == ParamDirection::Output {
+            self.value = None;
+        }
+        self
+    }
+
+    /// Get the parameter direction.
+    #[must_use]
+    pub fn direction(&self) -> ParamDirection {
+        match (self.flags.by_ref, &self.value) {
+            (false, _) => ParamDirection::Input,
+            (true, None) => ParamDirection::Output,
+            (true, Some(_)) => ParamDirection::InputOutput,
+        }
+    }
+
     /// Encode the parameter to buffer.
     pub fn encode(&self, buf: &mut BytesMut) {
         // Parameter name (B_VARCHAR - length-prefixed)
@@ -506,8 +711,8 @@ impl RpcParam {
                     buf.put_u8(value.len() as u8);
                     buf.put_slice(value);
                 }
-                0xE7 | 0xA5 => {
-                    // NVARCHARTYPE, BIGVARBINTYPE
+                0xE7 | 0xA5 | 0xA7 | 0xEF => {
+                    // NVARCHARTYPE, BIGVARBINTYPE, BIGVARCHARTYPE, NCHARTYPE
                     if self.type_info.max_length == Some(0xFFFF) {
                         // MAX type - use PLP format
                         // For simplicity, send as single chunk
@@ -526,12 +731,8 @@ impl RpcParam {
                     buf.put_u8(value.len() as u8);
                     buf.put_slice(value);
                 }
-                0x28 => {
-                    // DATETYPE (fixed 3 bytes)
-                    buf.put_slice(value);
-                }
-                0x2A => {
-                    // DATETIME2TYPE
+                0x28..=0x2B => {
+                    // DATETYPE, TIMETYPE, DATETIME2TYPE, DATETIMEOFFSETTYPE
                     buf.put_u8(value.len() as u8);
                     buf.put_slice(value);
                 }
@@ -555,7 +756,7 @@ impl RpcParam {
         } else {
             // NULL value
             match self.type_info.type_id {
-                0xE7 | 0xA5 => {
+                0xE7 | 0xA5 | 0xA7 | 0xEF => {
                     // Variable-length types use 0xFFFF for NULL
                     if self.type_info.max_length == Some(0xFFFF) {
                         buf.put_u64_le(0xFFFFFFFFFFFFFFFF); // PLP NULL
@@ -568,6 +769,10 @@ impl RpcParam {
                 }
             }
         }
+
+        if let Some(ref cipher_info) = self.cipher_info {
+            cipher_info.encode(buf);
+        }
     }
 }
 
@@ -641,10 +846,16 @@ impl RpcRequest {
     }
 
     /// Build parameter declaration string for sp_executesql.
-    fn build_param_declarations(params: &[RpcParam]) -> String {
+    ///
+    /// Encrypted parameters are declared with their plaintext type.
+    pub fn build_param_declarations(params: &[RpcParam]) -> String {
         params
             .iter()
             .map(|p| {
+                let type_info = p
+                    .cipher_info
+                    .as_ref()
+                    .map_or(&p.type_info, |c| &c.type_info);
                 let name = if p.name.starts_with('@') {
                     p.name.clone()
                 } else if p.name.is_empty() {
@@ -657,8 +868,8 @@ impl RpcRequest {
                     format!("@{}", p.name)
                 };
 
-                let type_name: String = match p.type_info.type_id {
-                    0x26 => match p.type_info.max_length {
+                let type_name: String = match type_info.type_id {
+                    0x26 => match type_info.max_length {
                         Some(1) => "tinyint".to_string(),
                         Some(2) => "smallint".to_string(),
                         Some(4) => "int".to_string(),
@@ -666,41 +877,65 @@ impl RpcRequest {
                         _ => "int".to_string(),
                     },
                     0x68 => "bit".to_string(),
-                    0x6D => match p.type_info.max_length {
+                    0x6D => match type_info.max_length {
                         Some(4) => "real".to_string(),
                         _ => "float".to_string(),
                     },
                     0xE7 => {
-                        if p.type_info.max_length == Some(0xFFFF) {
+                        if type_info.max_length == Some(0xFFFF) {
                             "nvarchar(max)".to_string()
                         } else {
-                            let len = p.type_info.max_length.unwrap_or(4000) / 2;
+                            let len = type_info.max_length.unwrap_or(4000) / 2;
                             format!("nvarchar({})", len)
                         }
                     }
+                    0xA7 => {
+                        if type_info.max_length == Some(0xFFFF) {
+                            "varchar(max)".to_string()
+                        } else {
+                            let len = type_info.max_length.unwrap_or(8000);
+                            format!("varchar({})", len)
+                        }
+                    }
+                    0xEF => {
+                        let len = type_info.max_length.unwrap_or(2) / 2;
+                        format!("nchar({})", len)
+                    }
                     0xA5 => {
-                        if p.type_info.max_length == Some(0xFFFF) {
+                        if type_info.max_length == Some(0xFFFF) {
                             "varbinary(max)".to_string()
                         } else {
-                            let len = p.type_info.max_length.unwrap_or(8000);
+                            let len = type_info.max_length.unwrap_or(8000);
                             format!("varbinary({})", len)
                         }
                     }
                     0x24 => "uniqueidentifier".to_string(),
                     0x28 => "date".to_string(),
+                    0x29 => {
+                        let scale = type_info.scale.unwrap_or(7);
+                        format!("time({})", scale)
+                    }
+                    0x6F => match type_info.max_length {
+                        Some(4) => "smalldatetime".to_string(),
+                        _ => "datetime".to_string(),
+                    },
                     0x2A => {
-                        let scale = p.type_info.scale.unwrap_or(7);
+                        let scale = type_info.scale.unwrap_or(7);
                         format!("datetime2({})", scale)
                     }
+                    0x2B => {
+                        let scale = type_info.scale.unwrap_or(7);
+                        format!("datetimeoffset({})", scale)
+                    }
                     0x6C => {
-                        let precision = p.type_info.precision.unwrap_or(18);
-                        let scale = p.type_info.scale.unwrap_or(0);
+                        let precision = type_info.precision.unwrap_or(18);
+                        let scale = type_info.scale.unwrap_or(0);
                         format!("decimal({}, {})", precision, scale)
                     }
                     0xF3 => {
                         // TVP - Table-Valued Parameter
                         // Must be declared with the table type name and READONLY
-                        if let Some(ref tvp_name) = p.type_info.tvp_type_name {
+                        if let Some(ref tvp_name) = type_info.tvp_type_name {
                             format!("{} READONLY", tvp_name)
                         } else {
                             // Fallback if type name is missing (shouldn't happen)
@@ -710,7 +945,11 @@ impl RpcRequest {
                     _ => "sql_variant".to_string(),
                 };
 
-                format!("{} {}", name, type_name)
+                if p.flags.by_ref {
+                    format!("{} {} OUTPUT", name, type_name)
+                } else {
+                    format!("{} {}", name, type_name)
+                }
             })
             .collect::<Vec<_>>()
             .join(", ")
@@ -760,6 +999,77 @@ impl RpcRequest {
         request
     }
 
+    /// Create an sp_cursoropen request.
+    ///
+    /// `scroll_options` and `concurrency_options` are the `@scrollopt` and
+    /// `@ccopt` bit flags; the server returns the values it actually used.
+    /// When `params` is non-empty, the `PARAMETERIZED_STMT` scroll option
+    /// (0x1000) is added automatically.
+    pub fn cursor_open(
+        sql: &str,
+        params: Vec<RpcParam>,
+        scroll_options: i32,
+        concurrency_options: i32,
+    ) -> Self {
+        let mut request = Self::by_id(ProcId::CursorOpen);
+        let scroll_options = if params.is_empty() {
+            scroll_options
+        } else {
+            scroll_options | 0x1000
+        };
+
+        // OUT: cursor handle (INT)
+        request
+            .params
+            .push(RpcParam::null("@cursor", TypeInfo::int()).as_output());
+
+        // SQL statement
+        request.params.push(RpcParam::nvarchar("@stmt", sql));
+
+        // IN/OUT: scroll and concurrency options
+        request
+            .params
+            .push(RpcParam::int("@scrollopt", scroll_options).as_output());
+        request
+            .params
+            .push(RpcParam::int("@ccopt", concurrency_options).as_output());
+
+        // OUT: row count
+        request
+            .params
+            .push(RpcParam::null("@rowcount", TypeInfo::int()).as_output());
+
+        if !params.is_empty() {
+            let declarations = Self::build_param_declarations(&params);
+            request
+                .params
+                .push(RpcParam::nvarchar("@paramdef", &declarations));
+            request.params.extend(params);
+        }
+
+        request
+    }
+
+    /// Create an sp_cursorfetch request.
+    ///
+    /// `fetch_type` is the `@fetchtype` flag (e.g. 0x0002 for NEXT) and
+    /// `row_num` is only meaningful for absolute and relative fetches.
+    pub fn cursor_fetch(handle: i32, fetch_type: i32, row_num: i32, n_rows: i32) -> Self {
+        let mut request = Self::by_id(ProcId::CursorFetch);
+        request.params.push(RpcParam::int("@cursor", handle));
+        request.params.push(RpcParam::int("@fetchtype", fetch_type));
+        request.params.push(RpcParam::int("@rownum", row_num));
+        request.params.push(RpcParam::int("@nrows", n_rows));
+        request
+    }
+
+    /// Create an sp_cursorclose request.
+    pub fn cursor_close(handle: i32) -> Self {
+        let mut request = Self::by_id(ProcId::CursorClose);
+        request.params.push(RpcParam::int("@cursor", handle));
+        request
+    }
+
     /// Set option flags.
     #[must_use]
     pub fn with_options(mut self, options: RpcOptionFlags) -> Self {
@@ -795,25 +1105,30 @@ impl RpcRequest {
     ///   or 0 for auto-commit mode.
     #[must_use]
     pub fn encode_with_transaction(&self, transaction_descriptor: u64) -> Bytes {
-        let mut buf = BytesMut::with_capacity(256);
-
-        // ALL_HEADERS - TDS 7.2+ requires this section
-        // Total length placeholder (will be filled in)
-        let all_headers_start = buf.len();
-        buf.put_u32_le(0); // Total length placeholder
-
-        // Transaction descriptor header (required for RPC)
-        // Per MS-TDS 2.2.5.3: HeaderLength (4) + HeaderType (2) + TransactionDescriptor (8) + OutstandingRequestCount (4)
-        buf.put_u32_le(18); // Header length
-        buf.put_u16_le(0x0002); // Header type: transaction descriptor
-        buf.put_u64_le(transaction_descriptor); // Transaction descriptor from BeginTransaction EnvChange
-        buf.put_u32_le(1); // Outstanding request count (1 for non-MARS connections)
+        self.encode_with_enclave_package(transaction_descriptor, None)
+    }
 
-        // Fill in ALL_HEADERS total length
-        let all_headers_len = buf.len() - all_headers_start;
-        let len_bytes = (all_headers_len as u32).to_le_bytes();
-        buf[all_headers_start..all_headers_start + 4].copy_from_slice(&len_bytes);
+    /// Encode the RPC request with a transaction descriptor and enclave package.
+    ///
+    /// Once COLUMNENCRYPTION version 2 or later is negotiated, every request
+    /// carries an enclave package after ALL_HEADERS, empty when no keys are
+    /// sent to the enclave. Pass `None` on connections without enclave
+    /// support to omit the field.
+    #[must_use]
+    pub fn encode_with_enclave_package(
+        &self,
+        transaction_descriptor: u64,
+        enclave_package: Option<&[u8]>,
+    ) -> Bytes {
+        let mut buf = BytesMut::with_capacity(256);
+        encode_all_headers(&mut buf, transaction_descriptor);
+        encode_enclave_package(&mut buf, enclave_package);
+        self.encode_body(&mut buf);
+        buf.freeze()
+    }
 
+    /// Encode the procedure name/ID, option flags, and parameters.
+    fn encode_body(&self, buf: &mut BytesMut) {
         // Procedure name or ID
         if let Some(proc_id) = self.proc_id {
             // Use PROCID format
@@ -823,7 +1138,7 @@ impl RpcRequest {
             // Use procedure name
             let name_len = proc_name.encode_utf16().count() as u16;
             buf.put_u16_le(name_len);
-            write_utf16_string(&mut buf, proc_name);
+            write_utf16_string(buf, proc_name);
         }
 
         // Option flags
@@ -831,10 +1146,72 @@ impl RpcRequest {
 
         // Parameters
         for param in &self.params {
-            param.encode(&mut buf);
+            param.encode(buf);
         }
+    }
+}
 
-        buf.freeze()
+/// Separator between RPC calls batched in a single RPC message (TDS 7.2+).
+pub const RPC_BATCH_FLAG: u8 = 0xFF;
+
+/// Encode several RPC requests into a single RPC message.
+///
+/// Per MS-TDS 2.2.6.6, one RPC message may carry multiple calls separated
+/// by [`RPC_BATCH_FLAG`]. The server executes them in order and terminates
+/// each call's response with a DONEPROC token, so the whole batch costs a
+/// single round trip.
+#[must_use]
+pub fn encode_rpc_batch(requests: &[RpcRequest], transaction_descriptor: u64) -> Bytes {
+    encode_rpc_batch_with_enclave_package(requests, transaction_descriptor, None)
+}
+
+/// Encode several RPC requests into a single RPC message with an enclave
+/// package (see [`RpcRequest::encode_with_enclave_package`]).
+#[must_use]
+pub fn encode_rpc_batch_with_enclave_package(
+    requests: &[RpcRequest],
+    transaction_descriptor: u64,
+    enclave_package: Option<&[u8]>,
+) -> Bytes {
+    let mut buf = BytesMut::with_capacity(256 * requests.len().max(1));
+    encode_all_headers(&mut buf, transaction_descriptor);
+    encode_enclave_package(&mut buf, enclave_package);
+
+    for (i, request) in requests.iter().enumerate() {
+        if i > 0 {
+            buf.put_u8(RPC_BATCH_FLAG);
+        }
+        request.encode_body(&mut buf);
+    }
+
+    buf.freeze()
+}
+
+/// Write the ALL_HEADERS section required for RPC requests in TDS 7.2+.
+fn encode_all_headers(buf: &mut BytesMut, transaction_descriptor: u64) {
+    // Total length placeholder (will be filled in)
+    let all_headers_start = buf.len();
+    buf.put_u32_le(0); // Total length placeholder
+
+    // Transaction descriptor header (required for RPC)
+    // Per MS-TDS 2.2.5.3: HeaderLength (4) + HeaderType (2) + TransactionDescriptor (8) + OutstandingRequestCount (4)
+    buf.put_u32_le(18); // Header length
+    buf.put_u16_le(0x0002); // Header type: transaction descriptor
+    buf.put_u64_le(transaction_descriptor); // Transaction descriptor from BeginTransaction EnvChange
+    buf.put_u32_le(1); // Outstanding request count (1 for non-MARS connections)
+
+    // Fill in ALL_HEADERS total length
+    let all_headers_len = buf.len() - all_headers_start;
+    let len_bytes = (all_headers_len as u32).to_le_bytes();
+    buf[all_headers_start..all_headers_start + 4].copy_from_slice(&len_bytes);
+}
+
+/// Write the enclave package that follows ALL_HEADERS when secure enclaves
+/// are negotiated: a USHORT length and the package bytes.
+pub(crate) fn encode_enclave_package(buf: &mut BytesMut, enclave_package: Option<&[u8]>) {
+    if let Some(package) = enclave_package {
+        buf.put_u16_le(package.len() as u16);
+        buf.put_slice(package);
     }
 }
 
@@ -843,6 +1220,25 @@ impl RpcRequest {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encode_with_enclave_package() {
+        let rpc = RpcRequest::execute_sql("SELECT 1", vec![]);
+        let plain = rpc.encode();
+
+        // Empty package: a zero length after ALL_HEADERS
+        let empty = rpc.encode_with_enclave_package(0, Some(&[]));
+        assert_eq!(empty.len(), plain.len() + 2);
+        assert_eq!(&empty[22..24], &[0, 0]);
+        assert_eq!(&empty[24..], &plain[22..]);
+
+        let package = rpc.encode_with_enclave_package(0, Some(&[1, 2, 3]));
+        assert_eq!(&package[22..27], &[3, 0, 1, 2, 3]);
+        assert_eq!(&package[27..], &plain[22..]);
+
+        let batch = encode_rpc_batch_with_enclave_package(&[rpc], 0, Some(&[]));
+        assert_eq!(batch, empty);
+    }
+
     #[test]
     fn test_proc_id_values() {
         assert_eq!(ProcId::ExecuteSql as u16, 0x000A);
@@ -904,6 +1300,120 @@ mod tests {
         assert!(decls.contains("@name nvarchar"));
     }
 
+    #[test]
+    fn test_output_param_declarations() {
+        let params = vec![
+            RpcParam::int("@p1", 42),
+            RpcParam::int("@p2", 0).with_direction(ParamDirection::Output),
+            RpcParam::int("@p3", 7).with_direction(ParamDirection::InputOutput),
+        ];
+        assert_eq!(params[0].direction(), ParamDirection::Input);
+        assert_eq!(params[1].direction(), ParamDirection::Output);
+        assert!(params[1].value.is_none());
+        assert_eq!(params[2].direction(), ParamDirection::InputOutput);
+        assert_eq!(params[2].flags.encode(), 0x01);
+
+        let decls = RpcRequest::build_param_declarations(&params);
+        assert_eq!(decls, "@p1 int, @p2 int OUTPUT, @p3 int OUTPUT");
+    }
+
+    #[test]
+    fn test_datetime_param_declarations() {
+        let params = vec![
+            RpcParam::new("@p1", TypeInfo::datetime2(0), Bytes::from_static(&[0; 6])),
+            RpcParam::new(
+                "@p2",
+                TypeInfo::datetimeoffset(7),
+                Bytes::from_static(&[0; 10]),
+            ),
+        ];
+
+        let decls = RpcRequest::build_param_declarations(&params);
+        assert_eq!(decls, "@p1 datetime2(0), @p2 datetimeoffset(7)");
+    }
+
+    #[test]
+    fn test_legacy_type_declarations() {
+        let params = vec![
+            RpcParam::null("@p1", TypeInfo::datetime()),
+            RpcParam::null("@p2", TypeInfo::smalldatetime()),
+            RpcParam::null("@p3", TypeInfo::time(3)),
+            RpcParam::null("@p4", TypeInfo::nchar(2)),
+        ];
+
+        let decls = RpcRequest::build_param_declarations(&params);
+        assert_eq!(
+            decls,
+            "@p1 datetime, @p2 smalldatetime, @p3 time(3), @p4 nchar(2)"
+        );
+    }
+
+    #[test]
+    fn test_varchar_param() {
+        let collation = [0x09, 0x04, 0xD0, 0x00, 0x34];
+        let param = RpcParam::new(
+            "@p1",
+            TypeInfo::varchar(8000, collation),
+            Bytes::from_static(b"abc"),
+        );
+        let mut buf = BytesMut::new();
+        param.encode(&mut buf);
+        // type, max length, collation, value length, value
+        assert!(buf.ends_with(&[
+            0xA7, 0x40, 0x1F, 0x09, 0x04, 0xD0, 0x00, 0x34, 3, 0, b'a', b'b', b'c'
+        ]));
+
+        let params = vec![
+            param,
+            RpcParam::null("@p2", TypeInfo::varchar_max(collation)),
+        ];
+        assert_eq!(
+            RpcRequest::build_param_declarations(&params),
+            "@p1 varchar(8000), @p2 varchar(max)"
+        );
+    }
+
+    #[test]
+    fn test_date_param_is_length_prefixed() {
+        let mut buf = BytesMut::new();
+        RpcParam::new("", TypeInfo::date(), Bytes::from_static(&[1, 2, 3])).encode(&mut buf);
+        assert_eq!(&buf[buf.len() - 4..], &[3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_encrypted_param() {
+        let cipher_info = ParamCipherInfo {
+            type_info: TypeInfo::int(),
+            algorithm_id: 2,
+            encryption_type: 1,
+            database_id: 5,
+            cek_id: 7,
+            cek_version: 1,
+            cek_md_version: 0x0102_0304_0506_0708,
+            normalization_version: 1,
+        };
+        let param = RpcParam::encrypted("@p1", Some(Bytes::from_static(&[0xAA; 65])), cipher_info);
+
+        let decls = RpcRequest::build_param_declarations(std::slice::from_ref(&param));
+        assert_eq!(decls, "@p1 int");
+
+        let mut buf = BytesMut::new();
+        param.encode(&mut buf);
+
+        // name (1 + 6), flags, varbinary(8000), length + ciphertext
+        assert_eq!(buf[7], 0x08);
+        assert_eq!(&buf[8..11], &[0xA5, 0x40, 0x1F]);
+        assert_eq!(&buf[11..13], &[65, 0]);
+
+        // ParamCipherInfo: INTN(4), algorithm, type, ids, md version, normalization
+        let cipher = &buf[13 + 65..];
+        assert_eq!(&cipher[..4], &[0x26, 4, 2, 1]);
+        assert_eq!(&cipher[4..8], &5u32.to_le_bytes());
+        assert_eq!(&cipher[16..24], &0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(cipher[24], 1);
+        assert_eq!(cipher.len(), 25);
+    }
+
     #[test]
     fn test_rpc_encode_not_empty() {
         let rpc = RpcRequest::execute_sql("SELECT 1", vec![]);
@@ -939,4 +1449,62 @@ mod tests {
         assert_eq!(rpc.proc_id, Some(ProcId::Unprepare));
         assert_eq!(rpc.params.len(), 1); // just the handle
     }
+
+    #[test]
+    fn test_encode_rpc_batch() {
+        let first = RpcRequest::execute_sql("DELETE FROM t", vec![]);
+        let second = RpcRequest::execute_sql("UPDATE t SET a = @p1", vec![RpcParam::int("@p1", 1)]);
+
+        let single_first = first.encode();
+        let single_second = second.encode();
+        let batch = encode_rpc_batch(&[first, second], 0);
+
+        // ALL_HEADERS (22 bytes) is written once, followed by each call
+        // separated by the batch flag
+        assert_eq!(
+            batch.len(),
+            single_first.len() + single_second.len() - 22 + 1
+        );
+        assert_eq!(&batch[..single_first.len()], &single_first[..]);
+        assert_eq!(batch[single_first.len()], RPC_BATCH_FLAG);
+        assert_eq!(&batch[single_first.len() + 1..], &single_second[22..]);
+    }
+
+    #[test]
+    fn test_cursor_open_request() {
+        let rpc = RpcRequest::cursor_open("SELECT * FROM users", vec![], 0x0004, 0x0001);
+
+        assert_eq!(rpc.proc_id, Some(ProcId::CursorOpen));
+        // cursor, stmt, scrollopt, ccopt, rowcount
+        assert_eq!(rpc.params.len(), 5);
+        assert!(rpc.params[0].flags.by_ref);
+        assert!(rpc.params[2].flags.by_ref);
+        assert!(rpc.params[4].flags.by_ref);
+    }
+
+    #[test]
+    fn test_cursor_open_parameterized() {
+        let rpc = RpcRequest::cursor_open(
+            "SELECT * FROM users WHERE id > @p1",
+            vec![RpcParam::int("@p1", 10)],
+            0x0004,
+            0x0001,
+        );
+
+        // 5 fixed params + paramdef + 1 param
+        assert_eq!(rpc.params.len(), 7);
+        let scrollopt = rpc.params[2].value.as_ref().unwrap();
+        assert_eq!(&scrollopt[..], &(0x1004i32).to_le_bytes());
+    }
+
+    #[test]
+    fn test_cursor_fetch_and_close_requests() {
+        let fetch = RpcRequest::cursor_fetch(180_150_003, 0x0002, 0, 100);
+        assert_eq!(fetch.proc_id, Some(ProcId::CursorFetch));
+        assert_eq!(fetch.params.len(), 4);
+
+        let close = RpcRequest::cursor_close(180_150_003);
+        assert_eq!(close.proc_id, Some(ProcId::CursorClose));
+        assert_eq!(close.params.len(), 1);
+    }
 }
@@ -60,6 +60,20 @@ pub fn encode_sql_batch(sql: &str) -> Bytes {
 /// ```
 #[must_use]
 pub fn encode_sql_batch_with_transaction(sql: &str, transaction_descriptor: u64) -> Bytes {
+    encode_sql_batch_with_enclave_package(sql, transaction_descriptor, None)
+}
+
+/// Encode a SQL batch request with a transaction descriptor and enclave package.
+///
+/// Once COLUMNENCRYPTION version 2 or later is negotiated, the enclave
+/// package follows ALL_HEADERS, empty when no keys are sent to the enclave.
+/// Pass `None` on connections without enclave support to omit the field.
+#[must_use]
+pub fn encode_sql_batch_with_enclave_package(
+    sql: &str,
+    transaction_descriptor: u64,
+    enclave_package: Option<&[u8]>,
+) -> Bytes {
     // Capacity: ALL_HEADERS (22 bytes) + SQL UTF-16LE (sql.len() * 2)
     let mut buf = BytesMut::with_capacity(22 + sql.len() * 2);
 
@@ -80,6 +94,8 @@ pub fn encode_sql_batch_with_transaction(sql: &str, transaction_descriptor: u64)
     let len_bytes = (all_headers_len as u32).to_le_bytes();
     buf[all_headers_start..all_headers_start + 4].copy_from_slice(&len_bytes);
 
+    crate::rpc::encode_enclave_package(&mut buf, enclave_package);
+
     // SQL text as UTF-16LE
     write_utf16_string(&mut buf, sql);
 
@@ -146,6 +162,14 @@ mod tests {
         assert_eq!(payload[25], 0);
     }
 
+    #[test]
+    fn test_encode_sql_batch_with_enclave_package() {
+        let payload = encode_sql_batch_with_enclave_package("SELECT 1", 0, Some(&[]));
+        assert_eq!(payload.len(), 40);
+        assert_eq!(&payload[22..24], &[0, 0]);
+        assert_eq!(payload[24], b'S');
+    }
+
     #[test]
     fn test_sql_batch_builder() {
         let batch = SqlBatch::new("SELECT @@VERSION");
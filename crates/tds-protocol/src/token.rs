@@ -30,9 +30,11 @@
 use bytes::{Buf, BufMut, Bytes};
 
 use crate::codec::{read_b_varchar, read_us_varchar};
+use crate::crypto::{CekTable, CryptoMetadata, EncryptionTypeWire, is_column_encrypted};
 use crate::error::ProtocolError;
 use crate::prelude::*;
 use crate::types::TypeId;
+use crate::version::TdsVersion;
 
 /// Token type identifier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -141,6 +143,10 @@ pub enum Token {
     EnvChange(EnvChange),
     /// Column ordering information.
     Order(Order),
+    /// Base tables of a browse-mode result set.
+    TabName(TabName),
+    /// Base table and column of each column in a browse-mode result set.
+    ColInfo(ColInfo),
     /// Feature extension acknowledgment.
     FeatureExtAck(FeatureExtAck),
     /// SSPI authentication data.
@@ -156,6 +162,10 @@ pub enum Token {
 pub struct ColMetaData {
     /// Column definitions.
     pub columns: Vec<ColumnData>,
+    /// Column encryption keys for the encrypted columns.
+    ///
+    /// Present only when column encryption has been negotiated.
+    pub cek_table: Option<CekTable>,
 }
 
 /// Column definition within metadata.
@@ -173,6 +183,84 @@ pub struct ColumnData {
     pub user_type: u32,
     /// Type-specific metadata.
     pub type_info: TypeInfo,
+    /// Always Encrypted metadata, if the column is encrypted.
+    pub encryption: Option<EncryptedColumn>,
+}
+
+impl ColumnData {
+    /// Check if string comparisons on the column are case-sensitive.
+    #[must_use]
+    pub fn is_case_sensitive(&self) -> bool {
+        self.flags & 0x0002 != 0
+    }
+
+    /// Check whether the column can be updated through the result set.
+    ///
+    /// Returns `None` when the server does not know.
+    #[must_use]
+    pub fn is_updatable(&self) -> Option<bool> {
+        match (self.flags >> 2) & 0x03 {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Check if the column is an identity column.
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        self.flags & 0x0010 != 0
+    }
+
+    /// Check if the column is computed.
+    #[must_use]
+    pub fn is_computed(&self) -> bool {
+        self.flags & 0x0020 != 0
+    }
+
+    /// Check if the column holds Always Encrypted ciphertext.
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Get the column as its values read once decrypted.
+    ///
+    /// For an encrypted column the type is replaced by the plaintext base
+    /// type; other columns are returned unchanged.
+    #[must_use]
+    pub fn plaintext_column(&self) -> ColumnData {
+        match &self.encryption {
+            Some(encryption) => ColumnData {
+                name: self.name.clone(),
+                type_id: encryption.base_type_id,
+                col_type: encryption.base_col_type,
+                flags: self.flags,
+                user_type: encryption.base_user_type,
+                type_info: encryption.base_type_info.clone(),
+                encryption: None,
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+/// Always Encrypted metadata of an encrypted column (CryptoMetaData).
+///
+/// Values of an encrypted column are sent as `varbinary` ciphertext; the
+/// base type describes the plaintext they decrypt to.
+#[derive(Debug, Clone)]
+pub struct EncryptedColumn {
+    /// CEK ordinal, algorithm, and encryption type.
+    pub crypto: CryptoMetadata,
+    /// User type of the plaintext.
+    pub base_user_type: u32,
+    /// Plaintext data type ID.
+    pub base_type_id: TypeId,
+    /// Plaintext data type raw byte.
+    pub base_col_type: u8,
+    /// Plaintext type-specific metadata.
+    pub base_type_info: TypeInfo,
 }
 
 /// Type-specific metadata.
@@ -366,6 +454,10 @@ pub struct ReturnValue {
     pub user_type: u32,
     /// Type flags.
     pub flags: u16,
+    /// Data type ID.
+    pub type_id: TypeId,
+    /// Data type raw byte (for unknown types).
+    pub col_type: u8,
     /// Type info.
     pub type_info: TypeInfo,
     /// Value data.
@@ -501,6 +593,66 @@ pub struct Order {
     pub columns: Vec<u16>,
 }
 
+/// Base tables of a browse-mode result set (TABNAME token).
+///
+/// Sent after column metadata for `FOR BROWSE` queries and when
+/// `SET NO_BROWSETABLE ON` is active.
+#[derive(Debug, Clone, Default)]
+pub struct TabName {
+    /// Table names, each split into its parts (e.g. `["dbo", "Orders"]`).
+    pub tables: Vec<Vec<String>>,
+}
+
+/// Browse-mode column information (COLINFO token).
+#[derive(Debug, Clone, Default)]
+pub struct ColInfo {
+    /// Properties of each result set column.
+    pub columns: Vec<ColumnProperty>,
+}
+
+/// Base table and column of one result set column.
+#[derive(Debug, Clone)]
+pub struct ColumnProperty {
+    /// Result set column number (1-based).
+    pub column: u8,
+    /// Index of the base table in the [`TabName`] token (1-based), or 0 for
+    /// expressions.
+    pub table: u8,
+    /// Status bits.
+    pub status: u8,
+    /// Name of the base column when it differs from the result column name.
+    pub base_name: Option<String>,
+}
+
+impl ColumnProperty {
+    /// Status bit: the column is an expression.
+    pub const EXPRESSION: u8 = 0x04;
+    /// Status bit: the column is part of the base table's key.
+    pub const KEY: u8 = 0x08;
+    /// Status bit: the column was added by the server to identify rows.
+    pub const HIDDEN: u8 = 0x10;
+    /// Status bit: the base column name differs from the result column name.
+    pub const DIFFERENT_NAME: u8 = 0x20;
+
+    /// Check if the column is an expression rather than a table column.
+    #[must_use]
+    pub fn is_expression(&self) -> bool {
+        self.status & Self::EXPRESSION != 0
+    }
+
+    /// Check if the column is part of the base table's key.
+    #[must_use]
+    pub fn is_key(&self) -> bool {
+        self.status & Self::KEY != 0
+    }
+
+    /// Check if the column was added by the server and not selected.
+    #[must_use]
+    pub fn is_hidden(&self) -> bool {
+        self.status & Self::HIDDEN != 0
+    }
+}
+
 /// Feature extension acknowledgment.
 #[derive(Debug, Clone)]
 pub struct FeatureExtAck {
@@ -517,6 +669,15 @@ pub struct FeatureAck {
     pub data: bytes::Bytes,
 }
 
+/// Server acknowledgment of the COLUMNENCRYPTION feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnEncryptionAck {
+    /// Column encryption version supported by the server.
+    pub version: u8,
+    /// Secure enclave type (e.g. `VBS` or `SGX`), sent with version 2 and later.
+    pub enclave_type: Option<String>,
+}
+
 /// SSPI authentication token.
 #[derive(Debug, Clone)]
 pub struct SspiToken {
@@ -550,6 +711,17 @@ impl ColMetaData {
 
     /// Decode a COLMETADATA token from bytes.
     pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        Self::decode_with_encryption(src, false)
+    }
+
+    /// Decode a COLMETADATA token, optionally in its column encryption form.
+    ///
+    /// Once column encryption is negotiated, the token carries a CEK table
+    /// after the column count and crypto metadata for each encrypted column.
+    pub fn decode_with_encryption(
+        src: &mut impl Buf,
+        column_encryption: bool,
+    ) -> Result<Self, ProtocolError> {
         if src.remaining() < 2 {
             return Err(ProtocolError::UnexpectedEof);
         }
@@ -560,21 +732,89 @@ impl ColMetaData {
         if column_count == Self::NO_METADATA {
             return Ok(Self {
                 columns: Vec::new(),
+                cek_table: None,
             });
         }
 
+        let cek_table = if column_encryption {
+            Some(CekTable::decode(src)?)
+        } else {
+            None
+        };
+
         let mut columns = Vec::with_capacity(column_count as usize);
 
         for _ in 0..column_count {
-            let column = Self::decode_column(src)?;
+            let column = Self::decode_column(src, column_encryption)?;
             columns.push(column);
         }
 
-        Ok(Self { columns })
+        Ok(Self { columns, cek_table })
+    }
+
+    /// Decode the crypto metadata of an encrypted column.
+    ///
+    /// ```text
+    /// CryptoMetaData:
+    ///   ordinal: USHORT
+    ///   user_type: ULONG
+    ///   base_type_info: TYPE_INFO
+    ///   algorithm_id: BYTE
+    ///   algorithm_name: B_VARCHAR (only for custom algorithms, id 0)
+    ///   encryption_type: BYTE
+    ///   normalization_version: BYTE
+    /// ```
+    fn decode_encrypted_column(src: &mut impl Buf) -> Result<EncryptedColumn, ProtocolError> {
+        // Ordinal (2 bytes) + UserType (4 bytes) + TypeId (1 byte)
+        if src.remaining() < 7 {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+
+        let cek_table_ordinal = src.get_u16_le();
+        let base_user_type = src.get_u32_le();
+        let base_col_type = src.get_u8();
+        let base_type_id = TypeId::from_u8(base_col_type).unwrap_or(TypeId::Null);
+        let base_type_info = Self::decode_type_info(src, base_type_id, base_col_type)?;
+
+        if src.remaining() < 1 {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        let algorithm_id = src.get_u8();
+        if algorithm_id == 0 {
+            let _ = read_b_varchar(src).ok_or(ProtocolError::UnexpectedEof)?;
+        }
+
+        if src.remaining() < 2 {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        let encryption_type_byte = src.get_u8();
+        let normalization_version = src.get_u8();
+        let encryption_type = EncryptionTypeWire::from_u8(encryption_type_byte).ok_or(
+            ProtocolError::InvalidField {
+                field: "encryption_type",
+                value: encryption_type_byte as u32,
+            },
+        )?;
+
+        Ok(EncryptedColumn {
+            crypto: CryptoMetadata {
+                cek_table_ordinal,
+                algorithm_id,
+                encryption_type,
+                normalization_version,
+            },
+            base_user_type,
+            base_type_id,
+            base_col_type,
+            base_type_info,
+        })
     }
 
     /// Decode a single column from the metadata.
-    fn decode_column(src: &mut impl Buf) -> Result<ColumnData, ProtocolError> {
+    fn decode_column(
+        src: &mut impl Buf,
+        column_encryption: bool,
+    ) -> Result<ColumnData, ProtocolError> {
         // UserType (4 bytes) + Flags (2 bytes) + TypeId (1 byte)
         if src.remaining() < 7 {
             return Err(ProtocolError::UnexpectedEof);
@@ -589,6 +829,13 @@ impl ColMetaData {
         // Parse type-specific metadata
         let type_info = Self::decode_type_info(src, type_id, col_type)?;
 
+        // Crypto metadata precedes the name of encrypted columns
+        let encryption = if column_encryption && is_column_encrypted(flags) {
+            Some(Self::decode_encrypted_column(src)?)
+        } else {
+            None
+        };
+
         // Read column name (B_VARCHAR format - 1 byte length in characters)
         let name = read_b_varchar(src).ok_or(ProtocolError::UnexpectedEof)?;
 
@@ -599,6 +846,7 @@ impl ColMetaData {
             flags,
             user_type,
             type_info,
+            encryption,
         })
     }
 
@@ -889,10 +1137,20 @@ impl RawRow {
     /// This function requires the column metadata to know how to parse the row.
     /// The row data is stored as raw bytes for later parsing.
     pub fn decode(src: &mut impl Buf, metadata: &ColMetaData) -> Result<Self, ProtocolError> {
+        Self::decode_with_max_lob_size(src, metadata, None)
+    }
+
+    /// Decode a ROW token, failing with [`ProtocolError::LobTooLarge`] if a
+    /// large value is longer than `max_lob_size` bytes.
+    pub fn decode_with_max_lob_size(
+        src: &mut impl Buf,
+        metadata: &ColMetaData,
+        max_lob_size: Option<u64>,
+    ) -> Result<Self, ProtocolError> {
         let mut data = bytes::BytesMut::new();
 
         for col in &metadata.columns {
-            Self::decode_column_value(src, col, &mut data)?;
+            Self::decode_column_value(src, col, &mut data, max_lob_size)?;
         }
 
         Ok(Self {
@@ -905,6 +1163,7 @@ impl RawRow {
         src: &mut impl Buf,
         col: &ColumnData,
         dst: &mut bytes::BytesMut,
+        max_lob_size: Option<u64>,
     ) -> Result<(), ProtocolError> {
         match col.type_id {
             // Fixed-length types
@@ -1004,7 +1263,7 @@ impl RawRow {
             TypeId::BigVarChar | TypeId::BigVarBinary => {
                 // max_length == 0xFFFF indicates VARCHAR(MAX) or VARBINARY(MAX), which uses PLP
                 if col.type_info.max_length == Some(0xFFFF) {
-                    Self::decode_plp_type(src, dst)?;
+                    Self::decode_plp_type(src, dst, max_lob_size)?;
                 } else {
                     Self::decode_ushortlen_type(src, dst)?;
                 }
@@ -1019,7 +1278,7 @@ impl RawRow {
             TypeId::NVarChar => {
                 // max_length == 0xFFFF indicates NVARCHAR(MAX), which uses PLP
                 if col.type_info.max_length == Some(0xFFFF) {
-                    Self::decode_plp_type(src, dst)?;
+                    Self::decode_plp_type(src, dst, max_lob_size)?;
                 } else {
                     Self::decode_ushortlen_type(src, dst)?;
                 }
@@ -1037,12 +1296,12 @@ impl RawRow {
 
             // TEXT/NTEXT/IMAGE - deprecated LOB types using textptr format
             TypeId::Text | TypeId::NText | TypeId::Image => {
-                Self::decode_textptr_type(src, dst)?;
+                Self::decode_textptr_type(src, dst, max_lob_size)?;
             }
 
             // XML - uses actual PLP format
             TypeId::Xml => {
-                Self::decode_plp_type(src, dst)?;
+                Self::decode_plp_type(src, dst, max_lob_size)?;
             }
 
             // Complex types
@@ -1052,7 +1311,7 @@ impl RawRow {
 
             TypeId::Udt => {
                 // UDT uses PLP encoding
-                Self::decode_plp_type(src, dst)?;
+                Self::decode_plp_type(src, dst, max_lob_size)?;
             }
 
             TypeId::Tvp => {
@@ -1162,6 +1421,7 @@ impl RawRow {
     fn decode_textptr_type(
         src: &mut impl Buf,
         dst: &mut bytes::BytesMut,
+        max_lob_size: Option<u64>,
     ) -> Result<(), ProtocolError> {
         if src.remaining() < 1 {
             return Err(ProtocolError::UnexpectedEof);
@@ -1192,6 +1452,7 @@ impl RawRow {
             return Err(ProtocolError::UnexpectedEof);
         }
         let data_len = src.get_u32_le() as usize;
+        check_lob_size(data_len as u64, max_lob_size)?;
 
         if src.remaining() < data_len {
             return Err(ProtocolError::UnexpectedEof);
@@ -1217,7 +1478,11 @@ impl RawRow {
     /// PLP format:
     /// - 8 bytes: total length (0xFFFFFFFFFFFFFFFE = unknown, 0xFFFFFFFFFFFFFFFF = NULL)
     /// - If not NULL: chunks of (4 byte chunk length + data) until chunk length = 0
-    fn decode_plp_type(src: &mut impl Buf, dst: &mut bytes::BytesMut) -> Result<(), ProtocolError> {
+    fn decode_plp_type(
+        src: &mut impl Buf,
+        dst: &mut bytes::BytesMut,
+        max_lob_size: Option<u64>,
+    ) -> Result<(), ProtocolError> {
         if src.remaining() < 8 {
             return Err(ProtocolError::UnexpectedEof);
         }
@@ -1231,8 +1496,12 @@ impl RawRow {
             // NULL value - no more data
             return Ok(());
         }
+        if total_len != 0xFFFFFFFFFFFFFFFE {
+            check_lob_size(total_len, max_lob_size)?;
+        }
 
         // Read chunks until terminator
+        let mut read_len = 0u64;
         loop {
             if src.remaining() < 4 {
                 return Err(ProtocolError::UnexpectedEof);
@@ -1245,6 +1514,10 @@ impl RawRow {
                 break;
             }
 
+            // The total length may be unknown, so check the chunks too
+            read_len += chunk_len as u64;
+            check_lob_size(read_len, max_lob_size)?;
+
             if src.remaining() < chunk_len {
                 return Err(ProtocolError::UnexpectedEof);
             }
@@ -1258,6 +1531,14 @@ impl RawRow {
     }
 }
 
+/// Fail if a large value of `length` bytes exceeds `max`.
+fn check_lob_size(length: u64, max: Option<u64>) -> Result<(), ProtocolError> {
+    match max {
+        Some(max) if length > max => Err(ProtocolError::LobTooLarge { length, max }),
+        _ => Ok(()),
+    }
+}
+
 // =============================================================================
 // NbcRow Parsing Implementation
 // =============================================================================
@@ -1268,6 +1549,16 @@ impl NbcRow {
     /// NBCROW (Null Bitmap Compressed Row) stores a bitmap indicating which
     /// columns are NULL, followed by only the non-NULL values.
     pub fn decode(src: &mut impl Buf, metadata: &ColMetaData) -> Result<Self, ProtocolError> {
+        Self::decode_with_max_lob_size(src, metadata, None)
+    }
+
+    /// Decode an NBCROW token, failing with [`ProtocolError::LobTooLarge`]
+    /// if a large value is longer than `max_lob_size` bytes.
+    pub fn decode_with_max_lob_size(
+        src: &mut impl Buf,
+        metadata: &ColMetaData,
+        max_lob_size: Option<u64>,
+    ) -> Result<Self, ProtocolError> {
         let col_count = metadata.columns.len();
         let bitmap_len = (col_count + 7) / 8;
 
@@ -1292,7 +1583,7 @@ impl NbcRow {
             if !is_null {
                 // Read the value - for NBCROW, we read without the length prefix
                 // for fixed-length types, and with length prefix for variable types
-                RawRow::decode_column_value(src, col, &mut data)?;
+                RawRow::decode_column_value(src, col, &mut data, max_lob_size)?;
             }
         }
 
@@ -1367,9 +1658,10 @@ impl ReturnValue {
             flags,
             user_type,
             type_info: type_info.clone(),
+            encryption: None,
         };
 
-        RawRow::decode_column_value(src, &temp_col, &mut value_buf)?;
+        RawRow::decode_column_value(src, &temp_col, &mut value_buf, None)?;
 
         Ok(Self {
             param_ordinal,
@@ -1377,6 +1669,8 @@ impl ReturnValue {
             status,
             user_type,
             flags,
+            type_id,
+            col_type,
             type_info,
             value: value_buf.freeze(),
         })
@@ -1465,23 +1759,51 @@ impl DoneStatus {
     }
 }
 
+/// Decode the status, current command and row count shared by the DONE tokens.
+fn decode_done_fields(
+    src: &mut impl Buf,
+    version: TdsVersion,
+) -> Result<(DoneStatus, u16, u64), ProtocolError> {
+    let size = if version.has_64bit_row_counts() {
+        Done::SIZE
+    } else {
+        Done::LEGACY_SIZE
+    };
+    if src.remaining() < size {
+        return Err(ProtocolError::IncompletePacket {
+            expected: size,
+            actual: src.remaining(),
+        });
+    }
+
+    let status = DoneStatus::from_bits(src.get_u16_le());
+    let cur_cmd = src.get_u16_le();
+    let row_count = if version.has_64bit_row_counts() {
+        src.get_u64_le()
+    } else {
+        u64::from(src.get_u32_le())
+    };
+    Ok((status, cur_cmd, row_count))
+}
+
 impl Done {
     /// Size of the DONE token in bytes (excluding token type byte).
     pub const SIZE: usize = 12; // 2 (status) + 2 (curcmd) + 8 (rowcount)
 
-    /// Decode a DONE token from bytes.
-    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
-        if src.remaining() < Self::SIZE {
-            return Err(ProtocolError::IncompletePacket {
-                expected: Self::SIZE,
-                actual: src.remaining(),
-            });
-        }
+    /// Size of the DONE token before TDS 7.2, with a 4-byte row count.
+    pub const LEGACY_SIZE: usize = 8;
 
-        let status = DoneStatus::from_bits(src.get_u16_le());
-        let cur_cmd = src.get_u16_le();
-        let row_count = src.get_u64_le();
+    /// Decode a DONE token from bytes, with the 8-byte row count of TDS 7.2+.
+    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        Self::decode_for_version(src, TdsVersion::V7_4)
+    }
 
+    /// Decode a DONE token from bytes using the row count width of `version`.
+    pub fn decode_for_version(
+        src: &mut impl Buf,
+        version: TdsVersion,
+    ) -> Result<Self, ProtocolError> {
+        let (status, cur_cmd, row_count) = decode_done_fields(src, version)?;
         Ok(Self {
             status,
             cur_cmd,
@@ -1520,19 +1842,20 @@ impl DoneProc {
     /// Size of the DONEPROC token in bytes (excluding token type byte).
     pub const SIZE: usize = 12;
 
-    /// Decode a DONEPROC token from bytes.
-    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
-        if src.remaining() < Self::SIZE {
-            return Err(ProtocolError::IncompletePacket {
-                expected: Self::SIZE,
-                actual: src.remaining(),
-            });
-        }
+    /// Size of the DONEPROC token before TDS 7.2, with a 4-byte row count.
+    pub const LEGACY_SIZE: usize = 8;
 
-        let status = DoneStatus::from_bits(src.get_u16_le());
-        let cur_cmd = src.get_u16_le();
-        let row_count = src.get_u64_le();
+    /// Decode a DONEPROC token from bytes, with the 8-byte row count of TDS 7.2+.
+    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        Self::decode_for_version(src, TdsVersion::V7_4)
+    }
 
+    /// Decode a DONEPROC token from bytes using the row count width of `version`.
+    pub fn decode_for_version(
+        src: &mut impl Buf,
+        version: TdsVersion,
+    ) -> Result<Self, ProtocolError> {
+        let (status, cur_cmd, row_count) = decode_done_fields(src, version)?;
         Ok(Self {
             status,
             cur_cmd,
@@ -1553,19 +1876,20 @@ impl DoneInProc {
     /// Size of the DONEINPROC token in bytes (excluding token type byte).
     pub const SIZE: usize = 12;
 
-    /// Decode a DONEINPROC token from bytes.
-    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
-        if src.remaining() < Self::SIZE {
-            return Err(ProtocolError::IncompletePacket {
-                expected: Self::SIZE,
-                actual: src.remaining(),
-            });
-        }
+    /// Size of the DONEINPROC token before TDS 7.2, with a 4-byte row count.
+    pub const LEGACY_SIZE: usize = 8;
 
-        let status = DoneStatus::from_bits(src.get_u16_le());
-        let cur_cmd = src.get_u16_le();
-        let row_count = src.get_u64_le();
+    /// Decode a DONEINPROC token from bytes, with the 8-byte row count of TDS 7.2+.
+    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        Self::decode_for_version(src, TdsVersion::V7_4)
+    }
 
+    /// Decode a DONEINPROC token from bytes using the row count width of `version`.
+    pub fn decode_for_version(
+        src: &mut impl Buf,
+        version: TdsVersion,
+    ) -> Result<Self, ProtocolError> {
+        let (status, cur_cmd, row_count) = decode_done_fields(src, version)?;
         Ok(Self {
             status,
             cur_cmd,
@@ -1689,7 +2013,8 @@ impl LoginAck {
         }
 
         let interface = src.get_u8();
-        let tds_version = src.get_u32_le();
+        // Unlike LOGIN7, LOGINACK sends the TDS version big-endian
+        let tds_version = src.get_u32();
         let prog_name = read_b_varchar(src).ok_or(ProtocolError::UnexpectedEof)?;
 
         if src.remaining() < 4 {
@@ -1755,13 +2080,21 @@ impl EnvChange {
             });
         }
 
+        // Decode from the token's own bytes so a value shorter or longer than
+        // expected cannot desynchronize the rest of the token stream
+        let mut body = src.copy_to_bytes(length);
+        let src = &mut body;
+
+        if !src.has_remaining() {
+            return Err(ProtocolError::UnexpectedEof);
+        }
         let env_type_byte = src.get_u8();
         let env_type = EnvChangeType::from_u8(env_type_byte)
             .ok_or(ProtocolError::InvalidTokenType(env_type_byte))?;
 
         let (new_value, old_value) = match env_type {
             EnvChangeType::Routing => {
-                // Routing has special format
+                // Routing has special format; the old value is always empty
                 let new_value = Self::decode_routing_value(src)?;
                 let old_value = EnvChangeValue::Binary(Bytes::new());
                 (new_value, old_value)
@@ -1811,19 +2144,29 @@ impl EnvChange {
         })
     }
 
+    /// Decode the ROUTING data of a routing ENVCHANGE.
+    ///
+    /// Format: length (2) + protocol (1) + port (2) + server_len (2) + server (UTF-16LE),
+    /// where protocol 0 is TCP, the only protocol defined by MS-TDS.
     fn decode_routing_value(src: &mut impl Buf) -> Result<EnvChangeValue, ProtocolError> {
-        // Routing format: length (2) + protocol (1) + port (2) + server_len (2) + server (utf16)
         if src.remaining() < 2 {
             return Err(ProtocolError::UnexpectedEof);
         }
 
-        let _routing_len = src.get_u16_le();
-
-        if src.remaining() < 5 {
+        let routing_len = src.get_u16_le() as usize;
+        if routing_len < 5 || src.remaining() < routing_len {
             return Err(ProtocolError::UnexpectedEof);
         }
+        let mut value = src.copy_to_bytes(routing_len);
+        let src = &mut value;
 
-        let _protocol = src.get_u8();
+        let protocol = src.get_u8();
+        if protocol != 0 {
+            return Err(ProtocolError::InvalidField {
+                field: "routing protocol",
+                value: u32::from(protocol),
+            });
+        }
         let port = src.get_u16_le();
         let server_len = src.get_u16_le() as usize;
 
@@ -1903,6 +2246,66 @@ impl Order {
     }
 }
 
+/// Read the 2-byte length of a token and take its data.
+fn token_data(src: &mut impl Buf) -> Result<Bytes, ProtocolError> {
+    if src.remaining() < 2 {
+        return Err(ProtocolError::UnexpectedEof);
+    }
+    let length = src.get_u16_le() as usize;
+    if src.remaining() < length {
+        return Err(ProtocolError::IncompletePacket {
+            expected: length,
+            actual: src.remaining(),
+        });
+    }
+    Ok(src.copy_to_bytes(length))
+}
+
+impl TabName {
+    /// Decode a TABNAME token from bytes.
+    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        let mut data = token_data(src)?;
+        let mut tables = Vec::new();
+        while data.has_remaining() {
+            let parts = data.get_u8();
+            let mut name = Vec::with_capacity(parts as usize);
+            for _ in 0..parts {
+                name.push(read_us_varchar(&mut data).ok_or(ProtocolError::UnexpectedEof)?);
+            }
+            tables.push(name);
+        }
+        Ok(Self { tables })
+    }
+}
+
+impl ColInfo {
+    /// Decode a COLINFO token from bytes.
+    pub fn decode(src: &mut impl Buf) -> Result<Self, ProtocolError> {
+        let mut data = token_data(src)?;
+        let mut columns = Vec::new();
+        while data.has_remaining() {
+            if data.remaining() < 3 {
+                return Err(ProtocolError::UnexpectedEof);
+            }
+            let column = data.get_u8();
+            let table = data.get_u8();
+            let status = data.get_u8();
+            let base_name = if status & ColumnProperty::DIFFERENT_NAME != 0 {
+                Some(read_b_varchar(&mut data).ok_or(ProtocolError::UnexpectedEof)?)
+            } else {
+                None
+            };
+            columns.push(ColumnProperty {
+                column,
+                table,
+                status,
+                base_name,
+            });
+        }
+        Ok(Self { columns })
+    }
+}
+
 impl FeatureExtAck {
     /// Feature terminator byte.
     pub const TERMINATOR: u8 = 0xFF;
@@ -1940,6 +2343,72 @@ impl FeatureExtAck {
 
         Ok(Self { features })
     }
+
+    /// Get the COLUMNENCRYPTION acknowledgment, if the server sent one.
+    ///
+    /// ```text
+    /// COLUMNENCRYPTION ack data:
+    ///   version: BYTE (1 to 3)
+    ///   enclave_type: B_VARCHAR (optional, version 2 and later)
+    /// ```
+    pub fn column_encryption(&self) -> Result<Option<ColumnEncryptionAck>, ProtocolError> {
+        let Some(feature) = self
+            .features
+            .iter()
+            .find(|f| f.feature_id == crate::login7::FeatureId::ColumnEncryption as u8)
+        else {
+            return Ok(None);
+        };
+
+        let mut data = feature.data.as_ref();
+        if !data.has_remaining() {
+            return Err(ProtocolError::UnexpectedEof);
+        }
+        let version = data.get_u8();
+        if !(crate::login7::COLUMN_ENCRYPTION_VERSION_1
+            ..=crate::login7::COLUMN_ENCRYPTION_VERSION_3)
+            .contains(&version)
+        {
+            return Err(ProtocolError::InvalidField {
+                field: "column_encryption_version",
+                value: version as u32,
+            });
+        }
+
+        let enclave_type =
+            if version >= crate::login7::COLUMN_ENCRYPTION_VERSION_2 && data.has_remaining() {
+                Some(read_b_varchar(&mut data).ok_or(ProtocolError::UnexpectedEof)?)
+            } else {
+                None
+            };
+
+        Ok(Some(ColumnEncryptionAck {
+            version,
+            enclave_type,
+        }))
+    }
+
+    /// Check whether the server acknowledged AZURESQLSUPPORT.
+    #[must_use]
+    pub fn azure_sql_support(&self) -> bool {
+        self.flag_acknowledged(crate::login7::FeatureId::AzureSqlSupport)
+    }
+
+    /// Check whether the server allows its address to be cached
+    /// (AZURESQLDNSCACHING acknowledged with `IsSupported = 1`).
+    #[must_use]
+    pub fn azure_sql_dns_caching(&self) -> bool {
+        self.flag_acknowledged(crate::login7::FeatureId::AzureSqlDnsCaching)
+    }
+
+    /// Check for a feature acknowledged with a first data byte of 1.
+    fn flag_acknowledged(&self, feature: crate::login7::FeatureId) -> bool {
+        self.features
+            .iter()
+            .find(|f| f.feature_id == feature as u8)
+            .and_then(|f| f.data.first())
+            .is_some_and(|&flag| flag & 0x01 != 0)
+    }
 }
 
 impl SspiToken {
@@ -2059,13 +2528,51 @@ impl FedAuthInfo {
 pub struct TokenParser {
     data: Bytes,
     position: usize,
+    column_encryption: bool,
+    tds_version: TdsVersion,
+    max_lob_size: Option<u64>,
 }
 
 impl TokenParser {
     /// Create a new token parser from bytes.
     #[must_use]
     pub fn new(data: Bytes) -> Self {
-        Self { data, position: 0 }
+        Self {
+            data,
+            position: 0,
+            column_encryption: false,
+            tds_version: TdsVersion::V7_4,
+            max_lob_size: None,
+        }
+    }
+
+    /// Fail with [`ProtocolError::LobTooLarge`] on row values of
+    /// `(max)`, XML, UDT, `TEXT`, `NTEXT` or `IMAGE` columns longer than
+    /// `max` bytes instead of decoding them.
+    #[must_use]
+    pub fn with_max_lob_size(mut self, max: Option<u64>) -> Self {
+        self.max_lob_size = max;
+        self
+    }
+
+    /// Parse tokens as sent with the negotiated TDS version.
+    ///
+    /// Defaults to TDS 7.4. Servers negotiating a version before TDS 7.2
+    /// send 4-byte row counts in DONE tokens.
+    #[must_use]
+    pub fn with_tds_version(mut self, version: TdsVersion) -> Self {
+        self.tds_version = version;
+        self
+    }
+
+    /// Parse COLMETADATA in its column encryption form.
+    ///
+    /// Enable this once the server has acknowledged the COLUMNENCRYPTION
+    /// feature extension.
+    #[must_use]
+    pub fn with_column_encryption(mut self, enabled: bool) -> Self {
+        self.column_encryption = enabled;
+        self
     }
 
     /// Get remaining bytes in the buffer.
@@ -2123,15 +2630,15 @@ impl TokenParser {
 
         let token = match token_type {
             Some(TokenType::Done) => {
-                let done = Done::decode(&mut buf)?;
+                let done = Done::decode_for_version(&mut buf, self.tds_version)?;
                 Token::Done(done)
             }
             Some(TokenType::DoneProc) => {
-                let done = DoneProc::decode(&mut buf)?;
+                let done = DoneProc::decode_for_version(&mut buf, self.tds_version)?;
                 Token::DoneProc(done)
             }
             Some(TokenType::DoneInProc) => {
-                let done = DoneInProc::decode(&mut buf)?;
+                let done = DoneInProc::decode_for_version(&mut buf, self.tds_version)?;
                 Token::DoneInProc(done)
             }
             Some(TokenType::Error) => {
@@ -2174,7 +2681,8 @@ impl TokenParser {
                 Token::ReturnStatus(status)
             }
             Some(TokenType::ColMetaData) => {
-                let col_meta = ColMetaData::decode(&mut buf)?;
+                let col_meta =
+                    ColMetaData::decode_with_encryption(&mut buf, self.column_encryption)?;
                 Token::ColMetaData(col_meta)
             }
             Some(TokenType::Row) => {
@@ -2186,7 +2694,7 @@ impl TokenParser {
                         "Row token requires column metadata",
                     )
                 })?;
-                let row = RawRow::decode(&mut buf, meta)?;
+                let row = RawRow::decode_with_max_lob_size(&mut buf, meta, self.max_lob_size)?;
                 Token::Row(row)
             }
             Some(TokenType::NbcRow) => {
@@ -2198,7 +2706,7 @@ impl TokenParser {
                         "NbcRow token requires column metadata",
                     )
                 })?;
-                let row = NbcRow::decode(&mut buf, meta)?;
+                let row = NbcRow::decode_with_max_lob_size(&mut buf, meta, self.max_lob_size)?;
                 Token::NbcRow(row)
             }
             Some(TokenType::ReturnValue) => {
@@ -2209,9 +2717,17 @@ impl TokenParser {
                 let session = SessionState::decode(&mut buf)?;
                 Token::SessionState(session)
             }
-            Some(TokenType::ColInfo) | Some(TokenType::TabName) | Some(TokenType::Offset) => {
-                // These tokens are rarely used and have complex formats.
-                // Skip them by reading the length and advancing.
+            Some(TokenType::TabName) => {
+                let tab_name = TabName::decode(&mut buf)?;
+                Token::TabName(tab_name)
+            }
+            Some(TokenType::ColInfo) => {
+                let col_info = ColInfo::decode(&mut buf)?;
+                Token::ColInfo(col_info)
+            }
+            Some(TokenType::Offset) => {
+                // This token is rarely used; skip it by reading the length
+                // and advancing.
                 if buf.remaining() < 2 {
                     return Err(ProtocolError::UnexpectedEof);
                 }
@@ -2255,7 +2771,11 @@ impl TokenParser {
         let skip_amount = match token_type {
             // Fixed-size tokens
             Some(TokenType::Done) | Some(TokenType::DoneProc) | Some(TokenType::DoneInProc) => {
-                1 + Done::SIZE // token type + 12 bytes
+                if self.tds_version.has_64bit_row_counts() {
+                    1 + Done::SIZE // token type + 12 bytes
+                } else {
+                    1 + Done::LEGACY_SIZE // token type + 8 bytes
+                }
             }
             Some(TokenType::ReturnStatus) => {
                 1 + 4 // token type + 4 bytes
@@ -2366,6 +2886,89 @@ mod tests {
         assert_eq!(decoded.row_count, done.row_count);
     }
 
+    #[test]
+    fn test_done_captured_tds_7_4() {
+        // SELECT of 5 rows, then an UPDATE of 2^32 + 1 rows inside a procedure
+        let data = Bytes::from_static(&[
+            0xFF, 0x11, 0x00, 0xC1, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, // DONEINPROC
+            0xFD, 0x10, 0x00, 0xC5, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, // DONE
+        ]);
+        let mut parser = TokenParser::new(data).with_tds_version(TdsVersion::V7_4);
+
+        let Some(Token::DoneInProc(done)) = parser.next_token().unwrap() else {
+            unreachable!("expected DONEINPROC");
+        };
+        assert!(done.status.more && done.status.count);
+        assert_eq!(done.cur_cmd, 0xC1);
+        assert_eq!(done.row_count, 5);
+
+        let Some(Token::Done(done)) = parser.next_token().unwrap() else {
+            unreachable!("expected DONE");
+        };
+        assert!(!done.status.more && done.status.count);
+        assert_eq!(done.cur_cmd, 0xC5);
+        assert_eq!(done.row_count, 0x1_0000_0001);
+        assert!(parser.next_token().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_done_captured_tds_7_1() {
+        // The same response from a TDS 7.1 server, with 4-byte row counts
+        let data = Bytes::from_static(&[
+            0xFF, 0x11, 0x00, 0xC1, 0x00, 0x05, 0x00, 0x00, 0x00, // DONEINPROC
+            0xFE, 0x00, 0x00, 0xE0, 0x00, 0x00, 0x00, 0x00, 0x00, // DONEPROC
+            0xFD, 0x10, 0x00, 0xC5, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, // DONE
+        ]);
+        let mut parser = TokenParser::new(data).with_tds_version(TdsVersion::V7_1_REV1);
+
+        let Some(Token::DoneInProc(done)) = parser.next_token().unwrap() else {
+            unreachable!("expected DONEINPROC");
+        };
+        assert_eq!(done.row_count, 5);
+
+        // Skipping uses the legacy size too
+        parser.skip_token().unwrap();
+
+        let Some(Token::Done(done)) = parser.next_token().unwrap() else {
+            unreachable!("expected DONE");
+        };
+        assert_eq!(done.cur_cmd, 0xC5);
+        assert_eq!(done.row_count, u64::from(u32::MAX));
+        assert!(parser.next_token().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_done_decode_for_version_incomplete() {
+        let data = [0x10, 0x00, 0xC1, 0x00, 0x05, 0x00, 0x00, 0x00];
+
+        let mut cursor: &[u8] = &data;
+        assert!(matches!(
+            Done::decode(&mut cursor),
+            Err(ProtocolError::IncompletePacket { expected: 12, .. })
+        ));
+
+        let mut cursor: &[u8] = &data;
+        let done = Done::decode_for_version(&mut cursor, TdsVersion::V7_1).unwrap();
+        assert_eq!(done.row_count, 5);
+    }
+
+    #[test]
+    fn test_login_ack_tds_version_big_endian() {
+        let data = Bytes::from_static(&[
+            0x0E, 0x00, // length
+            0x01, // interface: SQL
+            0x74, 0x00, 0x00, 0x04, // TDS 7.4
+            0x02, b'S', 0x00, b'Q', 0x00, // "SQ"
+            0x10, 0x00, 0x00, 0x00, // program version
+        ]);
+        let mut cursor: &[u8] = &data;
+        let ack = LoginAck::decode(&mut cursor).unwrap();
+        assert_eq!(ack.tds_version(), TdsVersion::V7_4);
+        assert_eq!(ack.prog_name, "SQ");
+    }
+
     #[test]
     fn test_done_status_bits() {
         let status = DoneStatus {
@@ -2420,6 +3023,55 @@ mod tests {
         assert_eq!(EnvChangeType::from_u8(100), None);
     }
 
+    /// Encode a routing ENVCHANGE token body (without the token type byte).
+    fn routing_env_change(protocol: u8, port: u16, host: &str) -> BytesMut {
+        let host: Vec<u16> = host.encode_utf16().collect();
+        let routing_len = 5 + host.len() * 2;
+
+        let mut data = BytesMut::new();
+        data.put_u16_le((1 + 2 + routing_len + 2) as u16); // token length
+        data.put_u8(20); // Routing
+        data.put_u16_le(routing_len as u16);
+        data.put_u8(protocol);
+        data.put_u16_le(port);
+        data.put_u16_le(host.len() as u16);
+        for c in host {
+            data.put_u16_le(c);
+        }
+        data.put_u16_le(0); // old value
+        data
+    }
+
+    #[test]
+    fn test_env_change_routing() {
+        let mut data = routing_env_change(0, 11001, "srv.database.windows.net");
+        data.put_u8(0xFD); // next token
+        let mut cursor: &[u8] = &data;
+
+        let env = EnvChange::decode(&mut cursor).unwrap();
+        assert!(env.is_routing());
+        assert_eq!(
+            env.routing_info(),
+            Some(("srv.database.windows.net", 11001))
+        );
+        // The empty old value is consumed with the token
+        assert_eq!(cursor, &[0xFD]);
+    }
+
+    #[test]
+    fn test_env_change_routing_invalid() {
+        let data = routing_env_change(1, 11001, "srv");
+        let mut cursor: &[u8] = &data;
+        assert!(matches!(
+            EnvChange::decode(&mut cursor),
+            Err(ProtocolError::InvalidField { .. })
+        ));
+
+        let data = routing_env_change(0, 11001, "srv");
+        let mut cursor: &[u8] = &data[..data.len() - 4];
+        assert!(EnvChange::decode(&mut cursor).is_err());
+    }
+
     #[test]
     fn test_colmetadata_no_columns() {
         // No metadata marker (0xFFFF)
@@ -2477,6 +3129,109 @@ mod tests {
         assert!(meta.columns[0].type_info.collation.is_some());
     }
 
+    #[test]
+    fn test_feature_ext_ack_column_encryption() {
+        // COLUMNENCRYPTION v2 with enclave type "VBS", then terminator
+        let data = Bytes::from_static(&[
+            0x04, 0x08, 0x00, 0x00, 0x00, 0x02, 0x03, b'V', 0x00, b'B', 0x00, b'S', 0x00, 0xFF,
+        ]);
+        let mut cursor: &[u8] = &data;
+        let ack = FeatureExtAck::decode(&mut cursor).unwrap();
+        assert_eq!(
+            ack.column_encryption().unwrap(),
+            Some(ColumnEncryptionAck {
+                version: 2,
+                enclave_type: Some("VBS".into()),
+            })
+        );
+
+        let data = Bytes::from_static(&[0x04, 0x01, 0x00, 0x00, 0x00, 0x01, 0xFF]);
+        let mut cursor: &[u8] = &data;
+        let ack = FeatureExtAck::decode(&mut cursor).unwrap();
+        let ack = ack.column_encryption().unwrap().unwrap();
+        assert_eq!(ack.version, 1);
+        assert_eq!(ack.enclave_type, None);
+
+        let data = Bytes::from_static(&[0x04, 0x01, 0x00, 0x00, 0x00, 0x09, 0xFF]);
+        let mut cursor: &[u8] = &data;
+        let ack = FeatureExtAck::decode(&mut cursor).unwrap();
+        assert!(ack.column_encryption().is_err());
+
+        let ack = FeatureExtAck { features: vec![] };
+        assert_eq!(ack.column_encryption().unwrap(), None);
+    }
+
+    #[test]
+    fn test_feature_ext_ack_azure_sql() {
+        // AZURESQLSUPPORT ack, AZURESQLDNSCACHING IsSupported = 1, terminator
+        let data = Bytes::from_static(&[
+            0x08, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0B, 0x01, 0x00, 0x00, 0x00, 0x01, 0xFF,
+        ]);
+        let mut cursor: &[u8] = &data;
+        let ack = FeatureExtAck::decode(&mut cursor).unwrap();
+        assert!(ack.azure_sql_support());
+        assert!(ack.azure_sql_dns_caching());
+
+        // IsSupported = 0
+        let data = Bytes::from_static(&[0x0B, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFF]);
+        let mut cursor: &[u8] = &data;
+        let ack = FeatureExtAck::decode(&mut cursor).unwrap();
+        assert!(!ack.azure_sql_dns_caching());
+        assert!(!ack.azure_sql_support());
+    }
+
+    #[test]
+    fn test_colmetadata_encrypted_column() {
+        // COLMETADATA with a CEK table and 1 encrypted INT column
+        let mut data = BytesMut::new();
+        data.extend_from_slice(&[0x01, 0x00]); // 1 column
+        // CEK table: 1 entry
+        data.extend_from_slice(&[0x01, 0x00]);
+        data.extend_from_slice(&5u32.to_le_bytes()); // database_id
+        data.extend_from_slice(&7u32.to_le_bytes()); // cek_id
+        data.extend_from_slice(&1u32.to_le_bytes()); // cek_version
+        data.extend_from_slice(&9u64.to_le_bytes()); // cek_md_version
+        data.extend_from_slice(&[0x01]); // 1 value
+        data.extend_from_slice(&[0x02, 0x00, 0xAA, 0xBB]); // encrypted CEK
+        data.extend_from_slice(&[0x01, b'P', 0x00]); // key store name
+        data.extend_from_slice(&[0x01, 0x00, b'k', 0x00]); // CMK path
+        data.extend_from_slice(&[0x01, b'R', 0x00]); // algorithm
+        // Column: varbinary(8000) ciphertext, flags nullable | encrypted
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // user_type = 0
+        data.extend_from_slice(&[0x01, 0x08]); // flags
+        data.extend_from_slice(&[0xA5, 0x40, 0x1F]); // BigVarBinary(8000)
+        // CryptoMetaData: ordinal, user type, base TYPE_INFO, algorithm, type, normalization
+        data.extend_from_slice(&[0x00, 0x00]);
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&[0x26, 0x04]); // IntN(4)
+        data.extend_from_slice(&[0x02, 0x01, 0x01]);
+        // Column name "id"
+        data.extend_from_slice(&[0x02, b'i', 0x00, b'd', 0x00]);
+
+        let mut cursor: &[u8] = &data;
+        let meta = ColMetaData::decode_with_encryption(&mut cursor, true).unwrap();
+        assert!(cursor.is_empty());
+
+        let cek_table = meta.cek_table.as_ref().unwrap();
+        assert_eq!(cek_table.len(), 1);
+        assert_eq!(cek_table.get(0).unwrap().cek_md_version, 9);
+
+        let column = &meta.columns[0];
+        assert_eq!(column.name, "id");
+        assert_eq!(column.type_id, TypeId::BigVarBinary);
+        let encryption = column.encryption.as_ref().unwrap();
+        assert_eq!(
+            encryption.crypto.encryption_type,
+            EncryptionTypeWire::Deterministic
+        );
+        assert_eq!(encryption.base_type_id, TypeId::IntN);
+
+        let plaintext = column.plaintext_column();
+        assert_eq!(plaintext.type_id, TypeId::IntN);
+        assert_eq!(plaintext.type_info.max_length, Some(4));
+        assert!(!plaintext.is_encrypted());
+    }
+
     #[test]
     fn test_raw_row_decode_int() {
         // Create metadata for a single INT column
@@ -2488,7 +3243,9 @@ mod tests {
                 flags: 0,
                 user_type: 0,
                 type_info: TypeInfo::default(),
+                encryption: None,
             }],
+            cek_table: None,
         };
 
         // Row data: just 4 bytes for the int value 42
@@ -2515,7 +3272,9 @@ mod tests {
                     max_length: Some(4),
                     ..Default::default()
                 },
+                encryption: None,
             }],
+            cek_table: None,
         };
 
         // Row data with value: 1 byte length + 4 bytes value
@@ -2542,7 +3301,9 @@ mod tests {
                     max_length: Some(4),
                     ..Default::default()
                 },
+                encryption: None,
             }],
+            cek_table: None,
         };
 
         // NULL value: length = 0xFF (for bytelen types)
@@ -2554,6 +3315,56 @@ mod tests {
         assert_eq!(row.data[0], 0xFF); // NULL marker
     }
 
+    #[test]
+    fn test_row_max_lob_size() {
+        let metadata = ColMetaData {
+            columns: vec![ColumnData {
+                name: "doc".to_string(),
+                type_id: TypeId::BigVarBinary,
+                col_type: 0xA5,
+                flags: 0x01,
+                user_type: 0,
+                type_info: TypeInfo {
+                    max_length: Some(0xFFFF),
+                    ..Default::default()
+                },
+                encryption: None,
+            }],
+            cek_table: None,
+        };
+        let plp = |total_len: u64| {
+            let mut data = BytesMut::new();
+            data.put_u64_le(total_len);
+            for _ in 0..2 {
+                data.put_u32_le(3);
+                data.put_slice(&[1, 2, 3]);
+            }
+            data.put_u32_le(0);
+            data.freeze()
+        };
+
+        let data = plp(6);
+        let row = RawRow::decode_with_max_lob_size(&mut data.as_ref(), &metadata, Some(6));
+        assert!(row.is_ok());
+
+        let err =
+            RawRow::decode_with_max_lob_size(&mut data.as_ref(), &metadata, Some(5)).unwrap_err();
+        assert!(matches!(
+            err,
+            ProtocolError::LobTooLarge { length: 6, max: 5 }
+        ));
+
+        // Unknown total length: caught once the chunks add up past the limit
+        let data = plp(0xFFFFFFFFFFFFFFFE);
+        let err =
+            RawRow::decode_with_max_lob_size(&mut data.as_ref(), &metadata, Some(4)).unwrap_err();
+        assert!(matches!(
+            err,
+            ProtocolError::LobTooLarge { length: 6, max: 4 }
+        ));
+        assert!(RawRow::decode(&mut data.as_ref(), &metadata).is_ok());
+    }
+
     #[test]
     fn test_nbcrow_null_bitmap() {
         let row = NbcRow {
@@ -2603,7 +3414,9 @@ mod tests {
                 flags: 0,
                 user_type: 0,
                 type_info: TypeInfo::default(),
+                encryption: None,
             }],
+            cek_table: None,
         };
 
         // Build ROW token
@@ -2651,6 +3464,44 @@ mod tests {
         assert_eq!(parser.peek_token_type(), Some(TokenType::Done));
     }
 
+    #[test]
+    fn test_token_parser_tabname_colinfo() {
+        let data = Bytes::from_static(&[
+            0xA4, // TABNAME token type
+            0x0D, 0x00, // length
+            0x02, // two name parts
+            0x03, 0x00, b'd', 0, b'b', 0, b'o', 0, // "dbo"
+            0x01, 0x00, b'T', 0,    // "T"
+            0xA5, // COLINFO token type
+            0x0B, 0x00, // length
+            0x01, 0x01, 0x08, // column 1 of table 1: key
+            0x02, 0x01, 0x20, // column 2 of table 1: different name
+            0x02, b'i', 0, b'd', 0, // "id"
+        ]);
+
+        let mut parser = TokenParser::new(data);
+        match parser.next_token().unwrap().unwrap() {
+            Token::TabName(tab_name) => {
+                assert_eq!(
+                    tab_name.tables,
+                    vec![vec!["dbo".to_string(), "T".to_string()]]
+                );
+            }
+            _ => panic!("Expected TabName token"),
+        }
+        match parser.next_token().unwrap().unwrap() {
+            Token::ColInfo(col_info) => {
+                assert_eq!(col_info.columns.len(), 2);
+                assert!(col_info.columns[0].is_key());
+                assert_eq!(col_info.columns[0].base_name, None);
+                assert_eq!(col_info.columns[1].table, 1);
+                assert_eq!(col_info.columns[1].base_name.as_deref(), Some("id"));
+            }
+            _ => panic!("Expected ColInfo token"),
+        }
+        assert!(parser.next_token().unwrap().is_none());
+    }
+
     #[test]
     fn test_column_data_fixed_size() {
         let col = ColumnData {
@@ -2660,6 +3511,7 @@ mod tests {
             flags: 0,
             user_type: 0,
             type_info: TypeInfo::default(),
+            encryption: None,
         };
         assert_eq!(col.fixed_size(), Some(4));
 
@@ -2670,6 +3522,7 @@ mod tests {
             flags: 0,
             user_type: 0,
             type_info: TypeInfo::default(),
+            encryption: None,
         };
         assert_eq!(col2.fixed_size(), None);
     }
@@ -2717,6 +3570,7 @@ mod tests {
                         scale: None,
                         collation: None,
                     },
+                    encryption: None,
                 },
                 ColumnData {
                     name: "number".to_string(),
@@ -2730,8 +3584,10 @@ mod tests {
                         scale: None,
                         collation: None,
                     },
+                    encryption: None,
                 },
             ],
+            cek_table: None,
         };
 
         // Decode the wire data into stored format
@@ -2828,6 +3684,7 @@ mod tests {
                         scale: None,
                         collation: None,
                     },
+                    encryption: None,
                 },
                 ColumnData {
                     name: "num".to_string(),
@@ -2841,8 +3698,10 @@ mod tests {
                         scale: None,
                         collation: None,
                     },
+                    encryption: None,
                 },
             ],
+            cek_table: None,
         };
 
         // Decode wire data
@@ -8,13 +8,16 @@
 //! TVPs are encoded as type `0xF3` with this structure:
 //!
 //! ```text
-//! TVP_TYPE_INFO = TVPTYPE TVP_TYPENAME TVP_COLMETADATA TVP_END_TOKEN *TVP_ROW TVP_END_TOKEN
+//! TVP_TYPE_INFO = TVPTYPE TVP_TYPENAME TVP_COLMETADATA [TVP_ORDER_UNIQUE] [TVP_COLUMN_ORDERING]
+//!                 TVP_END_TOKEN *TVP_ROW TVP_END_TOKEN
 //!
 //! TVPTYPE = %xF3
 //! TVP_TYPENAME = DbName OwningSchema TypeName (all B_VARCHAR)
 //! TVP_COLMETADATA = TVP_NULL_TOKEN / (Count TvpColumnMetaData*)
 //! TVP_NULL_TOKEN = %xFFFF
 //! TvpColumnMetaData = UserType Flags TYPE_INFO ColName
+//! TVP_ORDER_UNIQUE = %x10 Count *(ColNum OrderUniqueFlags)
+//! TVP_COLUMN_ORDERING = %x11 Count *ColNum
 //! TVP_ROW = TVP_ROW_TOKEN AllColumnData
 //! TVP_ROW_TOKEN = %x01
 //! TVP_END_TOKEN = %x00
@@ -24,6 +27,8 @@
 //!
 //! - `DbName` MUST be a zero-length string (empty)
 //! - `ColName` MUST be a zero-length string in each column definition
+//! - `ColNum` in the optional metadata tokens is a 1-based column ordinal
+//! - Columns flagged as default are omitted from `AllColumnData` in each row
 //! - TVPs can only be used as input parameters (not output)
 //! - Requires TDS 7.3 or later
 //!
@@ -48,6 +53,21 @@ pub const TVP_ROW_TOKEN: u8 = 0x01;
 /// Token indicating no columns (NULL TVP metadata).
 pub const TVP_NULL_TOKEN: u16 = 0xFFFF;
 
+/// Token introducing the optional sort order and uniqueness metadata.
+pub const TVP_ORDER_UNIQUE_TOKEN: u8 = 0x10;
+
+/// Token introducing the optional column ordering metadata.
+pub const TVP_COLUMN_ORDERING_TOKEN: u8 = 0x11;
+
+/// `OrderUniqueFlags` bit: rows are sorted ascending on the column.
+pub const TVP_ORDER_ASC: u8 = 0x01;
+
+/// `OrderUniqueFlags` bit: rows are sorted descending on the column.
+pub const TVP_ORDER_DESC: u8 = 0x02;
+
+/// `OrderUniqueFlags` bit: the column is part of a unique key.
+pub const TVP_UNIQUE: u8 = 0x04;
+
 /// Default collation for string types in TVPs.
 ///
 /// This is Latin1_General_CI_AS equivalent.
@@ -183,6 +203,8 @@ impl TvpWireType {
 pub struct TvpColumnFlags {
     /// Column is nullable.
     pub nullable: bool,
+    /// Column takes its server-side default; no data is sent for it in rows.
+    pub default: bool,
 }
 
 impl TvpColumnFlags {
@@ -193,6 +215,9 @@ impl TvpColumnFlags {
         if self.nullable {
             flags |= 0x0001;
         }
+        if self.default {
+            flags |= 0x0200;
+        }
         flags
     }
 }
@@ -212,7 +237,10 @@ impl TvpColumnDef {
     pub const fn new(wire_type: TvpWireType) -> Self {
         Self {
             wire_type,
-            flags: TvpColumnFlags { nullable: false },
+            flags: TvpColumnFlags {
+                nullable: false,
+                default: false,
+            },
         }
     }
 
@@ -221,10 +249,29 @@ impl TvpColumnDef {
     pub const fn nullable(wire_type: TvpWireType) -> Self {
         Self {
             wire_type,
-            flags: TvpColumnFlags { nullable: true },
+            flags: TvpColumnFlags {
+                nullable: true,
+                default: false,
+            },
         }
     }
 
+    /// Mark the column as a default column.
+    ///
+    /// The server applies the column's default value, and row data for
+    /// the column must be omitted.
+    #[must_use]
+    pub const fn with_default(mut self) -> Self {
+        self.flags.default = true;
+        self
+    }
+
+    /// Check whether row data is omitted for this column.
+    #[must_use]
+    pub const fn is_default(&self) -> bool {
+        self.flags.default
+    }
+
     /// Encode the column metadata.
     ///
     /// Format: UserType (4) + Flags (2) + TYPE_INFO + ColName (B_VARCHAR, must be empty)
@@ -243,6 +290,30 @@ impl TvpColumnDef {
     }
 }
 
+/// Sort order and uniqueness of one TVP column (a TVP_ORDER_UNIQUE entry).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TvpOrderUnique {
+    /// Zero-based index of the column.
+    pub column: u16,
+    /// `OrderUniqueFlags` bits ([`TVP_ORDER_ASC`], [`TVP_ORDER_DESC`],
+    /// [`TVP_UNIQUE`]).
+    pub flags: u8,
+}
+
+impl TvpOrderUnique {
+    /// Create an entry for a column with the given flags.
+    #[must_use]
+    pub const fn new(column: u16, flags: u8) -> Self {
+        Self { column, flags }
+    }
+
+    /// Encode the entry: ColNum (USHORT, 1-based) + OrderUniqueFlags (BYTE).
+    pub fn encode(&self, buf: &mut BytesMut) {
+        buf.put_u16_le(self.column + 1);
+        buf.put_u8(self.flags);
+    }
+}
+
 /// TVP value encoder.
 ///
 /// This provides the complete TVP encoding logic for RPC parameters.
@@ -254,6 +325,10 @@ pub struct TvpEncoder<'a> {
     pub type_name: &'a str,
     /// Column definitions.
     pub columns: &'a [TvpColumnDef],
+    /// Sort order and uniqueness hints, most significant sort column first.
+    pub order_unique: &'a [TvpOrderUnique],
+    /// Zero-based column indexes for TVP_COLUMN_ORDERING.
+    pub column_ordering: &'a [u16],
 }
 
 impl<'a> TvpEncoder<'a> {
@@ -264,15 +339,35 @@ impl<'a> TvpEncoder<'a> {
             schema,
             type_name,
             columns,
+            order_unique: &[],
+            column_ordering: &[],
         }
     }
 
+    /// Send sort order and uniqueness hints in a TVP_ORDER_UNIQUE token.
+    ///
+    /// Rows must actually be sorted as declared; the server uses the hints
+    /// to skip sorting when inserting from the TVP.
+    #[must_use]
+    pub const fn with_order_unique(mut self, order_unique: &'a [TvpOrderUnique]) -> Self {
+        self.order_unique = order_unique;
+        self
+    }
+
+    /// Send a TVP_COLUMN_ORDERING token listing the given columns.
+    #[must_use]
+    pub const fn with_column_ordering(mut self, column_ordering: &'a [u16]) -> Self {
+        self.column_ordering = column_ordering;
+        self
+    }
+
     /// Encode the complete TVP type info and metadata.
     ///
     /// This encodes:
     /// - TVP type ID (0xF3)
     /// - TVP_TYPENAME (DbName, OwningSchema, TypeName)
     /// - TVP_COLMETADATA
+    /// - TVP_ORDER_UNIQUE and TVP_COLUMN_ORDERING, when set
     /// - TVP_END_TOKEN (marks end of column metadata)
     ///
     /// After calling this, use [`Self::encode_row`] for each row, then
@@ -313,8 +408,21 @@ impl<'a> TvpEncoder<'a> {
             }
         }
 
-        // Optional: TVP_ORDER_UNIQUE and TVP_COLUMN_ORDERING could go here
-        // We don't use them for now
+        // Optional metadata tokens
+        if !self.order_unique.is_empty() {
+            buf.put_u8(TVP_ORDER_UNIQUE_TOKEN);
+            buf.put_u16_le(self.order_unique.len() as u16);
+            for entry in self.order_unique {
+                entry.encode(buf);
+            }
+        }
+        if !self.column_ordering.is_empty() {
+            buf.put_u8(TVP_COLUMN_ORDERING_TOKEN);
+            buf.put_u16_le(self.column_ordering.len() as u16);
+            for column in self.column_ordering {
+                buf.put_u16_le(column + 1);
+            }
+        }
 
         // TVP_END_TOKEN marks end of metadata
         buf.put_u8(TVP_END_TOKEN);
@@ -326,6 +434,7 @@ impl<'a> TvpEncoder<'a> {
     ///
     /// * `encode_values` - A closure that encodes the column values into the buffer.
     ///   Each value should be encoded according to its type (similar to RPC param encoding).
+    ///   Default columns must be skipped.
     pub fn encode_row<F>(&self, buf: &mut BytesMut, encode_values: F)
     where
         F: FnOnce(&mut BytesMut),
@@ -603,6 +712,62 @@ mod tests {
         assert_eq!(buf[5], 0x00);
     }
 
+    #[test]
+    fn test_tvp_default_column_flag() {
+        let col = TvpColumnDef::nullable(TvpWireType::Int { size: 4 }).with_default();
+        assert!(col.is_default());
+
+        let mut buf = BytesMut::new();
+        col.encode(&mut buf);
+        assert_eq!(&buf[4..6], &[0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_tvp_optional_metadata_encoding() {
+        let columns = vec![
+            TvpColumnDef::new(TvpWireType::Int { size: 4 }),
+            TvpColumnDef::new(TvpWireType::Int { size: 4 }),
+        ];
+        let order = [
+            TvpOrderUnique::new(1, TVP_ORDER_DESC),
+            TvpOrderUnique::new(0, TVP_ORDER_ASC | TVP_UNIQUE),
+        ];
+
+        let mut plain = BytesMut::new();
+        TvpEncoder::new("", "T", &columns).encode_metadata(&mut plain);
+
+        let mut buf = BytesMut::new();
+        TvpEncoder::new("", "T", &columns)
+            .with_order_unique(&order)
+            .with_column_ordering(&[1, 0])
+            .encode_metadata(&mut buf);
+
+        let prefix = plain.len() - 1;
+        assert_eq!(&buf[..prefix], &plain[..prefix]);
+        assert_eq!(
+            &buf[prefix..],
+            &[
+                TVP_ORDER_UNIQUE_TOKEN,
+                2,
+                0,
+                2,
+                0,
+                TVP_ORDER_DESC,
+                1,
+                0,
+                TVP_ORDER_ASC | TVP_UNIQUE,
+                TVP_COLUMN_ORDERING_TOKEN,
+                2,
+                0,
+                2,
+                0,
+                1,
+                0,
+                TVP_END_TOKEN,
+            ]
+        );
+    }
+
     #[test]
     fn test_tvp_nvarchar_encoding() {
         let mut buf = BytesMut::new();
@@ -121,6 +121,14 @@ impl TdsVersion {
         self.is_tds_8() || self.0 >= Self::V7_4.0
     }
 
+    /// Check if DONE, DONEPROC and DONEINPROC tokens carry 8-byte row counts.
+    ///
+    /// Row counts were widened from 4 to 8 bytes in TDS 7.2 (SQL Server 2005).
+    #[must_use]
+    pub const fn has_64bit_row_counts(self) -> bool {
+        self.is_tds_8() || self.0 >= 0x7200_0000
+    }
+
     /// Check if this is a legacy version (TDS 7.2 or earlier).
     ///
     /// Legacy versions (SQL Server 2005 and earlier) have different behaviors
@@ -465,6 +473,15 @@ mod tests {
         assert!(!TdsVersion::V7_3B.supports_session_recovery());
     }
 
+    #[test]
+    fn test_has_64bit_row_counts() {
+        assert!(TdsVersion::V7_2.has_64bit_row_counts());
+        assert!(TdsVersion::V7_4.has_64bit_row_counts());
+        assert!(TdsVersion::V8_0.has_64bit_row_counts());
+        assert!(!TdsVersion::V7_1_REV1.has_64bit_row_counts());
+        assert!(!TdsVersion::V7_0.has_64bit_row_counts());
+    }
+
     #[test]
     fn test_is_legacy() {
         assert!(TdsVersion::V7_2.is_legacy());
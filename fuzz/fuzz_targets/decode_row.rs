@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Fuzz row decoding: COLMETADATA followed by ROW/NBCROW tokens, decoded
+    // into client values the same way result sets are read
+    mssql_client::fuzzing::decode_token_stream(data);
+});
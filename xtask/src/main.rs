@@ -8,6 +8,7 @@
 //! - `fmt`: Check/apply code formatting
 //! - `clippy`: Run clippy lints
 //! - `test`: Run all tests
+//! - `it`: Run live integration tests against a SQL Server container (requires Docker)
 //! - `deny`: Run cargo-deny checks
 //! - `doc`: Generate documentation
 //! - `bench`: Run benchmarks
@@ -17,8 +18,10 @@
 //! - `codegen`: Generate protocol constants from TDS spec
 //! - `dist`: Build release artifacts for distribution
 
+use std::fmt::Write as _;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand};
@@ -56,6 +59,24 @@ enum Command {
         #[arg(long)]
         integration: bool,
     },
+    /// Run live integration tests against a SQL Server container (requires Docker)
+    It {
+        /// SQL Server image to run
+        #[arg(long, default_value = "mcr.microsoft.com/mssql/server:2022-latest")]
+        image: String,
+        /// Host port mapped to the container's 1433
+        #[arg(long, default_value = "1433")]
+        port: u16,
+        /// Test a specific package
+        #[arg(short, long)]
+        package: Option<String>,
+        /// Seconds to wait for the server to accept logins
+        #[arg(long, default_value = "120")]
+        timeout: u64,
+        /// Leave the container running after the tests
+        #[arg(long)]
+        keep: bool,
+    },
     /// Run cargo-deny checks
     Deny,
     /// Generate documentation
@@ -84,6 +105,9 @@ enum Command {
         /// List available fuzz targets
         #[arg(long)]
         list: bool,
+        /// Run every fuzz target in turn, each for --max-time seconds
+        #[arg(long, conflicts_with = "target")]
+        all: bool,
     },
     /// Generate protocol constants from TDS specification
     Codegen {
@@ -135,6 +159,13 @@ fn main() -> Result<()> {
             package,
             integration,
         } => test(&sh, package.as_deref(), integration)?,
+        Command::It {
+            image,
+            port,
+            package,
+            timeout,
+            keep,
+        } => integration_tests(&sh, &image, port, package.as_deref(), timeout, keep)?,
         Command::Deny => deny(&sh)?,
         Command::Doc { open } => doc(&sh, open)?,
         Command::Bench { filter } => bench(&sh, filter.as_deref())?,
@@ -144,7 +175,8 @@ fn main() -> Result<()> {
             target,
             max_time,
             list,
-        } => fuzz(&sh, &target, max_time, list)?,
+            all,
+        } => fuzz(&sh, &target, max_time, list, all)?,
         Command::Codegen { check } => codegen(&sh, check)?,
         Command::Dist { target, no_test } => dist(&sh, target.as_deref(), no_test)?,
         Command::FuzzInit => fuzz_init(&sh)?,
@@ -229,6 +261,223 @@ fn test(sh: &Shell, package: Option<&str>, integration: bool) -> Result<()> {
     Ok(())
 }
 
+/// Container name used by `cargo xtask it`.
+const IT_CONTAINER: &str = "mssql-driver-it";
+/// SA password for the integration test container.
+const IT_PASSWORD: &str = "YourStrong@Passw0rd";
+/// Database provisioned for the live tests.
+const IT_DATABASE: &str = "mssql_driver_test";
+/// Column master key path registered for the Always Encrypted tests.
+const IT_CMK_PATH: &str = "TestKey";
+
+fn integration_tests(
+    sh: &Shell,
+    image: &str,
+    port: u16,
+    package: Option<&str>,
+    timeout: u64,
+    keep: bool,
+) -> Result<()> {
+    // Remove a container left behind by an earlier `--keep` run
+    let _ = cmd!(sh, "docker rm -f {IT_CONTAINER}")
+        .quiet()
+        .ignore_stdout()
+        .ignore_stderr()
+        .run();
+
+    println!("Starting SQL Server container ({image})...");
+    let port_map = format!("{port}:1433");
+    let sa_password = format!("MSSQL_SA_PASSWORD={IT_PASSWORD}");
+    cmd!(
+        sh,
+        "docker run -d --name {IT_CONTAINER} -e ACCEPT_EULA=Y -e {sa_password} -e MSSQL_PID=Developer -p {port_map} {image}"
+    )
+    .ignore_stdout()
+    .run()?;
+
+    let result = wait_for_sql_server(sh, Duration::from_secs(timeout)).and_then(|sqlcmd| {
+        let cmk_pem = provision_test_database(sh, &sqlcmd)?;
+        run_live_tests(sh, port, package, &cmk_pem)
+    });
+
+    if keep {
+        println!("Container {IT_CONTAINER} left running on port {port}.");
+    } else {
+        println!("Removing SQL Server container...");
+        cmd!(sh, "docker rm -f {IT_CONTAINER}")
+            .quiet()
+            .ignore_stdout()
+            .run()?;
+    }
+
+    result?;
+    println!("✅ Integration tests passed.");
+    Ok(())
+}
+
+/// Wait until the server accepts logins, returning the sqlcmd invocation that works.
+fn wait_for_sql_server(sh: &Shell, timeout: Duration) -> Result<Vec<String>> {
+    // 2022 images ship mssql-tools18, which needs -C to trust the self-signed
+    // certificate; 2019 images ship the older mssql-tools
+    let candidates = [
+        vec!["/opt/mssql-tools18/bin/sqlcmd", "-C"],
+        vec!["/opt/mssql-tools/bin/sqlcmd"],
+    ];
+
+    println!("Waiting for SQL Server to accept logins...");
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        for candidate in &candidates {
+            let sqlcmd: Vec<String> = candidate
+                .iter()
+                .map(|s| s.to_string())
+                .chain(["-S", "localhost", "-U", "sa", "-P", IT_PASSWORD].map(String::from))
+                .collect();
+            let args = &sqlcmd;
+            let ready = cmd!(sh, "docker exec {IT_CONTAINER} {args...} -Q 'SELECT 1'")
+                .quiet()
+                .ignore_stdout()
+                .ignore_stderr()
+                .run()
+                .is_ok();
+            if ready {
+                println!("SQL Server is ready.");
+                return Ok(sqlcmd);
+            }
+        }
+        std::thread::sleep(Duration::from_secs(2));
+    }
+
+    bail!(
+        "SQL Server did not accept logins within {} seconds",
+        timeout.as_secs()
+    )
+}
+
+/// Create the test database and Always Encrypted keys, returning the path of
+/// the column master key PEM.
+fn provision_test_database(sh: &Shell, sqlcmd: &[String]) -> Result<PathBuf> {
+    println!("Provisioning {IT_DATABASE}...");
+
+    let key_dir = sh.current_dir().join("target/integration-tests");
+    fs::create_dir_all(&key_dir)?;
+    let cmk_pem = key_dir.join("cmk.pem");
+    let encrypted_cek = create_column_encryption_key(sh, &key_dir, &cmk_pem)?;
+    let encrypted_cek_hex = encrypted_cek.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02X}");
+        hex
+    });
+
+    let setup = format!(
+        "CREATE DATABASE [{IT_DATABASE}];
+GO
+USE [{IT_DATABASE}];
+GO
+CREATE COLUMN MASTER KEY [TestCMK]
+    WITH (KEY_STORE_PROVIDER_NAME = N'IN_MEMORY_KEY_STORE', KEY_PATH = N'{IT_CMK_PATH}');
+GO
+CREATE COLUMN ENCRYPTION KEY [TestCEK]
+    WITH VALUES (COLUMN_MASTER_KEY = [TestCMK], ALGORITHM = 'RSA_OAEP',
+                 ENCRYPTED_VALUE = 0x{encrypted_cek_hex});
+GO
+"
+    );
+    let setup_path = key_dir.join("setup.sql");
+    fs::write(&setup_path, setup)?;
+
+    cmd!(sh, "docker cp {setup_path} {IT_CONTAINER}:/tmp/setup.sql")
+        .quiet()
+        .run()?;
+    cmd!(
+        sh,
+        "docker exec {IT_CONTAINER} {sqlcmd...} -b -i /tmp/setup.sql"
+    )
+    .run()?;
+
+    Ok(cmk_pem)
+}
+
+/// Generate a column master key and a column encryption key wrapped with it,
+/// in the envelope `mssql-auth` unwraps: version, key path, RSA-OAEP-SHA256
+/// ciphertext, and an RSA-SHA256 signature over the preceding bytes.
+fn create_column_encryption_key(sh: &Shell, dir: &Path, cmk_pem: &Path) -> Result<Vec<u8>> {
+    let cek = dir.join("cek.bin");
+    let cek_enc = dir.join("cek.enc");
+    let unsigned = dir.join("cek.unsigned");
+    let signature = dir.join("cek.sig");
+
+    cmd!(
+        sh,
+        "openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048 -out {cmk_pem}"
+    )
+    .quiet()
+    .ignore_stderr()
+    .run()
+    .context("failed to generate the column master key (is openssl installed?)")?;
+    cmd!(sh, "openssl rand -out {cek} 32").quiet().run()?;
+    cmd!(
+        sh,
+        "openssl pkeyutl -encrypt -inkey {cmk_pem} -pkeyopt rsa_padding_mode:oaep -pkeyopt rsa_oaep_md:sha256 -in {cek} -out {cek_enc}"
+    )
+    .quiet()
+    .run()?;
+
+    let key_path: Vec<u8> = IT_CMK_PATH
+        .to_lowercase()
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let ciphertext = fs::read(&cek_enc)?;
+
+    let mut envelope = vec![0x01];
+    envelope.extend_from_slice(&(key_path.len() as u16).to_le_bytes());
+    envelope.extend_from_slice(&key_path);
+    envelope.extend_from_slice(&(ciphertext.len() as u16).to_le_bytes());
+    envelope.extend_from_slice(&ciphertext);
+    fs::write(&unsigned, &envelope)?;
+
+    cmd!(
+        sh,
+        "openssl dgst -sha256 -sign {cmk_pem} -out {signature} {unsigned}"
+    )
+    .quiet()
+    .run()?;
+    envelope.extend_from_slice(&fs::read(&signature)?);
+
+    for file in [&cek, &cek_enc, &unsigned, &signature] {
+        fs::remove_file(file)?;
+    }
+
+    Ok(envelope)
+}
+
+fn run_live_tests(sh: &Shell, port: u16, package: Option<&str>, cmk_pem: &Path) -> Result<()> {
+    println!("Running live integration tests...");
+
+    let target = match package {
+        Some(pkg) => vec!["-p", pkg],
+        None => vec!["--workspace"],
+    };
+    let port = port.to_string();
+
+    // Live tests share the provisioned database, so run them one at a time
+    cmd!(sh, "cargo test {target...} -- --ignored --test-threads=1")
+        .env("MSSQL_HOST", "localhost")
+        .env("MSSQL_PORT", &port)
+        .env("MSSQL_USER", "sa")
+        .env("MSSQL_PASSWORD", IT_PASSWORD)
+        .env("MSSQL_DATABASE", IT_DATABASE)
+        .env("MSSQL_ENCRYPT", "false")
+        .env("MSSQL_TEST_HOST", "localhost")
+        .env("MSSQL_TEST_PORT", &port)
+        .env("MSSQL_TEST_USER", "sa")
+        .env("MSSQL_TEST_PASSWORD", IT_PASSWORD)
+        .env("MSSQL_AE_CMK_PEM", cmk_pem)
+        .run()?;
+
+    Ok(())
+}
+
 fn deny(sh: &Shell) -> Result<()> {
     println!("Running cargo-deny...");
     cmd!(sh, "cargo deny check").run()?;
@@ -272,18 +521,14 @@ fn hakari(sh: &Shell) -> Result<()> {
     Ok(())
 }
 
-fn fuzz(sh: &Shell, target: &str, max_time: u64, list: bool) -> Result<()> {
+fn fuzz(sh: &Shell, target: &str, max_time: u64, list: bool, all: bool) -> Result<()> {
     let fuzz_dir = sh.current_dir().join("fuzz");
 
     if list {
         println!("Available fuzz targets:");
-        let targets_dir = fuzz_dir.join("fuzz_targets");
-        if targets_dir.exists() {
-            for entry in fs::read_dir(&targets_dir)? {
-                let entry = entry?;
-                if let Some(name) = entry.path().file_stem() {
-                    println!("  - {}", name.to_string_lossy());
-                }
+        if fuzz_dir.exists() {
+            for name in fuzz_targets(&fuzz_dir)? {
+                println!("  - {name}");
             }
         } else {
             println!("  No fuzz targets found. Run `cargo xtask fuzz-init` to set up fuzzing.");
@@ -297,20 +542,46 @@ fn fuzz(sh: &Shell, target: &str, max_time: u64, list: bool) -> Result<()> {
         );
     }
 
-    println!("Running fuzz target: {target}");
-    println!("Max time: {max_time} seconds");
+    let targets = if all {
+        fuzz_targets(&fuzz_dir)?
+    } else {
+        vec![target.to_string()]
+    };
 
     // cargo-fuzz requires nightly
     let max_time_str = max_time.to_string();
-    cmd!(
-        sh,
-        "cargo +nightly fuzz run {target} -- -max_total_time={max_time_str}"
-    )
-    .run()?;
+    for target in &targets {
+        println!("Running fuzz target: {target}");
+        println!("Max time: {max_time} seconds");
+        cmd!(
+            sh,
+            "cargo +nightly fuzz run {target} -- -max_total_time={max_time_str}"
+        )
+        .run()?;
+    }
+
+    if all {
+        println!("✅ All {} fuzz targets ran clean.", targets.len());
+    }
 
     Ok(())
 }
 
+/// List the fuzz targets in `fuzz/fuzz_targets`, sorted by name.
+fn fuzz_targets(fuzz_dir: &Path) -> Result<Vec<String>> {
+    let mut targets = Vec::new();
+    for entry in fs::read_dir(fuzz_dir.join("fuzz_targets"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            if let Some(name) = path.file_stem() {
+                targets.push(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+    targets.sort();
+    Ok(targets)
+}
+
 fn fuzz_init(sh: &Shell) -> Result<()> {
     let fuzz_dir = sh.current_dir().join("fuzz");
 